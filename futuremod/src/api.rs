@@ -1,14 +1,14 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::{BTreeMap, HashMap}, path::PathBuf};
 
 use crate::config::get_config;
 use anyhow::anyhow;
 use log::info;
 use reqwest::Body;
 use serde::de::DeserializeOwned;
-use tokio::fs;
+use tokio::{fs, io::AsyncWriteExt};
 use tokio_util::codec::{BytesCodec, FramedRead};
 
-use futuremod_data::plugin::{Plugin, PluginInfo};
+use futuremod_data::{audit::AuditReport, bookmark::Bookmark, benchmark::{BenchmarkState, StartBenchmark}, compatibility::{CompatibilityReport, SetCompatibilityReporting}, crash::CrashReport, debugger::{DebuggerState, EnvironmentEntry, StepFrames}, diagnostics::Diagnostics, docs::LibraryDocs, input::{InputRemap, KeyBinding, SetKeyBinding}, memory::{MemoryAddress, MemoryValue, NextScan, ScanComparison, ScanState, StartScan}, multiplayer::{ConnectPeer, MultiplayerStatus}, screenshot::Screenshot, plugin::{InstallDevPlugin, InvokeLifecycle, LifecycleEvent, Plugin, PluginInfo}, session::{RecordingState, SessionInfo}, settings::{PluginSettings, SettingValue}, statistics::UsageStatistics, structs::{ReadStruct, StructDefinition, StructFieldValue}};
 
 
 pub fn build_url(path: &str) -> String {
@@ -79,6 +79,106 @@ pub async fn install_plugin(path: PathBuf) -> Result<(), anyhow::Error> {
   Ok(())
 }
 
+/// Download the plugin package from the URL and stream it to the mod, which installs it.
+pub async fn install_plugin_from_url(url: String) -> Result<(), String> {
+  info!("Installing plugin from '{}'", url);
+
+  let download = handle_response(reqwest::get(&url).await)?
+    .error_for_status()
+    .map_err(|e| format!("Could not download the plugin from '{}': {}", url, e))?;
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/plugin/install"))
+      .body(Body::wrap_stream(download.bytes_stream()))
+      .send()
+      .await
+  )?;
+
+  check_status(response, &format!("Could not install the plugin from '{}'", url)).await
+}
+
+/// Update the installed plugin to the version in the plugin package, keeping its state, stored values, and settings.
+pub async fn update_plugin(path: PathBuf) -> Result<(), String> {
+  info!("Updating plugin from '{}'", path.display());
+
+  let file = fs::File::open(path.clone()).await.map_err(|e| format!("Could not open file: {}", e))?;
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/plugin/update"))
+      .body(Body::wrap_stream(FramedRead::new(file, BytesCodec::new())))
+      .send()
+      .await
+  )?;
+
+  check_status(response, &format!("Could not update plugin from '{}'", path.display())).await
+}
+
+/// Download the plugin package from the URL and stream it to the mod, which updates the installed plugin.
+pub async fn update_plugin_from_url(url: String) -> Result<(), String> {
+  info!("Updating plugin from '{}'", url);
+
+  let download = handle_response(reqwest::get(&url).await)?
+    .error_for_status()
+    .map_err(|e| format!("Could not download the plugin from '{}': {}", url, e))?;
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/plugin/update"))
+      .body(Body::wrap_stream(download.bytes_stream()))
+      .send()
+      .await
+  )?;
+
+  check_status(response, &format!("Could not update the plugin from '{}'", url)).await
+}
+
+/// Send the plugin package, which is already in memory, to the mod, which installs it.
+pub async fn install_plugin_package(name: String, package: Vec<u8>) -> Result<(), String> {
+  info!("Installing plugin package '{}'", name);
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/plugin/install"))
+      .body(package)
+      .send()
+      .await
+  )?;
+
+  check_status(response, &format!("Could not install plugin '{}'", name)).await
+}
+
+/// Send the plugin package, which is already in memory, to the mod, which updates the installed plugin.
+pub async fn update_plugin_package(name: String, package: Vec<u8>) -> Result<(), String> {
+  info!("Updating plugin '{}' from package", name);
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/plugin/update"))
+      .body(package)
+      .send()
+      .await
+  )?;
+
+  check_status(response, &format!("Could not update plugin '{}'", name)).await
+}
+
+/// Install the plugin in the folder in developer mode, i.e., link the folder instead of copying it.
+pub async fn install_dev_plugin(path: PathBuf) -> Result<(), String> {
+  info!("Installing plugin from '{}' in developer mode", path.display());
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/plugin/install-dev"))
+      .json(&InstallDevPlugin { path: path.clone() })
+      .send()
+      .await
+  )?;
+
+  check_status(response, &format!("Could not install plugin from '{}'", path.display())).await
+}
+
 pub async fn get_plugin_info(path: PathBuf) -> Result<PluginInfo, anyhow::Error> {
   let file = fs::File::open(path.clone()).await.map_err(|e| anyhow!("Could not open file: {}", e.to_string()))?;
 
@@ -87,6 +187,7 @@ pub async fn get_plugin_info(path: PathBuf) -> Result<PluginInfo, anyhow::Error>
 
   let response = reqwest::Client::new()
     .put(build_url("/plugin/info"))
+    .query(&[("language", get_config().language)])
     .body(body)
     .send()
     .await
@@ -147,8 +248,790 @@ pub async fn parse_json<T>(response: reqwest::Response) -> Result<T, String> whe
   }
 }
 
+/// Get all plugins. Their names and descriptions are in the configured language, if they translate them.
 pub async fn get_plugins() -> Result<HashMap<String, Plugin>, String> {
-  let response = handle_response(reqwest::get(build_url("/plugins")).await)?;
+  let response = handle_response(
+    reqwest::Client::new()
+      .get(build_url("/plugins"))
+      .query(&[("language", get_config().language)])
+      .send()
+      .await
+  )?;
+
+  parse_json(response).await
+}
+
+pub async fn get_api_docs() -> Result<Vec<LibraryDocs>, String> {
+  let response = handle_response(reqwest::get(build_url("/api-docs")).await)?;
+
+  parse_json(response).await
+}
+
+pub async fn get_audit_report() -> Result<AuditReport, String> {
+  let response = handle_response(reqwest::get(build_url("/audit")).await)?;
+
+  parse_json(response).await
+}
+
+pub async fn get_session() -> Result<SessionInfo, String> {
+  let response = handle_response(reqwest::get(build_url("/session")).await)?;
+
+  parse_json(response).await
+}
+
+pub async fn get_session_recording() -> Result<RecordingState, String> {
+  let response = handle_response(reqwest::get(build_url("/session/recording")).await)?;
+
+  parse_json(response).await
+}
+
+pub async fn start_session_recording() -> Result<(), String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/session/recording/start"))
+      .send()
+      .await
+  )?;
+
+  check_status(response, "Could not start recording the session").await
+}
+
+pub async fn stop_session_recording() -> Result<(), String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/session/recording/stop"))
+      .send()
+      .await
+  )?;
+
+  check_status(response, "Could not stop recording the session").await
+}
+
+/// Download the running or the last session recording as a bundle to attach to bug reports.
+pub async fn download_session_bundle(destination: PathBuf) -> Result<PathBuf, String> {
+  info!("Saving the session recording to '{}'", destination.display());
+
+  let response = handle_response(
+    reqwest::get(build_url("/session/recording/bundle")).await
+  )?;
+
+  if !response.status().is_success() {
+    let status = response.status();
+
+    return match response.text().await {
+      Ok(err) if err.len() > 0 => Err(format!("Could not save the recording: {}", err)),
+      _ => Err(format!("Could not save the recording: {}", status)),
+    };
+  }
+
+  let content = response.bytes().await.map_err(|e| format!("Could not download the recording: {}", e))?;
+  fs::write(&destination, &content).await.map_err(|e| format!("Could not write '{}': {}", destination.display(), e))?;
+
+  Ok(destination)
+}
+
+pub async fn get_compatibility_report() -> Result<CompatibilityReport, String> {
+  let response = handle_response(reqwest::get(build_url("/compatibility")).await)?;
+
+  parse_json(response).await
+}
+
+/// Enable or disable collecting which game builds and versions the plugins ran with.
+///
+/// Returns the report after the change.
+pub async fn set_compatibility_reporting(enabled: bool) -> Result<CompatibilityReport, String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .put(build_url("/compatibility"))
+      .json(&SetCompatibilityReporting { enabled })
+      .send()
+      .await
+  )?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not change the compatibility reporting").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+/// Delete the collected compatibility data.
+///
+/// Returns the report after the change.
+pub async fn clear_compatibility_report() -> Result<CompatibilityReport, String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .delete(build_url("/compatibility"))
+      .send()
+      .await
+  )?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not clear the compatibility data").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+pub async fn get_statistics() -> Result<UsageStatistics, String> {
+  let response = handle_response(reqwest::get(build_url("/statistics")).await)?;
+
+  parse_json(response).await
+}
+
+/// Delete the usage statistics and start over.
+///
+/// Returns the statistics after the reset.
+pub async fn reset_statistics() -> Result<UsageStatistics, String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .delete(build_url("/statistics"))
+      .send()
+      .await
+  )?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not reset the statistics").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+pub async fn get_diagnostics() -> Result<Diagnostics, String> {
+  let response = handle_response(reqwest::get(build_url("/diagnostics")).await)?;
+
+  parse_json(response).await
+}
+
+/// Get the report of the game's last crash, or `None` if the game didn't crash since the report was dismissed.
+pub async fn get_last_crash() -> Result<Option<CrashReport>, String> {
+  let response = handle_response(reqwest::get(build_url("/crash")).await)?;
+
+  parse_json(response).await
+}
+
+pub async fn dismiss_last_crash() -> Result<(), String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .delete(build_url("/crash"))
+      .send()
+      .await
+  )?;
+
+  check_status(response, "Could not dismiss the crash report").await
+}
+
+/// Eject the mod from the game. The mod unloads itself shortly after the response.
+pub async fn eject_mod() -> Result<(), String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/shutdown"))
+      .send()
+      .await
+  )?;
+
+  check_status(response, "Could not eject the mod").await
+}
+
+pub async fn get_benchmark() -> Result<BenchmarkState, String> {
+  let response = handle_response(reqwest::get(build_url("/benchmark")).await)?;
+
+  parse_json(response).await
+}
+
+pub async fn start_benchmark(frames: u32) -> Result<(), String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/benchmark/start"))
+      .json(&StartBenchmark { frames })
+      .send()
+      .await
+  )?;
+
+  check_status(response, "Could not start the benchmark").await
+}
+
+pub async fn cancel_benchmark() -> Result<(), String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/benchmark/cancel"))
+      .send()
+      .await
+  )?;
+
+  check_status(response, "Could not cancel the benchmark").await
+}
+
+pub async fn get_remappable_keys() -> Result<Vec<String>, String> {
+  let response = handle_response(reqwest::get(build_url("/input/keys")).await)?;
+
+  parse_json(response).await
+}
+
+pub async fn set_input_remaps(remaps: Vec<InputRemap>) -> Result<(), String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .put(build_url("/input/remaps"))
+      .json(&remaps)
+      .send()
+      .await
+  )?;
+
+  check_status(response, "Could not apply the remaps").await
+}
+
+/// URL of the plugin's resource, e.g., its key bindings.
+fn plugin_resource_url(name: &str, resource: &str) -> Result<reqwest::Url, String> {
+  // The name is a path segment and must be escaped
+  let mut url = reqwest::Url::parse(&build_url("/plugin")).map_err(|e| format!("Invalid mod address: {}", e))?;
+  url.path_segments_mut()
+    .map_err(|_| String::from("Invalid mod address"))?
+    .push(name)
+    .push(resource);
+
+  Ok(url)
+}
+
+pub async fn get_key_bindings(name: String) -> Result<Vec<KeyBinding>, String> {
+  let response = handle_response(reqwest::get(plugin_resource_url(&name, "bindings")?).await)?;
+
+  parse_json(response).await
+}
+
+/// Change the key of the plugin's binding.
+///
+/// Returns the plugin's bindings after the change.
+pub async fn set_key_binding(name: String, binding: SetKeyBinding) -> Result<Vec<KeyBinding>, String> {
+  info!("Changing key binding '{}' of plugin {} to {:?}", binding.name, name, binding.key);
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .put(plugin_resource_url(&name, "bindings")?)
+      .json(&binding)
+      .send()
+      .await
+  )?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not change the key binding").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+pub async fn get_plugin_config(name: String) -> Result<PluginSettings, String> {
+  let response = handle_response(reqwest::get(plugin_resource_url(&name, "config")?).await)?;
+
+  parse_json(response).await
+}
+
+/// Change the values of the plugin's settings.
+///
+/// Returns the plugin's settings after the change.
+pub async fn set_plugin_config(name: String, values: BTreeMap<String, SettingValue>) -> Result<PluginSettings, String> {
+  info!("Changing the settings of plugin {}", name);
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .put(plugin_resource_url(&name, "config")?)
+      .json(&values)
+      .send()
+      .await
+  )?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not change the settings").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+pub async fn get_multiplayer_status() -> Result<MultiplayerStatus, String> {
+  let response = handle_response(reqwest::get(build_url("/multiplayer")).await)?;
+
+  parse_json(response).await
+}
+
+pub async fn connect_multiplayer(port: u16, peer: String) -> Result<(), String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/multiplayer/connect"))
+      .json(&ConnectPeer { port, peer })
+      .send()
+      .await
+  )?;
+
+  check_status(response, "Could not connect").await
+}
+
+pub async fn disconnect_multiplayer() -> Result<(), String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/multiplayer/disconnect"))
+      .send()
+      .await
+  )?;
+
+  check_status(response, "Could not disconnect").await
+}
+
+pub async fn invoke_lifecycle(name: String, event: LifecycleEvent) -> Result<(), String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/plugin/lifecycle"))
+      .json(&InvokeLifecycle { name: name.clone(), event })
+      .send()
+      .await
+  )?;
+
+  check_status(response, &format!("Could not call {} of '{}'", event, name)).await
+}
+
+/// Reload the plugin including its info file.
+pub async fn force_reload_plugin(name: String) -> Result<(), String> {
+  info!("Force reloading plugin: {}", name);
+
+  let mut body = HashMap::new();
+  body.insert("name", name.clone());
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .put(build_url("/plugin/force-reload"))
+      .json(&body)
+      .send()
+      .await
+  )?;
+
+  check_status(response, &format!("Could not reload '{}'", name)).await
+}
+
+/// Convert the plugin installed in developer mode to a regular installation.
+pub async fn promote_plugin(name: String) -> Result<(), String> {
+  info!("Converting plugin to a regular installation: {}", name);
+
+  // The name is a path segment and must be escaped
+  let mut url = reqwest::Url::parse(&build_url("/plugin")).map_err(|e| format!("Invalid mod address: {}", e))?;
+  url.path_segments_mut()
+    .map_err(|_| String::from("Invalid mod address"))?
+    .push(&name)
+    .push("promote");
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(url)
+      .send()
+      .await
+  )?;
+
+  check_status(response, &format!("Could not convert '{}' to a regular installation", name)).await
+}
+
+/// Trust the current files of a plugin whose files changed since it was installed.
+pub async fn trust_plugin(name: String) -> Result<(), String> {
+  info!("Trusting the current files of plugin: {}", name);
+
+  let mut url = reqwest::Url::parse(&build_url("/plugin")).map_err(|e| format!("Invalid mod address: {}", e))?;
+  url.path_segments_mut()
+    .map_err(|_| String::from("Invalid mod address"))?
+    .push(&name)
+    .push("trust");
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(url)
+      .send()
+      .await
+  )?;
+
+  check_status(response, &format!("Could not trust plugin '{}'", name)).await
+}
+
+/// Stop suspecting a plugin of crashing the game without enabling it.
+pub async fn dismiss_suspect(name: String) -> Result<(), String> {
+  info!("Dismissing the crash suspicion of plugin: {}", name);
+
+  let mut url = reqwest::Url::parse(&build_url("/plugin")).map_err(|e| format!("Invalid mod address: {}", e))?;
+  url.path_segments_mut()
+    .map_err(|_| String::from("Invalid mod address"))?
+    .push(&name)
+    .push("dismiss-suspect");
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(url)
+      .send()
+      .await
+  )?;
+
+  check_status(response, &format!("Could not dismiss the crash suspicion of plugin '{}'", name)).await
+}
+
+/// Approve or revoke that the plugin sends requests to the hosts it declares.
+pub async fn set_network_approval(name: String, approved: bool) -> Result<(), String> {
+  info!("Setting the network approval of plugin '{}' to {}", name, approved);
+
+  let mut url = reqwest::Url::parse(&build_url("/plugin")).map_err(|e| format!("Invalid mod address: {}", e))?;
+  url.path_segments_mut()
+    .map_err(|_| String::from("Invalid mod address"))?
+    .push(&name)
+    .push("network");
+
+  let request = match approved {
+    true => reqwest::Client::new().put(url),
+    false => reqwest::Client::new().delete(url),
+  };
+
+  let response = handle_response(request.send().await)?;
+
+  check_status(response, &format!("Could not change the network approval of plugin '{}'", name)).await
+}
+
+/// Approve or revoke that the plugin runs the commands it declares.
+pub async fn set_command_approval(name: String, approved: bool) -> Result<(), String> {
+  info!("Setting the command approval of plugin '{}' to {}", name, approved);
+
+  let mut url = reqwest::Url::parse(&build_url("/plugin")).map_err(|e| format!("Invalid mod address: {}", e))?;
+  url.path_segments_mut()
+    .map_err(|_| String::from("Invalid mod address"))?
+    .push(&name)
+    .push("commands");
+
+  let request = match approved {
+    true => reqwest::Client::new().put(url),
+    false => reqwest::Client::new().delete(url),
+  };
+
+  let response = handle_response(request.send().await)?;
+
+  check_status(response, &format!("Could not change the command approval of plugin '{}'", name)).await
+}
+
+/// Download the mod's log files into `destination`.
+pub async fn download_log_files(destination: PathBuf) -> Result<(), String> {
+  info!("Downloading log files to '{}'", destination.display());
+
+  let mut response = handle_response(
+    reqwest::get(build_url("/logs/file")).await
+  )?;
+
+  if !response.status().is_success() {
+    return Err(format!("Could not download the log files: {}", response.status()));
+  }
+
+  let mut file = fs::File::create(&destination).await.map_err(|e| format!("Could not create '{}': {}", destination.display(), e))?;
+
+  while let Some(chunk) = response.chunk().await.map_err(|e| format!("Could not download the log files: {}", e))? {
+    file.write_all(&chunk).await.map_err(|e| format!("Could not write '{}': {}", destination.display(), e))?;
+  }
+
+  Ok(())
+}
+
+/// Download a backup of the plugins directory into `destination`.
+///
+/// Returns the path of the backup.
+pub async fn download_backup(destination: PathBuf) -> Result<PathBuf, String> {
+  info!("Backing up the plugins to '{}'", destination.display());
+
+  let mut response = handle_response(
+    reqwest::get(build_url("/backup")).await
+  )?;
+
+  if !response.status().is_success() {
+    let status = response.status();
+
+    return match response.text().await {
+      Ok(err) if err.len() > 0 => Err(format!("Could not create the backup: {}", err)),
+      _ => Err(format!("Could not create the backup: {}", status)),
+    };
+  }
+
+  let mut file = fs::File::create(&destination).await.map_err(|e| format!("Could not create '{}': {}", destination.display(), e))?;
+
+  while let Some(chunk) = response.chunk().await.map_err(|e| format!("Could not download the backup: {}", e))? {
+    file.write_all(&chunk).await.map_err(|e| format!("Could not write '{}': {}", destination.display(), e))?;
+  }
+
+  Ok(destination)
+}
+
+/// Replace all plugins with the plugins of the backup.
+pub async fn restore_backup(path: PathBuf) -> Result<(), String> {
+  info!("Restoring the plugins from '{}'", path.display());
+
+  let file = fs::File::open(&path).await.map_err(|e| format!("Could not open '{}': {}", path.display(), e))?;
+
+  let stream = FramedRead::new(file, BytesCodec::new());
+  let body = Body::wrap_stream(stream);
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/backup/restore"))
+      .body(body)
+      .send()
+      .await
+  )?;
+
+  check_status(response, "Could not restore the backup").await
+}
+
+/// Clear the mod's log history, so the logs start empty when the GUI connects again.
+pub async fn clear_log_history() -> Result<(), String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .delete(build_url("/log"))
+      .send()
+      .await
+  )?;
+
+  check_status(response, "Could not clear the logs").await
+}
+
+pub async fn get_screenshots() -> Result<Vec<Screenshot>, String> {
+  let response = handle_response(reqwest::get(build_url("/screenshots")).await)?;
+
+  parse_json(response).await
+}
+
+/// Get the PNG file of the screenshot.
+pub async fn get_screenshot_image(path: String) -> Result<Vec<u8>, String> {
+  let response = handle_response(reqwest::get(build_url(&format!("/screenshots/{}", path))).await)?;
+
+  if !response.status().is_success() {
+    return Err(format!("Could not load the screenshot '{}': {}", path, response.status()));
+  }
+
+  response
+    .bytes()
+    .await
+    .map(|bytes| bytes.to_vec())
+    .map_err(|e| format!("Could not load the screenshot '{}': {}", path, e))
+}
+
+async fn check_status(response: reqwest::Response, context: &str) -> Result<(), String> {
+  if response.status().is_success() {
+    return Ok(());
+  }
+
+  let status = response.status();
+
+  match response.text().await {
+    Ok(err) if err.len() > 0 => Err(format!("{}: {}", context, err)),
+    _ => Err(format!("{}: {}", context, status)),
+  }
+}
+
+pub async fn get_watches() -> Result<Vec<String>, String> {
+  let response = handle_response(reqwest::get(build_url("/watches")).await)?;
+
+  parse_json(response).await
+}
+
+/// Replace the watch expressions.
+///
+/// Returns the expressions the mod watches after the change.
+pub async fn set_watches(expressions: Vec<String>) -> Result<Vec<String>, String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .put(build_url("/watches"))
+      .json(&expressions)
+      .send()
+      .await
+  )?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not change the watch expressions").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+pub async fn get_bookmarks() -> Result<Vec<Bookmark>, String> {
+  let response = handle_response(reqwest::get(build_url("/bookmarks")).await)?;
+
+  parse_json(response).await
+}
+
+/// Add the bookmark or replace the bookmark with the same name.
+///
+/// Returns all bookmarks after the change.
+pub async fn set_bookmark(bookmark: Bookmark) -> Result<Vec<Bookmark>, String> {
+  info!("Bookmarking 0x{:x} as '{}'", bookmark.address, bookmark.name);
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .put(build_url("/bookmarks"))
+      .json(&bookmark)
+      .send()
+      .await
+  )?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not save the bookmark").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+/// Remove the bookmark.
+///
+/// Returns all bookmarks after the change.
+pub async fn remove_bookmark(name: String) -> Result<Vec<Bookmark>, String> {
+  // The name is a path segment and must be escaped
+  let mut url = reqwest::Url::parse(&build_url("/bookmarks")).map_err(|e| format!("Invalid mod address: {}", e))?;
+  url.path_segments_mut()
+    .map_err(|_| String::from("Invalid mod address"))?
+    .push(&name);
+
+  let response = handle_response(reqwest::Client::new().delete(url).send().await)?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not remove the bookmark").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+pub async fn get_struct_definitions() -> Result<Vec<StructDefinition>, String> {
+  let response = handle_response(reqwest::get(build_url("/structs")).await)?;
+
+  parse_json(response).await
+}
+
+/// Read the fields of the struct instance at the address.
+pub async fn read_struct(definition: String, address: u32) -> Result<Vec<StructFieldValue>, String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/structs/read"))
+      .json(&ReadStruct { definition, address })
+      .send()
+      .await
+  )?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not read the struct").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+pub async fn get_memory_scan() -> Result<ScanState, String> {
+  let response = handle_response(reqwest::get(build_url("/memory/scan")).await)?;
+
+  parse_json(response).await
+}
+
+/// Start a new memory scan for the value, replacing the current scan.
+pub async fn start_memory_scan(value_type: String, value: String) -> Result<ScanState, String> {
+  info!("Scanning memory for the {} {}", value_type, value);
+
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/memory/scan"))
+      .json(&StartScan { value_type, value })
+      .send()
+      .await
+  )?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not scan the memory").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+/// Filter the matches of the current memory scan.
+pub async fn next_memory_scan(comparison: ScanComparison, value: Option<String>) -> Result<ScanState, String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/memory/scan/next"))
+      .json(&NextScan { comparison, value })
+      .send()
+      .await
+  )?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not scan the memory").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+pub async fn reset_memory_scan() -> Result<(), String> {
+  let response = handle_response(reqwest::Client::new().delete(build_url("/memory/scan")).send().await)?;
+
+  check_status(response, "Could not reset the memory scan").await
+}
+
+/// Read the current values at the addresses.
+pub async fn read_memory_values(addresses: Vec<MemoryAddress>) -> Result<Vec<MemoryValue>, String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/memory/values"))
+      .json(&addresses)
+      .send()
+      .await
+  )?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not read the values").await.err().unwrap_or_default();
+    return Err(error);
+  }
+
+  parse_json(response).await
+}
+
+pub async fn get_debugger_state() -> Result<DebuggerState, String> {
+  let response = handle_response(reqwest::get(build_url("/debugger")).await)?;
+
+  parse_json(response).await
+}
+
+/// Pause the game loop before the next frame.
+pub async fn pause_game() -> Result<DebuggerState, String> {
+  let response = handle_response(reqwest::Client::new().post(build_url("/debugger/pause")).send().await)?;
+
+  parse_json(response).await
+}
+
+pub async fn resume_game() -> Result<DebuggerState, String> {
+  let response = handle_response(reqwest::Client::new().post(build_url("/debugger/resume")).send().await)?;
+
+  parse_json(response).await
+}
+
+/// Run the game for the number of frames and pause it again.
+pub async fn step_game(frames: u32) -> Result<DebuggerState, String> {
+  let response = handle_response(
+    reqwest::Client::new()
+      .post(build_url("/debugger/step"))
+      .json(&StepFrames { frames })
+      .send()
+      .await
+  )?;
+
+  parse_json(response).await
+}
+
+/// Get the globals the plugin set in its environment.
+pub async fn get_plugin_environment(name: String) -> Result<Vec<EnvironmentEntry>, String> {
+  let response = handle_response(reqwest::get(plugin_resource_url(&name, "environment")?).await)?;
+
+  if !response.status().is_success() {
+    let error = check_status(response, "Could not inspect the plugin").await.err().unwrap_or_default();
+    return Err(error);
+  }
 
   parse_json(response).await
 }
\ No newline at end of file
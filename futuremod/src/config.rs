@@ -1,13 +1,92 @@
-use std::{env, fs, path::Path};
+use std::{collections::BTreeMap, env, fs, path::{Path, PathBuf}, sync::RwLock};
 use anyhow::anyhow;
+use futuremod_data::{accessibility::ColorblindPreset, input::InputRemap, version::UpdateChannel};
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
-use tokio::sync::OnceCell;
+use windows::Win32::Globalization::GetUserDefaultLocaleName;
 
+const DEFAULT_PROFILE_NAME: &str = "Default";
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
-#[serde(rename_all = "camelCase")]
+/// Language used if the system's language can't be determined.
+const DEFAULT_LANGUAGE: &str = "en";
+
+/// Highest rating users can give a plugin.
+pub const MAX_RATING: u8 = 5;
+
+/// Configuration of the active profile.
+///
+/// Combines the settings of the active profile with the settings shared by all profiles.
+#[derive(Debug, Clone)]
 pub struct Config {
+    /// Name of the active profile.
+    pub profile: String,
+
+    pub mod_path: String,
+
+    pub mod_address: String,
+
+    pub process_name: String,
+
+    pub require_admin: bool,
+
+    pub plugins_directory: Option<String>,
+
+    pub game_path: Option<String>,
+
+    pub borderless_window: bool,
+
+    pub colorblind_preset: ColorblindPreset,
+
+    /// Show tools for plugin developers, such as manually calling a plugin's lifecycle functions.
+    pub developer_mode: bool,
+
+    /// Inject the mod as soon as the game is detected.
+    pub auto_inject: bool,
+
+    /// Releases FutureMod looks for updates in.
+    pub update_channel: UpdateChannel,
+
+    /// Keys the mod replaces with other keys while the game has the focus.
+    pub input_remaps: Vec<InputRemap>,
+
+    /// Language plugins' names and descriptions are shown in, e.g., `de` or `pt-BR`.
+    pub language: String,
+
+    /// The user's ratings and notes of plugins, by the plugin's name.
+    pub plugin_notes: BTreeMap<String, PluginNote>,
+
+    /// URL of the plugin index browsed in the GUI, see [`crate::marketplace`].
+    pub plugin_index_url: Option<String>,
+}
+
+/// Private rating and notes of a plugin.
+///
+/// Only stored in the GUI's config and never sent to the mod.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginNote {
+    /// Rating from 1 to [`MAX_RATING`] stars.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+impl PluginNote {
+    pub fn is_empty(&self) -> bool {
+        self.rating.is_none() && self.notes.trim().is_empty()
+    }
+}
+
+/// Named set of settings for one game installation.
+///
+/// Each profile has its own injection settings, mod address, and plugin directory.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub name: String,
+
     #[serde(default = "default_mod_path")]
     pub mod_path: String,
 
@@ -19,10 +98,138 @@ pub struct Config {
 
     #[serde(default = "default_require_admin")]
     pub require_admin: bool,
+
+    /// Directory from which the mod loads plugins.
+    ///
+    /// If set, it is written into the mod's config next to the game before injecting the mod.
+    /// Otherwise, the mod uses its own config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plugins_directory: Option<String>,
+
+    /// Path to the game's executable, used to launch the game with the mod.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_path: Option<String>,
+
+    /// Force the game's window into borderless windowed mode.
+    ///
+    /// Written into the mod's config before injecting the mod.
+    #[serde(default)]
+    pub borderless_window: bool,
+
+    /// Remap the game's colors for a kind of color blindness.
+    ///
+    /// Written into the mod's config before injecting the mod.
+    #[serde(default)]
+    pub colorblind_preset: ColorblindPreset,
+}
+
+/// Content of the config file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ConfigFile {
+    #[serde(default)]
+    active_profile: String,
+
+    #[serde(default)]
+    profiles: Vec<Profile>,
+
+    #[serde(default)]
+    developer_mode: bool,
+
+    #[serde(default = "default_auto_inject")]
+    auto_inject: bool,
+
+    #[serde(default)]
+    update_channel: UpdateChannel,
+
+    /// Shared by all profiles, since they belong to the player and not to the game installation.
+    #[serde(default)]
+    input_remaps: Vec<InputRemap>,
+
+    /// Language plugins' names and descriptions are shown in. Uses the system's language if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+
+    /// Shared by all profiles, since they belong to the player and not to the game installation.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    plugin_notes: BTreeMap<String, PluginNote>,
+
+    /// Shared by all profiles. Browsing plugins is disabled if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    plugin_index_url: Option<String>,
+
+    // Settings of config files from before profiles existed.
+    // They are migrated into the default profile.
+    #[serde(default, skip_serializing)]
+    mod_path: Option<String>,
+    #[serde(default, skip_serializing)]
+    mod_address: Option<String>,
+    #[serde(default, skip_serializing)]
+    process_name: Option<String>,
+    #[serde(default, skip_serializing)]
+    require_admin: Option<bool>,
+}
+
+impl ConfigFile {
+    /// Create the default profile if the config file doesn't contain any profiles.
+    ///
+    /// Returns `true` if the config changed.
+    fn migrate(&mut self) -> bool {
+        if !self.profiles.is_empty() {
+            return false;
+        }
+
+        info!("Config has no profiles, creating the default profile");
+
+        self.profiles.push(Profile {
+            name: DEFAULT_PROFILE_NAME.to_string(),
+            mod_path: self.mod_path.take().unwrap_or_else(default_mod_path),
+            mod_address: self.mod_address.take().unwrap_or_else(default_mod_address),
+            process_name: self.process_name.take().unwrap_or_else(default_process_name),
+            require_admin: self.require_admin.take().unwrap_or_else(default_require_admin),
+            plugins_directory: None,
+            game_path: None,
+            borderless_window: false,
+            colorblind_preset: ColorblindPreset::None,
+        });
+        self.active_profile = DEFAULT_PROFILE_NAME.to_string();
+
+        true
+    }
+
+    fn active_profile(&self) -> &Profile {
+        self.profiles
+            .iter()
+            .find(|profile| profile.name == self.active_profile)
+            .unwrap_or(&self.profiles[0])
+    }
+
+    fn to_config(&self) -> Config {
+        let profile = self.active_profile();
+
+        Config {
+            profile: profile.name.clone(),
+            mod_path: profile.mod_path.clone(),
+            mod_address: profile.mod_address.clone(),
+            process_name: profile.process_name.clone(),
+            require_admin: profile.require_admin,
+            plugins_directory: profile.plugins_directory.clone(),
+            game_path: profile.game_path.clone(),
+            borderless_window: profile.borderless_window,
+            colorblind_preset: profile.colorblind_preset,
+            developer_mode: self.developer_mode,
+            auto_inject: self.auto_inject,
+            update_channel: self.update_channel,
+            input_remaps: self.input_remaps.clone(),
+            language: self.language.clone().unwrap_or_else(system_language),
+            plugin_notes: self.plugin_notes.clone(),
+            plugin_index_url: self.plugin_index_url.clone(),
+        }
+    }
 }
 
 /// Get the default path to the mod dll.
-/// 
+///
 /// We expect the dll to be inside the same directory as the injector.
 fn default_mod_path() -> String {
   let mut current_dir_path = match env::current_dir() {
@@ -39,6 +246,19 @@ fn default_mod_path() -> String {
   String::from(current_dir)
 }
 
+/// Language of the system, e.g., `de-DE`.
+fn system_language() -> String {
+  let mut buffer = [0u16; 85];
+
+  let length = unsafe { GetUserDefaultLocaleName(&mut buffer) };
+  if length <= 1 {
+    return DEFAULT_LANGUAGE.to_string();
+  }
+
+  // The length includes the terminating null
+  String::from_utf16_lossy(&buffer[..length as usize - 1])
+}
+
 fn default_mod_address() -> String {
   "127.0.0.1:8000".to_string()
 }
@@ -51,40 +271,53 @@ fn default_require_admin() -> bool {
   false
 }
 
-static CONFIG: OnceCell<Config> = OnceCell::<Config>::const_new();
+fn default_auto_inject() -> bool {
+  true
+}
+
+struct LoadedConfig {
+    path: PathBuf,
+    file: ConfigFile,
+}
+
+static CONFIG: RwLock<Option<LoadedConfig>> = RwLock::new(None);
 
-fn create_default_config() -> Result<Config, serde_json::Error> {
+fn create_default_config() -> Result<ConfigFile, serde_json::Error> {
   serde_json::from_str("{}")
 }
 
-fn get_config_from_path(path: &Path) -> Result<Config, anyhow::Error> {
-  if path.exists() {
+fn write_config_file(path: &Path, config: &ConfigFile) -> Result<(), anyhow::Error> {
+  // Use pretty string. A human should be able to read and change the config
+  let config_as_str = serde_json::to_string_pretty(config)
+    .map_err(|e| anyhow!("Could not convert the config to string: {}", e))?;
+
+  fs::write(path, config_as_str)
+    .map_err(|e| anyhow!("Could not write the config to file: {}", e))
+}
+
+fn get_config_from_path(path: &Path) -> Result<ConfigFile, anyhow::Error> {
+  let mut config = if path.exists() {
     info!("Reading the config");
 
     // If the file exists, read its contents and parse it
     let config_content = fs::read_to_string(path)
       .map_err(|e| anyhow!("Could not read the config: {}", e))?;
 
-    let config: Config = serde_json::from_str(&config_content).
-      map_err(|e| anyhow!("Could not parse the config: {}", e))?;
-
-    Ok(config)
+    serde_json::from_str(&config_content).
+      map_err(|e| anyhow!("Could not parse the config: {}", e))?
   } else {
     info!("Config file doesn't exist, creating the default config");
 
     // If the file doesn't exist, create a default config and create the file
-    let config = create_default_config()
-      .map_err(|e| anyhow!("Could not create the default config: {}", e))?;
-
-    // Use pretty string. A human should be able to read and change the config
-    let config_as_str = serde_json::to_string_pretty(&config)
-      .map_err(|e| anyhow!("Could not convert the default config to string: {}", e))?;
-
-    fs::write(path, config_as_str)
-      .map_err(|e| anyhow!("Could not write the default config to file: {}", e))?;
+    create_default_config()
+      .map_err(|e| anyhow!("Could not create the default config: {}", e))?
+  };
 
-    Ok(config)
+  if config.migrate() || !path.exists() {
+    write_config_file(path, &config)?;
   }
+
+  Ok(config)
 }
 
 pub fn init(config_path_str: &str) -> Result<Config, anyhow::Error> {
@@ -95,22 +328,171 @@ pub fn init(config_path_str: &str) -> Result<Config, anyhow::Error> {
   let config = get_config_from_path(config_path)?;
 
   debug!("Setting config global");
-  match CONFIG.set(config) {
-    Ok(_) => debug!("set config"),
-    Err(_) => {
-      debug!("didn't set config");
-      return Err(anyhow!("config is already loaded"));
-    }
+  let mut global_config = CONFIG.write().map_err(|e| anyhow!("Could not get lock to the config: {}", e))?;
+  if global_config.is_some() {
+    debug!("didn't set config");
+    return Err(anyhow!("config is already loaded"));
   }
 
-  assert!(CONFIG.get().is_some(), "config wasn't set");
+  *global_config = Some(LoadedConfig { path: config_path.to_path_buf(), file: config });
+  drop(global_config);
 
   Ok(get_config())
 }
 
+/// Get the config of the active profile.
 pub fn get_config() -> Config {
-  match CONFIG.get() {
-    Some(config) => config.clone(),
-    None => panic!("config was not initialized")
+  match CONFIG.read() {
+    Ok(config) => match config.as_ref() {
+      Some(config) => config.file.to_config(),
+      None => panic!("config was not initialized"),
+    },
+    Err(e) => panic!("could not get lock to the config: {}", e),
+  }
+}
+
+/// Names of all profiles.
+pub fn get_profile_names() -> Vec<String> {
+  match CONFIG.read() {
+    Ok(config) => match config.as_ref() {
+      Some(config) => config.file.profiles.iter().map(|profile| profile.name.clone()).collect(),
+      None => panic!("config was not initialized"),
+    },
+    Err(e) => panic!("could not get lock to the config: {}", e),
+  }
+}
+
+/// Switch to the profile and persist the selection.
+pub fn set_active_profile(name: &str) -> Result<(), anyhow::Error> {
+  let mut config = CONFIG.write().map_err(|e| anyhow!("Could not get lock to the config: {}", e))?;
+  let config = config.as_mut().ok_or(anyhow!("config was not initialized"))?;
+
+  if !config.file.profiles.iter().any(|profile| profile.name == name) {
+    return Err(anyhow!("profile '{}' doesn't exist", name));
+  }
+
+  info!("Switching to profile '{}'", name);
+  config.file.active_profile = name.to_string();
+
+  write_config_file(&config.path, &config.file)
+}
+
+/// Replace the input remaps and persist them.
+pub fn set_input_remaps(remaps: Vec<InputRemap>) -> Result<(), anyhow::Error> {
+  let mut config = CONFIG.write().map_err(|e| anyhow!("Could not get lock to the config: {}", e))?;
+  let config = config.as_mut().ok_or(anyhow!("config was not initialized"))?;
+
+  info!("Saving {} input remaps", remaps.len());
+  config.file.input_remaps = remaps;
+
+  write_config_file(&config.path, &config.file)
+}
+
+/// Enable or disable borderless windowed mode for the active profile and persist it.
+pub fn set_borderless_window(enabled: bool) -> Result<(), anyhow::Error> {
+  let mut config = CONFIG.write().map_err(|e| anyhow!("Could not get lock to the config: {}", e))?;
+  let config = config.as_mut().ok_or(anyhow!("config was not initialized"))?;
+
+  let active_profile = config.file.active_profile().name.clone();
+  let profile = config.file.profiles
+    .iter_mut()
+    .find(|profile| profile.name == active_profile)
+    .ok_or(anyhow!("profile '{}' doesn't exist", active_profile))?;
+
+  info!("Setting borderless windowed mode of profile '{}' to {}", profile.name, enabled);
+  profile.borderless_window = enabled;
+
+  write_config_file(&config.path, &config.file)
+}
+
+/// Set the colorblind preset of the active profile and persist it.
+pub fn set_colorblind_preset(preset: ColorblindPreset) -> Result<(), anyhow::Error> {
+  let mut config = CONFIG.write().map_err(|e| anyhow!("Could not get lock to the config: {}", e))?;
+  let config = config.as_mut().ok_or(anyhow!("config was not initialized"))?;
+
+  let active_profile = config.file.active_profile().name.clone();
+  let profile = config.file.profiles
+    .iter_mut()
+    .find(|profile| profile.name == active_profile)
+    .ok_or(anyhow!("profile '{}' doesn't exist", active_profile))?;
+
+  info!("Setting colorblind preset of profile '{}' to {}", profile.name, preset);
+  profile.colorblind_preset = preset;
+
+  write_config_file(&config.path, &config.file)
+}
+
+/// Set the plugins directory of the active profile and persist it, the one in the game's folder if `None`.
+pub fn set_plugins_directory(directory: Option<String>) -> Result<(), anyhow::Error> {
+  let mut config = CONFIG.write().map_err(|e| anyhow!("Could not get lock to the config: {}", e))?;
+  let config = config.as_mut().ok_or(anyhow!("config was not initialized"))?;
+
+  let active_profile = config.file.active_profile().name.clone();
+  let profile = config.file.profiles
+    .iter_mut()
+    .find(|profile| profile.name == active_profile)
+    .ok_or(anyhow!("profile '{}' doesn't exist", active_profile))?;
+
+  info!("Setting the plugins directory of profile '{}' to {:?}", profile.name, directory);
+  profile.plugins_directory = directory;
+
+  write_config_file(&config.path, &config.file)
+}
+
+/// Set the path to the game's executable of the active profile and persist it.
+pub fn set_game_path(path: Option<String>) -> Result<(), anyhow::Error> {
+  let mut config = CONFIG.write().map_err(|e| anyhow!("Could not get lock to the config: {}", e))?;
+  let config = config.as_mut().ok_or(anyhow!("config was not initialized"))?;
+
+  let active_profile = config.file.active_profile().name.clone();
+  let profile = config.file.profiles
+    .iter_mut()
+    .find(|profile| profile.name == active_profile)
+    .ok_or(anyhow!("profile '{}' doesn't exist", active_profile))?;
+
+  info!("Setting the game path of profile '{}' to {:?}", profile.name, path);
+  profile.game_path = path;
+
+  write_config_file(&config.path, &config.file)
+}
+
+/// Enable or disable injecting the mod as soon as the game is detected and persist it.
+pub fn set_auto_inject(enabled: bool) -> Result<(), anyhow::Error> {
+  let mut config = CONFIG.write().map_err(|e| anyhow!("Could not get lock to the config: {}", e))?;
+  let config = config.as_mut().ok_or(anyhow!("config was not initialized"))?;
+
+  info!("Setting automatic injection to {}", enabled);
+  config.file.auto_inject = enabled;
+
+  write_config_file(&config.path, &config.file)
+}
+
+/// Set the channel FutureMod looks for updates in and persist it.
+pub fn set_update_channel(channel: UpdateChannel) -> Result<(), anyhow::Error> {
+  let mut config = CONFIG.write().map_err(|e| anyhow!("Could not get lock to the config: {}", e))?;
+  let config = config.as_mut().ok_or(anyhow!("config was not initialized"))?;
+
+  info!("Setting the update channel to {}", channel);
+  config.file.update_channel = channel;
+
+  write_config_file(&config.path, &config.file)
+}
+
+/// Set the user's rating and notes of the plugin and persist them. Empty notes are removed.
+pub fn set_plugin_note(plugin: &str, note: PluginNote) -> Result<(), anyhow::Error> {
+  let mut config = CONFIG.write().map_err(|e| anyhow!("Could not get lock to the config: {}", e))?;
+  let config = config.as_mut().ok_or(anyhow!("config was not initialized"))?;
+
+  match note.is_empty() {
+    true => {
+      info!("Removing the notes of plugin '{}'", plugin);
+      config.file.plugin_notes.remove(plugin);
+    },
+    false => {
+      info!("Saving the notes of plugin '{}'", plugin);
+      config.file.plugin_notes.insert(plugin.to_string(), note);
+    },
   }
-}
\ No newline at end of file
+
+  write_config_file(&config.path, &config.file)
+}
@@ -0,0 +1,80 @@
+use std::{fmt::Display, time::Duration};
+
+use log::{info, warn};
+
+use crate::{api::is_mod_running, injector::get_pid};
+
+/// Interval in which the game's process and the mod are checked.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// State of the connection between the GUI and the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConnectionState {
+    /// The game's process doesn't exist.
+    #[default]
+    NotRunning,
+    /// The game is running, but the mod wasn't injected into it.
+    RunningUninjected,
+    /// The mod is injected and responds.
+    Injected,
+    /// The mod was injected, but stopped responding while the game is still running.
+    ConnectionLost,
+    /// The mod reported that the game exits and shut down gracefully, the game's process didn't end yet.
+    Exiting,
+}
+
+impl ConnectionState {
+    /// Get the state that follows this state, given whether the game's process exists and whether the mod responds.
+    pub fn next(self, is_game_running: bool, is_mod_running: bool) -> ConnectionState {
+        match (is_game_running, is_mod_running, self) {
+            (_, true, _) => ConnectionState::Injected,
+            (false, false, _) => ConnectionState::NotRunning,
+            (true, false, ConnectionState::Exiting) => ConnectionState::Exiting,
+            (true, false, ConnectionState::Injected | ConnectionState::ConnectionLost) => ConnectionState::ConnectionLost,
+            (true, false, _) => ConnectionState::RunningUninjected,
+        }
+    }
+}
+
+impl Display for ConnectionState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            ConnectionState::NotRunning => "Game not running",
+            ConnectionState::RunningUninjected => "Game running, mod not injected",
+            ConnectionState::Injected => "Mod injected",
+            ConnectionState::ConnectionLost => "Connection to the mod lost",
+            ConnectionState::Exiting => "Game is exiting",
+        };
+
+        write!(f, "{}", description)
+    }
+}
+
+/// Result of checking the game's process and the mod.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionCheck {
+    pub is_game_running: bool,
+    pub is_mod_running: bool,
+}
+
+/// Check whether the game's process exists and whether the mod responds.
+pub async fn check_connection() -> ConnectionCheck {
+    let is_game_running = match get_pid() {
+        Ok(pid) => pid.is_some(),
+        Err(e) => {
+            warn!("Could not check if the game is running: {}", e);
+            false
+        },
+    };
+
+    let is_mod_running = is_mod_running().await;
+
+    ConnectionCheck { is_game_running, is_mod_running }
+}
+
+/// Log the transition between two states.
+pub fn log_transition(previous: ConnectionState, next: ConnectionState) {
+    if previous != next {
+        info!("Connection state changed from '{}' to '{}'", previous, next);
+    }
+}
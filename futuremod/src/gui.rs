@@ -1,14 +1,44 @@
-use iced::{executor, font, Application, Command, Subscription};
-use log::debug;
+use std::path::PathBuf;
 
+use iced::widget::{column, container, row, text};
+use iced::Alignment;
+use futuremod_data::session::GAME_EXIT_LOG_TARGET;
+use iced::{executor, font, Application, Command, Length, Subscription};
+use log::{debug, info, warn};
+use futuremod_data::paths::user_plugins_directory;
+use rfd::FileDialog;
+
+use crate::config::{get_config, set_plugins_directory};
+use crate::connection::{self, check_connection, ConnectionCheck, ConnectionState};
+use crate::log_subscriber;
+use crate::theme::Button;
+use crate::injector::{check_directory_writable, get_unwritable_plugins_directory};
+use crate::update::{check_for_update, Release};
+use crate::util::open_url;
+use crate::widget::button;
 use crate::palette::Palette;
+use crate::theme::Container;
 use crate::{theme, widget::Element};
 
 use super::view::{main, loading};
 
 
 #[derive(Debug)]
-pub enum ModInjector {
+pub struct ModInjector {
+    screen: Screen,
+    connection: ConnectionState,
+    /// Whether a connection check is pending, to not queue checks if the mod responds slowly.
+    is_checking: bool,
+    /// Newer release on the configured update channel.
+    update: Option<Release>,
+    /// Path of the mod that was injected last, to inject it again after it was ejected.
+    injected_mod: Option<PathBuf>,
+    /// Plugins directory in the game's folder that FutureMod can't write to, until the user chose another one.
+    unwritable_plugins_directory: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum Screen {
     Loading(loading::Loading),
     Main(main::Main),
 }
@@ -18,6 +48,14 @@ pub enum Message {
     Loading(loading::Message),
     FontLoaded(Result<(), font::Error>),
     Main(main::Message),
+    CheckConnection,
+    ConnectionChecked(ConnectionCheck),
+    UpdateChecked(Result<Option<Release>, String>),
+    OpenRelease,
+    DismissUpdate,
+    UseUserPluginsDirectory,
+    SelectPluginsDirectory,
+    DismissPluginsDirectoryWarning,
 }
 
 
@@ -31,10 +69,19 @@ impl Application for ModInjector {
         let (loading, message) = loading::Loading::new();
 
         (
-            ModInjector::Loading(loading),
+            ModInjector {
+                screen: Screen::Loading(loading),
+                connection: ConnectionState::NotRunning,
+                is_checking: true,
+                update: None,
+                injected_mod: None,
+                unwritable_plugins_directory: get_unwritable_plugins_directory(&get_config()),
+            },
             Command::batch(vec![
                 font::load(iced_aw::BOOTSTRAP_FONT_BYTES).map(Message::FontLoaded),
-                message.map(Message::Loading)
+                message.map(Message::Loading),
+                Command::perform(check_connection(), Message::ConnectionChecked),
+                Command::perform(check_for_update(get_config().update_channel), Message::UpdateChecked),
             ])
         )
     }
@@ -50,12 +97,100 @@ impl Application for ModInjector {
     fn update(&mut self, message: Self::Message) -> iced::Command<Self::Message> {
         debug!("Handling message: {:?}", message);
 
-        match self {
-            ModInjector::Loading(loading) => {
+        // The mod shut down gracefully, so it stopping to respond until the game's process ends isn't a lost connection
+        if let Message::Main(main::Message::LogEvent(log_subscriber::Event::Message(record))) = &message {
+            if record.target == GAME_EXIT_LOG_TARGET {
+                connection::log_transition(self.connection, ConnectionState::Exiting);
+                self.connection = ConnectionState::Exiting;
+            }
+        }
+
+        match message {
+            Message::CheckConnection => {
+                if self.is_checking {
+                    return Command::none();
+                }
+
+                self.is_checking = true;
+                return Command::perform(check_connection(), Message::ConnectionChecked);
+            },
+            Message::ConnectionChecked(check) => {
+                self.is_checking = false;
+
+                let next = self.connection.next(check.is_game_running, check.is_mod_running);
+                connection::log_transition(self.connection, next);
+                self.connection = next;
+
+                return self.handle_connection();
+            },
+            Message::UpdateChecked(response) => {
+                match response {
+                    Ok(update) => self.update = update,
+                    Err(e) => debug!("Could not check for updates: {}", e),
+                }
+
+                return Command::none();
+            },
+            Message::OpenRelease => {
+                if let Some(update) = &self.update {
+                    if let Err(e) = open_url(&update.url) {
+                        warn!("{}", e);
+                    }
+                }
+
+                return Command::none();
+            },
+            Message::DismissUpdate => {
+                self.update = None;
+                return Command::none();
+            },
+            Message::UseUserPluginsDirectory => {
+                match user_plugins_directory() {
+                    Some(directory) => self.change_plugins_directory(directory),
+                    None => warn!("Could not determine the plugins directory in the user's folder"),
+                }
+
+                return Command::none();
+            },
+            Message::SelectPluginsDirectory => {
+                info!("Prompting user to pick the plugins directory");
+                if let Some(directory) = FileDialog::new().pick_folder() {
+                    self.change_plugins_directory(directory);
+                }
+
+                return Command::none();
+            },
+            Message::DismissPluginsDirectoryWarning => {
+                self.unwritable_plugins_directory = None;
+                return Command::none();
+            },
+            // The plugins directory is in the newly selected game's folder
+            Message::Loading(loading::Message::SelectGamePath) => {
+                let command = match &mut self.screen {
+                    Screen::Loading(loading) => loading.update(loading::Message::SelectGamePath).map(Message::Loading),
+                    Screen::Main(_) => Command::none(),
+                };
+
+                self.unwritable_plugins_directory = get_unwritable_plugins_directory(&get_config());
+                return command;
+            },
+            // Look for updates on the newly selected channel
+            Message::Loading(loading::Message::SelectUpdateChannel(channel)) => {
+                return match &mut self.screen {
+                    Screen::Loading(loading) => Command::batch([
+                        loading.update(loading::Message::SelectUpdateChannel(channel)).map(Message::Loading),
+                        Command::perform(check_for_update(channel), Message::UpdateChecked),
+                    ]),
+                    Screen::Main(_) => Command::none(),
+                };
+            },
+            _ => (),
+        }
+
+        match &mut self.screen {
+            Screen::Loading(loading) => {
                 if let Message::Loading(loading::Message::IsModActive(true)) = message {
-                    let main = main::Main::new();
-                    *self = ModInjector::Main(main);
-                    return Command::none()
+                    return self.show_main();
                 }
 
                 if let Message::Loading(message) = message {
@@ -64,7 +199,8 @@ impl Application for ModInjector {
 
                 Command::none()
             },
-            ModInjector::Main(main) => match message {
+            Screen::Main(main) => match message {
+                Message::Main(main::Message::EjectResponse(Ok(()))) => self.show_ejected(),
                 Message::Main(message) => {
                     main.update(message).map(Message::Main)
                 },
@@ -74,16 +210,162 @@ impl Application for ModInjector {
     }
 
     fn view(&self) -> Element<'_, Self::Message> {
-        match self {
-            ModInjector::Loading(loading) => loading.view().map(Message::Loading),
-            ModInjector::Main(main) => main.view().map(Message::Main),
-        }
+        let screen = match &self.screen {
+            Screen::Loading(loading) => loading.view().map(Message::Loading),
+            Screen::Main(main) => main.view().map(Message::Main),
+        };
+
+        column![
+            connection_banner(self.connection),
+            update_banner(&self.update),
+            plugins_directory_banner(&self.unwritable_plugins_directory),
+            screen,
+        ]
+        .into()
     }
 
     fn subscription(&self) -> iced::Subscription<Self::Message> {
-        match self {
-            ModInjector::Main(main) => main.subscription().map(Message::Main),
-            _ => Subscription::none(),
+        let connection = iced::time::every(connection::CHECK_INTERVAL).map(|_| Message::CheckConnection);
+
+        match &self.screen {
+            Screen::Main(main) => Subscription::batch([
+                main.subscription().map(Message::Main),
+                connection,
+            ]),
+            _ => connection,
+        }
+    }
+}
+
+impl ModInjector {
+    fn show_main(&mut self) -> Command<Message> {
+        if let Screen::Loading(loading) = &self.screen {
+            self.injected_mod = loading.mod_path();
+        }
+
+        let (main, command) = main::Main::new();
+        self.screen = Screen::Main(main);
+
+        command.map(Message::Main)
+    }
+
+    /// Use the directory for plugins from now on and check that FutureMod can write to it.
+    ///
+    /// The mod loads plugins from it the next time it is injected.
+    fn change_plugins_directory(&mut self, directory: PathBuf) {
+        if let Err(e) = set_plugins_directory(Some(directory.to_string_lossy().to_string())) {
+            warn!("Could not change the plugins directory: {}", e);
+            return;
+        }
+
+        self.unwritable_plugins_directory = match check_directory_writable(&directory) {
+            Ok(()) => None,
+            Err(e) => {
+                warn!("Cannot write to the plugins directory '{}': {}", directory.display(), e);
+                Some(directory)
+            },
+        };
+    }
+
+    /// Show the loading screen until the ejected mod was unloaded and can be injected again.
+    fn show_ejected(&mut self) -> Command<Message> {
+        let mod_path = self.injected_mod.clone().unwrap_or(PathBuf::from(&get_config().mod_path));
+        self.screen = Screen::Loading(loading::Loading::Ejected { mod_path, is_unloaded: false });
+
+        // The mod stopped responding on purpose, the connection isn't lost
+        self.connection = ConnectionState::RunningUninjected;
+
+        Command::none()
+    }
+
+    /// Follow the game: show the main view once the mod responds and go back to waiting for the game after it exited.
+    fn handle_connection(&mut self) -> Command<Message> {
+        match (&mut self.screen, self.connection) {
+            // The ejected mod might still respond while it tears itself down
+            (Screen::Loading(loading), ConnectionState::Injected) if !loading.is_ejected() => self.show_main(),
+            (Screen::Loading(loading), state) => loading.update(loading::Message::ConnectionChanged(state)).map(Message::Loading),
+            (Screen::Main(_), ConnectionState::NotRunning) => {
+                let (loading, command) = loading::Loading::new();
+                self.screen = Screen::Loading(loading);
+
+                command.map(Message::Loading)
+            },
+            (Screen::Main(_), _) => Command::none(),
         }
     }
+}
+
+/// Banner at the top of the window that shows the state of the connection to the game.
+fn connection_banner<'a>(state: ConnectionState) -> Element<'a, Message> {
+    let style = match state {
+        ConnectionState::Injected => Container::Box,
+        ConnectionState::ConnectionLost => Container::Danger,
+        ConnectionState::NotRunning | ConnectionState::RunningUninjected | ConnectionState::Exiting => Container::Warning,
+    };
+
+    container(text(state.to_string()).size(12))
+        .padding([4, 16])
+        .width(Length::Fill)
+        .style(style)
+        .into()
+}
+
+/// Banner that points to a newer release of FutureMod, if there is one.
+fn update_banner<'a>(update: &Option<Release>) -> Element<'a, Message> {
+    let update = match update {
+        Some(update) => update,
+        None => return column![].into(),
+    };
+
+    let description = match update.prerelease {
+        true => format!("FutureMod {} is available as pre-release", update.version),
+        false => format!("FutureMod {} is available", update.version),
+    };
+
+    container(
+        row![
+            text(description).size(12).width(Length::Fill),
+            button(text("Open Release").size(12)).on_press(Message::OpenRelease).style(Button::Text),
+            button(text("Dismiss").size(12)).on_press(Message::DismissUpdate).style(Button::Text),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+    )
+    .padding([0, 16])
+    .width(Length::Fill)
+    .style(Container::Box)
+    .into()
+}
+
+/// Banner that explains that plugins can't be installed into the game's folder and offers to use another folder.
+fn plugins_directory_banner<'a>(directory: &Option<PathBuf>) -> Element<'a, Message> {
+    let directory = match directory {
+        Some(directory) => directory,
+        None => return column![].into(),
+    };
+
+    let description = format!(
+        "FutureMod can't write to '{}'. Installing plugins requires administrator access to this folder, use a folder in your user's folder instead.",
+        directory.display(),
+    );
+
+    let mut use_user_directory = button(text("Use User Folder").size(12)).style(Button::Text);
+    if user_plugins_directory().is_some() {
+        use_user_directory = use_user_directory.on_press(Message::UseUserPluginsDirectory);
+    }
+
+    container(
+        row![
+            text(description).size(12).width(Length::Fill),
+            use_user_directory,
+            button(text("Choose Folder").size(12)).on_press(Message::SelectPluginsDirectory).style(Button::Text),
+            button(text("Dismiss").size(12)).on_press(Message::DismissPluginsDirectoryWarning).style(Button::Text),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+    )
+    .padding([0, 16])
+    .width(Length::Fill)
+    .style(Container::Warning)
+    .into()
 }
\ No newline at end of file
@@ -1,14 +1,17 @@
-use std::{ffi::c_void, mem::size_of};
+use std::{ffi::c_void, fs, io, iter::once, mem::size_of, os::windows::ffi::OsStrExt, path::{Path, PathBuf}};
 
-use log::{debug, info};
-use windows::{core::PCSTR, Win32::{Foundation::{GetLastError, HANDLE}, Security::{GetTokenInformation, TokenElevation, TOKEN_ALL_ACCESS, TOKEN_ELEVATION}, System::{Diagnostics::{Debug::WriteProcessMemory, ToolHelp::{CreateToolhelp32Snapshot, Process32First, Process32Next, PROCESSENTRY32, TH32CS_SNAPPROCESS}}, LibraryLoader::{GetModuleHandleA, GetProcAddress}, Memory::{VirtualAllocEx, MEM_COMMIT, PAGE_READWRITE}, Threading::{CreateRemoteThread, OpenProcess, OpenProcessToken, LPTHREAD_START_ROUTINE, PROCESS_ALL_ACCESS}}}};
+use log::{debug, info, warn};
+use windows::{core::{PCSTR, PCWSTR, PWSTR}, Win32::{Foundation::{CloseHandle, FreeLibrary, GetLastError, HANDLE, WAIT_OBJECT_0}, Security::{GetTokenInformation, TokenElevation, TOKEN_ALL_ACCESS, TOKEN_ELEVATION}, System::{Diagnostics::{Debug::WriteProcessMemory, ToolHelp::{CreateToolhelp32Snapshot, Module32First, Module32Next, Process32First, Process32Next, MODULEENTRY32, PROCESSENTRY32, TH32CS_SNAPMODULE, TH32CS_SNAPMODULE32, TH32CS_SNAPPROCESS}}, LibraryLoader::{GetModuleHandleA, GetProcAddress, LoadLibraryExW, DONT_RESOLVE_DLL_REFERENCES}, Memory::{VirtualAllocEx, MEM_COMMIT, PAGE_READWRITE}, Threading::{CreateProcessW, CreateRemoteThread, GetExitCodeThread, OpenProcess, OpenProcessToken, QueryFullProcessImageNameW, ResumeThread, TerminateProcess, WaitForSingleObject, CREATE_SUSPENDED, LPTHREAD_START_ROUTINE, PROCESS_ALL_ACCESS, PROCESS_INFORMATION, PROCESS_NAME_WIN32, STARTUPINFOW}}}};
 use anyhow::anyhow;
+use futuremod_data::{accessibility::ColorblindPreset, paths::user_mod_config_path, version::API_VERSION_EXPORT};
 
-use super::config::get_config;
+use super::config::{get_config, Config};
 
+/// Time to wait for the mod to load into a launched game before giving up.
+const LOAD_TIMEOUT_MILLISECONDS: u32 = 10_000;
 
 pub fn get_pid() -> Result<Option<u32>, anyhow::Error> {
-  info!("Get process id of process");
+  debug!("Get process id of process");
   let config = get_config();
 
   unsafe {
@@ -43,6 +46,45 @@ pub fn get_pid() -> Result<Option<u32>, anyhow::Error> {
   }
 }
 
+/// Check whether the mod's DLL is loaded into the game.
+///
+/// Compares the file names of the game's modules with the mod's file name. Returns `false` if the game isn't running.
+pub fn is_mod_loaded(mod_path: &Path) -> Result<bool, anyhow::Error> {
+    let pid = match get_pid()? {
+        Some(pid) => pid,
+        None => return Ok(false),
+    };
+
+    let mod_name = match mod_path.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => return Err(anyhow!("The mod's path has no file name: {}", mod_path.display())),
+    };
+
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPMODULE | TH32CS_SNAPMODULE32, pid)
+            .map_err(|e| anyhow!("Error while getting the game's modules: {}", e))?;
+
+        let mut entry = MODULEENTRY32::default();
+        entry.dwSize = size_of::<MODULEENTRY32>() as u32;
+
+        let mut result = Module32First(snapshot, &mut entry);
+        while result.is_ok() {
+            if let Ok(module_name) = PCSTR::from_raw(entry.szModule.as_ptr()).to_string() {
+                if module_name.eq_ignore_ascii_case(&mod_name) {
+                    let _ = CloseHandle(snapshot);
+                    return Ok(true);
+                }
+            }
+
+            result = Module32Next(snapshot, &mut entry);
+        }
+
+        let _ = CloseHandle(snapshot);
+    }
+
+    Ok(false)
+}
+
 pub fn get_future_cop_handle(require_admin: bool) -> Result<Option<HANDLE>, anyhow::Error> {
     info!("Getting handle to futurecop process");
     let pid = match get_pid() {
@@ -106,8 +148,12 @@ pub fn get_future_cop_handle(require_admin: bool) -> Result<Option<HANDLE>, anyh
 
 }
 
-pub fn inject_mod(fcop_handle: HANDLE, mod_path: String) -> Result<(), anyhow::Error> {
+/// Inject the mod into the process.
+///
+/// Returns the handle of the remote thread that loads the mod.
+pub fn inject_mod(fcop_handle: HANDLE, mod_path: String) -> Result<HANDLE, anyhow::Error> {
     info!("Injecting mod");
+    let thread_handle: HANDLE;
     unsafe {
         debug!("Allocating memory in process");
         let buffer = VirtualAllocEx(fcop_handle, None, mod_path.len() + 1, MEM_COMMIT, PAGE_READWRITE);
@@ -142,7 +188,7 @@ pub fn inject_mod(fcop_handle: HANDLE, mod_path: String) -> Result<(), anyhow::E
         let start_routine_address: LPTHREAD_START_ROUTINE = std::mem::transmute(GetProcAddress(kernel32_handle, PCSTR("LoadLibraryA\0".as_ptr())));
 
         debug!("Creating remote thread to load mod");
-        match CreateRemoteThread(
+        thread_handle = match CreateRemoteThread(
             fcop_handle,
             None,
             0,
@@ -152,10 +198,292 @@ pub fn inject_mod(fcop_handle: HANDLE, mod_path: String) -> Result<(), anyhow::E
             None,
         ) {
             Err(e) => return Err(anyhow!("Could not create remote thread in process: {}", e)),
-            _ => (),
+            Ok(handle) => handle,
         }
     }
 
     info!("Successfully injected mod");
+    Ok(thread_handle)
+}
+
+/// Start the game suspended, inject the mod, and resume the game after the mod was loaded.
+///
+/// Because the game didn't run any of its code yet, the mod's hooks are installed before the game creates its window.
+/// If the mod can't be loaded, the game is terminated.
+pub fn launch_game(game_path: &Path, mod_path: String, config: &Config) -> Result<(), anyhow::Error> {
+    info!("Launching the game at '{}'", game_path.display());
+
+    if !game_path.is_file() {
+        return Err(anyhow!("The game's executable doesn't exist: {}", game_path.display()));
+    }
+
+    let directory = game_path.parent()
+        .ok_or(anyhow!("Path of the game has no parent directory: {}", game_path.display()))?;
+
+    let application: Vec<u16> = game_path.as_os_str().encode_wide().chain(once(0)).collect();
+    let mut command_line: Vec<u16> = format!("\"{}\"", game_path.display()).encode_utf16().chain(once(0)).collect();
+    let current_directory: Vec<u16> = directory.as_os_str().encode_wide().chain(once(0)).collect();
+
+    let startup_info = STARTUPINFOW {
+        cb: size_of::<STARTUPINFOW>() as u32,
+        ..Default::default()
+    };
+    let mut process_info = PROCESS_INFORMATION::default();
+
+    unsafe {
+        debug!("Creating the game's process suspended");
+        CreateProcessW(
+            PCWSTR(application.as_ptr()),
+            PWSTR(command_line.as_mut_ptr()),
+            None,
+            None,
+            None,
+            CREATE_SUSPENDED,
+            None,
+            PCWSTR(current_directory.as_ptr()),
+            &startup_info,
+            &mut process_info,
+        ).map_err(|e| anyhow!("Could not start the game: {}", e))?;
+    }
+
+    let result = configure_mod(process_info.hProcess, config)
+        .and_then(|_| inject_mod(process_info.hProcess, mod_path))
+        .and_then(wait_for_mod);
+
+    unsafe {
+        if let Err(e) = result {
+            warn!("Could not load the mod into the launched game, terminating it");
+            let _ = TerminateProcess(process_info.hProcess, 1);
+            let _ = CloseHandle(process_info.hThread);
+            let _ = CloseHandle(process_info.hProcess);
+
+            return Err(e);
+        }
+
+        debug!("Resuming the game's main thread");
+        let resumed = ResumeThread(process_info.hThread) != u32::MAX;
+
+        let _ = CloseHandle(process_info.hThread);
+        let _ = CloseHandle(process_info.hProcess);
+
+        if !resumed {
+            return Err(anyhow!("Could not resume the game"));
+        }
+    }
+
+    info!("Launched the game with the mod");
+    Ok(())
+}
+
+/// Wait until the remote thread loaded the mod and check that loading succeeded.
+fn wait_for_mod(thread_handle: HANDLE) -> Result<(), anyhow::Error> {
+    debug!("Waiting for the mod to load");
+
+    unsafe {
+        let wait_result = WaitForSingleObject(thread_handle, LOAD_TIMEOUT_MILLISECONDS);
+
+        let mut exit_code = 0u32;
+        let exit_code_result = GetExitCodeThread(thread_handle, &mut exit_code);
+        let _ = CloseHandle(thread_handle);
+
+        if wait_result != WAIT_OBJECT_0 {
+            return Err(anyhow!("The mod didn't load within {} seconds", LOAD_TIMEOUT_MILLISECONDS / 1000));
+        }
+
+        exit_code_result.map_err(|e| anyhow!("Could not check if the mod was loaded: {}", e))?;
+
+        // The thread returns the result of LoadLibraryA, which is null if the mod couldn't be loaded
+        if exit_code == 0 {
+            return Err(anyhow!("The game could not load the mod"));
+        }
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Get the API version the mod's DLL was built with.
+///
+/// Loads the DLL into FutureMod without running or resolving anything but the function that reports the version.
+/// DLLs from before API versions don't export the function and report 0.
+pub fn get_mod_api_version(mod_path: &Path) -> Result<u32, anyhow::Error> {
+    let path: Vec<u16> = mod_path.as_os_str().encode_wide().chain(once(0)).collect();
+    let export = format!("{}\0", API_VERSION_EXPORT);
+
+    unsafe {
+        let module = LoadLibraryExW(PCWSTR(path.as_ptr()), None, DONT_RESOLVE_DLL_REFERENCES)
+            .map_err(|e| anyhow!("Could not load the mod's DLL: {}", e))?;
+
+        let api_version = match GetProcAddress(module, PCSTR(export.as_ptr())) {
+            Some(function) => {
+                let function: extern "C" fn() -> u32 = std::mem::transmute(function);
+                function()
+            },
+            None => 0,
+        };
+
+        let _ = FreeLibrary(module);
+
+        Ok(api_version)
+    }
+}
+
+/// Plugins directory the mod uses for the game in the directory, the configured one or the one in the game's folder.
+pub fn get_plugins_directory(game_directory: &Path, config: &Config) -> PathBuf {
+    match &config.plugins_directory {
+        Some(directory) => PathBuf::from(directory),
+        None => game_directory.join("plugins"),
+    }
+}
+
+/// Check that the mod can install plugins into the directory by creating it and writing a file into it.
+///
+/// Folders like `C:\Program Files` can only be written to with administrator rights, which the game usually doesn't
+/// have. Without this check, installing a plugin would fail much later with an error that doesn't tell the user why.
+pub fn check_directory_writable(directory: &Path) -> Result<(), io::Error> {
+    fs::create_dir_all(directory)?;
+
+    let test_file = directory.join(".futuremod-write-test");
+    fs::write(&test_file, [])?;
+    fs::remove_file(&test_file)
+}
+
+/// Plugins directory of the configured game that the mod can't write to, `None` if it can or no game is configured.
+pub fn get_unwritable_plugins_directory(config: &Config) -> Option<PathBuf> {
+    let game_directory = config.game_path.as_ref().and_then(|game_path| Path::new(game_path).parent())?;
+    let plugins_directory = get_plugins_directory(game_directory, config);
+
+    match check_directory_writable(&plugins_directory) {
+        Ok(()) => None,
+        Err(e) => {
+            warn!("Cannot write to the plugins directory '{}': {}", plugins_directory.display(), e);
+            Some(plugins_directory)
+        },
+    }
+}
+
+/// Get the directory of the process's executable.
+pub fn get_process_directory(handle: HANDLE) -> Result<PathBuf, anyhow::Error> {
+    let mut buffer = [0u16; 1024];
+    let mut size = buffer.len() as u32;
+
+    unsafe {
+        QueryFullProcessImageNameW(handle, PROCESS_NAME_WIN32, PWSTR(buffer.as_mut_ptr()), &mut size)
+            .map_err(|e| anyhow!("Could not get the path of the process: {}", e))?;
+    }
+
+    let executable = PathBuf::from(String::from_utf16_lossy(&buffer[..size as usize]));
+
+    executable.parent()
+        .map(|directory| directory.to_path_buf())
+        .ok_or(anyhow!("Path of the process has no parent directory: {}", executable.display()))
+}
+
+/// Write the profile's settings and the input remaps for the mod into the mod's config next to the game.
+///
+/// Only overwrites settings the profile defines and keeps all others.
+pub fn configure_mod(handle: HANDLE, config: &Config) -> Result<(), anyhow::Error> {
+    let mod_config_path = get_process_directory(handle)?.join("config.json");
+
+    let mut mod_config: serde_json::Value = match mod_config_path.exists() {
+        true => {
+            let content = fs::read_to_string(&mod_config_path)
+                .map_err(|e| anyhow!("Could not read the mod's config: {}", e))?;
+
+            serde_json::from_str(&content).map_err(|e| anyhow!("Could not parse the mod's config: {}", e))?
+        },
+        false => serde_json::json!({}),
+    };
+
+    let mod_config_object = mod_config.as_object_mut().ok_or(anyhow!("The mod's config is not an object"))?;
+
+    // Settings that couldn't be written next to the game before, which the mod applies on top of its config
+    if let Some(user_config_path) = user_mod_config_path().filter(|path| path.exists()) {
+        let content = fs::read_to_string(&user_config_path)
+            .map_err(|e| anyhow!("Could not read the mod's config in the user's folder: {}", e))?;
+
+        match serde_json::from_str(&content) {
+            Ok(serde_json::Value::Object(user_config)) => mod_config_object.extend(user_config),
+            _ => warn!("Ignoring the mod's config in the user's folder, it is not an object"),
+        }
+    }
+
+    let mut settings = Vec::new();
+
+    if let Some(plugins_directory) = &config.plugins_directory {
+        settings.push(("pluginsDirectory", serde_json::Value::String(plugins_directory.clone())));
+    }
+
+    // Don't add an empty list to configs that never had remaps
+    if !config.input_remaps.is_empty() || mod_config_object.contains_key("inputRemaps") {
+        let input_remaps = serde_json::to_value(&config.input_remaps)
+            .map_err(|e| anyhow!("Could not serialize the input remaps: {}", e))?;
+
+        settings.push(("inputRemaps", input_remaps));
+    }
+
+    if config.borderless_window || mod_config_object.contains_key("borderlessWindow") {
+        settings.push(("borderlessWindow", serde_json::Value::Bool(config.borderless_window)));
+    }
+
+    if config.colorblind_preset != ColorblindPreset::None || mod_config_object.contains_key("colorblindPreset") {
+        let colorblind_preset = serde_json::to_value(config.colorblind_preset)
+            .map_err(|e| anyhow!("Could not serialize the colorblind preset: {}", e))?;
+
+        settings.push(("colorblindPreset", colorblind_preset));
+    }
+
+    if config.developer_mode || mod_config_object.contains_key("developerMode") {
+        settings.push(("developerMode", serde_json::Value::Bool(config.developer_mode)));
+    }
+
+    let mut changed = false;
+    for (key, value) in settings {
+        if mod_config_object.get(key) == Some(&value) {
+            continue;
+        }
+
+        info!("Setting the mod's '{}' to {}", key, value);
+        mod_config_object.insert(String::from(key), value);
+        changed = true;
+    }
+
+    if !changed {
+        return Ok(());
+    }
+
+    let content = serde_json::to_string_pretty(&mod_config)
+        .map_err(|e| anyhow!("Could not serialize the mod's config: {}", e))?;
+
+    write_mod_config(&mod_config_path, &content)
+}
+
+/// Write the mod's config next to the game, or into the user's folder if FutureMod can't write to the game's folder.
+///
+/// The config contains all settings, thus, the one in the user's folder is removed once it is written next to the game.
+fn write_mod_config(mod_config_path: &Path, content: &str) -> Result<(), anyhow::Error> {
+    let user_config_path = user_mod_config_path();
+
+    match fs::write(mod_config_path, content) {
+        Ok(()) => {
+            if let Some(user_config_path) = user_config_path.filter(|path| path.exists()) {
+                if let Err(e) = fs::remove_file(&user_config_path) {
+                    warn!("Could not remove the mod's config in the user's folder: {}", e);
+                }
+            }
+
+            Ok(())
+        },
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            let user_config_path = user_config_path.ok_or(anyhow!("Could not write the mod's config: {}", e))?;
+            warn!("Could not write the mod's config next to the game, writing it to '{}' instead: {}", user_config_path.display(), e);
+
+            if let Some(directory) = user_config_path.parent() {
+                fs::create_dir_all(directory).map_err(|e| anyhow!("Could not create '{}': {}", directory.display(), e))?;
+            }
+
+            fs::write(&user_config_path, content)
+                .map_err(|e| anyhow!("Could not write the mod's config: {}", e))
+        },
+        Err(e) => Err(anyhow!("Could not write the mod's config: {}", e)),
+    }
+}
@@ -10,11 +10,17 @@ mod config;
 mod view;
 mod api;
 mod injector;
+mod connection;
+mod update;
+mod marketplace;
 mod log_subscriber;
+mod watch_subscriber;
+mod repl_subscriber;
 mod theme;
 mod widget;
 mod util;
 mod palette;
+mod starter;
 
 
 #[derive(Parser)]
@@ -0,0 +1,71 @@
+use log::{debug, info};
+use serde::Deserialize;
+
+use crate::update::parse_version;
+
+/// Plugin index the GUI browses plugins in, e.g., a list of community plugins.
+///
+/// The index is a JSON file at [`Config::plugin_index_url`](crate::config::Config::plugin_index_url) that lists plugins
+/// with their latest version and the URL of their plugin package.
+#[derive(Debug, Deserialize)]
+struct PluginIndex {
+    plugins: Vec<IndexedPlugin>,
+}
+
+/// Plugin in the index.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedPlugin {
+    /// Name of the plugin, as in its `info.toml`.
+    pub name: String,
+
+    #[serde(default)]
+    pub description: String,
+
+    /// Latest version of the plugin.
+    pub version: String,
+
+    #[serde(default)]
+    pub authors: Vec<String>,
+
+    /// How often the plugin was downloaded, as reported by the index.
+    #[serde(default)]
+    pub downloads: u64,
+
+    /// URL of the plugin's zip file.
+    pub url: String,
+}
+
+/// Get the plugins in the index at the URL.
+pub async fn fetch_index(url: String) -> Result<Vec<IndexedPlugin>, String> {
+    debug!("Fetching the plugin index from '{}'", url);
+
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("futuremod/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Could not create the HTTP client: {}", e))?;
+
+    let index: PluginIndex = client.get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("Could not get the plugin index: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Could not get the plugin index: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Could not parse the plugin index: {}", e))?;
+
+    info!("Plugin index lists {} plugins", index.plugins.len());
+
+    Ok(index.plugins)
+}
+
+/// Whether the version in the index is newer than the installed version.
+///
+/// Versions that aren't semantic versions are only compared for equality.
+pub fn is_newer(available: &str, installed: &str) -> bool {
+    match (parse_version(available), parse_version(installed)) {
+        (Some(available), Some(installed)) => available > installed,
+        _ => available.trim() != installed.trim(),
+    }
+}
@@ -0,0 +1,114 @@
+use async_tungstenite::{WebSocketStream, tungstenite};
+use futuremod_data::debugger::{ChunkResult, ReplRequest};
+use iced::{subscription::{self, Subscription}, futures::{channel::mpsc, self}};
+use futures::sink::SinkExt;
+use futures::stream::StreamExt;
+use log::*;
+
+
+/// Chunks that can wait to be sent to the mod.
+const BUFFER_SIZE: usize = 16;
+
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Connected(Connection),
+    Disconnected,
+    /// Result of the chunk that was sent first of the chunks without a result.
+    Result(ChunkResult),
+}
+
+/// Connection to the mod's REPL, used to send chunks to the mod.
+#[derive(Debug, Clone)]
+pub struct Connection(mpsc::Sender<ReplRequest>);
+
+impl Connection {
+    /// Send the chunk to the mod. Returns whether the chunk was sent, i.e., the connection is open.
+    pub fn send(&mut self, request: ReplRequest) -> bool {
+        self.0.try_send(request).is_ok()
+    }
+}
+
+pub enum State {
+    Connected(WebSocketStream<async_tungstenite::tokio::ConnectStream>, mpsc::Receiver<ReplRequest>),
+    Disconnected,
+}
+
+pub fn connect(base_address: String) -> Subscription<Event> {
+    struct Connect;
+
+    subscription::channel(
+        std::any::TypeId::of::<Connect>(),
+        100,
+        |mut output| async move {
+            let mut state = State::Disconnected;
+
+            loop {
+                match &mut state {
+                    State::Disconnected => {
+                        match async_tungstenite::tokio::connect_async(
+                            format!("ws://{base_address}/repl")
+                        )
+                        .await
+                        {
+                            Ok((websocket, _)) => {
+                                info!("Connected to REPL websocket");
+                                let (sender, receiver) = mpsc::channel(BUFFER_SIZE);
+                                let _ = output.send(Event::Connected(Connection(sender))).await;
+
+                                state = State::Connected(websocket, receiver);
+                            }
+                            Err(e) => {
+                                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+
+                                warn!("Could not connect to REPL websocket: {}", e);
+
+                                let _ = output.send(Event::Disconnected).await;
+                            }
+                        }
+                    }
+                    State::Connected(websocket, input) => {
+                        let mut fused_websocket = websocket.by_ref().fuse();
+
+                        futures::select! {
+                            received = fused_websocket.select_next_some() => {
+                                match received {
+                                    Ok(tungstenite::Message::Text(message)) => {
+                                        match serde_json::from_str::<ChunkResult>(message.as_str()) {
+                                            Ok(result) => {
+                                                let _ = output.send(Event::Result(result)).await;
+                                            },
+                                            Err(e) => {
+                                                warn!("Could not parse incoming chunk result: {:?}", e);
+                                            }
+                                        }
+                                    },
+                                    Ok(_) => (),
+                                    Err(e) => {
+                                        warn!("Error occurred while receiving chunk results: {}", e.to_string());
+                                        state = State::Disconnected;
+                                        let _ = output.send(Event::Disconnected).await;
+                                    },
+                                }
+                            },
+                            request = input.select_next_some() => {
+                                match serde_json::to_string(&request) {
+                                    Ok(message) => {
+                                        if let Err(e) = websocket.send(tungstenite::Message::Text(message)).await {
+                                            warn!("Could not send chunk to the REPL: {}", e.to_string());
+                                            state = State::Disconnected;
+                                            let _ = output.send(Event::Disconnected).await;
+                                        }
+                                    },
+                                    Err(e) => {
+                                        warn!("Could not serialize chunk: {:?}", e);
+                                    },
+                                }
+                            },
+                        }
+                    },
+                }
+            }
+        }
+    )
+}
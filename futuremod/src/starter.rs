@@ -0,0 +1,82 @@
+use std::io::{Cursor, Write};
+
+use zip::{write::FileOptions, ZipWriter};
+
+/// Plugin that ships with the GUI.
+#[derive(Debug, Clone, Copy)]
+pub struct StarterPlugin {
+    /// Folder of the plugin in the `starter` folder.
+    pub folder: &'static str,
+
+    /// Files of the plugin with their content, by the file's name in the plugin package.
+    files: &'static [(&'static str, &'static str)],
+}
+
+macro_rules! starter_plugin {
+    ($folder:literal) => {
+        StarterPlugin {
+            folder: $folder,
+            files: &[
+                ("info.toml", include_str!(concat!("../starter/", $folder, "/info.toml"))),
+                ("main.lua", include_str!(concat!("../starter/", $folder, "/main.lua"))),
+            ],
+        }
+    };
+}
+
+/// Starter plugins that ship with FutureMod.
+///
+/// The plugins are in the `starter` folder of the repository and are embedded into the GUI when it's built,
+/// so they can be installed without a plugin index. They double as examples of the plugin API and are kept up to date with it.
+pub const STARTER_PLUGINS: [StarterPlugin; 4] = [
+    starter_plugin!("fps_display"),
+    starter_plugin!("sprint"),
+    starter_plugin!("damage_numbers"),
+    starter_plugin!("entity_labels"),
+];
+
+impl StarterPlugin {
+    /// Name of the plugin, as in its `info.toml`.
+    pub fn name(&self) -> String {
+        self.manifest_value("name").unwrap_or_else(|| self.folder.to_string())
+    }
+
+    pub fn description(&self) -> String {
+        self.manifest_value("description").unwrap_or_default()
+    }
+
+    pub fn version(&self) -> String {
+        self.manifest_value("version").unwrap_or_default()
+    }
+
+    /// Create the plugin package, a zip archive with the plugin's files at its root.
+    pub fn package(&self) -> Result<Vec<u8>, String> {
+        let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default();
+
+        for (name, content) in self.files {
+            let error = |e: &dyn std::fmt::Display| format!("Could not package '{}' of the starter plugin '{}': {}", name, self.folder, e);
+
+            archive.start_file(*name, options).map_err(|e| error(&e))?;
+            archive.write_all(content.as_bytes()).map_err(|e| error(&e))?;
+        }
+
+        let archive = archive.finish().map_err(|e| format!("Could not package the starter plugin '{}': {}", self.folder, e))?;
+
+        Ok(archive.into_inner())
+    }
+
+    /// Get a string value of the `info.toml`'s top-level table.
+    ///
+    /// The starter plugins' manifests are part of the repository, so only the simple `key = "value"` lines they use are supported.
+    fn manifest_value(&self, key: &str) -> Option<String> {
+        let (_, manifest) = self.files.iter().find(|(name, _)| *name == "info.toml")?;
+
+        manifest
+            .lines()
+            .take_while(|line| !line.trim_start().starts_with('['))
+            .filter_map(|line| line.split_once('='))
+            .find(|(name, _)| name.trim() == key)
+            .map(|(_, value)| value.trim().trim_matches('"').to_string())
+    }
+}
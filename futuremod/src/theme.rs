@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-use iced::{advanced::widget::text, application::StyleSheet, border::Radius, color, overlay::menu, theme::{self, palette::Pair, Checkbox, Menu, PickList, Toggler}, widget::{button, checkbox, container, pick_list, rule, scrollable, toggler}, Background, Border, Color, Shadow, Vector};
+use iced::{advanced::widget::text, application::StyleSheet, border::Radius, color, overlay::menu, theme::{self, palette::Pair, Checkbox, Menu, PickList, TextInput, Toggler}, widget::{button, checkbox, container, pick_list, rule, scrollable, text_input, toggler}, Background, Border, Color, Shadow, Vector};
 use iced_aw::{style::{card, modal, MenuBarStyle}, CardStyles, ModalStyles};
 
 use crate::{palette::ColorRange, util};
@@ -159,6 +159,8 @@ pub enum Container {
   Warning,
   /// Box used for dialogs
   Dialog,
+  /// Small highlighted label, e.g., to mark plugins installed in developer mode
+  Badge,
   Custom(Box<dyn iced::widget::container::StyleSheet<Style = Theme>>),
 }
 
@@ -192,6 +194,14 @@ impl container::StyleSheet for Theme {
                     shadow: Shadow::default(),
                 }
             },
+            Container::Badge => {
+                container::Appearance {
+                    text_color: Some(self.palette.primary.base.text),
+                    background: Some(self.palette.primary.base.color.into()),
+                    border: Border::with_radius(4),
+                    shadow: Shadow::default(),
+                }
+            },
             Container::Custom(custom) => custom.appearance(self),
             Container::Danger => {
               container::Appearance {
@@ -362,4 +372,36 @@ impl toggler::StyleSheet for Theme {
     fn hovered(&self, style: &Self::Style, is_active: bool) -> toggler::Appearance {
         self.theme.hovered(style, is_active)
     }
-}
\ No newline at end of file
+}
+
+impl text_input::StyleSheet for Theme {
+    type Style = TextInput;
+
+    fn active(&self, style: &Self::Style) -> text_input::Appearance {
+        text_input::StyleSheet::active(&self.theme, style)
+    }
+
+    fn focused(&self, style: &Self::Style) -> text_input::Appearance {
+        text_input::StyleSheet::focused(&self.theme, style)
+    }
+
+    fn placeholder_color(&self, style: &Self::Style) -> Color {
+        text_input::StyleSheet::placeholder_color(&self.theme, style)
+    }
+
+    fn value_color(&self, style: &Self::Style) -> Color {
+        text_input::StyleSheet::value_color(&self.theme, style)
+    }
+
+    fn disabled_color(&self, style: &Self::Style) -> Color {
+        text_input::StyleSheet::disabled_color(&self.theme, style)
+    }
+
+    fn selection_color(&self, style: &Self::Style) -> Color {
+        text_input::StyleSheet::selection_color(&self.theme, style)
+    }
+
+    fn disabled(&self, style: &Self::Style) -> text_input::Appearance {
+        text_input::StyleSheet::disabled(&self.theme, style)
+    }
+}
@@ -0,0 +1,115 @@
+use std::{cmp::Ordering, path::Path};
+
+use futuremod_data::version::{UpdateChannel, API_VERSION};
+use log::{debug, info};
+use serde::Deserialize;
+
+use crate::injector::get_mod_api_version;
+
+/// Releases of FutureMod on GitHub, newest first.
+const RELEASES_URL: &str = "https://api.github.com/repos/Ratsch0k/futuremod/releases";
+
+/// Release of FutureMod that is newer than the running FutureMod.
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub version: String,
+    pub url: String,
+    pub prerelease: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    html_url: String,
+    prerelease: bool,
+    draft: bool,
+}
+
+/// Look for a release on the channel that is newer than the running FutureMod.
+pub async fn check_for_update(channel: UpdateChannel) -> Result<Option<Release>, String> {
+    debug!("Checking for updates on the {} channel", channel);
+
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("futuremod/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Could not create the HTTP client: {}", e))?;
+
+    let releases: Vec<GithubRelease> = client.get(RELEASES_URL)
+        .send()
+        .await
+        .map_err(|e| format!("Could not get the releases: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Could not get the releases: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Could not parse the releases: {}", e))?;
+
+    let current = parse_version(env!("CARGO_PKG_VERSION"));
+
+    let newest = releases.into_iter()
+        .filter(|release| !release.draft)
+        .filter(|release| channel == UpdateChannel::Beta || !release.prerelease)
+        .filter_map(|release| parse_version(&release.tag_name).map(|version| (version, release)))
+        .filter(|(version, _)| current.as_ref().map_or(true, |current| version > current))
+        .max_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(newest.map(|(_, release)| {
+        info!("FutureMod {} is available", release.tag_name);
+
+        Release {
+            version: release.tag_name,
+            url: release.html_url,
+            prerelease: release.prerelease,
+        }
+    }))
+}
+
+/// Version of a release, ordered so that pre-releases come before the full release of the same version.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    numbers: [u64; 3],
+    /// Full releases have no pre-release part and are greater than their pre-releases.
+    is_release: bool,
+    prerelease: String,
+}
+
+/// Parse a version like `v1.2.0` or `1.3.0-beta.1`.
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim().trim_start_matches('v');
+    let (numbers, prerelease) = match version.split_once('-') {
+        Some((numbers, prerelease)) => (numbers, prerelease.to_string()),
+        None => (version, String::new()),
+    };
+
+    let mut parsed = [0u64; 3];
+    for (index, number) in numbers.split('.').enumerate() {
+        *parsed.get_mut(index)? = number.parse().ok()?;
+    }
+
+    Some(Version { numbers: parsed, is_release: prerelease.is_empty(), prerelease })
+}
+
+/// Check that the mod's DLL was built for the API version of FutureMod.
+///
+/// The error tells the user which of both to update.
+pub fn check_mod_compatibility(mod_path: &Path) -> Result<(), String> {
+    let mod_api_version = get_mod_api_version(mod_path)
+        .map_err(|e| format!("Could not check the version of the mod: {}", e))?;
+
+    compare_api_versions(mod_api_version)
+}
+
+/// Compare the API version of an engine with the API version of FutureMod.
+pub fn compare_api_versions(mod_api_version: u32) -> Result<(), String> {
+    match mod_api_version.cmp(&API_VERSION) {
+        Ordering::Equal => Ok(()),
+        Ordering::Less => Err(format!(
+            "The mod (API version {}) is older than FutureMod (API version {}). Update futuremod_engine.dll to the one released together with this FutureMod.",
+            mod_api_version, API_VERSION,
+        )),
+        Ordering::Greater => Err(format!(
+            "FutureMod (API version {}) is older than the mod (API version {}). Update FutureMod to the version released together with the mod.",
+            API_VERSION, mod_api_version,
+        )),
+    }
+}
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{path::Path, process::Command, time::Duration};
 
 use iced::Color;
 use palette::{Hsl, FromColor, rgb::Rgb, Mix};
@@ -69,4 +69,32 @@ pub fn alpha(a: Color, alpha: f32) -> Color {
 /// Waits for the given duration of milliseconds.
 pub async fn wait_for_ms(duration: u64) {
     tokio::time::sleep(Duration::from_millis(duration)).await
-}
\ No newline at end of file
+}
+
+/// Opens the folder in the file explorer or the file with its default application.
+pub fn open_path(path: &Path) -> Result<(), String> {
+    Command::new("explorer")
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Could not open '{}': {}", path.display(), e))
+}
+
+/// Opens the URL in the default browser.
+pub fn open_url(url: &str) -> Result<(), String> {
+    Command::new("explorer")
+        .arg(url)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Could not open '{}': {}", url, e))
+}
+
+/// Parse an address entered as hex with a `0x` prefix or as decimal number.
+pub fn parse_address(address: &str) -> Option<u32> {
+    let address = address.trim();
+
+    match address.strip_prefix("0x").or_else(|| address.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => address.parse().ok(),
+    }
+}
@@ -0,0 +1,280 @@
+use std::fs;
+
+use futuremod_data::docs::{to_markdown, FunctionDocs, LibraryDocs, TypeDocs, ValueDocs};
+use iced::{alignment::{Horizontal, Vertical}, widget::{column, container, row, rule, text, Scrollable}, Alignment, Command, Length};
+use iced_aw::BootstrapIcon;
+use log::info;
+use rfd::FileDialog;
+
+use crate::{api::get_api_docs, theme::{self, Button, Container, Text}, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  GetApiDocsResponse(Result<Vec<LibraryDocs>, String>),
+  SelectLibrary(usize),
+  Export,
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiDocsView {
+  libraries: Vec<LibraryDocs>,
+  selected_library: usize,
+  error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ApiDocs {
+  Loading,
+  Loaded(ApiDocsView),
+  Error(String),
+}
+
+impl ApiDocs {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      ApiDocs::Loading,
+      Command::perform(get_api_docs(), Message::GetApiDocsResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match self {
+      ApiDocs::Loading => match message {
+        Message::GetApiDocsResponse(response) => {
+          *self = match response {
+            Ok(libraries) => ApiDocs::Loaded(ApiDocsView { libraries, selected_library: 0, error: None }),
+            Err(e) => ApiDocs::Error(e),
+          };
+
+          Command::none()
+        },
+        _ => Command::none(),
+      },
+      ApiDocs::Loaded(view) => match message {
+        Message::SelectLibrary(index) => {
+          view.selected_library = index;
+          Command::none()
+        },
+        Message::Export => {
+          let path = match FileDialog::new()
+            .set_title("Export the API Reference")
+            .set_file_name("API.md")
+            .add_filter("Markdown", &["md"])
+            .save_file() {
+              Some(v) => v,
+              None => return Command::none(),
+          };
+
+          info!("Exporting API reference to '{}'", path.display());
+
+          if let Err(e) = fs::write(&path, to_markdown(&view.libraries)) {
+            view.error = Some(format!("Could not export the API reference: {}", e));
+          }
+
+          Command::none()
+        },
+        Message::ClearError => {
+          view.error = None;
+          Command::none()
+        },
+        _ => Command::none(),
+      },
+      ApiDocs::Error(_) => Command::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    match self {
+      ApiDocs::Loading => {
+        container(text("Loading..."))
+          .height(Length::Fill)
+          .width(Length::Fill)
+          .align_x(Horizontal::Center)
+          .align_y(Vertical::Center)
+          .into()
+      },
+      ApiDocs::Error(e) => {
+        column![
+          header(false),
+          container(text(format!("Could not get the API reference: {}", e)))
+            .height(Length::Fill)
+            .width(Length::Fill)
+            .align_x(Horizontal::Center)
+            .align_y(Vertical::Center),
+        ]
+        .into()
+      },
+      ApiDocs::Loaded(view) => {
+        let mut content = Column::new().push(header(true));
+
+        if let Some(err) = &view.error {
+          content = content.push(
+            container(
+              container(
+                row![
+                  text(err).width(Length::Fill),
+                  button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+                ].align_items(Alignment::Center),
+              )
+              .padding(16)
+              .style(Container::Danger)
+            )
+            .padding(16)
+          );
+        }
+
+        let details: Element<Message> = match view.libraries.get(view.selected_library) {
+          Some(library) => library_details(library),
+          None => text("No libraries").into(),
+        };
+
+        content
+          .push(
+            row![
+              library_list(&view.libraries, view.selected_library),
+              rule::Rule::vertical(1.0),
+              details,
+            ]
+            .height(Length::Fill)
+          )
+          .into()
+      },
+    }
+  }
+}
+
+fn header<'a>(can_export: bool) -> Element<'a, Message> {
+  let export_button = button("Export as Markdown").style(Button::Primary);
+  let export_button = match can_export {
+    true => export_button.on_press(Message::Export),
+    false => export_button,
+  };
+
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("API Docs").size(24)).width(Length::Fill),
+    export_button,
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
+
+fn library_list<'a>(libraries: &Vec<LibraryDocs>, selected_library: usize) -> Element<'a, Message> {
+  let mut list = Column::new();
+
+  for (index, library) in libraries.iter().enumerate() {
+    let style = match index == selected_library {
+      true => Button::Primary,
+      false => Button::Text,
+    };
+
+    list = list.push(
+      button(text(library.name.clone()).width(Length::Fill))
+        .on_press(Message::SelectLibrary(index))
+        .style(style)
+        .width(Length::Fill)
+    );
+  }
+
+  Scrollable::new(
+    list
+      .spacing(4)
+      .padding(8)
+  )
+  .width(160)
+  .into()
+}
+
+fn library_details<'a>(library: &LibraryDocs) -> Element<'a, Message> {
+  let mut content = Column::new()
+    .push(text(library.name.clone()).size(24))
+    .push(text(format!("Dependency: {}", library.dependency)));
+
+  if library.is_dangerous() {
+    content = content.push(
+      text("This library is dangerous. Users are warned before installing plugins that depend on it.").style(theme::Text::Warn)
+    );
+  }
+
+  content = content.push(text(library.description.clone()));
+
+  if !library.functions.is_empty() {
+    content = content.push(section("Functions", functions_list(&library.functions)));
+  }
+
+  if !library.constants.is_empty() {
+    content = content.push(section("Constants", values_list(&library.constants)));
+  }
+
+  for type_docs in library.types.iter() {
+    content = content.push(section(&type_docs.name, type_details(type_docs)));
+  }
+
+  Scrollable::new(
+    content
+      .spacing(16)
+      .padding(16)
+      .width(Length::Fill)
+  )
+  .width(Length::Fill)
+  .into()
+}
+
+fn section<'a>(title: &str, content: Element<'a, Message>) -> Element<'a, Message> {
+  column![
+    text(title).size(20),
+    content,
+  ]
+  .spacing(8)
+  .into()
+}
+
+fn functions_list<'a>(functions: &Vec<FunctionDocs>) -> Element<'a, Message> {
+  let mut list = Column::new();
+
+  for function in functions.iter() {
+    list = list.push(
+      column![
+        text(function.signature()).font(bold()),
+        text(function.description.clone()),
+      ]
+      .spacing(2)
+    );
+  }
+
+  list.spacing(12).into()
+}
+
+fn values_list<'a>(values: &Vec<ValueDocs>) -> Element<'a, Message> {
+  let mut list = Column::new();
+
+  for value in values.iter() {
+    list = list.push(
+      row![
+        text(format!("{}: {}", value.name, value.type_name)).font(bold()),
+        text(value.description.clone()),
+      ]
+      .spacing(8)
+    );
+  }
+
+  list.spacing(4).into()
+}
+
+fn type_details<'a>(type_docs: &TypeDocs) -> Element<'a, Message> {
+  let mut content = Column::new().push(text(type_docs.description.clone()));
+
+  if !type_docs.fields.is_empty() {
+    content = content.push(values_list(&type_docs.fields));
+  }
+
+  if !type_docs.methods.is_empty() {
+    content = content.push(functions_list(&type_docs.methods));
+  }
+
+  content.spacing(12).into()
+}
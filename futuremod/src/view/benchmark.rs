@@ -0,0 +1,181 @@
+use futuremod_data::benchmark::{BenchmarkResult, BenchmarkState, StartBenchmark};
+use iced::{alignment::Horizontal, widget::{column, container, row, text, Scrollable}, Alignment, Command, Length};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::{cancel_benchmark, get_benchmark, start_benchmark}, theme::{self, Button, Container, Text}, util::wait_for_ms, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+/// Interval in which the benchmark's state is polled while it runs.
+const POLL_INTERVAL: u64 = 500;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  Start,
+  Cancel,
+  Poll,
+  StateResponse(Result<BenchmarkState, String>),
+  ActionResponse(Result<(), String>),
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Benchmark {
+  state: Option<BenchmarkState>,
+  error: Option<String>,
+}
+
+impl Benchmark {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Benchmark { state: None, error: None },
+      Command::perform(get_benchmark(), Message::StateResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::Start => Command::perform(start_benchmark(StartBenchmark::default().frames), Message::ActionResponse),
+      Message::Cancel => Command::perform(cancel_benchmark(), Message::ActionResponse),
+      Message::Poll => Command::perform(get_benchmark(), Message::StateResponse),
+      Message::ActionResponse(response) => {
+        if let Err(e) = response {
+          self.error = Some(e);
+        }
+
+        Command::perform(get_benchmark(), Message::StateResponse)
+      },
+      Message::StateResponse(response) => match response {
+        Ok(state) => {
+          let is_running = matches!(state, BenchmarkState::Waiting | BenchmarkState::Running { .. });
+          self.state = Some(state);
+
+          match is_running {
+            true => Command::perform(wait_for_ms(POLL_INTERVAL), |_| Message::Poll),
+            false => Command::none(),
+          }
+        },
+        Err(e) => {
+          self.error = Some(e);
+          Command::none()
+        },
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header(&self.state));
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let body: Element<Message> = match &self.state {
+      None => text("Loading...").into(),
+      Some(BenchmarkState::Idle) => text("Start a mission and then start the benchmark to measure how much each enabled plugin slows down the game.").into(),
+      Some(BenchmarkState::Waiting) => text("Waiting for a mission to start...").into(),
+      Some(BenchmarkState::Running { plugin, finished, total }) => column![
+        text(format!("Measuring {} of {}", finished + 1, total)),
+        text(match plugin {
+          Some(plugin) => format!("Only '{}' is enabled", plugin),
+          None => String::from("Measuring the baseline with all plugins disabled"),
+        }),
+        text("Keep the mission running until the benchmark finishes.").style(theme::Text::Warn),
+      ].spacing(8).into(),
+      Some(BenchmarkState::Finished(result)) => benchmark_result(result),
+      Some(BenchmarkState::Cancelled) => text("The benchmark was cancelled").into(),
+    };
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn header<'a>(state: &Option<BenchmarkState>) -> Element<'a, Message> {
+  let action = match state {
+    Some(BenchmarkState::Waiting) | Some(BenchmarkState::Running { .. }) => {
+      button("Cancel").on_press(Message::Cancel).style(Button::Destructive)
+    },
+    Some(_) => button("Start Benchmark").on_press(Message::Start).style(Button::Primary),
+    None => button("Start Benchmark").style(Button::Primary),
+  };
+
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Benchmark").size(24)).width(Length::Fill),
+    action,
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
+
+fn benchmark_result<'a>(result: &BenchmarkResult) -> Element<'a, Message> {
+  fn cell<'a>(content: String) -> Element<'a, Message> {
+    text(content)
+      .width(Length::FillPortion(1))
+      .horizontal_alignment(Horizontal::Right)
+      .into()
+  }
+
+  let mut table = Column::new()
+    .push(
+      row![
+        text("Plugin").font(bold()).width(Length::FillPortion(2)),
+        text("Frame Time").font(bold()).width(Length::FillPortion(1)).horizontal_alignment(Horizontal::Right),
+        text("onUpdate").font(bold()).width(Length::FillPortion(1)).horizontal_alignment(Horizontal::Right),
+        text("Overhead").font(bold()).width(Length::FillPortion(1)).horizontal_alignment(Horizontal::Right),
+      ].spacing(8)
+    )
+    .push(
+      row![
+        text("Baseline (no plugins)").width(Length::FillPortion(2)),
+        cell(format!("{:.3} ms", result.baseline_frame_time)),
+        cell(String::from("-")),
+        cell(String::from("-")),
+      ].spacing(8)
+    );
+
+  for plugin in result.plugins.iter() {
+    table = table.push(
+      row![
+        text(plugin.name.clone()).width(Length::FillPortion(2)),
+        cell(format!("{:.3} ms", plugin.frame_time)),
+        cell(format!("{:.3} ms", plugin.update_time)),
+        cell(format!("{:+.3} ms", plugin.overhead)),
+      ].spacing(8)
+    );
+  }
+
+  column![
+    text(format!("Measured {} frames for the baseline and for every plugin. Plugins are sorted by their overhead.", result.frames)),
+    Scrollable::new(table.spacing(8).width(Length::Fill)).height(Length::Fill),
+  ]
+  .spacing(16)
+  .align_items(Alignment::Start)
+  .into()
+}
+
@@ -0,0 +1,216 @@
+use futuremod_data::bookmark::{Bookmark, BOOKMARK_TYPES};
+use iced::{widget::{column, container, pick_list, row, text, text_input, Scrollable}, Alignment, Command, Length};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::{get_bookmarks, remove_bookmark, set_bookmark}, theme::{Button, Container, Text}, util::parse_address, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  BookmarksResponse(Result<Vec<Bookmark>, String>),
+  NameChanged(String),
+  AddressChanged(String),
+  TypeSelected(String),
+  DescriptionChanged(String),
+  Save,
+  SaveResponse(Result<Vec<Bookmark>, String>),
+  /// Fill the form with the bookmark to change it.
+  Edit(Bookmark),
+  Remove(String),
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bookmarks {
+  bookmarks: Vec<Bookmark>,
+  name: String,
+  address: String,
+  value_type: Option<String>,
+  description: String,
+  error: Option<String>,
+}
+
+impl Bookmarks {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Bookmarks {
+        bookmarks: Vec::new(),
+        name: String::new(),
+        address: String::new(),
+        value_type: None,
+        description: String::new(),
+        error: None,
+      },
+      Command::perform(get_bookmarks(), Message::BookmarksResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::BookmarksResponse(response) => {
+        match response {
+          Ok(bookmarks) => self.bookmarks = bookmarks,
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::NameChanged(name) => {
+        self.name = name;
+        Command::none()
+      },
+      Message::AddressChanged(address) => {
+        self.address = address;
+        Command::none()
+      },
+      Message::TypeSelected(value_type) => {
+        self.value_type = Some(value_type);
+        Command::none()
+      },
+      Message::DescriptionChanged(description) => {
+        self.description = description;
+        Command::none()
+      },
+      Message::Save => {
+        let address = match parse_address(&self.address) {
+          Some(address) => address,
+          None => {
+            self.error = Some(format!("'{}' is not an address, enter it as hex, e.g., 0x4A3F10, or as decimal number", self.address));
+            return Command::none();
+          },
+        };
+
+        let value_type = match &self.value_type {
+          Some(value_type) => value_type.clone(),
+          None => {
+            self.error = Some(String::from("Select the type of the value"));
+            return Command::none();
+          },
+        };
+
+        let bookmark = Bookmark {
+          name: self.name.trim().to_string(),
+          address,
+          value_type,
+          description: self.description.trim().to_string(),
+        };
+
+        Command::perform(set_bookmark(bookmark), Message::SaveResponse)
+      },
+      Message::SaveResponse(response) => {
+        match response {
+          Ok(bookmarks) => {
+            self.bookmarks = bookmarks;
+            self.name.clear();
+            self.address.clear();
+            self.description.clear();
+          },
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::Edit(bookmark) => {
+        self.name = bookmark.name;
+        self.address = format!("0x{:X}", bookmark.address);
+        self.value_type = Some(bookmark.value_type);
+        self.description = bookmark.description;
+        Command::none()
+      },
+      Message::Remove(name) => Command::perform(remove_bookmark(name), Message::BookmarksResponse),
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header());
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let mut table = Column::new()
+      .push(
+        row![
+          text("Name").font(bold()).width(Length::FillPortion(2)),
+          text("Address").font(bold()).width(Length::FillPortion(1)),
+          text("Type").font(bold()).width(Length::FillPortion(1)),
+          text("Description").font(bold()).width(Length::FillPortion(3)),
+          text("").width(72),
+        ].spacing(8)
+      );
+
+    for bookmark in self.bookmarks.iter() {
+      table = table.push(
+        row![
+          text(&bookmark.name).width(Length::FillPortion(2)),
+          text(format!("0x{:X}", bookmark.address)).width(Length::FillPortion(1)),
+          text(&bookmark.value_type).width(Length::FillPortion(1)),
+          text(&bookmark.description).width(Length::FillPortion(3)),
+          row![
+            button(icon(BootstrapIcon::Pencil)).on_press(Message::Edit(bookmark.clone())).style(Button::Text).width(32),
+            button(icon_with_style(BootstrapIcon::Trash, Text::Danger)).on_press(Message::Remove(bookmark.name.clone())).style(Button::Text).width(32),
+          ].spacing(8),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+      );
+    }
+
+    let save = match self.name.trim().is_empty() || self.address.trim().is_empty() {
+      true => button("Save").style(Button::Primary),
+      false => button("Save").on_press(Message::Save).style(Button::Primary),
+    };
+
+    let types: Vec<String> = BOOKMARK_TYPES.iter().map(|value_type| value_type.to_string()).collect();
+
+    let body = column![
+      text("Name addresses in the game's memory to use them in plugins with the dangerous library's `bookmark` function. Saving a bookmark with an existing name replaces it."),
+      row![
+        column![text("Name").font(bold()), text_input("bossHealth", &self.name).on_input(Message::NameChanged)].spacing(4).width(Length::FillPortion(2)),
+        column![text("Address").font(bold()), text_input("0x4A3F10", &self.address).on_input(Message::AddressChanged)].spacing(4).width(Length::FillPortion(1)),
+        column![text("Type").font(bold()), pick_list(types, self.value_type.clone(), Message::TypeSelected)].spacing(4).width(Length::FillPortion(1)),
+        column![text("Description").font(bold()), text_input("", &self.description).on_input(Message::DescriptionChanged).on_submit(Message::Save)].spacing(4).width(Length::FillPortion(3)),
+      ].spacing(8),
+      save,
+      Scrollable::new(table.spacing(8).width(Length::Fill)).height(Length::Fill),
+    ]
+    .spacing(16);
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn header<'a>() -> Element<'a, Message> {
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Bookmarks").size(24)).width(Length::Fill),
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use futuremod_data::plugin::Plugin;
+use iced::{widget::{column, container, row, text, text_input, Scrollable}, Alignment, Command, Length};
+use iced_aw::BootstrapIcon;
+use log::info;
+
+use crate::{api::{get_plugins, install_plugin_from_url, install_plugin_package, update_plugin_from_url, update_plugin_package}, config::get_config, marketplace::{fetch_index, is_newer, IndexedPlugin}, starter::{StarterPlugin, STARTER_PLUGINS}, theme::{Button, Container, Text}, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  Refresh,
+  IndexResponse(Result<Vec<IndexedPlugin>, String>),
+  PluginsResponse(Result<HashMap<String, Plugin>, String>),
+  SearchChanged(String),
+  Install(String),
+  /// Update the installed plugin to the version in the index.
+  Update(String),
+  /// Install the starter plugin in the folder.
+  InstallStarter(String),
+  /// Update the installed plugin to the version of the starter plugin in the folder.
+  UpdateStarter(String),
+  InstallResponse(String, Result<(), String>),
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Browse {
+  /// URL of the plugin index, `None` if none is configured.
+  index_url: Option<String>,
+  index: Option<Vec<IndexedPlugin>>,
+  /// Versions of the installed plugins, by the plugin's name.
+  installed: HashMap<String, String>,
+  search: String,
+  /// Plugin that is being downloaded and installed or updated.
+  installing: Option<String>,
+  error: Option<String>,
+}
+
+impl Browse {
+  pub fn new() -> (Self, Command<Message>) {
+    let mut browse = Browse {
+      index_url: get_config().plugin_index_url,
+      index: None,
+      installed: HashMap::new(),
+      search: String::new(),
+      installing: None,
+      error: None,
+    };
+
+    let command = browse.update(Message::Refresh);
+
+    (browse, command)
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::Refresh => match &self.index_url {
+        Some(url) => Command::batch([
+          Command::perform(fetch_index(url.clone()), Message::IndexResponse),
+          Command::perform(get_plugins(), Message::PluginsResponse),
+        ]),
+        // The installed plugins are still needed for the starter plugins
+        None => Command::perform(get_plugins(), Message::PluginsResponse),
+      },
+      Message::IndexResponse(response) => {
+        match response {
+          Ok(mut plugins) => {
+            plugins.sort_by(|a, b| b.downloads.cmp(&a.downloads).then_with(|| a.name.cmp(&b.name)));
+            self.index = Some(plugins);
+          },
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::PluginsResponse(response) => {
+        match response {
+          Ok(plugins) => {
+            self.installed = plugins.into_iter().map(|(name, plugin)| (name, plugin.info.version)).collect();
+          },
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::SearchChanged(search) => {
+        self.search = search;
+        Command::none()
+      },
+      Message::Install(name) | Message::Update(name) | Message::InstallStarter(name) | Message::UpdateStarter(name) if self.installing.is_some() => {
+        // Only one plugin is installed at a time
+        info!("Not installing '{}' while another plugin is installed", name);
+        Command::none()
+      },
+      Message::Install(name) => match self.index.iter().flatten().find(|plugin| plugin.name == name) {
+        Some(plugin) => {
+          let url = plugin.url.clone();
+          self.installing = Some(name.clone());
+
+          Command::perform(install_plugin_from_url(url), move |result| Message::InstallResponse(name, result))
+        },
+        None => Command::none(),
+      },
+      Message::Update(name) => match self.index.iter().flatten().find(|plugin| plugin.name == name) {
+        Some(plugin) => {
+          let url = plugin.url.clone();
+          self.installing = Some(name.clone());
+
+          Command::perform(update_plugin_from_url(url), move |result| Message::InstallResponse(name, result))
+        },
+        None => Command::none(),
+      },
+      Message::InstallStarter(folder) => match STARTER_PLUGINS.iter().find(|plugin| plugin.folder == folder) {
+        Some(plugin) => self.install_starter(plugin, false),
+        None => Command::none(),
+      },
+      Message::UpdateStarter(folder) => match STARTER_PLUGINS.iter().find(|plugin| plugin.folder == folder) {
+        Some(plugin) => self.install_starter(plugin, true),
+        None => Command::none(),
+      },
+      Message::InstallResponse(name, response) => {
+        self.installing = None;
+
+        match response {
+          Ok(()) => {
+            info!("Installed plugin '{}'", name);
+            Command::perform(get_plugins(), Message::PluginsResponse)
+          },
+          Err(e) => {
+            self.error = Some(e);
+            Command::none()
+          },
+        }
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  /// Package the starter plugin and send it to the mod, which installs it or updates the installed plugin.
+  fn install_starter(&mut self, plugin: &StarterPlugin, update: bool) -> Command<Message> {
+    let package = match plugin.package() {
+      Ok(package) => package,
+      Err(e) => {
+        self.error = Some(e);
+        return Command::none();
+      },
+    };
+
+    let name = plugin.name();
+    self.installing = Some(name.clone());
+
+    if update {
+      Command::perform(update_plugin_package(name.clone(), package), move |result| Message::InstallResponse(name, result))
+    } else {
+      Command::perform(install_plugin_package(name.clone(), package), move |result| Message::InstallResponse(name, result))
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header(&self.search, self.index_url.is_some()));
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let search = self.search.to_lowercase();
+    let matches = |name: &str, description: &str| name.to_lowercase().contains(&search) || description.to_lowercase().contains(&search);
+
+    let mut list = Column::new().spacing(8).width(Length::Fill);
+
+    // Starter plugins ship with FutureMod and are listed even without a plugin index
+    let starters: Vec<&StarterPlugin> = STARTER_PLUGINS.iter().filter(|plugin| matches(&plugin.name(), &plugin.description())).collect();
+    if !starters.is_empty() {
+      list = list.push(text("Starter Plugins").size(20));
+      for plugin in starters {
+        list = list.push(plugin_view(Listing::starter(plugin), &self.installed, &self.installing));
+      }
+    }
+
+    list = list.push(text("Plugin Index").size(20));
+    match (&self.index_url, &self.index) {
+      (None, _) => list = list.push(text("No plugin index is configured. Set pluginIndexUrl in FutureMod's config.json to the URL of a plugin index.")),
+      (Some(_), None) => list = list.push(text("Loading...")),
+      (Some(_), Some(index)) => {
+        let plugins: Vec<&IndexedPlugin> = index
+          .iter()
+          .filter(|plugin| matches(&plugin.name, &plugin.description))
+          .collect();
+
+        if plugins.is_empty() {
+          list = list.push(text("No plugins found."));
+        }
+
+        for plugin in plugins {
+          list = list.push(plugin_view(Listing::indexed(plugin), &self.installed, &self.installing));
+        }
+      },
+    }
+
+    let body = Scrollable::new(list).height(Length::Fill);
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn header<'a>(search: &str, has_index: bool) -> Element<'a, Message> {
+  let mut refresh_button = button(icon(BootstrapIcon::ArrowClockwise)).style(Button::Text);
+  if has_index {
+    refresh_button = refresh_button.on_press(Message::Refresh);
+  }
+
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Browse Plugins").size(24)).width(Length::Fill),
+    text_input("Search", search).on_input(Message::SearchChanged).width(240),
+    refresh_button,
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
+
+/// Plugin that can be installed from the browser, either a starter plugin or a plugin in the index.
+struct Listing {
+  name: String,
+  description: String,
+  version: String,
+  details: String,
+  install: Message,
+  update: Message,
+}
+
+impl Listing {
+  fn starter(plugin: &StarterPlugin) -> Self {
+    Listing {
+      name: plugin.name(),
+      description: plugin.description(),
+      version: plugin.version(),
+      details: format!("Version {}  |  Ships with FutureMod", plugin.version()),
+      install: Message::InstallStarter(plugin.folder.to_string()),
+      update: Message::UpdateStarter(plugin.folder.to_string()),
+    }
+  }
+
+  fn indexed(plugin: &IndexedPlugin) -> Self {
+    let mut details = format!("Version {}  |  {} downloads", plugin.version, plugin.downloads);
+    if !plugin.authors.is_empty() {
+      details = format!("{}  |  by {}", details, plugin.authors.join(", "));
+    }
+
+    Listing {
+      name: plugin.name.clone(),
+      description: plugin.description.clone(),
+      version: plugin.version.clone(),
+      details,
+      install: Message::Install(plugin.name.clone()),
+      update: Message::Update(plugin.name.clone()),
+    }
+  }
+}
+
+fn plugin_view<'a>(plugin: Listing, installed: &HashMap<String, String>, installing: &Option<String>) -> Element<'a, Message> {
+  let action: Element<'a, Message> = match (installed.get(&plugin.name), installing) {
+    (_, Some(name)) if *name == plugin.name => text("Installing...").into(),
+    (Some(version), installing) if is_newer(&plugin.version, version) => {
+      let mut update_button = button(text("Update")).style(Button::Primary);
+      if installing.is_none() {
+        update_button = update_button.on_press(plugin.update);
+      }
+
+      row![
+        container(text(format!("Update available: {} → {}", version, plugin.version)).size(12).style(Text::Warn))
+          .style(Container::Badge)
+          .padding([2, 6]),
+        update_button,
+      ]
+      .spacing(8)
+      .align_items(Alignment::Center)
+      .into()
+    },
+    (Some(_), _) => {
+      container(text("Installed").size(12))
+        .style(Container::Badge)
+        .padding([2, 6])
+        .into()
+    },
+    (None, Some(_)) => button(text("Install")).style(Button::Primary).into(),
+    (None, None) => button(text("Install")).style(Button::Primary).on_press(plugin.install).into(),
+  };
+
+  container(
+    row![
+      column![
+        text(plugin.name).font(bold()),
+        text(plugin.description).size(12),
+        text(plugin.details).size(12),
+      ]
+      .spacing(4)
+      .width(Length::Fill),
+      action,
+    ]
+    .spacing(16)
+    .align_items(Alignment::Center)
+  )
+  .padding(12)
+  .width(Length::Fill)
+  .style(Container::Box)
+  .into()
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use futuremod_data::compatibility::{CompatibilityEntry, CompatibilityReport};
+use iced::{alignment::Horizontal, widget::{checkbox, column, container, row, text, Scrollable}, Alignment, Command, Length};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::{clear_compatibility_report, get_compatibility_report, set_compatibility_reporting}, theme::{Button, Container, Text}, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+/// Number of characters of a game's hash that are shown, enough to tell the builds apart.
+const SHORT_HASH_LENGTH: usize = 12;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  ToggleReporting(bool),
+  Clear,
+  ReportResponse(Result<CompatibilityReport, String>),
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Compatibility {
+  report: Option<CompatibilityReport>,
+  error: Option<String>,
+}
+
+impl Compatibility {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Compatibility { report: None, error: None },
+      Command::perform(get_compatibility_report(), Message::ReportResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::ToggleReporting(enabled) => Command::perform(set_compatibility_reporting(enabled), Message::ReportResponse),
+      Message::Clear => Command::perform(clear_compatibility_report(), Message::ReportResponse),
+      Message::ReportResponse(response) => {
+        match response {
+          Ok(report) => self.report = Some(report),
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header(&self.report));
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let body: Element<Message> = match &self.report {
+      None => text("Loading...").into(),
+      Some(report) => report_view(report),
+    };
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn header<'a>(report: &Option<CompatibilityReport>) -> Element<'a, Message> {
+  let enabled = report.as_ref().is_some_and(|report| report.enabled);
+
+  let mut toggle = checkbox("Collect compatibility data", enabled);
+  if report.is_some() {
+    toggle = toggle.on_toggle(Message::ToggleReporting);
+  }
+
+  let clear = match report {
+    Some(report) if !report.entries.is_empty() => button("Clear").on_press(Message::Clear).style(Button::Destructive),
+    _ => button("Clear").style(Button::Destructive),
+  };
+
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Compatibility").size(24)).width(Length::Fill),
+    toggle,
+    clear,
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
+
+fn short_hash(hash: &str) -> String {
+  hash.chars().take(SHORT_HASH_LENGTH).collect()
+}
+
+fn report_view<'a>(report: &CompatibilityReport) -> Element<'a, Message> {
+  let game = match &report.game_hash {
+    Some(hash) => format!("This game's build is {}, the engine's version is {}.", short_hash(hash), report.engine_version),
+    None => String::from("The game's build couldn't be identified, no data is collected."),
+  };
+
+  let intro = match report.enabled {
+    true => "The mod counts, on this computer only, in how many sessions each plugin's version ran and how many errors it threw with each build of the game and version of the engine.",
+    false => "Enable collecting compatibility data to count, on this computer only, in how many sessions each plugin's version ran and how many errors it threw with each build of the game and version of the engine. Nothing is sent anywhere.",
+  };
+
+  if report.entries.is_empty() {
+    return column![text(intro), text(game), text("No data was collected yet.")].spacing(16).into();
+  }
+
+  let mut plugins: BTreeMap<&str, Vec<&CompatibilityEntry>> = BTreeMap::new();
+  for entry in report.entries.iter() {
+    plugins.entry(&entry.plugin).or_default().push(entry);
+  }
+
+  let mut list = Column::new().spacing(24).width(Length::Fill);
+  for (plugin, entries) in plugins {
+    list = list.push(plugin_view(plugin, entries, report.game_hash.as_deref()));
+  }
+
+  column![
+    text(intro),
+    text(game),
+    Scrollable::new(list).height(Length::Fill),
+  ]
+  .spacing(16)
+  .into()
+}
+
+/// Summary of the plugin's compatibility, e.g., that it only fails with one of the game's builds.
+fn summary(entries: &[&CompatibilityEntry]) -> (String, Text) {
+  let mut failing: Vec<String> = entries.iter().filter(|entry| entry.errors > 0).map(|entry| short_hash(&entry.game_hash)).collect();
+  let mut working: Vec<String> = entries.iter().filter(|entry| entry.errors == 0).map(|entry| short_hash(&entry.game_hash)).collect();
+  failing.sort();
+  failing.dedup();
+  working.sort();
+  working.dedup();
+
+  // A build that failed with one version but worked with another still counts as failing
+  working.retain(|build| !failing.contains(build));
+
+  match (failing.is_empty(), working.is_empty()) {
+    (true, _) => (String::from("No errors"), Text::Default),
+    (false, true) => (format!("Errors on every build it ran on: {}", failing.join(", ")), Text::Danger),
+    (false, false) => (format!("Errors only on build {}, works on {}", failing.join(", "), working.join(", ")), Text::Warn),
+  }
+}
+
+fn plugin_view<'a>(plugin: &str, mut entries: Vec<&CompatibilityEntry>, game_hash: Option<&str>) -> Element<'a, Message> {
+  fn cell<'a>(content: String) -> Element<'a, Message> {
+    text(content)
+      .width(Length::FillPortion(1))
+      .horizontal_alignment(Horizontal::Right)
+      .into()
+  }
+
+  entries.sort_by(|a, b| (&a.game_hash, &a.engine_version, &a.plugin_version).cmp(&(&b.game_hash, &b.engine_version, &b.plugin_version)));
+
+  let (summary, summary_style) = summary(&entries);
+
+  let mut table = Column::new()
+    .push(
+      row![
+        text("Game Build").font(bold()).width(Length::FillPortion(2)),
+        text("Engine").font(bold()).width(Length::FillPortion(1)),
+        text("Plugin").font(bold()).width(Length::FillPortion(1)),
+        text("Sessions").font(bold()).width(Length::FillPortion(1)).horizontal_alignment(Horizontal::Right),
+        text("Errors").font(bold()).width(Length::FillPortion(1)).horizontal_alignment(Horizontal::Right),
+      ].spacing(8)
+    )
+    .spacing(8);
+
+  for entry in entries {
+    let build = match Some(entry.game_hash.as_str()) == game_hash {
+      true => format!("{} (this game)", short_hash(&entry.game_hash)),
+      false => short_hash(&entry.game_hash),
+    };
+
+    table = table.push(
+      row![
+        text(build).width(Length::FillPortion(2)),
+        text(entry.engine_version.clone()).width(Length::FillPortion(1)),
+        text(entry.plugin_version.clone()).width(Length::FillPortion(1)),
+        cell(entry.sessions.to_string()),
+        text(entry.errors.to_string())
+          .width(Length::FillPortion(1))
+          .horizontal_alignment(Horizontal::Right)
+          .style(if entry.errors > 0 { Text::Danger } else { Text::Default }),
+      ].spacing(8)
+    );
+  }
+
+  column![
+    text(plugin.to_string()).size(20),
+    text(summary).style(summary_style),
+    table,
+  ]
+  .spacing(8)
+  .into()
+}
@@ -0,0 +1,283 @@
+use std::{collections::HashMap, fmt::Display};
+
+use futuremod_data::{debugger::{ChunkResult, ReplRequest}, plugin::Plugin};
+use iced::{widget::{column, container, pick_list, row, scrollable::{self, RelativeOffset}, text, text_input, Scrollable}, Alignment, Command, Length, Subscription};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::get_plugins, config::get_config, repl_subscriber, theme::{self, Button, Container, Text}, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+/// Id of the output's scrollable, to scroll to new output.
+const OUTPUT_ID: &str = "console-output";
+
+/// Lines of output the console keeps.
+const MAX_LINES: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  Event(repl_subscriber::Event),
+  PluginsResponse(Result<HashMap<String, Plugin>, String>),
+  TargetSelected(Target),
+  InputChanged(String),
+  Run,
+  Clear,
+  ClearError,
+}
+
+/// Environment chunks run in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+  Scratch,
+  Plugin(String),
+}
+
+impl Display for Target {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Target::Scratch => write!(f, "Scratch environment"),
+      Target::Plugin(name) => write!(f, "Plugin: {}", name),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LineKind {
+  Chunk,
+  Output,
+  Value,
+  Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Console {
+  connection: Option<repl_subscriber::Connection>,
+  targets: Vec<Target>,
+  target: Target,
+  input: String,
+  lines: Vec<(LineKind, String)>,
+  /// Chunks sent to the mod without a result yet.
+  pending: usize,
+  error: Option<String>,
+}
+
+impl Console {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Console {
+        connection: None,
+        targets: vec![Target::Scratch],
+        target: Target::Scratch,
+        input: String::new(),
+        lines: Vec::new(),
+        pending: 0,
+        error: None,
+      },
+      Command::perform(get_plugins(), Message::PluginsResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::Event(event) => {
+        match event {
+          repl_subscriber::Event::Connected(connection) => self.connection = Some(connection),
+          repl_subscriber::Event::Disconnected => {
+            // Results of pending chunks are lost with the connection
+            if self.pending > 0 {
+              self.push(LineKind::Error, "Lost the connection to the mod before the chunk finished");
+            }
+
+            self.connection = None;
+            self.pending = 0;
+          },
+          repl_subscriber::Event::Result(result) => {
+            self.pending = self.pending.saturating_sub(1);
+            self.push_result(result);
+
+            return scrollable::snap_to(scrollable::Id::new(OUTPUT_ID), RelativeOffset::END);
+          },
+        }
+
+        Command::none()
+      },
+      Message::PluginsResponse(response) => {
+        match response {
+          Ok(plugins) => {
+            let mut names: Vec<String> = plugins
+              .into_iter()
+              .filter(|(_, plugin)| plugin.enabled)
+              .map(|(name, _)| name)
+              .collect();
+            names.sort();
+
+            self.targets = std::iter::once(Target::Scratch).chain(names.into_iter().map(Target::Plugin)).collect();
+          },
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::TargetSelected(target) => {
+        self.target = target;
+        Command::none()
+      },
+      Message::InputChanged(input) => {
+        self.input = input;
+        Command::none()
+      },
+      Message::Run => {
+        let chunk = self.input.trim().to_string();
+        if chunk.is_empty() {
+          return Command::none();
+        }
+
+        let connection = match &mut self.connection {
+          Some(connection) => connection,
+          None => {
+            self.error = Some("Not connected to the mod".to_string());
+            return Command::none();
+          },
+        };
+
+        let plugin = match &self.target {
+          Target::Scratch => None,
+          Target::Plugin(name) => Some(name.clone()),
+        };
+
+        if !connection.send(ReplRequest { chunk: chunk.clone(), plugin }) {
+          self.error = Some("Could not send the chunk to the mod".to_string());
+          return Command::none();
+        }
+
+        self.pending += 1;
+        self.input.clear();
+        self.push(LineKind::Chunk, &format!("> {}", chunk));
+
+        scrollable::snap_to(scrollable::Id::new(OUTPUT_ID), RelativeOffset::END)
+      },
+      Message::Clear => {
+        self.lines.clear();
+        Command::none()
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  fn push(&mut self, kind: LineKind, line: &str) {
+    self.lines.push((kind, line.to_string()));
+
+    if self.lines.len() > MAX_LINES {
+      self.lines.drain(..self.lines.len() - MAX_LINES);
+    }
+  }
+
+  fn push_result(&mut self, result: ChunkResult) {
+    for line in result.output {
+      self.push(LineKind::Output, &line);
+    }
+
+    if let Some(value) = result.value {
+      self.push(LineKind::Value, &value);
+    }
+
+    if let Some(error) = result.error {
+      self.push(LineKind::Error, &error);
+    }
+  }
+
+  /// Stay connected to the mod's REPL while the view is shown.
+  pub fn subscription(&self) -> Subscription<Message> {
+    repl_subscriber::connect(get_config().mod_address).map(Message::Event)
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header(self.connection.is_some()));
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let mut output = Column::new();
+    for (kind, line) in self.lines.iter() {
+      output = output.push(match kind {
+        LineKind::Chunk => text(line).font(bold()),
+        LineKind::Output => text(line),
+        LineKind::Value => text(line).style(theme::Text::Color(iced::Color::from_rgb8(154, 255, 117))),
+        LineKind::Error => text(line).style(theme::Text::Danger),
+      });
+    }
+
+    if self.pending > 0 {
+      output = output.push(text("Running...").style(theme::Text::Warn));
+    }
+
+    let run = match self.connection.is_some() && !self.input.trim().is_empty() {
+      true => button("Run").on_press(Message::Run).style(Button::Primary),
+      false => button("Run").style(Button::Primary),
+    };
+
+    let body = column![
+      text("Run Lua chunks in the game while it runs. Expressions show their value and the output of print is shown below the chunk. The scratch environment has the game and matrix libraries as globals and keeps its globals between chunks. Chunks that run longer than 500 ms are aborted."),
+      row![
+        column![text("Environment").font(bold()), pick_list(self.targets.clone(), Some(self.target.clone()), Message::TargetSelected)].spacing(4).width(Length::Fill),
+        button("Clear").on_press(Message::Clear).style(Button::Secondary),
+      ].spacing(8).align_items(Alignment::End),
+      container(
+        Scrollable::new(output.spacing(4).width(Length::Fill))
+          .id(scrollable::Id::new(OUTPUT_ID))
+          .height(Length::Fill)
+      )
+      .padding(8)
+      .height(Length::Fill)
+      .style(Container::Box),
+      row![
+        text_input("game.getPlayer(0).health", &self.input)
+          .on_input(Message::InputChanged)
+          .on_submit(Message::Run)
+          .width(Length::Fill),
+        run,
+      ].spacing(8),
+    ]
+    .spacing(16);
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn header<'a>(connected: bool) -> Element<'a, Message> {
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Console").size(24)).width(Length::Fill),
+    text(match connected {
+      true => "",
+      false => "Connecting...",
+    }).style(theme::Text::Warn),
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
@@ -0,0 +1,250 @@
+use std::{collections::HashMap, time::Duration};
+
+use futuremod_data::{debugger::{DebuggerState, EnvironmentEntry}, plugin::Plugin};
+use iced::{widget::{column, container, pick_list, row, text, Scrollable}, Alignment, Command, Length, Subscription};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::{get_debugger_state, get_plugin_environment, get_plugins, pause_game, resume_game, step_game}, theme::{Button, Container, Text}, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+/// Interval in which the debugger's state is polled.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  Refresh,
+  StateResponse(Result<DebuggerState, String>),
+  Pause,
+  Resume,
+  Step(u32),
+  PluginsResponse(Result<HashMap<String, Plugin>, String>),
+  PluginSelected(String),
+  EnvironmentResponse(Result<Vec<EnvironmentEntry>, String>),
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Debugger {
+  state: Option<DebuggerState>,
+  plugins: Vec<String>,
+  selected: Option<String>,
+  entries: Vec<EnvironmentEntry>,
+  /// Frame at which the environment was inspected last.
+  inspected_frame: Option<u64>,
+  /// Whether a state request is pending, to not queue requests if the mod responds slowly.
+  is_polling: bool,
+  error: Option<String>,
+}
+
+impl Debugger {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Debugger {
+        state: None,
+        plugins: Vec::new(),
+        selected: None,
+        entries: Vec::new(),
+        inspected_frame: None,
+        is_polling: true,
+        error: None,
+      },
+      Command::batch(vec![
+        Command::perform(get_debugger_state(), Message::StateResponse),
+        Command::perform(get_plugins(), Message::PluginsResponse),
+      ]),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::Refresh => {
+        if self.is_polling {
+          return Command::none();
+        }
+
+        self.is_polling = true;
+        Command::perform(get_debugger_state(), Message::StateResponse)
+      },
+      Message::StateResponse(response) => {
+        self.is_polling = false;
+
+        match response {
+          Ok(state) => {
+            let frame = state.frame;
+            self.state = Some(state);
+
+            // Inspect again once the game ran, the plugin may have changed its globals
+            if self.inspected_frame.is_some_and(|inspected| inspected != frame) {
+              return self.inspect();
+            }
+          },
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::Pause => Command::perform(pause_game(), Message::StateResponse),
+      Message::Resume => Command::perform(resume_game(), Message::StateResponse),
+      Message::Step(frames) => Command::perform(step_game(frames), Message::StateResponse),
+      Message::PluginsResponse(response) => {
+        match response {
+          Ok(plugins) => {
+            let mut names: Vec<String> = plugins
+              .into_iter()
+              .filter(|(_, plugin)| plugin.enabled)
+              .map(|(name, _)| name)
+              .collect();
+            names.sort();
+
+            self.plugins = names;
+          },
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::PluginSelected(name) => {
+        self.selected = Some(name);
+        self.entries.clear();
+        self.inspect()
+      },
+      Message::EnvironmentResponse(response) => {
+        match response {
+          Ok(entries) => self.entries = entries,
+          Err(e) => {
+            self.entries.clear();
+            self.error = Some(e);
+          },
+        }
+
+        Command::none()
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  fn inspect(&mut self) -> Command<Message> {
+    let name = match &self.selected {
+      Some(name) => name.clone(),
+      None => return Command::none(),
+    };
+
+    self.inspected_frame = self.state.as_ref().map(|state| state.frame);
+    Command::perform(get_plugin_environment(name), Message::EnvironmentResponse)
+  }
+
+  /// Poll the debugger's state to notice frames the game ran.
+  pub fn subscription(&self) -> Subscription<Message> {
+    iced::time::every(REFRESH_INTERVAL).map(|_| Message::Refresh)
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header());
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let paused = self.state.as_ref().is_some_and(|state| state.paused);
+    let status = match &self.state {
+      Some(state) if state.paused => format!("Paused at frame {}", state.frame),
+      Some(state) => format!("Running, frame {}", state.frame),
+      None => String::from("Loading..."),
+    };
+
+    let mut step_one = button("Step 1 frame").style(Button::Secondary);
+    let mut step_ten = button("Step 10 frames").style(Button::Secondary);
+    if paused {
+      step_one = step_one.on_press(Message::Step(1));
+      step_ten = step_ten.on_press(Message::Step(10));
+    }
+
+    let toggle = match paused {
+      true => button("Resume").on_press(Message::Resume).style(Button::Primary),
+      false => button("Pause").on_press(Message::Pause).style(Button::Primary),
+    };
+
+    let mut table = Column::new()
+      .push(
+        row![
+          text("Name").font(bold()).width(Length::FillPortion(1)),
+          text("Type").font(bold()).width(Length::FillPortion(1)),
+          text("Value").font(bold()).width(Length::FillPortion(3)),
+        ].spacing(8)
+      );
+
+    for entry in self.entries.iter() {
+      table = table.push(entry_row(entry, 0));
+
+      for field in entry.fields.iter() {
+        table = table.push(entry_row(field, 1));
+      }
+    }
+
+    let environment: Element<Message> = match (&self.selected, self.entries.is_empty()) {
+      (None, _) => text("Select a plugin to inspect its globals.").into(),
+      (Some(_), true) => text("The plugin has no globals.").into(),
+      (Some(_), false) => Scrollable::new(table.spacing(8).width(Length::Fill)).height(Length::Fill).into(),
+    };
+
+    let body = column![
+      text("Pause the game loop and step through it frame by frame while inspecting the globals of a plugin. Only missions are paused and the game window doesn't respond while the game is paused."),
+      row![
+        text(status).font(bold()).width(Length::Fill),
+        step_one,
+        step_ten,
+        toggle,
+      ].spacing(8).align_items(Alignment::Center),
+      column![text("Plugin").font(bold()), pick_list(self.plugins.clone(), self.selected.clone(), Message::PluginSelected)].spacing(4),
+      environment,
+    ]
+    .spacing(16);
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn entry_row(entry: &EnvironmentEntry, depth: u16) -> Element<Message> {
+  row![
+    container(text(&entry.name)).padding([0, 0, 0, depth * 16]).width(Length::FillPortion(1)),
+    text(&entry.value_type).width(Length::FillPortion(1)),
+    text(&entry.value).width(Length::FillPortion(3)),
+  ]
+  .spacing(8)
+  .align_items(Alignment::Center)
+  .into()
+}
+
+fn header<'a>() -> Element<'a, Message> {
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Debugger").size(24)).width(Length::Fill),
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
@@ -0,0 +1,189 @@
+use chrono::{Local, TimeZone};
+use futuremod_data::diagnostics::{CheckStatus, DiagnosticCheck, Diagnostics, TemporaryDirectoryUsage};
+use iced::{widget::{column, container, row, text, Scrollable}, Alignment, Color, Command, Length};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::get_diagnostics, theme::{Button, Container, Text}, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  Refresh,
+  DiagnosticsResponse(Result<Diagnostics, String>),
+  CopyReport,
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Health {
+  diagnostics: Option<Diagnostics>,
+  error: Option<String>,
+}
+
+impl Health {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Health { diagnostics: None, error: None },
+      Command::perform(get_diagnostics(), Message::DiagnosticsResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::Refresh => Command::perform(get_diagnostics(), Message::DiagnosticsResponse),
+      Message::DiagnosticsResponse(response) => {
+        match response {
+          Ok(diagnostics) => self.diagnostics = Some(diagnostics),
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::CopyReport => match &self.diagnostics {
+        Some(diagnostics) => iced::clipboard::write(report(diagnostics)),
+        None => Command::none(),
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header(self.diagnostics.is_some()));
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let body: Element<Message> = match &self.diagnostics {
+      None => text("Loading...").into(),
+      Some(diagnostics) => {
+        let mut checks = Column::new().spacing(8);
+
+        for check in diagnostics.checks.iter() {
+          checks = checks.push(check_view(check));
+        }
+
+        let mut overview = column![text(summary(diagnostics)).size(12)].spacing(4);
+        if let Some(usage) = &diagnostics.temporary_directory {
+          overview = overview.push(text(temporary_directory(usage)).size(12));
+        }
+
+        column![
+          overview,
+          Scrollable::new(checks.width(Length::Fill)).height(Length::Fill),
+        ]
+        .spacing(16)
+        .into()
+      },
+    };
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn header<'a>(has_diagnostics: bool) -> Element<'a, Message> {
+  let mut copy_button = button(text("Copy Report")).style(Button::Secondary);
+  if has_diagnostics {
+    copy_button = copy_button.on_press(Message::CopyReport);
+  }
+
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Health").size(24)).width(Length::Fill),
+    copy_button,
+    button(icon(BootstrapIcon::ArrowClockwise)).style(Button::Text).on_press(Message::Refresh),
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
+
+fn check_view(check: &DiagnosticCheck) -> Element<Message> {
+  let status_icon = match check.status {
+    CheckStatus::Passed => icon_with_style(BootstrapIcon::CheckCircleFill, Text::Color(Color::from_rgb8(154, 255, 117))),
+    CheckStatus::Warning => icon_with_style(BootstrapIcon::ExclamationTriangleFill, Text::Warn),
+    CheckStatus::Failed => icon_with_style(BootstrapIcon::XCircleFill, Text::Danger),
+  };
+
+  container(
+    row![
+      status_icon,
+      column![
+        text(&check.name).font(bold()),
+        text(&check.message).size(12),
+      ]
+      .spacing(4)
+      .width(Length::Fill),
+    ]
+    .spacing(16)
+    .align_items(Alignment::Center)
+  )
+  .padding(12)
+  .width(Length::Fill)
+  .style(Container::Box)
+  .into()
+}
+
+fn count(diagnostics: &Diagnostics, status: CheckStatus) -> usize {
+  diagnostics.checks.iter().filter(|check| check.status == status).count()
+}
+
+fn summary(diagnostics: &Diagnostics) -> String {
+  format!(
+    "Ran at {}: {} passed, {} warnings, {} failed",
+    format_time(diagnostics.ran_at),
+    count(diagnostics, CheckStatus::Passed),
+    count(diagnostics, CheckStatus::Warning),
+    count(diagnostics, CheckStatus::Failed),
+  )
+}
+
+/// Plain text report of the checks to attach to support requests.
+fn report(diagnostics: &Diagnostics) -> String {
+  let mut report = format!("FutureCop Mod health report\n{}\n\n", summary(diagnostics));
+
+  for check in diagnostics.checks.iter() {
+    report.push_str(&format!("[{:?}] {}: {}\n", check.status, check.name, check.message));
+  }
+
+  if let Some(usage) = &diagnostics.temporary_directory {
+    report.push_str(&format!("\n{}\n", temporary_directory(usage)));
+  }
+
+  report
+}
+
+fn temporary_directory(usage: &TemporaryDirectoryUsage) -> String {
+  format!("Temporary files: {} entries, {} KB in '{}'", usage.entries, usage.size / 1024, usage.path)
+}
+
+fn format_time(timestamp: u64) -> String {
+  match Local.timestamp_opt(timestamp as i64, 0).single() {
+    Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+    None => String::new(),
+  }
+}
@@ -1,9 +1,10 @@
 use std::{path::{Path, PathBuf}, time::{Duration, SystemTime}};
-use iced::{widget::{column, container, row, text, Column}, Alignment, Command, Length, Padding};
+use iced::{widget::{checkbox, column, container, pick_list, row, text, Column}, Alignment, Command, Length, Padding};
 use log::*;
 use rfd::FileDialog;
+use futuremod_data::{accessibility::ColorblindPreset, version::UpdateChannel};
 
-use crate::{api::{self, is_mod_running}, config::get_config, injector::{get_future_cop_handle, inject_mod}, theme, widget::{button, Element}};
+use crate::{api::{self, is_mod_running}, connection::ConnectionState, config::{get_config, get_profile_names, set_active_profile, set_borderless_window, set_auto_inject, set_colorblind_preset, set_game_path, set_update_channel}, injector::{configure_mod, get_future_cop_handle, inject_mod, is_mod_loaded, launch_game}, theme, update::check_mod_compatibility, widget::{button, Element}};
 
 const MAX_INJECTION_TRIES: u8 = 3;
 const INJECTION_WAIT_TIMEOUT_SECONDS: u64 = 5;
@@ -23,6 +24,11 @@ pub enum Loading {
   /// This variant keeps track of the time when the mod was injected in this injection
   /// attempt and how many attempts were already made.
   WaitingForMod{since: SystemTime, injection_attempts: u8, mod_path: PathBuf},
+  /// State after the mod was ejected from the running game.
+  ///
+  /// The mod unloads its DLL only after it tore itself down. Injecting it before that would only reuse the old DLL,
+  /// therefore, the mod can only be injected again once the game unloaded it.
+  Ejected{mod_path: PathBuf, is_unloaded: bool},
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +36,14 @@ pub enum Message {
   OpenPathSelection,
   CheckIfStarted,
   IsModActive(bool),
+  SelectProfile(String),
+  ToggleBorderless(bool),
+  SelectColorblindPreset(ColorblindPreset),
+  SelectGamePath,
+  LaunchGame,
+  ToggleAutoInject(bool),
+  SelectUpdateChannel(UpdateChannel),
+  ConnectionChanged(ConnectionState),
 }
 
 impl Loading {
@@ -38,8 +52,8 @@ impl Loading {
 
     match mod_path.exists() {
       true => {
-        info!("found mod file, checking if mode is active");
-        (Loading::WaitingForProgram{mod_path}, Command::perform(async {}, |_| Message::CheckIfStarted))
+        info!("found mod file, waiting for the game");
+        (Loading::WaitingForProgram{mod_path}, Command::none())
       }
       false => {
         info!("didn't found mod file, requesting user to select one");
@@ -48,6 +62,21 @@ impl Loading {
     }
   }
 
+  /// Path of the mod that is injected, if one was selected.
+  pub fn mod_path(&self) -> Option<PathBuf> {
+    match self {
+      Loading::NoPath => None,
+      Loading::WaitingForProgram{mod_path}
+      | Loading::InjectionError{mod_path, ..}
+      | Loading::WaitingForMod{mod_path, ..}
+      | Loading::Ejected{mod_path, ..} => Some(mod_path.clone()),
+    }
+  }
+
+  pub fn is_ejected(&self) -> bool {
+    matches!(self, Loading::Ejected{..})
+  }
+
   pub fn view(&self) -> Element<Message> {
     let content: Column<Message, theme::Theme> = match self {
       Loading::WaitingForProgram{mod_path} => {
@@ -58,16 +87,37 @@ impl Loading {
             text(mod_path.to_str().unwrap_or("error parsing mod path"))
           ).padding(Padding::from([0, 0, 8, 0])),
           button("Change Mod")
-            .on_press(Message::OpenPathSelection)
+            .on_press(Message::OpenPathSelection),
+          inject_button(),
+          game_launcher(),
         ].into()
       },
       Loading::WaitingForMod{..} => {
         column![text("Waiting for mod to start...")].into()
       },
+      Loading::Ejected{mod_path, is_unloaded} => {
+        let mut reinject = button("Re-inject");
+        if *is_unloaded {
+          reinject = reinject.on_press(Message::CheckIfStarted);
+        }
+
+        column![
+          text(match is_unloaded {
+            true => "Mod ejected",
+            false => "Ejecting the mod...",
+          })
+            .size(24),
+          container(
+            text(mod_path.to_str().unwrap_or("error parsing mod path"))
+          ).padding(Padding::from([0, 0, 8, 0])),
+          reinject,
+        ].into()
+      },
       Loading::InjectionError{error, ..} => {
         column![
           text(error),
           button("Retry").on_press(Message::CheckIfStarted),
+          game_launcher(),
         ].into()
       }
       Loading::NoPath => {
@@ -81,6 +131,12 @@ impl Loading {
       }
     };
 
+    let content = match self {
+      // The profile can only be switched before the mod is injected
+      Loading::WaitingForMod{..} => content,
+      _ => content.push(profile_picker()).push(auto_inject_toggle()).push(borderless_toggle()).push(colorblind_picker()).push(update_channel_picker()),
+    };
+
     return container(
       row![
         content
@@ -94,6 +150,58 @@ impl Loading {
 }
 
   pub fn update(&mut self, msg: Message) -> Command<Message> {
+    if let Message::SelectProfile(profile) = msg {
+      if let Loading::WaitingForMod{..} = self {
+        return Command::none();
+      }
+
+      if let Err(e) = set_active_profile(&profile) {
+        warn!("Could not switch to profile '{}': {}", profile, e);
+        return Command::none();
+      }
+
+      let (loading, command) = Loading::new();
+      *self = loading;
+
+      return command;
+    }
+
+    if let Message::ToggleBorderless(enabled) = msg {
+      if let Err(e) = set_borderless_window(enabled) {
+        warn!("Could not change borderless windowed mode: {}", e);
+      }
+
+      return Command::none();
+    }
+
+    if let Message::SelectColorblindPreset(preset) = msg {
+      if let Err(e) = set_colorblind_preset(preset) {
+        warn!("Could not change the colorblind preset: {}", e);
+      }
+
+      return Command::none();
+    }
+
+    if let Message::ToggleAutoInject(enabled) = msg {
+      if let Err(e) = set_auto_inject(enabled) {
+        warn!("Could not change automatic injection: {}", e);
+      }
+
+      return Command::none();
+    }
+
+    if let Message::SelectUpdateChannel(channel) = msg {
+      if let Err(e) = set_update_channel(channel) {
+        warn!("Could not change the update channel: {}", e);
+      }
+
+      return Command::none();
+    }
+
+    if let Message::SelectGamePath = msg {
+      return self.pick_game_path();
+    }
+
     match self {
       Loading::WaitingForProgram { mod_path } => match msg {
         Message::CheckIfStarted => {
@@ -103,6 +211,16 @@ impl Loading {
           return self.try_to_inject_mod(mod_path);
         },
         Message::OpenPathSelection => return self.pick_mod_path(),
+        Message::LaunchGame => {
+          let mod_path = mod_path.clone();
+          return self.launch_game(mod_path);
+        },
+        Message::ConnectionChanged(ConnectionState::RunningUninjected) if get_config().auto_inject => {
+          info!("Detected the game, injecting the mod");
+          let mod_path = mod_path.clone();
+
+          return self.try_to_inject_mod(mod_path);
+        },
         _ => (),
       },
       Loading::InjectionError{mod_path, ..} => match msg {
@@ -111,6 +229,10 @@ impl Loading {
           let mod_path = mod_path.clone();
           return self.try_to_inject_mod(mod_path);
         },
+        Message::LaunchGame => {
+          let mod_path = mod_path.clone();
+          return self.launch_game(mod_path);
+        },
         _ => (),
       },
       Loading::WaitingForMod{since, injection_attempts: injection_tries, mod_path} => match msg {
@@ -152,6 +274,21 @@ impl Loading {
         }
         _ => (),
       },
+      Loading::Ejected{mod_path, is_unloaded} => match msg {
+        Message::CheckIfStarted if *is_unloaded => {
+          info!("Injecting the ejected mod again");
+          let mod_path = mod_path.clone();
+          return self.try_to_inject_mod(mod_path);
+        },
+        Message::ConnectionChanged(ConnectionState::NotRunning) => {
+          *self = Loading::WaitingForProgram{mod_path: mod_path.clone()};
+        },
+        Message::ConnectionChanged(_) if !*is_unloaded => match is_mod_loaded(mod_path) {
+          Ok(is_loaded) => *is_unloaded = !is_loaded,
+          Err(e) => warn!("Could not check if the game unloaded the mod: {}", e),
+        },
+        _ => (),
+      },
       Loading::NoPath => match msg {
         Message::OpenPathSelection => return self.pick_mod_path(),
         _ => (),
@@ -175,6 +312,54 @@ impl Loading {
     }
   }
 
+  fn pick_game_path(&mut self) -> Command<Message> {
+    info!("Prompting user to pick the game's executable");
+    match FileDialog::new().add_filter("Executable", &["exe"]).pick_file() {
+      Some(path) => {
+        let path = path.to_string_lossy().to_string();
+
+        if let Err(e) = set_game_path(Some(path)) {
+          warn!("Could not change the game path: {}", e);
+        }
+
+        Command::none()
+      },
+      None => Command::none()
+    }
+  }
+
+  /// Start the game with the mod instead of waiting for the user to start it.
+  fn launch_game(&mut self, mod_path: PathBuf) -> Command<Message> {
+    let config = get_config();
+
+    let game_path = match &config.game_path {
+      Some(game_path) => PathBuf::from(game_path),
+      None => return Command::none(),
+    };
+
+    if let Err(e) = check_mod_compatibility(&mod_path) {
+      warn!("Not launching the game: {}", e);
+      *self = Loading::InjectionError{error: e, mod_path};
+      return Command::none();
+    }
+
+    match launch_game(&game_path, mod_path.to_str().unwrap().to_string(), &config) {
+      Err(e) => {
+        warn!("Could not launch the game: {}", e);
+        *self = Loading::InjectionError{
+          error: format!("Could not launch the game: {}", e),
+          mod_path,
+        };
+
+        Command::none()
+      },
+      Ok(_) => {
+        *self = Loading::WaitingForMod{since: SystemTime::now(), injection_attempts: 0, mod_path};
+        check_if_mod_running()
+      }
+    }
+  }
+
   fn try_to_inject_mod(&mut self, mod_path: PathBuf) -> Command<Message> {
     info!("Trying to inject mod");
     let config = get_config();
@@ -184,6 +369,22 @@ impl Loading {
       Ok(optional_handle) => match optional_handle {
         Some(handle) => {
           info!("Got handle to FutureCop process");
+
+          if let Err(e) = check_mod_compatibility(&mod_path) {
+            warn!("Not injecting the mod: {}", e);
+            *self = Loading::InjectionError{error: e, mod_path};
+            return Command::none();
+          }
+
+          if let Err(e) = configure_mod(handle, &config) {
+            warn!("Could not configure the mod: {}", e);
+            *self = Loading::InjectionError{
+              error: format!("Could not configure the mod for profile '{}': {}", config.profile, e),
+              mod_path,
+            };
+            return Command::none();
+          }
+
           match inject_mod(handle, mod_path.to_str().unwrap().to_string()) {
             Err(e) => {
               warn!("Error while injecting the mod into FutureCop: {}", e);
@@ -209,11 +410,116 @@ impl Loading {
       }
     }
 
-    info!("Injection not successful, trying again in 100ms");
-    return Command::perform(async {tokio::time::sleep(Duration::from_millis(100))}, |_| Message::CheckIfStarted);
+    // The next connection check tries again while the game is running
+    info!("Injection not successful");
+    return Command::none();
   }
 }
 
 fn check_if_mod_running() -> Command<Message> {
   Command::perform(is_mod_running(), Message::IsModActive)
 }
+
+/// Dropdown to switch between profiles.
+fn profile_picker<'a>() -> Element<'a, Message> {
+  let profiles = get_profile_names();
+
+  if profiles.len() <= 1 {
+    return column![].into();
+  }
+
+  container(
+    row![
+      text("Profile"),
+      pick_list(profiles, Some(get_config().profile), Message::SelectProfile),
+    ]
+    .spacing(8)
+    .align_items(Alignment::Center)
+  )
+  .padding(Padding::from([16, 0, 0, 0]))
+  .into()
+}
+
+
+/// Checkbox to inject the mod as soon as the game is detected.
+fn auto_inject_toggle<'a>() -> Element<'a, Message> {
+  container(
+    checkbox("Inject automatically", get_config().auto_inject).on_toggle(Message::ToggleAutoInject)
+  )
+  .padding(Padding::from([16, 0, 0, 0]))
+  .into()
+}
+
+/// Button to manually inject the mod if it isn't injected automatically.
+fn inject_button<'a>() -> Element<'a, Message> {
+  if get_config().auto_inject {
+    return column![].into();
+  }
+
+  container(button("Inject").on_press(Message::CheckIfStarted))
+    .padding(Padding::from([8, 0, 0, 0]))
+    .into()
+}
+
+/// Checkbox to force the game into borderless windowed mode when the mod is injected.
+fn borderless_toggle<'a>() -> Element<'a, Message> {
+  container(
+    checkbox("Borderless windowed mode", get_config().borderless_window).on_toggle(Message::ToggleBorderless)
+  )
+  .padding(Padding::from([16, 0, 0, 0]))
+  .into()
+}
+
+/// Picker of the colorblind preset the mod applies when it is injected.
+fn colorblind_picker<'a>() -> Element<'a, Message> {
+  container(
+    row![
+      text("Colorblind mode"),
+      pick_list(ColorblindPreset::ALL, Some(get_config().colorblind_preset), Message::SelectColorblindPreset),
+    ]
+    .spacing(8)
+    .align_items(Alignment::Center)
+  )
+  .padding(Padding::from([8, 0, 0, 0]))
+  .into()
+}
+
+/// Path to the game's executable and the button to launch the game with the mod.
+fn game_launcher<'a>() -> Element<'a, Message> {
+  let game_path = get_config().game_path;
+
+  let mut launch = button("Launch Game");
+  if game_path.is_some() {
+    launch = launch.on_press(Message::LaunchGame);
+  }
+
+  container(
+    column![
+      row![
+        text(game_path.unwrap_or(String::from("No game selected"))),
+        button("Select Game").on_press(Message::SelectGamePath),
+      ]
+      .spacing(8)
+      .align_items(Alignment::Center),
+      launch,
+    ]
+    .spacing(8)
+    .align_items(Alignment::Center)
+  )
+  .padding(Padding::from([16, 0, 0, 0]))
+  .into()
+}
+
+/// Picker of the channel FutureMod looks for updates in.
+fn update_channel_picker<'a>() -> Element<'a, Message> {
+  container(
+    row![
+      text("Update channel"),
+      pick_list(UpdateChannel::ALL, Some(get_config().update_channel), Message::SelectUpdateChannel),
+    ]
+    .spacing(8)
+    .align_items(Alignment::Center)
+  )
+  .padding(Padding::from([8, 0, 0, 0]))
+  .into()
+}
\ No newline at end of file
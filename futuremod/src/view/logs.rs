@@ -1,11 +1,13 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::{Path, PathBuf}};
 
 use chrono::{DateTime, Utc};
 use futuremod_data::plugin::Plugin;
-use iced::{alignment::{Horizontal, Vertical}, widget::{checkbox, column, container, row, scrollable::{Alignment, Direction, Properties, Scrollable}, text}, Command, Length, Renderer};
+use iced::{alignment::{Horizontal, Vertical}, widget::{checkbox, column, container, row, scrollable::{Alignment, Direction, Properties, Scrollable}, text, text_input}, Command, Length, Renderer};
 use iced_aw::{menu::{Item, Menu}, menu_bar, menu_items, BootstrapIcon};
 
-use crate::{api::get_plugins, theme::{Button, Theme}, widget::bold};
+use rfd::FileDialog;
+
+use crate::{api::{clear_log_history, download_log_files, get_plugins}, theme::{Button, Theme}, widget::bold};
 use crate::{log_subscriber::LogRecord, theme, view::main::LogState, widget::{button, icon, Element}};
 
 use super::main;
@@ -22,6 +24,17 @@ pub enum Message {
     ToggleLevelError(bool),
     GetPluginResponse(Result<HashMap<String, Plugin>, String>),
     ChangeOriginSelection(LogOrigin, bool),
+    DownloadLogFiles,
+    DownloadLogFilesResponse(Result<PathBuf, String>),
+    SearchChanged(String),
+    TargetChanged(String),
+    TogglePause,
+    /// Clear the logs of the GUI and the mod's log history.
+    Clear,
+    ClearResponse(Result<(), String>),
+    ToggleExportFiltered(bool),
+    Export,
+    ExportResponse(Result<PathBuf, String>),
     None,
 }
 
@@ -51,6 +64,77 @@ pub struct LogsState {
   selected_log_levels: SelectedLogLevels,
   selected_origins: HashMap<LogOrigin, bool>,
   plugins: HashMap<String, Plugin>,
+  /// Result of the last log file download.
+  download_result: Option<Result<PathBuf, String>>,
+  /// Only show records that contain the text, ignoring the case.
+  search: String,
+  /// Only show records whose target contains the text, e.g., `plugins::watcher`.
+  target: String,
+  /// Number of records received when the logs were paused. Records received later are hidden until resumed.
+  paused_at: Option<usize>,
+  /// Whether exporting only writes the records that pass the filters.
+  export_filtered: bool,
+  /// Result of the last export.
+  export_result: Option<Result<PathBuf, String>>,
+  error: Option<String>,
+}
+
+/// Format of exported logs, chosen by the file's extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportFormat {
+  /// One line per record like in the log view.
+  Text,
+  /// One JSON object per line with the record's fields.
+  JsonLines,
+}
+
+impl ExportFormat {
+  fn of(path: &Path) -> Self {
+    match path.extension().and_then(|extension| extension.to_str()) {
+      Some("jsonl") | Some("json") => ExportFormat::JsonLines,
+      _ => ExportFormat::Text,
+    }
+  }
+
+  fn format(&self, record: &LogRecord) -> String {
+    match self {
+      ExportFormat::Text => {
+        let origin = match &record.plugin {
+          Some(plugin) => format!("[{}]", plugin),
+          None => record.target.clone(),
+        };
+
+        format!("{} [{}] {} {}", record.timestamp, record.level, origin, record.message)
+      },
+      ExportFormat::JsonLines => serde_json::to_string(record).unwrap_or_default(),
+    }
+  }
+}
+
+impl LogsState {
+  fn is_shown(&self, record: &LogRecord) -> bool {
+    let is_selected_level = match record.level.as_str() {
+      "DEBUG" => self.selected_log_levels.debug,
+      "INFO" => self.selected_log_levels.info,
+      "WARN" => self.selected_log_levels.warn,
+      "ERROR" => self.selected_log_levels.error,
+      _ => false,
+    };
+
+    let origin = match &record.plugin {
+      Some(plugin) => LogOrigin::Plugin(plugin.clone()),
+      None => LogOrigin::System,
+    };
+    let is_selected_origin = *self.selected_origins.get(&origin).unwrap_or(&true);
+
+    let target = self.target.trim();
+    let search = self.search.trim().to_lowercase();
+
+    is_selected_level
+      && is_selected_origin
+      && (target.is_empty() || record.target.contains(target))
+      && (search.is_empty() || record.message.to_lowercase().contains(&search))
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -104,38 +188,16 @@ impl Logs {
           LogState::Disconnected => text("Disconnected").into(),
           LogState::Connecting => text("Connecting").into(),
           LogState::Connected => {
-              let mut filtered: Vec<&LogRecord> = Vec::new();
-
-              for message in &log.logs {
-                let valid = match &message.level.as_str() {
-                  &"DEBUG" => loaded_logs.selected_log_levels.debug,
-                  &"INFO" => loaded_logs.selected_log_levels.info,
-                  &"WARN" => loaded_logs.selected_log_levels.warn,
-                  &"ERROR" => loaded_logs.selected_log_levels.error,
-                  _ => false,
-                };
-
-                if !valid {
-                  continue
-                }
-
-                let is_selected_origin = match &message.plugin {
-                  Some(origin) => {
-                    let origin_key = LogOrigin::Plugin(origin.clone());
-
-                    *loaded_logs.selected_origins.get(&origin_key).unwrap_or(&true)
-                  },
-                  None => {
-                    *loaded_logs.selected_origins.get(&LogOrigin::System).unwrap_or(&true)
-                  }
-                };
-
-                if !is_selected_origin {
-                  continue
-                }
+              // Records received after pausing are kept but not shown
+              let shown = match loaded_logs.paused_at {
+                Some(paused_at) => paused_at.saturating_sub(log.first_index()).min(log.logs.len()),
+                None => log.logs.len(),
+              };
 
-                filtered.push(message)
-              }
+              let filtered: Vec<&LogRecord> = log.logs[..shown]
+                .iter()
+                .filter(|record| loaded_logs.is_shown(record))
+                .collect();
 
               let mut lines: Vec<Element<Message>> = Vec::new();
 
@@ -188,8 +250,12 @@ impl Logs {
       container(
           column![
             header(loaded_logs.unlimited_history, &loaded_logs.selected_log_levels, &loaded_logs.plugins, &loaded_logs.selected_origins),
-            content,
+            filter_bar(loaded_logs),
           ]
+          .push_maybe(download_result(&loaded_logs.download_result))
+          .push_maybe(export_result(&loaded_logs.export_result))
+          .push_maybe(loaded_logs.error.as_ref().map(|e| container(text(e).style(theme::Text::Danger)).padding([0.0, 16.0])))
+          .push(content)
       )
       .into()
     },
@@ -204,7 +270,7 @@ impl Logs {
   }
 }
 
-  pub fn update(&mut self, message: Message) -> Command<Message> {
+  pub fn update(&mut self, message: Message, log: &main::Logs) -> Command<Message> {
     match self {
       Logs::Loading => {
         match message {
@@ -256,6 +322,81 @@ impl Logs {
             logs.selected_origins.insert(origin, value);
             Command::none()
           }
+          Message::DownloadLogFiles => {
+            let destination = match FileDialog::new()
+              .set_title("Save the Log Files")
+              .set_file_name("fcop_mod.log")
+              .add_filter("Log File", &["log"])
+              .save_file() {
+                Some(v) => v,
+                None => return Command::none(),
+            };
+
+            Command::perform(async move {
+              download_log_files(destination.clone()).await?;
+              Ok(destination)
+            }, Message::DownloadLogFilesResponse)
+          },
+          Message::DownloadLogFilesResponse(result) => {
+            logs.download_result = Some(result);
+            Command::none()
+          }
+          Message::TogglePause => {
+            logs.paused_at = match logs.paused_at {
+              Some(_) => None,
+              None => Some(log.received),
+            };
+
+            Command::none()
+          },
+          Message::SearchChanged(search) => {
+            logs.search = search;
+            Command::none()
+          },
+          Message::TargetChanged(target) => {
+            logs.target = target;
+            Command::none()
+          },
+          Message::Clear => {
+            logs.error = None;
+            Command::perform(clear_log_history(), Message::ClearResponse)
+          },
+          Message::ClearResponse(result) => {
+            logs.error = result.err();
+            Command::none()
+          },
+          Message::ToggleExportFiltered(value) => {
+            logs.export_filtered = value;
+            Command::none()
+          },
+          Message::Export => {
+            let destination = match FileDialog::new()
+              .set_title("Export the Logs")
+              .set_file_name("futuremod-logs.txt")
+              .add_filter("Text", &["txt", "log"])
+              .add_filter("JSON Lines", &["jsonl"])
+              .save_file() {
+                Some(v) => v,
+                None => return Command::none(),
+            };
+
+            let format = ExportFormat::of(&destination);
+            let mut content = String::new();
+            for record in log.logs.iter().filter(|record| !logs.export_filtered || logs.is_shown(record)) {
+              content.push_str(&format.format(record));
+              content.push('\n');
+            }
+
+            Command::perform(async move {
+              tokio::fs::write(&destination, content).await
+                .map_err(|e| format!("Could not write '{}': {}", destination.display(), e))?;
+              Ok(destination)
+            }, Message::ExportResponse)
+          },
+          Message::ExportResponse(result) => {
+            logs.export_result = Some(result);
+            Command::none()
+          },
           _ => Command::none(),
         }
       },
@@ -272,10 +413,46 @@ fn header<'a>(unlimited_history: bool, selected_levels: &SelectedLogLevels, plug
         origin_picker(plugins, selected_origins),
         level_picker(&selected_levels),
         checkbox("Unlimited history", unlimited_history).on_toggle(Message::ToggleHistory),
+        button("Download Log Files").on_press(Message::DownloadLogFiles).style(Button::Default),
     ].spacing(16).padding([4.0, 16.0]).align_items(iced::Alignment::Center)
     .into()
 }
 
+fn filter_bar<'a>(logs: &LogsState) -> Element<'a, Message> {
+  let pause = match logs.paused_at {
+    Some(_) => button("Resume").on_press(Message::TogglePause).style(Button::Primary),
+    None => button("Pause").on_press(Message::TogglePause).style(Button::Default),
+  };
+
+  row![
+    text_input("Search", &logs.search).on_input(Message::SearchChanged).width(Length::FillPortion(3)),
+    text_input("Target", &logs.target).on_input(Message::TargetChanged).width(Length::FillPortion(1)),
+    pause,
+    checkbox("Export only shown", logs.export_filtered).on_toggle(Message::ToggleExportFiltered),
+    button("Export").on_press(Message::Export).style(Button::Default),
+    button("Clear").on_press(Message::Clear).style(Button::Destructive),
+  ].spacing(16).padding([4.0, 16.0]).align_items(iced::Alignment::Center)
+  .into()
+}
+
+fn download_result<'a>(result: &Option<Result<PathBuf, String>>) -> Option<Element<'a, Message>> {
+  let message = match result.as_ref()? {
+    Ok(path) => text(format!("Saved the log files to '{}'", path.display())),
+    Err(e) => text(e).style(theme::Text::Danger),
+  };
+
+  Some(container(message).padding([0.0, 16.0]).into())
+}
+
+fn export_result<'a>(result: &Option<Result<PathBuf, String>>) -> Option<Element<'a, Message>> {
+  let message = match result.as_ref()? {
+    Ok(path) => text(format!("Exported the logs to '{}'", path.display())),
+    Err(e) => text(e).style(theme::Text::Danger),
+  };
+
+  Some(container(message).padding([0.0, 16.0]).into())
+}
+
 fn level_picker<'a>(log_levels: &SelectedLogLevels) -> Element<'a, Message> {
   let filter_button = button("Log Level").on_press(Message::None).style(Button::Text);
 
@@ -1,23 +1,120 @@
-use iced::{alignment::{Horizontal, Vertical}, widget::{column, container, text}, Alignment, Command, Length};
+use std::{path::PathBuf, time::Duration};
+
+use iced::{alignment::{Horizontal, Vertical}, event, keyboard::{self, key::Named, Key, Modifiers}, widget::{column, container, text}, window, Alignment, Command, Event, Length, Subscription};
+use chrono::{Local, TimeZone};
 use log::debug;
 
-use crate::{config::get_config, log_subscriber::{self, LogRecord}, theme::{Button, Theme}, widget::{button, Element}};
+use futuremod_data::{crash::CrashReport, plugin::HOT_RELOAD_LOG_TARGET, session::SessionInfo};
+
+use crate::{api::{dismiss_last_crash, eject_mod, get_last_crash, get_session}, config::{get_config, get_profile_names}, log_subscriber::{self, LogRecord}, theme::{self, Button, Theme}, widget::{button, Element}};
 
-use super::{logs, plugins};
+use super::{api_docs, benchmark, bookmarks, browse, compatibility, console, debugger, health, logs, memory, multiplayer, plugins, remapping, screenshots, session, statistics, structs, watches};
 
 #[derive(Debug, Clone)]
 pub enum View {
     Plugins(plugins::Plugins),
     Logs(logs::Logs),
+    ApiDocs(api_docs::ApiDocs),
+    Benchmark(benchmark::Benchmark),
+    Multiplayer(multiplayer::Multiplayer),
+    Remapping(remapping::Remapping),
+    Screenshots(screenshots::Screenshots),
+    Health(health::Health),
+    Watches(watches::Watches),
+    Bookmarks(bookmarks::Bookmarks),
+    Structs(structs::Structs),
+    Debugger(debugger::Debugger),
+    Session(session::Session),
+    Compatibility(compatibility::Compatibility),
+    Statistics(statistics::Statistics),
+    Browse(browse::Browse),
+    Console(console::Console),
+    Memory(memory::Memory),
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ToLogs,
     ToPlugins,
+    ToApiDocs,
+    ToBenchmark,
+    ToMultiplayer,
+    ToRemapping,
+    ToScreenshots,
+    ToHealth,
+    ToWatches,
+    ToBookmarks,
+    ToStructs,
+    ToDebugger,
+    ToSession,
+    ToCompatibility,
+    ToStatistics,
+    ToBrowse,
+    ToConsole,
+    ToMemory,
     Plugins(plugins::Message),
     Logs(logs::Message),
-    LogEvent(log_subscriber::Event)
+    ApiDocs(api_docs::Message),
+    Benchmark(benchmark::Message),
+    Multiplayer(multiplayer::Message),
+    Remapping(remapping::Message),
+    Screenshots(screenshots::Message),
+    Health(health::Message),
+    Watches(watches::Message),
+    Bookmarks(bookmarks::Message),
+    Structs(structs::Message),
+    Debugger(debugger::Message),
+    Session(session::Message),
+    Compatibility(compatibility::Message),
+    Statistics(statistics::Message),
+    Browse(browse::Message),
+    Console(console::Message),
+    Memory(memory::Message),
+    LogEvent(log_subscriber::Event),
+    Shortcut(Shortcut),
+    FileDropped(PathBuf),
+    RefreshSession,
+    SessionResponse(Result<SessionInfo, String>),
+    LastCrashResponse(Result<Option<CrashReport>, String>),
+    DismissCrash,
+    DismissCrashResponse(Result<(), String>),
+    Eject,
+    EjectResponse(Result<(), String>),
+}
+
+/// Interval in which the session statistics are refreshed while the main menu is shown.
+const SESSION_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Global keyboard shortcuts that work in every view.
+#[derive(Debug, Clone, Copy)]
+pub enum Shortcut {
+    /// `Esc`: Go back to the previous view.
+    Back,
+    /// `Ctrl+L`: Show the logs.
+    Logs,
+    /// `Ctrl+P`: Show the plugins.
+    Plugins,
+    /// `F5`: Reload the selected plugin.
+    Reload,
+}
+
+fn shortcut_from_key(key: Key, modifiers: Modifiers) -> Option<Message> {
+    let shortcut = match key.as_ref() {
+        Key::Named(Named::Escape) => Shortcut::Back,
+        Key::Named(Named::F5) => Shortcut::Reload,
+        Key::Character("l") if modifiers.command() => Shortcut::Logs,
+        Key::Character("p") if modifiers.command() => Shortcut::Plugins,
+        _ => return None,
+    };
+
+    Some(Message::Shortcut(shortcut))
+}
+
+fn file_dropped(event: Event, _status: event::Status) -> Option<Message> {
+    match event {
+        Event::Window(_, window::Event::FileDropped(path)) => Some(Message::FileDropped(path)),
+        _ => None,
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -29,24 +126,61 @@ pub enum LogState {
     Error(String),
 }
 
+/// Log records kept by the GUI. Older records are dropped once the buffer is full.
+const MAX_LOG_BUFFER: usize = 10_000;
+
 #[derive(Debug, Clone)]
 pub struct Logs {
     pub state: LogState,
     pub logs: Vec<LogRecord>,
+    /// Number of records received since the GUI started, including dropped records.
+    pub received: usize,
+}
+
+impl Logs {
+    fn push(&mut self, record: LogRecord) {
+        self.logs.push(record);
+        self.received += 1;
+
+        // Drop records in batches, so not every record moves the whole buffer
+        if self.logs.len() > MAX_LOG_BUFFER + MAX_LOG_BUFFER / 10 {
+            let dropped = self.logs.len() - MAX_LOG_BUFFER;
+            self.logs.drain(..dropped);
+        }
+    }
+
+    /// Number of records received before the first record of the buffer.
+    pub fn first_index(&self) -> usize {
+        self.received - self.logs.len()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Main {
     logs: Logs,
     view: Option<View>,
+    session: Option<SessionInfo>,
+    /// Report of the game's last crash, shown in the main menu until it is dismissed.
+    last_crash: Option<CrashReport>,
+    /// Why the mod couldn't be ejected.
+    eject_error: Option<String>,
 }
 
 impl Main {
-    pub fn new() -> Self {
-        Main {
-            logs: Logs { state: LogState::Disconnected, logs: Vec::new() },
-            view: None,
-        }
+    pub fn new() -> (Self, Command<Message>) {
+        (
+            Main {
+                logs: Logs { state: LogState::Disconnected, logs: Vec::new(), received: 0 },
+                view: None,
+                session: None,
+                last_crash: None,
+                eject_error: None,
+            },
+            Command::batch([
+                Command::perform(get_session(), Message::SessionResponse),
+                Command::perform(get_last_crash(), Message::LastCrashResponse),
+            ]),
+        )
     }
 
     pub fn update(&mut self, message: Message) -> iced::Command<Message> {
@@ -64,12 +198,64 @@ impl Main {
                         self.logs.logs.clear();
                     },
                     log_subscriber::Event::Message(message) => {
-                        self.logs.logs.push(message);
+                        let is_hot_reload = message.target == HOT_RELOAD_LOG_TARGET;
+                        let successful = message.level != "ERROR";
+                        self.logs.push(message);
+
+                        // Show the plugin's new state after the mod reloaded it
+                        if let (true, Some(View::Plugins(plugins))) = (is_hot_reload, &mut self.view) {
+                            return plugins.update(plugins::Message::HotReloaded(successful)).map(Message::Plugins);
+                        }
                     },
                 };
 
                 return Command::none();
             }
+            Message::Shortcut(shortcut) => return self.handle_shortcut(shortcut),
+            Message::FileDropped(path) => return self.handle_file_dropped(path),
+            Message::RefreshSession => {
+                // The mod might have been injected since the last refresh
+                return Command::batch([
+                    Command::perform(get_session(), Message::SessionResponse),
+                    Command::perform(get_last_crash(), Message::LastCrashResponse),
+                ]);
+            },
+            Message::SessionResponse(response) => {
+                // Keep showing the last statistics if the mod can't be reached
+                if let Ok(session) = response {
+                    self.session = Some(session);
+                }
+
+                return Command::none();
+            },
+            Message::LastCrashResponse(response) => {
+                if let Ok(last_crash) = response {
+                    self.last_crash = last_crash;
+                }
+
+                return Command::none();
+            },
+            Message::DismissCrash => return Command::perform(dismiss_last_crash(), Message::DismissCrashResponse),
+            Message::DismissCrashResponse(response) => {
+                match response {
+                    Ok(_) => self.last_crash = None,
+                    Err(e) => debug!("Could not dismiss the last crash: {}", e),
+                }
+
+                return Command::none();
+            },
+            Message::Eject => {
+                self.eject_error = None;
+                return Command::perform(eject_mod(), Message::EjectResponse);
+            },
+            // The window switches to the loading screen once the mod was ejected
+            Message::EjectResponse(response) => {
+                if let Err(e) = response {
+                    self.eject_error = Some(e);
+                }
+
+                return Command::none();
+            },
             _ => (),
         }
 
@@ -88,9 +274,141 @@ impl Main {
                         self.view = None;
                         Command::none()
                     },
+                    Message::Logs(logs::Message::ClearResponse(Ok(()))) => {
+                        self.logs.logs.clear();
+                        Command::none()
+                    },
                     Message::Logs(msg) => {
-                        logs.update(msg).map(Message::Logs)
+                        logs.update(msg, &self.logs).map(Message::Logs)
+                    },
+                    _ => Command::none(),
+                },
+                View::ApiDocs(api_docs) => match message {
+                    Message::ApiDocs(api_docs::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::ApiDocs(msg) => api_docs.update(msg).map(Message::ApiDocs),
+                    _ => Command::none(),
+                },
+                View::Benchmark(benchmark) => match message {
+                    Message::Benchmark(benchmark::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Benchmark(msg) => benchmark.update(msg).map(Message::Benchmark),
+                    _ => Command::none(),
+                },
+                View::Multiplayer(multiplayer) => match message {
+                    Message::Multiplayer(multiplayer::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Multiplayer(msg) => multiplayer.update(msg).map(Message::Multiplayer),
+                    _ => Command::none(),
+                },
+                View::Remapping(remapping) => match message {
+                    Message::Remapping(remapping::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Remapping(msg) => remapping.update(msg).map(Message::Remapping),
+                    _ => Command::none(),
+                },
+                View::Screenshots(screenshots) => match message {
+                    Message::Screenshots(screenshots::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Screenshots(msg) => screenshots.update(msg).map(Message::Screenshots),
+                    _ => Command::none(),
+                },
+                View::Health(health) => match message {
+                    Message::Health(health::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Health(msg) => health.update(msg).map(Message::Health),
+                    _ => Command::none(),
+                },
+                View::Console(console) => match message {
+                    Message::Console(console::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Console(msg) => console.update(msg).map(Message::Console),
+                    _ => Command::none(),
+                },
+                View::Memory(memory) => match message {
+                    Message::Memory(memory::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Memory(msg) => memory.update(msg).map(Message::Memory),
+                    _ => Command::none(),
+                },
+                View::Watches(watches) => match message {
+                    Message::Watches(watches::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Watches(msg) => watches.update(msg).map(Message::Watches),
+                    _ => Command::none(),
+                },
+                View::Bookmarks(bookmarks) => match message {
+                    Message::Bookmarks(bookmarks::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Bookmarks(msg) => bookmarks.update(msg).map(Message::Bookmarks),
+                    _ => Command::none(),
+                },
+                View::Structs(structs) => match message {
+                    Message::Structs(structs::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Structs(msg) => structs.update(msg).map(Message::Structs),
+                    _ => Command::none(),
+                },
+                View::Debugger(debugger) => match message {
+                    Message::Debugger(debugger::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Debugger(msg) => debugger.update(msg).map(Message::Debugger),
+                    _ => Command::none(),
+                },
+                View::Session(session) => match message {
+                    Message::Session(session::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Session(msg) => session.update(msg).map(Message::Session),
+                    _ => Command::none(),
+                },
+                View::Compatibility(compatibility) => match message {
+                    Message::Compatibility(compatibility::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Compatibility(msg) => compatibility.update(msg).map(Message::Compatibility),
+                    _ => Command::none(),
+                },
+                View::Statistics(statistics) => match message {
+                    Message::Statistics(statistics::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
+                    },
+                    Message::Statistics(msg) => statistics.update(msg).map(Message::Statistics),
+                    _ => Command::none(),
+                },
+                View::Browse(browse) => match message {
+                    Message::Browse(browse::Message::GoBack) => {
+                        self.view = None;
+                        Command::none()
                     },
+                    Message::Browse(msg) => browse.update(msg).map(Message::Browse),
                     _ => Command::none(),
                 },
             },
@@ -106,11 +424,148 @@ impl Main {
                     self.view = Some(View::Logs(view));
                     message.map(Message::Logs)
                 },
+                Message::ToApiDocs => {
+                    let (view, message) = api_docs::ApiDocs::new();
+                    self.view = Some(View::ApiDocs(view));
+                    message.map(Message::ApiDocs)
+                },
+                Message::ToBenchmark => {
+                    let (view, message) = benchmark::Benchmark::new();
+                    self.view = Some(View::Benchmark(view));
+                    message.map(Message::Benchmark)
+                },
+                Message::ToMultiplayer => {
+                    let (view, message) = multiplayer::Multiplayer::new();
+                    self.view = Some(View::Multiplayer(view));
+                    message.map(Message::Multiplayer)
+                },
+                Message::ToRemapping => {
+                    let (view, message) = remapping::Remapping::new();
+                    self.view = Some(View::Remapping(view));
+                    message.map(Message::Remapping)
+                },
+                Message::ToScreenshots => {
+                    let (view, message) = screenshots::Screenshots::new();
+                    self.view = Some(View::Screenshots(view));
+                    message.map(Message::Screenshots)
+                },
+                Message::ToHealth => {
+                    let (view, message) = health::Health::new();
+                    self.view = Some(View::Health(view));
+                    message.map(Message::Health)
+                },
+                Message::ToConsole => {
+                    let (view, message) = console::Console::new();
+                    self.view = Some(View::Console(view));
+                    message.map(Message::Console)
+                },
+                Message::ToMemory => {
+                    let (view, message) = memory::Memory::new();
+                    self.view = Some(View::Memory(view));
+                    message.map(Message::Memory)
+                },
+                Message::ToWatches => {
+                    let (view, message) = watches::Watches::new();
+                    self.view = Some(View::Watches(view));
+                    message.map(Message::Watches)
+                },
+                Message::ToBookmarks => {
+                    let (view, message) = bookmarks::Bookmarks::new();
+                    self.view = Some(View::Bookmarks(view));
+                    message.map(Message::Bookmarks)
+                },
+                Message::ToStructs => {
+                    let (view, message) = structs::Structs::new();
+                    self.view = Some(View::Structs(view));
+                    message.map(Message::Structs)
+                },
+                Message::ToDebugger => {
+                    let (view, message) = debugger::Debugger::new();
+                    self.view = Some(View::Debugger(view));
+                    message.map(Message::Debugger)
+                },
+                Message::ToSession => {
+                    let (view, message) = session::Session::new();
+                    self.view = Some(View::Session(view));
+                    message.map(Message::Session)
+                },
+                Message::ToCompatibility => {
+                    let (view, message) = compatibility::Compatibility::new();
+                    self.view = Some(View::Compatibility(view));
+                    message.map(Message::Compatibility)
+                },
+                Message::ToStatistics => {
+                    let (view, message) = statistics::Statistics::new();
+                    self.view = Some(View::Statistics(view));
+                    message.map(Message::Statistics)
+                },
+                Message::ToBrowse => {
+                    let (view, message) = browse::Browse::new();
+                    self.view = Some(View::Browse(view));
+                    message.map(Message::Browse)
+                },
                 _ => Command::none()
             },
         }
     }
 
+    fn handle_shortcut(&mut self, shortcut: Shortcut) -> Command<Message> {
+        match shortcut {
+            Shortcut::Back => match &mut self.view {
+                Some(View::Plugins(plugins)) => match plugins.go_back() {
+                    true => Command::none(),
+                    false => {
+                        self.view = None;
+                        Command::none()
+                    },
+                },
+                Some(_) => {
+                    self.view = None;
+                    Command::none()
+                },
+                None => Command::none(),
+            },
+            Shortcut::Logs => {
+                if let Some(View::Logs(_)) = self.view {
+                    return Command::none();
+                }
+
+                let (view, message) = logs::Logs::new();
+                self.view = Some(View::Logs(view));
+                message.map(Message::Logs)
+            },
+            Shortcut::Plugins => {
+                if let Some(View::Plugins(_)) = self.view {
+                    return Command::none();
+                }
+
+                let (view, message) = plugins::Plugins::new();
+                self.view = Some(View::Plugins(view));
+                message.map(Message::Plugins)
+            },
+            Shortcut::Reload => match &mut self.view {
+                Some(View::Plugins(plugins)) => match plugins.selected_plugin() {
+                    Some(name) => plugins.update(plugins::Message::Reload(name)).map(Message::Plugins),
+                    None => Command::none(),
+                },
+                _ => Command::none(),
+            },
+        }
+    }
+
+    /// Install plugin folders dropped onto the plugins view in developer mode.
+    fn handle_file_dropped(&mut self, path: PathBuf) -> Command<Message> {
+        if !get_config().developer_mode || !path.is_dir() {
+            debug!("Ignoring dropped file '{}'", path.display());
+            return Command::none();
+        }
+
+        match &mut self.view {
+            Some(View::Plugins(plugins)) => plugins.update(plugins::Message::InstallFromFolder(path)).map(Message::Plugins),
+            _ => Command::none(),
+        }
+    }
+
     pub fn view(&self) -> Element<'_, Message> {
         fn menu_button(label: &str) -> iced::widget::Button<'_, Message, Theme> {
             button(text(label).horizontal_alignment(Horizontal::Center).width(Length::Fill)).width(Length::Fill).height(36)
@@ -121,10 +576,34 @@ impl Main {
                 container(
                     column![
                         text("FutureCop Mod").size(48),
+                        text(match get_profile_names().len() > 1 {
+                            true => format!("Profile: {}", get_config().profile),
+                            false => String::new(),
+                        }),
+                        session_summary(&self.session),
+                        crash_notice(&self.last_crash),
+                        text(self.eject_error.clone().unwrap_or_default()).style(theme::Text::Danger),
                         column![
                             menu_button("Plugins").on_press(Message::ToPlugins).style(Button::Primary),
-                            menu_button("Logs").on_press(Message::ToLogs)
+                            menu_button("Browse Plugins").on_press(Message::ToBrowse),
+                            menu_button("Logs").on_press(Message::ToLogs),
+                            menu_button("Benchmark").on_press(Message::ToBenchmark),
+                            menu_button("Input Remapping").on_press(Message::ToRemapping),
+                            menu_button("Multiplayer").on_press(Message::ToMultiplayer),
+                            menu_button("Screenshots").on_press(Message::ToScreenshots),
+                            menu_button("Health").on_press(Message::ToHealth),
+                            menu_button("Session Recording").on_press(Message::ToSession),
+                            menu_button("Compatibility").on_press(Message::ToCompatibility),
+                            menu_button("Statistics").on_press(Message::ToStatistics),
+                            menu_button("API Docs").on_press(Message::ToApiDocs)
                         ]
+                        .push_maybe(get_config().developer_mode.then(|| menu_button("Console").on_press(Message::ToConsole)))
+                        .push_maybe(get_config().developer_mode.then(|| menu_button("Watches").on_press(Message::ToWatches)))
+                        .push_maybe(get_config().developer_mode.then(|| menu_button("Bookmarks").on_press(Message::ToBookmarks)))
+                        .push_maybe(get_config().developer_mode.then(|| menu_button("Structs").on_press(Message::ToStructs)))
+                        .push_maybe(get_config().developer_mode.then(|| menu_button("Memory").on_press(Message::ToMemory)))
+                        .push_maybe(get_config().developer_mode.then(|| menu_button("Debugger").on_press(Message::ToDebugger)))
+                        .push_maybe(get_config().developer_mode.then(|| menu_button("Eject Mod").on_press(Message::Eject).style(Button::Destructive)))
                         .spacing(8)
                         .width(Length::Fill)
                         .max_width(200)
@@ -141,6 +620,22 @@ impl Main {
             Some(view) => match view {
                 View::Plugins(plugins) => plugins.view().map(Message::Plugins),
                 View::Logs(logs) => logs.view(&self.logs).map(Message::Logs),
+                View::ApiDocs(api_docs) => api_docs.view().map(Message::ApiDocs),
+                View::Benchmark(benchmark) => benchmark.view().map(Message::Benchmark),
+                View::Multiplayer(multiplayer) => multiplayer.view().map(Message::Multiplayer),
+                View::Remapping(remapping) => remapping.view().map(Message::Remapping),
+                View::Screenshots(screenshots) => screenshots.view().map(Message::Screenshots),
+                View::Health(health) => health.view().map(Message::Health),
+                View::Watches(watches) => watches.view().map(Message::Watches),
+                View::Bookmarks(bookmarks) => bookmarks.view().map(Message::Bookmarks),
+                View::Structs(structs) => structs.view().map(Message::Structs),
+                View::Debugger(debugger) => debugger.view().map(Message::Debugger),
+                View::Session(session) => session.view().map(Message::Session),
+                View::Compatibility(compatibility) => compatibility.view().map(Message::Compatibility),
+                View::Statistics(statistics) => statistics.view().map(Message::Statistics),
+                View::Browse(browse) => browse.view().map(Message::Browse),
+                View::Console(console) => console.view().map(Message::Console),
+                View::Memory(memory) => memory.view().map(Message::Memory),
             }
         }
     }
@@ -148,6 +643,83 @@ impl Main {
     pub fn subscription(&self) -> iced::Subscription<Message> {
         let config = get_config();
         
-        log_subscriber::connect(config.mod_address.clone()).map(Message::LogEvent)
+        let mut subscriptions = vec![
+            log_subscriber::connect(config.mod_address.clone()).map(Message::LogEvent),
+            keyboard::on_key_press(shortcut_from_key),
+            event::listen_with(file_dropped),
+        ];
+
+        match &self.view {
+            None => subscriptions.push(iced::time::every(SESSION_REFRESH_INTERVAL).map(|_| Message::RefreshSession)),
+            Some(View::Console(console)) => subscriptions.push(console.subscription().map(Message::Console)),
+            Some(View::Watches(watches)) => subscriptions.push(watches.subscription().map(Message::Watches)),
+            Some(View::Structs(structs)) => subscriptions.push(structs.subscription().map(Message::Structs)),
+            Some(View::Memory(memory)) => subscriptions.push(memory.subscription().map(Message::Memory)),
+            Some(View::Debugger(debugger)) => subscriptions.push(debugger.subscription().map(Message::Debugger)),
+            _ => (),
+        }
+
+        Subscription::batch(subscriptions)
+    }
+}
+
+/// Short summary of the current game session for the main menu.
+fn session_summary<'a>(session: &Option<SessionInfo>) -> Element<'a, Message> {
+    let session = match session {
+        Some(session) => session,
+        None => return text("").into(),
+    };
+
+    let uptime = format!("{}h {}m", session.uptime / 3600, (session.uptime % 3600) / 60);
+    let errors: u32 = session.plugin_errors.values().sum();
+
+    let mut summary = column![
+        text(format!("Uptime: {}  |  Missions: {}  |  Frames: {}", uptime, session.missions, session.frames)).size(12),
+    ]
+    .spacing(4)
+    .align_items(Alignment::Center);
+
+    if errors > 0 {
+        let mut plugins: Vec<(&String, &u32)> = session.plugin_errors.iter().collect();
+        plugins.sort_by(|a, b| b.1.cmp(a.1));
+
+        let plugins: Vec<String> = plugins.iter().map(|(name, count)| format!("{} ({})", name, count)).collect();
+
+        summary = summary.push(
+            text(format!("Plugin errors: {}", plugins.join(", "))).size(12).style(theme::Text::Warn)
+        );
     }
-}
\ No newline at end of file
+
+    summary.into()
+}
+
+/// Notice about the game's last crash and the plugin that likely caused it.
+fn crash_notice<'a>(last_crash: &Option<CrashReport>) -> Element<'a, Message> {
+    let report = match last_crash {
+        Some(report) => report,
+        None => return text("").into(),
+    };
+
+    let time = match Local.timestamp_opt(report.crashed_at as i64, 0).single() {
+        Some(time) => time.format("%Y-%m-%d %H:%M").to_string(),
+        None => String::from("an unknown time"),
+    };
+
+    let summary = match (&report.plugin, report.hook) {
+        (Some(plugin), Some(hook)) => format!("The game crashed on {}, likely in the hook of plugin {} on {:#010x}", time, plugin, hook),
+        (Some(plugin), None) => format!("The game crashed on {}, likely in plugin {}", time, plugin),
+        (None, _) => format!("The game crashed on {}", time),
+    };
+
+    container(
+        column![
+            text(summary).style(theme::Text::Danger),
+            text(&report.cause).size(12),
+            button("Dismiss").on_press(Message::DismissCrash).style(Button::Text),
+        ]
+        .spacing(4)
+        .align_items(Alignment::Center)
+    )
+    .max_width(480)
+    .into()
+}
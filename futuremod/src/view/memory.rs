@@ -0,0 +1,378 @@
+use std::time::Duration;
+
+use futuremod_data::memory::{MemoryAddress, MemoryValue, ScanComparison, ScanState, SCAN_TYPES};
+use iced::{widget::{column, container, pick_list, row, text, text_input, Scrollable}, Alignment, Command, Length, Subscription};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::{get_memory_scan, next_memory_scan, read_memory_values, reset_memory_scan, start_memory_scan}, theme::{self, Button, Container, Text}, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+/// Interval in which the values of the matches and the watched addresses are read again.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  TypeSelected(String),
+  ValueChanged(String),
+  ComparisonSelected(ScanComparison),
+  FirstScan,
+  NextScan,
+  Reset,
+  ResetResponse(Result<(), String>),
+  ScanResponse(Result<ScanState, String>),
+  Refresh,
+  RefreshResponse(Result<ScanState, String>),
+  Watch(u32),
+  Unwatch(usize),
+  WatchedResponse(Result<Vec<MemoryValue>, String>),
+  Copy(MemoryAddress),
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Memory {
+  value_type: String,
+  value: String,
+  comparison: ScanComparison,
+  state: ScanState,
+  /// Addresses whose values are shown live, with their latest values.
+  watched: Vec<(MemoryAddress, Option<String>)>,
+  /// Whether a scan is running, scans of the whole memory take a few seconds.
+  is_scanning: bool,
+  /// Whether a refresh is pending, to not queue requests if the mod responds slowly.
+  is_refreshing: bool,
+  error: Option<String>,
+}
+
+impl Memory {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Memory {
+        value_type: String::from("int"),
+        value: String::new(),
+        comparison: ScanComparison::Exact,
+        state: ScanState::default(),
+        watched: Vec::new(),
+        is_scanning: false,
+        is_refreshing: false,
+        error: None,
+      },
+      Command::perform(get_memory_scan(), Message::ScanResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::TypeSelected(value_type) => {
+        self.value_type = value_type;
+        Command::none()
+      },
+      Message::ValueChanged(value) => {
+        self.value = value;
+        Command::none()
+      },
+      Message::ComparisonSelected(comparison) => {
+        self.comparison = comparison;
+        Command::none()
+      },
+      Message::FirstScan => {
+        if self.is_scanning || self.value.trim().is_empty() {
+          return Command::none();
+        }
+
+        self.is_scanning = true;
+        Command::perform(start_memory_scan(self.value_type.clone(), self.value.trim().to_string()), Message::ScanResponse)
+      },
+      Message::NextScan => {
+        if self.is_scanning || self.state.scans == 0 {
+          return Command::none();
+        }
+
+        let value = match self.comparison {
+          ScanComparison::Exact if self.value.trim().is_empty() => {
+            self.error = Some(String::from("Enter the value to scan for"));
+            return Command::none();
+          },
+          ScanComparison::Exact => Some(self.value.trim().to_string()),
+          _ => None,
+        };
+
+        self.is_scanning = true;
+        Command::perform(next_memory_scan(self.comparison, value), Message::ScanResponse)
+      },
+      Message::Reset => Command::perform(reset_memory_scan(), Message::ResetResponse),
+      Message::ResetResponse(response) => {
+        match response {
+          Ok(_) => {
+            self.state = ScanState::default();
+            self.comparison = ScanComparison::Exact;
+          },
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::ScanResponse(response) => {
+        self.is_scanning = false;
+
+        match response {
+          Ok(state) => {
+            // Scans of the same type are kept when the GUI is opened again
+            if let Some(value_type) = &state.value_type {
+              self.value_type = value_type.clone();
+            }
+
+            self.state = state;
+          },
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::Refresh => {
+        if self.is_refreshing || self.is_scanning {
+          return Command::none();
+        }
+
+        let addresses: Vec<MemoryAddress> = self.watched.iter().map(|(address, _)| address.clone()).collect();
+
+        let mut commands = Vec::new();
+        if self.state.scans > 0 {
+          commands.push(Command::perform(get_memory_scan(), Message::RefreshResponse));
+        }
+        if !addresses.is_empty() {
+          commands.push(Command::perform(read_memory_values(addresses), Message::WatchedResponse));
+        }
+
+        self.is_refreshing = !commands.is_empty();
+        Command::batch(commands)
+      },
+      Message::RefreshResponse(response) => {
+        self.is_refreshing = false;
+
+        match response {
+          // A scan may have been started while refreshing, its response replaces the state
+          Ok(state) if !self.is_scanning => self.state = state,
+          Ok(_) => (),
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::Watch(address) => {
+        let value_type = match &self.state.value_type {
+          Some(value_type) => value_type.clone(),
+          None => return Command::none(),
+        };
+
+        if self.watched.iter().any(|(watched, _)| watched.address == address && watched.value_type == value_type) {
+          return Command::none();
+        }
+
+        let value = self.state.matches.iter().find(|value| value.address == address).and_then(|value| value.value.clone());
+        self.watched.push((MemoryAddress { address, value_type }, value));
+
+        Command::none()
+      },
+      Message::Unwatch(index) => {
+        if index < self.watched.len() {
+          self.watched.remove(index);
+        }
+
+        Command::none()
+      },
+      Message::WatchedResponse(response) => {
+        self.is_refreshing = false;
+
+        match response {
+          Ok(values) => {
+            for (watched, value) in self.watched.iter_mut() {
+              if let Some(current) = values.iter().find(|current| current.address == watched.address) {
+                *value = current.value.clone();
+              }
+            }
+          },
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::Copy(address) => iced::clipboard::write(plugin_code(&address)),
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  /// Read the values of the matches and the watched addresses periodically.
+  pub fn subscription(&self) -> Subscription<Message> {
+    match self.state.scans > 0 || !self.watched.is_empty() {
+      true => iced::time::every(REFRESH_INTERVAL).map(|_| Message::Refresh),
+      false => Subscription::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header());
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let types: Vec<String> = SCAN_TYPES.iter().map(|value_type| value_type.to_string()).collect();
+    let has_scan = self.state.scans > 0;
+
+    let mut first_scan = button("New Scan").style(Button::Primary);
+    if !self.is_scanning && !self.value.trim().is_empty() {
+      first_scan = first_scan.on_press(Message::FirstScan);
+    }
+
+    let mut next_scan = button("Next Scan").style(Button::Secondary);
+    if !self.is_scanning && has_scan {
+      next_scan = next_scan.on_press(Message::NextScan);
+    }
+
+    let mut reset = button("Reset").style(Button::Secondary);
+    if !self.is_scanning && has_scan {
+      reset = reset.on_press(Message::Reset);
+    }
+
+    // The type can't change between the scans of a scan
+    let type_picker: Element<Message> = match has_scan {
+      true => text(&self.value_type).into(),
+      false => pick_list(types, Some(self.value_type.clone()), Message::TypeSelected).into(),
+    };
+
+    let status = match (self.is_scanning, has_scan) {
+      (true, _) => String::from("Scanning..."),
+      (false, true) => format!("{} matches after {} scans", self.state.match_count, self.state.scans),
+      (false, false) => String::from("Start a scan to find the addresses of a value"),
+    };
+
+    let mut matches = Column::new()
+      .push(
+        row![
+          text("Address").font(bold()).width(Length::FillPortion(1)),
+          text("Value").font(bold()).width(Length::FillPortion(1)),
+          text("").width(80),
+        ].spacing(8)
+      );
+
+    for value in self.state.matches.iter() {
+      let address = MemoryAddress { address: value.address, value_type: self.value_type.clone() };
+
+      matches = matches.push(
+        row![
+          text(format!("{:#010x}", value.address)).width(Length::FillPortion(1)),
+          container(value_text(&value.value)).width(Length::FillPortion(1)),
+          row![
+            button(icon(BootstrapIcon::Eye)).on_press(Message::Watch(value.address)).style(Button::Text),
+            button(icon(BootstrapIcon::Clipboard)).on_press(Message::Copy(address)).style(Button::Text),
+          ].width(80),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+      );
+    }
+
+    if (self.state.match_count as usize) > self.state.matches.len() {
+      matches = matches.push(text(format!("{} more matches, scan again to narrow them down", self.state.match_count as usize - self.state.matches.len())).style(theme::Text::Warn));
+    }
+
+    let mut watched = Column::new()
+      .push(
+        row![
+          text("Address").font(bold()).width(Length::FillPortion(1)),
+          text("Type").font(bold()).width(Length::FillPortion(1)),
+          text("Value").font(bold()).width(Length::FillPortion(1)),
+          text("").width(80),
+        ].spacing(8)
+      );
+
+    for (index, (address, value)) in self.watched.iter().enumerate() {
+      watched = watched.push(
+        row![
+          text(format!("{:#010x}", address.address)).width(Length::FillPortion(1)),
+          text(&address.value_type).width(Length::FillPortion(1)),
+          container(value_text(value)).width(Length::FillPortion(1)),
+          row![
+            button(icon(BootstrapIcon::Clipboard)).on_press(Message::Copy(address.clone())).style(Button::Text),
+            button(icon_with_style(BootstrapIcon::Trash, Text::Danger)).on_press(Message::Unwatch(index)).style(Button::Text),
+          ].width(80),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+      );
+    }
+
+    let comparisons: Vec<ScanComparison> = ScanComparison::ALL.to_vec();
+
+    let body = column![
+      text("Find the address of a value in the game's memory. Scan for the value, change it in the game, e.g., by taking damage, and scan again for the new value or how it changed until only a few addresses are left. Copy an address to read it with the dangerous library."),
+      row![
+        column![text("Type").font(bold()), type_picker].spacing(4).width(Length::FillPortion(1)),
+        column![text("Value").font(bold()), text_input("100", &self.value).on_input(Message::ValueChanged).on_submit(match has_scan {
+          true => Message::NextScan,
+          false => Message::FirstScan,
+        })].spacing(4).width(Length::FillPortion(2)),
+        column![text("Next Scan").font(bold()), pick_list(comparisons, Some(self.comparison), Message::ComparisonSelected)].spacing(4).width(Length::FillPortion(1)),
+      ].spacing(8),
+      row![
+        text(status).font(bold()).width(Length::Fill),
+        first_scan,
+        next_scan,
+        reset,
+      ].spacing(8).align_items(Alignment::Center),
+      Scrollable::new(matches.spacing(8).width(Length::Fill)).height(Length::FillPortion(2)),
+      text("Watched Addresses").size(20),
+      Scrollable::new(watched.spacing(8).width(Length::Fill)).height(Length::FillPortion(1)),
+    ]
+    .spacing(16);
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn value_text<'a>(value: &Option<String>) -> Element<'a, Message> {
+  match value {
+    Some(value) => text(value).into(),
+    None => text("Not readable").style(theme::Text::Warn).into(),
+  }
+}
+
+/// Code that reads the value at the address in a plugin.
+fn plugin_code(address: &MemoryAddress) -> String {
+  format!("dangerous.readMemory({:#010x}, \"{}\")", address.address, address.value_type)
+}
+
+fn header<'a>() -> Element<'a, Message> {
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Memory").size(24)).width(Length::Fill),
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
@@ -1,4 +1,20 @@
 pub mod loading;
 pub mod main;
 pub mod plugins;
-pub mod logs;
\ No newline at end of file
+pub mod logs;
+pub mod api_docs;
+pub mod benchmark;
+pub mod multiplayer;
+pub mod remapping;
+pub mod screenshots;
+pub mod health;
+pub mod watches;
+pub mod bookmarks;
+pub mod structs;
+pub mod debugger;
+pub mod session;
+pub mod compatibility;
+pub mod statistics;
+pub mod browse;
+pub mod console;
+pub mod memory;
\ No newline at end of file
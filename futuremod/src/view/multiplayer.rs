@@ -0,0 +1,187 @@
+use futuremod_data::multiplayer::{default_multiplayer_port, MultiplayerStatus};
+use iced::{widget::{column, container, row, text, text_input}, Alignment, Command, Length};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::{connect_multiplayer, disconnect_multiplayer, get_multiplayer_status}, theme::{self, Button, Container, Text}, util::wait_for_ms, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+/// Interval in which the connection's state is polled while the mod syncs with a peer.
+const POLL_INTERVAL: u64 = 1000;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  PortChanged(String),
+  PeerChanged(String),
+  Connect,
+  Disconnect,
+  Poll,
+  StatusResponse(Result<MultiplayerStatus, String>),
+  ActionResponse(Result<(), String>),
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Multiplayer {
+  status: Option<MultiplayerStatus>,
+  port: String,
+  peer: String,
+  error: Option<String>,
+}
+
+impl Multiplayer {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Multiplayer {
+        status: None,
+        port: default_multiplayer_port().to_string(),
+        peer: String::new(),
+        error: None,
+      },
+      Command::perform(get_multiplayer_status(), Message::StatusResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::PortChanged(port) => {
+        self.port = port;
+        Command::none()
+      },
+      Message::PeerChanged(peer) => {
+        self.peer = peer;
+        Command::none()
+      },
+      Message::Connect => match self.port.trim().parse::<u16>() {
+        Ok(port) => Command::perform(connect_multiplayer(port, self.peer.trim().to_string()), Message::ActionResponse),
+        Err(_) => {
+          self.error = Some(format!("'{}' is not a valid port", self.port));
+          Command::none()
+        },
+      },
+      Message::Disconnect => Command::perform(disconnect_multiplayer(), Message::ActionResponse),
+      Message::Poll => Command::perform(get_multiplayer_status(), Message::StatusResponse),
+      Message::ActionResponse(response) => {
+        if let Err(e) = response {
+          self.error = Some(e);
+        }
+
+        Command::perform(get_multiplayer_status(), Message::StatusResponse)
+      },
+      Message::StatusResponse(response) => match response {
+        Ok(status) => {
+          if let (Some(port), Some(peer)) = (status.port, &status.peer) {
+            self.port = port.to_string();
+            self.peer = peer.clone();
+          }
+
+          let is_active = status.peer.is_some();
+          self.status = Some(status);
+
+          match is_active {
+            true => Command::perform(wait_for_ms(POLL_INTERVAL), |_| Message::Poll),
+            false => Command::none(),
+          }
+        },
+        Err(e) => {
+          self.error = Some(e);
+          Command::none()
+        },
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header());
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let is_active = self.status.as_ref().is_some_and(|status| status.peer.is_some());
+
+    let mut port_input = text_input("17000", &self.port);
+    let mut peer_input = text_input("192.168.0.2:17000", &self.peer);
+    if !is_active {
+      port_input = port_input.on_input(Message::PortChanged);
+      peer_input = peer_input.on_input(Message::PeerChanged).on_submit(Message::Connect);
+    }
+
+    let action = match (&self.status, is_active) {
+      (None, _) => button("Connect").style(Button::Primary),
+      (Some(_), true) => button("Disconnect").on_press(Message::Disconnect).style(Button::Destructive),
+      (Some(_), false) => match self.peer.trim().is_empty() {
+        true => button("Connect").style(Button::Primary),
+        false => button("Connect").on_press(Message::Connect).style(Button::Primary),
+      },
+    };
+
+    let body = column![
+      text("Sync player positions with the FutureMod of another player. Both players enter the other's address and connect. The port must be reachable by the other player."),
+      text("This is a prototype. It only provides the synced state to plugins and doesn't change the game on its own.").style(theme::Text::Warn),
+      row![
+        column![text("Local Port").font(bold()), port_input].spacing(4).width(Length::FillPortion(1)),
+        column![text("Peer Address").font(bold()), peer_input].spacing(4).width(Length::FillPortion(3)),
+      ].spacing(16),
+      action,
+      status_section(&self.status),
+    ]
+    .spacing(16);
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn header<'a>() -> Element<'a, Message> {
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Multiplayer").size(24)).width(Length::Fill),
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
+
+fn status_section<'a>(status: &Option<MultiplayerStatus>) -> Element<'a, Message> {
+  let status = match status {
+    Some(status) => status,
+    None => return text("Loading...").into(),
+  };
+
+  let state = match (&status.peer, status.connected) {
+    (None, _) => text("Not connected"),
+    (Some(peer), true) => text(format!("Connected to {}", peer)),
+    (Some(peer), false) => text(format!("Waiting for {}...", peer)).style(theme::Text::Warn),
+  };
+
+  column![
+    state,
+    text(format!("Packets sent: {}  |  Packets received: {}", status.sent, status.received)).size(12),
+  ]
+  .spacing(4)
+  .into()
+}
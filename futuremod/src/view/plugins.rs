@@ -1,26 +1,63 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::{BTreeMap, HashMap}, path::PathBuf, sync::Mutex};
 
-use iced::{alignment::Vertical, futures::TryFutureExt, widget::{column, container, row, rule, scrollable, text, Scrollable, Space, Toggler}, Alignment, Command, Length, Padding};
+use chrono::Local;
+use iced::{alignment::Vertical, futures::TryFutureExt, widget::{column, container, pick_list, row, rule, scrollable, text, text_input, Scrollable, Space, Toggler}, Alignment, Command, Length, Padding};
 use iced_aw::{modal, BootstrapIcon};
 use log::{info, warn};
 use rfd::FileDialog;
-use futuremod_data::plugin::*;
+use futuremod_data::{audit::{AuditReport, PluginAudit}, input::{KeyBinding, SetKeyBinding}, plugin::*, settings::{PluginSettings, SettingKind, SettingValue}};
 
-use crate::{api::{build_url, get_plugin_info, get_plugins, install_plugin, reload_plugin, uninstall_plugin}, theme::{self, Container, Text, Theme}, util::wait_for_ms, widget::{button, icon, icon_with_style, Column, Element, Row}};
+use crate::{api::{build_url, dismiss_suspect, download_backup, force_reload_plugin, get_audit_report, get_key_bindings, get_plugin_config, get_plugin_info, get_plugins, get_remappable_keys, install_dev_plugin, install_plugin, invoke_lifecycle, promote_plugin, reload_plugin, restore_backup, set_command_approval, set_key_binding, set_network_approval, set_plugin_config, trust_plugin, uninstall_plugin, update_plugin}, config::{get_config, set_plugin_note, PluginNote, MAX_RATING}, theme::{self, Container, Text, Theme}, util::{open_path, wait_for_ms}, widget::{bold, button, icon, icon_with_style, Column, Element, Row}};
 use crate::theme::Button;
 
+/// Plugins of the last successful request.
+///
+/// Shown while the mod cannot be reached, so users still see what they have installed.
+static LAST_KNOWN_PLUGINS: Mutex<Option<HashMap<String, Plugin>>> = Mutex::new(None);
+
+fn remember_plugins(plugins: &HashMap<String, Plugin>) {
+  if let Ok(mut last_known_plugins) = LAST_KNOWN_PLUGINS.lock() {
+    *last_known_plugins = Some(plugins.clone());
+  }
+}
+
+fn last_known_plugins() -> Option<HashMap<String, Plugin>> {
+  LAST_KNOWN_PLUGINS.lock().ok().and_then(|plugins| plugins.clone())
+}
+
 #[derive(Debug, Clone)]
 pub struct PluginsView {
   plugins: HashMap<String, Plugin>,
   selected_plugin: Option<String>,
   error: Option<String>,
   confirm_installation: Option<InstallConfirmationPrompt>,
+  /// Folder to install in developer mode, waiting for the user's confirmation.
+  confirm_dev_installation: Option<PathBuf>,
   show_reload_success_message: bool,
+  /// Result of the last manually called lifecycle function.
+  lifecycle_result: Option<Result<String, String>>,
+  /// Report of the mod's sandbox audit mode.
+  audit_report: Option<AuditReport>,
+  /// Key bindings of the selected plugin.
+  key_bindings: Option<Vec<KeyBinding>>,
+  /// Keys that can be chosen for key bindings.
+  keys: Vec<String>,
+  /// Settings of the selected plugin.
+  settings: Option<PluginSettings>,
+  /// Text of number settings the user is editing, by the setting's name.
+  number_inputs: HashMap<String, String>,
+  /// Backup to restore, waiting for the user's confirmation.
+  confirm_restore: Option<PathBuf>,
+  /// Result of the last backup or restore.
+  backup_message: Option<String>,
+  /// Notes of the selected plugin the user is editing.
+  notes_input: String,
 }
 
 #[derive(Debug, Clone)]
 pub enum Plugins {
-  Error(String),
+  /// The mod couldn't be reached.
+  Disconnected{error: String, last_known_plugins: Option<HashMap<String, Plugin>>},
   Loading,
   Loaded(PluginsView)
 }
@@ -29,6 +66,8 @@ pub enum Plugins {
 pub struct InstallConfirmationPrompt {
   pub plugin: PluginInfo,
   pub path: PathBuf,
+  /// Version of the plugin if it is already installed, in which case the installed plugin is updated.
+  pub installed_version: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +91,50 @@ pub enum Message {
   UninstallPlugin(String),
   UninstallPluginResponse(Result<String, String>),
   HideReloadSuccessfulMessage,
+  Retry,
+  InvokeLifecycle(String, LifecycleEvent),
+  InvokeLifecycleResponse(Result<String, String>),
+  OpenPath(PathBuf),
+  CopyError(String),
+  SelectFolderToInstall,
+  InstallFromFolder(PathBuf),
+  ConfirmDevInstallation(PathBuf),
+  Promote(String),
+  AuditReportResponse(Result<AuditReport, String>),
+  PromoteResponse(Result<HashMap<String, Plugin>, String>),
+  Trust(String),
+  TrustResponse(Result<HashMap<String, Plugin>, String>),
+  DismissSuspect(String),
+  DismissSuspectResponse(Result<HashMap<String, Plugin>, String>),
+  ForceReload(String),
+  KeyBindingsResponse(Result<Vec<KeyBinding>, String>),
+  KeysResponse(Result<Vec<String>, String>),
+  SetKeyBinding(String, SetKeyBinding),
+  SetKeyBindingResponse(Result<Vec<KeyBinding>, String>),
+  SettingsResponse(Result<PluginSettings, String>),
+  /// Change the plugin's setting to the value.
+  SetSetting(String, String, SettingValue),
+  SetSettingResponse(Result<PluginSettings, String>),
+  NumberInputChanged(String, String),
+  /// Change the plugin's number setting to the number the user entered.
+  SubmitNumber(String, String),
+  /// The mod reloaded a plugin because its files changed, and whether the reload succeeded.
+  HotReloaded(bool),
+  CreateBackup,
+  BackupResponse(Result<PathBuf, String>),
+  SelectBackupToRestore,
+  ConfirmRestore(PathBuf),
+  RestoreResponse(Result<HashMap<String, Plugin>, String>),
+  ClearBackupMessage,
+  /// Approve or revoke the plugin's requests to its hosts.
+  SetNetworkApproval(String, bool),
+  SetCommandApproval(String, bool),
+  ApprovalResponse(Result<HashMap<String, Plugin>, String>),
+  /// Rate the plugin, or remove its rating.
+  SetRating(String, Option<u8>),
+  NotesChanged(String),
+  /// Save the notes the user entered for the plugin.
+  SaveNotes(String),
 }
 
 
@@ -68,31 +151,51 @@ impl Plugins {
         Plugins::Loading => match message {
           Message::GetPluginsResult(result) => match result {
               Ok(result) => {
+                remember_plugins(&result);
                 *self = Plugins::Loaded(PluginsView{
                   plugins: result,
                   selected_plugin: None, 
                   error: None, 
                   confirm_installation: None, 
-                  show_reload_success_message: false
+                  confirm_dev_installation: None,
+                  show_reload_success_message: false,
+                  lifecycle_result: None,
+                  audit_report: None,
+                  key_bindings: None,
+                  keys: Vec::new(),
+                  settings: None,
+                  number_inputs: HashMap::new(),
+                  confirm_restore: None,
+                  backup_message: None,
+                  notes_input: String::new(),
                 });
                 Command::none()
               },
               Err(e) => {
-                *self = Plugins::Error(e);
+                *self = Plugins::disconnected(e);
                 Command::none()
               },
           },
           _ => Command::none(),
         }
-        Plugins::Error(_) => todo!(),
+        Plugins::Disconnected{..} => match message {
+          Message::Retry => {
+            info!("Retrying to get plugins");
+            *self = Plugins::Loading;
+
+            Command::perform(get_plugins(), Message::GetPluginsResult)
+          },
+          _ => Command::none(),
+        },
         Plugins::Loaded(plugins_view) => match message {
           Message::GetPluginsResult(result) => match result {
               Ok(result) => {
+                remember_plugins(&result);
                 plugins_view.plugins = result;
                 Command::none()
               },
               Err(e) => {
-                *self = Plugins::Error(e);
+                *self = Plugins::disconnected(e);
                 Command::none()
               },
           },
@@ -116,6 +219,8 @@ impl Plugins {
               match plugins_view.plugins.get_mut(&name) {
                 Some(plugin) => {
                   plugin.enabled = true;
+                  // Enabling a suspect clears the suspicion
+                  plugin.suspect = false;
 
                   Command::none()
                 },
@@ -125,19 +230,162 @@ impl Plugins {
             None => Command::none(),
           },
           Message::GoToDetails(name) => {
-            plugins_view.selected_plugin = Some(name);
+            plugins_view.selected_plugin = Some(name.clone());
+            plugins_view.lifecycle_result = None;
+            plugins_view.key_bindings = None;
+            plugins_view.settings = None;
+            plugins_view.number_inputs.clear();
+            plugins_view.notes_input = get_config().plugin_notes.get(&name).map(|note| note.notes.clone()).unwrap_or_default();
+            Command::batch([
+              Command::perform(get_audit_report(), Message::AuditReportResponse),
+              Command::perform(get_plugin_config(name.clone()), Message::SettingsResponse),
+              Command::perform(get_key_bindings(name), Message::KeyBindingsResponse),
+              Command::perform(get_remappable_keys(), Message::KeysResponse),
+            ])
+          },
+          Message::KeyBindingsResponse(response) => {
+            match response {
+              Ok(bindings) => plugins_view.key_bindings = Some(bindings),
+              Err(e) => warn!("Could not get the key bindings: {}", e),
+            }
+
+            Command::none()
+          },
+          Message::KeysResponse(response) => {
+            match response {
+              Ok(keys) => plugins_view.keys = keys,
+              Err(e) => warn!("Could not get the keys: {}", e),
+            }
+
+            Command::none()
+          },
+          Message::SetKeyBinding(name, binding) => Command::perform(set_key_binding(name, binding), Message::SetKeyBindingResponse),
+          Message::SetKeyBindingResponse(response) => {
+            match response {
+              Ok(bindings) => plugins_view.key_bindings = Some(bindings),
+              Err(e) => plugins_view.error = Some(e),
+            }
+
+            Command::none()
+          },
+          Message::SettingsResponse(response) => {
+            match response {
+              Ok(settings) => {
+                plugins_view.settings = Some(settings);
+                plugins_view.number_inputs.clear();
+              },
+              Err(e) => warn!("Could not get the settings: {}", e),
+            }
+
+            Command::none()
+          },
+          Message::SetSetting(name, setting, value) => Command::perform(set_plugin_config(name, BTreeMap::from([(setting, value)])), Message::SetSettingResponse),
+          Message::SetSettingResponse(response) => {
+            match response {
+              Ok(settings) => {
+                plugins_view.settings = Some(settings);
+                plugins_view.number_inputs.clear();
+              },
+              Err(e) => plugins_view.error = Some(e),
+            }
+
+            Command::none()
+          },
+          Message::SetRating(name, rating) => {
+            let note = PluginNote { rating, ..get_config().plugin_notes.get(&name).cloned().unwrap_or_default() };
+
+            if let Err(e) = set_plugin_note(&name, note) {
+              plugins_view.error = Some(format!("Could not save the rating: {}", e));
+            }
+
+            Command::none()
+          },
+          Message::NotesChanged(notes) => {
+            plugins_view.notes_input = notes;
+            Command::none()
+          },
+          Message::SaveNotes(name) => {
+            let note = PluginNote { notes: plugins_view.notes_input.trim().to_string(), ..get_config().plugin_notes.get(&name).cloned().unwrap_or_default() };
+
+            if let Err(e) = set_plugin_note(&name, note) {
+              plugins_view.error = Some(format!("Could not save the notes: {}", e));
+            }
+
+            Command::none()
+          },
+          Message::NumberInputChanged(setting, input) => {
+            plugins_view.number_inputs.insert(setting, input);
+            Command::none()
+          },
+          Message::SubmitNumber(name, setting) => {
+            let input = plugins_view.number_inputs.get(&setting).cloned().unwrap_or_default();
+
+            match input.trim().parse::<f64>() {
+              Ok(number) => Command::perform(
+                set_plugin_config(name, BTreeMap::from([(setting, SettingValue::Number(number))])),
+                Message::SetSettingResponse,
+              ),
+              Err(_) => {
+                plugins_view.error = Some(format!("'{}' is not a number", input));
+                Command::none()
+              },
+            }
+          },
+          Message::AuditReportResponse(response) => {
+            match response {
+              Ok(report) => plugins_view.audit_report = Some(report),
+              Err(e) => warn!("Could not get the audit report: {}", e),
+            }
+
             Command::none()
           },
           Message::GoToOverview => {
             plugins_view.selected_plugin = None;
+            plugins_view.lifecycle_result = None;
+            plugins_view.key_bindings = None;
+            plugins_view.settings = None;
+            plugins_view.number_inputs.clear();
+            Command::none()
+          }
+          Message::InvokeLifecycle(name, event) => {
+            info!("Calling {} of plugin '{}'", event, name);
+
+            Command::perform(async move {
+              invoke_lifecycle(name.clone(), event).await?;
+              Ok(format!("Called {} of '{}'", event, name))
+            }, Message::InvokeLifecycleResponse)
+          },
+          Message::InvokeLifecycleResponse(result) => {
+            plugins_view.lifecycle_result = Some(result);
+
             Command::none()
           }
           Message::Reload(plugin_name) => {
             Command::perform(reload_and_get_plugins(plugin_name.clone()), Message::ReloadResponse)
           },
+          Message::ForceReload(plugin_name) => {
+            Command::perform(async move {
+              force_reload_plugin(plugin_name).await?;
+              get_plugins().await
+            }, Message::ReloadResponse)
+          },
+          Message::HotReloaded(successful) => match successful {
+            true => Command::perform(get_plugins(), Message::ReloadResponse),
+            false => Command::perform(get_plugins(), Message::GetPluginsResult),
+          },
+          Message::CopyError(error) => iced::clipboard::write(error),
+          Message::OpenPath(path) => {
+            if let Err(e) = open_path(&path) {
+              warn!("{}", e);
+              plugins_view.error = Some(e);
+            }
+
+            Command::none()
+          },
           Message::ReloadResponse(response) => {
             match response {
               Ok(new_plugins) => {
+                remember_plugins(&new_plugins);
                 plugins_view.plugins = new_plugins;
                 plugins_view.show_reload_success_message = true;
 
@@ -147,8 +395,7 @@ impl Plugins {
                 )
               },
               Err(e) => {
-                *self = Plugins::Error(e);
-
+                plugins_view.error = Some(format!("Could not reload the plugin: {}", e));
 
                 Command::none()
               }
@@ -161,7 +408,7 @@ impl Plugins {
           }
           Message::SelectPluginToInstall => {
             let plugin_package = match FileDialog::new()
-              .set_title("Select the Plugin Package to install")
+              .set_title("Select the Plugin Package to install or update")
               .add_filter("Plugin Package", &["zip"])
               .pick_file() {
                 Some(v) => v,
@@ -176,11 +423,13 @@ impl Plugins {
               Ok(InstallConfirmationPrompt {
                 plugin: response,
                 path: plugin_package,
+                installed_version: None,
               })
             }, Message::PluginInfoResponse)
           },
           Message::PluginInfoResponse(result) => match result {
-            Ok(info) => {
+            Ok(mut info) => {
+              info.installed_version = plugins_view.plugins.get(&info.plugin.name).map(|plugin| plugin.info.version.clone());
               plugins_view.confirm_installation = Some(info);
               Command::none()
             },
@@ -190,17 +439,198 @@ impl Plugins {
             }
           },
           Message::ConfirmInstallation(confirmation) => {
+            if confirmation.installed_version.is_some() {
+              info!("Update plugin '{}' with plugin package at '{}'", confirmation.plugin.name, confirmation.path.display());
+
+              return Command::perform(update_plugin(confirmation.path), Message::InstallResponse);
+            }
+
             info!("Install plugin package at '{}'", confirmation.path.display());
 
             Command::perform(install_plugin(confirmation.path).map_err(|e| e.to_string()), Message::InstallResponse)
           },
+          Message::SelectFolderToInstall => {
+            if let Some(folder) = FileDialog::new()
+              .set_title("Select the Plugin Folder to link")
+              .pick_folder() {
+                plugins_view.confirm_dev_installation = Some(folder);
+            }
+
+            Command::none()
+          },
+          Message::InstallFromFolder(folder) => {
+            if !get_config().developer_mode {
+              warn!("Installing from a folder requires developer mode");
+              return Command::none();
+            }
+
+            plugins_view.confirm_dev_installation = Some(folder);
+
+            Command::none()
+          },
+          Message::ConfirmDevInstallation(folder) => {
+            Command::perform(install_dev_plugin(folder), Message::InstallResponse)
+          },
+          Message::Promote(plugin_name) => {
+            Command::perform(async move {
+              promote_plugin(plugin_name).await?;
+              get_plugins().await
+            }, Message::PromoteResponse)
+          },
+          Message::PromoteResponse(response) => {
+            match response {
+              Ok(new_plugins) => {
+                remember_plugins(&new_plugins);
+                plugins_view.plugins = new_plugins;
+              },
+              Err(e) => {
+                warn!("Could not convert plugin: {}", e);
+                plugins_view.error = Some(e);
+              },
+            }
+
+            Command::none()
+          },
+          Message::Trust(plugin_name) => {
+            Command::perform(async move {
+              trust_plugin(plugin_name).await?;
+              get_plugins().await
+            }, Message::TrustResponse)
+          },
+          Message::TrustResponse(response) => {
+            match response {
+              Ok(new_plugins) => {
+                remember_plugins(&new_plugins);
+                plugins_view.plugins = new_plugins;
+              },
+              Err(e) => {
+                warn!("Could not trust plugin: {}", e);
+                plugins_view.error = Some(e);
+              },
+            }
+
+            Command::none()
+          },
+          Message::DismissSuspect(plugin_name) => {
+            Command::perform(async move {
+              dismiss_suspect(plugin_name).await?;
+              get_plugins().await
+            }, Message::DismissSuspectResponse)
+          },
+          Message::DismissSuspectResponse(response) => {
+            match response {
+              Ok(new_plugins) => {
+                remember_plugins(&new_plugins);
+                plugins_view.plugins = new_plugins;
+              },
+              Err(e) => {
+                warn!("Could not dismiss the crash suspicion: {}", e);
+                plugins_view.error = Some(e);
+              },
+            }
+
+            Command::none()
+          },
+          Message::SetNetworkApproval(plugin_name, approved) => {
+            Command::perform(async move {
+              set_network_approval(plugin_name, approved).await?;
+              get_plugins().await
+            }, Message::ApprovalResponse)
+          },
+          Message::SetCommandApproval(plugin_name, approved) => {
+            Command::perform(async move {
+              set_command_approval(plugin_name, approved).await?;
+              get_plugins().await
+            }, Message::ApprovalResponse)
+          },
+          Message::ApprovalResponse(response) => {
+            match response {
+              Ok(new_plugins) => {
+                remember_plugins(&new_plugins);
+                plugins_view.plugins = new_plugins;
+              },
+              Err(e) => {
+                warn!("Could not change the approval: {}", e);
+                plugins_view.error = Some(e);
+              },
+            }
+
+            Command::none()
+          },
           Message::CancelInstallation => {
             plugins_view.confirm_installation = None;
+            plugins_view.confirm_dev_installation = None;
+            plugins_view.confirm_restore = None;
+
+            Command::none()
+          },
+          Message::CreateBackup => {
+            let destination = match FileDialog::new()
+              .set_title("Save the Backup of the Plugins")
+              .set_file_name(&format!("futuremod-backup-{}.zip", Local::now().format("%Y-%m-%d")))
+              .add_filter("Backup", &["zip"])
+              .save_file() {
+                Some(v) => v,
+                None => return Command::none(),
+            };
+
+            Command::perform(download_backup(destination), Message::BackupResponse)
+          },
+          Message::BackupResponse(result) => {
+            match result {
+              Ok(path) => plugins_view.backup_message = Some(format!("Backed up the plugins to '{}'", path.display())),
+              Err(e) => {
+                warn!("Could not back up the plugins: {}", e);
+                plugins_view.error = Some(e);
+              },
+            }
+
+            Command::none()
+          },
+          Message::SelectBackupToRestore => {
+            if let Some(backup) = FileDialog::new()
+              .set_title("Select the Backup to restore")
+              .add_filter("Backup", &["zip"])
+              .pick_file() {
+                plugins_view.confirm_restore = Some(backup);
+            }
+
+            Command::none()
+          },
+          Message::ConfirmRestore(backup) => {
+            plugins_view.confirm_restore = None;
+
+            Command::perform(async move {
+              restore_backup(backup).await?;
+              get_plugins().await
+            }, Message::RestoreResponse)
+          },
+          Message::RestoreResponse(response) => {
+            match response {
+              Ok(new_plugins) => {
+                remember_plugins(&new_plugins);
+                plugins_view.plugins = new_plugins;
+                plugins_view.backup_message = Some(String::from("Restored the plugins from the backup"));
+
+                Command::none()
+              },
+              Err(e) => {
+                warn!("Could not restore the backup: {}", e);
+                plugins_view.error = Some(e);
+
+                // Plugins were unloaded even if the backup couldn't be restored entirely
+                Command::perform(get_plugins(), Message::GetPluginsResult)
+              },
+            }
+          },
+          Message::ClearBackupMessage => {
+            plugins_view.backup_message = None;
 
             Command::none()
           },
           Message::InstallResponse(result) => {
             plugins_view.confirm_installation = None;
+            plugins_view.confirm_dev_installation = None;
 
             match result {
               Ok(()) => {
@@ -254,12 +684,48 @@ impl Plugins {
       }
   }
 
+  fn disconnected(error: String) -> Self {
+    warn!("Could not get plugins: {}", error);
+
+    Plugins::Disconnected { error, last_known_plugins: last_known_plugins() }
+  }
+
+  /// Name of the plugin whose details are shown.
+  pub fn selected_plugin(&self) -> Option<String> {
+    match self {
+      Plugins::Loaded(plugins_view) => plugins_view.selected_plugin.clone(),
+      _ => None,
+    }
+  }
+
+  /// Go back within the view, i.e., close the installation prompt or leave the plugin's details.
+  ///
+  /// Returns `false` if the view cannot go back any further.
+  pub fn go_back(&mut self) -> bool {
+    let plugins_view = match self {
+      Plugins::Loaded(plugins_view) => plugins_view,
+      _ => return false,
+    };
+
+    if plugins_view.confirm_installation.is_some() || plugins_view.confirm_dev_installation.is_some() || plugins_view.confirm_restore.is_some() {
+      plugins_view.confirm_installation = None;
+      plugins_view.confirm_dev_installation = None;
+      plugins_view.confirm_restore = None;
+      return true;
+    }
+
+    if plugins_view.selected_plugin.is_some() {
+      plugins_view.selected_plugin = None;
+      plugins_view.lifecycle_result = None;
+      return true;
+    }
+
+    false
+  }
+
   pub fn view(&self) -> Element<Message> {
       match self {
-          Plugins::Error(e) => {
-            text(format!("Could not get plugins: {}", e))
-            .into()
-          },
+          Plugins::Disconnected{error, last_known_plugins} => disconnected_view(error, last_known_plugins),
           Plugins::Loading => {
             text("Loading plugins...")
             .into()
@@ -268,13 +734,13 @@ impl Plugins {
             if let Some(plugin_name) = &plugin_view.selected_plugin {
               let plugin = plugin_view.plugins.get(plugin_name).unwrap();
 
-              return plugin_details_view(plugin, plugin_view.show_reload_success_message);
+              return plugin_details_view(plugin, plugin_view.show_reload_success_message, &plugin_view.lifecycle_result, &plugin_view.audit_report, &plugin_view.key_bindings, &plugin_view.keys, &plugin_view.settings, &plugin_view.number_inputs, &plugin_view.notes_input);
             }
 
             let mut list = Column::new();
 
-            for (name, plugin) in plugin_view.plugins.iter() {
-              list = list.push(plugin_card(name, plugin));
+            for plugin in plugin_view.plugins.values() {
+              list = list.push(plugin_card(plugin));
             }
 
             list = list
@@ -288,8 +754,11 @@ impl Plugins {
                 row![
                   button(icon(iced_aw::BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
                   container(text("Plugins").size(24).vertical_alignment(Vertical::Center)).width(Length::Fill).align_y(Vertical::Center),
-                  button("Install Plugin").on_press(Message::SelectPluginToInstall).style(Button::Primary)
                 ]
+                  .push(button("Back Up").on_press(Message::CreateBackup).style(Button::Default))
+                  .push(button("Restore").on_press(Message::SelectBackupToRestore).style(Button::Default))
+                  .push_maybe(get_config().developer_mode.then(|| button("Install from Folder").on_press(Message::SelectFolderToInstall).style(Button::Default)))
+                  .push(button("Install Plugin").on_press(Message::SelectPluginToInstall).style(Button::Primary))
                   .spacing(16)
                   .align_items(iced::Alignment::Center),
               ).padding(8),  
@@ -311,12 +780,29 @@ impl Plugins {
               )
             }
 
+            if let Some(message) = &plugin_view.backup_message {
+              content = content.push(
+                container(
+                    container(
+                      row![
+                        text(message).width(Length::Fill),
+                        button(icon(BootstrapIcon::X)).on_press(Message::ClearBackupMessage).style(Button::Text)
+                      ].align_items(iced::Alignment::Center),
+                    )
+                    .padding(16)
+                    .style(Container::Box)
+                  )
+                  .padding(16)
+              )
+            }
+
             let underlay: Element<'_, Message> = content
+              .push_maybe(suspects_notification(&plugin_view.plugins))
               .push(list)
               .into();
 
             let overlay = if let Some(confirmation_prompt) = &plugin_view.confirm_installation {
-              let warning: Option<iced::widget::Container<Message, Theme>> = if confirmation_prompt.plugin.dependencies.contains(&PluginDependency::Dangerous) {
+              let warning: Option<iced::widget::Container<Message, Theme>> = if confirmation_prompt.plugin.dependencies.iter().any(PluginDependency::is_dangerous) {
                 Some(
                   container(
                     text("This plugin has a dangerous dependency. This plugin can easily access your entire computer. Only install plugins with dangerous dependency if you are sure they are not malicious.")
@@ -328,21 +814,40 @@ impl Plugins {
                 None
               };
 
+              let (title, question, confirm_label) = match &confirmation_prompt.installed_version {
+                Some(installed_version) => (
+                  "Confirm update",
+                  format!(
+                    "Are you sure you want to update the plugin '{}' from version {} to {}. Its settings and stored data are kept.",
+                    confirmation_prompt.plugin.display_name(),
+                    installed_version,
+                    confirmation_prompt.plugin.version,
+                  ),
+                  "Update",
+                ),
+                None => (
+                  "Confirm installation",
+                  format!("Are you sure you want to install the plugin '{}'.", confirmation_prompt.plugin.display_name()),
+                  "Install",
+                ),
+              };
+
               Some(
                 container(
                   column![
-                  text("Confirm installation").size(24.0),
+                  text(title).size(24.0),
                   Space::with_height(12.0),
                   container(
                     scrollable(
                       Column::new()
-                        .push(text(format!("Are you sure you want to install the plugin '{}'.", confirmation_prompt.plugin.name.clone())))
+                        .push(text(question))
                         .push_maybe(warning)
                         .push(column![
                           text("General Information").size(24),
-                          text(format!("Name: {}", confirmation_prompt.plugin.name.clone())),
+                          text(format!("Name: {}", confirmation_prompt.plugin.display_name())),
                           text(format!("Authors: {}", confirmation_prompt.plugin.authors.clone().join(", "))),
                           text(format!("Version: {}", confirmation_prompt.plugin.version)),
+                          text(format!("Plugin API version: {}", confirmation_prompt.plugin.api_version.as_deref().unwrap_or("Not declared"))),
                         ].spacing(4))
                         .push(column![
                           text("Description").size(24),
@@ -358,6 +863,10 @@ impl Plugins {
                           text("Dependencies").size(24),
                           dependencies_list(&confirmation_prompt.plugin.dependencies),
                         ].spacing(4))
+                        .push(column![
+                          text("Permissions").size(24),
+                          permissions_list(&confirmation_prompt.plugin),
+                        ].spacing(4))
                         .spacing(24)
                         .padding([0, 16, 0, 8]),
                     )
@@ -367,7 +876,7 @@ impl Plugins {
                   row![
                     Space::with_width(Length::Fill),
                     button(text("Cancel")).style(Button::Destructive).on_press(Message::CancelInstallation),
-                    button(text("Install")).on_press(Message::ConfirmInstallation(confirmation_prompt.clone())).style(Button::Primary),
+                    button(text(confirm_label)).on_press(Message::ConfirmInstallation(confirmation_prompt.clone())).style(Button::Primary),
                   ]
                   .align_items(Alignment::End)
                   .spacing(8.0)
@@ -377,6 +886,10 @@ impl Plugins {
                   .style(Container::Dialog)
                   .padding(16.0)
               )
+            } else if let Some(folder) = &plugin_view.confirm_dev_installation {
+              Some(dev_installation_prompt(folder))
+            } else if let Some(backup) = &plugin_view.confirm_restore {
+              Some(restore_prompt(backup))
             } else {
               None
             };
@@ -390,14 +903,138 @@ impl Plugins {
   }
 }
 
-fn plugin_card<'a>(name: &String, plugin: &Plugin) -> Element<'a, Message> {
+/// Dialog to confirm linking a plugin folder in developer mode.
+fn dev_installation_prompt<'a>(folder: &PathBuf) -> iced::widget::Container<'a, Message, Theme> {
+  container(
+    column![
+      text("Install from folder").size(24.0),
+      text(format!("Install the plugin in '{}' in developer mode?", folder.display())),
+      container(
+        text("The folder is linked into the plugins directory, not copied. Changes to the plugin's files take effect when you reload it. Uninstalling the plugin only removes the link and keeps your files.")
+      )
+      .style(Container::Warning)
+      .padding(8),
+      row![
+        Space::with_width(Length::Fill),
+        button(text("Cancel")).style(Button::Destructive).on_press(Message::CancelInstallation),
+        button(text("Link Plugin")).on_press(Message::ConfirmDevInstallation(folder.clone())).style(Button::Primary),
+      ]
+      .spacing(8.0)
+      .width(Length::Fill),
+    ]
+    .spacing(12)
+  )
+  .max_width(500.0)
+  .style(Container::Dialog)
+  .padding(16.0)
+}
+
+/// Dialog to confirm replacing all plugins with a backup.
+fn restore_prompt<'a>(backup: &PathBuf) -> iced::widget::Container<'a, Message, Theme> {
+  container(
+    column![
+      text("Restore backup").size(24.0),
+      text(format!("Replace all plugins with the plugins in the backup '{}'?", backup.display())),
+      container(
+        text("All installed plugins, their settings, and their stored data are replaced by the backup. Back up the current plugins first to keep them. Plugins installed from a folder aren't part of backups and stay installed.")
+      )
+      .style(Container::Warning)
+      .padding(8),
+      row![
+        Space::with_width(Length::Fill),
+        button(text("Cancel")).on_press(Message::CancelInstallation),
+        button(text("Restore")).on_press(Message::ConfirmRestore(backup.clone())).style(Button::Destructive),
+      ]
+      .spacing(8.0)
+      .width(Length::Fill),
+    ]
+    .spacing(12)
+  )
+  .max_width(500.0)
+  .style(Container::Dialog)
+  .padding(16.0)
+}
+
+fn disconnected_view<'a>(error: &String, last_known_plugins: &Option<HashMap<String, Plugin>>) -> Element<'a, Message> {
+  let mut content = column![
+    container(
+      row![
+        button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+        container(text("Plugins").size(24).vertical_alignment(Vertical::Center)).width(Length::Fill).align_y(Vertical::Center),
+        button("Retry").on_press(Message::Retry).style(Button::Primary),
+      ]
+        .spacing(16)
+        .align_items(iced::Alignment::Center),
+    ).padding(8),
+    container(
+      container(
+        column![
+          text("Cannot reach the mod").size(20),
+          text("Make sure Future Cop is running and FutureMod injected the mod. If the game was restarted, restart FutureMod to inject the mod again."),
+          text(format!("Error: {}", error)).size(12),
+        ].spacing(8)
+      )
+      .padding(16)
+      .width(Length::Fill)
+      .style(Container::Warning)
+    ).padding([0, 16]),
+  ];
+
+  if let Some(plugins) = last_known_plugins {
+    let mut names: Vec<&String> = plugins.keys().collect();
+    names.sort();
+
+    let mut list = Column::new()
+      .push(text("Last known plugins").size(20))
+      .push(text("The state of these plugins might have changed since the connection was lost.").size(12));
+
+    for name in names {
+      list = list.push(
+        container(
+          Column::new()
+            .push(text(name).size(20))
+            .push(plugin_state_component(&plugins[name]))
+            .width(Length::Fill)
+        )
+        .style(Container::Box)
+        .padding(16)
+      );
+    }
+
+    content = content.push(
+      Scrollable::new(
+        list
+          .spacing(12)
+          .padding(Padding::new(24.0))
+          .width(Length::Fill)
+      )
+      .height(Length::Fill)
+    );
+  }
+
+  content.into()
+}
+
+fn plugin_card<'a>(plugin: &Plugin) -> Element<'a, Message> {
   container(
     row![
       Column::new()
-        .push(text(name).size(20))
+        .push(
+          Row::new()
+            .push(text(plugin.info.display_name().to_string()).size(20))
+            .push_maybe(dev_badge(plugin))
+            .push_maybe(tampered_badge(plugin))
+            .push_maybe(suspect_badge(plugin))
+            .push_maybe(api_version_badge(plugin))
+            .spacing(8)
+            .align_items(Alignment::Center)
+        )
         .push(plugin_state_component(plugin))
+        .push_maybe(plugin.is_dev().then(|| text(plugin.info.path.display().to_string()).size(12)))
+        .push_maybe(plugin_note_summary(plugin))
         .width(Length::Fill),
       Row::new()
+      .push_maybe(plugin.is_dev().then(|| plugin_open_folder_button(plugin)))
       .push(plugin_go_to_details_button(plugin))
       .push_maybe(plugin_toggle_button(plugin))
       .spacing(8)
@@ -410,6 +1047,152 @@ fn plugin_card<'a>(name: &String, plugin: &Plugin) -> Element<'a, Message> {
   .into()
 }
 
+/// Number of characters of the notes shown on a plugin's card.
+const NOTES_PREVIEW_LENGTH: usize = 80;
+
+/// The user's rating and the beginning of their notes, if they rated the plugin or took notes.
+fn plugin_note_summary<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  let note = get_config().plugin_notes.get(&plugin.info.name).cloned()?;
+
+  let mut notes = note.notes.lines().next().unwrap_or_default().to_string();
+  if notes.chars().count() > NOTES_PREVIEW_LENGTH || note.notes.lines().count() > 1 {
+    notes = format!("{}...", notes.chars().take(NOTES_PREVIEW_LENGTH).collect::<String>());
+  }
+
+  Some(
+    Row::new()
+      .push_maybe(note.rating.map(|rating| text(format!("{}{}", "\u{2605}".repeat(rating as usize), "\u{2606}".repeat(MAX_RATING.saturating_sub(rating) as usize))).style(Text::Warn)))
+      .push_maybe((!notes.is_empty()).then(|| text(notes).size(12)))
+      .spacing(8)
+      .align_items(Alignment::Center)
+      .into()
+  )
+}
+
+/// Badge that marks plugins installed in developer mode.
+fn dev_badge<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  if !plugin.is_dev() {
+    return None;
+  }
+
+  Some(
+    container(text("Developer").size(12))
+      .style(Container::Badge)
+      .padding([2, 6])
+      .into()
+  )
+}
+
+/// Badge that marks plugins whose files changed since they were installed.
+fn tampered_badge<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  if !plugin.tampered {
+    return None;
+  }
+
+  Some(
+    container(text("Tampered").size(12))
+      .style(Container::Warning)
+      .padding([2, 6])
+      .into()
+  )
+}
+
+/// Badge with the plugin API version the plugin targets, marked if the mod doesn't support it.
+///
+/// The mod checks the compatibility when loading the plugin. Plugins that don't declare a version have no badge.
+fn api_version_badge<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  if let PluginState::Error(PluginError::IncompatibleApiVersion { required, .. }) = &plugin.state {
+    return Some(
+      container(text(format!("Incompatible API {}", required)).size(12))
+        .style(Container::Warning)
+        .padding([2, 6])
+        .into()
+    );
+  }
+
+  let api_version = plugin.info.api_version.as_ref()?;
+
+  Some(
+    container(text(format!("API {}", api_version)).size(12))
+      .style(Container::Badge)
+      .padding([2, 6])
+      .into()
+  )
+}
+
+/// Badge that marks plugins the game crashed shortly after enabling.
+fn suspect_badge<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  if !plugin.suspect {
+    return None;
+  }
+
+  Some(
+    container(text("Crash Suspect").size(12))
+      .style(Container::Warning)
+      .padding([2, 6])
+      .into()
+  )
+}
+
+/// Notification about the plugins that were disabled because the game crashed shortly after enabling them.
+fn suspects_notification<'a>(plugins: &HashMap<String, Plugin>) -> Option<Element<'a, Message>> {
+  let mut suspects: Vec<&String> = plugins.iter().filter(|(_, plugin)| plugin.suspect).map(|(name, _)| name).collect();
+  if suspects.is_empty() {
+    return None;
+  }
+  suspects.sort();
+
+  let mut content = Column::new()
+    .push(text("The game crashed shortly after enabling the following plugins. They were disabled to prevent the game from crashing again."))
+    .spacing(8);
+
+  for name in suspects {
+    content = content.push(
+      row![
+        text(name).font(bold()).width(Length::Fill),
+        button(text("Dismiss")).on_press(Message::DismissSuspect(name.clone())).style(Button::Text),
+        button(text("Enable")).on_press(Message::Enable(name.clone())).style(Button::Default),
+      ]
+      .spacing(8)
+      .align_items(Alignment::Center)
+    );
+  }
+
+  Some(
+    container(
+      container(content)
+        .padding(16)
+        .width(Length::Fill)
+        .style(Container::Warning)
+    )
+    .padding([0, 24])
+    .into()
+  )
+}
+
+/// Warning about a plugin the game crashed shortly after enabling.
+fn suspect_warning<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  if !plugin.suspect {
+    return None;
+  }
+
+  Some(
+    container(
+      column![
+        text("The game crashed shortly after this plugin was enabled, so it was disabled to prevent the game from crashing again."),
+        text("Enable the plugin to try it again, or dismiss this warning to keep it disabled."),
+        button(text("Dismiss"))
+          .on_press(Message::DismissSuspect(plugin.info.name.clone()))
+          .style(Button::Default),
+      ].spacing(8)
+    )
+    .padding(16)
+    .width(Length::Fill)
+    .style(Container::Warning)
+    .into()
+  )
+}
+
 fn plugin_state_component<'a>(plugin: &Plugin) -> Element<'a, Message> {
   let message = match &plugin.state {
     PluginState::Error(_) => String::from("Error"),
@@ -469,13 +1252,40 @@ fn plugin_reload_button<'a>(plugin: &Plugin) -> Element<'a, Message> {
     .into()
 }
 
+fn plugin_open_folder_button<'a>(plugin: &Plugin) -> Element<'a, Message> {
+  button(text("Open Folder"))
+    .on_press(Message::OpenPath(plugin.info.path.clone()))
+    .style(Button::Default)
+    .into()
+}
+
+/// Warning about a plugin whose files changed since it was installed.
+fn tampered_warning<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  if !plugin.tampered {
+    return None;
+  }
+
+  Some(
+    container(
+      column![
+        text("The plugin's files changed since it was installed. Someone or something may have tampered with them, so the plugin was not loaded."),
+        text("Only trust the current files if you changed them yourself or got them from a source you trust."),
+        button(text("Trust Current Files"))
+          .on_press(Message::Trust(plugin.info.name.clone()))
+          .style(Button::Destructive),
+      ].spacing(8)
+    )
+    .padding(16)
+    .width(Length::Fill)
+    .style(Container::Warning)
+    .into()
+  )
+}
+
 fn plugin_details_state<'a>(plugin: &Plugin) -> Element<'a, Message> {
   let content: Element<_> = match &plugin.state {
     PluginState::Unloaded => text("The plugin is currently unloaded").into(),
-    PluginState::Error(e) => column![
-      text("The plugin has errored with the following error:"),
-      text(format!("{:?}", e)),
-    ].into(),
+    PluginState::Error(e) => plugin_error_details(e),
     PluginState::Loaded(_) => match plugin.enabled {
       true => text("The plugin is loaded and enabled").into(),
       false => text("The plugin is loaded but disabled").into(),
@@ -485,6 +1295,128 @@ fn plugin_details_state<'a>(plugin: &Plugin) -> Element<'a, Message> {
   content.into()
 }
 
+/// State of the plugin captured by an error breakpoint.
+fn error_snapshot_details<'a>(snapshot: &ErrorSnapshot) -> Element<'a, Message> {
+  let arguments = match snapshot.arguments.is_empty() {
+    true => String::from("None"),
+    false => snapshot.arguments.join(", "),
+  };
+
+  let logs = match snapshot.logs.is_empty() {
+    true => String::from("The plugin didn't log anything before the error"),
+    false => snapshot.logs.join("\n"),
+  };
+
+  column![
+    text(format!("Frame: {}", snapshot.frame)),
+    text(format!("Arguments: {}", arguments)),
+    text("Latest logs").font(bold()),
+    container(
+      Scrollable::new(text(logs).size(12)).width(Length::Fill)
+    )
+    .max_height(200)
+    .width(Length::Fill)
+    .padding(8)
+    .style(Container::Box),
+  ]
+  .spacing(4)
+  .into()
+}
+
+/// Plain text of the snapshot to copy it together with the error.
+fn error_snapshot_report(snapshot: &ErrorSnapshot) -> String {
+  format!(
+    "Frame: {}\nArguments: {}\nLatest logs:\n{}",
+    snapshot.frame,
+    snapshot.arguments.join(", "),
+    snapshot.logs.join("\n"),
+  )
+}
+
+/// Details of the error that prevented the plugin from loading or stopped it.
+fn plugin_error_details<'a>(error: &PluginError) -> Element<'a, Message> {
+  let mut content = Column::new()
+    .push(text("The plugin has errored with the following error:"));
+
+  let copy_text = match error {
+    PluginError::ScriptError(e) => {
+      content = content
+        .push(text(e.context.clone()).font(bold()))
+        .push(text(e.message.clone()).style(theme::Text::Danger))
+        .push_maybe(e.location().map(|location| text(format!("Location: {}", location))));
+
+      if let Some(traceback) = &e.traceback {
+        content = content.push(
+          container(
+            Scrollable::new(text(traceback.clone()).size(12)).width(Length::Fill)
+          )
+          .max_height(200)
+          .width(Length::Fill)
+          .padding(8)
+          .style(Container::Box)
+        );
+      }
+
+      if let Some(snapshot) = &e.snapshot {
+        content = content.push(error_snapshot_details(snapshot));
+      }
+
+      let mut copy_text = match &e.traceback {
+        Some(traceback) => format!("{}\n{}", e, traceback),
+        None => e.to_string(),
+      };
+
+      if let Some(snapshot) = &e.snapshot {
+        copy_text.push_str(&format!("\n\n{}", error_snapshot_report(snapshot)));
+      }
+
+      copy_text
+    },
+    e => {
+      content = content.push(text(e.to_string()).style(theme::Text::Danger));
+      e.to_string()
+    },
+  };
+
+  // Opening the file is a developer tool and only useful for files that still exist
+  let open_file_button = match error {
+    PluginError::ScriptError(ScriptError { file: Some(file), .. }) if get_config().developer_mode && file.is_file() => Some(
+      button(text("Open File"))
+        .on_press(Message::OpenPath(file.clone()))
+        .style(Button::Default)
+    ),
+    _ => None,
+  };
+
+  content
+    .push(
+      Row::new()
+        .push(
+          button(text("Copy Error"))
+            .on_press(Message::CopyError(copy_text))
+            .style(Button::Default)
+        )
+        .push_maybe(open_file_button)
+        .spacing(8)
+    )
+    .spacing(8)
+    .into()
+}
+
+/// Button to update the plugin from a plugin package. Plugins in developer mode are updated by changing their folder.
+fn plugin_update_button<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  if plugin.is_dev() {
+    return None;
+  }
+
+  Some(
+    button(text("Update"))
+      .on_press(Message::SelectPluginToInstall)
+      .style(Button::Default)
+      .into()
+  )
+}
+
 fn plugin_uninstall_button<'a>(plugin: &Plugin) -> Element<'a, Message> {
   button(text("Uninstall"))
   .on_press(Message::UninstallPlugin(plugin.info.name.clone()))
@@ -492,7 +1424,7 @@ fn plugin_uninstall_button<'a>(plugin: &Plugin) -> Element<'a, Message> {
   .into()
 }
 
-fn plugin_details_view<'a>(plugin: &Plugin, show_reload_success_msg: bool) -> Element<'a, Message> {
+fn plugin_details_view<'a>(plugin: &Plugin, show_reload_success_msg: bool, lifecycle_result: &Option<Result<String, String>>, audit_report: &Option<AuditReport>, key_bindings: &Option<Vec<KeyBinding>>, keys: &[String], settings: &Option<PluginSettings>, number_inputs: &HashMap<String, String>, notes_input: &str) -> Element<'a, Message> {
   let reload_success_msg = match show_reload_success_msg {
     true => Some(text("Successfully reloaded")),
     false => None, 
@@ -503,8 +1435,8 @@ fn plugin_details_view<'a>(plugin: &Plugin, show_reload_success_msg: bool) -> El
       column![
         row![
           button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoToOverview),
-          text(plugin.info.name.clone()).size(24),
-        ].spacing(16).padding([0, 0, 8, 0]).align_items(Alignment::Center),
+          text(plugin.info.display_name().to_string()).size(24),
+        ].push_maybe(dev_badge(plugin)).push_maybe(tampered_badge(plugin)).push_maybe(suspect_badge(plugin)).push_maybe(api_version_badge(plugin)).spacing(16).padding([0, 0, 8, 0]).align_items(Alignment::Center),
         row![
           text(plugin.info.version.clone()),
           text(format!("by {}", plugin.info.authors.join(", "))),
@@ -512,16 +1444,17 @@ fn plugin_details_view<'a>(plugin: &Plugin, show_reload_success_msg: bool) -> El
         Row::new()
           .push(plugin_reload_button(plugin))
           .push_maybe(plugin_toggle_button(plugin))
+          .push_maybe(plugin_update_button(plugin))
           .push(plugin_uninstall_button(plugin))
           .push_maybe(reload_success_msg)
           .spacing(8)
           .padding([0, 0, 8, 0])
           .align_items(Alignment::Center),
         plugin_details_state(plugin),
-      ]
+      ].push_maybe(tampered_warning(plugin)).push_maybe(suspect_warning(plugin))
     ).padding(8),
     container(rule::Rule::horizontal(1.0)).padding([0, 8, 0, 8]),
-    plugin_details_content(plugin),
+    plugin_details_content(plugin, lifecycle_result, audit_report, key_bindings, keys, settings, number_inputs, notes_input),
   ]
   .into()
 }
@@ -539,7 +1472,7 @@ fn plugin_description<'a>(description: String) -> Element<'a, Message> {
     .into()
 }
 
-fn plugin_details_content<'a>(plugin: &Plugin) -> Element<'a, Message> {
+fn plugin_details_content<'a>(plugin: &Plugin, lifecycle_result: &Option<Result<String, String>>, audit_report: &Option<AuditReport>, key_bindings: &Option<Vec<KeyBinding>>, keys: &[String], settings: &Option<PluginSettings>, number_inputs: &HashMap<String, String>, notes_input: &str) -> Element<'a, Message> {
   let description = if plugin.info.description.len() > 0 {
     plugin.info.description.clone()
   } else {
@@ -556,18 +1489,420 @@ fn plugin_details_content<'a>(plugin: &Plugin) -> Element<'a, Message> {
       column![
         text("Dependencies").size(24),
         dependencies_list(&plugin.info.dependencies),
-      ]
+      ],
+
+      column![
+        text("Permissions").size(24),
+        permissions_list(&plugin.info),
+      ],
+
+      notes_section(plugin, notes_input),
     ]
+    .push_maybe(settings_section(plugin, settings, number_inputs))
+    .push_maybe(features_section(plugin))
+    .push_maybe(network_section(plugin))
+    .push_maybe(commands_section(plugin))
+    .push_maybe(checksum_section(plugin))
+    .push_maybe(dev_plugin_section(plugin))
+    .push_maybe(key_bindings_section(plugin, key_bindings, keys))
+    .push_maybe(audit_section(plugin, audit_report))
+    .push_maybe(developer_tools(plugin, lifecycle_result))
     .spacing(24)
     .padding([8, 8, 8, 8])
   )
   .into()
 }
 
+/// The user's private rating and notes of the plugin.
+fn notes_section<'a>(plugin: &Plugin, notes_input: &str) -> Element<'a, Message> {
+  let note = get_config().plugin_notes.get(&plugin.info.name).cloned().unwrap_or_default();
+
+  let mut stars = Row::new().spacing(4);
+  for rating in 1..=MAX_RATING {
+    let star = match note.rating.is_some_and(|current| current >= rating) {
+      true => BootstrapIcon::StarFill,
+      false => BootstrapIcon::Star,
+    };
+
+    // Clicking the current rating removes it
+    let next = match note.rating == Some(rating) {
+      true => None,
+      false => Some(rating),
+    };
+
+    stars = stars.push(
+      button(icon_with_style(star, Text::Warn))
+        .on_press(Message::SetRating(plugin.info.name.clone(), next))
+        .style(Button::Text)
+    );
+  }
+
+  let is_saved = notes_input.trim() == note.notes;
+  let mut save = button(text("Save"));
+  if !is_saved {
+    save = save.on_press(Message::SaveNotes(plugin.info.name.clone()));
+  }
+
+  column![
+    text("Your Notes").size(24),
+    text("Only stored on this computer.").size(12),
+    stars,
+    row![
+      text_input("Notes", notes_input)
+        .on_input(Message::NotesChanged)
+        .on_submit(Message::SaveNotes(plugin.info.name.clone())),
+      save,
+    ]
+    .spacing(8)
+    .align_items(Alignment::Center),
+  ]
+  .spacing(8)
+  .into()
+}
+
+/// Engine features the plugin requires.
+fn features_section<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  if plugin.info.features.is_empty() {
+    return None;
+  }
+
+  let mut list = Column::new();
+  for feature in plugin.info.features.iter() {
+    list = list.push(text(format!("- {}", feature)));
+  }
+
+  Some(
+    column![
+      text("Engine Features").size(24),
+      list,
+    ]
+    .into()
+  )
+}
+
+/// Hosts the plugin sends requests to and whether the user approved them.
+fn network_section<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  if plugin.info.hosts.is_empty() && !plugin.info.dependencies.contains(&PluginDependency::Net) {
+    return None;
+  }
+
+  let mut hosts = Column::new();
+  for host in plugin.info.hosts.iter() {
+    hosts = hosts.push(text(format!("- {}", host)));
+  }
+
+  let (status, action) = match (plugin.info.hosts.is_empty(), plugin.network_approved) {
+    (true, _) => (text("The plugin doesn't declare any hosts, so it can't send requests."), None),
+    (false, true) => (
+      text("You approved that the plugin sends requests to these hosts."),
+      Some(button(text("Revoke")).on_press(Message::SetNetworkApproval(plugin.info.name.clone(), false)).style(Button::Destructive)),
+    ),
+    (false, false) => (
+      text("The plugin can't send requests until you approve these hosts. The plugin can send any data to them, including what it reads from the game.").style(theme::Text::Warn),
+      Some(button(text("Approve")).on_press(Message::SetNetworkApproval(plugin.info.name.clone(), true)).style(Button::Primary)),
+    ),
+  };
+
+  Some(
+    column![
+      text("Network Access").size(24),
+      hosts,
+      row![status.width(Length::Fill)].push_maybe(action).spacing(8).align_items(Alignment::Center),
+    ]
+    .spacing(8)
+    .into()
+  )
+}
+
+/// Programs the plugin runs and whether the user approved them.
+fn commands_section<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  if plugin.info.commands.is_empty() && !plugin.info.granted_permissions().contains(&PluginPermission::Exec) {
+    return None;
+  }
+
+  let mut commands = Column::new();
+  for command in plugin.info.commands.iter() {
+    commands = commands.push(text(format!("- {}", command)));
+  }
+
+  let (status, action) = match (plugin.info.commands.is_empty(), plugin.commands_approved) {
+    (true, _) => (text("The plugin doesn't declare any programs, so it can't run any."), None),
+    (false, true) => (
+      text("You approved that the plugin runs these programs."),
+      Some(button(text("Revoke")).on_press(Message::SetCommandApproval(plugin.info.name.clone(), false)).style(Button::Destructive)),
+    ),
+    (false, false) => (
+      text("The plugin can't run these programs until you approve them. It chooses their arguments, so only approve programs you trust with any arguments.").style(theme::Text::Warn),
+      Some(button(text("Approve")).on_press(Message::SetCommandApproval(plugin.info.name.clone(), true)).style(Button::Primary)),
+    ),
+  };
+
+  Some(
+    column![
+      text("External Programs").size(24),
+      commands,
+      row![status.width(Length::Fill)].push_maybe(action).spacing(8).align_items(Alignment::Center),
+    ]
+    .spacing(8)
+    .into()
+  )
+}
+
+/// Checksum of the plugin's files pinned when it was installed.
+fn checksum_section<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  let checksum = plugin.checksum.as_ref()?;
+
+  Some(
+    column![
+      text("Checksum").size(24),
+      text(format!("SHA-256: {}", checksum)).size(12),
+      text("Compare it with the checksum published by the plugin's author to make sure you got the original plugin."),
+    ]
+    .spacing(8)
+    .into()
+  )
+}
+
+/// Source folder and actions of a plugin installed in developer mode.
+fn dev_plugin_section<'a>(plugin: &Plugin) -> Option<Element<'a, Message>> {
+  let link = plugin.dev_link.as_ref()?;
+
+  Some(
+    column![
+      text("Developer Plugin").size(24),
+      text("This plugin is linked to its source folder. Changes to its files take effect when reloading the plugin."),
+      text(format!("Source folder: {}", plugin.info.path.display())),
+      text(format!("Linked at: {}", link.display())).size(12),
+      row![
+        plugin_open_folder_button(plugin),
+        button(text("Force Reload"))
+          .on_press(Message::ForceReload(plugin.info.name.clone()))
+          .style(Button::Default),
+        button(text("Convert to Regular Install"))
+          .on_press(Message::Promote(plugin.info.name.clone()))
+          .style(Button::Default),
+      ].spacing(8),
+      text("Force reloading also reloads the plugin's info.toml, e.g., to pick up new dependencies.").size(12),
+      text("Converting copies the plugin's files into the plugins directory. Afterwards, changes to the source folder don't affect the installed plugin.").size(12),
+    ].spacing(8).into()
+  )
+}
+
+/// Form to change the plugin's settings. Changes apply immediately.
+///
+/// Key binding settings are changed with the plugin's other key bindings.
+fn settings_section<'a>(plugin: &Plugin, settings: &Option<PluginSettings>, number_inputs: &HashMap<String, String>) -> Option<Element<'a, Message>> {
+  let settings = settings.as_ref()?;
+
+  let mut form = Column::new().spacing(16);
+  let mut is_empty = true;
+  for setting in settings.settings.iter() {
+    let value = settings.values.get(&setting.name).cloned().unwrap_or_else(|| setting.default_value());
+    let plugin_name = plugin.info.name.clone();
+    let setting_name = setting.name.clone();
+
+    let control: Element<'a, Message> = match (&setting.kind, value) {
+      (SettingKind::Boolean { .. }, SettingValue::Boolean(enabled)) => Toggler::new(
+        setting.label().to_string(),
+        enabled,
+        move |enabled| Message::SetSetting(plugin_name.clone(), setting_name.clone(), SettingValue::Boolean(enabled)),
+      ).into(),
+      (SettingKind::Number { min, max, .. }, SettingValue::Number(number)) => {
+        let input = number_inputs.get(&setting.name).cloned().unwrap_or_else(|| number.to_string());
+        let range = match (min, max) {
+          (Some(min), Some(max)) => format!("Between {} and {}. ", min, max),
+          (Some(min), None) => format!("At least {}. ", min),
+          (None, Some(max)) => format!("At most {}. ", max),
+          (None, None) => String::new(),
+        };
+
+        column![
+          text(setting.label().to_string()).font(bold()),
+          text_input("", &input)
+            .on_input(move |input| Message::NumberInputChanged(setting_name.clone(), input))
+            .on_submit(Message::SubmitNumber(plugin_name, setting.name.clone())),
+          text(format!("{}Press Enter to apply.", range)).size(12),
+        ].spacing(4).into()
+      },
+      (SettingKind::Enum { options, .. }, SettingValue::Text(option)) => column![
+        text(setting.label().to_string()).font(bold()),
+        pick_list(options.clone(), Some(option), move |option| Message::SetSetting(plugin_name.clone(), setting_name.clone(), SettingValue::Text(option))),
+      ].spacing(4).into(),
+      _ => continue,
+    };
+
+    let mut entry = Column::new().push(control).spacing(4);
+    if !setting.description.is_empty() {
+      entry = entry.push(text(setting.description.clone()).size(12));
+    }
+
+    form = form.push(entry);
+    is_empty = false;
+  }
+
+  if is_empty {
+    return None;
+  }
+
+  Some(
+    column![
+      text("Settings").size(24),
+      form,
+    ]
+    .spacing(8)
+    .into()
+  )
+}
+
+/// Key bindings the plugin registered, with a key picker for each binding.
+///
+/// Changes apply immediately and are kept when the plugin is reloaded.
+fn key_bindings_section<'a>(plugin: &Plugin, key_bindings: &Option<Vec<KeyBinding>>, keys: &[String]) -> Option<Element<'a, Message>> {
+  let bindings = key_bindings.as_ref().filter(|bindings| !bindings.is_empty())?;
+
+  let mut list = Column::new().spacing(8);
+  for binding in bindings.iter() {
+    let plugin_name = plugin.info.name.clone();
+    let binding_name = binding.name.clone();
+
+    let mut binding_row = row![
+      text(binding.name.clone()).width(Length::FillPortion(1)),
+      pick_list(keys.to_vec(), Some(binding.key.clone()), move |key| Message::SetKeyBinding(
+        plugin_name.clone(),
+        SetKeyBinding { name: binding_name.clone(), key: Some(key) },
+      )).width(Length::FillPortion(1)),
+    ]
+    .spacing(8)
+    .align_items(Alignment::Center);
+
+    if binding.key != binding.default_key {
+      binding_row = binding_row.push(
+        button(text(format!("Reset to {}", binding.default_key)))
+          .on_press(Message::SetKeyBinding(
+            plugin.info.name.clone(),
+            SetKeyBinding { name: binding.name.clone(), key: None },
+          ))
+          .style(Button::Default)
+      );
+    }
+
+    list = list.push(binding_row);
+  }
+
+  Some(
+    column![
+      text("Key Bindings").size(24),
+      list,
+    ]
+    .spacing(8)
+    .into()
+  )
+}
+
+/// What the plugin accessed according to the mod's sandbox audit mode.
+///
+/// Only shown if the audit mode is enabled in the mod's config.
+fn audit_section<'a>(plugin: &Plugin, audit_report: &Option<AuditReport>) -> Option<Element<'a, Message>> {
+  let report = audit_report.as_ref().filter(|report| report.enabled)?;
+  let default_audit = PluginAudit::default();
+  let audit = report.plugins.get(&plugin.info.name).unwrap_or(&default_audit);
+
+  let mut content = Column::new()
+    .push(text("Audit").size(24))
+    .push(text("Sensitive functionality the plugin accessed since the game started."));
+
+  content = content.push(text("Dangerous functions").font(bold()));
+  if audit.dangerous_calls.is_empty() {
+    content = content.push(text("None"));
+  }
+  for (function, calls) in audit.dangerous_calls.iter() {
+    content = content.push(text(format!("- dangerous.{}: {} calls", function, calls)));
+  }
+
+  content = content.push(text("Memory addresses").font(bold()));
+  let addresses: Vec<String> = audit.addresses.iter().map(|address| format!("{:#010x}", address)).collect();
+  content = content.push(text(match addresses.is_empty() {
+    true => String::from("None"),
+    false => addresses.join(", "),
+  }).size(12));
+  if audit.addresses_truncated {
+    content = content.push(text("The plugin accessed more addresses than the report can hold.").style(theme::Text::Warn));
+  }
+
+  content = content.push(text("Files").font(bold()));
+  if audit.files.is_empty() {
+    content = content.push(text("None"));
+  }
+  for file in audit.files.iter() {
+    content = content.push(text(format!("- {}", file.display())));
+  }
+
+  let report_text = serde_json::to_string_pretty(audit).unwrap_or_default();
+
+  Some(
+    content
+      .push(button(text("Copy Report")).on_press(Message::CopyError(report_text)).style(Button::Default))
+      .spacing(8)
+      .into()
+  )
+}
+
+/// Buttons to manually call the plugin's lifecycle functions.
+///
+/// Only shown in developer mode.
+fn developer_tools<'a>(plugin: &Plugin, lifecycle_result: &Option<Result<String, String>>) -> Option<Element<'a, Message>> {
+  if !get_config().developer_mode {
+    return None;
+  }
+
+  let context = match &plugin.state {
+    PluginState::Loaded(context) => context,
+    _ => return Some(
+      column![
+        text("Lifecycle").size(24),
+        text("The plugin must be loaded to call its lifecycle functions."),
+      ].spacing(8).into()
+    ),
+  };
+
+  let mut buttons = Row::new();
+
+  for event in LifecycleEvent::ALL {
+    let label = match event {
+      LifecycleEvent::Update => String::from("onUpdate (once)"),
+      event => event.to_string(),
+    };
+
+    let mut event_button = button(text(label)).style(Button::Default);
+
+    if event.is_defined(context) {
+      event_button = event_button.on_press(Message::InvokeLifecycle(plugin.info.name.clone(), event));
+    }
+
+    buttons = buttons.push(event_button);
+  }
+
+  let result: Option<Element<'a, Message>> = match lifecycle_result {
+    Some(Ok(message)) => Some(text(message).into()),
+    Some(Err(e)) => Some(text(e).style(theme::Text::Danger).into()),
+    None => None,
+  };
+
+  Some(
+    Column::new()
+      .push(text("Lifecycle").size(24))
+      .push(text("Call the plugin's lifecycle functions without changing its state. Functions the plugin doesn't define are disabled."))
+      .push(buttons.spacing(8))
+      .push_maybe(result)
+      .spacing(8)
+      .into()
+  )
+}
+
 fn dependencies_list<'a>(dependencies: &Vec<PluginDependency>) -> Element<'a, Message> {
   let mut list: Vec<Element<'a, Message>> = Vec::new();
 
-  if dependencies.contains(&PluginDependency::Dangerous) {
+  if dependencies.iter().any(PluginDependency::is_dangerous) {
     list.push(text("This plugin has a dangerous dependency. This means it is effectively able to escape the usual safety features. Make sure to audit the plugin.").style(theme::Text::Warn).into())
   }
 
@@ -582,6 +1917,41 @@ fn dependencies_list<'a>(dependencies: &Vec<PluginDependency>) -> Element<'a, Me
   Column::<'a, Message>::from_vec(list).into()
 }
 
+fn permissions_list<'a>(info: &PluginInfo) -> Element<'a, Message> {
+  let permissions = info.granted_permissions();
+
+  if permissions.is_empty() {
+    return text("No permissions").into();
+  }
+
+  let mut list: Vec<Element<'a, Message>> = Vec::new();
+
+  if info.permissions.is_none() {
+    list.push(text("This plugin doesn't declare its permissions. It is granted all permissions of its dependencies.").style(theme::Text::Warn).into());
+  }
+
+  for permission in permissions.iter() {
+    list.push(
+      row![
+        text(permission.to_string()).font(bold()).width(Length::FillPortion(1)),
+        text(permission.description()).width(Length::FillPortion(3)),
+      ]
+      .spacing(8)
+      .into()
+    );
+  }
+
+  if permissions.contains(&PluginPermission::Network) && !info.hosts.is_empty() {
+    list.push(text(format!("Hosts: {}", info.hosts.join(", "))).into());
+  }
+
+  if permissions.contains(&PluginPermission::Exec) && !info.commands.is_empty() {
+    list.push(text(format!("Programs: {}", info.commands.join(", "))).into());
+  }
+
+  Column::<'a, Message>::from_vec(list).spacing(4).into()
+}
+
 async fn enable_plugin(name: String) -> Option<String> {
   let mut body = HashMap::new();
   body.insert("name", name.clone());
@@ -0,0 +1,206 @@
+use futuremod_data::input::InputRemap;
+use iced::{widget::{column, container, pick_list, row, text, Scrollable}, Alignment, Command, Length};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::{get_remappable_keys, set_input_remaps}, config::{self, get_config}, theme::{self, Button, Container, Text}, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  KeysResponse(Result<Vec<String>, String>),
+  Add,
+  Remove(usize),
+  FromSelected(usize, String),
+  ToSelected(usize, String),
+  Save,
+  SaveResponse(Result<Vec<InputRemap>, String>),
+  ClearError,
+}
+
+/// Remap that is being edited and might not have both keys yet.
+#[derive(Debug, Clone, Default)]
+struct Remap {
+  from: Option<String>,
+  to: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Remapping {
+  keys: Vec<String>,
+  remaps: Vec<Remap>,
+  changed: bool,
+  error: Option<String>,
+}
+
+impl Remapping {
+  pub fn new() -> (Self, Command<Message>) {
+    let remaps = get_config().input_remaps
+      .into_iter()
+      .map(|remap| Remap { from: Some(remap.from), to: Some(remap.to) })
+      .collect();
+
+    (
+      Remapping { keys: Vec::new(), remaps, changed: false, error: None },
+      Command::perform(get_remappable_keys(), Message::KeysResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::KeysResponse(response) => {
+        match response {
+          Ok(keys) => self.keys = keys,
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::Add => {
+        self.remaps.push(Remap::default());
+        self.changed = true;
+        Command::none()
+      },
+      Message::Remove(index) => {
+        if index < self.remaps.len() {
+          self.remaps.remove(index);
+          self.changed = true;
+        }
+
+        Command::none()
+      },
+      Message::FromSelected(index, key) => {
+        if let Some(remap) = self.remaps.get_mut(index) {
+          remap.from = Some(key);
+          self.changed = true;
+        }
+
+        Command::none()
+      },
+      Message::ToSelected(index, key) => {
+        if let Some(remap) = self.remaps.get_mut(index) {
+          remap.to = Some(key);
+          self.changed = true;
+        }
+
+        Command::none()
+      },
+      Message::Save => {
+        let mut remaps = Vec::new();
+
+        for remap in self.remaps.iter() {
+          match (&remap.from, &remap.to) {
+            (Some(from), Some(to)) => remaps.push(InputRemap { from: from.clone(), to: to.clone() }),
+            _ => {
+              self.error = Some(String::from("Select both keys of every remap"));
+              return Command::none();
+            },
+          }
+        }
+
+        // The mod validates the remaps. Only persist them if the mod accepted them.
+        Command::perform(
+          async move {
+            set_input_remaps(remaps.clone()).await?;
+            Ok(remaps)
+          },
+          Message::SaveResponse,
+        )
+      },
+      Message::SaveResponse(response) => {
+        let result = response.and_then(|remaps| {
+          config::set_input_remaps(remaps).map_err(|e| format!("Could not save the remaps: {}", e))
+        });
+
+        match result {
+          Ok(_) => self.changed = false,
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header(self.changed));
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let mut table = Column::new()
+      .push(
+        row![
+          text("Pressed Key").font(bold()).width(Length::FillPortion(1)),
+          text("Game Receives").font(bold()).width(Length::FillPortion(1)),
+          text("").width(32),
+        ].spacing(8)
+      );
+
+    for (index, remap) in self.remaps.iter().enumerate() {
+      table = table.push(
+        row![
+          pick_list(self.keys.as_slice(), remap.from.clone(), move |key| Message::FromSelected(index, key)).width(Length::FillPortion(1)),
+          pick_list(self.keys.as_slice(), remap.to.clone(), move |key| Message::ToSelected(index, key)).width(Length::FillPortion(1)),
+          button(icon_with_style(BootstrapIcon::Trash, Text::Danger)).on_press(Message::Remove(index)).style(Button::Text).width(32),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+      );
+    }
+
+    let body = column![
+      text("Replace keys with other keys while the game has the focus, including keys the game doesn't allow to rebind. The remaps are shared by all profiles and apply immediately when saved."),
+      Scrollable::new(table.spacing(8).width(Length::Fill)).height(Length::Fill),
+      button("Add Remap").on_press(Message::Add),
+    ]
+    .spacing(16);
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn header<'a>(changed: bool) -> Element<'a, Message> {
+  let save = match changed {
+    true => button("Save").on_press(Message::Save).style(Button::Primary),
+    false => button("Save").style(Button::Primary),
+  };
+
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Input Remapping").size(24)).width(Length::Fill),
+    text(match changed {
+      true => "Unsaved changes",
+      false => "",
+    }).style(theme::Text::Warn),
+    save,
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use chrono::{Local, TimeZone};
+use futuremod_data::screenshot::Screenshot;
+use iced::{widget::{column, container, image, row, text, Scrollable}, Alignment, Command, ContentFit, Length};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::{get_screenshot_image, get_screenshots}, theme::{Button, Container, Text}, widget::{bold, button, icon, icon_with_style, Column, Element, Row}};
+
+/// Only the newest screenshots are shown, since every shown screenshot is loaded from the mod.
+const MAX_SHOWN: usize = 60;
+
+const THUMBNAILS_PER_ROW: usize = 4;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  Refresh,
+  ScreenshotsResponse(Result<Vec<Screenshot>, String>),
+  ImageResponse(String, Result<Vec<u8>, String>),
+  Select(String),
+  CloseSelected,
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Screenshots {
+  screenshots: Option<Vec<Screenshot>>,
+  images: HashMap<String, image::Handle>,
+  selected: Option<String>,
+  error: Option<String>,
+}
+
+impl Screenshots {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Screenshots { screenshots: None, images: HashMap::new(), selected: None, error: None },
+      Command::perform(get_screenshots(), Message::ScreenshotsResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::Refresh => Command::perform(get_screenshots(), Message::ScreenshotsResponse),
+      Message::ScreenshotsResponse(response) => match response {
+        Ok(screenshots) => {
+          // Only load screenshots that weren't loaded before
+          let commands: Vec<Command<Message>> = screenshots
+            .iter()
+            .take(MAX_SHOWN)
+            .filter(|screenshot| !self.images.contains_key(&screenshot.path))
+            .map(|screenshot| {
+              let path = screenshot.path.clone();
+              Command::perform(get_screenshot_image(path.clone()), move |response| Message::ImageResponse(path.clone(), response))
+            })
+            .collect();
+
+          self.screenshots = Some(screenshots);
+
+          Command::batch(commands)
+        },
+        Err(e) => {
+          self.error = Some(e);
+          Command::none()
+        },
+      },
+      Message::ImageResponse(path, response) => {
+        match response {
+          Ok(bytes) => {
+            self.images.insert(path, image::Handle::from_memory(bytes));
+          },
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::Select(path) => {
+        self.selected = Some(path);
+        Command::none()
+      },
+      Message::CloseSelected => {
+        self.selected = None;
+        Command::none()
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header());
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let body: Element<Message> = match &self.screenshots {
+      None => text("Loading...").into(),
+      Some(screenshots) if screenshots.is_empty() => text("No screenshots yet. Press the screenshot key in the game, F12 by default.").into(),
+      Some(screenshots) => match self.selected.as_ref().and_then(|path| screenshots.iter().find(|screenshot| &screenshot.path == path)) {
+        Some(screenshot) => self.selected_view(screenshot),
+        None => self.gallery(screenshots),
+      },
+    };
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+
+  fn gallery(&self, screenshots: &[Screenshot]) -> Element<Message> {
+    let shown = &screenshots[..screenshots.len().min(MAX_SHOWN)];
+
+    // Group by folder, keeping the order of the newest screenshot of each folder
+    let mut folders: Vec<(&str, Vec<&Screenshot>)> = Vec::new();
+    for screenshot in shown {
+      match folders.iter_mut().find(|(folder, _)| *folder == screenshot.folder) {
+        Some((_, folder_screenshots)) => folder_screenshots.push(screenshot),
+        None => folders.push((&screenshot.folder, vec![screenshot])),
+      }
+    }
+
+    let mut gallery = Column::new().spacing(16);
+
+    if screenshots.len() > MAX_SHOWN {
+      gallery = gallery.push(text(format!("Showing the {} newest of {} screenshots.", MAX_SHOWN, screenshots.len())).size(12));
+    }
+
+    for (folder, folder_screenshots) in folders {
+      let mut rows = Column::new().spacing(8);
+
+      for chunk in folder_screenshots.chunks(THUMBNAILS_PER_ROW) {
+        let mut thumbnails = Row::new().spacing(8);
+
+        for screenshot in chunk {
+          thumbnails = thumbnails.push(
+            button(
+              column![
+                self.image(&screenshot.path, 90),
+                text(format_time(screenshot.taken_at)).size(12),
+              ].spacing(4)
+            )
+            .on_press(Message::Select(screenshot.path.clone()))
+            .style(Button::Text)
+            .width(Length::FillPortion(1))
+          );
+        }
+
+        // Keep thumbnails of incomplete rows at the same size
+        for _ in chunk.len()..THUMBNAILS_PER_ROW {
+          thumbnails = thumbnails.push(container(text("")).width(Length::FillPortion(1)));
+        }
+
+        rows = rows.push(thumbnails);
+      }
+
+      let name = match folder.is_empty() {
+        true => "Other",
+        false => folder,
+      };
+
+      gallery = gallery.push(column![text(name).font(bold()).size(18), rows].spacing(8));
+    }
+
+    Scrollable::new(gallery.width(Length::Fill)).height(Length::Fill).into()
+  }
+
+  fn selected_view(&self, screenshot: &Screenshot) -> Element<Message> {
+    column![
+      row![
+        button(icon(BootstrapIcon::X)).on_press(Message::CloseSelected).style(Button::Text),
+        text(&screenshot.path).font(bold()).width(Length::Fill),
+        text(format!("{}  |  {} KB", format_time(screenshot.taken_at), screenshot.size / 1024)).size(12),
+      ]
+      .spacing(8)
+      .align_items(Alignment::Center),
+      container(self.image(&screenshot.path, 0))
+        .width(Length::Fill)
+        .height(Length::Fill),
+    ]
+    .spacing(8)
+    .into()
+  }
+
+  /// Image of the screenshot, or a placeholder while it loads.
+  ///
+  /// A height of `0` fills the available space.
+  fn image(&self, path: &str, height: u16) -> Element<Message> {
+    let height = match height {
+      0 => Length::Fill,
+      height => Length::Fixed(height as f32),
+    };
+
+    match self.images.get(path) {
+      Some(handle) => image(handle.clone())
+        .content_fit(ContentFit::Contain)
+        .width(Length::Fill)
+        .height(height)
+        .into(),
+      None => container(text("Loading...").size(12))
+        .center_x()
+        .center_y()
+        .width(Length::Fill)
+        .height(height)
+        .into(),
+    }
+  }
+}
+
+fn header<'a>() -> Element<'a, Message> {
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Screenshots").size(24)).width(Length::Fill),
+    button(icon(BootstrapIcon::ArrowClockwise)).style(Button::Text).on_press(Message::Refresh),
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
+
+fn format_time(timestamp: u64) -> String {
+  match Local.timestamp_opt(timestamp as i64, 0).single() {
+    Some(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+    None => String::new(),
+  }
+}
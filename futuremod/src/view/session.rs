@@ -0,0 +1,444 @@
+use std::{fs, io::Read, path::PathBuf};
+
+use chrono::Local;
+use futuremod_data::session::{GameSample, RecordingState, SessionRecording, TimelineEntry, TimelineEvent, SESSION_BUNDLE_FILE_NAME};
+use iced::{widget::{column, container, row, text, Scrollable}, Alignment, Command, Length};
+use iced_aw::BootstrapIcon;
+use log::warn;
+use rfd::FileDialog;
+
+use crate::{api::{download_session_bundle, get_session_recording, start_session_recording, stop_session_recording}, theme::{self, Button, Container, Text}, util::wait_for_ms, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+/// Interval in which the recording's state is polled while the session is recorded.
+const POLL_INTERVAL: u64 = 1000;
+
+/// Interval in which the playback advances.
+const TICK_INTERVAL: u64 = 100;
+
+/// Speeds the playback can be switched between.
+const SPEEDS: [f64; 4] = [1.0, 2.0, 4.0, 8.0];
+
+/// Number of the latest timeline entries shown at most, so long recordings stay responsive.
+const MAX_SHOWN_ENTRIES: usize = 200;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  StartRecording,
+  StopRecording,
+  Poll,
+  StateResponse(Result<RecordingState, String>),
+  ActionResponse(Result<(), String>),
+  SaveBundle,
+  SaveResponse(Result<PathBuf, String>),
+  OpenBundle,
+  OpenResponse(Result<(PathBuf, SessionRecording), String>),
+  Play,
+  Pause,
+  Restart,
+  StepBack,
+  StepForward,
+  ChangeSpeed,
+  /// Advance the playback started with the given id.
+  Tick(u64),
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Session {
+  state: Option<RecordingState>,
+  error: Option<String>,
+  info: Option<String>,
+  playback: Option<Playback>,
+}
+
+/// Playback of a recording that was loaded from a bundle.
+#[derive(Debug, Clone)]
+struct Playback {
+  path: PathBuf,
+  recording: SessionRecording,
+
+  /// Seconds into the recording.
+  position: f64,
+  playing: bool,
+  speed: usize,
+
+  /// Id of the running playback, so ticks of a playback that was paused are ignored.
+  id: u64,
+}
+
+impl Playback {
+  /// Number of entries that happened until the current position.
+  fn shown(&self) -> usize {
+    self.recording.entries.partition_point(|entry| entry.time <= self.position)
+  }
+
+  fn latest_sample(&self) -> Option<(&TimelineEntry, &GameSample)> {
+    self.recording.entries[..self.shown()]
+      .iter()
+      .rev()
+      .find_map(|entry| match &entry.event {
+        TimelineEvent::Sample(sample) => Some((entry, sample)),
+        _ => None,
+      })
+  }
+
+  fn play(&mut self) -> Command<Message> {
+    if self.position >= self.recording.duration {
+      self.position = 0.0;
+    }
+
+    self.playing = true;
+    self.id += 1;
+
+    let id = self.id;
+    Command::perform(wait_for_ms(TICK_INTERVAL), move |_| Message::Tick(id))
+  }
+
+  fn pause(&mut self) {
+    self.playing = false;
+    self.id += 1;
+  }
+}
+
+impl Session {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Session { state: None, error: None, info: None, playback: None },
+      Command::perform(get_session_recording(), Message::StateResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::StartRecording => Command::perform(start_session_recording(), Message::ActionResponse),
+      Message::StopRecording => Command::perform(stop_session_recording(), Message::ActionResponse),
+      Message::Poll => Command::perform(get_session_recording(), Message::StateResponse),
+      Message::ActionResponse(response) => {
+        if let Err(e) = response {
+          self.error = Some(e);
+        }
+
+        Command::perform(get_session_recording(), Message::StateResponse)
+      },
+      Message::StateResponse(response) => match response {
+        Ok(state) => {
+          let is_recording = state.recording;
+          self.state = Some(state);
+
+          match is_recording {
+            true => Command::perform(wait_for_ms(POLL_INTERVAL), |_| Message::Poll),
+            false => Command::none(),
+          }
+        },
+        Err(e) => {
+          self.error = Some(e);
+          Command::none()
+        },
+      },
+      Message::SaveBundle => {
+        let destination = match FileDialog::new()
+          .set_title("Save the Session Recording")
+          .set_file_name(&format!("futuremod-session-{}.zip", Local::now().format("%Y-%m-%d-%H%M%S")))
+          .add_filter("Session Recording", &["zip"])
+          .save_file() {
+            Some(v) => v,
+            None => return Command::none(),
+        };
+
+        Command::perform(download_session_bundle(destination), Message::SaveResponse)
+      },
+      Message::SaveResponse(response) => {
+        match response {
+          Ok(path) => self.info = Some(format!("Saved the recording to '{}', attach it to your bug report", path.display())),
+          Err(e) => {
+            warn!("Could not save the session recording: {}", e);
+            self.error = Some(e);
+          },
+        }
+
+        Command::none()
+      },
+      Message::OpenBundle => {
+        let path = match FileDialog::new()
+          .set_title("Open a Session Recording")
+          .add_filter("Session Recording", &["zip"])
+          .pick_file() {
+            Some(v) => v,
+            None => return Command::none(),
+        };
+
+        Command::perform(read_bundle(path), Message::OpenResponse)
+      },
+      Message::OpenResponse(response) => {
+        match response {
+          Ok((path, recording)) => {
+            self.info = None;
+            self.playback = Some(Playback { path, recording, position: 0.0, playing: false, speed: 0, id: 0 });
+          },
+          Err(e) => {
+            warn!("Could not open the session recording: {}", e);
+            self.error = Some(e);
+          },
+        }
+
+        Command::none()
+      },
+      Message::Play => match &mut self.playback {
+        Some(playback) => playback.play(),
+        None => Command::none(),
+      },
+      Message::Pause => {
+        if let Some(playback) = &mut self.playback {
+          playback.pause();
+        }
+
+        Command::none()
+      },
+      Message::Restart => {
+        if let Some(playback) = &mut self.playback {
+          playback.position = 0.0;
+        }
+
+        Command::none()
+      },
+      Message::StepBack => {
+        if let Some(playback) = &mut self.playback {
+          playback.pause();
+
+          // Hide the last shown entry
+          let shown = playback.shown();
+          playback.position = match shown {
+            0 | 1 => 0.0,
+            _ => playback.recording.entries[shown - 2].time,
+          };
+        }
+
+        Command::none()
+      },
+      Message::StepForward => {
+        if let Some(playback) = &mut self.playback {
+          playback.pause();
+
+          playback.position = match playback.recording.entries.get(playback.shown()) {
+            Some(entry) => entry.time,
+            None => playback.recording.duration,
+          };
+        }
+
+        Command::none()
+      },
+      Message::ChangeSpeed => {
+        if let Some(playback) = &mut self.playback {
+          playback.speed = (playback.speed + 1) % SPEEDS.len();
+        }
+
+        Command::none()
+      },
+      Message::Tick(id) => {
+        let playback = match &mut self.playback {
+          Some(playback) if playback.playing && playback.id == id => playback,
+          _ => return Command::none(),
+        };
+
+        playback.position += TICK_INTERVAL as f64 / 1000.0 * SPEEDS[playback.speed];
+
+        if playback.position >= playback.recording.duration {
+          playback.position = playback.recording.duration;
+          playback.pause();
+
+          return Command::none();
+        }
+
+        Command::perform(wait_for_ms(TICK_INTERVAL), move |_| Message::Tick(id))
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header(&self.state));
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let status = match &self.state {
+      None => String::from("Loading..."),
+      Some(state) if state.recording => format!("Recording the session... {} entries recorded so far", state.entries),
+      Some(state) if state.available => format!("The last recording has {} entries. Save it to attach it to a bug report.", state.entries),
+      Some(_) => String::from("Record the session to capture the logs, what happened in the engine, and samples of the game's state. Save the recording and attach it to your bug report."),
+    };
+
+    let mut body = Column::new()
+      .push(text(status))
+      .push_maybe(self.info.as_ref().map(|info| text(info)))
+      .spacing(16);
+
+    body = body.push(match &self.playback {
+      Some(playback) => playback_view(playback),
+      None => text("Open a saved recording to play it back.").into(),
+    });
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+/// Read the recording from a session bundle.
+async fn read_bundle(path: PathBuf) -> Result<(PathBuf, SessionRecording), String> {
+  let file = fs::File::open(&path).map_err(|e| format!("Could not open '{}': {}", path.display(), e))?;
+  let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("'{}' is not a session recording: {}", path.display(), e))?;
+
+  let mut content = String::new();
+  archive
+    .by_name(SESSION_BUNDLE_FILE_NAME)
+    .map_err(|e| format!("'{}' is not a session recording: {}", path.display(), e))?
+    .read_to_string(&mut content)
+    .map_err(|e| format!("Could not read '{}': {}", path.display(), e))?;
+
+  let recording = serde_json::from_str(&content).map_err(|e| format!("Could not parse the session recording: {}", e))?;
+
+  Ok((path, recording))
+}
+
+fn header<'a>(state: &Option<RecordingState>) -> Element<'a, Message> {
+  let record = match state {
+    Some(state) if state.recording => button("Stop Recording").on_press(Message::StopRecording).style(Button::Destructive),
+    Some(_) => button("Start Recording").on_press(Message::StartRecording).style(Button::Primary),
+    None => button("Start Recording").style(Button::Primary),
+  };
+
+  let save = match state {
+    Some(state) if state.available => button("Save Recording").on_press(Message::SaveBundle),
+    _ => button("Save Recording"),
+  };
+
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Session Recording").size(24)).width(Length::Fill),
+    button("Open Recording").on_press(Message::OpenBundle),
+    save,
+    record,
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
+
+fn playback_view<'a>(playback: &Playback) -> Element<'a, Message> {
+  let recording = &playback.recording;
+
+  let play = match playback.playing {
+    true => button(icon(BootstrapIcon::PauseFill)).on_press(Message::Pause),
+    false => button(icon(BootstrapIcon::PlayFill)).on_press(Message::Play),
+  };
+
+  let controls = row![
+    button(icon(BootstrapIcon::ArrowCounterclockwise)).on_press(Message::Restart),
+    button(icon(BootstrapIcon::SkipStartFill)).on_press(Message::StepBack),
+    play,
+    button(icon(BootstrapIcon::SkipEndFill)).on_press(Message::StepForward),
+    button(text(format!("{}x", SPEEDS[playback.speed]))).on_press(Message::ChangeSpeed),
+    text(format!("{:.1}s / {:.1}s", playback.position, recording.duration)),
+  ]
+  .spacing(8)
+  .align_items(Alignment::Center);
+
+  let shown = playback.shown();
+  let mut timeline = Column::new().spacing(4).width(Length::Fill);
+
+  // Latest entries first, so the current ones don't have to be scrolled to
+  for entry in recording.entries[..shown].iter().rev().filter(|entry| !matches!(entry.event, TimelineEvent::Sample(_))).take(MAX_SHOWN_ENTRIES) {
+    timeline = timeline.push(timeline_entry(entry));
+  }
+
+  let sample: Element<Message> = match playback.latest_sample() {
+    Some((entry, sample)) => {
+      let mut players = Column::new()
+        .push(text("Game State").font(bold()))
+        .push(text(format!("Frame {}", entry.frame)))
+        .push(text(format!("{} entities", sample.entities)))
+        .spacing(8);
+
+      for (index, player) in sample.players.iter().enumerate() {
+        players = players.push(
+          column![
+            text(format!("Player {}", index + 1)).font(bold()),
+            text(format!("Health: {}", player.health)),
+            text(format!("Position: {}, {}, {}", player.position[0], player.position[1], player.position[2])),
+          ].spacing(4)
+        );
+      }
+
+      players.into()
+    },
+    None => text("No sample of the game's state yet").into(),
+  };
+
+  column![
+    text(format!("'{}' was recorded at {} with API version {}", playback.path.display(), recording.started_at, recording.api_version)),
+  ]
+  .push_maybe(recording.truncated.then(|| text("The recording reached its size limit, later entries are missing.").style(theme::Text::Warn)))
+  .push(controls)
+  .push(
+    row![
+      Scrollable::new(timeline).width(Length::FillPortion(3)).height(Length::Fill),
+      container(sample).width(Length::FillPortion(1)),
+    ].spacing(16)
+  )
+  .spacing(16)
+  .into()
+}
+
+fn timeline_entry<'a>(entry: &TimelineEntry) -> Element<'a, Message> {
+  let time = text(format!("{:>8.2}s", entry.time)).width(80);
+
+  match &entry.event {
+    TimelineEvent::Log { level, target, message, plugin } => {
+      let style = match level.as_str() {
+        "ERROR" => Text::Danger,
+        "WARN" => Text::Warn,
+        _ => Text::Default,
+      };
+
+      row![
+        time,
+        text(level).width(60).style(style),
+        text(format!("{}: {}", plugin.as_ref().unwrap_or(target), message)).width(Length::Fill),
+      ].spacing(8).into()
+    },
+    TimelineEvent::Engine { name, detail } => row![
+      time,
+      text("EVENT").width(60).font(bold()),
+      text(match detail {
+        Some(detail) => format!("{}: {}", name, detail),
+        None => name.clone(),
+      }).font(bold()).width(Length::Fill),
+    ].spacing(8).into(),
+    TimelineEvent::Sample(_) => Column::new().into(),
+  }
+}
@@ -0,0 +1,192 @@
+use chrono::{Local, TimeZone};
+use futuremod_data::statistics::{PluginUsage, UsageStatistics};
+use iced::{alignment::Horizontal, widget::{column, container, row, text, Scrollable}, Alignment, Command, Length};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::{get_statistics, reset_statistics}, theme::{Button, Container, Text}, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  Refresh,
+  Reset,
+  StatisticsResponse(Result<UsageStatistics, String>),
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Statistics {
+  statistics: Option<UsageStatistics>,
+  error: Option<String>,
+}
+
+impl Statistics {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Statistics { statistics: None, error: None },
+      Command::perform(get_statistics(), Message::StatisticsResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::Refresh => Command::perform(get_statistics(), Message::StatisticsResponse),
+      Message::Reset => Command::perform(reset_statistics(), Message::StatisticsResponse),
+      Message::StatisticsResponse(response) => {
+        match response {
+          Ok(statistics) => self.statistics = Some(statistics),
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header(&self.statistics));
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let body: Element<Message> = match &self.statistics {
+      None => text("Loading...").into(),
+      Some(statistics) => statistics_view(statistics),
+    };
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn header<'a>(statistics: &Option<UsageStatistics>) -> Element<'a, Message> {
+  let reset = match statistics {
+    Some(_) => button("Reset").on_press(Message::Reset).style(Button::Destructive),
+    None => button("Reset").style(Button::Destructive),
+  };
+
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Statistics").size(24)).width(Length::Fill),
+    button(icon(BootstrapIcon::ArrowClockwise)).style(Button::Text).on_press(Message::Refresh),
+    reset,
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
+
+/// Format the seconds as hours and minutes, e.g., `3h 12m`.
+fn format_duration(seconds: u64) -> String {
+  match (seconds / 3600, (seconds % 3600) / 60) {
+    (0, 0) => format!("{}s", seconds),
+    (0, minutes) => format!("{}m", minutes),
+    (hours, minutes) => format!("{}h {}m", hours, minutes),
+  }
+}
+
+fn format_date(timestamp: u64) -> String {
+  match Local.timestamp_opt(timestamp as i64, 0).single() {
+    Some(time) => time.format("%Y-%m-%d").to_string(),
+    None => String::new(),
+  }
+}
+
+/// Box with a statistic's value and its label.
+fn tile<'a>(label: &str, value: String) -> Element<'a, Message> {
+  container(
+    column![
+      text(value).size(24),
+      text(label.to_string()).size(14),
+    ]
+    .spacing(4)
+  )
+  .padding(16)
+  .width(Length::FillPortion(1))
+  .style(Container::Box)
+  .into()
+}
+
+fn statistics_view<'a>(statistics: &UsageStatistics) -> Element<'a, Message> {
+  let intro = match statistics.since {
+    Some(since) => format!("Counted on this computer since {}. The statistics are never sent anywhere.", format_date(since)),
+    None => String::from("Counted on this computer only. The statistics are never sent anywhere."),
+  };
+
+  let tiles = row![
+    tile("Playtime with FutureMod", format_duration(statistics.playtime)),
+    tile("Sessions", statistics.sessions.to_string()),
+    tile("Missions started", statistics.missions_started.to_string()),
+    tile("Missions completed", statistics.missions_completed.to_string()),
+    tile("Time in missions", format_duration(statistics.mission_time)),
+  ]
+  .spacing(16);
+
+  let plugins: Element<Message> = match statistics.plugins.is_empty() {
+    true => text("No plugin was enabled yet.").into(),
+    false => plugins_view(statistics),
+  };
+
+  column![
+    text(intro),
+    tiles,
+    text("Plugins").size(20),
+    plugins,
+  ]
+  .spacing(16)
+  .into()
+}
+
+/// Table of how long each plugin was enabled, the longest first.
+fn plugins_view<'a>(statistics: &UsageStatistics) -> Element<'a, Message> {
+  let mut plugins: Vec<(&String, &PluginUsage)> = statistics.plugins.iter().collect();
+  plugins.sort_by(|(a_name, a), (b_name, b)| b.enabled_time.cmp(&a.enabled_time).then(a_name.cmp(b_name)));
+
+  let mut table = Column::new()
+    .push(
+      row![
+        text("Plugin").font(bold()).width(Length::FillPortion(3)),
+        text("Enabled").font(bold()).width(Length::FillPortion(1)).horizontal_alignment(Horizontal::Right),
+        text("Times Enabled").font(bold()).width(Length::FillPortion(1)).horizontal_alignment(Horizontal::Right),
+      ].spacing(8)
+    )
+    .spacing(8)
+    .width(Length::Fill);
+
+  for (name, usage) in plugins {
+    table = table.push(
+      row![
+        text(name.clone()).width(Length::FillPortion(3)),
+        text(format_duration(usage.enabled_time)).width(Length::FillPortion(1)).horizontal_alignment(Horizontal::Right),
+        text(usage.enabled_count.to_string()).width(Length::FillPortion(1)).horizontal_alignment(Horizontal::Right),
+      ].spacing(8)
+    );
+  }
+
+  Scrollable::new(table).height(Length::Fill).into()
+}
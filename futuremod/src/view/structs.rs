@@ -0,0 +1,235 @@
+use std::time::Duration;
+
+use futuremod_data::structs::{StructDefinition, StructFieldValue};
+use iced::{widget::{column, container, pick_list, row, text, text_input, Scrollable}, Alignment, Command, Length, Subscription};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::{get_struct_definitions, read_struct}, theme::{self, Button, Container, Text}, util::parse_address, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+/// Interval in which the inspected instance is read again.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  DefinitionsResponse(Result<Vec<StructDefinition>, String>),
+  DefinitionSelected(String),
+  AddressChanged(String),
+  Inspect,
+  Stop,
+  Refresh,
+  ReadResponse(Result<Vec<StructFieldValue>, String>),
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Structs {
+  definitions: Vec<StructDefinition>,
+  selected: Option<String>,
+  address: String,
+  /// Definition and address of the instance that is read periodically.
+  inspected: Option<(String, u32)>,
+  values: Vec<StructFieldValue>,
+  /// Whether a read is pending, to not queue reads if the mod responds slowly.
+  is_reading: bool,
+  error: Option<String>,
+}
+
+impl Structs {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Structs {
+        definitions: Vec::new(),
+        selected: None,
+        address: String::new(),
+        inspected: None,
+        values: Vec::new(),
+        is_reading: false,
+        error: None,
+      },
+      Command::perform(get_struct_definitions(), Message::DefinitionsResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::DefinitionsResponse(response) => {
+        match response {
+          Ok(definitions) => self.definitions = definitions,
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::DefinitionSelected(name) => {
+        self.selected = Some(name);
+        Command::none()
+      },
+      Message::AddressChanged(address) => {
+        self.address = address;
+        Command::none()
+      },
+      Message::Inspect => {
+        let address = match parse_address(&self.address) {
+          Some(address) => address,
+          None => {
+            self.error = Some(format!("'{}' is not an address, enter it as hex, e.g., 0x4A3F10, or as decimal number", self.address));
+            return Command::none();
+          },
+        };
+
+        let definition = match &self.selected {
+          Some(definition) => definition.clone(),
+          None => {
+            self.error = Some(String::from("Select the struct to inspect"));
+            return Command::none();
+          },
+        };
+
+        self.inspected = Some((definition, address));
+        self.values.clear();
+        self.read()
+      },
+      Message::Stop => {
+        self.inspected = None;
+        Command::none()
+      },
+      Message::Refresh => self.read(),
+      Message::ReadResponse(response) => {
+        self.is_reading = false;
+
+        match response {
+          Ok(values) => self.values = values,
+          Err(e) => {
+            // Stop reading, the error would repeat
+            self.inspected = None;
+            self.error = Some(e);
+          },
+        }
+
+        Command::none()
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  fn read(&mut self) -> Command<Message> {
+    let (definition, address) = match &self.inspected {
+      Some(inspected) if !self.is_reading => inspected.clone(),
+      _ => return Command::none(),
+    };
+
+    self.is_reading = true;
+    Command::perform(read_struct(definition, address), Message::ReadResponse)
+  }
+
+  /// Read the inspected instance periodically.
+  pub fn subscription(&self) -> Subscription<Message> {
+    match self.inspected {
+      Some(_) => iced::time::every(REFRESH_INTERVAL).map(|_| Message::Refresh),
+      None => Subscription::none(),
+    }
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header());
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let names: Vec<String> = self.definitions.iter().map(|definition| definition.name.clone()).collect();
+    let description = self.selected
+      .as_ref()
+      .and_then(|selected| self.definitions.iter().find(|definition| &definition.name == selected))
+      .map(|definition| match &definition.plugin {
+        Some(plugin) => format!("{} Registered by plugin {}.", definition.description, plugin),
+        None => definition.description.clone(),
+      })
+      .unwrap_or_default();
+
+    let action = match (&self.inspected, self.selected.is_some() && !self.address.trim().is_empty()) {
+      (Some(_), _) => button("Stop").on_press(Message::Stop).style(Button::Destructive),
+      (None, true) => button("Inspect").on_press(Message::Inspect).style(Button::Primary),
+      (None, false) => button("Inspect").style(Button::Primary),
+    };
+
+    let mut table = Column::new()
+      .push(
+        row![
+          text("Offset").font(bold()).width(Length::FillPortion(1)),
+          text("Field").font(bold()).width(Length::FillPortion(2)),
+          text("Type").font(bold()).width(Length::FillPortion(1)),
+          text("Value").font(bold()).width(Length::FillPortion(2)),
+          text("Bytes").font(bold()).width(Length::FillPortion(2)),
+        ].spacing(8)
+      );
+
+    for field in self.values.iter() {
+      let value: Element<Message> = match &field.value {
+        Some(value) => text(value).into(),
+        None => text("Not readable").style(theme::Text::Warn).into(),
+      };
+
+      table = table.push(
+        row![
+          text(format!("{:#06x}", field.offset)).width(Length::FillPortion(1)),
+          text(&field.name).width(Length::FillPortion(2)),
+          text(&field.field_type).width(Length::FillPortion(1)),
+          container(value).width(Length::FillPortion(2)),
+          text(field.hex.clone().unwrap_or_default()).width(Length::FillPortion(2)),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+      );
+    }
+
+    let body = column![
+      text("Inspect an instance of a game struct at an address. Its fields are read twice per second. Plugins add their structs with the dangerous library's `registerStruct` function."),
+      row![
+        column![text("Struct").font(bold()), pick_list(names, self.selected.clone(), Message::DefinitionSelected)].spacing(4).width(Length::FillPortion(1)),
+        column![text("Address").font(bold()), text_input("0x4A3F10", &self.address).on_input(Message::AddressChanged).on_submit(Message::Inspect)].spacing(4).width(Length::FillPortion(1)),
+      ].spacing(8),
+      text(description),
+      action,
+      Scrollable::new(table.spacing(8).width(Length::Fill)).height(Length::Fill),
+    ]
+    .spacing(16);
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn header<'a>() -> Element<'a, Message> {
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Structs").size(24)).width(Length::Fill),
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
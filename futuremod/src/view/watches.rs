@@ -0,0 +1,186 @@
+use futuremod_data::watch::WatchValue;
+use iced::{widget::{column, container, row, text, text_input, Scrollable}, Alignment, Command, Length, Subscription};
+use iced_aw::BootstrapIcon;
+
+use crate::{api::{get_watches, set_watches}, config::get_config, theme::{self, Button, Container, Text}, watch_subscriber, widget::{bold, button, icon, icon_with_style, Column, Element}};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+  GoBack,
+  WatchesResponse(Result<Vec<String>, String>),
+  InputChanged(String),
+  Add,
+  Remove(usize),
+  SetResponse(Result<Vec<String>, String>),
+  Event(watch_subscriber::Event),
+  ClearError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Watches {
+  expressions: Vec<String>,
+  /// Values of the latest evaluation the mod streamed.
+  values: Vec<WatchValue>,
+  input: String,
+  connected: bool,
+  error: Option<String>,
+}
+
+impl Watches {
+  pub fn new() -> (Self, Command<Message>) {
+    (
+      Watches { expressions: Vec::new(), values: Vec::new(), input: String::new(), connected: false, error: None },
+      Command::perform(get_watches(), Message::WatchesResponse),
+    )
+  }
+
+  pub fn update(&mut self, message: Message) -> Command<Message> {
+    match message {
+      Message::WatchesResponse(response) | Message::SetResponse(response) => {
+        match response {
+          Ok(expressions) => self.expressions = expressions,
+          Err(e) => self.error = Some(e),
+        }
+
+        Command::none()
+      },
+      Message::InputChanged(input) => {
+        self.input = input;
+        Command::none()
+      },
+      Message::Add => {
+        let expression = self.input.trim().to_string();
+        if expression.is_empty() {
+          return Command::none();
+        }
+
+        self.input.clear();
+
+        let mut expressions = self.expressions.clone();
+        expressions.push(expression);
+
+        Command::perform(set_watches(expressions), Message::SetResponse)
+      },
+      Message::Remove(index) => {
+        if index >= self.expressions.len() {
+          return Command::none();
+        }
+
+        let mut expressions = self.expressions.clone();
+        expressions.remove(index);
+
+        Command::perform(set_watches(expressions), Message::SetResponse)
+      },
+      Message::Event(event) => {
+        match event {
+          watch_subscriber::Event::Connected => self.connected = true,
+          watch_subscriber::Event::Disconnected => self.connected = false,
+          watch_subscriber::Event::Values(values) => self.values = values,
+        }
+
+        Command::none()
+      },
+      Message::ClearError => {
+        self.error = None;
+        Command::none()
+      },
+      Message::GoBack => Command::none(),
+    }
+  }
+
+  /// Receive the values of the watch expressions while the view is shown.
+  pub fn subscription(&self) -> Subscription<Message> {
+    watch_subscriber::connect(get_config().mod_address).map(Message::Event)
+  }
+
+  pub fn view(&self) -> Element<Message> {
+    let mut content = Column::new().push(header(self.connected));
+
+    if let Some(err) = &self.error {
+      content = content.push(
+        container(
+          container(
+            row![
+              text(err).width(Length::Fill),
+              button(icon_with_style(BootstrapIcon::X, Text::Danger)).on_press(Message::ClearError).style(Button::Text)
+            ].align_items(Alignment::Center),
+          )
+          .padding(16)
+          .style(Container::Danger)
+        )
+        .padding(16)
+      );
+    }
+
+    let mut table = Column::new()
+      .push(
+        row![
+          text("Expression").font(bold()).width(Length::FillPortion(1)),
+          text("Value").font(bold()).width(Length::FillPortion(2)),
+          text("").width(32),
+        ].spacing(8)
+      );
+
+    for (index, expression) in self.expressions.iter().enumerate() {
+      let value = self.values.iter().find(|value| &value.expression == expression);
+
+      let value: Element<Message> = match value {
+        Some(WatchValue { value: Some(value), .. }) => text(value).into(),
+        Some(WatchValue { error: Some(error), .. }) => text(error).style(theme::Text::Danger).into(),
+        _ => text("Not evaluated yet").style(theme::Text::Warn).into(),
+      };
+
+      table = table.push(
+        row![
+          text(expression).width(Length::FillPortion(1)),
+          container(value).width(Length::FillPortion(2)),
+          button(icon_with_style(BootstrapIcon::Trash, Text::Danger)).on_press(Message::Remove(index)).style(Button::Text).width(32),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center)
+      );
+    }
+
+    let add = match self.input.trim().is_empty() {
+      true => button("Watch").style(Button::Primary),
+      false => button("Watch").on_press(Message::Add).style(Button::Primary),
+    };
+
+    let body = column![
+      text("Evaluate Lua expressions every second while a mission runs, e.g., to monitor game values without writing a plugin. Expressions can use the game, dangerous, structs, input and matrix libraries as globals."),
+      row![
+        text_input("game.getPlayer(0).health", &self.input)
+          .on_input(Message::InputChanged)
+          .on_submit(Message::Add)
+          .width(Length::Fill),
+        add,
+      ].spacing(8),
+      Scrollable::new(table.spacing(8).width(Length::Fill)).height(Length::Fill),
+    ]
+    .spacing(16);
+
+    content
+      .push(
+        container(body)
+          .padding(16)
+          .width(Length::Fill)
+          .height(Length::Fill)
+      )
+      .into()
+  }
+}
+
+fn header<'a>(connected: bool) -> Element<'a, Message> {
+  row![
+    button(icon(BootstrapIcon::ArrowLeft)).style(Button::Text).on_press(Message::GoBack),
+    container(text("Watches").size(24)).width(Length::Fill),
+    text(match connected {
+      true => "",
+      false => "Connecting...",
+    }).style(theme::Text::Warn),
+  ]
+  .spacing(16)
+  .padding([4.0, 16.0])
+  .align_items(Alignment::Center)
+  .into()
+}
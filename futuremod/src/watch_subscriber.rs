@@ -0,0 +1,82 @@
+use async_tungstenite::{WebSocketStream, tungstenite};
+use futuremod_data::watch::WatchValue;
+use iced::{subscription::{self, Subscription}, futures};
+use futures::sink::SinkExt;
+use futures::stream::StreamExt;
+use log::*;
+
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Connected,
+    Disconnected,
+    /// Values of the latest evaluation of the watch expressions.
+    Values(Vec<WatchValue>),
+}
+
+pub enum State {
+    Connected(WebSocketStream<async_tungstenite::tokio::ConnectStream>),
+    Disconnected,
+}
+
+pub fn connect(base_address: String) -> Subscription<Event> {
+    struct Connect;
+
+    subscription::channel(
+        std::any::TypeId::of::<Connect>(),
+        100,
+        |mut output| async move {
+            let mut state = State::Disconnected;
+
+            loop {
+                match &mut state {
+                    State::Disconnected => {
+                        match async_tungstenite::tokio::connect_async(
+                            format!("ws://{base_address}/watches/stream")
+                        )
+                        .await
+                        {
+                            Ok((websocket, _)) => {
+                                info!("Connected to watches websocket");
+                                let _ = output.send(Event::Connected).await;
+
+                                state = State::Connected(websocket);
+                            }
+                            Err(e) => {
+                                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+
+                                warn!("Could not connect to watches websocket: {}", e);
+
+                                let _ = output.send(Event::Disconnected).await;
+                            }
+                        }
+                    }
+                    State::Connected(websocket) => {
+                        match websocket.next().await {
+                            Some(Ok(tungstenite::Message::Text(message))) => {
+                                match serde_json::from_str::<Vec<WatchValue>>(message.as_str()) {
+                                    Ok(values) => {
+                                        let _ = output.send(Event::Values(values)).await;
+                                    },
+                                    Err(e) => {
+                                        warn!("Could not parse incoming watch values: {:?}", e);
+                                    }
+                                }
+                            },
+                            Some(Ok(_)) => (),
+                            Some(Err(e)) => {
+                                warn!("Error occurred while receiving watch values: {}", e.to_string());
+                                state = State::Disconnected;
+                                let _ = output.send(Event::Disconnected).await;
+                            },
+                            None => {
+                                state = State::Disconnected;
+                                let _ = output.send(Event::Disconnected).await;
+                            },
+                        }
+                    },
+                }
+            }
+        }
+    )
+}
\ No newline at end of file
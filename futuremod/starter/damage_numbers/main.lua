@@ -0,0 +1,53 @@
+-- Collects the damage of the events and renders each number for a short time.
+local ui = require("ui")
+local events = require("events")
+local time = require("time")
+local math = require("math")
+local table = require("table")
+
+-- Seconds a number is shown
+local DURATION = 1.5
+
+-- Pixels a number floats up while it is shown
+local RISE = 24
+
+-- Where each player's numbers start, player one on the left and player two on the right
+local ORIGINS = {
+  [0] = {x = 40, y = 200},
+  [1] = {x = 260, y = 200},
+}
+
+local numbers = {}
+
+function onEnable()
+  -- Subscriptions are removed when the plugin is disabled
+  events.on("playerDamaged", function(event)
+    table.insert(numbers, {player = event.player, damage = event.damage, shownAt = time.now()})
+  end)
+
+  events.on("missionEnded", function()
+    numbers = {}
+  end)
+end
+
+function onUpdate()
+  local now = time.now()
+  local shown = {}
+
+  for _, number in numbers do
+    local age = now - number.shownAt
+
+    if age < DURATION then
+      local origin = ORIGINS[number.player]
+      local y = origin.y - RISE * age / DURATION
+
+      -- Numbers turn from red to yellow before they disappear
+      local palette = if age < DURATION / 2 then ui.PaletteRed else ui.PaletteYellow
+      ui.renderText(`-{math.round(number.damage)}`, origin.x, y, palette)
+
+      table.insert(shown, number)
+    end
+  end
+
+  numbers = shown
+end
@@ -0,0 +1,78 @@
+-- Shows a panel with the closest entities, built with the widgets of the ui library.
+local ui = require("ui")
+local game = require("game")
+local input = require("input")
+local math = require("math")
+local table = require("table")
+local string = require("string")
+
+local PANEL = "entities"
+
+local visible = false
+local count = 5
+local showPositions = false
+
+local function distance(entity, player)
+  local x = entity.positionX - player.positionX
+  local y = entity.positionY - player.positionY
+  local z = entity.positionZ - player.positionZ
+
+  return math.sqrt(x * x + y * y + z * z)
+end
+
+-- Entities sorted by their distance to the player, without the players' own entities
+local function closestEntities(player)
+  local entities = {}
+
+  for _, entity in game.entities() do
+    if entity.health == nil then
+      table.insert(entities, {entity = entity, distance = distance(entity, player)})
+    end
+  end
+
+  table.sort(entities, function(a, b) return a.distance < b.distance end)
+
+  return entities
+end
+
+-- The key setting is registered as binding, so the callback follows the key the user chose
+input.onKeyDown("toggleKey", function()
+  visible = not visible
+
+  if visible then
+    ui.widgets.focus(PANEL)
+  end
+end)
+
+function onUpdate()
+  if not visible or not game.getState().isInMission then
+    return
+  end
+
+  local player = game.getPlayer(0)
+
+  ui.widgets.beginPanel(PANEL, 4, 40, 160)
+  ui.widgets.label("Closest entities")
+
+  for index, closest in closestEntities(player) do
+    if index > count then
+      break
+    end
+
+    local entity = closest.entity
+    ui.widgets.label(`#{entity.id} {string.format("0x%x", entity.behaviorType)} {math.round(closest.distance)}`)
+
+    if showPositions then
+      ui.widgets.label(`  {math.round(entity.positionX)} {math.round(entity.positionY)} {math.round(entity.positionZ)}`)
+    end
+  end
+
+  count = ui.widgets.slider("Entities", count, 1, 10, 1)
+  showPositions = ui.widgets.checkbox("Positions", showPositions)
+
+  if ui.widgets.button("Close") then
+    visible = false
+  end
+
+  ui.widgets.endPanel()
+end
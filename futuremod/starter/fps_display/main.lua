@@ -0,0 +1,35 @@
+-- Counts the frames between updates and shows their average rate.
+local ui = require("ui")
+local time = require("time")
+local config = require("config")
+local math = require("math")
+
+-- Position of the text in each corner of the 320x240 layout
+local POSITIONS = {
+  topLeft = {x = 4, y = 4},
+  topRight = {x = 256, y = 4},
+  bottomLeft = {x = 4, y = 226},
+  bottomRight = {x = 256, y = 226},
+}
+
+local frames = 0
+local since = time.now()
+local fps = 0
+
+function onUpdate()
+  frames += 1
+
+  local elapsed = time.now() - since
+  if elapsed >= config.get("interval") then
+    fps = math.round(frames / elapsed)
+    frames = 0
+    since = time.now()
+  end
+
+  -- The layout's anchor keeps the text in its corner on widescreen resolutions
+  local corner = config.get("corner")
+  ui.setLayout({anchor = corner})
+
+  local position = POSITIONS[corner]
+  ui.renderText(`FPS: {fps}`, position.x, position.y, ui.PaletteWhite)
+end
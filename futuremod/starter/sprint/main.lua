@@ -0,0 +1,45 @@
+-- Moves the player further in the direction the game moved them in the last frame.
+-- Changing the movement instead of the acceleration keeps the game's physics and collisions as they are.
+local game = require("game")
+local input = require("input")
+local config = require("config")
+local math = require("math")
+
+-- Larger moves are teleports, e.g., respawns, which must not be repeated
+local MAX_MOVE = 64
+
+-- Position of each player after the last frame, by the player's index
+local lastPositions = {}
+
+local function sprint(player)
+  local last = lastPositions[player.index]
+  lastPositions[player.index] = {x = player.positionX, z = player.positionZ}
+
+  if last == nil or not input.isKeyPressed(config.get("sprintKey")) then
+    return
+  end
+
+  local moveX = player.positionX - last.x
+  local moveZ = player.positionZ - last.z
+  if math.abs(moveX) > MAX_MOVE or math.abs(moveZ) > MAX_MOVE then
+    return
+  end
+
+  local extra = config.get("speed") - 1
+  -- Positions are whole numbers
+  player.positionX += math.round(moveX * extra)
+  player.positionZ += math.round(moveZ * extra)
+
+  lastPositions[player.index] = {x = player.positionX, z = player.positionZ}
+end
+
+function onUpdate()
+  if not game.getState().isInMission then
+    lastPositions = {}
+    return
+  end
+
+  for _, player in game.getPlayers() do
+    sprint(player)
+  end
+end
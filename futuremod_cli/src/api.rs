@@ -0,0 +1,306 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::anyhow;
+use futuremod_data::plugin::{Plugin, PluginInfo};
+use reqwest::{Body, Response};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio_util::codec::{BytesCodec, FramedRead};
+
+/// Log record as sent by the mod's log websocket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub target: String,
+    pub message: String,
+    pub level: String,
+    pub timestamp: String,
+    pub plugin: Option<String>,
+}
+
+/// Client of the mod's HTTP API.
+pub struct Client {
+    address: String,
+    client: reqwest::Client,
+}
+
+impl Client {
+    pub fn new(address: String) -> Self {
+        Client {
+            address,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.address, path)
+    }
+
+    pub fn log_url(&self) -> String {
+        format!("ws://{}/log", self.address)
+    }
+
+    pub async fn ping(&self) -> Result<(), anyhow::Error> {
+        let response = self.client.get(self.url("/ping"))
+            .send()
+            .await
+            .map_err(|e| anyhow!("could not reach the mod at {}: {}", self.address, e))?;
+
+        let response = response.text().await.map_err(|e| anyhow!("received malformed response: {}", e))?;
+
+        match response.as_str() {
+            "Pong" => Ok(()),
+            other => Err(anyhow!("unexpected response to ping: '{}'", other)),
+        }
+    }
+
+    pub async fn get_plugins(&self) -> Result<HashMap<String, Plugin>, anyhow::Error> {
+        let response = self.client.get(self.url("/plugins"))
+            .send()
+            .await
+            .map_err(|e| anyhow!("could not get plugins: {}", e))?;
+
+        let response = check_response(response, "could not get plugins").await?;
+
+        response.json().await.map_err(|e| anyhow!("could not parse plugins: {}", e))
+    }
+
+    pub async fn enable_plugin(&self, name: &str) -> Result<(), anyhow::Error> {
+        self.put_plugin("/plugin/enable", name, "enable").await
+    }
+
+    pub async fn disable_plugin(&self, name: &str) -> Result<(), anyhow::Error> {
+        self.put_plugin("/plugin/disable", name, "disable").await
+    }
+
+    pub async fn reload_plugin(&self, name: &str) -> Result<(), anyhow::Error> {
+        self.put_plugin("/plugin/reload", name, "reload").await
+    }
+
+    pub async fn uninstall_plugin(&self, name: &str) -> Result<(), anyhow::Error> {
+        let response = self.client.post(self.url("/plugin/uninstall"))
+            .json(&HashMap::from([("name", name)]))
+            .send()
+            .await
+            .map_err(|e| anyhow!("could not uninstall plugin '{}': {}", name, e))?;
+
+        check_response(response, &format!("could not uninstall plugin '{}'", name)).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_plugin_info(&self, path: &Path) -> Result<PluginInfo, anyhow::Error> {
+        let response = self.client.put(self.url("/plugin/info"))
+            .body(file_body(path).await?)
+            .send()
+            .await
+            .map_err(|e| anyhow!("could not get plugin info: {}", e))?;
+
+        let response = check_response(response, &format!("could not get plugin info of '{}'", path.display())).await?;
+
+        response.json().await.map_err(|e| anyhow!("could not parse plugin info: {}", e))
+    }
+
+    pub async fn install_plugin(&self, path: &Path) -> Result<(), anyhow::Error> {
+        let response = self.client.post(self.url("/plugin/install"))
+            .body(file_body(path).await?)
+            .send()
+            .await
+            .map_err(|e| anyhow!("could not install plugin: {}", e))?;
+
+        check_response(response, &format!("could not install plugin '{}'", path.display())).await?;
+
+        Ok(())
+    }
+
+    async fn put_plugin(&self, path: &str, name: &str, action: &str) -> Result<(), anyhow::Error> {
+        let response = self.client.put(self.url(path))
+            .json(&HashMap::from([("name", name)]))
+            .send()
+            .await
+            .map_err(|e| anyhow!("could not {} plugin '{}': {}", action, name, e))?;
+
+        check_response(response, &format!("could not {} plugin '{}'", action, name)).await?;
+
+        Ok(())
+    }
+}
+
+async fn file_body(path: &Path) -> Result<Body, anyhow::Error> {
+    let file = fs::File::open(path).await.map_err(|e| anyhow!("could not open '{}': {}", path.display(), e))?;
+
+    Ok(Body::wrap_stream(FramedRead::new(file, BytesCodec::new())))
+}
+
+/// Turn error responses into errors that contain the mod's error message.
+async fn check_response(response: Response, context: &str) -> Result<Response, anyhow::Error> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status();
+    let message = response.text().await.unwrap_or_default();
+
+    match message.is_empty() {
+        true => Err(anyhow!("{}: {}", context, status)),
+        false => Err(anyhow!("{}: {} ({})", context, message, status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener, task::JoinHandle};
+
+    use super::*;
+
+    /// Answer the next request with the status and body, like the mod's server would.
+    ///
+    /// Returns the client of the server and the request it received.
+    async fn serve_once(status: &'static str, body: &'static str) -> (Client, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let request = read_request(&mut socket).await;
+
+            let response = format!("HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}", status, body.len(), body);
+            socket.write_all(response.as_bytes()).await.unwrap();
+
+            request
+        });
+
+        (Client::new(address), server)
+    }
+
+    /// Read the request line, the headers, and the body, which is either of its content length or chunked.
+    async fn read_request(socket: &mut tokio::net::TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+
+        loop {
+            let read = socket.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+
+            let text = String::from_utf8_lossy(&request).to_string();
+            if read == 0 || is_complete(&text) {
+                return text;
+            }
+        }
+    }
+
+    fn is_complete(request: &str) -> bool {
+        let end = match request.find("\r\n\r\n") {
+            Some(end) => end,
+            None => return false,
+        };
+        let headers = request[..end].to_lowercase();
+
+        if headers.contains("transfer-encoding: chunked") {
+            return request.ends_with("0\r\n\r\n");
+        }
+
+        let content_length = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|length| length.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+
+        request.len() >= end + 4 + content_length
+    }
+
+    #[tokio::test]
+    async fn enable_plugin_sends_name() {
+        let (client, server) = serve_once("200 OK", "").await;
+
+        client.enable_plugin("example").await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("PUT /plugin/enable "));
+        assert!(request.ends_with(r#"{"name":"example"}"#));
+    }
+
+    #[tokio::test]
+    async fn disable_and_reload_plugin_use_their_routes() {
+        let (client, server) = serve_once("200 OK", "").await;
+        client.disable_plugin("example").await.unwrap();
+        assert!(server.await.unwrap().starts_with("PUT /plugin/disable "));
+
+        let (client, server) = serve_once("200 OK", "").await;
+        client.reload_plugin("example").await.unwrap();
+        assert!(server.await.unwrap().starts_with("PUT /plugin/reload "));
+    }
+
+    #[tokio::test]
+    async fn uninstall_plugin_posts_name() {
+        let (client, server) = serve_once("200 OK", "").await;
+
+        client.uninstall_plugin("example").await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /plugin/uninstall "));
+        assert!(request.ends_with(r#"{"name":"example"}"#));
+    }
+
+    #[tokio::test]
+    async fn missing_plugin_error_contains_message_and_status() {
+        let (client, _server) = serve_once("404 Not Found", "Something went wrong: plugin doesn't exist").await;
+
+        let error = client.enable_plugin("example").await.unwrap_err();
+
+        assert_eq!(error.to_string(), "could not enable plugin 'example': Something went wrong: plugin doesn't exist (404 Not Found)");
+    }
+
+    #[tokio::test]
+    async fn tampered_plugin_error_contains_message_and_status() {
+        let (client, _server) = serve_once("409 Conflict", "Something went wrong: the plugin's files changed since it was installed").await;
+
+        let error = client.reload_plugin("example").await.unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "could not reload plugin 'example': Something went wrong: the plugin's files changed since it was installed (409 Conflict)",
+        );
+    }
+
+    #[tokio::test]
+    async fn error_without_message_contains_status() {
+        let (client, _server) = serve_once("404 Not Found", "").await;
+
+        let error = client.uninstall_plugin("example").await.unwrap_err();
+
+        assert_eq!(error.to_string(), "could not uninstall plugin 'example': 404 Not Found");
+    }
+
+    #[tokio::test]
+    async fn install_plugin_uploads_the_package() {
+        let path = std::env::temp_dir().join(format!("futuremod-cli-test-{}.zip", std::process::id()));
+        fs::write(&path, b"package").await.unwrap();
+
+        let (client, server) = serve_once("200 OK", "").await;
+        let result = client.install_plugin(&path).await;
+        let _ = fs::remove_file(&path).await;
+
+        result.unwrap();
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /plugin/install "));
+        assert!(request.contains("package"));
+    }
+
+    #[tokio::test]
+    async fn plugin_info_error_names_the_package() {
+        let path = std::env::temp_dir().join(format!("futuremod-cli-test-info-{}.zip", std::process::id()));
+        fs::write(&path, b"package").await.unwrap();
+
+        let (client, server) = serve_once("400 Bad Request", "invalid plugin package").await;
+        let result = client.get_plugin_info(&path).await;
+        let _ = fs::remove_file(&path).await;
+
+        let error = result.unwrap_err();
+        assert!(server.await.unwrap().starts_with("PUT /plugin/info "));
+        assert_eq!(error.to_string(), format!("could not get plugin info of '{}': invalid plugin package (400 Bad Request)", path.display()));
+    }
+}
@@ -0,0 +1,310 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use anyhow::anyhow;
+use async_tungstenite::tungstenite;
+use clap::{Parser, Subcommand, ValueEnum};
+use futuremod_data::plugin::{Plugin, PluginDependency, PluginState};
+use futures::StreamExt;
+
+mod api;
+
+use api::{Client, LogRecord};
+
+
+/// Manage the plugins of a running FutureMod without the GUI.
+#[derive(Parser)]
+#[command(name = "futuremod-cli")]
+struct Cli {
+    /// Address of the mod's server.
+    #[arg(short, long, default_value_t = String::from("127.0.0.1:8000"))]
+    address: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check whether the mod is running.
+    Ping,
+
+    /// List all installed plugins and their state.
+    List {
+        /// Print the plugins as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Enable a plugin.
+    Enable { name: String },
+
+    /// Disable a plugin.
+    Disable { name: String },
+
+    /// Reload a plugin from disk.
+    Reload { name: String },
+
+    /// Install a plugin package.
+    Install {
+        /// Path to the plugin package (zip file).
+        path: PathBuf,
+
+        /// Install the plugin even if it depends on dangerous libraries.
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Uninstall a plugin.
+    Uninstall { name: String },
+
+    /// Print the mod's logs and follow new ones.
+    Logs {
+        /// Minimum log level to print.
+        #[arg(short, long, value_enum, default_value_t = LogLevel::Info)]
+        level: LogLevel,
+
+        /// Only print logs of this plugin.
+        #[arg(short, long)]
+        plugin: Option<String>,
+    },
+
+    /// Dump the mod's state, including all plugins, as JSON.
+    State,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, ValueEnum)]
+enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn from_record(level: &str) -> LogLevel {
+        match level {
+            "ERROR" => LogLevel::Error,
+            "WARN" => LogLevel::Warn,
+            "INFO" => LogLevel::Info,
+            _ => LogLevel::Debug,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Cli::parse();
+    let client = Client::new(args.address);
+
+    match run(&client, args.command).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+async fn run(client: &Client, command: Command) -> Result<(), anyhow::Error> {
+    match command {
+        Command::Ping => {
+            client.ping().await?;
+            println!("Mod is running");
+        },
+        Command::List { json } => {
+            let plugins = client.get_plugins().await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&plugins)?);
+                return Ok(());
+            }
+
+            let mut names: Vec<&String> = plugins.keys().collect();
+            names.sort();
+
+            for name in names {
+                let plugin = &plugins[name];
+                println!("{:<32} {:<10} {}", name, plugin.info.version, plugin_state(plugin));
+            }
+        },
+        Command::Enable { name } => {
+            client.enable_plugin(&name).await?;
+            println!("Enabled plugin '{}'", name);
+        },
+        Command::Disable { name } => {
+            client.disable_plugin(&name).await?;
+            println!("Disabled plugin '{}'", name);
+        },
+        Command::Reload { name } => {
+            client.reload_plugin(&name).await?;
+            println!("Reloaded plugin '{}'", name);
+        },
+        Command::Install { path, yes } => {
+            let info = client.get_plugin_info(&path).await?;
+
+            println!("Installing '{}' {} by {}", info.name, info.version, info.authors.join(", "));
+
+            if info.dependencies.iter().any(PluginDependency::is_dangerous) && !yes {
+                return Err(anyhow!("'{}' has a dangerous dependency and can access your entire computer, pass --yes to install it anyway", info.name));
+            }
+
+            client.install_plugin(&path).await?;
+            println!("Installed plugin '{}'", info.name);
+        },
+        Command::Uninstall { name } => {
+            client.uninstall_plugin(&name).await?;
+            println!("Uninstalled plugin '{}'", name);
+        },
+        Command::Logs { level, plugin } => tail_logs(client, level, plugin).await?,
+        Command::State => {
+            let plugins = client.get_plugins().await?;
+
+            let state = serde_json::json!({
+                "address": client.address(),
+                "plugins": plugins,
+            });
+
+            println!("{}", serde_json::to_string_pretty(&state)?);
+        },
+    }
+
+    Ok(())
+}
+
+fn plugin_state(plugin: &Plugin) -> String {
+    match &plugin.state {
+        PluginState::Error(e) => format!("error: {}", e),
+        PluginState::Unloaded => String::from("unloaded"),
+        PluginState::Loaded(_) => match plugin.enabled {
+            true => String::from("enabled"),
+            false => String::from("disabled"),
+        },
+    }
+}
+
+/// Print the log history and all new logs until the mod closes the connection.
+async fn tail_logs(client: &Client, level: LogLevel, plugin: Option<String>) -> Result<(), anyhow::Error> {
+    let (mut websocket, _) = async_tungstenite::tokio::connect_async(client.log_url())
+        .await
+        .map_err(|e| anyhow!("could not connect to the mod's logs: {}", e))?;
+
+    while let Some(message) = websocket.next().await {
+        let message = match message.map_err(|e| anyhow!("lost connection to the mod's logs: {}", e))? {
+            tungstenite::Message::Text(message) => message,
+            tungstenite::Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let record: LogRecord = match serde_json::from_str(&message) {
+            Ok(record) => record,
+            Err(e) => {
+                eprintln!("Received malformed log record: {}", e);
+                continue;
+            },
+        };
+
+        if LogLevel::from_record(&record.level) < level {
+            continue;
+        }
+
+        if plugin.is_some() && record.plugin != plugin {
+            continue;
+        }
+
+        let origin = match &record.plugin {
+            Some(plugin) => format!("[{}]", plugin),
+            None => record.target.replace("futuremod_engine::", ""),
+        };
+
+        println!("{} [{}] {} - {}", record.timestamp, record.level, origin, record.message);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("futuremod-cli").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn cli_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn address_defaults_to_local_server() {
+        let cli = parse(&["ping"]).unwrap();
+
+        assert_eq!(cli.address, "127.0.0.1:8000");
+        assert!(matches!(cli.command, Command::Ping));
+    }
+
+    #[test]
+    fn address_can_be_set() {
+        let cli = parse(&["--address", "192.168.0.2:8000", "ping"]).unwrap();
+
+        assert_eq!(cli.address, "192.168.0.2:8000");
+    }
+
+    #[test]
+    fn plugin_commands_take_the_name() {
+        assert!(matches!(parse(&["enable", "example"]).unwrap().command, Command::Enable { name } if name == "example"));
+        assert!(matches!(parse(&["disable", "example"]).unwrap().command, Command::Disable { name } if name == "example"));
+        assert!(matches!(parse(&["reload", "example"]).unwrap().command, Command::Reload { name } if name == "example"));
+        assert!(matches!(parse(&["uninstall", "example"]).unwrap().command, Command::Uninstall { name } if name == "example"));
+    }
+
+    #[test]
+    fn plugin_commands_require_the_name() {
+        assert!(parse(&["enable"]).is_err());
+        assert!(parse(&["uninstall"]).is_err());
+    }
+
+    #[test]
+    fn install_takes_the_path_and_confirmation() {
+        let command = parse(&["install", "plugin.zip"]).unwrap().command;
+        assert!(matches!(command, Command::Install { path, yes: false } if path.as_os_str() == "plugin.zip"));
+
+        let command = parse(&["install", "-y", "plugin.zip"]).unwrap().command;
+        assert!(matches!(command, Command::Install { yes: true, .. }));
+    }
+
+    #[test]
+    fn list_prints_json_with_flag() {
+        assert!(matches!(parse(&["list"]).unwrap().command, Command::List { json: false }));
+        assert!(matches!(parse(&["list", "--json"]).unwrap().command, Command::List { json: true }));
+    }
+
+    #[test]
+    fn logs_default_to_info_of_all_plugins() {
+        let command = parse(&["logs"]).unwrap().command;
+        assert!(matches!(command, Command::Logs { level: LogLevel::Info, plugin: None }));
+
+        let command = parse(&["logs", "--level", "warn", "--plugin", "example"]).unwrap().command;
+        assert!(matches!(command, Command::Logs { level: LogLevel::Warn, plugin: Some(plugin) } if plugin == "example"));
+    }
+
+    #[test]
+    fn logs_reject_unknown_level() {
+        assert!(parse(&["logs", "--level", "verbose"]).is_err());
+    }
+
+    #[test]
+    fn unknown_command_is_rejected() {
+        assert!(parse(&["start"]).is_err());
+        assert!(parse(&[]).is_err());
+    }
+
+    #[test]
+    fn log_levels_are_ordered_by_severity() {
+        assert!(LogLevel::from_record("ERROR") > LogLevel::from_record("WARN"));
+        assert!(LogLevel::from_record("WARN") > LogLevel::from_record("INFO"));
+        assert!(LogLevel::from_record("INFO") > LogLevel::from_record("TRACE"));
+    }
+}
@@ -0,0 +1,38 @@
+use std::fmt::Display;
+
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Preset that remaps the game's colors to be distinguishable with a kind of color blindness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ColorblindPreset {
+  #[default]
+  None,
+  /// Red-blind
+  Protanopia,
+  /// Green-blind
+  Deuteranopia,
+  /// Blue-blind
+  Tritanopia,
+}
+
+impl ColorblindPreset {
+  pub const ALL: [ColorblindPreset; 4] = [
+    ColorblindPreset::None,
+    ColorblindPreset::Protanopia,
+    ColorblindPreset::Deuteranopia,
+    ColorblindPreset::Tritanopia,
+  ];
+}
+
+impl Display for ColorblindPreset {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ColorblindPreset::None => f.write_str("Off"),
+      ColorblindPreset::Protanopia => f.write_str("Protanopia (red-blind)"),
+      ColorblindPreset::Deuteranopia => f.write_str("Deuteranopia (green-blind)"),
+      ColorblindPreset::Tritanopia => f.write_str("Tritanopia (blue-blind)"),
+    }
+  }
+}
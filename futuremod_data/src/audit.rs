@@ -0,0 +1,33 @@
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, path::PathBuf};
+
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Report of the sandbox audit mode.
+///
+/// Lists which sensitive functionality every plugin accessed since the mod was injected.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditReport {
+  /// Whether the audit mode is enabled. If not, the report is empty.
+  pub enabled: bool,
+
+  pub plugins: HashMap<String, PluginAudit>,
+}
+
+/// Sensitive functionality a plugin accessed.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginAudit {
+  /// Number of calls per function of the dangerous library.
+  pub dangerous_calls: BTreeMap<String, u64>,
+
+  /// Memory addresses passed to functions of the dangerous library.
+  pub addresses: BTreeSet<u32>,
+
+  /// Whether further addresses were accessed but not recorded, because the report reached its limit.
+  pub addresses_truncated: bool,
+
+  /// Files the plugin loaded.
+  pub files: BTreeSet<PathBuf>,
+}
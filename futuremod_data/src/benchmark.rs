@@ -0,0 +1,76 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// State of the benchmark that measures the frame time overhead of plugins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BenchmarkState {
+  /// No benchmark was started.
+  Idle,
+
+  /// The benchmark was started and waits for a mission to run.
+  Waiting,
+
+  /// The benchmark is measuring.
+  #[serde(rename_all = "camelCase")]
+  Running {
+    /// Plugin that is currently measured. `None` while measuring the baseline.
+    plugin: Option<String>,
+
+    /// Number of finished measurements, including the baseline.
+    finished: usize,
+
+    /// Total number of measurements, including the baseline.
+    total: usize,
+  },
+
+  Finished(BenchmarkResult),
+
+  Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkResult {
+  /// Number of frames measured for the baseline and for every plugin.
+  pub frames: u32,
+
+  /// Average frame time in milliseconds with all plugins disabled.
+  pub baseline_frame_time: f64,
+
+  pub plugins: Vec<PluginOverhead>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginOverhead {
+  pub name: String,
+
+  /// Average frame time in milliseconds with only this plugin enabled.
+  pub frame_time: f64,
+
+  /// Average time in milliseconds spent in the plugin's `onUpdate` per frame.
+  pub update_time: f64,
+
+  /// Difference between the plugin's and the baseline frame time in milliseconds.
+  pub overhead: f64,
+}
+
+/// Request to start a benchmark.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartBenchmark {
+  /// Number of frames to measure for the baseline and for every plugin.
+  #[serde(default = "default_benchmark_frames")]
+  pub frames: u32,
+}
+
+fn default_benchmark_frames() -> u32 {
+  300
+}
+
+impl Default for StartBenchmark {
+  fn default() -> Self {
+    StartBenchmark { frames: default_benchmark_frames() }
+  }
+}
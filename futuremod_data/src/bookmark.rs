@@ -0,0 +1,23 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Types a bookmarked value can have, named like the types of the dangerous library's `readMemory`.
+pub const BOOKMARK_TYPES: [&str; 8] = ["int", "uint", "short", "ushort", "byte", "ubyte", "float", "string"];
+
+/// Named address in the game's memory.
+///
+/// Bookmarks are shared by the GUI and all plugins, so an address found once can be used everywhere by its name.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Bookmark {
+  pub name: String,
+
+  pub address: u32,
+
+  /// Type of the value at the address, one of [`BOOKMARK_TYPES`].
+  #[serde(rename = "type")]
+  pub value_type: String,
+
+  #[serde(default)]
+  pub description: String,
+}
@@ -0,0 +1,51 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Which game builds, engine versions, and plugin versions ran together and how often the plugins failed.
+///
+/// Only collected locally and only if the user enabled it.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatibilityReport {
+  /// Whether the engine collects the data.
+  pub enabled: bool,
+
+  /// SHA-256 hash of the running game's executable, if it could be read.
+  pub game_hash: Option<String>,
+
+  /// Version of the running engine.
+  pub engine_version: String,
+
+  pub entries: Vec<CompatibilityEntry>,
+}
+
+/// How a plugin's version ran on a game build with an engine version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatibilityEntry {
+  pub plugin: String,
+
+  pub plugin_version: String,
+
+  /// SHA-256 hash of the game's executable.
+  pub game_hash: String,
+
+  pub engine_version: String,
+
+  /// Number of sessions in which the plugin was enabled.
+  pub sessions: u32,
+
+  /// Number of errors the plugin threw.
+  pub errors: u32,
+
+  /// Unix timestamp in seconds of the last error.
+  #[serde(default)]
+  pub last_error_at: Option<u64>,
+}
+
+/// Enable or disable collecting the compatibility data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCompatibilityReporting {
+  pub enabled: bool,
+}
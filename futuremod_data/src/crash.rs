@@ -0,0 +1,48 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Report of a crash of the game, written by the mod's crash handler.
+///
+/// The mod serves the report of the last crash, so the GUI can tell which plugin likely caused it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+  /// Unix timestamp in seconds at which the game crashed.
+  pub crashed_at: u64,
+
+  /// What crashed the game, e.g., `access violation reading 0x00000010` or the panic's message.
+  pub cause: String,
+
+  /// Code of the exception, or `None` if the engine panicked.
+  pub exception_code: Option<u32>,
+
+  /// Address of the instruction that caused the exception.
+  pub address: Option<u32>,
+
+  /// Registers at the time of the exception.
+  pub registers: Option<Registers>,
+
+  /// Plugin whose code ran when the game crashed, e.g., its `onUpdate` function or one of its hooks.
+  pub plugin: Option<String>,
+
+  /// Address of the hooked function if the game crashed in a plugin's hook.
+  pub hook: Option<u32>,
+
+  /// Path of the minidump written for the crash.
+  pub minidump: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Registers {
+  pub eax: u32,
+  pub ebx: u32,
+  pub ecx: u32,
+  pub edx: u32,
+  pub esi: u32,
+  pub edi: u32,
+  pub ebp: u32,
+  pub esp: u32,
+  pub eip: u32,
+  pub eflags: u32,
+}
@@ -0,0 +1,65 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// State of the frame-step debugger.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DebuggerState {
+  /// Whether the game loop is paused.
+  pub paused: bool,
+
+  /// Number of mission frames the game ran since the mod was injected.
+  pub frame: u64,
+}
+
+/// Request to run the game for the number of frames and pause again.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StepFrames {
+  pub frames: u32,
+}
+
+/// Global of a plugin's environment.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentEntry {
+  pub name: String,
+
+  /// Lua type of the value, e.g., `number` or `function`.
+  #[serde(rename = "type")]
+  pub value_type: String,
+
+  /// The value, e.g., tables as JSON or where a function is defined.
+  pub value: String,
+
+  /// Entries of a table value, only for tables of the environment itself.
+  #[serde(default)]
+  pub fields: Vec<EnvironmentEntry>,
+}
+
+/// Result of a Lua chunk a developer ran in a console.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkResult {
+  /// Lines the chunk printed.
+  pub output: Vec<String>,
+
+  /// Description of the values the chunk returned, or `None` if it returned nothing or failed.
+  pub value: Option<String>,
+
+  /// Error of the chunk, or `None` if it succeeded.
+  pub error: Option<String>,
+}
+
+/// Lua chunk sent to the mod's `/repl` websocket, which responds with a [`ChunkResult`] for each chunk.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplRequest {
+  pub chunk: String,
+
+  /// Plugin whose environment the chunk runs in, or `None` for the scratch environment.
+  ///
+  /// Plugin environments are only available in developer mode.
+  #[serde(default)]
+  pub plugin: Option<String>,
+}
@@ -0,0 +1,89 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Results of the diagnostic checks the mod ran when it was injected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostics {
+  /// Unix timestamp in seconds at which the mod was injected.
+  pub ran_at: u64,
+
+  /// Results of the checks in the order they ran.
+  pub checks: Vec<DiagnosticCheck>,
+
+  /// Functions whose signature was found at another address than the hard-coded one, e.g., after a patch of the game.
+  #[serde(default)]
+  pub migrations: Vec<AddressMigration>,
+
+  /// Disk usage of the directory plugin packages are uploaded and extracted to.
+  #[serde(default)]
+  pub temporary_directory: Option<TemporaryDirectoryUsage>,
+}
+
+/// Function of the game that moved, see [`Diagnostics::migrations`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressMigration {
+  /// Name of the function, e.g., `renderText`.
+  pub name: String,
+
+  /// Hard-coded address of the function.
+  pub expected: u32,
+
+  /// Address the function's signature was found at, which is used instead.
+  pub found: u32,
+}
+
+/// Disk usage of the temporary directory, see [`Diagnostics::temporary_directory`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemporaryDirectoryUsage {
+  pub path: String,
+
+  /// Size of all files in bytes.
+  pub size: u64,
+
+  /// Number of uploaded packages and extracted folders.
+  pub entries: usize,
+}
+
+/// Result of a single diagnostic check.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCheck {
+  /// Name of the check, e.g., `Server bind`.
+  pub name: String,
+
+  pub status: CheckStatus,
+
+  /// What the check found, e.g., the address that isn't readable.
+  pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+  Passed,
+  /// The mod works, but some features might not.
+  Warning,
+  /// The mod likely doesn't work.
+  Failed,
+}
+
+/// Version and capabilities of the running engine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EngineHealth {
+  pub version: String,
+
+  /// The engine's [`crate::version::API_VERSION`]. Engines from before API versions report 0.
+  #[serde(default)]
+  pub api_version: u32,
+
+  /// Features of the engine that plugins can require, e.g., `httpRoutes`.
+  pub features: Vec<String>,
+
+  /// The engine's [`crate::version::PLUGIN_API_VERSION`]. Empty for engines from before plugin API versions.
+  #[serde(default)]
+  pub plugin_api_version: String,
+}
@@ -0,0 +1,213 @@
+use std::fmt::Write;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::plugin::PluginDependency;
+
+
+/// Documentation of a library that plugins can require.
+///
+/// The documentation is created by the engine while registering the library's functions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryDocs {
+  /// Name used to require the library.
+  pub name: String,
+
+  /// Dependency a plugin must declare to use the library.
+  pub dependency: PluginDependency,
+
+  pub description: String,
+
+  #[serde(default)]
+  pub functions: Vec<FunctionDocs>,
+
+  #[serde(default)]
+  pub constants: Vec<ValueDocs>,
+
+  /// Types returned by the library's functions.
+  #[serde(default)]
+  pub types: Vec<TypeDocs>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionDocs {
+  pub name: String,
+  pub description: String,
+  pub parameters: Vec<ParameterDocs>,
+  /// Return type. `None` if the function doesn't return anything.
+  pub returns: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParameterDocs {
+  pub name: String,
+  pub type_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueDocs {
+  pub name: String,
+  pub type_name: String,
+  pub description: String,
+}
+
+/// Documentation of a userdata type, its fields, and methods.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeDocs {
+  pub name: String,
+  pub description: String,
+  pub fields: Vec<ValueDocs>,
+  pub methods: Vec<FunctionDocs>,
+}
+
+impl LibraryDocs {
+  pub fn new(dependency: PluginDependency, description: &str) -> Self {
+    LibraryDocs {
+      name: dependency.library_name().to_string(),
+      dependency,
+      description: description.to_string(),
+      functions: Vec::new(),
+      constants: Vec::new(),
+      types: Vec::new(),
+    }
+  }
+
+  /// Whether the library is dangerous and users are warned before installing plugins that use it.
+  pub fn is_dangerous(&self) -> bool {
+    self.dependency.is_dangerous()
+  }
+}
+
+impl FunctionDocs {
+  pub fn new(description: &str) -> Self {
+    FunctionDocs {
+      name: String::new(),
+      description: description.to_string(),
+      parameters: Vec::new(),
+      returns: None,
+    }
+  }
+
+  /// Add a parameter.
+  pub fn param(mut self, name: &str, type_name: &str) -> Self {
+    self.parameters.push(ParameterDocs { name: name.to_string(), type_name: type_name.to_string() });
+    self
+  }
+
+  /// Set the return type.
+  pub fn returns(mut self, type_name: &str) -> Self {
+    self.returns = Some(type_name.to_string());
+    self
+  }
+
+  /// Signature of the function, e.g., `getPlayer(player: number): Player`.
+  pub fn signature(&self) -> String {
+    let parameters = self.parameters
+      .iter()
+      .map(|parameter| format!("{}: {}", parameter.name, parameter.type_name))
+      .collect::<Vec<String>>()
+      .join(", ");
+
+    match &self.returns {
+      Some(returns) => format!("{}({}): {}", self.name, parameters, returns),
+      None => format!("{}({})", self.name, parameters),
+    }
+  }
+}
+
+impl ValueDocs {
+  pub fn new(name: &str, type_name: &str, description: &str) -> Self {
+    ValueDocs { name: name.to_string(), type_name: type_name.to_string(), description: description.to_string() }
+  }
+}
+
+impl TypeDocs {
+  pub fn new(name: &str, description: &str) -> Self {
+    TypeDocs { name: name.to_string(), description: description.to_string(), fields: Vec::new(), methods: Vec::new() }
+  }
+
+  /// Add a field.
+  pub fn field(mut self, name: &str, type_name: &str, description: &str) -> Self {
+    self.fields.push(ValueDocs::new(name, type_name, description));
+    self
+  }
+
+  /// Add a method.
+  pub fn method(mut self, name: &str, docs: FunctionDocs) -> Self {
+    self.methods.push(FunctionDocs { name: name.to_string(), ..docs });
+    self
+  }
+}
+
+/// Render the documentation of all libraries as markdown.
+pub fn to_markdown(libraries: &[LibraryDocs]) -> String {
+  let mut markdown = String::from("# Plugin API Reference\n");
+
+  for library in libraries {
+    // Writing into a string never fails
+    let _ = write_library(&mut markdown, library);
+  }
+
+  markdown
+}
+
+fn write_library(markdown: &mut String, library: &LibraryDocs) -> std::fmt::Result {
+  writeln!(markdown, "\n## {}", library.name)?;
+  writeln!(markdown, "Dependency: `{}`", library.name)?;
+
+  if library.is_dangerous() {
+    writeln!(markdown, "\n**Dangerous:** users are warned before installing plugins that depend on this library.")?;
+  }
+
+  writeln!(markdown, "\n{}", library.description)?;
+
+  if !library.functions.is_empty() {
+    writeln!(markdown, "\n### Functions")?;
+  }
+
+  for function in library.functions.iter() {
+    write_function(markdown, function)?;
+  }
+
+  if !library.constants.is_empty() {
+    writeln!(markdown, "\n### Constants")?;
+    write_values(markdown, &library.constants)?;
+  }
+
+  for type_docs in library.types.iter() {
+    writeln!(markdown, "\n### `{}`", type_docs.name)?;
+    writeln!(markdown, "{}", type_docs.description)?;
+
+    if !type_docs.fields.is_empty() {
+      writeln!(markdown, "\n#### Fields")?;
+      write_values(markdown, &type_docs.fields)?;
+    }
+
+    for method in type_docs.methods.iter() {
+      write_function(markdown, method)?;
+    }
+  }
+
+  Ok(())
+}
+
+fn write_function(markdown: &mut String, function: &FunctionDocs) -> std::fmt::Result {
+  writeln!(markdown, "\n#### `{}`", function.signature())?;
+  writeln!(markdown, "{}", function.description)
+}
+
+fn write_values(markdown: &mut String, values: &[ValueDocs]) -> std::fmt::Result {
+  writeln!(markdown, "| Name | Type | Description |")?;
+  writeln!(markdown, "|------|------|-------------|")?;
+
+  for value in values.iter() {
+    writeln!(markdown, "| `{}` | `{}` | {} |", value.name, value.type_name, value.description)?;
+  }
+
+  Ok(())
+}
@@ -0,0 +1,39 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Remap of a key to another key.
+///
+/// Keys use the same names as the input library, e.g., `KeySpace`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InputRemap {
+  /// Key the user presses.
+  pub from: String,
+
+  /// Key the game receives instead.
+  pub to: String,
+}
+
+/// Key binding a plugin registered with the input library.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyBinding {
+  /// Name the plugin registered the binding with.
+  pub name: String,
+
+  /// Key the plugin suggested.
+  pub default_key: String,
+
+  /// Key the binding currently uses, either the default key or the key the user chose.
+  pub key: String,
+}
+
+/// Change of a plugin's key binding by the user.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SetKeyBinding {
+  pub name: String,
+
+  /// Key the binding uses. `None` resets the binding to its default key.
+  pub key: Option<String>,
+}
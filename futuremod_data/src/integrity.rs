@@ -0,0 +1,75 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Report of the enabled plugins and active memory patches, sent to the verification endpoint of a community event.
+///
+/// Organizers compare the hash of the participants' reports to verify that everyone ran the agreed plugin set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+  /// Id of the participant as set in the config, e.g., the name the organizers know the player by.
+  pub participant: String,
+
+  pub engine_version: String,
+
+  /// Enabled plugins ordered by their name.
+  pub plugins: Vec<PluginFingerprint>,
+
+  /// Functions of the game that are currently hooked, ordered by their address.
+  pub patches: Vec<MemoryPatch>,
+
+  /// SHA-256 hash of the engine version, the plugins, and the patches.
+  ///
+  /// Equal for all participants that run the same plugin set.
+  pub hash: String,
+
+  /// Unix timestamp in seconds at which the report was created.
+  pub created_at: u64,
+}
+
+/// Enabled plugin in an [`IntegrityReport`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginFingerprint {
+  pub name: String,
+  pub version: String,
+
+  /// Checksum of the plugin's files pinned when it was installed, `None` for plugins installed in developer mode.
+  pub checksum: Option<String>,
+
+  /// Whether the plugin's files don't match the checksum anymore.
+  pub tampered: bool,
+
+  /// Whether the plugin is installed in developer mode, i.e., its files can change at any time.
+  pub developer: bool,
+}
+
+/// Hooked function in an [`IntegrityReport`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryPatch {
+  pub address: u32,
+
+  /// Name of the plugin that hooked the function, `None` for the mod's own hooks.
+  pub owner: Option<String>,
+}
+
+/// State of the integrity reports.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityStatus {
+  /// Whether integrity reports are configured. If not, nothing is sent.
+  pub enabled: bool,
+
+  /// Verification endpoint the reports are sent to.
+  pub endpoint: Option<String>,
+
+  /// Latest report that was sent or tried to be sent.
+  pub report: Option<IntegrityReport>,
+
+  /// Unix timestamp in seconds at which the endpoint last accepted a report.
+  pub last_sent_at: Option<u64>,
+
+  /// Error of the last report, `None` if it was accepted.
+  pub last_error: Option<String>,
+}
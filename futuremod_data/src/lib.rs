@@ -1,2 +1,24 @@
 pub mod plugin;
-pub mod game;
\ No newline at end of file
+pub mod game;
+pub mod docs;
+pub mod benchmark;
+pub mod session;
+pub mod audit;
+pub mod multiplayer;
+pub mod input;
+pub mod screenshot;
+pub mod diagnostics;
+pub mod accessibility;
+pub mod settings;
+pub mod watch;
+pub mod bookmark;
+pub mod structs;
+pub mod crash;
+pub mod debugger;
+pub mod metrics;
+pub mod version;
+pub mod compatibility;
+pub mod statistics;
+pub mod integrity;
+pub mod paths;
+pub mod memory;
\ No newline at end of file
@@ -0,0 +1,103 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Types of values the memory scanner can scan for, named like the types of the dangerous library's `readMemory`.
+pub const SCAN_TYPES: [&str; 7] = ["int", "uint", "short", "ushort", "byte", "ubyte", "float"];
+
+/// Request to start a new scan of the game's memory for a value.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StartScan {
+  /// Type of the value, one of [`SCAN_TYPES`].
+  #[serde(rename = "type")]
+  pub value_type: String,
+
+  /// The value to scan for, e.g., `100` or `12.5`.
+  pub value: String,
+}
+
+/// How the value at an address of the previous scan's matches is compared in the next scan.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ScanComparison {
+  /// The value is the scan's value.
+  #[default]
+  Exact,
+  /// The value changed since the previous scan.
+  Changed,
+  Unchanged,
+  Increased,
+  Decreased,
+}
+
+impl ScanComparison {
+  pub const ALL: [ScanComparison; 5] = [
+    ScanComparison::Exact,
+    ScanComparison::Changed,
+    ScanComparison::Unchanged,
+    ScanComparison::Increased,
+    ScanComparison::Decreased,
+  ];
+}
+
+impl std::fmt::Display for ScanComparison {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ScanComparison::Exact => write!(f, "Exact value"),
+      ScanComparison::Changed => write!(f, "Changed"),
+      ScanComparison::Unchanged => write!(f, "Unchanged"),
+      ScanComparison::Increased => write!(f, "Increased"),
+      ScanComparison::Decreased => write!(f, "Decreased"),
+    }
+  }
+}
+
+/// Request to filter the matches of the previous scan.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NextScan {
+  pub comparison: ScanComparison,
+
+  /// The value to scan for, only used by [`ScanComparison::Exact`].
+  #[serde(default)]
+  pub value: Option<String>,
+}
+
+/// State of the memory scanner after a scan.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanState {
+  /// Type of the scanned value, or `None` if no scan was started.
+  #[serde(rename = "type")]
+  pub value_type: Option<String>,
+
+  /// Scans of the current scan, including the first one.
+  pub scans: u32,
+
+  /// Number of addresses that matched all scans.
+  pub match_count: u32,
+
+  /// The first matches with their current values. Further matches are only counted.
+  pub matches: Vec<MemoryValue>,
+}
+
+/// Address in the game's memory with the type of its value, e.g., to watch the value.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryAddress {
+  pub address: u32,
+
+  /// Type of the value at the address, one of [`SCAN_TYPES`].
+  #[serde(rename = "type")]
+  pub value_type: String,
+}
+
+/// Current value at an address in the game's memory.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryValue {
+  pub address: u32,
+
+  /// The value, or `None` if the memory isn't readable.
+  pub value: Option<String>,
+}
@@ -0,0 +1,43 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Performance metrics of the mission's game loop, averaged over the last frames.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Metrics {
+  pub fps: f64,
+
+  /// Average frame time in milliseconds.
+  pub frame_time: f64,
+
+  /// Average time in milliseconds spent in the `onUpdate` functions of all plugins per frame.
+  pub plugin_time: f64,
+
+  /// Share of the frame time spent in plugins in percent.
+  pub plugin_share: f64,
+
+  /// Statistics of the Lua VM's garbage collector.
+  pub garbage_collection: GarbageCollectionStats,
+}
+
+/// Statistics of the garbage collector of the Lua VM all plugins share.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GarbageCollectionStats {
+  /// Memory in kilobytes the Lua VM currently uses.
+  pub used_memory: f64,
+
+  /// Whether garbage is collected in steps after each frame.
+  pub frame_stepping: bool,
+
+  /// Average time in milliseconds per frame spent collecting garbage after the frame.
+  ///
+  /// Always 0 if the garbage collector doesn't step after each frame.
+  pub step_time: f64,
+
+  /// Longest time in milliseconds spent collecting garbage after a frame.
+  pub max_step_time: f64,
+
+  /// Number of garbage collection cycles finished by the steps after the frames.
+  pub cycles: u64,
+}
@@ -0,0 +1,38 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// State of the connection to the FutureMod instance of another player.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiplayerStatus {
+  /// Local UDP port the mod listens on. `None` if the mod isn't connected to a peer.
+  pub port: Option<u16>,
+
+  /// Address of the peer, e.g., `192.168.0.2:17000`.
+  pub peer: Option<String>,
+
+  /// Whether the peer's state was received recently.
+  pub connected: bool,
+
+  /// Number of packets sent to the peer.
+  pub sent: u64,
+
+  /// Number of packets received from the peer.
+  pub received: u64,
+}
+
+/// Request to start syncing with a peer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectPeer {
+  /// Local UDP port to listen on. The peer sends its state to this port.
+  #[serde(default = "default_multiplayer_port")]
+  pub port: u16,
+
+  /// Address of the peer, including the port it listens on.
+  pub peer: String,
+}
+
+pub fn default_multiplayer_port() -> u16 {
+  17000
+}
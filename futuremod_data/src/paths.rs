@@ -0,0 +1,27 @@
+use std::{env, path::PathBuf};
+
+/// Name of FutureMod's folder in the user's `AppData\Roaming` folder.
+const USER_DIRECTORY_NAME: &str = "FutureMod";
+
+/// Settings of the mod that FutureMod couldn't write into the mod's `config.json` next to the game.
+///
+/// The mod applies them on top of its `config.json`.
+const USER_MOD_CONFIG_FILE_NAME: &str = "mod-config.json";
+
+/// FutureMod's folder in the user's `AppData\Roaming` folder, `None` if the user's folder is unknown.
+///
+/// FutureMod and the mod share it if the game is installed in a folder FutureMod can't write to without administrator rights,
+/// e.g., `C:\Program Files`.
+pub fn user_directory() -> Option<PathBuf> {
+  env::var_os("APPDATA").map(|app_data| PathBuf::from(app_data).join(USER_DIRECTORY_NAME))
+}
+
+/// Plugins directory in the user's folder, which can be used instead of the one next to the game.
+pub fn user_plugins_directory() -> Option<PathBuf> {
+  user_directory().map(|directory| directory.join("plugins"))
+}
+
+/// Path of the mod's settings in the user's folder, see [`USER_MOD_CONFIG_FILE_NAME`].
+pub fn user_mod_config_path() -> Option<PathBuf> {
+  user_directory().map(|directory| directory.join(USER_MOD_CONFIG_FILE_NAME))
+}
@@ -1,7 +1,12 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{collections::BTreeMap, fmt::Display, path::PathBuf};
 
 use serde_derive::{Deserialize, Serialize};
 
+use crate::settings::Setting;
+
+/// Log target of the messages about plugins reloaded because their files changed.
+pub const HOT_RELOAD_LOG_TARGET: &str = "hot_reload";
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum PluginDependency {
@@ -12,6 +17,20 @@ pub enum PluginDependency {
   UI,
   System,
   Matrix,
+  Http,
+  Replay,
+  Timer,
+  Presence,
+  Multiplayer,
+  Macro,
+  Storage,
+  Captions,
+  Config,
+  Structs,
+  Net,
+  Time,
+  Events,
+  Sound,
 
   // The following libraries are from the standard library
   Math,
@@ -21,6 +40,66 @@ pub enum PluginDependency {
   Utf8,
 }
 
+impl PluginDependency {
+  /// Name used to require the library.
+  pub fn library_name(&self) -> &'static str {
+    match self {
+      PluginDependency::Dangerous => "dangerous",
+      PluginDependency::Game => "game",
+      PluginDependency::Input => "input",
+      PluginDependency::UI => "ui",
+      PluginDependency::System => "system",
+      PluginDependency::Matrix => "matrix",
+      PluginDependency::Http => "http",
+      PluginDependency::Replay => "replay",
+      PluginDependency::Timer => "timer",
+      PluginDependency::Presence => "presence",
+      PluginDependency::Multiplayer => "multiplayer",
+      PluginDependency::Macro => "macro",
+      PluginDependency::Storage => "storage",
+      PluginDependency::Captions => "captions",
+      PluginDependency::Config => "config",
+      PluginDependency::Structs => "structs",
+      PluginDependency::Net => "net",
+      PluginDependency::Time => "time",
+      PluginDependency::Events => "events",
+      PluginDependency::Sound => "sound",
+      PluginDependency::Math => "math",
+      PluginDependency::Table => "table",
+      PluginDependency::Bit32 => "bit32",
+      PluginDependency::String => "string",
+      PluginDependency::Utf8 => "utf8",
+    }
+  }
+
+  /// Whether the library gives plugins unrestricted access to the game and the user's system.
+  pub fn is_dangerous(&self) -> bool {
+    matches!(self, PluginDependency::Dangerous | PluginDependency::Structs)
+  }
+
+  /// Permissions that guard the library's functions.
+  pub fn permissions(&self) -> &'static [PluginPermission] {
+    match self {
+      PluginDependency::Dangerous => &[PluginPermission::MemoryRead, PluginPermission::MemoryWrite, PluginPermission::Hook],
+      PluginDependency::Structs => &[PluginPermission::MemoryRead, PluginPermission::MemoryWrite],
+      PluginDependency::Storage => &[PluginPermission::Filesystem],
+      PluginDependency::Http | PluginDependency::Multiplayer | PluginDependency::Net => &[PluginPermission::Network],
+      _ => &[],
+    }
+  }
+
+  /// Whether a plugin with the granted permissions may use the library.
+  ///
+  /// The dangerous library is available with any of its permissions, but only contains the functions they allow.
+  /// Every other library requires all of its permissions.
+  pub fn is_permitted(&self, granted: &[PluginPermission]) -> bool {
+    match self {
+      PluginDependency::Dangerous => self.permissions().iter().any(|permission| granted.contains(permission)),
+      _ => self.permissions().iter().all(|permission| granted.contains(permission)),
+    }
+  }
+}
+
 impl Display for PluginDependency {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
       match self {
@@ -35,6 +114,64 @@ impl Display for PluginDependency {
         PluginDependency::String => f.write_str("String"),
         PluginDependency::Utf8 => f.write_str("Utf8"),
         PluginDependency::Matrix => f.write_str("Matrix"),
+        PluginDependency::Http => f.write_str("Http"),
+        PluginDependency::Replay => f.write_str("Replay"),
+        PluginDependency::Timer => f.write_str("Timer"),
+        PluginDependency::Presence => f.write_str("Presence"),
+        PluginDependency::Multiplayer => f.write_str("Multiplayer"),
+        PluginDependency::Macro => f.write_str("Macro"),
+        PluginDependency::Storage => f.write_str("Storage"),
+        PluginDependency::Captions => f.write_str("Captions"),
+        PluginDependency::Config => f.write_str("Config"),
+        PluginDependency::Structs => f.write_str("Structs"),
+        PluginDependency::Net => f.write_str("Net"),
+        PluginDependency::Time => f.write_str("Time"),
+        PluginDependency::Events => f.write_str("Events"),
+        PluginDependency::Sound => f.write_str("Sound"),
+      }
+    }
+}
+
+/// Permission a plugin must declare to use functions of a library that access the game or the user's system.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PluginPermission {
+  #[serde(rename = "memory.read")]
+  MemoryRead,
+  #[serde(rename = "memory.write")]
+  MemoryWrite,
+  #[serde(rename = "hook")]
+  Hook,
+  #[serde(rename = "filesystem")]
+  Filesystem,
+  #[serde(rename = "network")]
+  Network,
+  #[serde(rename = "exec")]
+  Exec,
+}
+
+impl PluginPermission {
+  /// What the permission allows a plugin to do, e.g., to explain it before installing a plugin.
+  pub fn description(&self) -> &'static str {
+    match self {
+      PluginPermission::MemoryRead => "Read the game's memory",
+      PluginPermission::MemoryWrite => "Change the game's memory",
+      PluginPermission::Hook => "Hook and call the game's functions, which allows running arbitrary code",
+      PluginPermission::Filesystem => "Store files in the plugin's folder",
+      PluginPermission::Network => "Send and receive data over the network",
+      PluginPermission::Exec => "Run the external programs it declares, after you approved them",
+    }
+  }
+}
+
+impl Display for PluginPermission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+      match self {
+        PluginPermission::MemoryRead => f.write_str("memory.read"),
+        PluginPermission::MemoryWrite => f.write_str("memory.write"),
+        PluginPermission::Hook => f.write_str("hook"),
+        PluginPermission::Filesystem => f.write_str("filesystem"),
+        PluginPermission::Network => f.write_str("network"),
+        PluginPermission::Exec => f.write_str("exec"),
       }
     }
 }
@@ -51,7 +188,32 @@ pub struct PluginInfoContent {
   #[serde(default)]
   pub dependencies: Vec<PluginDependency>,
   #[serde(default)]
+  pub permissions: Option<Vec<PluginPermission>>,
+  #[serde(default)]
+  pub hosts: Vec<String>,
+  #[serde(default)]
+  pub commands: Vec<String>,
+  #[serde(default)]
   pub description: String,
+  #[serde(default)]
+  pub features: Vec<String>,
+  #[serde(default)]
+  pub api_version: Option<String>,
+  #[serde(default)]
+  pub settings: Vec<Setting>,
+  #[serde(default)]
+  pub translations: BTreeMap<String, PluginTranslation>,
+}
+
+/// Name and description of a plugin in another language.
+///
+/// Missing values fall back to the plugin's name and description.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PluginTranslation {
+  #[serde(default)]
+  pub name: Option<String>,
+  #[serde(default)]
+  pub description: Option<String>,
 }
 
 
@@ -59,7 +221,7 @@ pub struct PluginInfoContent {
 /// 
 /// Contains all information about a plugin, such as name and authors.
 /// These information are loaded from the plugin `info.toml` file.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PluginInfo {
   /// Path to the plugin
   pub path: PathBuf,
@@ -78,10 +240,108 @@ pub struct PluginInfo {
   /// A plugin only is granted access to the library it requests.
   pub dependencies: Vec<PluginDependency>,
 
+  /// Permissions requested by the plugin.
+  ///
+  /// `None` if the plugin doesn't declare any, see [`PluginInfo::granted_permissions`].
+  #[serde(default)]
+  pub permissions: Option<Vec<PluginPermission>>,
+
+  /// Hosts the plugin sends requests to with the net library, e.g., `example.com` or `*.example.com`.
+  ///
+  /// Requests to any other host are refused.
+  #[serde(default)]
+  pub hosts: Vec<String>,
+
+  /// Programs the plugin runs with `system.exec`, e.g., `C:\Tools\obs-cmd.exe`.
+  ///
+  /// Any other program is refused.
+  #[serde(default)]
+  pub commands: Vec<String>,
+
   /// Plugin description.
   /// 
   /// A short plugin description that explains what the plugin does.
   pub description: String,
+
+  /// Features of the engine the plugin requires, e.g., `httpRoutes`.
+  ///
+  /// The plugin isn't loaded if the running engine doesn't support all of them.
+  #[serde(default)]
+  pub features: Vec<String>,
+
+  /// Version of the plugin API the plugin targets, e.g., `1.0`, see [`crate::version::PLUGIN_API_VERSION`].
+  ///
+  /// The plugin isn't loaded if the engine's API has another major version or an older minor version.
+  /// Plugins from before API versions don't declare one and are always loaded.
+  #[serde(default)]
+  pub api_version: Option<String>,
+
+  /// Settings the plugin declares in its info file.
+  ///
+  /// Settings the plugin defines with the config library are only known to the engine.
+  #[serde(default)]
+  pub settings: Vec<Setting>,
+
+  /// Names and descriptions in other languages by language code, e.g., `de` or `pt-BR`.
+  #[serde(default)]
+  pub translations: BTreeMap<String, PluginTranslation>,
+
+  /// Name shown to users, in the language the GUI requested.
+  ///
+  /// `None` if the plugin doesn't translate its name or the engine doesn't localize plugins, see [`PluginInfo::display_name`].
+  #[serde(default)]
+  pub display_name: Option<String>,
+}
+
+impl PluginInfo {
+  /// Translation that matches the language best.
+  ///
+  /// Prefers the exact language, e.g., `pt-BR`, and falls back to the language without its region, e.g., `pt`.
+  /// Language codes are compared case-insensitively.
+  pub fn translation(&self, language: &str) -> Option<&PluginTranslation> {
+    let find = |language: &str| self.translations
+      .iter()
+      .find(|(code, _)| code.eq_ignore_ascii_case(language))
+      .map(|(_, translation)| translation);
+
+    let primary = language.split(['-', '_']).next().unwrap_or(language);
+
+    find(language).or_else(|| find(primary))
+  }
+
+  /// Use the translated name and description of the language, if the plugin translates them.
+  pub fn localize(&mut self, language: &str) {
+    let translation = match self.translation(language) {
+      Some(translation) => translation.clone(),
+      None => return,
+    };
+
+    self.display_name = translation.name;
+    if let Some(description) = translation.description {
+      self.description = description;
+    }
+  }
+
+  /// Name shown to users. The translated name if there is one, otherwise, the plugin's name.
+  pub fn display_name(&self) -> &str {
+    self.display_name.as_deref().unwrap_or(&self.name)
+  }
+
+  /// Permissions the plugin is granted.
+  ///
+  /// Plugins that don't declare permissions were written before permissions existed.
+  /// They are granted all permissions of their dependencies, just like before.
+  pub fn granted_permissions(&self) -> Vec<PluginPermission> {
+    let mut permissions = match &self.permissions {
+      Some(permissions) => permissions.clone(),
+      None => self.dependencies.iter().flat_map(|dependency| dependency.permissions().iter().copied()).collect(),
+    };
+
+    permissions.sort();
+    permissions.dedup();
+
+    permissions
+  }
 }
 
 #[derive(Debug, Serialize, Clone, Deserialize)]
@@ -90,8 +350,84 @@ pub enum PluginError {
     Error(String),
     NotEnabledError,
     NoMainFile,
-    ScriptError(String),
+    ScriptError(ScriptError),
     NotLoaded,
+    MissingFeatures { features: Vec<String> },
+    /// The plugin targets a version of the plugin API the engine doesn't support.
+    IncompatibleApiVersion { required: String, supported: String },
+    /// The plugin was disabled because it exceeded an execution limit, e.g., its instruction budget.
+    LimitExceeded(String),
+}
+
+impl Display for PluginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+      match self {
+        PluginError::Error(e) => f.write_str(e),
+        PluginError::NotEnabledError => f.write_str("The plugin is not enabled"),
+        PluginError::NoMainFile => f.write_str("The plugin has no main file"),
+        PluginError::ScriptError(e) => e.fmt(f),
+        PluginError::NotLoaded => f.write_str("The plugin is not loaded"),
+        PluginError::MissingFeatures { features } => write!(f, "The plugin requires engine features that this version of the mod doesn't support: {}", features.join(", ")),
+        PluginError::IncompatibleApiVersion { required, supported } => write!(f, "The plugin targets version {} of the plugin API, but this version of the mod supports version {}", required, supported),
+        PluginError::LimitExceeded(limit) => write!(f, "The plugin was disabled because it exceeded {}", limit),
+      }
+    }
+}
+
+/// Error thrown by a plugin's script.
+#[derive(Debug, Serialize, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptError {
+  /// What the engine did when the error occurred, e.g., executing the plugin's `onLoad` function.
+  pub context: String,
+
+  /// The full error message as reported by Lua.
+  pub message: String,
+
+  /// File in which the error occurred, if the message contains it.
+  pub file: Option<PathBuf>,
+
+  /// Line in which the error occurred, if the message contains it.
+  pub line: Option<u32>,
+
+  /// Lua's stack traceback, if available.
+  pub traceback: Option<String>,
+
+  /// State of the plugin when the error occurred, only captured if error breakpoints are enabled.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub snapshot: Option<ErrorSnapshot>,
+}
+
+/// State of a plugin captured at the moment one of its functions threw an error.
+#[derive(Debug, Serialize, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorSnapshot {
+  /// Frame of the game loop in which the error occurred.
+  pub frame: u64,
+
+  /// Arguments the engine passed to the function that threw the error.
+  pub arguments: Vec<String>,
+
+  /// The plugin's latest log lines before the error, oldest first.
+  pub logs: Vec<String>,
+}
+
+impl ScriptError {
+  /// Location of the error formatted as `file:line`.
+  pub fn location(&self) -> Option<String> {
+    let file = self.file.as_ref()?;
+
+    match self.line {
+      Some(line) => Some(format!("{}:{}", file.display(), line)),
+      None => Some(file.display().to_string()),
+    }
+  }
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+      write!(f, "{}: {}", self.context, self.message)
+    }
 }
 
 
@@ -121,4 +457,108 @@ pub struct Plugin {
   pub enabled: bool,
   pub state: PluginState,
   pub info: PluginInfo,
-}
\ No newline at end of file
+
+  /// Link in the plugins directory that points to the plugin's folder.
+  ///
+  /// Only set for plugins installed in developer mode. The plugin's source folder is [`PluginInfo::path`].
+  #[serde(default)]
+  pub dev_link: Option<PathBuf>,
+
+  /// SHA-256 checksum of the plugin's files, pinned when the plugin was installed.
+  #[serde(default)]
+  pub checksum: Option<String>,
+
+  /// Whether the plugin's files changed since it was installed.
+  ///
+  /// Tampered plugins are not loaded until the user trusts their current files.
+  #[serde(default)]
+  pub tampered: bool,
+
+  /// Whether the game crashed shortly after the plugin was enabled.
+  ///
+  /// Suspected plugins start disabled until the user enables them again or dismisses the suspicion.
+  #[serde(default)]
+  pub suspect: bool,
+
+  /// Whether the user approved that the plugin sends requests to its hosts.
+  ///
+  /// The approval is revoked if the plugin declares other hosts.
+  #[serde(default)]
+  pub network_approved: bool,
+
+  /// Whether the user approved that the plugin runs its commands.
+  ///
+  /// The approval is revoked if the plugin declares other commands.
+  #[serde(default)]
+  pub commands_approved: bool,
+}
+
+impl Plugin {
+  /// Whether the plugin was installed in developer mode by linking its folder.
+  pub fn is_dev(&self) -> bool {
+    self.dev_link.is_some()
+  }
+}
+
+
+/// Lifecycle function of a plugin that can be invoked manually by developers.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum LifecycleEvent {
+  Load,
+  Enable,
+  Disable,
+  Update,
+  Unload,
+}
+
+impl LifecycleEvent {
+  pub const ALL: [LifecycleEvent; 5] = [
+    LifecycleEvent::Load,
+    LifecycleEvent::Enable,
+    LifecycleEvent::Disable,
+    LifecycleEvent::Update,
+    LifecycleEvent::Unload,
+  ];
+
+  /// Whether the plugin defines the event's function.
+  pub fn is_defined(&self, context: &PluginContext) -> bool {
+    match self {
+      LifecycleEvent::Load => context.on_load,
+      LifecycleEvent::Enable => context.on_enable,
+      LifecycleEvent::Disable => context.on_disable,
+      LifecycleEvent::Update => context.on_update,
+      LifecycleEvent::Unload => context.on_unload,
+    }
+  }
+}
+
+impl Display for LifecycleEvent {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LifecycleEvent::Load => f.write_str("onLoad"),
+      LifecycleEvent::Enable => f.write_str("onEnable"),
+      LifecycleEvent::Disable => f.write_str("onDisable"),
+      LifecycleEvent::Update => f.write_str("onUpdate"),
+      LifecycleEvent::Unload => f.write_str("onUnload"),
+    }
+  }
+}
+
+/// Request to invoke a plugin's lifecycle function.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvokeLifecycle {
+  pub name: String,
+  pub event: LifecycleEvent,
+}
+
+/// Request to install a plugin in developer mode.
+///
+/// The plugin's folder is linked into the plugins directory instead of copied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallDevPlugin {
+  /// Folder containing the plugin's `info.toml`.
+  pub path: PathBuf,
+}
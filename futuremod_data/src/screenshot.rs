@@ -0,0 +1,19 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Screenshot taken with the mod's screenshot key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Screenshot {
+  /// Path of the screenshot relative to the screenshot directory, separated by `/`, e.g., `CrimeWar/2024-05-01_18-30-12-345.png`.
+  pub path: String,
+
+  /// Folder the screenshot is organized in, i.e., the game mode of the mission or `Menu`.
+  pub folder: String,
+
+  /// Unix timestamp in seconds at which the screenshot was taken.
+  pub taken_at: u64,
+
+  /// Size of the file in bytes.
+  pub size: u64,
+}
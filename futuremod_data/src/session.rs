@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+/// Log target of the message that the game exits and the engine shut down gracefully.
+pub const GAME_EXIT_LOG_TARGET: &str = "game_exit";
+
+/// Statistics of the current game session, i.e., since the mod was injected.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+  /// Unix timestamp in seconds at which the mod was injected.
+  pub injected_at: u64,
+
+  /// Seconds since the mod was injected.
+  pub uptime: u64,
+
+  /// Number of frames of the mission's game loop.
+  pub frames: u64,
+
+  /// Number of missions started.
+  pub missions: u32,
+
+  /// Number of errors per plugin, e.g., errors thrown by the plugin's `onUpdate` function.
+  pub plugin_errors: HashMap<String, u32>,
+}
+
+/// Name of the file in a session bundle that contains the [`SessionRecording`].
+pub const SESSION_BUNDLE_FILE_NAME: &str = "session.json";
+
+/// State of the session recording.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingState {
+  /// Whether the session is currently recorded.
+  pub recording: bool,
+
+  /// Number of entries in the current or last recording.
+  pub entries: usize,
+
+  /// Whether a recording exists that can be downloaded.
+  pub available: bool,
+}
+
+/// Recording of logs, engine events, and samples of the game's state, e.g., to attach to a bug report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRecording {
+  /// Time the recording started, formatted as RFC 3339.
+  pub started_at: String,
+
+  /// Seconds the recording ran.
+  pub duration: f64,
+
+  /// API version of the engine that recorded the session.
+  pub api_version: u32,
+
+  /// Whether entries were dropped because the recording reached its size limit.
+  pub truncated: bool,
+
+  /// Entries ordered by their time.
+  pub entries: Vec<TimelineEntry>,
+}
+
+/// Entry of a session's timeline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEntry {
+  /// Seconds since the recording started.
+  pub time: f64,
+
+  /// Number of mission frames since the mod was injected.
+  pub frame: u64,
+
+  pub event: TimelineEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TimelineEvent {
+  /// Log record of the engine or a plugin.
+  Log {
+    level: String,
+    target: String,
+    message: String,
+    plugin: Option<String>,
+  },
+
+  /// Something that happened in the engine, e.g., a mission started or a plugin was enabled.
+  Engine {
+    name: String,
+    detail: Option<String>,
+  },
+
+  /// Sample of the game's state, taken regularly while a mission runs.
+  Sample(GameSample),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSample {
+  pub players: Vec<PlayerSample>,
+
+  /// Number of entities in the game's entity list.
+  pub entities: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerSample {
+  pub health: i16,
+  pub position: [u32; 3],
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Setting a plugin declares in its `info.toml` or with the config library.
+///
+/// The GUI shows the settings in a form on the plugin's details page.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Setting {
+  /// Name the plugin reads the setting's value with.
+  pub name: String,
+
+  /// Name shown to the user. The setting's name is shown if not set.
+  #[serde(default)]
+  pub label: Option<String>,
+
+  #[serde(default)]
+  pub description: String,
+
+  #[serde(flatten)]
+  pub kind: SettingKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SettingKind {
+  Boolean {
+    default: bool,
+  },
+  Number {
+    default: f64,
+    #[serde(default)]
+    min: Option<f64>,
+    #[serde(default)]
+    max: Option<f64>,
+  },
+  Enum {
+    options: Vec<String>,
+    default: String,
+  },
+  /// Registered as key binding of the input library. Its key is changed like the plugin's other key bindings.
+  KeyBinding {
+    default: String,
+  },
+}
+
+/// Value of a setting.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum SettingValue {
+  Boolean(bool),
+  Number(f64),
+  /// Option of an enum or key of a key binding.
+  Text(String),
+}
+
+impl Setting {
+  pub fn label(&self) -> &str {
+    self.label.as_deref().unwrap_or(&self.name)
+  }
+
+  pub fn default_value(&self) -> SettingValue {
+    match &self.kind {
+      SettingKind::Boolean { default } => SettingValue::Boolean(*default),
+      SettingKind::Number { default, .. } => SettingValue::Number(*default),
+      SettingKind::Enum { default, .. } => SettingValue::Text(default.clone()),
+      SettingKind::KeyBinding { default } => SettingValue::Text(default.clone()),
+    }
+  }
+
+  /// Check whether the value is valid for the setting.
+  ///
+  /// Keys of key bindings aren't checked, since the engine knows the supported keys.
+  pub fn validate(&self, value: &SettingValue) -> Result<(), String> {
+    match (&self.kind, value) {
+      (SettingKind::Boolean { .. }, SettingValue::Boolean(_)) => Ok(()),
+      (SettingKind::Number { min, max, .. }, SettingValue::Number(number)) => {
+        if !number.is_finite() {
+          return Err(format!("'{}' must be a finite number", self.name));
+        }
+        if min.is_some_and(|min| *number < min) || max.is_some_and(|max| *number > max) {
+          return Err(format!("'{}' must be between {} and {}", self.name, min.unwrap_or(f64::MIN), max.unwrap_or(f64::MAX)));
+        }
+
+        Ok(())
+      },
+      (SettingKind::Enum { options, .. }, SettingValue::Text(option)) => match options.contains(option) {
+        true => Ok(()),
+        false => Err(format!("'{}' must be one of {}", self.name, options.join(", "))),
+      },
+      (SettingKind::KeyBinding { .. }, SettingValue::Text(_)) => Ok(()),
+      _ => Err(format!("'{}' has the wrong type", self.name)),
+    }
+  }
+}
+
+/// Settings of a plugin and their current values.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginSettings {
+  pub settings: Vec<Setting>,
+
+  /// Current value of each setting by the setting's name.
+  pub values: BTreeMap<String, SettingValue>,
+}
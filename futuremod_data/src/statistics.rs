@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Local statistics of how FutureMod is used, summed up over all sessions.
+///
+/// The statistics stay on the user's computer and are never sent anywhere.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageStatistics {
+  /// Unix timestamp in seconds at which the statistics were started, `None` if nothing was recorded yet.
+  #[serde(default)]
+  pub since: Option<u64>,
+
+  /// Seconds the game ran with the mod injected.
+  #[serde(default)]
+  pub playtime: u64,
+
+  /// Number of times the mod was injected.
+  #[serde(default)]
+  pub sessions: u32,
+
+  /// Number of missions started.
+  #[serde(default)]
+  pub missions_started: u32,
+
+  /// Number of missions that were played until their end. The game doesn't tell whether a mission was won.
+  #[serde(default)]
+  pub missions_completed: u32,
+
+  /// Seconds spent in missions.
+  #[serde(default)]
+  pub mission_time: u64,
+
+  /// Usage of each plugin by its name.
+  #[serde(default)]
+  pub plugins: BTreeMap<String, PluginUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginUsage {
+  /// Seconds the plugin was enabled.
+  #[serde(default)]
+  pub enabled_time: u64,
+
+  /// Number of times the plugin was enabled.
+  #[serde(default)]
+  pub enabled_count: u32,
+}
@@ -0,0 +1,61 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Layout of a struct in the game's memory, shown field by field by the GUI's struct viewer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StructDefinition {
+  pub name: String,
+
+  #[serde(default)]
+  pub description: String,
+
+  /// Plugin that registered the definition, or `None` for the definitions of the mod's catalog.
+  #[serde(default)]
+  pub plugin: Option<String>,
+
+  pub fields: Vec<StructField>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StructField {
+  pub name: String,
+
+  /// Offset of the field from the start of the struct in bytes.
+  pub offset: u32,
+
+  /// Type of the field, named like the types of the dangerous library's `readMemory`.
+  ///
+  /// Complex types of plugins are named `complex` and are not read.
+  #[serde(rename = "type")]
+  pub field_type: String,
+}
+
+/// Request to read the instance of a struct at an address.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadStruct {
+  /// Name of the struct's definition.
+  pub definition: String,
+
+  pub address: u32,
+}
+
+/// Current value of a field of a struct instance.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StructFieldValue {
+  pub name: String,
+
+  pub offset: u32,
+
+  #[serde(rename = "type")]
+  pub field_type: String,
+
+  /// Value of the field, or `None` if the memory isn't readable or the type can't be read.
+  pub value: Option<String>,
+
+  /// Bytes of the field as hex, e.g., `0a 00 00 00`.
+  pub hex: Option<String>,
+}
@@ -0,0 +1,83 @@
+use std::fmt::Display;
+
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Version of the API between the GUI and the engine.
+///
+/// Increase it whenever a change to the engine's endpoints or shared data breaks older GUIs or engines.
+/// The GUI refuses to inject an engine with another API version.
+pub const API_VERSION: u32 = 1;
+
+/// Name of the function the engine's DLL exports to report its [`API_VERSION`].
+pub const API_VERSION_EXPORT: &str = "futuremod_api_version";
+
+/// Version of the API plugins use, i.e., the engine's Lua libraries and the plugin functions it calls.
+///
+/// Increase the major version whenever a change breaks existing plugins, and the minor version when adding to the API.
+/// Plugins declare the version they target as `api_version` in their info file.
+pub const PLUGIN_API_VERSION: &str = "1.2.0";
+
+/// Semantic version, e.g., `1.2.0`. Pre-release and build suffixes are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SemanticVersion {
+  pub major: u32,
+  pub minor: u32,
+  pub patch: u32,
+}
+
+impl SemanticVersion {
+  /// Parse the version. The minor and patch versions may be omitted, e.g., `1` is `1.0.0`.
+  pub fn parse(version: &str) -> Option<SemanticVersion> {
+    let version = version.trim().trim_start_matches('v');
+    let version = version.split(['-', '+']).next().unwrap_or_default();
+
+    let mut parts = version.split('.').map(|part| part.parse::<u32>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+
+    if parts.next().is_some() {
+      return None;
+    }
+
+    Some(SemanticVersion { major, minor, patch })
+  }
+
+  /// Whether a plugin that targets this API version runs with the `supported` API version.
+  ///
+  /// The major versions must match, and the plugin can't target a newer minor version than the supported one.
+  pub fn is_compatible_with(&self, supported: &SemanticVersion) -> bool {
+    self.major == supported.major && self.minor <= supported.minor
+  }
+}
+
+impl Display for SemanticVersion {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+  }
+}
+
+/// Releases the GUI looks for updates in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateChannel {
+  /// Only full releases.
+  #[default]
+  Stable,
+  /// Pre-releases in addition to full releases.
+  Beta,
+}
+
+impl UpdateChannel {
+  pub const ALL: [UpdateChannel; 2] = [UpdateChannel::Stable, UpdateChannel::Beta];
+}
+
+impl Display for UpdateChannel {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      UpdateChannel::Stable => f.write_str("Stable"),
+      UpdateChannel::Beta => f.write_str("Beta"),
+    }
+  }
+}
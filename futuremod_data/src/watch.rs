@@ -0,0 +1,15 @@
+use serde_derive::{Deserialize, Serialize};
+
+
+/// Latest result of a watch expression.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchValue {
+  pub expression: String,
+
+  /// Description of the expression's value, or `None` if the expression failed.
+  pub value: Option<String>,
+
+  /// Error of the expression, or `None` if it succeeded.
+  pub error: Option<String>,
+}
@@ -0,0 +1,9 @@
+fn main() {
+    // The mock environment simulates the game's memory at the game's image base.
+    // Move the mock executable out of the way, so that the region is free.
+    // The arguments are MSVC linker arguments, other linkers don't understand them.
+    if std::env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("msvc") {
+        println!("cargo:rustc-link-arg-bin=futuremod_mock=/BASE:0x10000000");
+        println!("cargo:rustc-link-arg-bin=futuremod_mock=/DYNAMICBASE:NO");
+    }
+}
@@ -2,7 +2,7 @@ use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 
-use crate::futurecop::{self, RenderCharacterFunction, RENDER_CHARACTER_FUNCTION_ADDRESS};
+use crate::futurecop;
 
 
 /// Renders a character onto the screen at the position with a palette.
@@ -12,11 +12,7 @@ use crate::futurecop::{self, RenderCharacterFunction, RENDER_CHARACTER_FUNCTION_
 /// Directly calls an internal game function to accomplish the rendering.
 /// **For now, this function does not perform any runtime checks to avoid crashes, so be careful.**
 pub fn render_character(character: u32, pos_x: u32, pos_y: u32, palette: u32) -> u32 {
-    let fn_ptr = RENDER_CHARACTER_FUNCTION_ADDRESS as *const();
-    unsafe {
-        let render_character_fn = {std::mem::transmute::<_, RenderCharacterFunction>(fn_ptr)};
-        render_character_fn(character, pos_x, pos_y, palette)
-    }
+    futurecop::render_character(character, pos_x, pos_y, palette)
 }
 
 /// Render text at a position with a specific palette.
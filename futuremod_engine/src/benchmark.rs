@@ -0,0 +1,294 @@
+use std::{sync::Mutex, time::{Duration, Instant}};
+
+use futuremod_data::benchmark::{BenchmarkResult, BenchmarkState, PluginOverhead};
+use log::*;
+
+use crate::plugins::PluginManager;
+
+/// Frames skipped after enabling a plugin before measuring.
+///
+/// Plugins often do more work in their first frames, e.g., to set up their state.
+const WARMUP_FRAMES: u32 = 30;
+
+lazy_static! {
+    static ref BENCHMARK: Mutex<Benchmark> = Mutex::new(Benchmark::Idle);
+}
+
+/// Benchmark that measures the frame time with all plugins disabled and then with every plugin enabled on its own.
+///
+/// The benchmark is driven by the mission's game loop. Thus, it only measures while a mission is running.
+/// Plugins are enabled and disabled without persisting the change.
+/// When the benchmark finishes or is cancelled, plugins that were enabled before are enabled again.
+enum Benchmark {
+    Idle,
+    Waiting { frames: u32 },
+    Running(Run),
+    Finished(BenchmarkResult),
+    Cancelled,
+}
+
+struct Run {
+    frames: u32,
+
+    /// Plugins that were enabled when the benchmark started. Measured in this order.
+    plugins: Vec<String>,
+
+    /// Index of the plugin that is currently measured. `None` while measuring the baseline.
+    current: Option<usize>,
+
+    last_frame: Option<Instant>,
+    warmup: u32,
+    measured_frames: u32,
+    frame_time: Duration,
+    update_time: Duration,
+
+    /// Average frame time without plugins in milliseconds.
+    baseline: f64,
+    results: Vec<PluginOverhead>,
+}
+
+impl Run {
+    fn new(frames: u32, plugins: Vec<String>) -> Self {
+        Run {
+            frames,
+            plugins,
+            current: None,
+            last_frame: None,
+            warmup: WARMUP_FRAMES,
+            measured_frames: 0,
+            frame_time: Duration::ZERO,
+            update_time: Duration::ZERO,
+            baseline: 0.0,
+            results: Vec::new(),
+        }
+    }
+
+    fn state(&self) -> BenchmarkState {
+        BenchmarkState::Running {
+            plugin: self.current.map(|index| self.plugins[index].clone()),
+            finished: self.current.map_or(0, |index| index + 1),
+            total: self.plugins.len() + 1,
+        }
+    }
+
+    /// Start measuring the next plugin. Returns `false` if all plugins were measured.
+    fn next(&mut self, plugin_manager: &mut PluginManager) -> bool {
+        if let Some(index) = self.current {
+            set_enabled(plugin_manager, &self.plugins[index], false);
+        }
+
+        let next = self.current.map_or(0, |index| index + 1);
+        if next >= self.plugins.len() {
+            return false;
+        }
+
+        self.current = Some(next);
+        self.last_frame = None;
+        self.warmup = WARMUP_FRAMES;
+        self.measured_frames = 0;
+        self.frame_time = Duration::ZERO;
+        self.update_time = Duration::ZERO;
+
+        info!("Benchmark: measuring plugin '{}'", self.plugins[next]);
+        set_enabled(plugin_manager, &self.plugins[next], true);
+
+        true
+    }
+}
+
+/// Start a benchmark.
+///
+/// The benchmark starts measuring with the next frame of a running mission.
+pub fn start(frames: u32) -> Result<(), anyhow::Error> {
+    let mut benchmark = BENCHMARK.lock().map_err(|e| anyhow::anyhow!("could not get lock to the benchmark: {}", e))?;
+
+    if let Benchmark::Waiting { .. } | Benchmark::Running(_) = *benchmark {
+        anyhow::bail!("a benchmark is already running");
+    }
+
+    if frames == 0 {
+        anyhow::bail!("the benchmark must measure at least one frame");
+    }
+
+    info!("Starting benchmark with {} frames per measurement, waiting for a mission", frames);
+    *benchmark = Benchmark::Waiting { frames };
+
+    Ok(())
+}
+
+/// Cancel the running benchmark and enable the plugins that were enabled before it started.
+pub fn cancel(plugin_manager: &mut PluginManager) -> Result<(), anyhow::Error> {
+    let mut benchmark = BENCHMARK.lock().map_err(|e| anyhow::anyhow!("could not get lock to the benchmark: {}", e))?;
+
+    match &*benchmark {
+        Benchmark::Running(run) => restore_plugins(plugin_manager, run),
+        Benchmark::Waiting { .. } => (),
+        _ => anyhow::bail!("no benchmark is running"),
+    }
+
+    info!("Cancelled benchmark");
+    *benchmark = Benchmark::Cancelled;
+
+    Ok(())
+}
+
+/// Cancel the running benchmark when the mission ends and enable the plugins that were enabled before it started.
+///
+/// Frames of the next mission aren't comparable with the frames measured so far, e.g., the baseline.
+/// A benchmark that waits for a mission keeps waiting.
+pub fn on_mission_end(plugin_manager: &mut PluginManager) {
+    let mut benchmark = match BENCHMARK.lock() {
+        Ok(benchmark) => benchmark,
+        Err(e) => {
+            warn!("Could not get lock to the benchmark: {}", e);
+            return;
+        },
+    };
+
+    if let Benchmark::Running(run) = &*benchmark {
+        warn!("Benchmark: the mission ended before the benchmark finished, cancelling it");
+        restore_plugins(plugin_manager, run);
+        *benchmark = Benchmark::Cancelled;
+    }
+}
+
+pub fn get_state() -> Result<BenchmarkState, anyhow::Error> {
+    let benchmark = BENCHMARK.lock().map_err(|e| anyhow::anyhow!("could not get lock to the benchmark: {}", e))?;
+
+    Ok(match &*benchmark {
+        Benchmark::Idle => BenchmarkState::Idle,
+        Benchmark::Waiting { .. } => BenchmarkState::Waiting,
+        Benchmark::Running(run) => run.state(),
+        Benchmark::Finished(result) => BenchmarkState::Finished(result.clone()),
+        Benchmark::Cancelled => BenchmarkState::Cancelled,
+    })
+}
+
+/// Measure the frame.
+///
+/// Must be called once per frame of the mission's game loop after the plugins were updated.
+/// `update_time` is the time it took to update all plugins in this frame.
+pub fn on_frame(plugin_manager: &mut PluginManager, update_time: Duration) {
+    let mut benchmark = match BENCHMARK.lock() {
+        Ok(benchmark) => benchmark,
+        Err(e) => {
+            warn!("Could not get lock to the benchmark: {}", e);
+            return;
+        },
+    };
+
+    let next_state = match &mut *benchmark {
+        Benchmark::Waiting { frames } => {
+            let plugins: Vec<String> = plugin_manager.get_plugins()
+                .iter()
+                .filter(|(_, plugin)| plugin.is_enabled())
+                .map(|(name, _)| name.clone())
+                .collect();
+
+            info!("Benchmark: measuring baseline, temporarily disabling {} plugins", plugins.len());
+            for name in plugins.iter() {
+                set_enabled(plugin_manager, name, false);
+            }
+
+            Some(Benchmark::Running(Run::new(*frames, plugins)))
+        },
+        Benchmark::Running(run) => measure(plugin_manager, run, update_time),
+        _ => None,
+    };
+
+    if let Some(next_state) = next_state {
+        *benchmark = next_state;
+    }
+}
+
+/// Measure a frame of the run. Returns the next state of the benchmark if the run finished.
+fn measure(plugin_manager: &mut PluginManager, run: &mut Run, update_time: Duration) -> Option<Benchmark> {
+    let now = Instant::now();
+    let last_frame = run.last_frame.replace(now)?;
+
+    // The first frames after enabling a plugin are not representative
+    if run.warmup > 0 {
+        run.warmup -= 1;
+        return None;
+    }
+
+    run.frame_time += now - last_frame;
+    run.update_time += update_time;
+    run.measured_frames += 1;
+
+    if run.measured_frames < run.frames {
+        return None;
+    }
+
+    let frame_time = average_ms(run.frame_time, run.measured_frames);
+
+    match run.current {
+        None => {
+            info!("Benchmark: baseline frame time is {:.3} ms", frame_time);
+            run.baseline = frame_time;
+        },
+        Some(index) => {
+            let name = run.plugins[index].clone();
+            let overhead = frame_time - run.baseline;
+
+            info!("Benchmark: plugin '{}' has an overhead of {:.3} ms", name, overhead);
+            run.results.push(PluginOverhead {
+                name,
+                frame_time,
+                update_time: average_ms(run.update_time, run.measured_frames),
+                overhead,
+            });
+        },
+    }
+
+    if run.next(plugin_manager) {
+        return None;
+    }
+
+    restore_plugins(plugin_manager, run);
+
+    let mut plugins = run.results.clone();
+    plugins.sort_by(|a, b| b.overhead.total_cmp(&a.overhead));
+
+    info!("Benchmark finished");
+    Some(Benchmark::Finished(BenchmarkResult {
+        frames: run.frames,
+        baseline_frame_time: run.baseline,
+        plugins,
+    }))
+}
+
+/// Enable all plugins that were enabled before the run started and disable the rest.
+fn restore_plugins(plugin_manager: &mut PluginManager, run: &Run) {
+    if let Some(index) = run.current {
+        set_enabled(plugin_manager, &run.plugins[index], false);
+    }
+
+    for name in run.plugins.iter() {
+        set_enabled(plugin_manager, name, true);
+    }
+}
+
+/// Enable or disable the plugin without persisting the change.
+fn set_enabled(plugin_manager: &mut PluginManager, name: &str, enabled: bool) {
+    let plugin = match plugin_manager.plugins.get_mut(name) {
+        Some(plugin) => plugin,
+        None => {
+            warn!("Benchmark: plugin '{}' doesn't exist anymore", name);
+            return;
+        },
+    };
+
+    let result = match enabled {
+        true => plugin.enable(),
+        false => plugin.disable(),
+    };
+
+    if let Err(e) = result {
+        warn!("Benchmark: could not {} plugin '{}': {:?}", if enabled { "enable" } else { "disable" }, name, e);
+    }
+}
+
+fn average_ms(total: Duration, frames: u32) -> f64 {
+    total.as_secs_f64() * 1000.0 / frames as f64
+}
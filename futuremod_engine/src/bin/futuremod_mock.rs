@@ -0,0 +1,78 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use futuremod_engine::mock::{self, MockOptions, Scenario};
+
+/// Run plugins in a headless mock game environment.
+#[derive(Parser)]
+struct Cli {
+    /// Scenario file to simulate. If omitted, the default scenario is simulated.
+    scenario: Option<PathBuf>,
+
+    /// Directory from which plugins are loaded.
+    #[arg(short, long, default_value_t = String::from("plugins"))]
+    plugins_directory: String,
+
+    /// Start the server, so that the GUI can connect to the mock environment.
+    #[arg(short, long)]
+    server: bool,
+
+    #[arg(
+        long,
+        default_value_t = String::from("INFO"),
+        value_parser = clap::builder::PossibleValuesParser::new(["DEBUG", "INFO", "WARN", "ERROR"]),
+    )]
+    log_level: String,
+}
+
+fn main() -> ExitCode {
+    let args = Cli::parse();
+
+    let scenario = match args.scenario {
+        Some(path) => match Scenario::from_file(&path) {
+            Ok(scenario) => scenario,
+            Err(e) => {
+                eprintln!("{}", e);
+                return ExitCode::FAILURE;
+            },
+        },
+        None => Scenario::default(),
+    };
+
+    let options = MockOptions {
+        scenario,
+        plugins_directory: PathBuf::from(args.plugins_directory),
+        server: args.server,
+        log_level: args.log_level,
+    };
+
+    let summary = match mock::run(options) {
+        Ok(summary) => summary,
+        Err(e) => {
+            eprintln!("Error while running the mock environment: {}", e);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    if summary.errors.is_empty() && summary.replay_mismatches.is_empty() {
+        println!("Simulated {} frames without errors", summary.frames);
+        return ExitCode::SUCCESS;
+    }
+
+    println!("Simulated {} frames, plugins threw {} errors:", summary.frames, summary.errors.len());
+    for (frame, plugin, error) in summary.errors.iter() {
+        println!("- Frame {}, plugin '{}': {}", frame, plugin, error);
+    }
+
+    if !summary.replay_mismatches.is_empty() {
+        println!("{} replayed hook invocations returned a different value:", summary.replay_mismatches.len());
+        for mismatch in summary.replay_mismatches.iter() {
+            println!(
+                "- Frame {}, hook of {:#010x}: recorded {:#x}, replayed {:#x}",
+                mismatch.frame, mismatch.address, mismatch.recorded, mismatch.replayed,
+            );
+        }
+    }
+
+    ExitCode::FAILURE
+}
@@ -0,0 +1,106 @@
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}, sync::Mutex};
+
+use anyhow::{anyhow, bail};
+use futuremod_data::bookmark::{Bookmark, BOOKMARK_TYPES};
+use log::*;
+
+const MAX_NAME_LENGTH: usize = 64;
+
+lazy_static! {
+    static ref BOOKMARKS: Mutex<Bookmarks> = Mutex::new(Bookmarks { bookmarks: BTreeMap::new(), path: None });
+}
+
+/// Named addresses in the game's memory by their name.
+struct Bookmarks {
+    /// Persisted, so they survive restarts of the game.
+    bookmarks: BTreeMap<String, Bookmark>,
+    path: Option<PathBuf>,
+}
+
+impl Bookmarks {
+    fn write_to_file(&self) -> Result<(), anyhow::Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let bookmarks: Vec<&Bookmark> = self.bookmarks.values().collect();
+        let content = serde_json::to_string_pretty(&bookmarks).map_err(|e| anyhow!("could not serialize bookmarks: {}", e))?;
+
+        fs::write(path, content).map_err(|e| anyhow!("could not write bookmarks: {}", e))
+    }
+}
+
+/// Read the persisted bookmarks. Must be called when the mod is injected.
+pub fn init(path: &Path) {
+    debug!("Reading bookmarks from '{}'", path.display());
+
+    let bookmarks: Vec<Bookmark> = match fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(bookmarks) => bookmarks,
+            Err(e) => {
+                warn!("Could not parse the bookmarks, starting without bookmarks: {}", e);
+                Vec::new()
+            },
+        },
+        Err(_) => Vec::new(),
+    };
+
+    match BOOKMARKS.lock() {
+        Ok(mut state) => {
+            state.bookmarks = bookmarks.into_iter().map(|bookmark| (bookmark.name.clone(), bookmark)).collect();
+            state.path = Some(path.to_path_buf());
+        },
+        Err(e) => warn!("Could not get lock to the bookmarks: {}", e),
+    }
+}
+
+/// Get all bookmarks ordered by their name.
+pub fn get_bookmarks() -> Vec<Bookmark> {
+    BOOKMARKS.lock().map(|state| state.bookmarks.values().cloned().collect()).unwrap_or_default()
+}
+
+/// Get the bookmark with the name, or `None` if no such bookmark exists.
+pub fn get(name: &str) -> Option<Bookmark> {
+    BOOKMARKS.lock().ok()?.bookmarks.get(name).cloned()
+}
+
+/// Add the bookmark or replace the bookmark with the same name and persist the bookmarks.
+pub fn set(bookmark: Bookmark) -> Result<(), anyhow::Error> {
+    if bookmark.name.trim().is_empty() || bookmark.name.len() > MAX_NAME_LENGTH {
+        bail!("the name must have between 1 and {} characters", MAX_NAME_LENGTH);
+    }
+    if !BOOKMARK_TYPES.contains(&bookmark.value_type.as_str()) {
+        bail!("'{}' is not a supported type, use one of {}", bookmark.value_type, BOOKMARK_TYPES.join(", "));
+    }
+    if bookmark.address == 0 {
+        bail!("the address must not be 0");
+    }
+
+    let mut state = BOOKMARKS.lock().map_err(|e| anyhow!("could not get lock to the bookmarks: {}", e))?;
+
+    info!("Bookmarking 0x{:x} as '{}'", bookmark.address, bookmark.name);
+    let previous = state.bookmarks.insert(bookmark.name.clone(), bookmark.clone());
+
+    if let Err(e) = state.write_to_file() {
+        match previous {
+            Some(previous) => state.bookmarks.insert(bookmark.name, previous),
+            None => state.bookmarks.remove(&bookmark.name),
+        };
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Remove the bookmark with the name and persist the bookmarks.
+pub fn remove(name: &str) -> Result<(), anyhow::Error> {
+    let mut state = BOOKMARKS.lock().map_err(|e| anyhow!("could not get lock to the bookmarks: {}", e))?;
+
+    if state.bookmarks.remove(name).is_none() {
+        bail!("no bookmark '{}' exists", name);
+    }
+
+    info!("Removed bookmark '{}'", name);
+    state.write_to_file()
+}
@@ -0,0 +1,161 @@
+use std::{ffi::c_void, mem};
+
+use futuremod_hook::native::HookCall;
+use log::*;
+use windows::{core::{s, PCSTR}, Win32::{Foundation::{HINSTANCE, HWND}, System::{Diagnostics::Debug::{IMAGE_DIRECTORY_ENTRY_IMPORT, IMAGE_NT_HEADERS32}, LibraryLoader::{GetModuleHandleA, GetProcAddress}, Memory::{VirtualProtect, PAGE_PROTECTION_FLAGS, PAGE_READWRITE}, SystemServices::{IMAGE_DOS_HEADER, IMAGE_IMPORT_DESCRIPTOR}}, UI::WindowsAndMessaging::*}};
+
+use crate::futurecop::state::FUTURE_COP;
+
+type CreateWindowExAFunction = unsafe extern "system" fn(WINDOW_EX_STYLE, PCSTR, PCSTR, WINDOW_STYLE, i32, i32, i32, i32, HWND, HMENU, HINSTANCE, *const c_void) -> HWND;
+
+static mut ORIGINAL_CREATE_WINDOW: Option<CreateWindowExAFunction> = None;
+
+/// Styles that give a window its border and title bar.
+const BORDER_STYLES: u32 = WS_OVERLAPPEDWINDOW.0 | WS_BORDER.0 | WS_DLGFRAME.0;
+
+/// Extended styles that give a window its border or keep it above all other windows.
+const BORDER_EX_STYLES: u32 = WS_EX_DLGMODALFRAME.0 | WS_EX_WINDOWEDGE.0 | WS_EX_CLIENTEDGE.0 | WS_EX_STATICEDGE.0 | WS_EX_TOPMOST.0;
+
+/// Force the game into borderless windowed mode if enabled. Must be called while the game's threads are suspended.
+///
+/// Hooks the creation of the game's window, in case the mod is injected before the game created it,
+/// and restyles the game's window if it already exists.
+pub fn init(enabled: bool) {
+    if !enabled {
+        return;
+    }
+
+    unsafe {
+        match patch_import(s!("user32.dll"), s!("CreateWindowExA"), create_window as u32) {
+            Some(original) => ORIGINAL_CREATE_WINDOW = Some(mem::transmute::<u32, CreateWindowExAFunction>(original)),
+            None => warn!("Could not hook the creation of the game's window"),
+        }
+
+        let main_window = *FUTURE_COP.handles.main_window.get();
+        if main_window != 0 {
+            make_borderless(HWND(main_window as isize));
+        }
+    }
+}
+
+/// Restore the game's original window creation, e.g., when the engine is torn down.
+pub fn restore() {
+    unsafe {
+        if let Some(original) = ORIGINAL_CREATE_WINDOW {
+            if replace_import(create_window as u32, original as u32).is_none() {
+                warn!("Could not restore the creation of the game's window");
+            }
+
+            ORIGINAL_CREATE_WINDOW = None;
+        }
+    }
+}
+
+/// Replace the function the game imports from the module with the hook by patching the game's import address table.
+///
+/// Returns the address of the original function, or `None` if the game doesn't import it.
+pub(crate) unsafe fn patch_import(module_name: PCSTR, function_name: PCSTR, hook: u32) -> Option<u32> {
+    let function = GetProcAddress(GetModuleHandleA(module_name).ok()?, function_name)? as u32;
+
+    replace_import(function, hook).map(|_| function)
+}
+
+/// Replace the function in the game's import address table with the replacement.
+///
+/// Returns `None` if the game's import address table doesn't contain the function.
+pub(crate) unsafe fn replace_import(function: u32, replacement: u32) -> Option<()> {
+    let game = GetModuleHandleA(PCSTR::null()).ok()?.0 as u32;
+
+    let dos_header = &*(game as *const IMAGE_DOS_HEADER);
+    let nt_headers = &*((game + dos_header.e_lfanew as u32) as *const IMAGE_NT_HEADERS32);
+    let import_directory = &nt_headers.OptionalHeader.DataDirectory[IMAGE_DIRECTORY_ENTRY_IMPORT.0 as usize];
+
+    if import_directory.VirtualAddress == 0 {
+        return None;
+    }
+
+    let mut descriptor = (game + import_directory.VirtualAddress) as *const IMAGE_IMPORT_DESCRIPTOR;
+    while (*descriptor).Name != 0 {
+        let mut thunk = (game + (*descriptor).FirstThunk) as *mut u32;
+
+        while *thunk != 0 {
+            if *thunk == function {
+                let mut old_protect = PAGE_PROTECTION_FLAGS::default();
+                VirtualProtect(thunk as *const c_void, mem::size_of::<u32>(), PAGE_READWRITE, &mut old_protect).ok()?;
+
+                *thunk = replacement;
+
+                let _ = VirtualProtect(thunk as *const c_void, mem::size_of::<u32>(), old_protect, &mut old_protect);
+
+                return Some(());
+            }
+
+            thunk = thunk.add(1);
+        }
+
+        descriptor = descriptor.add(1);
+    }
+
+    None
+}
+
+fn borderless_style(style: WINDOW_STYLE) -> WINDOW_STYLE {
+    WINDOW_STYLE((style.0 & !BORDER_STYLES) | WS_POPUP.0)
+}
+
+fn borderless_ex_style(ex_style: WINDOW_EX_STYLE) -> WINDOW_EX_STYLE {
+    WINDOW_EX_STYLE(ex_style.0 & !BORDER_EX_STYLES)
+}
+
+/// Size of the primary monitor.
+fn screen_size() -> (i32, i32) {
+    unsafe { (GetSystemMetrics(SM_CXSCREEN), GetSystemMetrics(SM_CYSCREEN)) }
+}
+
+/// Create the game's top-level windows without border, covering the entire screen.
+#[allow(clippy::too_many_arguments)]
+unsafe extern "system" fn create_window(
+    ex_style: WINDOW_EX_STYLE,
+    class_name: PCSTR,
+    window_name: PCSTR,
+    style: WINDOW_STYLE,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    parent: HWND,
+    menu: HMENU,
+    instance: HINSTANCE,
+    param: *const c_void,
+) -> HWND {
+    let _call = HookCall::enter();
+
+    let original = match ORIGINAL_CREATE_WINDOW {
+        Some(original) => original,
+        None => return HWND::default(),
+    };
+
+    if parent.0 != 0 || style.0 & WS_CHILD.0 != 0 {
+        return original(ex_style, class_name, window_name, style, x, y, width, height, parent, menu, instance, param);
+    }
+
+    let (screen_width, screen_height) = screen_size();
+    info!("Creating the game's window borderless with {}x{}", screen_width, screen_height);
+
+    original(borderless_ex_style(ex_style), class_name, window_name, borderless_style(style), 0, 0, screen_width, screen_height, parent, menu, instance, param)
+}
+
+/// Remove the border of an existing window and resize it to cover the entire screen.
+unsafe fn make_borderless(window: HWND) {
+    let style = WINDOW_STYLE(GetWindowLongA(window, GWL_STYLE) as u32);
+    let ex_style = WINDOW_EX_STYLE(GetWindowLongA(window, GWL_EXSTYLE) as u32);
+
+    SetWindowLongA(window, GWL_STYLE, borderless_style(style).0 as i32);
+    SetWindowLongA(window, GWL_EXSTYLE, borderless_ex_style(ex_style).0 as i32);
+
+    let (screen_width, screen_height) = screen_size();
+    match SetWindowPos(window, HWND_NOTOPMOST, 0, 0, screen_width, screen_height, SWP_FRAMECHANGED | SWP_SHOWWINDOW) {
+        Ok(_) => info!("Made the game's window borderless with {}x{}", screen_width, screen_height),
+        Err(e) => warn!("Could not resize the game's window: {}", e),
+    }
+}
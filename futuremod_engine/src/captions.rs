@@ -0,0 +1,230 @@
+use std::{collections::VecDeque, sync::Mutex, time::{Duration, Instant}};
+
+use log::*;
+
+use crate::{api::ui::{render_rectangle, render_text, Color, TextPalette}, config::{CaptionPosition, CaptionSize, CaptionsConfig}, viewport};
+
+/// Approximate width of a character of the game's font.
+const CHARACTER_WIDTH: u32 = 5;
+
+const LINE_HEIGHT: u32 = 10;
+
+/// Distance of the caption area to the top or bottom of the screen.
+const MARGIN: u32 = 12;
+
+/// Space between the caption's background and its text.
+const PADDING: u32 = 3;
+
+/// Captions that are queued at the same time. Further captions are dropped.
+const MAX_QUEUED: usize = 32;
+
+/// Frames that took longer, e.g., because the game was paused, only count with this time.
+/// Otherwise, captions would be gone after the pause.
+const MAX_FRAME_TIME: Duration = Duration::from_millis(100);
+
+const BACKGROUND: Color = Color { red: 0, green: 0, blue: 0 };
+
+lazy_static! {
+    static ref CAPTIONS: Mutex<Captions> = Mutex::new(Captions::new(&CaptionsConfig::default()));
+}
+
+/// Caption that waits to be shown or is shown.
+struct Caption {
+    /// Who queued the caption, e.g., the plugin's name.
+    source: String,
+    lines: Vec<String>,
+    /// Time the caption is still shown.
+    remaining: Duration,
+}
+
+/// Captions shown one after the other in a styled area of the screen.
+struct Captions {
+    queue: VecDeque<Caption>,
+    position: CaptionPosition,
+    size: CaptionSize,
+    last_frame: Option<Instant>,
+}
+
+impl Captions {
+    fn new(config: &CaptionsConfig) -> Self {
+        Captions {
+            queue: VecDeque::new(),
+            position: config.position,
+            size: config.size,
+            last_frame: None,
+        }
+    }
+}
+
+impl CaptionSize {
+    /// Characters per line.
+    ///
+    /// The game's font can't be scaled. Thus, larger captions use wider lines and show more lines instead.
+    fn line_length(&self) -> usize {
+        match self {
+            CaptionSize::Small => 36,
+            CaptionSize::Medium => 48,
+            CaptionSize::Large => 60,
+        }
+    }
+
+    fn max_lines(&self) -> usize {
+        match self {
+            CaptionSize::Small => 2,
+            CaptionSize::Medium => 3,
+            CaptionSize::Large => 4,
+        }
+    }
+}
+
+/// Configure the position and size of the captions.
+pub fn init(config: &CaptionsConfig) {
+    match CAPTIONS.lock() {
+        Ok(mut captions) => {
+            captions.position = config.position;
+            captions.size = config.size;
+        },
+        Err(e) => warn!("Could not get lock to the captions: {}", e),
+    }
+}
+
+/// Queue the caption to be shown for the duration after the captions queued before it.
+///
+/// Longer text is wrapped and cut off after the configured number of lines.
+pub fn push(source: &str, text: &str, duration: Duration) {
+    let mut captions = match CAPTIONS.lock() {
+        Ok(captions) => captions,
+        Err(e) => {
+            warn!("Could not get lock to the captions: {}", e);
+            return;
+        },
+    };
+
+    if captions.queue.len() >= MAX_QUEUED {
+        warn!("Too many captions are queued, dropping caption of {}", source);
+        return;
+    }
+
+    let lines = wrap(text, captions.size.line_length(), captions.size.max_lines());
+    if lines.is_empty() {
+        return;
+    }
+
+    captions.queue.push_back(Caption { source: source.to_string(), lines, remaining: duration });
+}
+
+/// Remove the shown and queued captions of the source.
+pub fn clear(source: &str) {
+    if let Ok(mut captions) = CAPTIONS.lock() {
+        captions.queue.retain(|caption| caption.source != source);
+    }
+}
+
+/// Render the current caption and advance to the next one once its time is up.
+///
+/// Must be called once per frame of the mission's game loop.
+pub fn on_frame() {
+    let now = Instant::now();
+
+    let (lines, position, size) = {
+        let mut captions = match CAPTIONS.lock() {
+            Ok(captions) => captions,
+            Err(_) => return,
+        };
+
+        let elapsed = captions.last_frame.map(|last_frame| now - last_frame).unwrap_or_default().min(MAX_FRAME_TIME);
+        captions.last_frame = Some(now);
+
+        let is_expired = match captions.queue.front_mut() {
+            Some(caption) => {
+                caption.remaining = caption.remaining.saturating_sub(elapsed);
+                caption.remaining.is_zero()
+            },
+            None => false,
+        };
+
+        if is_expired {
+            captions.queue.pop_front();
+        }
+
+        match captions.queue.front() {
+            Some(caption) => (caption.lines.clone(), captions.position, captions.size),
+            None => return,
+        }
+    };
+
+    // Render without holding the lock, rendering may log
+    render(&lines, position, size);
+}
+
+fn render(lines: &[String], position: CaptionPosition, size: CaptionSize) {
+    let width = size.line_length() as u32 * CHARACTER_WIDTH + 2 * PADDING;
+    let height = lines.len() as u32 * LINE_HEIGHT + 2 * PADDING;
+
+    // The game's font doesn't scale with the resolution, so the area keeps its size and only follows the screen's edges
+    let (screen_width, screen_height) = viewport::screen_size();
+    let x = screen_width.saturating_sub(width) / 2;
+    let y = match position {
+        CaptionPosition::Top => MARGIN,
+        CaptionPosition::Bottom => screen_height.saturating_sub(MARGIN + height),
+    };
+
+    render_rectangle(BACKGROUND, x as u16, y as u16, width as u16, height as u16, true);
+
+    for (index, line) in lines.iter().enumerate() {
+        // Center each line in the area
+        let line_x = x + PADDING + (size.line_length() - line.len()) as u32 * CHARACTER_WIDTH / 2;
+
+        render_text(line_x, y + PADDING + index as u32 * LINE_HEIGHT, TextPalette::White, line);
+    }
+}
+
+/// Wrap the text at spaces into lines of at most `line_length` characters.
+///
+/// Characters the game's font can't render are replaced with spaces, and words longer than a line are split.
+fn wrap(text: &str, line_length: usize, max_lines: usize) -> Vec<String> {
+    let sanitized: String = text
+        .chars()
+        .map(|character| match character.is_ascii_graphic() {
+            true => character,
+            false => ' ',
+        })
+        .collect();
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+
+    for word in sanitized.split_whitespace() {
+        let mut word = word;
+
+        while !word.is_empty() {
+            let separator = match line.is_empty() {
+                true => 0,
+                false => 1,
+            };
+
+            if line.len() + separator + word.len() <= line_length {
+                if separator == 1 {
+                    line.push(' ');
+                }
+                line.push_str(word);
+                break;
+            }
+
+            if line.is_empty() {
+                // Word is longer than a line
+                line.push_str(&word[..line_length]);
+                word = &word[line_length..];
+            }
+
+            lines.push(std::mem::take(&mut line));
+        }
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines.truncate(max_lines);
+    lines
+}
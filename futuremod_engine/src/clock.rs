@@ -0,0 +1,44 @@
+use std::{sync::Mutex, time::{Duration, Instant}};
+
+use log::*;
+
+/// Longest time a single frame advances the clock.
+///
+/// The game loop only runs in missions and stops while the debugger paused it,
+/// time between two missions or spent paused mustn't run out all timers at once.
+const MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+
+lazy_static! {
+    static ref CLOCK: Mutex<Clock> = Mutex::new(Clock { elapsed: Duration::ZERO, last_frame: None });
+}
+
+/// Time that passed in the game loop, independent of the frame rate.
+struct Clock {
+    elapsed: Duration,
+    last_frame: Option<Instant>,
+}
+
+/// Advance the clock by the time since the previous frame. Must be called at the start of every frame of a mission.
+pub fn on_frame() {
+    let mut clock = match CLOCK.lock() {
+        Ok(clock) => clock,
+        Err(e) => {
+            warn!("Could not get lock to the clock: {}", e);
+            return;
+        },
+    };
+
+    let now = Instant::now();
+    if let Some(last_frame) = clock.last_frame {
+        clock.elapsed += now.duration_since(last_frame).min(MAX_FRAME_TIME);
+    }
+    clock.last_frame = Some(now);
+}
+
+/// Seconds the game loop ran since the mod was injected.
+pub fn now() -> f64 {
+    match CLOCK.lock() {
+        Ok(clock) => clock.elapsed.as_secs_f64(),
+        Err(_) => 0.0,
+    }
+}
@@ -0,0 +1,133 @@
+use futuremod_data::accessibility::ColorblindPreset;
+use futuremod_hook::native::{install_hook, HookCall};
+use log::*;
+
+use crate::{api::ui::TextPalette, futurecop::{RenderCharacterFunction, RenderRectangleFunction, RenderTextFunction, RENDER_CHARACTER_FUNCTION_ADDRESS, RENDER_TEXT_FUNCTION_ADDRESS, RENDRE_RECTANGLE_FUNCTION_ADDRESS}};
+
+static mut PRESET: ColorblindPreset = ColorblindPreset::None;
+
+static mut ORIGINAL_RENDER_TEXT: Option<RenderTextFunction> = None;
+static mut ORIGINAL_RENDER_CHARACTER: Option<RenderCharacterFunction> = None;
+static mut ORIGINAL_RENDER_RECTANGLE: Option<RenderRectangleFunction> = None;
+
+/// Bits of a rectangle's color that hold the red, green, and blue value with 5 bits each.
+const RGB_BITS: u32 = 0x7fff;
+
+/// Remap the colors of the game's and the plugins' text and rectangles for the preset.
+/// Must be called while the game's threads are suspended.
+///
+/// Only the text palettes and rectangle colors passed to the game's render functions are remapped. The game's palette
+/// entries of textures and models aren't, as it isn't known yet where the game stores them.
+pub fn init(preset: ColorblindPreset) {
+    if preset == ColorblindPreset::None {
+        return;
+    }
+
+    unsafe {
+        PRESET = preset;
+
+        ORIGINAL_RENDER_TEXT = install_hook(RENDER_TEXT_FUNCTION_ADDRESS as usize, render_text);
+        ORIGINAL_RENDER_CHARACTER = install_hook(RENDER_CHARACTER_FUNCTION_ADDRESS as usize, render_character);
+        ORIGINAL_RENDER_RECTANGLE = install_hook(RENDRE_RECTANGLE_FUNCTION_ADDRESS as usize, render_rectangle);
+
+        if ORIGINAL_RENDER_TEXT.is_none() || ORIGINAL_RENDER_CHARACTER.is_none() || ORIGINAL_RENDER_RECTANGLE.is_none() {
+            warn!("Could not hook all render functions, some colors are not remapped");
+        }
+    }
+
+    info!("Remapping colors for {}", preset);
+}
+
+/// Text palettes replaced by palettes that are distinguishable with the color blindness.
+///
+/// No replacement is replaced again, since the game might render text character by character
+/// and both are remapped.
+fn text_palette_replacements(preset: ColorblindPreset) -> &'static [(TextPalette, TextPalette)] {
+    match preset {
+        ColorblindPreset::None => &[],
+        ColorblindPreset::Protanopia | ColorblindPreset::Deuteranopia => &[
+            (TextPalette::Red, TextPalette::Amber),
+            (TextPalette::LightRed, TextPalette::Yellow),
+            (TextPalette::Green, TextPalette::Blue),
+            (TextPalette::LightGreen, TextPalette::SkyBlue),
+        ],
+        ColorblindPreset::Tritanopia => &[
+            (TextPalette::Blue, TextPalette::Red),
+            (TextPalette::LightBlue, TextPalette::LightRed),
+            (TextPalette::SkyBlue, TextPalette::Pink),
+            (TextPalette::Yellow, TextPalette::White),
+        ],
+    }
+}
+
+fn remap_text_palette(palette: u32) -> u32 {
+    let preset = unsafe { PRESET };
+
+    for (from, to) in text_palette_replacements(preset) {
+        if Into::<u32>::into(*from) == palette {
+            return (*to).into();
+        }
+    }
+
+    palette
+}
+
+/// Matrix that simulates how the color blindness perceives RGB colors.
+fn simulation_matrix(preset: ColorblindPreset) -> Option<[[f32; 3]; 3]> {
+    match preset {
+        ColorblindPreset::None => None,
+        ColorblindPreset::Protanopia => Some([[0.567, 0.433, 0.0], [0.558, 0.442, 0.0], [0.0, 0.242, 0.758]]),
+        ColorblindPreset::Deuteranopia => Some([[0.625, 0.375, 0.0], [0.7, 0.3, 0.0], [0.0, 0.3, 0.7]]),
+        ColorblindPreset::Tritanopia => Some([[0.95, 0.05, 0.0], [0.0, 0.433, 0.567], [0.0, 0.475, 0.525]]),
+    }
+}
+
+/// Shift the difference the color blindness can't perceive into the channels it can perceive (daltonization).
+///
+/// The color has 5 bits per channel in the order red, green, blue from the most significant bits.
+/// All other bits are kept.
+fn remap_rectangle_color(color: u32) -> u32 {
+    let matrix = match simulation_matrix(unsafe { PRESET }) {
+        Some(matrix) => matrix,
+        None => return color,
+    };
+
+    let rgb = [(color >> 10) & 0x1f, (color >> 5) & 0x1f, color & 0x1f].map(|channel| channel as f32 / 31.0);
+
+    let simulated = matrix.map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]);
+    let error = [rgb[0] - simulated[0], rgb[1] - simulated[1], rgb[2] - simulated[2]];
+
+    let corrected = [
+        rgb[0],
+        rgb[1] + 0.7 * error[0] + error[1],
+        rgb[2] + 0.7 * error[0] + error[2],
+    ]
+    .map(|channel| (channel.clamp(0.0, 1.0) * 31.0).round() as u32);
+
+    (color & !RGB_BITS) | (corrected[0] << 10) | (corrected[1] << 5) | corrected[2]
+}
+
+unsafe fn render_text(text: *const u8, pos_x: u32, pos_y: u32, palette: u32) {
+    let _call = HookCall::enter();
+
+    if let Some(original) = ORIGINAL_RENDER_TEXT {
+        original(text, pos_x, pos_y, remap_text_palette(palette));
+    }
+}
+
+unsafe fn render_character(character: u32, pos_x: u32, pos_y: u32, palette: u32) -> u32 {
+    let _call = HookCall::enter();
+
+    match ORIGINAL_RENDER_CHARACTER {
+        Some(original) => original(character, pos_x, pos_y, remap_text_palette(palette)),
+        None => pos_x,
+    }
+}
+
+unsafe fn render_rectangle(color: u32, pos_x: u16, pos_y: u16, width: u16, height: u16, semi_transparent: u8) {
+    let _call = HookCall::enter();
+
+    if let Some(original) = ORIGINAL_RENDER_RECTANGLE {
+        original(remap_rectangle_color(color), pos_x, pos_y, width, height, semi_transparent);
+    }
+}
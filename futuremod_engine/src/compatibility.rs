@@ -0,0 +1,190 @@
+use std::{collections::HashSet, fs, path::{Path, PathBuf}, sync::Mutex, time::{SystemTime, UNIX_EPOCH}};
+
+use anyhow::anyhow;
+use futuremod_data::{compatibility::{CompatibilityEntry, CompatibilityReport}, plugin::PluginInfo};
+use log::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::plugins::persistence;
+
+const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+lazy_static! {
+    static ref COMPATIBILITY: Mutex<Compatibility> = Mutex::new(Compatibility::default());
+}
+
+/// Content of the compatibility file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompatibilityFile {
+    #[serde(default)]
+    enabled: bool,
+
+    #[serde(default)]
+    entries: Vec<CompatibilityEntry>,
+}
+
+/// Local, opt-in collection of which game builds, engine versions, and plugin versions ran together and how often the plugins failed.
+///
+/// Nothing is sent anywhere, users look at the summary in the GUI, e.g., to tell that a plugin only fails with one of the game's builds.
+#[derive(Default)]
+struct Compatibility {
+    file: CompatibilityFile,
+    path: Option<PathBuf>,
+    game_hash: Option<String>,
+
+    /// Plugin versions whose session was already counted, so reloading a plugin doesn't count again.
+    counted: HashSet<(String, String)>,
+    changed: bool,
+}
+
+impl Compatibility {
+    /// Get the entry of the plugin's version with the running game and engine, if the data is collected.
+    fn entry(&mut self, info: &PluginInfo) -> Option<&mut CompatibilityEntry> {
+        if !self.file.enabled {
+            return None;
+        }
+        let game_hash = self.game_hash.as_ref()?;
+
+        let index = self.file.entries.iter().position(|entry| {
+            entry.plugin == info.name
+                && entry.plugin_version == info.version
+                && &entry.game_hash == game_hash
+                && entry.engine_version == ENGINE_VERSION
+        });
+
+        let index = match index {
+            Some(index) => index,
+            None => {
+                self.file.entries.push(CompatibilityEntry {
+                    plugin: info.name.clone(),
+                    plugin_version: info.version.clone(),
+                    game_hash: game_hash.clone(),
+                    engine_version: ENGINE_VERSION.to_string(),
+                    sessions: 0,
+                    errors: 0,
+                    last_error_at: None,
+                });
+                self.file.entries.len() - 1
+            },
+        };
+
+        self.changed = true;
+        self.file.entries.get_mut(index)
+    }
+}
+
+/// Compute the SHA-256 hash of the game's executable, which identifies the game's build.
+fn hash_game_executable() -> Result<String, anyhow::Error> {
+    let path = std::env::current_exe().map_err(|e| anyhow!("could not get the game's executable: {}", e))?;
+    let content = fs::read(&path).map_err(|e| anyhow!("could not read {}: {}", path.display(), e))?;
+
+    Ok(format!("{:x}", Sha256::digest(&content)))
+}
+
+/// Read the collected data and identify the game's build. Must be called when the mod is injected.
+pub fn init(path: &Path) {
+    let file = match persistence::read::<CompatibilityFile>(path) {
+        Ok(file) => file.unwrap_or_default(),
+        Err(e) => {
+            warn!("Could not read the compatibility data, starting over: {}", e);
+            CompatibilityFile::default()
+        },
+    };
+
+    let game_hash = match hash_game_executable() {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            warn!("Could not identify the game's build, not collecting compatibility data: {}", e);
+            None
+        },
+    };
+
+    match COMPATIBILITY.lock() {
+        Ok(mut compatibility) => *compatibility = Compatibility { file, path: Some(path.to_path_buf()), game_hash, ..Default::default() },
+        Err(e) => warn!("Could not get lock to the compatibility data: {}", e),
+    }
+}
+
+/// Count the session of the plugin's version, once per session. Must be called when the plugin is enabled.
+pub fn record_session(info: &PluginInfo) {
+    with_compatibility(|compatibility| {
+        if !compatibility.counted.insert((info.name.clone(), info.version.clone())) {
+            return;
+        }
+
+        if let Some(entry) = compatibility.entry(info) {
+            entry.sessions += 1;
+        }
+    });
+}
+
+/// Count an error of the plugin's version.
+pub fn record_error(info: &PluginInfo) {
+    with_compatibility(|compatibility| {
+        if let Some(entry) = compatibility.entry(info) {
+            entry.errors += 1;
+            entry.last_error_at = SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|time| time.as_secs());
+        }
+    });
+}
+
+pub fn get_report() -> Result<CompatibilityReport, anyhow::Error> {
+    let compatibility = COMPATIBILITY.lock().map_err(|e| anyhow!("could not get lock to the compatibility data: {}", e))?;
+
+    Ok(CompatibilityReport {
+        enabled: compatibility.file.enabled,
+        game_hash: compatibility.game_hash.clone(),
+        engine_version: ENGINE_VERSION.to_string(),
+        entries: compatibility.file.entries.clone(),
+    })
+}
+
+/// Enable or disable collecting the data. The collected data is kept when disabling it.
+pub fn set_enabled(enabled: bool) -> Result<(), anyhow::Error> {
+    {
+        let mut compatibility = COMPATIBILITY.lock().map_err(|e| anyhow!("could not get lock to the compatibility data: {}", e))?;
+        compatibility.file.enabled = enabled;
+        compatibility.changed = true;
+    }
+
+    info!("{} collecting compatibility data", if enabled { "Started" } else { "Stopped" });
+    persist()
+}
+
+/// Delete all collected data.
+pub fn clear() -> Result<(), anyhow::Error> {
+    {
+        let mut compatibility = COMPATIBILITY.lock().map_err(|e| anyhow!("could not get lock to the compatibility data: {}", e))?;
+        compatibility.file.entries.clear();
+        compatibility.counted.clear();
+        compatibility.changed = true;
+    }
+
+    info!("Cleared the compatibility data");
+    persist()
+}
+
+/// Write the collected data if it changed.
+pub fn persist() -> Result<(), anyhow::Error> {
+    let mut compatibility = COMPATIBILITY.lock().map_err(|e| anyhow!("could not get lock to the compatibility data: {}", e))?;
+
+    let path = match (&compatibility.path, compatibility.changed) {
+        (Some(path), true) => path.clone(),
+        _ => return Ok(()),
+    };
+
+    let content = serde_json::to_vec_pretty(&compatibility.file).map_err(|e| anyhow!("could not serialize the compatibility data: {}", e))?;
+    persistence::write(&path, &content)?;
+    compatibility.changed = false;
+
+    Ok(())
+}
+
+fn with_compatibility(f: impl FnOnce(&mut Compatibility)) {
+    match COMPATIBILITY.lock() {
+        Ok(mut compatibility) => f(&mut compatibility),
+        Err(e) => warn!("Could not get lock to the compatibility data: {}", e),
+    }
+}
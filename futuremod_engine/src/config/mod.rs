@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use futuremod_data::{accessibility::ColorblindPreset, input::InputRemap, plugin::PluginDependency};
 use serde::{Serialize, Deserialize};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -14,6 +17,359 @@ pub struct SprintConfig {
     pub player_two: u32,
 }
 
+/// Developer option to record invocations of selected hooks to a file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookRecordingConfig {
+    /// Addresses of the hooked functions to record.
+    pub addresses: Vec<u32>,
+
+    /// File the invocations are written to.
+    pub file: String,
+}
+
+/// Developer option to record the pressed keys per frame or to replay a recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputRecordingConfig {
+    /// File the pressed keys are written to or read from.
+    pub file: String,
+
+    #[serde(default)]
+    pub mode: InputRecordingMode,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InputRecordingMode {
+    #[default]
+    Record,
+    Playback,
+}
+
+/// In-game overlay that shows the latest warnings and errors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogOverlayConfig {
+    /// Key that shows and hides the overlay, e.g., `F9`.
+    #[serde(default = "default_log_overlay_toggle_key")]
+    pub toggle_key: String,
+
+    /// Number of log lines the overlay shows.
+    #[serde(default = "default_log_overlay_lines")]
+    pub lines: usize,
+}
+
+fn default_log_overlay_toggle_key() -> String {
+    "F9".to_string()
+}
+
+fn default_log_overlay_lines() -> usize {
+    8
+}
+
+impl Default for LogOverlayConfig {
+    fn default() -> Self {
+        LogOverlayConfig {
+            toggle_key: default_log_overlay_toggle_key(),
+            lines: default_log_overlay_lines(),
+        }
+    }
+}
+
+/// In-game overlay that shows the FPS, the frame, the players' positions, enabled plugins, the latest errors, and
+/// the lines plugins add to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebugOverlayConfig {
+    /// Key that shows and hides the overlay, e.g., `F8`.
+    #[serde(default = "default_debug_overlay_toggle_key")]
+    pub toggle_key: String,
+}
+
+fn default_debug_overlay_toggle_key() -> String {
+    "F8".to_string()
+}
+
+impl Default for DebugOverlayConfig {
+    fn default() -> Self {
+        DebugOverlayConfig {
+            toggle_key: default_debug_overlay_toggle_key(),
+        }
+    }
+}
+
+/// In-game Lua console for plugin developers, only available in developer mode.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsoleConfig {
+    /// Key that opens and closes the console, e.g., `Grave`.
+    #[serde(default = "default_console_toggle_key")]
+    pub toggle_key: String,
+}
+
+fn default_console_toggle_key() -> String {
+    "Grave".to_string()
+}
+
+impl Default for ConsoleConfig {
+    fn default() -> Self {
+        ConsoleConfig {
+            toggle_key: default_console_toggle_key(),
+        }
+    }
+}
+
+/// Area in which the captions of plugins are shown.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptionsConfig {
+    #[serde(default)]
+    pub position: CaptionPosition,
+
+    /// Width of the caption area and the number of lines it shows.
+    #[serde(default)]
+    pub size: CaptionSize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptionPosition {
+    Top,
+    #[default]
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptionSize {
+    Small,
+    #[default]
+    Medium,
+    Large,
+}
+
+/// Size of the screen space the game renders text and rectangles in.
+///
+/// Resolution patches change it. Plugin HUDs are scaled from their design resolution to it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RenderResolution {
+    /// The game's original resolution, for games without a resolution patch.
+    #[default]
+    Original,
+    /// Size of the game window's client area, for resolution patches that render at the window's size.
+    Window,
+    /// Fixed size, for resolution patches that render at another size than the window's.
+    Fixed { width: u32, height: u32 },
+}
+
+/// Connection to LiveSplit's server component that receives the run timer's events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveSplitConfig {
+    /// Address of the LiveSplit server, by default LiveSplit's default port on the local machine.
+    #[serde(default = "default_live_split_address")]
+    pub address: String,
+}
+
+fn default_live_split_address() -> String {
+    "127.0.0.1:16834".to_string()
+}
+
+/// Publishes the current mission, game mode, and health to Discord's rich presence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RichPresenceConfig {
+    /// Id of the Discord application that is shown as the game.
+    pub client_id: String,
+
+    /// Seconds between updates of the presence.
+    ///
+    /// Discord only accepts a few updates per minute. Thus, shorter intervals don't update the presence faster.
+    #[serde(default = "default_rich_presence_interval")]
+    pub interval: u64,
+}
+
+fn default_rich_presence_interval() -> u64 {
+    15
+}
+
+/// Reports of the enabled plugins and hooked functions for community events, so organizers can verify the plugin set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityConfig {
+    /// URL of the event's verification endpoint the reports are posted to.
+    pub endpoint: String,
+
+    /// Id of the participant sent with every report, e.g., the name the organizers know the player by.
+    pub participant: String,
+
+    /// Seconds between reports while nothing changes. Changes are reported right away.
+    #[serde(default = "default_integrity_interval")]
+    pub interval: u64,
+}
+
+fn default_integrity_interval() -> u64 {
+    60
+}
+
+/// How the Lua garbage collector runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GarbageCollectionConfig {
+    /// Collect garbage in steps after each frame instead of whenever plugins allocate memory.
+    #[serde(default)]
+    pub frame_stepping: bool,
+
+    /// Kilobytes of memory each step collects.
+    #[serde(default = "default_gc_step_size")]
+    pub step_size: u32,
+
+    /// Milliseconds per frame the garbage collector may take when it runs after each frame.
+    #[serde(default = "default_gc_frame_budget")]
+    pub frame_budget: f64,
+}
+
+fn default_gc_step_size() -> u32 {
+    64
+}
+
+fn default_gc_frame_budget() -> f64 {
+    1.0
+}
+
+impl Default for GarbageCollectionConfig {
+    fn default() -> Self {
+        GarbageCollectionConfig {
+            frame_stepping: false,
+            step_size: default_gc_step_size(),
+            frame_budget: default_gc_frame_budget(),
+        }
+    }
+}
+
+/// Limits of what plugins may run, so that a buggy plugin can't freeze the game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionLimitsConfig {
+    /// Instructions, i.e., function calls and loop iterations, each `onUpdate` call of a plugin may run. `0` disables the limit.
+    #[serde(default = "default_update_budget")]
+    pub update_budget: u64,
+
+    /// Megabytes of memory each plugin may use. `0` disables the limit.
+    #[serde(default = "default_memory_limit")]
+    pub memory_limit: u32,
+}
+
+fn default_update_budget() -> u64 {
+    10_000_000
+}
+
+fn default_memory_limit() -> u32 {
+    512
+}
+
+impl Default for ExecutionLimitsConfig {
+    fn default() -> Self {
+        ExecutionLimitsConfig {
+            update_budget: default_update_budget(),
+            memory_limit: default_memory_limit(),
+        }
+    }
+}
+
+/// Screenshots of the game's window saved as PNG.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenshotConfig {
+    /// Key that saves a screenshot, e.g., `F12`.
+    #[serde(default = "default_screenshot_key")]
+    pub key: String,
+
+    /// Directory the screenshots are saved to. Relative paths are relative to the game's directory.
+    #[serde(default = "default_screenshot_directory")]
+    pub directory: String,
+}
+
+fn default_screenshot_key() -> String {
+    "F12".to_string()
+}
+
+fn default_screenshot_directory() -> String {
+    "screenshots".to_string()
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        ScreenshotConfig {
+            key: default_screenshot_key(),
+            directory: default_screenshot_directory(),
+        }
+    }
+}
+
+/// Translates an XInput controller into the keys the game expects.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControllerConfig {
+    /// Index of the controller, from `0` to `3`.
+    #[serde(default)]
+    pub controller: u32,
+
+    /// Fraction of the sticks' and triggers' range that is ignored, from `0.0` to `1.0`.
+    #[serde(default = "default_controller_deadzone")]
+    pub deadzone: f32,
+
+    /// Multiplier of the sticks' and triggers' deflection. Higher values press keys with less deflection.
+    #[serde(default = "default_controller_sensitivity")]
+    pub sensitivity: f32,
+
+    /// Keys pressed by the controller's inputs, e.g., `"A": "KeySpace"`.
+    #[serde(default = "default_controller_bindings")]
+    pub bindings: HashMap<String, String>,
+}
+
+fn default_controller_deadzone() -> f32 {
+    0.25
+}
+
+fn default_controller_sensitivity() -> f32 {
+    1.0
+}
+
+fn default_controller_bindings() -> HashMap<String, String> {
+    [
+        ("LeftStickUp", "KeyUp"),
+        ("LeftStickDown", "KeyDown"),
+        ("LeftStickLeft", "KeyLeft"),
+        ("LeftStickRight", "KeyRight"),
+        ("DpadUp", "KeyUp"),
+        ("DpadDown", "KeyDown"),
+        ("DpadLeft", "KeyLeft"),
+        ("DpadRight", "KeyRight"),
+        ("Start", "KeyEscape"),
+    ]
+    .into_iter()
+    .map(|(input, key)| (input.to_string(), key.to_string()))
+    .collect()
+}
+
+/// Small Lua snippet that runs at startup without a plugin package.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TweakConfig {
+    /// Name of the tweak shown in the logs.
+    pub name: String,
+
+    /// Lua code of the tweak. If it defines an `onUpdate` function, it's called every frame of a mission.
+    pub script: String,
+
+    /// Libraries the tweak uses, set as globals. Libraries that access memory directly, the filesystem, or the network aren't available.
+    #[serde(default)]
+    pub dependencies: Vec<PluginDependency>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
@@ -30,10 +386,122 @@ pub struct Config {
     /// the games root directory. For example: `C:\\Program Files (x86)\\Electronic Arts\\Future Cop\\plugins`
     pub plugins_directory: Option<String>,
 
+    /// Fixed path to the directory uploaded plugin packages and backups are extracted to.
+    ///
+    /// If this is None, the directory "fcop" in the system's temporary directory is used.
+    #[serde(default)]
+    pub temporary_directory: Option<String>,
+
     /// Optional sprint config that specifies for both players their sprint key.
     /// 
     /// As the sprint mod should be shifted to an actual plugin this will be removed in the future.
     pub sprint_config: Option<SprintConfig>,
+
+    /// Optional developer option to record invocations of plugin hooks.
+    /// 
+    /// Recordings can be replayed in the mock environment.
+    #[serde(default)]
+    pub hook_recording: Option<HookRecordingConfig>,
+
+    /// Optional developer option to record the pressed keys per frame or to replay them.
+    #[serde(default)]
+    pub input_recording: Option<InputRecordingConfig>,
+
+    /// Developer option to audit what plugins access.
+    ///
+    /// Records calls to the dangerous library, the memory addresses passed to it, and the files plugins load.
+    #[serde(default)]
+    pub audit: bool,
+
+    /// In-game overlay that shows the latest warnings and errors.
+    #[serde(default)]
+    pub log_overlay: LogOverlayConfig,
+
+    /// In-game overlay with the state of the game and the mod, for plugin developers.
+    #[serde(default)]
+    pub debug_overlay: DebugOverlayConfig,
+
+    /// Show the FPS, frame time, and the time spent in plugins in-game.
+    #[serde(default)]
+    pub performance_overlay: bool,
+
+    /// Position and size of the captions plugins show.
+    #[serde(default)]
+    pub captions: CaptionsConfig,
+
+    /// Size of the screen space the game renders text and rectangles in. Set it if the game runs with a resolution patch.
+    #[serde(default)]
+    pub render_resolution: RenderResolution,
+
+    /// Optional connection to LiveSplit that starts and splits LiveSplit's timer together with the run timer.
+    #[serde(default)]
+    pub live_split: Option<LiveSplitConfig>,
+
+    /// Optional Discord rich presence. Disabled by default.
+    #[serde(default)]
+    pub rich_presence: Option<RichPresenceConfig>,
+
+    /// Optional integrity reports for community events. Disabled by default.
+    #[serde(default)]
+    pub integrity: Option<IntegrityConfig>,
+
+    /// Keys that are replaced with other keys while the game has the focus.
+    ///
+    /// Set by the GUI before injecting the mod.
+    #[serde(default)]
+    pub input_remaps: Vec<InputRemap>,
+
+    /// Optional translation of an XInput controller into key presses.
+    #[serde(default)]
+    pub controller: Option<ControllerConfig>,
+
+    /// Force the game's window into borderless windowed mode.
+    ///
+    /// Set by the GUI before injecting the mod.
+    #[serde(default)]
+    pub borderless_window: bool,
+
+    /// Remap the colors of text and rectangles for a kind of color blindness.
+    ///
+    /// Set by the GUI before injecting the mod.
+    #[serde(default)]
+    pub colorblind_preset: ColorblindPreset,
+
+    /// Key that saves a screenshot of the game and the directory of the screenshots.
+    #[serde(default)]
+    pub screenshots: ScreenshotConfig,
+
+    /// Seconds after enabling a plugin in which a crash makes the plugin a suspect.
+    ///
+    /// Suspects start disabled on the next start, so a crashing plugin can't lock users out of the game.
+    #[serde(default = "default_crash_suspect_window")]
+    pub crash_suspect_window: u64,
+
+    /// Developer option to stop plugins at errors and capture their state, e.g., their latest log lines.
+    #[serde(default)]
+    pub error_breakpoints: bool,
+
+    /// How the Lua garbage collector runs, e.g., in steps limited to a budget per frame.
+    #[serde(default)]
+    pub garbage_collection: GarbageCollectionConfig,
+
+    /// Instruction budget of `onUpdate` calls and memory limit of the plugins. Plugins that exceed them are disabled.
+    #[serde(default)]
+    pub execution_limits: ExecutionLimitsConfig,
+
+    /// Small Lua snippets that run in a restricted environment at startup, for tweaks that don't need a plugin.
+    #[serde(default)]
+    pub tweaks: Vec<TweakConfig>,
+
+    /// Make the developer tools available in-game, e.g., the Lua console.
+    ///
+    /// Set by the GUI before injecting the mod.
+    #[serde(default)]
+    pub developer_mode: bool,
+
+    /// In-game Lua console, only available in developer mode.
+    #[serde(default)]
+    pub console: ConsoleConfig,
 }
 
 fn default_server() -> ServerConfig {
@@ -47,13 +515,41 @@ fn default_log_level() -> String {
     "INFO".to_string()
 }
 
+fn default_crash_suspect_window() -> u64 {
+    30
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
             server: default_server(),
             log_level: default_log_level(),
             plugins_directory: None,
+            temporary_directory: None,
             sprint_config: None,
+            hook_recording: None,
+            input_recording: None,
+            audit: false,
+            log_overlay: LogOverlayConfig::default(),
+            debug_overlay: DebugOverlayConfig::default(),
+            performance_overlay: false,
+            captions: CaptionsConfig::default(),
+            render_resolution: RenderResolution::Original,
+            live_split: None,
+            rich_presence: None,
+            integrity: None,
+            input_remaps: Vec::new(),
+            controller: None,
+            borderless_window: false,
+            colorblind_preset: ColorblindPreset::None,
+            screenshots: ScreenshotConfig::default(),
+            crash_suspect_window: default_crash_suspect_window(),
+            error_breakpoints: false,
+            garbage_collection: GarbageCollectionConfig::default(),
+            execution_limits: ExecutionLimitsConfig::default(),
+            tweaks: Vec::new(),
+            developer_mode: false,
+            console: ConsoleConfig::default(),
         }
     }
 }
\ No newline at end of file
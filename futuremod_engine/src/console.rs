@@ -0,0 +1,377 @@
+use std::{collections::VecDeque, sync::{atomic::{AtomicBool, AtomicU16, Ordering}, Mutex}, time::Duration};
+
+use log::*;
+use windows::Win32::UI::Input::KeyboardAndMouse::*;
+
+use crate::{api::ui::{render_rectangle, render_text, Color, TextPalette}, config::ConsoleConfig, overlay::{sanitize, CHARACTER_WIDTH}, plugins::PluginManager, remapping, viewport};
+
+/// Characters a line of the console shows. Longer lines are wrapped.
+const LINE_LENGTH: usize = 60;
+
+const LINE_HEIGHT: u32 = 10;
+
+/// Lines of output shown above the input line.
+const VISIBLE_LINES: usize = 12;
+
+/// Lines of output the console keeps.
+const MAX_LINES: usize = 200;
+
+/// Chunks the history keeps.
+const MAX_HISTORY: usize = 50;
+
+const MAX_INPUT_LENGTH: usize = 500;
+
+/// Space between the console's background and its text.
+const PADDING: u32 = 3;
+
+/// A chunk is aborted after this time.
+///
+/// Chunks run in the game loop, so a slow chunk would freeze the game.
+const CHUNK_TIMEOUT: Duration = Duration::from_millis(100);
+
+const BACKGROUND: Color = Color { red: 0, green: 0, blue: 0 };
+
+const HELP: [&str; 5] = [
+    "Type Lua and press Enter. Expressions show their value.",
+    ":plugin <name>  run chunks in the plugin's environment",
+    ":dev            run chunks in the developer environment",
+    ":clear          clear the output",
+    "Up/Down browse the history, Escape closes the console.",
+];
+
+lazy_static! {
+    static ref CONSOLE: Mutex<Console> = Mutex::new(Console::new());
+
+    /// Keys pressed while the console is open, handled in the next frame.
+    static ref PENDING_KEYS: Mutex<Vec<ConsoleKey>> = Mutex::new(Vec::new());
+}
+
+/// Whether the console is available, i.e., developer mode is on and the toggle key is valid.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Virtual key that opens and closes the console.
+static TOGGLE_KEY: AtomicU16 = AtomicU16::new(0);
+
+/// Whether the console is open and receives the pressed keys.
+///
+/// Read by the keyboard hook, which must not wait for the console's lock.
+static OPEN: AtomicBool = AtomicBool::new(false);
+
+/// Whether a shift key is held, since the console swallows keys before Windows updates the key state.
+static SHIFT: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ConsoleKey {
+    Character(char),
+    Backspace,
+    Enter,
+    HistoryUp,
+    HistoryDown,
+}
+
+/// In-game Lua console for plugin developers, only available in developer mode and opened with its toggle key during missions.
+///
+/// Developers type Lua chunks that run in the developer environment or in a plugin's environment, e.g., to inspect or
+/// change a plugin's state while the game runs. While the console is open, the keyboard hook passes the pressed keys to
+/// the console instead of the game.
+struct Console {
+    input: String,
+    /// Chunks that were run, newest last.
+    history: VecDeque<String>,
+    /// Position in the history while browsing it, `None` while typing a new chunk.
+    history_index: Option<usize>,
+    lines: VecDeque<(TextPalette, String)>,
+    /// Plugin whose environment chunks run in, `None` for the developer environment.
+    target: Option<String>,
+}
+
+impl Console {
+    fn new() -> Self {
+        let mut console = Console {
+            input: String::new(),
+            history: VecDeque::new(),
+            history_index: None,
+            lines: VecDeque::new(),
+            target: None,
+        };
+
+        console.print(TextPalette::Gray, "Lua console. Type :help for help.");
+
+        console
+    }
+
+    fn prompt(&self) -> String {
+        format!("{}> ", self.target.as_deref().unwrap_or("dev"))
+    }
+
+    /// Add the text to the output, wrapped into lines that fit the console.
+    fn print(&mut self, palette: TextPalette, text: &str) {
+        for line in text.lines() {
+            let characters: Vec<char> = sanitize(line, usize::MAX).chars().collect();
+
+            for chunk in characters.chunks(LINE_LENGTH) {
+                self.lines.push_back((palette, chunk.iter().collect()));
+            }
+        }
+
+        while self.lines.len() > MAX_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    fn browse_history(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let index = match (self.history_index, older) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < self.history.len() => Some(index + 1),
+            (Some(_), false) => None,
+        };
+
+        self.history_index = index;
+        self.input = match index {
+            Some(index) => self.history[index].clone(),
+            None => String::new(),
+        };
+    }
+
+    fn submit(&mut self, plugin_manager: &mut PluginManager) {
+        let input = std::mem::take(&mut self.input).trim().to_string();
+        self.history_index = None;
+
+        if input.is_empty() {
+            return;
+        }
+
+        if self.history.back() != Some(&input) {
+            self.history.push_back(input.clone());
+        }
+        while self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+
+        self.print(TextPalette::Yellow, &format!("{}{}", self.prompt(), input));
+
+        if let Some(command) = input.strip_prefix(':') {
+            self.run_command(command, plugin_manager);
+            return;
+        }
+
+        info!("Running console chunk in {}", self.target.as_deref().unwrap_or("the developer environment"));
+
+        match plugin_manager.run_chunk(self.target.as_deref(), &input, CHUNK_TIMEOUT) {
+            Ok(result) => {
+                for line in result.output {
+                    self.print(TextPalette::White, &line);
+                }
+                if let Some(value) = result.value {
+                    self.print(TextPalette::LightGreen, &value);
+                }
+                if let Some(error) = result.error {
+                    self.print(TextPalette::Red, &error);
+                }
+            },
+            Err(e) => self.print(TextPalette::Red, &e.to_string()),
+        }
+    }
+
+    fn run_command(&mut self, command: &str, plugin_manager: &PluginManager) {
+        let (name, argument) = match command.split_once(' ') {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match name {
+            "plugin" if plugin_manager.get_plugins().contains_key(argument) => {
+                self.target = Some(argument.to_string());
+                self.print(TextPalette::Gray, &format!("Running chunks in the environment of plugin '{}'", argument));
+            },
+            "plugin" => self.print(TextPalette::Red, &format!("Plugin '{}' doesn't exist", argument)),
+            "dev" => {
+                self.target = None;
+                self.print(TextPalette::Gray, "Running chunks in the developer environment");
+            },
+            "clear" => self.lines.clear(),
+            "help" => {
+                for line in HELP {
+                    self.print(TextPalette::Gray, line);
+                }
+            },
+            _ => self.print(TextPalette::Red, &format!("Unknown command ':{}', type :help for help", name)),
+        }
+    }
+}
+
+/// Make the console available if developer mode is on. Must be called when the mod is injected.
+pub fn init(config: &ConsoleConfig, developer_mode: bool) {
+    let toggle_key = match remapping::parse_key(&config.toggle_key) {
+        Ok(key) => key.0,
+        Err(e) => {
+            warn!("Invalid toggle key '{}' for the console, the console is disabled: {}", config.toggle_key, e);
+            0
+        },
+    };
+
+    TOGGLE_KEY.store(toggle_key, Ordering::SeqCst);
+    ENABLED.store(developer_mode && toggle_key != 0, Ordering::SeqCst);
+
+    if developer_mode && toggle_key != 0 {
+        info!("The Lua console opens with {}", config.toggle_key);
+
+        // The console reads the keys in the keyboard hook
+        remapping::start_keyboard_hook();
+    }
+}
+
+/// Record the key for the console. Called by the keyboard hook for keys pressed while the game has the focus.
+///
+/// Returns whether the key is swallowed, i.e., all keys pressed while the console is open and the key that opens it.
+/// Releases always reach the game, so keys held when the console opened don't stay held.
+pub(crate) fn record_key(key: VIRTUAL_KEY, is_key_up: bool) -> bool {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return false;
+    }
+
+    if matches!(key, VK_SHIFT | VK_LSHIFT | VK_RSHIFT) {
+        SHIFT.store(!is_key_up, Ordering::Relaxed);
+        return false;
+    }
+
+    if is_key_up {
+        return false;
+    }
+
+    let is_toggle_key = key.0 == TOGGLE_KEY.load(Ordering::Relaxed);
+
+    if !OPEN.load(Ordering::Relaxed) {
+        if is_toggle_key {
+            OPEN.store(true, Ordering::Relaxed);
+        }
+
+        return is_toggle_key;
+    }
+
+    let console_key = match key {
+        _ if is_toggle_key => None,
+        VK_ESCAPE => None,
+        VK_RETURN => Some(ConsoleKey::Enter),
+        VK_BACK => Some(ConsoleKey::Backspace),
+        VK_UP => Some(ConsoleKey::HistoryUp),
+        VK_DOWN => Some(ConsoleKey::HistoryDown),
+        key => match character(key, SHIFT.load(Ordering::Relaxed)) {
+            Some(character) => Some(ConsoleKey::Character(character)),
+            // Other keys, e.g., function keys, are swallowed without doing anything
+            None => return true,
+        },
+    };
+
+    match console_key {
+        Some(console_key) => {
+            if let Ok(mut pending_keys) = PENDING_KEYS.try_lock() {
+                pending_keys.push(console_key);
+            }
+        },
+        None => OPEN.store(false, Ordering::Relaxed),
+    }
+
+    true
+}
+
+/// Handle the keys pressed since the last frame and render the console if it's open.
+///
+/// Must be called once per frame of the mission's game loop with the plugin manager, after the plugins were updated.
+pub fn on_frame(plugin_manager: &mut PluginManager) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let keys = match PENDING_KEYS.lock() {
+        Ok(mut pending_keys) => std::mem::take(&mut *pending_keys),
+        Err(_) => return,
+    };
+
+    let mut console = match CONSOLE.lock() {
+        Ok(console) => console,
+        Err(_) => return,
+    };
+
+    for key in keys {
+        match key {
+            ConsoleKey::Character(character) if console.input.len() < MAX_INPUT_LENGTH => console.input.push(character),
+            ConsoleKey::Character(_) => (),
+            ConsoleKey::Backspace => { console.input.pop(); },
+            ConsoleKey::Enter => console.submit(plugin_manager),
+            ConsoleKey::HistoryUp => console.browse_history(true),
+            ConsoleKey::HistoryDown => console.browse_history(false),
+        }
+    }
+
+    if OPEN.load(Ordering::Relaxed) {
+        render(&console);
+    }
+}
+
+/// Render the latest lines of the output and the input line at the bottom of the screen.
+fn render(console: &Console) {
+    let (screen_width, screen_height) = viewport::screen_size();
+    let height = (VISIBLE_LINES as u32 + 1) * LINE_HEIGHT + 2 * PADDING;
+    let pos_y = screen_height.saturating_sub(height);
+
+    render_rectangle(BACKGROUND, 0, pos_y as u16, screen_width.min(u16::MAX as u32) as u16, height as u16, true);
+
+    let lines: Vec<&(TextPalette, String)> = console.lines.iter().rev().take(VISIBLE_LINES).collect();
+    for (index, (palette, line)) in lines.iter().rev().enumerate() {
+        render_text(PADDING, pos_y + PADDING + index as u32 * LINE_HEIGHT, *palette, line);
+    }
+
+    // Long inputs show their end, where the developer types
+    let input: Vec<char> = sanitize(&format!("{}{}_", console.prompt(), console.input), usize::MAX).chars().collect();
+    let visible_input: String = input[input.len().saturating_sub(LINE_LENGTH)..].iter().collect();
+    let input_x = PADDING.min(screen_width.saturating_sub(LINE_LENGTH as u32 * CHARACTER_WIDTH));
+
+    render_text(input_x, pos_y + PADDING + VISIBLE_LINES as u32 * LINE_HEIGHT, TextPalette::White, &visible_input);
+}
+
+/// Character the key types with the US keyboard layout, `None` for keys that don't type a character.
+fn character(key: VIRTUAL_KEY, shift: bool) -> Option<char> {
+    const SHIFTED_DIGITS: [char; 10] = [')', '!', '@', '#', '$', '%', '^', '&', '*', '('];
+
+    let (normal, shifted) = match key {
+        VIRTUAL_KEY(code) if (VK_A.0..=VK_Z.0).contains(&code) => {
+            let letter = code as u8 as char;
+            (letter.to_ascii_lowercase(), letter)
+        },
+        VIRTUAL_KEY(code) if (VK_0.0..=VK_9.0).contains(&code) => {
+            let digit = (code - VK_0.0) as usize;
+            (char::from(b'0' + digit as u8), SHIFTED_DIGITS[digit])
+        },
+        VIRTUAL_KEY(code) if (VK_NUMPAD0.0..=VK_NUMPAD9.0).contains(&code) => {
+            let digit = char::from(b'0' + (code - VK_NUMPAD0.0) as u8);
+            (digit, digit)
+        },
+        VK_SPACE => (' ', ' '),
+        VK_OEM_MINUS => ('-', '_'),
+        VK_OEM_PLUS => ('=', '+'),
+        VK_OEM_4 => ('[', '{'),
+        VK_OEM_6 => (']', '}'),
+        VK_OEM_5 => ('\\', '|'),
+        VK_OEM_1 => (';', ':'),
+        VK_OEM_7 => ('\'', '"'),
+        VK_OEM_COMMA => (',', '<'),
+        VK_OEM_PERIOD => ('.', '>'),
+        VK_OEM_2 => ('/', '?'),
+        VK_OEM_3 => ('`', '~'),
+        VK_ADD => ('+', '+'),
+        VK_SUBTRACT => ('-', '-'),
+        VK_MULTIPLY => ('*', '*'),
+        VK_DIVIDE => ('/', '/'),
+        VK_DECIMAL => ('.', '.'),
+        _ => return None,
+    };
+
+    Some(if shift { shifted } else { normal })
+}
@@ -0,0 +1,193 @@
+use std::{collections::HashSet, str::FromStr, thread, time::Duration};
+
+use anyhow::anyhow;
+use log::*;
+use windows::Win32::UI::Input::{KeyboardAndMouse::VIRTUAL_KEY, XboxController::*};
+
+use crate::{config::ControllerConfig, remapping::{is_game_focused, parse_key, send_key}, teardown};
+
+/// Interval in which the controller's state is read.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Analog inputs press their key once their value, after applying the deadzone and sensitivity, reaches this threshold.
+pub(crate) const PRESS_THRESHOLD: f32 = 0.5;
+
+/// Input of an XInput controller that can be bound to a key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ControllerInput {
+    A,
+    B,
+    X,
+    Y,
+    LeftShoulder,
+    RightShoulder,
+    Back,
+    Start,
+    LeftThumb,
+    RightThumb,
+    DpadUp,
+    DpadDown,
+    DpadLeft,
+    DpadRight,
+    LeftTrigger,
+    RightTrigger,
+    LeftStickUp,
+    LeftStickDown,
+    LeftStickLeft,
+    LeftStickRight,
+    RightStickUp,
+    RightStickDown,
+    RightStickLeft,
+    RightStickRight,
+}
+
+impl FromStr for ControllerInput {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = match s {
+            "A" => ControllerInput::A,
+            "B" => ControllerInput::B,
+            "X" => ControllerInput::X,
+            "Y" => ControllerInput::Y,
+            "LeftShoulder" => ControllerInput::LeftShoulder,
+            "RightShoulder" => ControllerInput::RightShoulder,
+            "Back" => ControllerInput::Back,
+            "Start" => ControllerInput::Start,
+            "LeftThumb" => ControllerInput::LeftThumb,
+            "RightThumb" => ControllerInput::RightThumb,
+            "DpadUp" => ControllerInput::DpadUp,
+            "DpadDown" => ControllerInput::DpadDown,
+            "DpadLeft" => ControllerInput::DpadLeft,
+            "DpadRight" => ControllerInput::DpadRight,
+            "LeftTrigger" => ControllerInput::LeftTrigger,
+            "RightTrigger" => ControllerInput::RightTrigger,
+            "LeftStickUp" => ControllerInput::LeftStickUp,
+            "LeftStickDown" => ControllerInput::LeftStickDown,
+            "LeftStickLeft" => ControllerInput::LeftStickLeft,
+            "LeftStickRight" => ControllerInput::LeftStickRight,
+            "RightStickUp" => ControllerInput::RightStickUp,
+            "RightStickDown" => ControllerInput::RightStickDown,
+            "RightStickLeft" => ControllerInput::RightStickLeft,
+            "RightStickRight" => ControllerInput::RightStickRight,
+            _ => return Err(anyhow!("'{}' is not a controller input", s)),
+        };
+
+        Ok(input)
+    }
+}
+
+impl ControllerInput {
+    /// Whether the input is pressed in the controller's state.
+    fn is_pressed(&self, gamepad: &XINPUT_GAMEPAD, deadzone: f32, sensitivity: f32) -> bool {
+        let button = |flag: XINPUT_GAMEPAD_BUTTON_FLAGS| gamepad.wButtons.0 & flag.0 != 0;
+        let analog = |value: f32| apply_deadzone(value, deadzone) * sensitivity >= PRESS_THRESHOLD;
+        let stick = |value: i16| value as f32 / i16::MAX as f32;
+        let trigger = |value: u8| value as f32 / u8::MAX as f32;
+
+        match self {
+            ControllerInput::A => button(XINPUT_GAMEPAD_A),
+            ControllerInput::B => button(XINPUT_GAMEPAD_B),
+            ControllerInput::X => button(XINPUT_GAMEPAD_X),
+            ControllerInput::Y => button(XINPUT_GAMEPAD_Y),
+            ControllerInput::LeftShoulder => button(XINPUT_GAMEPAD_LEFT_SHOULDER),
+            ControllerInput::RightShoulder => button(XINPUT_GAMEPAD_RIGHT_SHOULDER),
+            ControllerInput::Back => button(XINPUT_GAMEPAD_BACK),
+            ControllerInput::Start => button(XINPUT_GAMEPAD_START),
+            ControllerInput::LeftThumb => button(XINPUT_GAMEPAD_LEFT_THUMB),
+            ControllerInput::RightThumb => button(XINPUT_GAMEPAD_RIGHT_THUMB),
+            ControllerInput::DpadUp => button(XINPUT_GAMEPAD_DPAD_UP),
+            ControllerInput::DpadDown => button(XINPUT_GAMEPAD_DPAD_DOWN),
+            ControllerInput::DpadLeft => button(XINPUT_GAMEPAD_DPAD_LEFT),
+            ControllerInput::DpadRight => button(XINPUT_GAMEPAD_DPAD_RIGHT),
+            ControllerInput::LeftTrigger => analog(trigger(gamepad.bLeftTrigger)),
+            ControllerInput::RightTrigger => analog(trigger(gamepad.bRightTrigger)),
+            ControllerInput::LeftStickUp => analog(stick(gamepad.sThumbLY)),
+            ControllerInput::LeftStickDown => analog(-stick(gamepad.sThumbLY)),
+            ControllerInput::LeftStickLeft => analog(-stick(gamepad.sThumbLX)),
+            ControllerInput::LeftStickRight => analog(stick(gamepad.sThumbLX)),
+            ControllerInput::RightStickUp => analog(stick(gamepad.sThumbRY)),
+            ControllerInput::RightStickDown => analog(-stick(gamepad.sThumbRY)),
+            ControllerInput::RightStickLeft => analog(-stick(gamepad.sThumbRX)),
+            ControllerInput::RightStickRight => analog(stick(gamepad.sThumbRX)),
+        }
+    }
+}
+
+/// Rescale the value such that the deadzone maps to `0.0` and the full deflection to `1.0`.
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    if value <= deadzone || deadzone >= 1.0 {
+        return 0.0;
+    }
+
+    (value - deadzone) / (1.0 - deadzone)
+}
+
+/// Start translating the controller into key presses if it is configured. Must be called when the mod is injected.
+pub fn init(config: &Option<ControllerConfig>) {
+    let config = match config {
+        Some(config) => config.clone(),
+        None => return,
+    };
+
+    let mut bindings = Vec::new();
+    for (input, key) in config.bindings.iter() {
+        let binding = input.parse::<ControllerInput>().and_then(|input| Ok((input, parse_key(key)?)));
+
+        match binding {
+            Ok(binding) => bindings.push(binding),
+            Err(e) => warn!("Ignoring invalid controller binding '{}': {}", input, e),
+        }
+    }
+
+    info!("Translating controller {} into {} keys", config.controller, bindings.len());
+
+    thread::spawn(move || run(config, bindings));
+}
+
+fn run(config: ControllerConfig, bindings: Vec<(ControllerInput, VIRTUAL_KEY)>) {
+    let _worker = teardown::worker();
+    let mut pressed: HashSet<u16> = HashSet::new();
+    let mut is_connected = true;
+
+    loop {
+        let mut state = XINPUT_STATE::default();
+        let result = unsafe { XInputGetState(config.controller, &mut state) };
+
+        if (result == 0) != is_connected {
+            is_connected = result == 0;
+
+            match is_connected {
+                true => info!("Controller {} connected", config.controller),
+                false => warn!("Controller {} is not connected", config.controller),
+            }
+        }
+
+        // Release all keys when the game loses the focus, so they don't get stuck
+        let mut wanted = HashSet::new();
+        if is_connected && is_game_focused() {
+            for (input, key) in bindings.iter() {
+                if input.is_pressed(&state.Gamepad, config.deadzone, config.sensitivity) {
+                    wanted.insert(key.0);
+                }
+            }
+        }
+
+        for key in pressed.difference(&wanted) {
+            send_key(VIRTUAL_KEY(*key), true);
+        }
+        for key in wanted.difference(&pressed) {
+            send_key(VIRTUAL_KEY(*key), false);
+        }
+        pressed = wanted;
+
+        if !teardown::sleep(POLL_INTERVAL) {
+            break;
+        }
+    }
+
+    // Keys must not stay pressed once the engine is ejected
+    for key in pressed {
+        send_key(VIRTUAL_KEY(key), true);
+    }
+}
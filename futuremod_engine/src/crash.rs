@@ -0,0 +1,454 @@
+use std::{backtrace::Backtrace, cell::RefCell, ffi::c_void, fs, os::windows::io::AsRawHandle, panic::{self, PanicInfo}, path::{Path, PathBuf}, sync::{atomic::{AtomicBool, AtomicPtr, Ordering}, Mutex, TryLockError}, time::{SystemTime, UNIX_EPOCH}};
+
+use anyhow::anyhow;
+use futuremod_data::crash::{CrashReport, Registers};
+use log::*;
+use windows::Win32::{Foundation::{BOOL, EXCEPTION_ACCESS_VIOLATION, EXCEPTION_ILLEGAL_INSTRUCTION, EXCEPTION_INT_DIVIDE_BY_ZERO, EXCEPTION_IN_PAGE_ERROR, EXCEPTION_PRIV_INSTRUCTION, EXCEPTION_STACK_OVERFLOW, HANDLE, NTSTATUS}, System::{Diagnostics::Debug::*, Threading::{GetCurrentProcess, GetCurrentProcessId, GetCurrentThreadId}}};
+
+use crate::{plugins::{plugin_manager::GlobalPluginManager, suspect}, session};
+
+/// Directory, relative to the game's directory, into which minidumps are written.
+const CRASH_DIRECTORY: &str = "crashes";
+
+/// File in the crash directory containing the report of the last crash.
+const REPORT_FILE: &str = "last-crash.json";
+
+/// Tells windows to continue searching for another exception handler.
+const EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+
+/// Exceptions after which the game can't continue.
+const FATAL_EXCEPTIONS: [NTSTATUS; 6] = [
+    EXCEPTION_ACCESS_VIOLATION,
+    EXCEPTION_ILLEGAL_INSTRUCTION,
+    EXCEPTION_PRIV_INSTRUCTION,
+    EXCEPTION_INT_DIVIDE_BY_ZERO,
+    EXCEPTION_STACK_OVERFLOW,
+    EXCEPTION_IN_PAGE_ERROR,
+];
+
+/// Whether the engine crashed at least once.
+static HAS_CRASHED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a crash report was written in this session.
+static HAS_REPORT: AtomicBool = AtomicBool::new(false);
+
+/// Handle of the vectored exception handler, to remove it when the engine is torn down.
+static VECTORED_HANDLER: AtomicPtr<c_void> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Unhandled exception filter that was set before the engine's.
+static mut PREVIOUS_FILTER: LPTOP_LEVEL_EXCEPTION_FILTER = None;
+
+lazy_static! {
+    /// Report of the last crash, which usually happened in a previous session.
+    static ref LAST_CRASH: Mutex<Option<CrashReport>> = Mutex::new(None);
+}
+
+thread_local! {
+    /// Plugins whose code runs on this thread, the innermost last.
+    ///
+    /// A plugin's code can run inside another plugin's code, e.g., if a plugin calls a native function
+    /// another plugin hooked.
+    static ACTIVE_PLUGINS: RefCell<Vec<ActivePlugin>> = RefCell::new(Vec::new());
+}
+
+#[derive(Debug, Clone)]
+struct ActivePlugin {
+    name: String,
+    /// Address of the hooked function if the plugin's hook runs.
+    hook: Option<u32>,
+}
+
+/// Marks a plugin's code as running on the current thread until it is dropped.
+///
+/// Crashes while the guard exists are attributed to the plugin.
+pub struct ActivePluginGuard(());
+
+impl Drop for ActivePluginGuard {
+    fn drop(&mut self) {
+        let _ = ACTIVE_PLUGINS.try_with(|active| active.borrow_mut().pop());
+    }
+}
+
+/// Mark the plugin's code as running on the current thread, e.g., while calling its `onUpdate` function.
+pub fn enter_plugin(name: &str) -> ActivePluginGuard {
+    enter(ActivePlugin { name: name.to_string(), hook: None })
+}
+
+/// Mark the plugin's hook of the function at the address as running on the current thread.
+pub fn enter_hook(name: &str, address: u32) -> ActivePluginGuard {
+    enter(ActivePlugin { name: name.to_string(), hook: Some(address) })
+}
+
+fn enter(plugin: ActivePlugin) -> ActivePluginGuard {
+    let _ = ACTIVE_PLUGINS.try_with(|active| active.borrow_mut().push(plugin));
+
+    ActivePluginGuard(())
+}
+
+/// Plugin whose code runs on the current thread.
+///
+/// Only tries to borrow, as it is called by the crash handlers, which might interrupt a borrow.
+fn active_plugin() -> Option<ActivePlugin> {
+    ACTIVE_PLUGINS
+        .try_with(|active| active.try_borrow().ok().and_then(|active| active.last().cloned()))
+        .ok()
+        .flatten()
+}
+
+/// Install the crash handlers.
+///
+/// Installs a panic hook, a vectored exception handler, and an unhandled exception filter.
+/// The vectored exception handler writes a crash report for exceptions in plugin code, before the game
+/// can handle them on its own.
+/// The panic hook and the unhandled exception filter log the crash, write a minidump into the crash directory,
+/// and disable all plugins.
+///
+/// Should be called as early as possible, but after logging was set up.
+pub fn install() {
+    read_last_crash();
+
+    panic::set_hook(Box::new(panic_hook));
+
+    unsafe {
+        VECTORED_HANDLER.store(AddVectoredExceptionHandler(1, Some(vectored_exception_handler)), Ordering::SeqCst);
+        PREVIOUS_FILTER = SetUnhandledExceptionFilter(Some(unhandled_exception_filter));
+    }
+
+    debug!("Installed crash handlers");
+}
+
+/// Remove the crash handlers and restore the previous unhandled exception filter, e.g., when the engine is torn down.
+pub fn uninstall() {
+    let _ = panic::take_hook();
+
+    unsafe {
+        let handler = VECTORED_HANDLER.swap(std::ptr::null_mut(), Ordering::SeqCst);
+        if !handler.is_null() {
+            RemoveVectoredExceptionHandler(handler);
+        }
+
+        SetUnhandledExceptionFilter(PREVIOUS_FILTER);
+    }
+
+    debug!("Removed crash handlers");
+}
+
+/// Get the report of the last crash, or `None` if the game didn't crash or the report was dismissed.
+pub fn get_last_crash() -> Option<CrashReport> {
+    LAST_CRASH.lock().ok()?.clone()
+}
+
+/// Dismiss the report of the last crash, e.g., after the user saw it.
+pub fn dismiss_last_crash() -> Result<(), anyhow::Error> {
+    let mut last_crash = LAST_CRASH.lock().map_err(|e| anyhow!("could not get lock to the last crash: {}", e))?;
+
+    let path = Path::new(CRASH_DIRECTORY).join(REPORT_FILE);
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| anyhow!("could not remove the crash report: {}", e))?;
+    }
+
+    *last_crash = None;
+    Ok(())
+}
+
+fn read_last_crash() {
+    let path = Path::new(CRASH_DIRECTORY).join(REPORT_FILE);
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+
+    match serde_json::from_str::<CrashReport>(&content) {
+        Ok(report) => {
+            warn!("The game crashed in a previous session: {}", report.cause);
+
+            if let Ok(mut last_crash) = LAST_CRASH.lock() {
+                *last_crash = Some(report);
+            }
+        },
+        Err(e) => warn!("Could not parse the report of the last crash: {}", e),
+    }
+}
+
+/// Whether the engine crashed.
+///
+/// After a crash, plugins should not be executed anymore.
+pub fn has_crashed() -> bool {
+    HAS_CRASHED.load(Ordering::SeqCst)
+}
+
+fn panic_hook(info: &PanicInfo) {
+    let backtrace = Backtrace::force_capture();
+
+    let location = match info.location() {
+        Some(location) => format!("{}:{}:{}", location.file(), location.line(), location.column()),
+        None => String::from("unknown location"),
+    };
+
+    let message = match info.payload().downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match info.payload().downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => String::from("no panic message"),
+        },
+    };
+
+    error!("Engine panicked at {}: {}\nBacktrace:\n{}", location, message, backtrace);
+
+    handle_crash(None, format!("engine panicked at {}: {}", location, message));
+}
+
+/// Write a crash report for fatal exceptions in plugin code.
+///
+/// Vectored exception handlers are called before the game's exception handlers, which might end the game without
+/// calling the unhandled exception filter.
+/// Exceptions in the game's own code are ignored, as the game might handle them.
+unsafe extern "system" fn vectored_exception_handler(exception_info: *mut EXCEPTION_POINTERS) -> i32 {
+    if exception_info.is_null() || (*exception_info).ExceptionRecord.is_null() {
+        return EXCEPTION_CONTINUE_SEARCH;
+    }
+
+    let record = &*(*exception_info).ExceptionRecord;
+    if !FATAL_EXCEPTIONS.contains(&record.ExceptionCode) || HAS_REPORT.load(Ordering::SeqCst) {
+        return EXCEPTION_CONTINUE_SEARCH;
+    }
+
+    let plugin = match active_plugin() {
+        Some(plugin) => plugin,
+        None => return EXCEPTION_CONTINUE_SEARCH,
+    };
+
+    error!("Exception {:#010x} at {:?} while running plugin '{}'", record.ExceptionCode.0, record.ExceptionAddress, plugin.name);
+
+    write_report(exception_report(exception_info, Some(plugin)));
+
+    EXCEPTION_CONTINUE_SEARCH
+}
+
+unsafe extern "system" fn unhandled_exception_filter(exception_info: *const EXCEPTION_POINTERS) -> i32 {
+    let cause = if !exception_info.is_null() && !(*exception_info).ExceptionRecord.is_null() {
+        let record = &*(*exception_info).ExceptionRecord;
+
+        error!("Unhandled exception {:#010x} at {:?}", record.ExceptionCode.0, record.ExceptionAddress);
+        describe_exception(record)
+    } else {
+        error!("Unhandled exception without exception information");
+        String::from("unhandled exception")
+    };
+
+    handle_crash(Some(exception_info), cause);
+
+    EXCEPTION_CONTINUE_SEARCH
+}
+
+/// Handle a crash.
+///
+/// Writes a minidump and a crash report for the first crash, marks recently enabled plugins as suspects,
+/// and disables all plugins.
+/// Subsequent crashes are only logged to avoid flooding the crash directory.
+fn handle_crash(exception_info: Option<*const EXCEPTION_POINTERS>, cause: String) {
+    let already_crashed = HAS_CRASHED.swap(true, Ordering::SeqCst);
+
+    if already_crashed {
+        warn!("Engine already crashed before, not writing another minidump");
+        return;
+    }
+
+    let minidump = match write_minidump(exception_info) {
+        Ok(path) => {
+            error!("Wrote minidump to {}", path.display());
+
+            if let Err(e) = write_session_info(&path.with_extension("json")) {
+                error!("Could not write session info: {}", e);
+            }
+
+            Some(path)
+        },
+        Err(e) => {
+            error!("Could not write minidump: {}", e);
+            None
+        },
+    };
+
+    // The vectored exception handler might have already written the report, only add the minidump to it
+    let report = match HAS_REPORT.load(Ordering::SeqCst) {
+        true => LAST_CRASH.try_lock().ok().and_then(|last_crash| last_crash.clone()),
+        false => None,
+    };
+    let mut report = report.unwrap_or_else(|| match exception_info {
+        Some(exception_info) => unsafe { exception_report(exception_info, active_plugin()) },
+        None => new_report(cause, active_plugin()),
+    });
+    report.minidump = minidump.map(|path| path.display().to_string());
+
+    write_report(report);
+
+    suspect::record_crash();
+    disable_plugins();
+}
+
+fn new_report(cause: String, plugin: Option<ActivePlugin>) -> CrashReport {
+    CrashReport {
+        crashed_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0),
+        cause,
+        exception_code: None,
+        address: None,
+        registers: None,
+        hook: plugin.as_ref().and_then(|plugin| plugin.hook),
+        plugin: plugin.map(|plugin| plugin.name),
+        minidump: None,
+    }
+}
+
+/// Create the report of the exception, including the registers at the time of the exception.
+unsafe fn exception_report(exception_info: *const EXCEPTION_POINTERS, plugin: Option<ActivePlugin>) -> CrashReport {
+    if exception_info.is_null() || (*exception_info).ExceptionRecord.is_null() {
+        return new_report(String::from("unhandled exception"), plugin);
+    }
+
+    let record = &*(*exception_info).ExceptionRecord;
+    let mut report = new_report(describe_exception(record), plugin);
+    report.exception_code = Some(record.ExceptionCode.0 as u32);
+    report.address = Some(record.ExceptionAddress as u32);
+
+    let context = (*exception_info).ContextRecord;
+    if !context.is_null() {
+        let context = &*context;
+
+        report.registers = Some(Registers {
+            eax: context.Eax,
+            ebx: context.Ebx,
+            ecx: context.Ecx,
+            edx: context.Edx,
+            esi: context.Esi,
+            edi: context.Edi,
+            ebp: context.Ebp,
+            esp: context.Esp,
+            eip: context.Eip,
+            eflags: context.EFlags,
+        });
+    }
+
+    report
+}
+
+/// Describe the exception, e.g., `access violation writing 0x00000010`.
+fn describe_exception(record: &EXCEPTION_RECORD) -> String {
+    match record.ExceptionCode {
+        EXCEPTION_ACCESS_VIOLATION if record.NumberParameters >= 2 => {
+            let access = match record.ExceptionInformation[0] {
+                0 => "reading",
+                1 => "writing",
+                8 => "executing",
+                _ => "accessing",
+            };
+
+            format!("access violation {} {:#010x}", access, record.ExceptionInformation[1])
+        },
+        EXCEPTION_ACCESS_VIOLATION => String::from("access violation"),
+        EXCEPTION_ILLEGAL_INSTRUCTION => String::from("illegal instruction"),
+        EXCEPTION_PRIV_INSTRUCTION => String::from("privileged instruction"),
+        EXCEPTION_INT_DIVIDE_BY_ZERO => String::from("integer division by zero"),
+        EXCEPTION_STACK_OVERFLOW => String::from("stack overflow"),
+        EXCEPTION_IN_PAGE_ERROR => String::from("page error"),
+        code => format!("exception {:#010x}", code.0),
+    }
+}
+
+/// Write the report into the crash directory and make it the last crash.
+///
+/// Only tries to get the lock to the last crash, as the crash might have happened while holding it.
+fn write_report(report: CrashReport) {
+    HAS_REPORT.store(true, Ordering::SeqCst);
+
+    let path = Path::new(CRASH_DIRECTORY).join(REPORT_FILE);
+    let result = fs::create_dir_all(CRASH_DIRECTORY)
+        .map_err(|e| anyhow!("could not create crash directory: {}", e))
+        .and_then(|_| serde_json::to_string_pretty(&report).map_err(|e| anyhow!("could not serialize the crash report: {}", e)))
+        .and_then(|content| fs::write(&path, content).map_err(|e| anyhow!("could not write the crash report: {}", e)));
+
+    match result {
+        Ok(_) => error!("Wrote crash report to {}", path.display()),
+        Err(e) => error!("Could not write the crash report: {}", e),
+    }
+
+    if let Ok(mut last_crash) = LAST_CRASH.try_lock() {
+        *last_crash = Some(report);
+    }
+}
+
+/// Disable all plugins without persisting the change.
+///
+/// The crash might have happened while the plugin manager is locked, e.g., while calling
+/// a plugin's `onUpdate` function.
+/// Therefore, we only try to get the lock. If we can't get it, the plugin manager will
+/// stop calling plugins on its own because [`has_crashed`] returns `true`.
+fn disable_plugins() {
+    let plugin_manager = match GlobalPluginManager::try_get() {
+        Some(plugin_manager) => plugin_manager,
+        None => {
+            debug!("Plugin manager not initialized yet, no plugins to disable");
+            return;
+        },
+    };
+
+    let result = match plugin_manager.try_write() {
+        Ok(mut plugin_manager) => {
+            plugin_manager.disable_all_plugins();
+            Ok(())
+        },
+        Err(TryLockError::Poisoned(poisoned)) => {
+            poisoned.into_inner().disable_all_plugins();
+            Ok(())
+        },
+        Err(TryLockError::WouldBlock) => Err(()),
+    };
+
+    match result {
+        Ok(_) => warn!("Disabled all plugins due to the crash"),
+        Err(_) => warn!("Plugin manager is locked, plugins will be stopped instead of disabled"),
+    }
+}
+
+/// Write a minidump of the game's process into the crash directory.
+///
+/// Returns the path to the minidump.
+fn write_minidump(exception_info: Option<*const EXCEPTION_POINTERS>) -> Result<PathBuf, anyhow::Error> {
+    let directory = Path::new(CRASH_DIRECTORY);
+
+    fs::create_dir_all(directory).map_err(|e| anyhow!("could not create crash directory: {}", e))?;
+
+    let file_name = format!("futuremod_{}.dmp", chrono::Local::now().format("%Y-%m-%d_%H-%M-%S"));
+    let path = directory.join(file_name);
+
+    let file = fs::File::create(&path).map_err(|e| anyhow!("could not create minidump file: {}", e))?;
+
+    unsafe {
+        let exception_information = exception_info.map(|exception_pointers| MINIDUMP_EXCEPTION_INFORMATION {
+            ThreadId: GetCurrentThreadId(),
+            ExceptionPointers: exception_pointers as *mut EXCEPTION_POINTERS,
+            ClientPointers: BOOL(0),
+        });
+
+        MiniDumpWriteDump(
+            GetCurrentProcess(),
+            GetCurrentProcessId(),
+            HANDLE(file.as_raw_handle() as isize),
+            MiniDumpWithDataSegs,
+            exception_information.as_ref().map(|information| information as *const MINIDUMP_EXCEPTION_INFORMATION),
+            None,
+            None,
+        ).map_err(|e| anyhow!("could not write minidump: {}", e))?;
+    }
+
+    Ok(path)
+}
+
+/// Write the session's statistics next to the minidump, so crash reports contain them.
+fn write_session_info(path: &Path) -> Result<(), anyhow::Error> {
+    let info = session::try_get_info().ok_or(anyhow!("session is locked"))?;
+    let content = serde_json::to_string_pretty(&info).map_err(|e| anyhow!("could not serialize session info: {}", e))?;
+
+    fs::write(path, content).map_err(|e| anyhow!("could not write session info: {}", e))
+}
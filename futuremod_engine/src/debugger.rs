@@ -0,0 +1,75 @@
+use std::{sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering}, thread, time::Duration};
+
+use futuremod_data::debugger::DebuggerState;
+use log::*;
+
+use crate::{crash, session_recording};
+
+/// Interval in which a paused game loop checks whether it may continue.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Whether the game loop is paused.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Number of frames the paused game loop may still run.
+static STEPS: AtomicU32 = AtomicU32::new(0);
+
+/// Number of mission frames since the mod was injected.
+static FRAME: AtomicU64 = AtomicU64::new(0);
+
+pub fn get_state() -> DebuggerState {
+    DebuggerState {
+        paused: PAUSED.load(Ordering::SeqCst),
+        frame: FRAME.load(Ordering::SeqCst),
+    }
+}
+
+/// Pause the game loop before the next frame.
+pub fn pause() {
+    STEPS.store(0, Ordering::SeqCst);
+
+    if !PAUSED.swap(true, Ordering::SeqCst) {
+        info!("Pausing the game loop at frame {}", FRAME.load(Ordering::SeqCst));
+        session_recording::record_event("debuggerPaused", None);
+    }
+}
+
+pub fn resume() {
+    STEPS.store(0, Ordering::SeqCst);
+
+    if PAUSED.swap(false, Ordering::SeqCst) {
+        info!("Resuming the game loop at frame {}", FRAME.load(Ordering::SeqCst));
+        session_recording::record_event("debuggerResumed", None);
+    }
+}
+
+/// Run the game for the number of frames and pause again. Pauses the game loop if it isn't paused.
+pub fn step(frames: u32) {
+    STEPS.fetch_add(frames, Ordering::SeqCst);
+    PAUSED.store(true, Ordering::SeqCst);
+
+    debug!("Stepping {} frames from frame {}", frames, FRAME.load(Ordering::SeqCst));
+}
+
+/// Block the game loop while it is paused. Must be called at the start of every frame of a mission.
+///
+/// Plugins, and the game with them, can be inspected while the game loop is blocked,
+/// since the plugin manager isn't locked.
+pub fn on_frame() {
+    while PAUSED.load(Ordering::SeqCst) && !take_step() {
+        // A crashed engine must not keep the game frozen
+        if crash::has_crashed() {
+            resume();
+            break;
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    FRAME.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Take one of the frames the paused game loop may run, if any are left.
+fn take_step() -> bool {
+    STEPS.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |steps| steps.checked_sub(1)).is_ok()
+}
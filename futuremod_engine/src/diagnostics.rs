@@ -0,0 +1,236 @@
+use std::{ffi::c_void, fs, mem, path::Path, sync::Mutex, time::{SystemTime, UNIX_EPOCH}};
+
+use anyhow::anyhow;
+use futuremod_data::diagnostics::{CheckStatus, DiagnosticCheck, Diagnostics};
+use iced_x86::{Decoder, DecoderOptions, FlowControl};
+use log::*;
+use windows::{core::PCSTR, Win32::System::{LibraryLoader::GetModuleHandleA, Memory::*}};
+
+use crate::{futurecop::{addresses::{self, GAME_BASE_ADDRESS}, FUN_00406A30_ADDRESS, GAME_FUNCTIONS, PLAYER_ARRAY_ADDR}, temporary};
+
+/// Globals of the game the mod reads and writes.
+const KNOWN_GLOBALS: [(&str, u32); 12] = [
+    ("in game loop", 0x004c987c),
+    ("is two player", 0x00511f54),
+    ("is playing", 0x00486248),
+    ("game mode", 0x00511e03),
+    ("scene", 0x00511fb8),
+    ("frame number", 0x00511f40),
+    ("main window", 0x00512db4),
+    ("heap", 0x00512ebc),
+    ("module", 0x004a005c),
+    ("events", 0x00512044),
+    ("surface", 0x00511f64),
+    ("player array", PLAYER_ARRAY_ADDR),
+];
+
+/// Functions of the game the mod only hooks. The functions it calls are in [`GAME_FUNCTIONS`].
+const HOOKED_FUNCTIONS: [(&str, u32); 2] = [
+    ("player method", 0x00446800),
+    ("mission game loop", FUN_00406A30_ADDRESS),
+];
+
+/// Number of bytes a hook overwrites at the start of a function.
+const HOOK_SIZE: usize = 5;
+
+lazy_static! {
+    static ref DIAGNOSTICS: Mutex<Diagnostics> = Mutex::new(Diagnostics { ran_at: 0, checks: Vec::new(), migrations: Vec::new(), temporary_directory: None });
+}
+
+/// Record the result of a check, replacing the previous result of the check with the same name.
+fn record(name: &str, result: Result<String, (CheckStatus, String)>) {
+    let (status, message) = match result {
+        Ok(message) => (CheckStatus::Passed, message),
+        Err((status, message)) => (status, message),
+    };
+
+    match status {
+        CheckStatus::Passed => info!("Diagnostics: {}: {}", name, message),
+        CheckStatus::Warning => warn!("Diagnostics: {}: {}", name, message),
+        CheckStatus::Failed => error!("Diagnostics: {}: {}", name, message),
+    }
+
+    let check = DiagnosticCheck { name: name.to_string(), status, message };
+
+    match DIAGNOSTICS.lock() {
+        Ok(mut diagnostics) => match diagnostics.checks.iter_mut().find(|c| c.name == check.name) {
+            Some(existing) => *existing = check,
+            None => diagnostics.checks.push(check),
+        },
+        Err(e) => warn!("Could not get lock to the diagnostics: {}", e),
+    }
+}
+
+/// Run the checks of the game's memory. Must be called when the mod is injected, before any hook is installed.
+pub fn run_game_checks() {
+    if let Ok(mut diagnostics) = DIAGNOSTICS.lock() {
+        diagnostics.ran_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+    }
+
+    record("Game module", check_game_module());
+    record("Known addresses", check_known_addresses());
+    record("Function signatures", check_function_signatures());
+    record("Address migrations", addresses::summary().map_err(|problems| (CheckStatus::Warning, problems)));
+    record("Trampoline allocation", check_trampoline_allocation());
+}
+
+/// Check that the game's executable is loaded at the address all known addresses assume.
+fn check_game_module() -> Result<String, (CheckStatus, String)> {
+    let module = unsafe { GetModuleHandleA(PCSTR::null()) }
+        .map_err(|e| (CheckStatus::Failed, format!("could not get the game's module: {}", e)))?;
+
+    match module.0 as u32 {
+        GAME_BASE_ADDRESS => Ok(format!("loaded at {:#010x}", GAME_BASE_ADDRESS)),
+        base => Err((CheckStatus::Failed, format!("loaded at {:#010x} instead of {:#010x}, this version of the game is not supported", base, GAME_BASE_ADDRESS))),
+    }
+}
+
+/// Whether the memory at the address is committed, readable, and part of the game's executable.
+fn is_game_memory(address: u32) -> bool {
+    let mut info = MEMORY_BASIC_INFORMATION::default();
+    let size = unsafe { VirtualQuery(Some(address as *const c_void), &mut info, mem::size_of::<MEMORY_BASIC_INFORMATION>()) };
+
+    size != 0
+        && info.State == MEM_COMMIT
+        && info.Protect & (PAGE_NOACCESS | PAGE_GUARD) == PAGE_PROTECTION_FLAGS(0)
+        && info.AllocationBase as u32 == GAME_BASE_ADDRESS
+}
+
+/// Functions of the game the mod hooks or calls.
+fn known_functions() -> Vec<(&'static str, u32)> {
+    HOOKED_FUNCTIONS.iter().copied().chain(GAME_FUNCTIONS.iter().map(|function| (function.name, function.resolved_address()))).collect()
+}
+
+/// Check that all known globals and functions are in the game's executable.
+fn check_known_addresses() -> Result<String, (CheckStatus, String)> {
+    let addresses: Vec<(&str, u32)> = KNOWN_GLOBALS.iter().copied().chain(known_functions()).collect();
+
+    let invalid: Vec<String> = addresses
+        .iter()
+        .filter(|(_, address)| !is_game_memory(*address))
+        .map(|(name, address)| format!("{} ({:#010x})", name, address))
+        .collect();
+
+    match invalid.is_empty() {
+        true => Ok(format!("all {} addresses are readable", addresses.len())),
+        false => Err((CheckStatus::Failed, format!("not readable in the game's executable: {}", invalid.join(", ")))),
+    }
+}
+
+/// Check that the known functions look like the start of a function.
+///
+/// The start of a function follows a return or padding, and its first instructions must be decodable,
+/// since hooks relocate them.
+fn check_function_signatures() -> Result<String, (CheckStatus, String)> {
+    let mut mismatches = Vec::new();
+
+    let functions = known_functions();
+
+    for (name, address) in functions.iter() {
+        if !is_game_memory(*address) || !is_game_memory(*address - 3) {
+            mismatches.push(format!("{} ({:#010x}) is not readable", name, address));
+            continue;
+        }
+
+        let (preceding, code) = unsafe {
+            (
+                std::slice::from_raw_parts((*address - 3) as *const u8, 3),
+                std::slice::from_raw_parts(*address as *const u8, 16),
+            )
+        };
+
+        // ret, ret imm16, int3 or nop
+        let follows_boundary = matches!(preceding[2], 0xc3 | 0xcc | 0x90) || preceding[0] == 0xc2;
+
+        let mut decoder = Decoder::with_ip(32, code, *address as u64, DecoderOptions::NONE);
+        let mut decoded = 0;
+        let mut is_decodable = true;
+        while decoded < HOOK_SIZE && decoder.can_decode() {
+            let instruction = decoder.decode();
+            if instruction.is_invalid() || instruction.flow_control() == FlowControl::Return {
+                is_decodable = false;
+                break;
+            }
+            decoded += instruction.len();
+        }
+
+        if !follows_boundary || !is_decodable {
+            mismatches.push(format!("{} ({:#010x}) doesn't look like the start of a function", name, address));
+        }
+    }
+
+    match mismatches.is_empty() {
+        true => Ok(format!("all {} functions match", functions.len())),
+        false => Err((CheckStatus::Warning, mismatches.join(", "))),
+    }
+}
+
+/// Check that executable memory for trampolines can be allocated and executed.
+fn check_trampoline_allocation() -> Result<String, (CheckStatus, String)> {
+    // mov eax, 0x2a; ret
+    let code: [u8; 6] = [0xb8, 0x2a, 0x00, 0x00, 0x00, 0xc3];
+
+    unsafe {
+        let trampoline = VirtualAlloc(None, code.len(), MEM_COMMIT | MEM_RESERVE, PAGE_EXECUTE_READWRITE);
+        if trampoline.is_null() {
+            return Err((CheckStatus::Failed, "could not allocate executable memory, hooks can't be installed".to_string()));
+        }
+
+        std::ptr::copy_nonoverlapping(code.as_ptr(), trampoline as *mut u8, code.len());
+
+        let function = mem::transmute::<*mut c_void, extern "C" fn() -> u32>(trampoline);
+        let result = function();
+
+        let _ = VirtualFree(trampoline, 0, MEM_RELEASE);
+
+        match result {
+            0x2a => Ok("executable memory can be allocated".to_string()),
+            result => Err((CheckStatus::Failed, format!("allocated code returned {:#x} instead of 0x2a", result))),
+        }
+    }
+}
+
+/// Check that plugins can be written to the plugins directory.
+pub fn check_plugins_directory(directory: &Path) {
+    let test_file = directory.join(".futuremod-write-test");
+
+    let result = fs::write(&test_file, b"")
+        .and_then(|_| fs::remove_file(&test_file))
+        .map(|_| format!("'{}' is writable", directory.display()))
+        .map_err(|e| (CheckStatus::Failed, format!("could not write to '{}', plugins can't be installed: {}", directory.display(), e)));
+
+    record("Plugins directory", result);
+}
+
+/// Delete the temporary files left over by previous installs and record whether it succeeded.
+pub fn clean_temporary_directory() {
+    let directory = temporary::directory();
+
+    let result = temporary::cleanup()
+        .map(|deleted| format!("deleted {} stale files from '{}'", deleted, directory.display()))
+        .map_err(|e| (CheckStatus::Warning, format!("could not clean up '{}', stale files of failed installs remain: {}", directory.display(), e)));
+
+    record("Temporary directory", result);
+}
+
+/// Record whether the server could bind to its address.
+pub fn record_server_bind(address: &str, error: Option<&anyhow::Error>) {
+    let result = match error {
+        None => Ok(format!("listening on {}", address)),
+        Some(e) => Err((CheckStatus::Failed, format!("could not bind to {}, the GUI can't connect: {}", address, e))),
+    };
+
+    record("Server bind", result);
+}
+
+/// Results of all checks that ran so far, the functions whose address migrated, and the current usage of the temporary directory.
+pub fn get() -> Result<Diagnostics, anyhow::Error> {
+    DIAGNOSTICS
+        .lock()
+        .map(|diagnostics| Diagnostics {
+            migrations: addresses::migrations(),
+            temporary_directory: Some(temporary::usage()),
+            ..diagnostics.clone()
+        })
+        .map_err(|e| anyhow!("could not get lock to the diagnostics: {}", e))
+}
@@ -1,13 +1,13 @@
-use std::{cell::OnceCell, path::{Path, PathBuf}, sync::{Arc, Mutex}, thread, time};
+use std::{cell::OnceCell, panic, path::{Path, PathBuf}, sync::{Arc, Mutex}, time::{self, Instant}};
 
 use log::*;
 use num;
 use windows::Win32::UI::Input::KeyboardAndMouse::*;
 use crate::{api::graphics::{self, EXAMPLE_ITEM}, config::Config, futurecop::*, input::KeyState, plugins::plugin_manager::GlobalPluginManager, util::resume_all_threads};
 use crate::futurecop::global::*;
-use futuremod_hook::native::{install_hook, Hook};
-use crate::server;
-use crate::plugins::PluginManager;
+use futuremod_hook::native::{install_hook, Hook, HookCall};
+use crate::{benchmark, bookmarks, borderless, captions, clock, colorblind, compatibility, console, controller, debugger, diagnostics, events::{self, GameEvent}, exec, game_exit, game_input, gc, ghost, injection, integrity, key_bindings, macros, input_recording, metrics, multiplayer, net, overlay, recording, remapping, rich_presence, run_timer, screenshot, server, session, session_recording, statistics, teardown, temporary, viewport, watches, widgets};
+use crate::plugins::{audit, breakpoints, limits, persistence, snapshot, suspect, watcher, PluginManager};
 
 static mut CONFIG: Option<Config> = None;
 
@@ -31,6 +31,10 @@ type MissionGameLoop = fn() -> ();
 /// 
 /// Sets some always active hooks, configures and initializes global services (e.g. PluginManager) and starts the server.
 pub fn main(config: Config) {
+    session::start();
+    addresses::init();
+    diagnostics::run_game_checks();
+
     unsafe {
         ORIGINAL_PLAYER_METHOD = install_hook(0x00446800, player_method);
 
@@ -50,14 +54,69 @@ pub fn main(config: Config) {
         }
     );
 
+    if let Some(hook_recording) = &config.hook_recording {
+        if let Err(e) = recording::start(hook_recording) {
+            error!("Could not start recording hook invocations: {}", e);
+        }
+    }
+
+    if let Some(input_recording) = &config.input_recording {
+        if let Err(e) = input_recording::start(input_recording) {
+            error!("Could not start the input recording: {}", e);
+        }
+    }
+
+    audit::set_enabled(config.audit);
+    suspect::set_window(config.crash_suspect_window);
+    breakpoints::set_enabled(config.error_breakpoints);
+    gc::init(&config.garbage_collection);
+    limits::init(&config.execution_limits);
+    overlay::init(&config.log_overlay, &config.debug_overlay, config.performance_overlay);
+    viewport::init(&config.render_resolution);
+    captions::init(&config.captions);
+    run_timer::init(&config.live_split);
+    rich_presence::init(&config.rich_presence);
+    remapping::init(&config.input_remaps);
+    controller::init(&config.controller);
+    console::init(&config.console, config.developer_mode);
+    borderless::init(config.borderless_window);
+    game_exit::init();
+    game_input::init();
+    colorblind::init(config.colorblind_preset);
+    screenshot::init(&config.screenshots);
+
+    // Plugins register their key bindings when they are loaded
+    key_bindings::init(&plugins_directory.join("key-bindings.json"));
+    // Watch expressions run in the developer environment, so persisted ones are only evaluated in developer mode
+    if config.developer_mode {
+        watches::init(&plugins_directory.join("watches.json"));
+    }
+    bookmarks::init(&plugins_directory.join("bookmarks.json"));
+    net::init(&plugins_directory.join("network-approvals.json"));
+    exec::init(&plugins_directory.join("command-approvals.json"));
+    compatibility::init(&plugins_directory.join("compatibility.json"));
+    statistics::init(&plugins_directory.join("statistics.json"));
+
     // Initialize global plugin manager or panic
-    match GlobalPluginManager::initialize(plugins_directory) {
+    match GlobalPluginManager::initialize(plugins_directory.clone()) {
         Err(e) => {
             panic!("error while initializing the global plugin manager: {}", e);
         },
         Ok(_) => (),
     }
 
+    match GlobalPluginManager::get().write() {
+        Ok(mut manager) => manager.run_tweaks(&config.tweaks),
+        Err(e) => error!("Could not get lock to the plugin manager to run the tweaks: {}", e),
+    }
+
+    watcher::start();
+    persistence::start();
+    integrity::init(&config.integrity);
+    diagnostics::check_plugins_directory(&plugins_directory);
+    temporary::init(&config.temporary_directory);
+    diagnostics::clean_temporary_directory();
+
     server::start_server(config);
 
     // Now resume the game
@@ -70,6 +129,24 @@ pub fn main(config: Config) {
 }
 
 fn first_mission_game_loop_function(o: MissionGameLoop) {
+    let _call = HookCall::enter();
+
+    // Blocks while the frame-step debugger paused the game
+    debugger::on_frame();
+    clock::on_frame();
+
+    // This function is called directly by the game.
+    // A panic must never unwind into the game's code, therefore, catch it and continue
+    // with the game's original function. The crash handler already disabled plugins at this point.
+    if panic::catch_unwind(update_plugins).is_err() {
+        error!("Panicked while updating plugins, continuing with the game loop");
+    }
+
+    o();
+}
+
+/// Update the key state and call `onUpdate` of all plugins.
+fn update_plugins() {
     // Update the current key state
     let key_states = KeyState::new();
     match key_states.update() {
@@ -77,19 +154,77 @@ fn first_mission_game_loop_function(o: MissionGameLoop) {
         Err(e) => error!("Error while updating the key state: {}", e.to_string()),
     }
 
-    match GlobalPluginManager::get().lock() {
-        Ok(manager) => {
+    remapping::on_frame(&key_states);
+    input_recording::on_frame(&key_states);
+    injection::on_frame(&key_states);
+    macros::on_frame();
+    viewport::on_frame();
+    widgets::on_frame();
+
+    let first_player = unsafe { FIRST_PLAYER.map(|player| &*player) };
+    ghost::on_frame(first_player.map(ghost::Position::of));
+    rich_presence::on_frame(first_player);
+
+    let players = unsafe { [FIRST_PLAYER, SECOND_PLAYER] };
+    session_recording::on_frame(&players);
+    events::on_frame(&players);
+    multiplayer::on_frame(players.iter().flatten().map(|player| unsafe { multiplayer::PlayerSnapshot::of(&**player) }).collect());
+
+    match GlobalPluginManager::get().write() {
+        Ok(mut manager) => {
+            for split in run_timer::take_splits() {
+                manager.on_split(&split);
+            }
+            for key in key_bindings::take_key_downs() {
+                manager.on_key_down(&key);
+            }
+            for response in net::take_responses() {
+                manager.on_net_response(&response);
+            }
+            for result in exec::take_results() {
+                manager.on_exec_result(&result);
+            }
+            for event in events::take_events() {
+                manager.on_game_event(&event);
+            }
+            manager.on_timers(clock::now());
+
             // Then call onUpdate
+            let update_start = Instant::now();
             manager.on_update();
+            let update_time = update_start.elapsed();
+
+            // Collect garbage before measuring, so its pauses show up in the metrics
+            let gc_time = manager.collect_frame_garbage();
+
+            benchmark::on_frame(&mut manager, update_time);
+            watches::on_frame(&mut manager);
+            console::on_frame(&mut manager);
+            metrics::on_frame(update_time, gc_time);
+            session::on_frame();
+
+            snapshot::publish_if_due(&manager);
         }
         Err(e) => {
             error!("error while getting a lock to the plugin manager to call on_update: {:?}", e)
         },
     }
 
+    captions::on_frame();
+    overlay::on_frame(&players);
+
     graphics::render_item(EXAMPLE_ITEM);
+}
 
-    o();
+/// Pass the queued game events to the plugins outside of the mission's game loop.
+///
+/// Leaves the events queued for the next frame if the plugin manager is in use, e.g., because a plugin's code destroyed the player.
+fn dispatch_game_events() {
+    if let Ok(mut manager) = GlobalPluginManager::get().try_write() {
+        for event in events::take_events() {
+            manager.on_game_event(&event);
+        }
+    }
 }
 
 fn is_key_pressed(vkey: i32) -> bool {
@@ -108,6 +243,8 @@ fn is_key_pressed(vkey: i32) -> bool {
 /// function and instead hope that our function overrides the acceleration after the game's logic
 /// clamped it and before the game moved the player.
 pub fn mod_loop() {
+    let _worker = teardown::worker();
+
     loop {
         unsafe {
             if FIRST_PLAYER.is_some() {
@@ -119,7 +256,9 @@ pub fn mod_loop() {
             }
         }
 
-        thread::sleep(time::Duration::from_millis(10));
+        if !teardown::sleep(time::Duration::from_millis(10)) {
+            return;
+        }
     }
 }
 
@@ -170,6 +309,8 @@ fn handle_player_sprint(player_id: u8, player_entity: &mut PlayerEntity) {
 }
 
 unsafe fn player_method(param1: i32, player_entity: u32, param3: u32, param4: u32) -> u32 {
+    let _call = HookCall::enter();
+
     if player_entity > 0  {
         if PLAYER_ENTITY_ADDRESS.is_none() {
             PLAYER_ENTITY_ADDRESS = Some(player_entity);
@@ -195,14 +336,38 @@ unsafe fn player_method(param1: i32, player_entity: u32, param3: u32, param4: u3
             if player == Some(0) && FIRST_PLAYER.is_none() {
                 info!("Player 1 created");
                 FIRST_PLAYER = Some(player_entity_data);
+                // Every mission creates the first player
+                session::on_mission_start();
+                session_recording::record_event("missionStart", None);
+                events::publish(GameEvent::MissionLoaded);
+                events::publish(GameEvent::PlayerSpawned { player: 0 });
+                ghost::on_mission_start();
+                run_timer::on_mission_start();
+                rich_presence::on_mission_start();
+                statistics::on_mission_start();
             } else if player == Some(1) && SECOND_PLAYER.is_none() {
                 info!("Player 2 created");
                 SECOND_PLAYER = Some(player_entity_data);
+                events::publish(GameEvent::PlayerSpawned { player: 1 });
             }
         } else if param1 == 5 {
             if FIRST_PLAYER.is_some() && FIRST_PLAYER.unwrap() as u32 == player_entity {
                 info!("Player 1 destroyed");
                 FIRST_PLAYER = None;
+                session_recording::record_event("missionEnd", None);
+                events::publish(GameEvent::MissionEnded);
+                ghost::on_mission_end();
+                run_timer::on_mission_end();
+                rich_presence::on_mission_end();
+                statistics::on_mission_end();
+
+                let _ = GlobalPluginManager::with_plugin_manager_mut(|manager| {
+                    benchmark::on_mission_end(manager);
+                    Ok(())
+                }).map_err(|e| warn!("Could not cancel the benchmark at the end of the mission: {}", e));
+
+                // The game loop doesn't run between missions, pass the events of the mission's end right away
+                dispatch_game_events();
             }
             if SECOND_PLAYER.is_some() && SECOND_PLAYER.unwrap() as u32 == player_entity {
                 info!("Player 2 destroyed");
@@ -0,0 +1,149 @@
+use std::sync::Mutex;
+
+use log::*;
+use serde::Serialize;
+
+use crate::futurecop::{global::GetterSetter, PlayerEntity, GAME_MODE};
+
+/// Names of the events plugins can subscribe to.
+pub const EVENT_NAMES: [&str; 6] = [
+    "playerSpawned",
+    "playerDamaged",
+    "playerDied",
+    "missionLoaded",
+    "missionEnded",
+    "gameModeChanged",
+];
+
+/// Upper bound of queued events, so events aren't collected forever if no mission runs to pass them to the plugins.
+const MAX_QUEUED_EVENTS: usize = 1024;
+
+/// Event of the game that plugins subscribe to with the `events` library.
+///
+/// The engine's own hooks publish the events, so plugins don't have to install their own hooks on the same game functions.
+/// Events that can't be hooked directly, e.g., damage, are derived from the players' state once per frame, see [`on_frame`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum GameEvent {
+    /// The player's entity was created, e.g., at the start of a mission.
+    PlayerSpawned { player: u8 },
+
+    /// The player lost health since the last frame.
+    PlayerDamaged { player: u8, damage: i16, health: i16 },
+
+    /// The player's number of deaths increased.
+    PlayerDied { player: u8, deaths: u16 },
+
+    /// A mission was loaded and its first player created.
+    MissionLoaded,
+
+    /// The mission ended and its first player was destroyed.
+    MissionEnded,
+
+    /// The selected game mode changed, e.g., `CrimeWar`.
+    GameModeChanged { game_mode: String },
+}
+
+impl GameEvent {
+    /// Name plugins subscribe to the event with.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameEvent::PlayerSpawned { .. } => "playerSpawned",
+            GameEvent::PlayerDamaged { .. } => "playerDamaged",
+            GameEvent::PlayerDied { .. } => "playerDied",
+            GameEvent::MissionLoaded => "missionLoaded",
+            GameEvent::MissionEnded => "missionEnded",
+            GameEvent::GameModeChanged { .. } => "gameModeChanged",
+        }
+    }
+}
+
+lazy_static! {
+    static ref QUEUE: Mutex<Vec<GameEvent>> = Mutex::new(Vec::new());
+    static ref TRACKER: Mutex<Tracker> = Mutex::new(Tracker { players: [None, None], game_mode: None });
+}
+
+/// State of the last frame, to derive events from changes.
+struct Tracker {
+    players: [Option<TrackedPlayer>; 2],
+    game_mode: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+struct TrackedPlayer {
+    /// Address of the player's entity, so that a new entity isn't compared with the previous one.
+    entity: u32,
+    health: i16,
+    deaths: u16,
+}
+
+/// Queue the event to be passed to the plugins.
+pub fn publish(event: GameEvent) {
+    match QUEUE.lock() {
+        Ok(mut queue) => {
+            if queue.len() >= MAX_QUEUED_EVENTS {
+                queue.remove(0);
+            }
+
+            queue.push(event);
+        },
+        Err(e) => warn!("Could not get lock to the event queue: {}", e),
+    }
+}
+
+/// Take the events that weren't passed to the plugins yet.
+pub fn take_events() -> Vec<GameEvent> {
+    match QUEUE.lock() {
+        Ok(mut queue) => std::mem::take(&mut *queue),
+        Err(e) => {
+            warn!("Could not get lock to the event queue: {}", e);
+            Vec::new()
+        },
+    }
+}
+
+/// Derive events from the changes of the players and the game mode since the last frame.
+///
+/// Must be called in every frame of a mission.
+pub fn on_frame(players: &[Option<*mut PlayerEntity>; 2]) {
+    let mut tracker = match TRACKER.lock() {
+        Ok(tracker) => tracker,
+        Err(e) => {
+            warn!("Could not get lock to the event tracker: {}", e);
+            return;
+        },
+    };
+
+    for (index, player) in players.iter().enumerate() {
+        let current = player.map(|player| unsafe {
+            TrackedPlayer {
+                entity: player as u32,
+                health: (*player).health.health,
+                deaths: match (*player).player.is_null() {
+                    true => 0,
+                    false => (*(*player).player).deaths,
+                },
+            }
+        });
+
+        if let (Some(previous), Some(current)) = (tracker.players[index], current) {
+            if previous.entity == current.entity {
+                if current.health < previous.health {
+                    publish(GameEvent::PlayerDamaged { player: index as u8, damage: previous.health - current.health, health: current.health });
+                }
+
+                if current.deaths > previous.deaths {
+                    publish(GameEvent::PlayerDied { player: index as u8, deaths: current.deaths });
+                }
+            }
+        }
+
+        tracker.players[index] = current;
+    }
+
+    let game_mode = format!("{:?}", GAME_MODE.get());
+    if tracker.game_mode.as_ref().is_some_and(|previous| *previous != game_mode) {
+        publish(GameEvent::GameModeChanged { game_mode: game_mode.clone() });
+    }
+    tracker.game_mode = Some(game_mode);
+}
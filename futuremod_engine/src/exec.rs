@@ -0,0 +1,240 @@
+use std::{collections::{BTreeMap, HashMap}, io::{self, Read}, os::windows::process::CommandExt, path::{Path, PathBuf}, process::{Child, Command, Stdio}, sync::{atomic::{AtomicU32, Ordering}, Mutex}, thread, time::{Duration, Instant}};
+
+use anyhow::{anyhow, bail};
+use futuremod_data::plugin::PluginInfo;
+use log::*;
+
+use crate::{plugins::persistence, teardown};
+
+/// Programs that didn't exit in this time are killed.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of programs of a plugin that run at the same time.
+const MAX_RUNNING_PROGRAMS: usize = 4;
+
+/// Output after this many bytes is dropped.
+const MAX_OUTPUT_SIZE: u64 = 64 * 1024;
+
+/// Interval in which a running program is checked for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Don't open a console window for console programs.
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+lazy_static! {
+    static ref APPROVALS: Mutex<Approvals> = Mutex::new(Approvals { commands: BTreeMap::new(), path: None });
+    static ref RUNNING: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+    static ref RESULTS: Mutex<Vec<ExecResult>> = Mutex::new(Vec::new());
+}
+
+static NEXT_EXECUTION_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Commands the user approved, by the plugin's name.
+struct Approvals {
+    /// Persisted, so users only approve a plugin once.
+    commands: BTreeMap<String, Vec<String>>,
+    path: Option<PathBuf>,
+}
+
+impl Approvals {
+    fn write_to_file(&self) -> Result<(), anyhow::Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let content = serde_json::to_vec_pretty(&self.commands).map_err(|e| anyhow!("could not serialize the command approvals: {}", e))?;
+
+        persistence::write(path, &content)
+    }
+}
+
+/// Result of a program a plugin ran.
+#[derive(Debug)]
+pub struct ExecResult {
+    pub plugin: String,
+    pub id: u32,
+    pub result: Result<ProgramOutput, String>,
+}
+
+#[derive(Debug)]
+pub struct ProgramOutput {
+    /// `None` if the program was terminated without an exit code.
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Read the commands the user approved. Must be called when the mod is injected.
+pub fn init(path: &Path) {
+    debug!("Reading command approvals from '{}'", path.display());
+
+    let commands = match persistence::read::<BTreeMap<String, Vec<String>>>(path) {
+        Ok(commands) => commands.unwrap_or_default(),
+        Err(e) => {
+            warn!("Could not read the command approvals, no plugin may run programs: {}", e);
+            BTreeMap::new()
+        },
+    };
+
+    match APPROVALS.lock() {
+        Ok(mut approvals) => {
+            approvals.commands = commands;
+            approvals.path = Some(path.to_path_buf());
+        },
+        Err(e) => warn!("Could not get lock to the command approvals: {}", e),
+    }
+}
+
+/// Whether the user approved all commands the plugin declares.
+pub fn is_approved(info: &PluginInfo) -> bool {
+    let approvals = match APPROVALS.lock() {
+        Ok(approvals) => approvals,
+        Err(_) => return false,
+    };
+
+    approvals.commands
+        .get(&info.name)
+        .is_some_and(|approved| info.commands.iter().all(|command| approved.contains(command)))
+}
+
+/// Approve that the plugin runs the commands it declares and persist the approval.
+pub fn approve(info: &PluginInfo) -> Result<(), anyhow::Error> {
+    info!("Approving that plugin '{}' runs {}", info.name, info.commands.join(", "));
+
+    let mut approvals = APPROVALS.lock().map_err(|e| anyhow!("could not get lock to the command approvals: {}", e))?;
+    approvals.commands.insert(info.name.clone(), info.commands.clone());
+
+    approvals.write_to_file()
+}
+
+/// Revoke the approval of the plugin's commands, e.g., when the plugin is uninstalled.
+pub fn revoke(plugin: &str) -> Result<(), anyhow::Error> {
+    let mut approvals = APPROVALS.lock().map_err(|e| anyhow!("could not get lock to the command approvals: {}", e))?;
+
+    if approvals.commands.remove(plugin).is_none() {
+        return Ok(());
+    }
+
+    info!("Revoked the command approval of plugin '{}'", plugin);
+    approvals.write_to_file()
+}
+
+/// Run the command of the plugin with the arguments in the background, e.g., to save OBS's replay buffer.
+///
+/// The command is only run if the plugin declares it in its info file and the user approved the plugin's commands in the GUI.
+/// Programs are started directly without a shell.
+/// Returns the execution's id, the result is available with [`take_results`] once the program exited.
+pub fn run(info: &PluginInfo, command: &str, args: Vec<String>) -> Result<u32, anyhow::Error> {
+    if !info.commands.iter().any(|declared| declared == command) {
+        bail!("the plugin doesn't declare the command '{}' in its info file", command);
+    }
+    if !is_approved(info) {
+        bail!("the user didn't approve the plugin's commands in the GUI");
+    }
+
+    {
+        let mut running = RUNNING.lock().map_err(|e| anyhow!("could not get lock to the running programs: {}", e))?;
+        let count = running.entry(info.name.clone()).or_insert(0);
+
+        if *count >= MAX_RUNNING_PROGRAMS {
+            bail!("the plugin already runs {} programs", MAX_RUNNING_PROGRAMS);
+        }
+        *count += 1;
+    }
+
+    let id = NEXT_EXECUTION_ID.fetch_add(1, Ordering::SeqCst);
+    let plugin = info.name.clone();
+
+    info!("Plugin '{}' runs {} {:?}", plugin, command, args);
+
+    let command = command.to_string();
+    thread::spawn(move || {
+        let _worker = teardown::worker();
+        let result = execute(&command, &args).map_err(|e| e.to_string());
+
+        if let Ok(mut running) = RUNNING.lock() {
+            if let Some(count) = running.get_mut(&plugin) {
+                *count = count.saturating_sub(1);
+            }
+        }
+
+        match RESULTS.lock() {
+            Ok(mut results) => results.push(ExecResult { plugin, id, result }),
+            Err(e) => warn!("Could not get lock to the program results: {}", e),
+        }
+    });
+
+    Ok(id)
+}
+
+fn execute(command: &str, args: &[String]) -> Result<ProgramOutput, anyhow::Error> {
+    let mut child = Command::new(command)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn()
+        .map_err(|e| anyhow!("could not start '{}': {}", command, e))?;
+
+    // Read the output while the program runs, so it doesn't block on a full pipe
+    let stdout = child.stdout.take().map(read_output);
+    let stderr = child.stderr.take().map(read_output);
+
+    let status = wait(&mut child)?;
+
+    let join = |reader: Option<thread::JoinHandle<String>>| reader.and_then(|reader| reader.join().ok()).unwrap_or_default();
+
+    Ok(ProgramOutput {
+        exit_code: status,
+        stdout: join(stdout),
+        stderr: join(stderr),
+    })
+}
+
+/// Wait until the program exited, killing it after the timeout or when the engine is torn down.
+fn wait(child: &mut Child) -> Result<Option<i32>, anyhow::Error> {
+    let start = Instant::now();
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status.code());
+        }
+
+        if start.elapsed() > TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("the program didn't exit within {} seconds", TIMEOUT.as_secs());
+        }
+
+        if teardown::is_requested() {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("the engine was ejected before the program exited");
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Read the output in the background. Output beyond the limit is read but dropped.
+fn read_output(output: impl Read + Send + 'static) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut content = Vec::new();
+        let mut output = output;
+
+        let _ = output.by_ref().take(MAX_OUTPUT_SIZE).read_to_end(&mut content);
+        let _ = io::copy(&mut output, &mut io::sink());
+
+        String::from_utf8_lossy(&content).to_string()
+    })
+}
+
+/// Take the results of the programs that exited since the last call, to pass them to the plugins on the game's thread.
+pub fn take_results() -> Vec<ExecResult> {
+    match RESULTS.lock() {
+        Ok(mut results) => results.drain(..).collect(),
+        Err(_) => Vec::new(),
+    }
+}
@@ -0,0 +1,228 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use anyhow::{anyhow, bail};
+use futuremod_data::diagnostics::AddressMigration;
+use log::*;
+
+use super::GAME_FUNCTIONS;
+
+/// Address the game's executable is loaded at. All hard-coded addresses assume this base.
+pub const GAME_BASE_ADDRESS: u32 = 0x00400000;
+
+/// Characteristic of sections that contain executable code.
+const SECTION_EXECUTABLE: u32 = 0x20000000;
+
+static REGISTRY: OnceLock<AddressRegistry> = OnceLock::new();
+
+/// Byte pattern of the start of a function, `??` matches any byte, e.g., `55 8b ec 83 ec ?? a1`.
+#[derive(Debug, Clone)]
+pub struct Signature {
+    bytes: Vec<Option<u8>>,
+}
+
+impl Signature {
+    pub fn parse(pattern: &str) -> Result<Signature, anyhow::Error> {
+        let bytes = pattern
+            .split_whitespace()
+            .map(|byte| match byte {
+                "??" => Ok(None),
+                byte => u8::from_str_radix(byte, 16).map(Some).map_err(|_| anyhow!("invalid byte '{}'", byte)),
+            })
+            .collect::<Result<Vec<Option<u8>>, anyhow::Error>>()?;
+
+        if !matches!(bytes.first(), Some(Some(_))) {
+            bail!("signature must start with a byte that isn't a wildcard");
+        }
+
+        Ok(Signature { bytes })
+    }
+
+    fn matches(&self, code: &[u8]) -> bool {
+        code.len() >= self.bytes.len() && self.bytes.iter().zip(code).all(|(expected, actual)| expected.is_none() || *expected == Some(*actual))
+    }
+
+    /// Addresses of all matches in the code that starts at the address.
+    fn find(&self, code: &[u8], address: u32) -> Vec<u32> {
+        (0..code.len())
+            .filter(|offset| self.matches(&code[*offset..]))
+            .map(|offset| address + offset as u32)
+            .collect()
+    }
+}
+
+/// Resolved addresses and the migrations that were found.
+#[derive(Debug, Default)]
+struct AddressRegistry {
+    /// Addresses that differ from the hard-coded ones, by the function's name in Lua.
+    resolved: HashMap<&'static str, u32>,
+    migrations: Vec<AddressMigration>,
+
+    /// Functions whose signature is missing in or ambiguous in the game's code, with the reason.
+    unresolved: Vec<(&'static str, String)>,
+
+    /// Number of functions with a signature.
+    signatures: usize,
+}
+
+/// Executable sections of the game's module, as their address and code.
+fn code_sections() -> Result<Vec<(u32, &'static [u8])>, anyhow::Error> {
+    unsafe {
+        let read_u16 = |address: u32| *(address as *const u16);
+        let read_u32 = |address: u32| *(address as *const u32);
+
+        if read_u16(GAME_BASE_ADDRESS) != 0x5a4d {
+            bail!("the game's module doesn't start with a DOS header");
+        }
+
+        let nt_headers = GAME_BASE_ADDRESS + read_u32(GAME_BASE_ADDRESS + 0x3c);
+        if read_u32(nt_headers) != 0x4550 {
+            bail!("the game's module doesn't have a PE header");
+        }
+
+        let section_count = read_u16(nt_headers + 6) as u32;
+        let optional_header_size = read_u16(nt_headers + 20) as u32;
+        let section_table = nt_headers + 24 + optional_header_size;
+
+        let sections = (0..section_count)
+            .map(|i| section_table + i * 40)
+            .filter(|section| read_u32(section + 36) & SECTION_EXECUTABLE != 0)
+            .map(|section| {
+                let address = GAME_BASE_ADDRESS + read_u32(section + 12);
+                let size = read_u32(section + 8);
+
+                (address, std::slice::from_raw_parts(address as *const u8, size as usize))
+            })
+            .collect();
+
+        Ok(sections)
+    }
+}
+
+/// Resolve the addresses of all functions with a signature.
+///
+/// If a signature is found at another address than the hard-coded one, e.g., after a patch of the game, the found address
+/// is used and a migration is recorded, so that maintainers can update the address map.
+/// Functions without a signature, or whose signature isn't found, keep their hard-coded address.
+///
+/// Must be called when the mod is injected, before any hook is installed, since hooks change the start of functions.
+pub fn init() {
+    let mut registry = AddressRegistry::default();
+    let functions: Vec<_> = GAME_FUNCTIONS.iter().filter(|function| function.signature.is_some()).collect();
+
+    if !functions.is_empty() {
+        match code_sections() {
+            Ok(sections) => {
+                for function in functions {
+                    registry.signatures += 1;
+                    resolve_function(&mut registry, &sections, function.name, function.address, function.signature.unwrap_or_default());
+                }
+            },
+            Err(e) => warn!("Could not read the game's code, using the hard-coded addresses: {}", e),
+        }
+    }
+
+    if REGISTRY.set(registry).is_err() {
+        warn!("Addresses of the game's functions were already resolved");
+    }
+}
+
+fn resolve_function(registry: &mut AddressRegistry, sections: &[(u32, &[u8])], name: &'static str, expected: u32, pattern: &str) {
+    let signature = match Signature::parse(pattern) {
+        Ok(signature) => signature,
+        Err(e) => {
+            registry.unresolved.push((name, format!("invalid signature: {}", e)));
+            return;
+        },
+    };
+
+    let matches: Vec<u32> = sections.iter().flat_map(|(address, code)| signature.find(code, *address)).collect();
+
+    match matches.as_slice() {
+        [] => registry.unresolved.push((name, "signature not found".to_string())),
+        [found] if *found == expected => (),
+        [found] => {
+            warn!(
+                target: "migration",
+                function:% = name, expected:% = format!("{:#010x}", expected), found:% = format!("{:#010x}", found);
+                "Function {} moved from {:#010x} to {:#010x}, update its address", name, expected, found
+            );
+            registry.resolved.insert(name, *found);
+            registry.migrations.push(AddressMigration { name: name.to_string(), expected, found: *found });
+        },
+        // The hard-coded address is kept, as it's likely still right if it is one of the matches
+        _ => registry.unresolved.push((name, format!("signature matches {} addresses", matches.len()))),
+    }
+}
+
+/// Get the address of the function with the name it has in Lua, the hard-coded `address` unless its signature was found elsewhere.
+pub fn resolve(name: &str, address: u32) -> u32 {
+    REGISTRY.get().and_then(|registry| registry.resolved.get(name).copied()).unwrap_or(address)
+}
+
+/// Functions whose signature was found at another address than the hard-coded one.
+pub fn migrations() -> Vec<AddressMigration> {
+    REGISTRY.get().map(|registry| registry.migrations.clone()).unwrap_or_default()
+}
+
+/// Summary of the resolved signatures, and whether all were found at their hard-coded address.
+pub fn summary() -> Result<String, String> {
+    let registry = match REGISTRY.get() {
+        Some(registry) => registry,
+        None => return Err("addresses weren't resolved yet".to_string()),
+    };
+
+    if registry.signatures == 0 {
+        return Ok("no function has a signature, using the hard-coded addresses".to_string());
+    }
+
+    let mut problems: Vec<String> = registry.migrations
+        .iter()
+        .map(|migration| format!("{} moved from {:#010x} to {:#010x}", migration.name, migration.expected, migration.found))
+        .collect();
+    problems.extend(registry.unresolved.iter().map(|(name, reason)| format!("{}: {}, using the hard-coded address", name, reason)));
+
+    match problems.is_empty() {
+        true => Ok(format!("all {} signatures match their hard-coded address", registry.signatures)),
+        false => Err(problems.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_parses_bytes_and_wildcards() {
+        let signature = Signature::parse("55 8b EC ?? a1").unwrap();
+
+        assert_eq!(signature.bytes, vec![Some(0x55), Some(0x8b), Some(0xec), None, Some(0xa1)]);
+    }
+
+    #[test]
+    fn signature_rejects_invalid_bytes() {
+        assert!(Signature::parse("55 8g").is_err());
+        assert!(Signature::parse("55 123").is_err());
+    }
+
+    #[test]
+    fn signature_rejects_leading_wildcard_and_empty_pattern() {
+        assert!(Signature::parse("?? 8b ec").is_err());
+        assert!(Signature::parse("").is_err());
+    }
+
+    #[test]
+    fn signature_finds_all_matches_at_their_address() {
+        let signature = Signature::parse("55 ?? ec").unwrap();
+        let code = [0x90, 0x55, 0x8b, 0xec, 0x90, 0x55, 0x89, 0xec];
+
+        assert_eq!(signature.find(&code, 0x00401000), vec![0x00401001, 0x00401005]);
+    }
+
+    #[test]
+    fn signature_doesnt_match_past_the_end_of_the_code() {
+        let signature = Signature::parse("55 8b ec").unwrap();
+        let code = [0x90, 0x55, 0x8b];
+
+        assert!(signature.find(&code, 0x00401000).is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+pub use futuremod_hook::types::CallingConvention;
+use futuremod_hook::types::Type;
+
+use super::addresses;
+
+/// Game function in the registry.
+#[derive(Debug)]
+pub struct GameFunction {
+    /// Name of the function in Lua, e.g., `renderText`.
+    pub name: &'static str,
+    /// Hard-coded address of the function.
+    pub address: u32,
+    pub convention: CallingConvention,
+    pub arg_types: &'static [Type],
+    pub return_type: Type,
+    /// Byte pattern of the function's start, to find the function if it moved, see [`addresses`].
+    pub signature: Option<&'static str>,
+}
+
+impl GameFunction {
+    /// Address of the function, which differs from the hard-coded one if its signature was found elsewhere.
+    pub fn resolved_address(&self) -> u32 {
+        addresses::resolve(self.name, self.address)
+    }
+}
+
+/// Get the game function with the name it has in Lua.
+pub fn get_game_function(name: &str) -> Option<&'static GameFunction> {
+    GAME_FUNCTIONS.iter().find(|function| function.name == name)
+}
+
+/// Rust type of a game function's argument or return value, with the type native functions of plugins convert it with.
+pub trait NativeType {
+    const TYPE: Type;
+}
+
+macro_rules! native_type {
+    ($($rust_type:ty => $native_type:expr),* $(,)?) => {
+        $(
+            impl NativeType for $rust_type {
+                const TYPE: Type = $native_type;
+            }
+        )*
+    };
+}
+
+native_type! {
+    () => Type::Void,
+    i32 => Type::Integer,
+    u32 => Type::UnsignedInteger,
+    i16 => Type::Short,
+    u16 => Type::UnsignedShort,
+    i8 => Type::Byte,
+    u8 => Type::UnsignedByte,
+    f32 => Type::Float,
+    f64 => Type::Double,
+    *const u8 => Type::String,
+    *mut u32 => Type::UnsignedInteger,
+}
+
+/// Declare game functions.
+///
+/// Each declaration has the function's name in Lua, optionally the byte pattern of its start, calling convention (`cdecl`, `stdcall`,
+/// `thiscall`, or `fastcall`), wrapper, signature, and the name and value of its address constant.
+/// Each declaration generates the address constant, the wrapper that calls the function, and the function's entry in
+/// `GAME_FUNCTIONS`, which plugins call with `game.fn`.
+/// Only add the byte pattern once it was verified against the game, as a wrong pattern moves the function.
+macro_rules! game_functions {
+    (@fn_type cdecl ($($arg_type:ty),*) -> $ret:ty) => { unsafe extern "C" fn($($arg_type),*) -> $ret };
+    (@fn_type stdcall ($($arg_type:ty),*) -> $ret:ty) => { unsafe extern "stdcall" fn($($arg_type),*) -> $ret };
+    (@fn_type thiscall ($($arg_type:ty),*) -> $ret:ty) => { unsafe extern "thiscall" fn($($arg_type),*) -> $ret };
+    (@fn_type fastcall ($($arg_type:ty),*) -> $ret:ty) => { unsafe extern "fastcall" fn($($arg_type),*) -> $ret };
+    (@convention cdecl) => { CallingConvention::Cdecl };
+    (@convention stdcall) => { CallingConvention::Stdcall };
+    (@convention thiscall) => { CallingConvention::Thiscall };
+    (@convention fastcall) => { CallingConvention::Fastcall };
+    (@signature) => { None };
+    (@signature $signature:literal) => { Some($signature) };
+    (
+        $(
+            $(#[doc = $doc:literal])*
+            #[lua = $lua_name:literal]
+            $(#[signature = $signature:literal])?
+            $convention:ident fn $name:ident($($arg:ident: $arg_type:ty),* $(,)?) -> $ret:ty = $address_name:ident @ $address:literal;
+        )*
+    ) => {
+        $(
+            #[doc = concat!("Address of [`", stringify!($name), "`].")]
+            pub const $address_name: u32 = $address;
+
+            $(#[doc = $doc])*
+            #[allow(clippy::unused_unit)]
+            pub fn $name($($arg: $arg_type),*) -> $ret {
+                unsafe {
+                    let address = addresses::resolve($lua_name, $address_name);
+                    let function = std::mem::transmute::<*const (), game_functions!(@fn_type $convention ($($arg_type),*) -> $ret)>(address as _);
+                    function($($arg),*)
+                }
+            }
+        )*
+
+        /// All functions in the registry.
+        pub static GAME_FUNCTIONS: &[GameFunction] = &[
+            $(
+                GameFunction {
+                    name: $lua_name,
+                    address: $address_name,
+                    convention: game_functions!(@convention $convention),
+                    arg_types: &[$(<$arg_type as NativeType>::TYPE),*],
+                    return_type: <$ret as NativeType>::TYPE,
+                    signature: game_functions!(@signature $($signature)?),
+                },
+            )*
+        ];
+    };
+}
+
+game_functions! {
+    /// Render a character of the game's font at the position with the palette.
+    ///
+    /// Returns the x position of the next character.
+    #[lua = "renderCharacter"]
+    cdecl fn render_character(character: u32, pos_x: u32, pos_y: u32, palette: u32) -> u32 = RENDER_CHARACTER_FUNCTION_ADDRESS @ 0x00436130;
+
+    /// Render the null-terminated text at the position with the palette.
+    #[lua = "renderText"]
+    cdecl fn render_text(text: *const u8, pos_x: u32, pos_y: u32, palette: u32) -> () = RENDER_TEXT_FUNCTION_ADDRESS @ 0x00435f40;
+
+    /// Render a rectangle with the color at the position.
+    #[lua = "renderRectangle"]
+    cdecl fn render_rectangle(color: u32, pos_x: u16, pos_y: u16, width: u16, height: u16, semi_transparent: u8) -> () = RENDRE_RECTANGLE_FUNCTION_ADDRESS @ 0x00415450;
+
+    /// Update function of entities with behavior `0xa0`.
+    #[lua = "updateBehavior0xa0"]
+    cdecl fn update_function_behavior_0xa0(arg1: u32, arg2: u32, arg3: u32) -> u32 = UPDATE_FUNCTION_BEHAVIOR_0XA0_ADDRESS @ 0x0041a420;
+
+    #[lua = "renderObjectRaw"]
+    cdecl fn render_object_raw(arg1: u32, arg2: u32, arg3: u32) -> () = RENDER_OBJECT_ADDRESS @ 0x004284b0;
+
+    #[lua = "renderObject"]
+    cdecl fn render_object(model_data: u32, value_ref: *mut u32, arg3: u32) -> () = FUN_004280A0_ADDRESS @ 0x004280a0;
+}
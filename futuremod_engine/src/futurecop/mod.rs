@@ -5,12 +5,19 @@ use global::*;
 
 pub(crate) mod state;
 
+pub mod addresses;
+mod functions;
+pub use functions::*;
+
 
 ///////////////////////////////////////////////////////////
 // Known addresses
 ///////////////////////////////////////////////////////////
 pub const PLAYER_ARRAY_ADDR: u32 = 0x00511fd0;
 
+/// Upper bound of the entity list's length, so that a list corrupted into a cycle can't hang the game.
+pub const MAX_ENTITIES: usize = 8192;
+
 
 ///////////////////////////////////////////////////////////
 // Enums
@@ -66,69 +73,32 @@ pub type RenderObject = unsafe fn (u32, *mut u32, u32);
 ///////////////////////////////////////////////////////////
 // Function Addresses
 ///////////////////////////////////////////////////////////
+// Functions the mod calls are declared in the registry in `functions`.
+
 /// This is the first game function called in the main mission game loop.
 pub const FUN_00406A30_ADDRESS: u32 = 0x00406a30;
-pub const RENDER_CHARACTER_FUNCTION_ADDRESS: u32 = 0x00436130;
-pub const RENDER_TEXT_FUNCTION_ADDRESS: u32 = 0x00435f40;
-pub const RENDRE_RECTANGLE_FUNCTION_ADDRESS: u32 = 0x00415450;
 pub const GET_UPDATE_FUNCTION_OF_BEHAVIOR_ADDRESS: u32 = 0x0041a950;
-pub const UPDATE_FUNCTION_BEHAVIOR_0XA0_ADDRESS: u32 = 0x0041a420;
-pub const RENDER_OBJECT_ADDRESS: u32 = 0x004284b0;
-pub const FUN_004280A0_ADDRESS: u32 = 0x004280a0;
 
 
 ///////////////////////////////////////////////////////////
 // Functions
 ///////////////////////////////////////////////////////////
-macro_rules! fn_cast {
-    ($address:expr, $t:ty) => {
-        std::mem::transmute::<*const (), $t>($address as _)
-    };
-}
-
-pub fn render_character(character: u32, pos_x: u32, pos_y: u32, palette: u32) -> u32 {
-    let fn_ptr = RENDER_CHARACTER_FUNCTION_ADDRESS as *const();
-    unsafe {
-        let render_character_fn = {std::mem::transmute::<_, RenderCharacterFunction>(fn_ptr)};
-        render_character_fn(character, pos_x, pos_y, palette)
+/// Walk the game's entity list, starting at [`ENTITY_LIST_FIRST`] and following every entity's `next_entity`.
+///
+/// The game changes the list while it runs, thus, this must be called on the game's thread,
+/// e.g., by a plugin, and the entities are only valid until the game runs again.
+pub fn get_entities() -> Vec<*mut Entity> {
+    let mut entities = Vec::new();
+    let mut next = *ENTITY_LIST_FIRST.get();
+
+    while next != 0 && entities.len() < MAX_ENTITIES {
+        let entity = next as *mut Entity;
+        entities.push(entity);
+
+        next = unsafe { (*entity).next_entity };
     }
-}
 
-pub fn render_text(text: *const u8, pos_x: u32, pos_y: u32, palette: u32) {
-    unsafe {
-        let render_text_fn = fn_cast!(RENDER_TEXT_FUNCTION_ADDRESS, RenderTextFunction);
-        render_text_fn(text, pos_x, pos_y, palette);
-    }
-
-}
-
-pub fn render_rectangle(color: u32, pos_x: u16, pos_y: u16, width: u16, height: u16, semi_transparent: u8) {
-    unsafe {
-        let render_rect_fn = fn_cast!(RENDRE_RECTANGLE_FUNCTION_ADDRESS, RenderRectangleFunction);
-        render_rect_fn(color, pos_x, pos_y, width, height, semi_transparent);
-    }
-}
-
-pub fn update_function_behavior_0xa0(arg1: u32, arg2: u32, arg3: u32) -> u32 {
-    unsafe {
-        let update_fn = fn_cast!(UPDATE_FUNCTION_BEHAVIOR_0XA0_ADDRESS, UpdateFunction);
-        update_fn(arg1, arg2, arg3)
-    }
-}
-
-
-pub fn render_object_raw(arg1: u32, arg2: u32, arg3: u32) {
-    unsafe {
-        let render_object_fn = fn_cast!(RENDER_OBJECT_ADDRESS, RenderObjectRaw);
-        render_object_fn(arg1, arg2, arg3);
-    }
-}
-
-pub fn render_object(model_data: u32, value_ref: *mut u32, arg3: u32) {
-    unsafe {
-        let render_object_fn = fn_cast!(FUN_004280A0_ADDRESS, RenderObject);
-        render_object_fn(model_data, value_ref, arg3);
-    }
+    entities
 }
 
 ///////////////////////////////////////////////////////////
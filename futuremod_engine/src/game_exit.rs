@@ -0,0 +1,113 @@
+use std::{mem, sync::atomic::{AtomicBool, Ordering}, thread, time::Duration};
+
+use futuremod_data::session::GAME_EXIT_LOG_TARGET;
+use futuremod_hook::native::HookCall;
+use log::*;
+use windows::core::s;
+
+use crate::{borderless::{patch_import, replace_import}, plugins::plugin_manager::GlobalPluginManager, teardown};
+
+type ExitProcessFunction = unsafe extern "system" fn(u32);
+
+static mut ORIGINAL_EXIT_PROCESS: Option<ExitProcessFunction> = None;
+
+/// Set once the engine shut down because the game exits.
+static EXITED: AtomicBool = AtomicBool::new(false);
+
+/// Time the server gets to send the notice of the exit to the GUI before the process ends.
+const NOTIFY_GRACE_PERIOD: Duration = Duration::from_millis(200);
+
+/// Hook the game's exit. Must be called while the game's threads are suspended.
+///
+/// The game's import of `ExitProcess` is replaced, so that the engine shuts down on the game's thread before the process ends.
+/// If the game exits another way, the states and logs are still flushed in [`on_process_detach`].
+pub fn init() {
+    unsafe {
+        match patch_import(s!("kernel32.dll"), s!("ExitProcess"), exit_process as u32) {
+            Some(original) => ORIGINAL_EXIT_PROCESS = Some(mem::transmute::<u32, ExitProcessFunction>(original)),
+            None => warn!("Could not hook the game's exit, plugins won't be shut down when the game exits"),
+        }
+    }
+}
+
+/// Restore the game's original exit, e.g., when the engine is torn down.
+pub fn restore() {
+    unsafe {
+        if let Some(original) = ORIGINAL_EXIT_PROCESS {
+            if replace_import(exit_process as u32, original as u32).is_none() {
+                warn!("Could not restore the game's exit");
+            }
+
+            ORIGINAL_EXIT_PROCESS = None;
+        }
+    }
+}
+
+/// Whether the engine already shut down because the game exits.
+pub fn has_exited() -> bool {
+    EXITED.load(Ordering::SeqCst)
+}
+
+/// Shut down the engine before the game's process ends.
+///
+/// Disables and unloads all plugins, which runs their `onDisable` and `onUnload` functions, persists the plugins' states,
+/// notifies the GUI, and flushes the logs.
+/// Only the first call shuts down the engine, and nothing happens if the engine was already torn down.
+pub fn shutdown(exit_code: u32) {
+    if teardown::is_requested() || EXITED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    info!(target: GAME_EXIT_LOG_TARGET, "The game is exiting with code {}, shutting down the engine", exit_code);
+
+    if let Some(plugin_manager) = GlobalPluginManager::try_get() {
+        match plugin_manager.write() {
+            Ok(mut manager) => {
+                // Unloaded first, so that the persisted statistics include the plugins' sessions
+                manager.unload_all_plugins();
+                manager.persist();
+            },
+            Err(e) => warn!("Could not get lock to the plugin manager to shut down the plugins: {}", e),
+        }
+    }
+
+    info!("Shut down the engine, the game exits");
+    logger().flush();
+
+    // The server runs in another thread, which ends together with the process
+    thread::sleep(NOTIFY_GRACE_PERIOD);
+}
+
+/// Persist the plugins' states and flush the logs if the process ends without [`shutdown`].
+///
+/// Runs while the DLL is detached, after Windows terminated all other threads. Their locks might never be released,
+/// thus, the plugin manager is only persisted if it isn't locked, and no plugin runs anymore.
+pub fn on_process_detach() {
+    if teardown::is_requested() || has_exited() {
+        return;
+    }
+
+    let plugin_manager = match GlobalPluginManager::try_get() {
+        Some(plugin_manager) => plugin_manager,
+        None => return,
+    };
+
+    match plugin_manager.try_write() {
+        Ok(mut manager) => manager.persist(),
+        Err(_) => warn!("The game exited while the plugin manager was in use, the plugins' states weren't persisted"),
+    };
+
+    warn!("The game exited without shutting down the engine");
+    logger().flush();
+}
+
+unsafe extern "system" fn exit_process(exit_code: u32) {
+    {
+        let _call = HookCall::enter();
+        shutdown(exit_code);
+    }
+
+    if let Some(original) = ORIGINAL_EXIT_PROCESS {
+        original(exit_code);
+    }
+}
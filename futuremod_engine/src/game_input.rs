@@ -0,0 +1,242 @@
+use std::{collections::{HashMap, HashSet}, mem, sync::RwLock};
+
+use futuremod_hook::native::HookCall;
+use log::*;
+use windows::{core::s, Win32::Foundation::BOOL};
+
+use crate::borderless::{patch_import, replace_import};
+
+type KeyStateFunction = unsafe extern "system" fn(i32) -> i16;
+type KeyboardStateFunction = unsafe extern "system" fn(*mut u8) -> BOOL;
+
+static mut ORIGINAL_GET_ASYNC_KEY_STATE: Option<KeyStateFunction> = None;
+static mut ORIGINAL_GET_KEY_STATE: Option<KeyStateFunction> = None;
+static mut ORIGINAL_GET_KEYBOARD_STATE: Option<KeyboardStateFunction> = None;
+
+/// Bit of a key's state that is set while the key is held down.
+const KEY_DOWN: i16 = i16::MIN;
+
+/// Bit of a key's byte in the keyboard state that is set while the key is held down.
+const KEYBOARD_STATE_DOWN: u8 = 0x80;
+
+lazy_static! {
+    static ref GAME_KEYS: RwLock<GameKeys> = RwLock::new(GameKeys::default());
+}
+
+/// Changes to the keys the game reads when it polls the keyboard.
+#[derive(Debug, Default)]
+struct GameKeys {
+    /// Virtual keys plugins hold down in the current frame.
+    injected: HashSet<u16>,
+
+    /// Remaps from the virtual key the user presses to the virtual key the game reads.
+    remaps: HashMap<u16, u16>,
+}
+
+impl GameKeys {
+    /// Whether the game reads the key as held down, or `None` if the game reads the keyboard's state of the key.
+    ///
+    /// A remapped key reads as held while a key remapped to it is held, and not while the user holds the key itself.
+    /// Injected keys aren't remapped.
+    fn is_pressed(&self, key: u16, is_held: impl Fn(u16) -> bool) -> Option<bool> {
+        if self.injected.contains(&key) {
+            return Some(true);
+        }
+
+        let is_remapped = self.remaps.contains_key(&key);
+        let mut sources = self.remaps.iter().filter(|(_, target)| **target == key).map(|(source, _)| *source).peekable();
+
+        if !is_remapped && sources.peek().is_none() {
+            return None;
+        }
+
+        Some((!is_remapped && is_held(key)) || sources.any(&is_held))
+    }
+
+    /// Change the keys of the keyboard state, which holds a byte for every virtual key.
+    fn apply(&self, state: &mut [u8; 256]) {
+        let held = *state;
+
+        for key in 0..state.len() {
+            match self.is_pressed(key as u16, |key| held[key as usize] & KEYBOARD_STATE_DOWN != 0) {
+                Some(true) => state[key] |= KEYBOARD_STATE_DOWN,
+                Some(false) => state[key] &= !KEYBOARD_STATE_DOWN,
+                None => (),
+            }
+        }
+    }
+}
+
+/// Hook the game's input poll. Must be called while the game's threads are suspended.
+///
+/// The game's imports of `GetAsyncKeyState`, `GetKeyState`, and `GetKeyboardState`, which the game polls the keyboard with, are replaced
+/// with functions that add the keys plugins inject to the keys the game reads and apply the input remaps.
+pub fn init() {
+    unsafe {
+        ORIGINAL_GET_ASYNC_KEY_STATE = patch_import(s!("user32.dll"), s!("GetAsyncKeyState"), get_async_key_state as u32)
+            .map(|original| mem::transmute::<u32, KeyStateFunction>(original));
+        ORIGINAL_GET_KEY_STATE = patch_import(s!("user32.dll"), s!("GetKeyState"), get_key_state as u32)
+            .map(|original| mem::transmute::<u32, KeyStateFunction>(original));
+        ORIGINAL_GET_KEYBOARD_STATE = patch_import(s!("user32.dll"), s!("GetKeyboardState"), get_keyboard_state as u32)
+            .map(|original| mem::transmute::<u32, KeyboardStateFunction>(original));
+
+        if ORIGINAL_GET_ASYNC_KEY_STATE.is_none() && ORIGINAL_GET_KEY_STATE.is_none() && ORIGINAL_GET_KEYBOARD_STATE.is_none() {
+            warn!("Could not hook the game's input poll, injected keys and input remaps only reach plugins");
+        }
+    }
+}
+
+/// Restore the game's original input poll, e.g., when the engine is torn down.
+pub fn restore() {
+    unsafe {
+        restore_import(get_async_key_state as u32, ORIGINAL_GET_ASYNC_KEY_STATE.map(|original| original as u32), "GetAsyncKeyState");
+        restore_import(get_key_state as u32, ORIGINAL_GET_KEY_STATE.map(|original| original as u32), "GetKeyState");
+        restore_import(get_keyboard_state as u32, ORIGINAL_GET_KEYBOARD_STATE.map(|original| original as u32), "GetKeyboardState");
+
+        ORIGINAL_GET_ASYNC_KEY_STATE = None;
+        ORIGINAL_GET_KEY_STATE = None;
+        ORIGINAL_GET_KEYBOARD_STATE = None;
+    }
+}
+
+unsafe fn restore_import(hook: u32, original: Option<u32>, name: &str) {
+    if let Some(original) = original {
+        if replace_import(hook, original).is_none() {
+            warn!("Could not restore the game's import of {}", name);
+        }
+    }
+}
+
+/// Replace the virtual keys plugins hold down. Must be called once per frame, before the game polls its input.
+pub fn set_injected_keys(keys: HashSet<u16>) {
+    match GAME_KEYS.write() {
+        Ok(mut game_keys) => game_keys.injected = keys,
+        Err(e) => warn!("Could not get lock to the game's keys: {}", e),
+    }
+}
+
+/// Replace the remaps from the virtual key the user presses to the virtual key the game reads.
+pub fn set_remaps(remaps: HashMap<u16, u16>) {
+    match GAME_KEYS.write() {
+        Ok(mut game_keys) => game_keys.remaps = remaps,
+        Err(e) => warn!("Could not get lock to the game's keys: {}", e),
+    }
+}
+
+/// Whether the game reads the key as held down, or `None` if the game reads the keyboard's state of the key.
+///
+/// Doesn't wait for the lock, as the game's input poll must not block.
+fn is_pressed(key: i32, is_held: impl Fn(u16) -> bool) -> Option<bool> {
+    let key = u16::try_from(key).ok()?;
+
+    GAME_KEYS.try_read().ok().and_then(|game_keys| game_keys.is_pressed(key, is_held))
+}
+
+unsafe extern "system" fn get_async_key_state(key: i32) -> i16 {
+    let _call = HookCall::enter();
+
+    let original = match ORIGINAL_GET_ASYNC_KEY_STATE {
+        Some(original) => original,
+        None => return 0,
+    };
+
+    let state = original(key);
+    match is_pressed(key, |key| original(key as i32) & KEY_DOWN != 0) {
+        Some(true) => state | KEY_DOWN,
+        Some(false) => 0,
+        None => state,
+    }
+}
+
+unsafe extern "system" fn get_key_state(key: i32) -> i16 {
+    let _call = HookCall::enter();
+
+    let original = match ORIGINAL_GET_KEY_STATE {
+        Some(original) => original,
+        None => return 0,
+    };
+
+    // The lowest bit is whether a key like caps lock is toggled, which is kept
+    let state = original(key);
+    match is_pressed(key, |key| original(key as i32) & KEY_DOWN != 0) {
+        Some(true) => state | KEY_DOWN,
+        Some(false) => state & 1,
+        None => state,
+    }
+}
+
+unsafe extern "system" fn get_keyboard_state(state: *mut u8) -> BOOL {
+    let _call = HookCall::enter();
+
+    let original = match ORIGINAL_GET_KEYBOARD_STATE {
+        Some(original) => original,
+        None => return BOOL(0),
+    };
+
+    let result = original(state);
+    if !result.as_bool() || state.is_null() {
+        return result;
+    }
+
+    if let Ok(game_keys) = GAME_KEYS.try_read() {
+        game_keys.apply(&mut *(state as *mut [u8; 256]));
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn injected_keys_are_pressed() {
+        let game_keys = GameKeys { injected: HashSet::from([0x41]), ..Default::default() };
+
+        assert_eq!(game_keys.is_pressed(0x41, |_| false), Some(true));
+        assert_eq!(game_keys.is_pressed(0x42, |_| true), None);
+    }
+
+    #[test]
+    fn remapped_keys_read_as_their_target() {
+        let game_keys = GameKeys { remaps: HashMap::from([(0x51, 0x20)]), ..Default::default() };
+        let is_held = |key| key == 0x51;
+
+        assert_eq!(game_keys.is_pressed(0x20, is_held), Some(true));
+        assert_eq!(game_keys.is_pressed(0x51, is_held), Some(false));
+        assert_eq!(game_keys.is_pressed(0x41, is_held), None);
+    }
+
+    #[test]
+    fn swapped_keys_read_as_each_other() {
+        let game_keys = GameKeys { remaps: HashMap::from([(0x41, 0x42), (0x42, 0x41)]), ..Default::default() };
+        let is_held = |key| key == 0x41;
+
+        assert_eq!(game_keys.is_pressed(0x41, is_held), Some(false));
+        assert_eq!(game_keys.is_pressed(0x42, is_held), Some(true));
+    }
+
+    #[test]
+    fn remaps_are_applied_to_the_keyboard_state() {
+        let game_keys = GameKeys { remaps: HashMap::from([(0x51, 0x20)]), ..Default::default() };
+        let mut state = [0; 256];
+        state[0x51] = KEYBOARD_STATE_DOWN;
+
+        game_keys.apply(&mut state);
+
+        assert_eq!(state[0x20], KEYBOARD_STATE_DOWN);
+        assert_eq!(state[0x51], 0);
+    }
+
+    #[test]
+    fn injected_keys_are_added_to_the_keyboard_state() {
+        let game_keys = GameKeys { injected: HashSet::from([0x41]), ..Default::default() };
+        let mut state = [0; 256];
+        state[0x42] = KEYBOARD_STATE_DOWN;
+
+        game_keys.apply(&mut state);
+
+        assert_eq!(state[0x41], KEYBOARD_STATE_DOWN);
+        assert_eq!(state[0x42], KEYBOARD_STATE_DOWN);
+    }
+}
@@ -0,0 +1,105 @@
+use std::{sync::{atomic::{AtomicU64, AtomicUsize, Ordering}, RwLock}, time::{Duration, Instant}};
+
+use log::*;
+use mlua::Lua;
+
+use crate::config::GarbageCollectionConfig;
+
+lazy_static! {
+    static ref CONFIG: RwLock<GarbageCollectionConfig> = RwLock::new(GarbageCollectionConfig::default());
+}
+
+/// Number of cycles the steps after the frames finished.
+static CYCLES: AtomicU64 = AtomicU64::new(0);
+
+/// Bytes the Lua states used together after the last frame.
+static USED_MEMORY: AtomicUsize = AtomicUsize::new(0);
+
+/// Lua state whose garbage is collected first after the next frame.
+static NEXT_STATE: AtomicUsize = AtomicUsize::new(0);
+
+/// Set how the garbage collector runs. Must be called when the mod is injected.
+pub fn init(config: &GarbageCollectionConfig) {
+    if config.frame_stepping {
+        info!("Collecting garbage in steps of {} KB for at most {} ms per frame", config.step_size, config.frame_budget);
+    }
+
+    match CONFIG.write() {
+        Ok(mut gc_config) => *gc_config = config.clone(),
+        Err(e) => warn!("Could not get lock to the garbage collection config: {}", e),
+    }
+}
+
+pub fn is_frame_stepping() -> bool {
+    CONFIG.read().map(|config| config.frame_stepping).unwrap_or(false)
+}
+
+/// Collect garbage of the Lua states after the frame until each state finished a cycle or the frame budget is spent.
+///
+/// Does nothing but record the used memory if the garbage collector doesn't step after each frame.
+/// Must be called once per frame of the mission's game loop after the plugins were updated.
+/// Returns the time spent collecting garbage.
+pub fn on_frame(states: &[&Lua]) -> Duration {
+    let (step_size, frame_budget) = match CONFIG.read() {
+        Ok(config) if config.frame_stepping => (config.step_size, config.frame_budget),
+        _ => {
+            record_used_memory(states);
+            return Duration::ZERO;
+        },
+    };
+
+    let budget = Duration::from_secs_f64(frame_budget.max(0.0) / 1000.0);
+    let start = Instant::now();
+
+    // Each frame starts with another state, so that all states get a share of the budget
+    let first = NEXT_STATE.fetch_add(1, Ordering::Relaxed);
+
+    for i in 0..states.len() {
+        let lua = states[(first + i) % states.len()];
+
+        // Garbage is only collected by the steps, so allocations don't trigger collections within a frame.
+        // States are stopped every frame, as plugins that were loaded since the last frame have new states.
+        lua.gc_stop();
+
+        loop {
+            match lua.gc_step_kbytes(step_size as i32) {
+                Ok(true) => {
+                    // The remaining garbage is collected by the next cycle in the following frames
+                    CYCLES.fetch_add(1, Ordering::Relaxed);
+                    break;
+                },
+                Ok(false) => (),
+                Err(e) => {
+                    warn!("Could not collect garbage: {}", e);
+                    break;
+                },
+            }
+
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+
+        if start.elapsed() >= budget {
+            break;
+        }
+    }
+
+    record_used_memory(states);
+
+    start.elapsed()
+}
+
+fn record_used_memory(states: &[&Lua]) {
+    USED_MEMORY.store(states.iter().map(|lua| lua.used_memory()).sum(), Ordering::Relaxed);
+}
+
+/// Kilobytes the Lua states used together after the last frame.
+pub fn used_memory() -> f64 {
+    USED_MEMORY.load(Ordering::Relaxed) as f64 / 1024.0
+}
+
+/// Number of cycles the steps after the frames finished.
+pub fn cycles() -> u64 {
+    CYCLES.load(Ordering::Relaxed)
+}
@@ -0,0 +1,99 @@
+use std::sync::Mutex;
+
+use log::*;
+use serde::Serialize;
+
+use crate::futurecop::PlayerEntity;
+
+lazy_static! {
+    static ref GHOST: Mutex<Ghost> = Mutex::new(Ghost { current: None, previous: Vec::new() });
+}
+
+/// Position of player one in a frame of a run.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Position {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
+impl Position {
+    pub fn of(player: &PlayerEntity) -> Position {
+        Position { x: player.position_x, y: player.position_y, z: player.position_z }
+    }
+}
+
+/// Positions of player one per frame of the current and the previous run.
+///
+/// A run starts when player one is created and ends when player one is destroyed.
+/// The previous run is the ghost that plugins can race against. Plugins read it with the `replay` library
+/// and compare it with the player's position, e.g., to show how far ahead or behind the player is.
+struct Ghost {
+    current: Option<Vec<Position>>,
+    previous: Vec<Position>,
+}
+
+/// Start recording a new run. Must be called when a mission starts.
+pub fn on_mission_start() {
+    with_ghost(|ghost| ghost.current = Some(Vec::new()));
+}
+
+/// Record the position of player one in the frame of the mission's game loop.
+///
+/// Must be called once per frame before plugins are updated.
+pub fn on_frame(position: Option<Position>) {
+    with_ghost(|ghost| {
+        if let (Some(run), Some(position)) = (ghost.current.as_mut(), position) {
+            run.push(position);
+        }
+    });
+}
+
+/// Finish the current run and make it the ghost. Must be called when a mission ends.
+pub fn on_mission_end() {
+    with_ghost(|ghost| {
+        if let Some(run) = ghost.current.take() {
+            if !run.is_empty() {
+                info!("Recorded a run with {} frames as the ghost", run.len());
+                ghost.previous = run;
+            }
+        }
+    });
+}
+
+/// Frame of the current run, counted from zero, or `None` if no run is recorded.
+pub fn current_frame() -> Option<usize> {
+    read_ghost(|ghost| ghost.current.as_ref().and_then(|run| run.len().checked_sub(1)))
+}
+
+/// Number of frames of the ghost.
+pub fn ghost_length() -> usize {
+    read_ghost(|ghost| ghost.previous.len())
+}
+
+/// Position of the ghost in the frame, or `None` if the ghost's run is shorter.
+pub fn ghost_position(frame: usize) -> Option<Position> {
+    read_ghost(|ghost| ghost.previous.get(frame).copied())
+}
+
+/// Discard the ghost.
+pub fn clear_ghost() {
+    with_ghost(|ghost| ghost.previous.clear());
+}
+
+fn with_ghost(f: impl FnOnce(&mut Ghost)) {
+    match GHOST.lock() {
+        Ok(mut ghost) => f(&mut ghost),
+        Err(e) => warn!("Could not get lock to the ghost: {}", e),
+    }
+}
+
+fn read_ghost<T: Default>(f: impl FnOnce(&Ghost) -> T) -> T {
+    match GHOST.lock() {
+        Ok(ghost) => f(&ghost),
+        Err(e) => {
+            warn!("Could not get lock to the ghost: {}", e);
+            T::default()
+        },
+    }
+}
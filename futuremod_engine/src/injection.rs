@@ -0,0 +1,145 @@
+use std::{collections::{HashMap, HashSet}, sync::Mutex};
+
+use device_query::Keycode;
+use log::*;
+
+use crate::{controller::PRESS_THRESHOLD, game_input, input::KeyState, key_bindings, remapping::virtual_key};
+
+lazy_static! {
+    static ref INJECTION: Mutex<Injection> = Mutex::new(Injection::default());
+}
+
+/// Input plugins inject with `input.sendKey` and `input.setAxis`, e.g., for macros, accessibility tools, or netplay.
+///
+/// Injected keys are added to the frame's key state instead of being sent to the OS, thus, they apply in exactly the frames
+/// they were injected for, independent of the game's focus. The input library, key bindings, and input recordings see them
+/// like the keys the user presses, and the game reads them when it polls the keyboard, see [`game_input`].
+#[derive(Debug, Default)]
+struct Injection {
+    /// Input of the plugins by the plugin's name.
+    plugins: HashMap<String, PluginInput>,
+
+    /// Keys that were injected in the last frame, to detect presses and releases.
+    pressed: HashSet<Keycode>,
+}
+
+/// Input a plugin injects.
+#[derive(Debug, Default)]
+struct PluginInput {
+    /// Frames each key is still held down.
+    keys: HashMap<Keycode, u32>,
+
+    /// Value of each axis by its negative and positive key.
+    axes: HashMap<(Keycode, Keycode), f32>,
+}
+
+impl PluginInput {
+    /// Keys the plugin holds down in the current frame.
+    fn pressed_keys(&self) -> impl Iterator<Item = Keycode> + '_ {
+        let axis_keys = self.axes.iter().filter_map(|((negative, positive), value)| {
+            if *value <= -PRESS_THRESHOLD {
+                Some(*negative)
+            } else if *value >= PRESS_THRESHOLD {
+                Some(*positive)
+            } else {
+                None
+            }
+        });
+
+        self.keys.keys().copied().chain(axis_keys)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.keys.is_empty() && self.axes.is_empty()
+    }
+}
+
+/// Hold the key down for the number of frames, starting with the next frame.
+///
+/// Sending a key that is already held extends it to the number of frames.
+pub fn send_key(plugin: &str, key: Keycode, frames: u32) {
+    with_plugin_input(plugin, |input| {
+        let held = input.keys.entry(key).or_insert(0);
+        *held = (*held).max(frames.max(1));
+    });
+}
+
+/// Set the axis between the two keys to the value, from `-1.0` for the negative to `1.0` for the positive key.
+///
+/// The axis holds its value until it is set again, `0.0` releases both keys.
+pub fn set_axis(plugin: &str, negative: Keycode, positive: Keycode, value: f32) {
+    with_plugin_input(plugin, |input| {
+        match value == 0.0 {
+            true => {
+                input.axes.remove(&(negative, positive));
+            },
+            false => {
+                input.axes.insert((negative, positive), value.clamp(-1.0, 1.0));
+            },
+        }
+    });
+}
+
+/// Release all keys and axes of the plugin, e.g., when it is disabled.
+pub fn release(plugin: &str) {
+    if let Ok(mut injection) = INJECTION.lock() {
+        injection.plugins.remove(plugin);
+    }
+}
+
+/// Add the injected keys to the key state.
+///
+/// Must be called once per frame after the key state was updated and before plugins are updated.
+pub fn on_frame(key_state: &KeyState) {
+    let mut injection = match INJECTION.lock() {
+        Ok(injection) => injection,
+        Err(e) => {
+            warn!("Could not get lock to the injected input: {}", e);
+            return;
+        },
+    };
+    let injection = &mut *injection;
+
+    let pressed: HashSet<Keycode> = injection.plugins.values().flat_map(PluginInput::pressed_keys).collect();
+
+    // Presses and releases are recorded like the keyboard's, so that `onKeyDown` callbacks fire for injected keys
+    for key in pressed.difference(&injection.pressed) {
+        if let Some(key) = virtual_key(*key) {
+            key_bindings::record_key(key, false);
+        }
+    }
+    for key in injection.pressed.difference(&pressed) {
+        if let Some(key) = virtual_key(*key) {
+            key_bindings::record_key(key, true);
+        }
+    }
+
+    game_input::set_injected_keys(pressed.iter().filter_map(|key| virtual_key(*key)).map(|key| key.0).collect());
+
+    if !pressed.is_empty() {
+        let result = key_state.get_state().and_then(|mut keys| {
+            keys.extend(pressed.iter().copied());
+            key_state.set_state(keys)
+        });
+
+        if let Err(e) = result {
+            warn!("Could not inject the keys: {}", e);
+        }
+    }
+
+    for input in injection.plugins.values_mut() {
+        input.keys.retain(|_, frames| {
+            *frames -= 1;
+            *frames > 0
+        });
+    }
+    injection.plugins.retain(|_, input| !input.is_empty());
+    injection.pressed = pressed;
+}
+
+fn with_plugin_input(plugin: &str, f: impl FnOnce(&mut PluginInput)) {
+    match INJECTION.lock() {
+        Ok(mut injection) => f(injection.plugins.entry(plugin.to_string()).or_default()),
+        Err(e) => warn!("Could not get lock to the injected input: {}", e),
+    }
+}
@@ -42,6 +42,20 @@ impl KeyState {
     }
   }
 
+  /// Overwrite the key state with the given keys.
+  /// 
+  /// Used to simulate input when the game isn't running, e.g., in the mock environment.
+  /// Like [`KeyState::update`], only call this function once per frame.
+  pub fn set_state(&self, pressed_keys: HashSet<Keycode>) -> Result<(), anyhow::Error> {
+    match self.state.lock() {
+      Ok(mut key_state) => {
+        *key_state = pressed_keys;
+        Ok(())
+      },
+      Err(e) => anyhow::bail!("Could not get lock to key state global: {}", e.to_string()),
+    }
+  }
+
   /// Get all currently pressed keys.
   /// 
   /// The returned hashset will contain all keys that are currently pressed.
@@ -0,0 +1,160 @@
+use std::{collections::HashSet, fs::File, io::{BufRead, BufReader, Write}, path::Path, sync::Mutex};
+
+use anyhow::anyhow;
+use device_query::Keycode;
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::{InputRecordingConfig, InputRecordingMode}, input::KeyState, plugins::library::input::{keycode_from_string, keycode_to_string}};
+
+lazy_static! {
+    static ref INPUT_RECORDING: Mutex<InputRecording> = Mutex::new(InputRecording::Idle);
+}
+
+/// Keys that are pressed starting from a frame of a recording.
+///
+/// Recordings are stored as JSON lines and only contain an entry when the pressed keys change.
+/// Keys use the same names as the input library, e.g., `KeySpace`.
+/// The format matches the scripted input of the mock environment, so recordings can be replayed there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedInput {
+    /// Frame of the mission's game loop, counted from the start of the recording.
+    pub frame: u32,
+
+    pub keys: Vec<String>,
+}
+
+/// Records the pressed keys per frame or replays a recording.
+enum InputRecording {
+    Idle,
+    Recording {
+        file: File,
+        frame: u32,
+        last_keys: Option<HashSet<Keycode>>,
+    },
+    Playback {
+        inputs: Vec<RecordedInput>,
+        next: usize,
+        frame: u32,
+        keys: HashSet<Keycode>,
+    },
+}
+
+/// Start recording or replaying the pressed keys.
+pub fn start(config: &InputRecordingConfig) -> Result<(), anyhow::Error> {
+    let mut input_recording = INPUT_RECORDING.lock().map_err(|e| anyhow!("could not get lock to the input recording: {}", e))?;
+
+    *input_recording = match config.mode {
+        InputRecordingMode::Record => {
+            let file = File::create(&config.file).map_err(|e| anyhow!("could not create input recording '{}': {}", config.file, e))?;
+            info!("Recording the pressed keys to '{}'", config.file);
+
+            InputRecording::Recording { file, frame: 0, last_keys: None }
+        },
+        InputRecordingMode::Playback => {
+            let inputs = load(Path::new(&config.file))?;
+            info!("Replaying {} inputs from '{}'", inputs.len(), config.file);
+
+            InputRecording::Playback { inputs, next: 0, frame: 0, keys: HashSet::new() }
+        },
+    };
+
+    Ok(())
+}
+
+/// Record the pressed keys of the frame or overwrite them with the recording.
+///
+/// Must be called once per frame of the mission's game loop after the key state was updated and before plugins are updated.
+pub fn on_frame(key_state: &KeyState) {
+    let mut input_recording = match INPUT_RECORDING.lock() {
+        Ok(input_recording) => input_recording,
+        Err(e) => {
+            warn!("Could not get lock to the input recording: {}", e);
+            return;
+        },
+    };
+
+    let finished = match &mut *input_recording {
+        InputRecording::Idle => false,
+        InputRecording::Recording { file, frame, last_keys } => {
+            record_frame(file, *frame, last_keys, key_state);
+            *frame += 1;
+            false
+        },
+        InputRecording::Playback { inputs, next, frame, keys } => {
+            while *next < inputs.len() && inputs[*next].frame <= *frame {
+                *keys = inputs[*next].keys.iter().filter_map(|key| keycode_from_string(key.clone()).ok()).collect();
+                *next += 1;
+            }
+
+            if let Err(e) = key_state.set_state(keys.clone()) {
+                warn!("Could not replay the pressed keys: {}", e);
+            }
+
+            *frame += 1;
+            *next >= inputs.len()
+        },
+    };
+
+    if finished {
+        info!("Finished replaying the input recording");
+        *input_recording = InputRecording::Idle;
+    }
+}
+
+fn record_frame(file: &mut File, frame: u32, last_keys: &mut Option<HashSet<Keycode>>, key_state: &KeyState) {
+    let keys = match key_state.get_state() {
+        Ok(keys) => keys,
+        Err(e) => {
+            warn!("Could not get the pressed keys: {}", e);
+            return;
+        },
+    };
+
+    if last_keys.as_ref() == Some(&keys) {
+        return;
+    }
+
+    let mut key_names: Vec<String> = keys.iter().map(|key| keycode_to_string(*key)).collect();
+    key_names.sort();
+
+    let line = match serde_json::to_string(&RecordedInput { frame, keys: key_names }) {
+        Ok(line) => line,
+        Err(e) => {
+            warn!("Could not serialize the pressed keys: {}", e);
+            return;
+        },
+    };
+
+    if let Err(e) = writeln!(file, "{}", line) {
+        warn!("Could not write the pressed keys to the input recording: {}", e);
+    }
+
+    *last_keys = Some(keys);
+}
+
+/// Load an input recording from a file.
+pub fn load(path: &Path) -> Result<Vec<RecordedInput>, anyhow::Error> {
+    let file = File::open(path).map_err(|e| anyhow!("could not open input recording '{}': {}", path.display(), e))?;
+
+    let mut inputs = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| anyhow!("could not read input recording '{}': {}", path.display(), e))?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let input: RecordedInput = serde_json::from_str(&line)
+            .map_err(|e| anyhow!("invalid input in line {} of input recording '{}': {}", number + 1, path.display(), e))?;
+
+        for key in input.keys.iter() {
+            keycode_from_string(key.clone()).map_err(|_| anyhow!("input in line {} of input recording '{}' contains invalid key '{}'", number + 1, path.display(), key))?;
+        }
+
+        inputs.push(input);
+    }
+
+    Ok(inputs)
+}
@@ -0,0 +1,188 @@
+use std::{sync::Mutex, thread, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use anyhow::{anyhow, bail};
+use futuremod_data::integrity::{IntegrityReport, IntegrityStatus, MemoryPatch, PluginFingerprint};
+use futuremod_hook::native::active_hooks;
+use log::*;
+use reqwest::{blocking::Client, header::CONTENT_TYPE, redirect::Policy};
+use sha2::{Digest, Sha256};
+
+use crate::{config::IntegrityConfig, plugins::plugin_manager::GlobalPluginManager, teardown};
+
+const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Interval in which the report is checked for changes, e.g., a plugin that was enabled.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reports that weren't accepted in this time fail.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+lazy_static! {
+    static ref STATUS: Mutex<IntegrityStatus> = Mutex::new(IntegrityStatus::default());
+}
+
+/// Start sending reports if an endpoint is configured. Must be called after the plugin manager was initialized.
+///
+/// Integrity reports are meant for community events, e.g., online races with an agreed plugin set.
+/// The engine regularly sends a report of the enabled plugins and the hooked functions to the event's verification endpoint.
+/// The report's hash is equal for all participants that run the same plugins, so organizers can tell who deviated.
+/// Nothing is sent unless the user configures an endpoint.
+pub fn init(config: &Option<IntegrityConfig>) {
+    let config = match config {
+        Some(config) => config.clone(),
+        None => return,
+    };
+
+    info!("Sending integrity reports to '{}'", config.endpoint);
+    with_status(|status| {
+        status.enabled = true;
+        status.endpoint = Some(config.endpoint.clone());
+    });
+
+    // Sending blocks. Thus, the reports are sent from their own thread and never from the game's thread.
+    thread::spawn(move || send_reports(config));
+}
+
+/// Get the state of the integrity reports, including the latest report.
+pub fn get_status() -> Result<IntegrityStatus, anyhow::Error> {
+    let status = STATUS.lock().map_err(|e| anyhow!("could not get lock to the integrity status: {}", e))?;
+
+    Ok(status.clone())
+}
+
+/// Create a report of the currently enabled plugins and hooked functions.
+pub fn create_report(participant: &str) -> Result<IntegrityReport, anyhow::Error> {
+    let plugin_manager = GlobalPluginManager::try_get().ok_or(anyhow!("the plugins aren't loaded yet"))?;
+    let plugin_manager = plugin_manager.read().map_err(|e| anyhow!("could not get lock to the plugin manager: {}", e))?;
+
+    let mut plugins: Vec<PluginFingerprint> = plugin_manager.get_plugins()
+        .values()
+        .filter(|plugin| plugin.is_enabled())
+        .map(|plugin| PluginFingerprint {
+            name: plugin.info.name.clone(),
+            version: plugin.info.version.clone(),
+            checksum: plugin.checksum.clone(),
+            tampered: plugin.tampered,
+            developer: plugin.dev_link.is_some(),
+        })
+        .collect();
+    drop(plugin_manager);
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let patches: Vec<MemoryPatch> = active_hooks()
+        .into_iter()
+        .map(|hook| MemoryPatch { address: hook.address, owner: hook.owner })
+        .collect();
+
+    Ok(IntegrityReport {
+        participant: participant.to_string(),
+        engine_version: ENGINE_VERSION.to_string(),
+        hash: hash(&plugins, &patches),
+        plugins,
+        patches,
+        created_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0),
+    })
+}
+
+/// Hash the engine version, plugins, and patches. Every field is terminated, so that no two reports hash the same bytes.
+fn hash(plugins: &[PluginFingerprint], patches: &[MemoryPatch]) -> String {
+    let mut hasher = Sha256::new();
+
+    hasher.update(ENGINE_VERSION.as_bytes());
+    hasher.update([0]);
+
+    hasher.update((plugins.len() as u64).to_le_bytes());
+    for plugin in plugins {
+        for field in [&plugin.name, &plugin.version, plugin.checksum.as_deref().unwrap_or("")] {
+            hasher.update(field.as_bytes());
+            hasher.update([0]);
+        }
+        hasher.update([plugin.tampered as u8, plugin.developer as u8]);
+    }
+
+    hasher.update((patches.len() as u64).to_le_bytes());
+    for patch in patches {
+        hasher.update(patch.address.to_le_bytes());
+        hasher.update(patch.owner.as_deref().unwrap_or("").as_bytes());
+        hasher.update([0]);
+    }
+
+    format!("{:x}", hasher.finalize())
+}
+
+/// Send a report whenever the plugins or patches change, and at least once per interval.
+fn send_reports(config: IntegrityConfig) {
+    let _worker = teardown::worker();
+    let client = match Client::builder()
+        .timeout(TIMEOUT)
+        .redirect(Policy::none())
+        .user_agent(concat!("futuremod/", env!("CARGO_PKG_VERSION")))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Could not create the client for integrity reports: {}", e);
+            return;
+        },
+    };
+
+    let interval = Duration::from_secs(config.interval.max(1));
+    let mut sent: Option<(String, Instant)> = None;
+
+    loop {
+        match create_report(&config.participant) {
+            Ok(report) => {
+                let due = match &sent {
+                    Some((hash, at)) => *hash != report.hash || at.elapsed() >= interval,
+                    None => true,
+                };
+
+                if due {
+                    let result = send(&client, &config.endpoint, &report);
+                    let hash = report.hash.clone();
+
+                    with_status(|status| {
+                        status.report = Some(report);
+                        match result {
+                            Ok(_) => {
+                                status.last_sent_at = status.report.as_ref().map(|report| report.created_at);
+                                status.last_error = None;
+                            },
+                            Err(e) => {
+                                warn!("Could not send the integrity report: {}", e);
+                                status.last_error = Some(e.to_string());
+                            },
+                        }
+                    });
+
+                    // Failed reports are retried after the interval, not on every check
+                    sent = Some((hash, Instant::now()));
+                }
+            },
+            Err(e) => debug!("Could not create the integrity report: {}", e),
+        }
+
+        if !teardown::sleep(CHECK_INTERVAL) {
+            return;
+        }
+    }
+}
+
+fn send(client: &Client, endpoint: &str, report: &IntegrityReport) -> Result<(), anyhow::Error> {
+    let body = serde_json::to_vec(report).map_err(|e| anyhow!("could not serialize the report: {}", e))?;
+
+    let response = client.post(endpoint).header(CONTENT_TYPE, "application/json").body(body).send()?;
+    if !response.status().is_success() {
+        bail!("the endpoint responded with status {}", response.status());
+    }
+
+    debug!("Sent integrity report with hash {}", report.hash);
+    Ok(())
+}
+
+fn with_status(f: impl FnOnce(&mut IntegrityStatus)) {
+    match STATUS.lock() {
+        Ok(mut status) => f(&mut status),
+        Err(e) => warn!("Could not get lock to the integrity status: {}", e),
+    }
+}
@@ -0,0 +1,229 @@
+use std::{collections::{BTreeMap, HashMap, HashSet, VecDeque}, fs, path::{Path, PathBuf}, sync::Mutex, time::{Duration, Instant}};
+
+use anyhow::{anyhow, bail};
+use futuremod_data::input::{KeyBinding, SetKeyBinding};
+use log::*;
+use windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY;
+
+use crate::remapping::{key_name, parse_key};
+
+/// Key presses that are waiting to be dispatched at the same time. Further presses are dropped.
+const MAX_PENDING: usize = 64;
+
+/// Key presses that weren't dispatched within this time are dropped.
+///
+/// Key presses are only dispatched during missions, so presses in the menu must not fire once a mission starts.
+const MAX_PENDING_AGE: Duration = Duration::from_millis(500);
+
+lazy_static! {
+    static ref BINDINGS: Mutex<KeyBindings> = Mutex::new(KeyBindings { registered: HashMap::new(), overrides: BTreeMap::new(), path: None });
+
+    /// Separate from the bindings, so the keyboard hook never waits for them.
+    static ref KEY_EVENTS: Mutex<KeyEvents> = Mutex::new(KeyEvents { held: HashSet::new(), pending: VecDeque::new() });
+}
+
+/// Key bindings plugins registered and the keys the user chose for them.
+struct KeyBindings {
+    /// Name and default key of the bindings by plugin, in the order they were registered.
+    registered: HashMap<String, Vec<(String, String)>>,
+    /// Keys the user chose by plugin and binding. Persisted, so they survive reloads and restarts.
+    overrides: BTreeMap<String, BTreeMap<String, String>>,
+    path: Option<PathBuf>,
+}
+
+impl KeyBindings {
+    fn key(&self, plugin: &str, name: &str, default_key: &str) -> String {
+        self.overrides
+            .get(plugin)
+            .and_then(|overrides| overrides.get(name))
+            .cloned()
+            .unwrap_or_else(|| default_key.to_string())
+    }
+
+    fn write_to_file(&self) -> Result<(), anyhow::Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let content = serde_json::to_string_pretty(&self.overrides).map_err(|e| anyhow!("could not serialize key bindings: {}", e))?;
+
+        fs::write(path, content).map_err(|e| anyhow!("could not write key bindings: {}", e))
+    }
+}
+
+struct KeyEvents {
+    /// Keys that are held down, to ignore the repeated key downs of held keys.
+    held: HashSet<u16>,
+    pending: VecDeque<(Instant, u16)>,
+}
+
+/// Read the keys the user chose for key bindings. Must be called when the mod is injected.
+pub fn init(path: &Path) {
+    debug!("Reading key bindings from '{}'", path.display());
+
+    let overrides = match fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(overrides) => overrides,
+            Err(e) => {
+                warn!("Could not parse the key bindings, using the default keys: {}", e);
+                BTreeMap::new()
+            },
+        },
+        Err(_) => BTreeMap::new(),
+    };
+
+    match BINDINGS.lock() {
+        Ok(mut bindings) => {
+            bindings.overrides = overrides;
+            bindings.path = Some(path.to_path_buf());
+        },
+        Err(e) => warn!("Could not get lock to the key bindings: {}", e),
+    }
+}
+
+/// Name of the key as used by the input library, e.g., `KeyF5` for `F5`.
+pub fn canonical_key(key: &str) -> Result<String, anyhow::Error> {
+    parse_key(key).ok().and_then(key_name).ok_or(anyhow!("'{}' is not a supported key", key))
+}
+
+/// Register the plugin's key binding with its default key.
+///
+/// Returns the key the binding uses, which is the key the user chose if they changed it.
+pub fn register(plugin: &str, name: &str, default_key: &str) -> Result<String, anyhow::Error> {
+    if canonical_key(name).is_ok() {
+        bail!("'{}' is a key and can't be the name of a binding", name);
+    }
+
+    let default_key = canonical_key(default_key)?;
+    let mut bindings = BINDINGS.lock().map_err(|e| anyhow!("could not get lock to the key bindings: {}", e))?;
+
+    let registered = bindings.registered.entry(plugin.to_string()).or_default();
+    match registered.iter_mut().find(|(registered_name, _)| registered_name == name) {
+        Some(binding) => binding.1 = default_key.clone(),
+        None => registered.push((name.to_string(), default_key.clone())),
+    }
+
+    Ok(bindings.key(plugin, name, &default_key))
+}
+
+/// Get the key the plugin's binding uses, or `None` if the plugin didn't register the binding.
+pub fn get_key(plugin: &str, name: &str) -> Option<String> {
+    let bindings = BINDINGS.lock().ok()?;
+
+    let (_, default_key) = bindings.registered.get(plugin)?.iter().find(|(registered_name, _)| registered_name == name)?;
+
+    Some(bindings.key(plugin, name, default_key))
+}
+
+/// Get the key bindings the plugin registered.
+pub fn get_bindings(plugin: &str) -> Vec<KeyBinding> {
+    let bindings = match BINDINGS.lock() {
+        Ok(bindings) => bindings,
+        Err(_) => return Vec::new(),
+    };
+
+    bindings
+        .registered
+        .get(plugin)
+        .map(|registered| {
+            registered
+                .iter()
+                .map(|(name, default_key)| KeyBinding {
+                    name: name.clone(),
+                    default_key: default_key.clone(),
+                    key: bindings.key(plugin, name, default_key),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Change the key of the plugin's binding and persist it.
+///
+/// The binding must be registered, and the change applies immediately.
+pub fn set_binding(plugin: &str, binding: &SetKeyBinding) -> Result<(), anyhow::Error> {
+    let key = binding.key.as_deref().map(canonical_key).transpose()?;
+    let mut bindings = BINDINGS.lock().map_err(|e| anyhow!("could not get lock to the key bindings: {}", e))?;
+
+    let is_registered = bindings
+        .registered
+        .get(plugin)
+        .is_some_and(|registered| registered.iter().any(|(name, _)| name == &binding.name));
+    if !is_registered {
+        bail!("plugin '{}' has no key binding '{}'", plugin, binding.name);
+    }
+
+    let overrides = bindings.overrides.entry(plugin.to_string()).or_default();
+    match key {
+        Some(key) => {
+            info!("Binding '{}' of plugin {} to {}", binding.name, plugin, key);
+            overrides.insert(binding.name.clone(), key);
+        },
+        None => {
+            info!("Resetting binding '{}' of plugin {}", binding.name, plugin);
+            overrides.remove(&binding.name);
+        },
+    }
+    if overrides.is_empty() {
+        bindings.overrides.remove(plugin);
+    }
+
+    bindings.write_to_file()
+}
+
+/// Forget the plugin's registered bindings, e.g., before it is loaded again and registers them anew.
+///
+/// Keeps the keys the user chose.
+pub fn clear(plugin: &str) {
+    if let Ok(mut bindings) = BINDINGS.lock() {
+        bindings.registered.remove(plugin);
+    }
+}
+
+/// Forget the plugin's bindings including the keys the user chose, e.g., when the plugin is uninstalled.
+pub fn remove(plugin: &str) -> Result<(), anyhow::Error> {
+    let mut bindings = BINDINGS.lock().map_err(|e| anyhow!("could not get lock to the key bindings: {}", e))?;
+
+    bindings.registered.remove(plugin);
+    if bindings.overrides.remove(plugin).is_none() {
+        return Ok(());
+    }
+
+    bindings.write_to_file()
+}
+
+/// Record a key event of the keyboard hook.
+///
+/// Called from the keyboard hook, which must never block. Events are dropped if the lock is taken.
+pub(crate) fn record_key(key: VIRTUAL_KEY, is_key_up: bool) {
+    let mut events = match KEY_EVENTS.try_lock() {
+        Ok(events) => events,
+        Err(_) => return,
+    };
+
+    if is_key_up {
+        events.held.remove(&key.0);
+        return;
+    }
+
+    if !events.held.insert(key.0) || events.pending.len() >= MAX_PENDING {
+        return;
+    }
+
+    events.pending.push_back((Instant::now(), key.0));
+}
+
+/// Take the keys pressed since the last call, in the order they were pressed.
+pub fn take_key_downs() -> Vec<String> {
+    let pending = match KEY_EVENTS.lock() {
+        Ok(mut events) => std::mem::take(&mut events.pending),
+        Err(_) => return Vec::new(),
+    };
+
+    pending
+        .into_iter()
+        .filter(|(pressed_at, _)| pressed_at.elapsed() <= MAX_PENDING_AGE)
+        .filter_map(|(_, key)| key_name(VIRTUAL_KEY(key)))
+        .collect()
+}
@@ -1,9 +1,9 @@
 #![allow(dead_code)]
-use std::{ffi::c_void, fs, path, str::FromStr};
+use std::{ffi::c_void, fs, path::{self, PathBuf}, str::FromStr};
 use anyhow::anyhow;
 use config::Config;
 use log::Log;
-use log4rs::{append::file::FileAppender, config::{Appender, Logger, Root}};
+use log4rs::{append::rolling_file::{policy::compound::{roll::fixed_window::FixedWindowRoller, trigger::size::SizeTrigger, CompoundPolicy}, RollingFileAppender}, config::{Appender, Logger, Root}};
 use util::suspend_all_other_threads;
 use windows::{ Win32::Foundation::*, Win32::System::SystemServices::*, Win32::System::Diagnostics::Debug::*, Win32::System::Threading::*, core::{s, PCSTR}};
 mod futurecop;
@@ -14,6 +14,50 @@ mod plugins;
 mod util;
 mod input;
 mod api;
+mod crash;
+pub mod mock;
+mod recording;
+mod input_recording;
+mod benchmark;
+mod session;
+mod session_recording;
+mod overlay;
+mod metrics;
+mod gc;
+mod ghost;
+mod run_timer;
+mod rich_presence;
+mod multiplayer;
+mod remapping;
+mod controller;
+mod borderless;
+mod screenshot;
+mod diagnostics;
+mod macros;
+mod colorblind;
+mod captions;
+mod sound;
+mod key_bindings;
+mod viewport;
+mod watches;
+mod bookmarks;
+mod net;
+mod structs;
+mod debugger;
+mod clock;
+mod compatibility;
+mod events;
+mod exec;
+mod teardown;
+mod statistics;
+mod injection;
+mod integrity;
+mod game_exit;
+mod game_input;
+mod temporary;
+mod widgets;
+mod console;
+mod memory_scan;
 
 #[macro_use]
 extern crate lazy_static;
@@ -21,6 +65,18 @@ extern crate lazy_static;
 
 static mut IS_ATTACHED: bool = false;
 
+/// Log file in the game's directory.
+const LOG_FILE: &str = "fcop_mod.log";
+
+/// Pattern of rotated log files. `{}` is replaced by the file's index, where 0 is the newest.
+const LOG_ARCHIVE_PATTERN: &str = "fcop_mod.{}.log";
+
+/// Number of rotated log files to keep.
+const LOG_ARCHIVE_COUNT: u32 = 3;
+
+/// Size at which the log file is rotated.
+const LOG_FILE_SIZE_LIMIT: u64 = 10 * 1024 * 1024;
+
 /// Main entry point to the DLL.
 /// 
 /// Simply attaches itself to the game.
@@ -29,27 +85,38 @@ static mut IS_ATTACHED: bool = false;
 unsafe extern "system" fn DllMain(
     dll_module: HINSTANCE,
     call_reason: u32,
-    _: *mut ())
+    reserved: *mut ())
     -> bool
 {
     match call_reason {
-        DLL_PROCESS_ATTACH => attach(),
-        DLL_PROCESS_DETACH => detach(),
+        DLL_PROCESS_ATTACH => attach(dll_module),
+        // Reserved is set if the process exits instead of unloading the DLL
+        DLL_PROCESS_DETACH => detach(!reserved.is_null()),
         _ => (),
     }
 
     true
 }
 
+/// Report the API version the engine was built with.
+///
+/// The GUI calls it before injecting the DLL to refuse engines that don't match it.
+/// Must not use anything else, since the GUI loads the DLL without resolving its imports.
+#[no_mangle]
+pub extern "C" fn futuremod_api_version() -> u32 {
+    futuremod_data::version::API_VERSION
+}
+
 /// Attach the mod
 /// 
 /// Calls the mod's entry main function in a separate thread.
-unsafe fn attach() {
+unsafe fn attach(dll_module: HINSTANCE) {
     if IS_ATTACHED {
         OutputDebugStringA(s!("Already attached"));
     } else {
         OutputDebugStringA(s!("Attaching dll"));
         IS_ATTACHED = true;
+        teardown::set_module(dll_module);
 
         let result = CreateThread(None, 500, Some(main), None, THREAD_CREATE_RUN_IMMEDIATELY, None);
         match result {
@@ -59,27 +126,74 @@ unsafe fn attach() {
     }
 }
 
-unsafe fn detach() {
+/// Detach the mod.
+///
+/// If the DLL is unloaded while the game keeps running, the engine is torn down, unless that already happened, e.g.,
+/// because the engine was ejected.
+/// As this runs while Windows holds the loader lock, threads can't exit in the meantime, so the teardown is best effort.
+/// If the game exits, all other threads were already terminated and nothing is torn down. Only the plugins' states
+/// and the logs are flushed, unless the engine already shut down before the game exited.
+unsafe fn detach(is_process_exiting: bool) {
     OutputDebugStringA(s!("Detached rust dll"));
+
+    if !IS_ATTACHED {
+        return;
+    }
+
+    if is_process_exiting {
+        game_exit::on_process_detach();
+        return;
+    }
+
+    if let Err(e) = teardown::teardown() {
+        log::error!("Could not tear down the engine: {}", e);
+    }
 }
 
+/// Read the config next to the game and apply the settings in the user's folder on top of it.
+///
+/// FutureMod writes the settings into the user's folder if it can't write to the game's folder.
 fn read_config() -> Result<Config, anyhow::Error> {
-    let config_path = path::Path::new("config.json");
+    let config = read_config_file(path::Path::new("config.json"))?;
+    let user_config = match futuremod_data::paths::user_mod_config_path() {
+        Some(path) => read_config_file(&path)?,
+        None => None,
+    };
 
-    if !config_path.exists() {
-        return Ok(Config::default());
+    let config = match (config, user_config) {
+        (None, None) => return Ok(Config::default()),
+        (Some(config), None) => config,
+        (None, Some(user_config)) => user_config,
+        (Some(mut config), Some(serde_json::Value::Object(user_config))) => {
+            if let serde_json::Value::Object(config) = &mut config {
+                config.extend(user_config);
+            }
+
+            config
+        },
+        (Some(_), Some(_)) => return Err(anyhow!("cannot parse config: the config in the user's folder is not an object")),
+    };
+
+    match serde_json::from_value(config) {
+        Ok(c) => Ok(c),
+        Err(e) => Err(anyhow!("cannot parse config: {}", e.to_string())),
     }
+}
 
-    let config_content_opt = fs::read_to_string(config_path);
+/// Read the config file, `None` if it doesn't exist.
+fn read_config_file(config_path: &path::Path) -> Result<Option<serde_json::Value>, anyhow::Error> {
+    if !config_path.exists() {
+        return Ok(None);
+    }
 
-    let config_content = match config_content_opt {
+    let config_content = match fs::read_to_string(config_path) {
         Ok(c) => c,
-        Err(e) => return Err(anyhow!("cannot read config: {}", e.to_string())),
+        Err(e) => return Err(anyhow!("cannot read config '{}': {}", config_path.display(), e.to_string())),
     };
 
     match serde_json::from_str(&config_content) {
-        Ok(c) => Ok(c),
-        Err(e) => Err(anyhow!("cannot parse config: {}", e.to_string())),
+        Ok(c) => Ok(Some(c)),
+        Err(e) => Err(anyhow!("cannot parse config '{}': {}", config_path.display(), e.to_string())),
     }
 }
 
@@ -102,6 +216,8 @@ unsafe extern "system" fn main(_: *mut c_void) -> u32 {
         _ => (),
     }
 
+    crash::install();
+
     if let Err(e) = suspend_all_other_threads() {
         OutputDebugStringA(PCSTR::from_raw(format!("Could not suspend all other thread: {}", e).as_ptr()));
         panic!("Could not suspend all other threads: {}", e);
@@ -118,16 +234,23 @@ unsafe extern "system" fn main(_: *mut c_void) -> u32 {
 fn setup_logging(level: &str) -> Result<(), anyhow::Error> {
     let level = log::LevelFilter::from_str(level).map_err(|_| anyhow!("Invalid log level"))?;
 
-    let file_appender = FileAppender::builder()
-        .build("fcop_mod.log")
+    let roller = FixedWindowRoller::builder()
+        .build(LOG_ARCHIVE_PATTERN, LOG_ARCHIVE_COUNT)
+        .map_err(|e| anyhow!("Could not build log roller: {}", e))?;
+    let policy = CompoundPolicy::new(Box::new(SizeTrigger::new(LOG_FILE_SIZE_LIMIT)), Box::new(roller));
+
+    let file_appender = RollingFileAppender::builder()
+        .build(LOG_FILE, Box::new(policy))
         .map_err(|e| anyhow!("Could not build file appender: {}", e))?;
 
     let config = log4rs::Config::builder()
         .appender(Appender::builder().build("websocket", Box::new(&*server::LOG_PUBLISHER)))
         .appender(Appender::builder().build("debug", Box::new(WindowsLogger)))
         .appender(Appender::builder().build("file", Box::new(file_appender)))
+        .appender(Appender::builder().build("overlay", Box::new(overlay::OverlayLogger)))
+        .appender(Appender::builder().build("session", Box::new(session_recording::SessionLogger)))
         .logger(Logger::builder().build("hyper", log::LevelFilter::Off))
-        .build(Root::builder().appender("debug").appender("websocket").appender("file").build(level))
+        .build(Root::builder().appender("debug").appender("websocket").appender("file").appender("overlay").appender("session").build(level))
         .map_err(|e| anyhow!("Could not build logger: {}", e))?;
 
     log4rs::init_config(config).map_err(|e| anyhow!("Could not initialize logger config: {}", e))?;
@@ -135,6 +258,17 @@ fn setup_logging(level: &str) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Existing log files, including rotated ones, ordered from oldest to newest.
+pub(crate) fn log_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = (0..LOG_ARCHIVE_COUNT)
+        .rev()
+        .map(|index| PathBuf::from(LOG_ARCHIVE_PATTERN.replace("{}", &index.to_string())))
+        .collect();
+    files.push(PathBuf::from(LOG_FILE));
+
+    files.into_iter().filter(|file| file.is_file()).collect()
+}
+
 #[derive(Debug)]
 struct WindowsLogger;
 impl Log for WindowsLogger {
@@ -0,0 +1,121 @@
+use std::{collections::{HashMap, VecDeque}, sync::Mutex};
+
+use log::*;
+use windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY;
+
+use crate::remapping::{is_game_focused, send_key};
+
+/// Step of a plugin's macro.
+#[derive(Debug, Clone)]
+pub enum Step {
+    /// Hold the keys for the number of frames.
+    Press(Vec<VIRTUAL_KEY>, u32),
+    /// Wait for the number of frames.
+    Wait(u32),
+}
+
+impl Step {
+    fn frames(&self) -> u32 {
+        match self {
+            Step::Press(_, frames) | Step::Wait(frames) => (*frames).max(1),
+        }
+    }
+}
+
+/// Queued steps of a plugin's macro.
+#[derive(Debug, Default)]
+struct Queue {
+    steps: VecDeque<Step>,
+
+    /// Frames the current step already ran.
+    elapsed: u32,
+
+    /// Keys the macro currently holds down.
+    held: Vec<VIRTUAL_KEY>,
+}
+
+impl Queue {
+    /// Run the current step for one frame.
+    fn advance(&mut self) {
+        let step = match self.steps.front() {
+            Some(step) => step.clone(),
+            None => return,
+        };
+
+        // Press the keys at the start of the step, or again after the game regained the focus
+        if let Step::Press(keys, _) = &step {
+            for key in keys {
+                if !self.held.contains(key) {
+                    send_key(*key, false);
+                    self.held.push(*key);
+                }
+            }
+        }
+
+        self.elapsed += 1;
+        if self.elapsed >= step.frames() {
+            self.release();
+            self.steps.pop_front();
+            self.elapsed = 0;
+        }
+    }
+
+    fn release(&mut self) {
+        for key in self.held.drain(..) {
+            send_key(key, true);
+        }
+    }
+}
+
+lazy_static! {
+    /// Macros of the plugins by the plugin's name.
+    static ref QUEUES: Mutex<HashMap<String, Queue>> = Mutex::new(HashMap::new());
+}
+
+/// Append the step to the plugin's macro.
+pub fn push(plugin: &str, step: Step) {
+    match QUEUES.lock() {
+        Ok(mut queues) => queues.entry(plugin.to_string()).or_default().steps.push_back(step),
+        Err(e) => warn!("Could not get lock to the macros: {}", e),
+    }
+}
+
+/// Stop the plugin's macro and release the keys it holds.
+pub fn cancel(plugin: &str) {
+    if let Ok(mut queues) = QUEUES.lock() {
+        if let Some(mut queue) = queues.remove(plugin) {
+            queue.release();
+        }
+    }
+}
+
+/// Whether the plugin's macro has steps left.
+pub fn is_running(plugin: &str) -> bool {
+    match QUEUES.lock() {
+        Ok(queues) => queues.get(plugin).is_some_and(|queue| !queue.steps.is_empty()),
+        Err(_) => false,
+    }
+}
+
+/// Run all macros for one frame. Must be called every frame of a mission.
+pub fn on_frame() {
+    let mut queues = match QUEUES.lock() {
+        Ok(queues) => queues,
+        Err(e) => {
+            warn!("Could not get lock to the macros: {}", e);
+            return;
+        },
+    };
+
+    // Synthesized keys go to the focused window, therefore, pause the macros while the game isn't focused
+    let is_focused = is_game_focused();
+
+    for queue in queues.values_mut() {
+        match is_focused {
+            true => queue.advance(),
+            false => queue.release(),
+        }
+    }
+
+    queues.retain(|_, queue| !queue.steps.is_empty());
+}
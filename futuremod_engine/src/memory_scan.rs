@@ -0,0 +1,345 @@
+use std::{ffi::c_void, mem, sync::Mutex};
+
+use anyhow::{anyhow, bail};
+use futuremod_data::memory::{MemoryAddress, MemoryValue, NextScan, ScanComparison, ScanState, StartScan};
+use log::*;
+use windows::Win32::System::{Diagnostics::Debug::ReadProcessMemory, Memory::{VirtualQuery, MEMORY_BASIC_INFORMATION, MEM_COMMIT, PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY, PAGE_GUARD, PAGE_PROTECTION_FLAGS, PAGE_READWRITE, PAGE_WRITECOPY}, Threading::GetCurrentProcess};
+
+/// Lowest address of the game's memory that can be allocated.
+const MIN_ADDRESS: u64 = 0x10000;
+
+/// Highest address of a 32-bit process' user space.
+const MAX_ADDRESS: u64 = 0x7FFE_FFFF;
+
+/// Matches a scan may have. Scans with more matches fail, as their matches would use too much memory.
+const MAX_MATCHES: usize = 2_000_000;
+
+/// Matches whose values are returned with the scanner's state. Further matches are only counted.
+const RETURNED_MATCHES: usize = 100;
+
+/// Floats match the scanned value if they differ by less than this, as the game's floats are rarely round.
+const FLOAT_TOLERANCE: f32 = 0.01;
+
+/// Bytes of a region read at once.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+lazy_static! {
+    static ref SCAN: Mutex<Option<Scan>> = Mutex::new(None);
+}
+
+/// Scan that finds the addresses of values in the game's memory, e.g., the address of the player's ammo.
+///
+/// A scan starts by searching the game's writable memory for a value.
+/// Next scans filter the matches by how their values changed, until only a few addresses are left.
+struct Scan {
+    value_type: ValueType,
+    /// Addresses that matched all scans with their value at the latest scan, as little-endian bytes padded to 4 bytes.
+    matches: Vec<(u32, u32)>,
+    scans: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueType {
+    Int,
+    Uint,
+    Short,
+    Ushort,
+    Byte,
+    Ubyte,
+    Float,
+}
+
+impl ValueType {
+    fn parse(name: &str) -> Result<Self, anyhow::Error> {
+        let value_type = match name {
+            "int" => ValueType::Int,
+            "uint" => ValueType::Uint,
+            "short" => ValueType::Short,
+            "ushort" => ValueType::Ushort,
+            "byte" => ValueType::Byte,
+            "ubyte" => ValueType::Ubyte,
+            "float" => ValueType::Float,
+            _ => bail!("'{}' is not a type that can be scanned", name),
+        };
+
+        Ok(value_type)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ValueType::Int => "int",
+            ValueType::Uint => "uint",
+            ValueType::Short => "short",
+            ValueType::Ushort => "ushort",
+            ValueType::Byte => "byte",
+            ValueType::Ubyte => "ubyte",
+            ValueType::Float => "float",
+        }
+    }
+
+    /// Size of a value of the type in bytes. Values are aligned to their size.
+    fn size(&self) -> usize {
+        match self {
+            ValueType::Int | ValueType::Uint | ValueType::Float => 4,
+            ValueType::Short | ValueType::Ushort => 2,
+            ValueType::Byte | ValueType::Ubyte => 1,
+        }
+    }
+
+    /// Decode the value from its little-endian bytes padded to 4 bytes.
+    fn decode(&self, raw: u32) -> Value {
+        match self {
+            ValueType::Int => Value::Integer(raw as i32 as i64),
+            ValueType::Uint => Value::Integer(raw as i64),
+            ValueType::Short => Value::Integer(raw as u16 as i16 as i64),
+            ValueType::Ushort => Value::Integer(raw as u16 as i64),
+            ValueType::Byte => Value::Integer(raw as u8 as i8 as i64),
+            ValueType::Ubyte => Value::Integer(raw as u8 as i64),
+            ValueType::Float => Value::Float(f32::from_bits(raw)),
+        }
+    }
+
+    /// Parse the value a user entered, e.g., `100`. Errors if the value doesn't fit the type.
+    fn parse_value(&self, value: &str) -> Result<Value, anyhow::Error> {
+        let value = value.trim();
+        let invalid = |e: &dyn std::fmt::Display| anyhow!("'{}' is not a valid {}: {}", value, self.name(), e);
+
+        let value = match self {
+            ValueType::Int => Value::Integer(value.parse::<i32>().map_err(|e| invalid(&e))? as i64),
+            ValueType::Uint => Value::Integer(value.parse::<u32>().map_err(|e| invalid(&e))? as i64),
+            ValueType::Short => Value::Integer(value.parse::<i16>().map_err(|e| invalid(&e))? as i64),
+            ValueType::Ushort => Value::Integer(value.parse::<u16>().map_err(|e| invalid(&e))? as i64),
+            ValueType::Byte => Value::Integer(value.parse::<i8>().map_err(|e| invalid(&e))? as i64),
+            ValueType::Ubyte => Value::Integer(value.parse::<u8>().map_err(|e| invalid(&e))? as i64),
+            ValueType::Float => Value::Float(value.parse::<f32>().map_err(|e| invalid(&e))?),
+        };
+
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Value {
+    Integer(i64),
+    Float(f32),
+}
+
+impl Value {
+    /// Whether the value is the scanned value. Floats only need to be close to it.
+    fn matches(&self, scanned: &Value) -> bool {
+        match (self, scanned) {
+            (Value::Integer(value), Value::Integer(scanned)) => value == scanned,
+            (Value::Float(value), Value::Float(scanned)) => (value - scanned).abs() < FLOAT_TOLERANCE,
+            _ => false,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Value::Integer(value) => value.to_string(),
+            Value::Float(value) => value.to_string(),
+        }
+    }
+}
+
+/// Start a new scan, replacing the current one, and search the game's writable memory for the value.
+///
+/// Memory is read with `ReadProcessMemory`, so memory the game frees during the scan fails the read instead of crashing the game.
+/// As the scanner runs in the game's process, a few matches can be the scanner's own buffers. They fall out with the next scans.
+///
+/// Errors if the value is invalid or matches more than [`MAX_MATCHES`] addresses.
+pub fn start(request: &StartScan) -> Result<ScanState, anyhow::Error> {
+    let value_type = ValueType::parse(&request.value_type)?;
+    let scanned = value_type.parse_value(&request.value)?;
+    let size = value_type.size();
+
+    let mut scan = SCAN.lock().map_err(|e| anyhow!("could not get the scan: {}", e))?;
+
+    // Forget the previous matches first, so they don't use memory during the scan
+    *scan = None;
+
+    info!("Scanning the game's memory for the {} {}", value_type.name(), scanned.describe());
+
+    // Only addresses are collected first, so the matches don't contain the scanned value and don't match themselves
+    let mut addresses: Vec<u32> = Vec::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+
+    for (base, length) in writable_regions() {
+        for offset in (0..length).step_by(CHUNK_SIZE) {
+            let chunk_address = base + offset as u32;
+            let chunk = &mut buffer[..CHUNK_SIZE.min(length - offset)];
+
+            if !read(chunk_address, chunk) {
+                continue;
+            }
+
+            for (index, bytes) in chunk.chunks_exact(size).enumerate() {
+                if value_type.decode(to_raw(bytes)).matches(&scanned) {
+                    addresses.push(chunk_address + (index * size) as u32);
+                }
+            }
+
+            if addresses.len() > MAX_MATCHES {
+                bail!("the value matches more than {} addresses, scan for a less common value", MAX_MATCHES);
+            }
+        }
+    }
+
+    let matches = addresses
+        .into_iter()
+        .filter_map(|address| read_raw(address, size).map(|raw| (address, raw)))
+        .collect::<Vec<(u32, u32)>>();
+
+    info!("Found {} matches", matches.len());
+
+    let new_scan = Scan { value_type, matches, scans: 1 };
+    let state = state(&new_scan);
+    *scan = Some(new_scan);
+
+    Ok(state)
+}
+
+/// Keep the matches of the current scan whose value compares to their value at the previous scan as requested.
+pub fn next(request: &NextScan) -> Result<ScanState, anyhow::Error> {
+    let mut scan = SCAN.lock().map_err(|e| anyhow!("could not get the scan: {}", e))?;
+    let scan = scan.as_mut().ok_or(anyhow!("no scan was started"))?;
+
+    let value_type = scan.value_type;
+    let size = value_type.size();
+
+    let scanned = match (request.comparison, &request.value) {
+        (ScanComparison::Exact, Some(value)) => Some(value_type.parse_value(value)?),
+        (ScanComparison::Exact, None) => bail!("scanning for an exact value requires a value"),
+        _ => None,
+    };
+
+    scan.matches.retain_mut(|(address, previous)| {
+        // Memory that was freed since the previous scan doesn't hold the value anymore
+        let current = match read_raw(*address, size) {
+            Some(current) => current,
+            None => return false,
+        };
+
+        let value = value_type.decode(current);
+        let previous_value = value_type.decode(*previous);
+
+        let keep = match request.comparison {
+            ScanComparison::Exact => scanned.is_some_and(|scanned| value.matches(&scanned)),
+            ScanComparison::Changed => current != *previous,
+            ScanComparison::Unchanged => current == *previous,
+            ScanComparison::Increased => value > previous_value,
+            ScanComparison::Decreased => value < previous_value,
+        };
+
+        *previous = current;
+        keep
+    });
+    scan.scans += 1;
+
+    debug!("{} matches are left after scan {}", scan.matches.len(), scan.scans);
+
+    Ok(state(scan))
+}
+
+/// Get the current scan's state with the current values of its first matches.
+pub fn get_state() -> Result<ScanState, anyhow::Error> {
+    let scan = SCAN.lock().map_err(|e| anyhow!("could not get the scan: {}", e))?;
+
+    Ok(scan.as_ref().map(state).unwrap_or_default())
+}
+
+/// Forget the current scan and its matches.
+pub fn reset() {
+    match SCAN.lock() {
+        Ok(mut scan) => *scan = None,
+        Err(e) => warn!("Could not reset the memory scan: {}", e),
+    }
+}
+
+/// Read the current values at the addresses. Values in memory that isn't readable are `None`.
+///
+/// Errors if the type of an address can't be scanned.
+pub fn read_values(addresses: &[MemoryAddress]) -> Result<Vec<MemoryValue>, anyhow::Error> {
+    addresses
+        .iter()
+        .map(|address| {
+            let value_type = ValueType::parse(&address.value_type)?;
+
+            Ok(MemoryValue {
+                address: address.address,
+                value: read_raw(address.address, value_type.size()).map(|raw| value_type.decode(raw).describe()),
+            })
+        })
+        .collect()
+}
+
+fn state(scan: &Scan) -> ScanState {
+    let size = scan.value_type.size();
+
+    ScanState {
+        value_type: Some(scan.value_type.name().to_string()),
+        scans: scan.scans,
+        match_count: scan.matches.len() as u32,
+        matches: scan
+            .matches
+            .iter()
+            .take(RETURNED_MATCHES)
+            .map(|(address, _)| MemoryValue {
+                address: *address,
+                value: read_raw(*address, size).map(|raw| scan.value_type.decode(raw).describe()),
+            })
+            .collect(),
+    }
+}
+
+/// Regions of the game's memory that are committed and writable, as their base address and size.
+///
+/// Values the game changes are in writable memory, so read-only memory, e.g., the game's code, isn't scanned.
+fn writable_regions() -> Vec<(u32, usize)> {
+    let writable = PAGE_READWRITE | PAGE_WRITECOPY | PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY;
+
+    let mut regions = Vec::new();
+    let mut current = MIN_ADDRESS;
+
+    while current < MAX_ADDRESS {
+        let mut info = MEMORY_BASIC_INFORMATION::default();
+        let queried = unsafe { VirtualQuery(Some(current as *const c_void), &mut info, mem::size_of::<MEMORY_BASIC_INFORMATION>()) };
+
+        if queried == 0 {
+            break;
+        }
+
+        let is_writable = info.Protect & writable != PAGE_PROTECTION_FLAGS(0) && info.Protect & PAGE_GUARD == PAGE_PROTECTION_FLAGS(0);
+        if info.State == MEM_COMMIT && is_writable {
+            regions.push((info.BaseAddress as u32, info.RegionSize));
+        }
+
+        current = info.BaseAddress as u64 + info.RegionSize as u64;
+    }
+
+    regions
+}
+
+/// Read the memory at the address into the buffer. Returns whether the whole buffer was read.
+fn read(address: u32, buffer: &mut [u8]) -> bool {
+    unsafe {
+        ReadProcessMemory(GetCurrentProcess(), address as *const c_void, buffer.as_mut_ptr() as *mut c_void, buffer.len(), None).is_ok()
+    }
+}
+
+/// Read the value of the size at the address as little-endian bytes padded to 4 bytes.
+fn read_raw(address: u32, size: usize) -> Option<u32> {
+    let mut bytes = [0u8; 4];
+
+    match read(address, &mut bytes[..size]) {
+        true => Some(u32::from_le_bytes(bytes)),
+        false => None,
+    }
+}
+
+fn to_raw(bytes: &[u8]) -> u32 {
+    let mut raw = [0u8; 4];
+    raw[..bytes.len()].copy_from_slice(bytes);
+
+    u32::from_le_bytes(raw)
+}
@@ -0,0 +1,114 @@
+use std::{collections::VecDeque, sync::Mutex, time::{Duration, Instant}};
+
+use log::*;
+
+/// Number of frames the metrics are averaged over.
+const WINDOW: usize = 60;
+
+/// Frames that take longer are not measured, e.g., the first frame after the game was paused or in a menu.
+const MAX_FRAME_TIME: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    static ref METRICS: Mutex<Metrics> = Mutex::new(Metrics::new());
+}
+
+/// Frame time, time spent in plugins, and time spent collecting garbage of the last frames of the mission's game loop.
+struct Metrics {
+    last_frame: Option<Instant>,
+    frame_times: VecDeque<Duration>,
+    update_times: VecDeque<Duration>,
+    gc_times: VecDeque<Duration>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Metrics {
+            last_frame: None,
+            frame_times: VecDeque::with_capacity(WINDOW),
+            update_times: VecDeque::with_capacity(WINDOW),
+            gc_times: VecDeque::with_capacity(WINDOW),
+        }
+    }
+}
+
+/// Averaged performance metrics.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameMetrics {
+    pub fps: f64,
+
+    /// Average frame time in milliseconds.
+    pub frame_time: f64,
+
+    /// Average time in milliseconds spent in the `onUpdate` functions of all plugins per frame.
+    pub plugin_time: f64,
+
+    /// Share of the frame time spent in plugins in percent.
+    pub plugin_share: f64,
+
+    /// Average time in milliseconds spent collecting garbage after the frame.
+    pub gc_time: f64,
+
+    /// Longest time in milliseconds spent collecting garbage after one of the frames.
+    pub max_gc_time: f64,
+}
+
+/// Measure the frame.
+///
+/// Must be called once per frame of the mission's game loop after the plugins were updated.
+/// `update_time` is the time it took to update all plugins in this frame,
+/// and `gc_time` the time it took to collect garbage after updating them.
+pub fn on_frame(update_time: Duration, gc_time: Duration) {
+    let mut metrics = match METRICS.lock() {
+        Ok(metrics) => metrics,
+        Err(e) => {
+            warn!("Could not get lock to the metrics: {}", e);
+            return;
+        },
+    };
+
+    let now = Instant::now();
+    let last_frame = match metrics.last_frame.replace(now) {
+        Some(last_frame) => last_frame,
+        None => return,
+    };
+
+    let frame_time = now - last_frame;
+    if frame_time > MAX_FRAME_TIME {
+        return;
+    }
+
+    if metrics.frame_times.len() >= WINDOW {
+        metrics.frame_times.pop_front();
+        metrics.update_times.pop_front();
+        metrics.gc_times.pop_front();
+    }
+    metrics.frame_times.push_back(frame_time);
+    metrics.update_times.push_back(update_time);
+    metrics.gc_times.push_back(gc_time);
+}
+
+/// Get the metrics averaged over the last frames.
+///
+/// Returns `None` if no frame was measured yet.
+pub fn get() -> Option<FrameMetrics> {
+    let metrics = METRICS.lock().ok()?;
+
+    if metrics.frame_times.is_empty() {
+        return None;
+    }
+
+    let frames = metrics.frame_times.len() as f64;
+    let frame_time = metrics.frame_times.iter().sum::<Duration>().as_secs_f64() * 1000.0 / frames;
+    let plugin_time = metrics.update_times.iter().sum::<Duration>().as_secs_f64() * 1000.0 / frames;
+    let gc_time = metrics.gc_times.iter().sum::<Duration>().as_secs_f64() * 1000.0 / frames;
+    let max_gc_time = metrics.gc_times.iter().max().copied().unwrap_or_default().as_secs_f64() * 1000.0;
+
+    Some(FrameMetrics {
+        fps: if frame_time > 0.0 { 1000.0 / frame_time } else { 0.0 },
+        frame_time,
+        plugin_time,
+        plugin_share: if frame_time > 0.0 { plugin_time / frame_time * 100.0 } else { 0.0 },
+        gc_time,
+        max_gc_time,
+    })
+}
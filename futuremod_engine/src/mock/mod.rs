@@ -0,0 +1,387 @@
+use std::{alloc::{alloc_zeroed, Layout}, collections::HashSet, ffi::{c_void, CStr}, path::PathBuf, str::FromStr, sync::atomic::{AtomicU32, Ordering}, thread, time::Duration};
+
+use anyhow::anyhow;
+use futuremod_hook::lua::call_native_raw;
+use log::*;
+use log4rs::{append::console::ConsoleAppender, config::{Appender, Logger, Root}};
+use windows::Win32::System::Memory::*;
+
+use crate::{config::Config, futurecop::{global::GetterSetter, *}, injection, input::KeyState, plugins::plugin_manager::GlobalPluginManager, recording::{self, RecordedInvocation}, server, widgets};
+
+mod scenario;
+
+pub use scenario::*;
+
+/// Start of the memory region simulated by the mock environment.
+const GAME_MEMORY_START: u32 = 0x00400000;
+
+/// Size of the simulated memory region.
+///
+/// Covers the game's code and all known globals.
+const GAME_MEMORY_SIZE: usize = 0x120000;
+
+/// Options of the mock environment.
+#[derive(Debug, Clone)]
+pub struct MockOptions {
+    /// The scenario to simulate.
+    pub scenario: Scenario,
+
+    /// Directory from which plugins are loaded.
+    pub plugins_directory: PathBuf,
+
+    /// Whether to start the server, so that the GUI can connect to the mock environment.
+    pub server: bool,
+
+    /// Log level.
+    pub log_level: String,
+}
+
+/// Summary of a simulation.
+#[derive(Debug, Default)]
+pub struct MockSummary {
+    /// Amount of simulated frames.
+    pub frames: u32,
+
+    /// Errors thrown by plugins as tuples of frame, plugin name and error.
+    pub errors: Vec<(u32, String, String)>,
+
+    /// Replayed hook invocations that returned a different value than recorded.
+    pub replay_mismatches: Vec<ReplayMismatch>,
+}
+
+/// Replayed hook invocation whose return value differs from the recording.
+#[derive(Debug)]
+pub struct ReplayMismatch {
+    pub frame: u32,
+    pub address: u32,
+    pub recorded: u32,
+    pub replayed: u32,
+}
+
+/// Run the mock environment, which runs the plugin system without the game.
+///
+/// Sets up the simulated game, initializes the global plugin manager and simulates
+/// all frames of the scenario.
+/// The game's memory region is allocated and filled with a simulated game state (players, entities and globals),
+/// and the game functions used by the engine are replaced by stubs.
+/// Returns a summary of the simulation, which contains all errors thrown by plugins.
+///
+/// Should only be called once per process.
+/// **The game's memory region must be free in the process.**
+/// Processes that run the mock environment must not be loaded at the game's default image base `0x00400000`.
+pub fn run(options: MockOptions) -> Result<MockSummary, anyhow::Error> {
+    setup_logging(&options.log_level)?;
+
+    info!("Setting up mock game environment");
+    unsafe {
+        allocate_game_memory()?;
+        install_function_stubs();
+        setup_game_state(&options.scenario);
+    }
+
+    // Stubs for replayed functions must be installed before plugins hook them
+    let recording = match &options.scenario.replay {
+        Some(path) => {
+            let recording = recording::load(path)?;
+            unsafe { install_replay_stubs(&recording)? };
+
+            info!("Loaded {} hook invocations to replay from '{}'", recording.len(), path.display());
+            recording
+        },
+        None => Vec::new(),
+    };
+    let first_recorded_frame = recording.first().map(|invocation| invocation.frame).unwrap_or(0);
+    let mut next_invocation = 0;
+
+    GlobalPluginManager::initialize(options.plugins_directory.clone())?;
+
+    if !options.scenario.plugins.is_empty() {
+        enable_plugins(&options.scenario.plugins)?;
+    }
+
+    if options.server {
+        server::start_server(Config::default());
+    }
+
+    info!("Simulating {} frames", options.scenario.frames);
+    let mut summary = MockSummary::default();
+    let key_state = KeyState::new();
+
+    for frame in 0..options.scenario.frames {
+        let mut frame_number = FRAME_NUMBER;
+        frame_number.set(frame);
+
+        key_state.set_state(options.scenario.keys_at(frame))?;
+        injection::on_frame(&key_state);
+        widgets::on_frame();
+
+        let errors = update_plugins();
+        for (plugin, error) in errors {
+            warn!("Frame {}: plugin '{}' threw error: {}", frame, plugin, error);
+            summary.errors.push((frame, plugin, error));
+        }
+
+        // Hooks are called by the game after the plugins were updated
+        while next_invocation < recording.len() && recording[next_invocation].frame.saturating_sub(first_recorded_frame) <= frame {
+            let invocation = &recording[next_invocation];
+            let replayed = unsafe { replay_invocation(invocation) };
+
+            if replayed != invocation.return_value {
+                warn!(
+                    "Frame {}: replayed hook of {:#010x} returned {:#x}, but recorded {:#x}",
+                    frame, invocation.address, replayed, invocation.return_value,
+                );
+                summary.replay_mismatches.push(ReplayMismatch {
+                    frame,
+                    address: invocation.address,
+                    recorded: invocation.return_value,
+                    replayed,
+                });
+            }
+
+            next_invocation += 1;
+        }
+
+        summary.frames += 1;
+
+        if options.scenario.frame_time > 0 {
+            thread::sleep(Duration::from_millis(options.scenario.frame_time));
+        }
+    }
+
+    info!("Simulated {} frames, plugins threw {} errors", summary.frames, summary.errors.len());
+
+    Ok(summary)
+}
+
+/// Setup logging for the mock environment.
+///
+/// Logs to the console instead of the debugger and still publishes logs to the websocket.
+fn setup_logging(level: &str) -> Result<(), anyhow::Error> {
+    let level = log::LevelFilter::from_str(level).map_err(|_| anyhow!("Invalid log level"))?;
+
+    let config = log4rs::Config::builder()
+        .appender(Appender::builder().build("websocket", Box::new(&*server::LOG_PUBLISHER)))
+        .appender(Appender::builder().build("console", Box::new(ConsoleAppender::builder().build())))
+        .logger(Logger::builder().build("hyper", log::LevelFilter::Off))
+        .build(Root::builder().appender("console").appender("websocket").build(level))
+        .map_err(|e| anyhow!("Could not build logger: {}", e))?;
+
+    log4rs::init_config(config).map_err(|e| anyhow!("Could not initialize logger config: {}", e))?;
+
+    Ok(())
+}
+
+/// Enable the given plugins without persisting the change.
+fn enable_plugins(names: &Vec<String>) -> Result<(), anyhow::Error> {
+    GlobalPluginManager::with_plugin_manager_mut(|plugin_manager| {
+        for name in names {
+            let plugin = plugin_manager.plugins.get_mut(name).ok_or(anyhow!("plugin '{}' doesn't exist", name))?;
+
+            plugin.enable().map_err(|e| anyhow!("could not enable plugin '{}': {:?}", name, e))?;
+            info!("Enabled plugin '{}'", name);
+        }
+
+        Ok(())
+    })
+}
+
+/// Call the `onUpdate` function of all enabled plugins.
+///
+/// Returns all errors thrown by plugins.
+fn update_plugins() -> Vec<(String, String)> {
+    let result = GlobalPluginManager::with_plugin_manager(|plugin_manager| {
+        let errors = plugin_manager.plugins
+            .iter()
+            .filter(|(_, plugin)| plugin.is_enabled())
+            .filter_map(|(name, plugin)| match plugin.on_update() {
+                Ok(_) => None,
+                Err(e) => Some((name.clone(), format!("{:?}", e))),
+            })
+            .collect();
+
+        Ok(errors)
+    });
+
+    match result {
+        Ok(errors) => errors,
+        Err(e) => vec![(String::from("engine"), e.to_string())],
+    }
+}
+
+/// Allocate the game's memory region.
+unsafe fn allocate_game_memory() -> Result<(), anyhow::Error> {
+    let memory = VirtualAlloc(
+        Some(GAME_MEMORY_START as *const c_void),
+        GAME_MEMORY_SIZE,
+        MEM_COMMIT | MEM_RESERVE,
+        PAGE_EXECUTE_READWRITE,
+    );
+
+    if memory.is_null() || memory as u32 != GAME_MEMORY_START {
+        anyhow::bail!("could not allocate the game's memory region at {:#010x}, is the region already in use?", GAME_MEMORY_START);
+    }
+
+    debug!("Allocated game memory at {:#010x}", memory as u32);
+
+    Ok(())
+}
+
+/// Setup the simulated game state according to the scenario.
+unsafe fn setup_game_state(scenario: &Scenario) {
+    let mut is_playing = IS_PLAYING;
+    is_playing.set(true);
+
+    let mut in_game_loop = IN_GAME_LOOP;
+    in_game_loop.set(true);
+
+    let mut is_two_player = IS_TWO_PLAYER;
+    is_two_player.set(scenario.players.len() == 2);
+
+    let mut game_mode = GAME_MODE;
+    game_mode.set(match scenario.game_mode {
+        MockGameMode::CrimeWar => GameMode::CrimeWar,
+        MockGameMode::PrecinctAssault => GameMode::PrecinctAssault,
+    });
+
+    for (index, mock_player) in scenario.players.iter().enumerate() {
+        let player_entity = create_player(mock_player);
+        let player_array_item = (PLAYER_ARRAY_ADDR + index as u32 * 8) as *mut u32;
+
+        *player_array_item = player_entity as u32;
+        debug!("Created player {} at {:#010x}", index, player_entity as u32);
+    }
+
+    // Build the entity list in reverse to keep the scenario's order
+    let mut next_entity: u32 = 0;
+    for mock_entity in scenario.entities.iter().rev() {
+        let entity = alloc_zeroed(Layout::new::<Entity>()) as *mut Entity;
+
+        (*entity).next_entity = next_entity;
+        (*entity).id = mock_entity.id;
+        (*entity).behavior_type = mock_entity.behavior_type;
+        (*entity).position = Position {
+            x: mock_entity.position[0],
+            y: mock_entity.position[1],
+            z: mock_entity.position[2],
+        };
+
+        next_entity = entity as u32;
+    }
+
+    let mut entity_list_first = ENTITY_LIST_FIRST;
+    entity_list_first.set(next_entity);
+
+    debug!("Created {} players and {} entities", scenario.players.len(), scenario.entities.len());
+}
+
+unsafe fn create_player(mock_player: &MockPlayer) -> *mut PlayerEntity {
+    let player = alloc_zeroed(Layout::new::<Player>()) as *mut Player;
+    (*player).gun_weapon_ammo = mock_player.gun_weapon_ammo;
+    (*player).heavy_weapon_ammo = mock_player.heavy_weapon_ammo;
+    (*player).special_weapon_ammo = mock_player.special_weapon_ammo;
+
+    let player_entity = alloc_zeroed(Layout::new::<PlayerEntity>()) as *mut PlayerEntity;
+    (*player_entity).main_method = mock_entity_method;
+    (*player_entity).health.health = mock_player.health;
+    (*player_entity).health.max_health = mock_player.max_health;
+    (*player_entity).position_x = mock_player.position[0];
+    (*player_entity).position_y = mock_player.position[1];
+    (*player_entity).position_z = mock_player.position[2];
+    (*player_entity).player = player;
+
+    player_entity
+}
+
+/// Replace the game functions called by the engine with stubs.
+///
+/// Writes a relative jump to the stub at the function's address.
+unsafe fn install_function_stubs() {
+    write_jump(RENDER_TEXT_FUNCTION_ADDRESS, mock_render_text as RenderTextFunction as u32);
+    write_jump(RENDER_CHARACTER_FUNCTION_ADDRESS, mock_render_character as RenderCharacterFunction as u32);
+    write_jump(RENDRE_RECTANGLE_FUNCTION_ADDRESS, mock_render_rectangle as RenderRectangleFunction as u32);
+    write_jump(UPDATE_FUNCTION_BEHAVIOR_0XA0_ADDRESS, mock_update_function as UpdateFunction as u32);
+    write_jump(RENDER_OBJECT_ADDRESS, mock_render_object_raw as RenderObjectRaw as u32);
+    write_jump(FUN_004280A0_ADDRESS, mock_render_object as RenderObject as u32);
+}
+
+/// Return value of the function stub of the replayed invocation.
+static REPLAY_RETURN_VALUE: AtomicU32 = AtomicU32::new(0);
+
+/// Replace the functions in the recording with stubs that return the recorded return value.
+///
+/// The stub is hookable and loads the return value from [`REPLAY_RETURN_VALUE`].
+unsafe fn install_replay_stubs(recording: &Vec<RecordedInvocation>) -> Result<(), anyhow::Error> {
+    let addresses: HashSet<u32> = recording.iter().map(|invocation| invocation.address).collect();
+    let return_value_address = &REPLAY_RETURN_VALUE as *const AtomicU32 as u32;
+
+    for address in addresses {
+        if address < GAME_MEMORY_START || address + 6 > GAME_MEMORY_START + GAME_MEMORY_SIZE as u32 {
+            anyhow::bail!("recorded hook of {:#010x} is outside of the game's memory", address);
+        }
+
+        // mov eax, [return_value_address]
+        // ret
+        let stub = address as *mut u8;
+        *stub = 0xa1;
+        (stub.add(1) as *mut u32).write_unaligned(return_value_address);
+        *stub.add(5) = 0xc3;
+    }
+
+    Ok(())
+}
+
+/// Replay the invocation by calling the hooked function with the recorded arguments.
+///
+/// Returns the value returned by the hook.
+unsafe fn replay_invocation(invocation: &RecordedInvocation) -> u32 {
+    REPLAY_RETURN_VALUE.store(invocation.return_value, Ordering::SeqCst);
+
+    // Arguments are pushed in reverse order
+    let arguments: Vec<u32> = invocation.arguments.iter().rev().cloned().collect();
+
+    call_native_raw(invocation.address, &arguments)
+}
+
+unsafe fn write_jump(address: u32, target: u32) {
+    let instruction = address as *mut u8;
+    let offset = target.wrapping_sub(address + 5);
+
+    *instruction = 0xe9;
+    (instruction.add(1) as *mut u32).write_unaligned(offset);
+}
+
+unsafe fn mock_render_text(text: *const u8, pos_x: u32, pos_y: u32, palette: u32) {
+    let text = CStr::from_ptr(text as *const i8).to_string_lossy();
+    debug!(target: "mock::render", "Render text '{}' at ({}, {}) with palette {}", text, pos_x, pos_y, palette);
+}
+
+unsafe fn mock_render_character(character: u32, pos_x: u32, pos_y: u32, palette: u32) -> u32 {
+    debug!(target: "mock::render", "Render character {:#x} at ({}, {}) with palette {}", character, pos_x, pos_y, palette);
+    0
+}
+
+unsafe fn mock_render_rectangle(color: u32, pos_x: u16, pos_y: u16, width: u16, height: u16, semi_transparent: u8) {
+    debug!(
+        target: "mock::render",
+        "Render rectangle at ({}, {}) with size {}x{}, color {:#x} and semi-transparency {}",
+        pos_x, pos_y, width, height, color, semi_transparent,
+    );
+}
+
+unsafe fn mock_update_function(arg1: u32, arg2: u32, arg3: u32) -> u32 {
+    debug!(target: "mock::update", "Update function called with ({:#x}, {:#x}, {:#x})", arg1, arg2, arg3);
+    0
+}
+
+unsafe fn mock_render_object_raw(arg1: u32, arg2: u32, arg3: u32) {
+    debug!(target: "mock::render", "Render object called with ({:#x}, {:#x}, {:#x})", arg1, arg2, arg3);
+}
+
+unsafe fn mock_render_object(model_data: u32, _value_ref: *mut u32, arg3: u32) {
+    debug!(target: "mock::render", "Render object {:#x} with {:#x}", model_data, arg3);
+}
+
+unsafe fn mock_entity_method(_: i32, _: u32, _: u32, _: u32) -> u32 {
+    0
+}
@@ -0,0 +1,172 @@
+use std::{collections::HashSet, fs, path::{Path, PathBuf}};
+
+use anyhow::anyhow;
+use device_query::Keycode;
+use serde::Deserialize;
+
+use crate::{input_recording, plugins::library::input::keycode_from_string};
+
+/// Scripted scenario that the mock environment simulates.
+///
+/// Scenarios are loaded from a TOML file.
+/// Every field is optional, an empty scenario simulates a single player mission
+/// with one player and without any entities.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Scenario {
+    /// Amount of frames to simulate.
+    pub frames: u32,
+
+    /// Time between two frames in milliseconds.
+    ///
+    /// Plugins that depend on the system time behave as in the game.
+    /// Set this to `0` to simulate frames as fast as possible.
+    pub frame_time: u64,
+
+    /// Game mode of the simulated mission.
+    pub game_mode: MockGameMode,
+
+    /// Plugins that should be enabled for the simulation.
+    ///
+    /// If empty, plugins are enabled according to the plugin directory's persistence file.
+    pub plugins: Vec<String>,
+
+    /// Simulated players. At most two players are supported.
+    pub players: Vec<MockPlayer>,
+
+    /// Simulated entities in the entity list.
+    pub entities: Vec<MockEntity>,
+
+    /// Scripted input.
+    pub inputs: Vec<MockInput>,
+
+    /// Recording of hook invocations to replay.
+    ///
+    /// The first recorded frame is replayed in the first simulated frame.
+    pub replay: Option<PathBuf>,
+
+    /// Recording of pressed keys to replay instead of the scripted input.
+    ///
+    /// Recorded with the mod's `inputRecording` option.
+    pub input_replay: Option<PathBuf>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario {
+            frames: 600,
+            frame_time: 16,
+            game_mode: MockGameMode::PrecinctAssault,
+            plugins: Vec::new(),
+            players: vec![MockPlayer::default()],
+            entities: Vec::new(),
+            inputs: Vec::new(),
+            replay: None,
+            input_replay: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum MockGameMode {
+    CrimeWar,
+    PrecinctAssault,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MockPlayer {
+    pub health: i16,
+    pub max_health: i16,
+    pub position: [u32; 3],
+    pub gun_weapon_ammo: u16,
+    pub heavy_weapon_ammo: u16,
+    pub special_weapon_ammo: u16,
+}
+
+impl Default for MockPlayer {
+    fn default() -> Self {
+        MockPlayer {
+            health: 100,
+            max_health: 100,
+            position: [0, 0, 0],
+            gun_weapon_ammo: 100,
+            heavy_weapon_ammo: 10,
+            special_weapon_ammo: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MockEntity {
+    pub id: u32,
+    pub behavior_type: u16,
+    pub position: [u32; 3],
+}
+
+/// Keys that are pressed starting from a specific frame.
+///
+/// The keys stay pressed until the next input entry.
+/// Keys use the same names as the input library, e.g., `KeySpace`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockInput {
+    pub frame: u32,
+    #[serde(default)]
+    pub keys: Vec<String>,
+}
+
+impl Scenario {
+    /// Load the scenario from a TOML file.
+    pub fn from_file(path: &Path) -> Result<Scenario, anyhow::Error> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("could not read scenario '{}': {}", path.display(), e))?;
+
+        let mut scenario: Scenario = toml::from_str(&content)
+            .map_err(|e| anyhow!("could not parse scenario '{}': {}", path.display(), e))?;
+
+        // Recordings are relative to the scenario file
+        if let (Some(replay), Some(directory)) = (&scenario.replay, path.parent()) {
+            scenario.replay = Some(directory.join(replay));
+        }
+        if let (Some(input_replay), Some(directory)) = (&scenario.input_replay, path.parent()) {
+            scenario.input_replay = Some(directory.join(input_replay));
+        }
+
+        if let Some(input_replay) = &scenario.input_replay {
+            scenario.inputs = input_recording::load(input_replay)?
+                .into_iter()
+                .map(|input| MockInput { frame: input.frame, keys: input.keys })
+                .collect();
+        }
+
+        if scenario.players.len() > 2 {
+            anyhow::bail!("scenario contains {} players, but at most two are supported", scenario.players.len());
+        }
+
+        // Validate the key names before starting the simulation
+        for input in scenario.inputs.iter() {
+            for key in input.keys.iter() {
+                keycode_from_string(key.clone()).map_err(|_| anyhow!("scripted input at frame {} contains invalid key '{}'", input.frame, key))?;
+            }
+        }
+
+        Ok(scenario)
+    }
+
+    /// Get the keys that are pressed in the given frame.
+    pub fn keys_at(&self, frame: u32) -> HashSet<Keycode> {
+        let input = self.inputs
+            .iter()
+            .filter(|input| input.frame <= frame)
+            .max_by_key(|input| input.frame);
+
+        match input {
+            Some(input) => input.keys
+                .iter()
+                .filter_map(|key| keycode_from_string(key.clone()).ok())
+                .collect(),
+            None => HashSet::new(),
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use std::{collections::HashMap, net::{SocketAddr, ToSocketAddrs, UdpSocket}, sync::Mutex, time::{Duration, Instant}};
+
+use anyhow::anyhow;
+use futuremod_data::multiplayer::{ConnectPeer, MultiplayerStatus};
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::futurecop::PlayerEntity;
+
+/// Version of the packet format. Packets of other versions are ignored.
+const PROTOCOL_VERSION: u32 = 1;
+
+/// Maximum size of a packet. Larger packets are not sent to avoid fragmentation.
+const MAX_PACKET_SIZE: usize = 8192;
+
+/// The peer counts as disconnected if no packet was received for this long.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+lazy_static! {
+    static ref MULTIPLAYER: Mutex<Option<Connection>> = Mutex::new(None);
+}
+
+/// State of a player sent to the peer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerSnapshot {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+    pub rotation: i32,
+    pub health: i16,
+}
+
+impl PlayerSnapshot {
+    pub fn of(player: &PlayerEntity) -> PlayerSnapshot {
+        PlayerSnapshot {
+            x: player.position_x,
+            y: player.position_y,
+            z: player.position_z,
+            rotation: player.rotation,
+            health: player.health.health,
+        }
+    }
+}
+
+/// State of the game sent to the peer every frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Packet {
+    version: u32,
+
+    /// Increases with every packet. Used to drop packets that arrive out of order.
+    sequence: u64,
+
+    /// Local players, player one first. Empty while no mission is running.
+    players: Vec<PlayerSnapshot>,
+
+    /// Data set by plugins, by the plugin's name.
+    data: HashMap<String, serde_json::Value>,
+}
+
+/// Connection to the FutureMod instance of another player.
+///
+/// UDP is connectionless. Both players connect to each other and the connection counts as established
+/// as long as packets of the peer arrive.
+struct Connection {
+    socket: UdpSocket,
+    port: u16,
+    peer: SocketAddr,
+    sequence: u64,
+    sent: u64,
+    received: u64,
+    last_received: Option<Instant>,
+    peer_state: Option<Packet>,
+    data: HashMap<String, serde_json::Value>,
+}
+
+impl Connection {
+    fn send(&mut self, players: Vec<PlayerSnapshot>) {
+        self.sequence += 1;
+
+        let packet = Packet { version: PROTOCOL_VERSION, sequence: self.sequence, players, data: self.data.clone() };
+        let bytes = match serde_json::to_vec(&packet) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Could not serialize the multiplayer state: {}", e);
+                return;
+            },
+        };
+
+        if bytes.len() > MAX_PACKET_SIZE {
+            warn!("Multiplayer state is too large to send ({} bytes), reduce the data set by plugins", bytes.len());
+            return;
+        }
+
+        match self.socket.send_to(&bytes, self.peer) {
+            Ok(_) => self.sent += 1,
+            Err(e) => debug!("Could not send the multiplayer state: {}", e),
+        }
+    }
+
+    /// Read all packets that arrived since the last frame and keep the latest state of the peer.
+    fn receive(&mut self) {
+        let mut buffer = [0u8; MAX_PACKET_SIZE];
+
+        loop {
+            let (size, address) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                // The socket is non-blocking and returns an error once no packets are left
+                Err(_) => return,
+            };
+
+            if address != self.peer {
+                debug!("Ignoring multiplayer packet from unknown address '{}'", address);
+                continue;
+            }
+
+            let packet: Packet = match serde_json::from_slice(&buffer[..size]) {
+                Ok(packet) => packet,
+                Err(e) => {
+                    debug!("Ignoring invalid multiplayer packet: {}", e);
+                    continue;
+                },
+            };
+
+            if packet.version != PROTOCOL_VERSION {
+                debug!("Ignoring multiplayer packet of version {}", packet.version);
+                continue;
+            }
+
+            self.received += 1;
+            self.last_received = Some(Instant::now());
+
+            let is_newer = match &self.peer_state {
+                Some(state) => packet.sequence > state.sequence,
+                None => true,
+            };
+
+            if is_newer {
+                self.peer_state = Some(packet);
+            }
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        self.last_received.is_some_and(|last_received| last_received.elapsed() < CONNECTION_TIMEOUT)
+    }
+}
+
+/// Start syncing the game state with the peer.
+///
+/// Replaces the connection to the previous peer.
+pub fn connect(request: &ConnectPeer) -> Result<(), anyhow::Error> {
+    let peer = request.peer
+        .to_socket_addrs()
+        .map_err(|e| anyhow!("invalid peer address '{}': {}", request.peer, e))?
+        .next()
+        .ok_or(anyhow!("peer address '{}' could not be resolved", request.peer))?;
+
+    let mut multiplayer = MULTIPLAYER.lock().map_err(|e| anyhow!("could not get lock to the multiplayer connection: {}", e))?;
+
+    // Close the previous socket first, the new one might use the same port
+    *multiplayer = None;
+
+    let socket = UdpSocket::bind(("0.0.0.0", request.port)).map_err(|e| anyhow!("could not listen on port {}: {}", request.port, e))?;
+    socket.set_nonblocking(true)?;
+
+    info!("Syncing the game state with '{}' on port {}", peer, request.port);
+
+    *multiplayer = Some(Connection {
+        socket,
+        port: request.port,
+        peer,
+        sequence: 0,
+        sent: 0,
+        received: 0,
+        last_received: None,
+        peer_state: None,
+        data: HashMap::new(),
+    });
+
+    Ok(())
+}
+
+/// Stop syncing the game state.
+pub fn disconnect() -> Result<(), anyhow::Error> {
+    let mut multiplayer = MULTIPLAYER.lock().map_err(|e| anyhow!("could not get lock to the multiplayer connection: {}", e))?;
+
+    if multiplayer.take().is_some() {
+        info!("Stopped syncing the game state");
+    }
+
+    Ok(())
+}
+
+/// Send the local players to the peer and receive the peer's state.
+///
+/// Must be called once per frame of the mission's game loop before plugins are updated.
+pub fn on_frame(players: Vec<PlayerSnapshot>) {
+    with_connection(|connection| {
+        connection.receive();
+        connection.send(players);
+    });
+}
+
+pub fn get_status() -> Result<MultiplayerStatus, anyhow::Error> {
+    let multiplayer = MULTIPLAYER.lock().map_err(|e| anyhow!("could not get lock to the multiplayer connection: {}", e))?;
+
+    Ok(match multiplayer.as_ref() {
+        Some(connection) => MultiplayerStatus {
+            port: Some(connection.port),
+            peer: Some(connection.peer.to_string()),
+            connected: connection.is_connected(),
+            sent: connection.sent,
+            received: connection.received,
+        },
+        None => MultiplayerStatus::default(),
+    })
+}
+
+/// Whether the peer's state was received recently.
+pub fn is_connected() -> bool {
+    match MULTIPLAYER.lock() {
+        Ok(multiplayer) => multiplayer.as_ref().is_some_and(Connection::is_connected),
+        Err(_) => false,
+    }
+}
+
+/// Latest players of the peer, or `None` if the peer isn't connected.
+pub fn get_peer_players() -> Option<Vec<PlayerSnapshot>> {
+    let multiplayer = MULTIPLAYER.lock().ok()?;
+    let connection = multiplayer.as_ref().filter(|connection| connection.is_connected())?;
+
+    connection.peer_state.as_ref().map(|state| state.players.clone())
+}
+
+/// Set the data the plugin sends to the peer. `None` stops sending data.
+pub fn set_data(plugin: &str, data: Option<serde_json::Value>) {
+    with_connection(|connection| {
+        match data {
+            Some(data) => connection.data.insert(plugin.to_string(), data),
+            None => connection.data.remove(plugin),
+        };
+    });
+}
+
+/// Latest data the peer's instance of the plugin set, or `None` if the peer isn't connected or didn't set data.
+pub fn get_peer_data(plugin: &str) -> Option<serde_json::Value> {
+    let multiplayer = MULTIPLAYER.lock().ok()?;
+    let connection = multiplayer.as_ref().filter(|connection| connection.is_connected())?;
+
+    connection.peer_state.as_ref().and_then(|state| state.data.get(plugin).cloned())
+}
+
+fn with_connection(f: impl FnOnce(&mut Connection)) {
+    match MULTIPLAYER.lock() {
+        Ok(mut multiplayer) => {
+            if let Some(connection) = multiplayer.as_mut() {
+                f(connection);
+            }
+        },
+        Err(e) => warn!("Could not get lock to the multiplayer connection: {}", e),
+    }
+}
@@ -0,0 +1,224 @@
+use std::{collections::{BTreeMap, HashMap}, fs, path::{Path, PathBuf}, sync::{atomic::{AtomicU32, Ordering}, Mutex}, thread, time::Duration};
+
+use anyhow::{anyhow, bail};
+use futuremod_data::plugin::PluginInfo;
+use log::*;
+use reqwest::{blocking::Client, header::CONTENT_TYPE, redirect::Policy, Url};
+
+use crate::teardown;
+
+/// Requests that didn't complete in this time fail.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of requests a plugin can wait for at the same time.
+const MAX_PENDING_REQUESTS: usize = 8;
+
+/// Responses with larger bodies fail.
+const MAX_RESPONSE_SIZE: usize = 1024 * 1024;
+
+lazy_static! {
+    static ref APPROVALS: Mutex<Approvals> = Mutex::new(Approvals { hosts: BTreeMap::new(), path: None });
+    static ref PENDING: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+    static ref RESPONSES: Mutex<Vec<Response>> = Mutex::new(Vec::new());
+}
+
+static NEXT_REQUEST_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Hosts the user approved, by the plugin's name.
+struct Approvals {
+    /// Persisted, so users only approve a plugin once.
+    hosts: BTreeMap<String, Vec<String>>,
+    path: Option<PathBuf>,
+}
+
+impl Approvals {
+    fn write_to_file(&self) -> Result<(), anyhow::Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let content = serde_json::to_string_pretty(&self.hosts).map_err(|e| anyhow!("could not serialize the network approvals: {}", e))?;
+
+        fs::write(path, content).map_err(|e| anyhow!("could not write the network approvals: {}", e))
+    }
+}
+
+/// Body of a request.
+pub struct RequestBody {
+    pub content: Vec<u8>,
+    pub content_type: &'static str,
+}
+
+/// Response to a plugin's request.
+#[derive(Debug)]
+pub struct Response {
+    pub plugin: String,
+    pub id: u32,
+    pub result: Result<HttpResponse, String>,
+}
+
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Read the hosts the user approved. Must be called when the mod is injected.
+pub fn init(path: &Path) {
+    debug!("Reading network approvals from '{}'", path.display());
+
+    let hosts = match fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(hosts) => hosts,
+            Err(e) => {
+                warn!("Could not parse the network approvals, no plugin may send requests: {}", e);
+                BTreeMap::new()
+            },
+        },
+        Err(_) => BTreeMap::new(),
+    };
+
+    match APPROVALS.lock() {
+        Ok(mut approvals) => {
+            approvals.hosts = hosts;
+            approvals.path = Some(path.to_path_buf());
+        },
+        Err(e) => warn!("Could not get lock to the network approvals: {}", e),
+    }
+}
+
+/// Whether the user approved all hosts the plugin declares.
+pub fn is_approved(info: &PluginInfo) -> bool {
+    let approvals = match APPROVALS.lock() {
+        Ok(approvals) => approvals,
+        Err(_) => return false,
+    };
+
+    approvals.hosts
+        .get(&info.name)
+        .is_some_and(|approved| info.hosts.iter().all(|host| approved.contains(host)))
+}
+
+/// Approve that the plugin sends requests to the hosts it declares and persist the approval.
+pub fn approve(info: &PluginInfo) -> Result<(), anyhow::Error> {
+    info!("Approving requests of plugin '{}' to {}", info.name, info.hosts.join(", "));
+
+    let mut approvals = APPROVALS.lock().map_err(|e| anyhow!("could not get lock to the network approvals: {}", e))?;
+    approvals.hosts.insert(info.name.clone(), info.hosts.clone());
+
+    approvals.write_to_file()
+}
+
+/// Revoke the approval of the plugin's hosts, e.g., when the plugin is uninstalled.
+pub fn revoke(plugin: &str) -> Result<(), anyhow::Error> {
+    let mut approvals = APPROVALS.lock().map_err(|e| anyhow!("could not get lock to the network approvals: {}", e))?;
+
+    if approvals.hosts.remove(plugin).is_none() {
+        return Ok(());
+    }
+
+    info!("Revoked the network approval of plugin '{}'", plugin);
+    approvals.write_to_file()
+}
+
+/// Check that the URL's host is one of the hosts. `*.example.com` matches all subdomains of `example.com`.
+fn check_host(url: &Url, hosts: &[String]) -> Result<(), anyhow::Error> {
+    let host = url.host_str().ok_or_else(|| anyhow!("the URL has no host"))?.to_ascii_lowercase();
+
+    let is_declared = hosts.iter().map(|declared| declared.to_ascii_lowercase()).any(|declared| match declared.strip_prefix("*.") {
+        Some(domain) => host.ends_with(&format!(".{}", domain)),
+        None => host == declared,
+    });
+
+    if !is_declared {
+        bail!("the plugin doesn't declare the host '{}' in its info file", host);
+    }
+
+    Ok(())
+}
+
+/// Send the request of the plugin in the background.
+///
+/// The request is only sent if its host is declared by the plugin and the user approved the plugin's hosts.
+/// Returns the request's id, the response is available with [`take_responses`] once it arrived.
+pub fn send(info: &PluginInfo, url: &str, body: Option<RequestBody>) -> Result<u32, anyhow::Error> {
+    let url = Url::parse(url).map_err(|e| anyhow!("invalid URL: {}", e))?;
+
+    if url.scheme() != "http" && url.scheme() != "https" {
+        bail!("only HTTP and HTTPS URLs are supported");
+    }
+    check_host(&url, &info.hosts)?;
+    if !is_approved(info) {
+        bail!("the user didn't approve the plugin's network access in the GUI");
+    }
+
+    {
+        let mut pending = PENDING.lock().map_err(|e| anyhow!("could not get lock to the pending requests: {}", e))?;
+        let count = pending.entry(info.name.clone()).or_insert(0);
+
+        if *count >= MAX_PENDING_REQUESTS {
+            bail!("the plugin already waits for {} responses", MAX_PENDING_REQUESTS);
+        }
+        *count += 1;
+    }
+
+    let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::SeqCst);
+    let plugin = info.name.clone();
+
+    debug!("Plugin '{}' sends request {} to {}", plugin, id, url);
+
+    thread::spawn(move || {
+        let _worker = teardown::worker();
+        let result = execute(url, body).map_err(|e| e.to_string());
+
+        if let Ok(mut pending) = PENDING.lock() {
+            if let Some(count) = pending.get_mut(&plugin) {
+                *count = count.saturating_sub(1);
+            }
+        }
+
+        match RESPONSES.lock() {
+            Ok(mut responses) => responses.push(Response { plugin, id, result }),
+            Err(e) => warn!("Could not get lock to the responses: {}", e),
+        }
+    });
+
+    Ok(id)
+}
+
+fn execute(url: Url, body: Option<RequestBody>) -> Result<HttpResponse, anyhow::Error> {
+    // Redirects aren't followed, as they could lead to hosts the plugin doesn't declare
+    let client = Client::builder()
+        .timeout(TIMEOUT)
+        .redirect(Policy::none())
+        .user_agent(concat!("futuremod/", env!("CARGO_PKG_VERSION")))
+        .build()?;
+
+    let request = match body {
+        Some(body) => client.post(url).header(CONTENT_TYPE, body.content_type).body(body.content),
+        None => client.get(url),
+    };
+
+    let response = request.send()?;
+    let status = response.status().as_u16();
+
+    if response.content_length().is_some_and(|length| length as usize > MAX_RESPONSE_SIZE) {
+        bail!("the response is larger than {} bytes", MAX_RESPONSE_SIZE);
+    }
+
+    let content = response.bytes()?;
+    if content.len() > MAX_RESPONSE_SIZE {
+        bail!("the response is larger than {} bytes", MAX_RESPONSE_SIZE);
+    }
+
+    Ok(HttpResponse { status, body: String::from_utf8_lossy(&content).to_string() })
+}
+
+/// Take the responses that arrived since the last call, to pass them to the plugins on the game's thread.
+pub fn take_responses() -> Vec<Response> {
+    match RESPONSES.lock() {
+        Ok(mut responses) => responses.drain(..).collect(),
+        Err(_) => Vec::new(),
+    }
+}
@@ -0,0 +1,352 @@
+use std::{collections::{BTreeMap, VecDeque}, str::FromStr, sync::{atomic::{AtomicBool, Ordering}, Mutex}};
+
+use device_query::Keycode;
+use log::*;
+
+use crate::{api::ui::{render_rectangle, render_text, Color, TextPalette}, config::{DebugOverlayConfig, LogOverlayConfig}, debugger, futurecop::PlayerEntity, gc, input::KeyState, metrics, plugins::snapshot, viewport};
+
+/// Position of the log overlay's first line.
+const POSITION_X: u32 = 4;
+const POSITION_Y: u32 = 26;
+
+/// Position of the performance overlay's two lines, above the log overlay.
+const PERFORMANCE_POSITION_Y: u32 = 4;
+
+const LINE_HEIGHT: u32 = 10;
+
+/// Longer lines are cut off, since they wouldn't fit on the screen.
+const MAX_LINE_LENGTH: usize = 60;
+
+/// Lines of the debug overlay are shorter, since it only covers the right side of the screen.
+pub const DEBUG_LINE_LENGTH: usize = 30;
+
+/// Width of a character of the game's font.
+pub(crate) const CHARACTER_WIDTH: u32 = 5;
+
+/// Errors the debug overlay shows.
+const DEBUG_ERRORS: usize = 3;
+
+/// Lines each plugin can add to the debug overlay.
+pub const MAX_PLUGIN_DEBUG_LINES: usize = 4;
+
+const DEBUG_BACKGROUND: Color = Color { red: 0, green: 0, blue: 0 };
+
+lazy_static! {
+    static ref OVERLAY: Mutex<LogOverlay> = Mutex::new(LogOverlay::new(&LogOverlayConfig::default()));
+    static ref DEBUG_OVERLAY: Mutex<DebugOverlay> = Mutex::new(DebugOverlay::new(&DebugOverlayConfig::default()));
+}
+
+/// Whether the performance overlay with the FPS, frame time, and time spent in plugins is shown.
+static SHOW_PERFORMANCE: AtomicBool = AtomicBool::new(false);
+
+/// In-game overlay that shows the latest warnings and errors.
+///
+/// Lets plugin developers spot errors without switching to the GUI.
+/// The overlay is hidden until the user presses the toggle key.
+struct LogOverlay {
+    toggle_key: Option<Keycode>,
+    max_lines: usize,
+    visible: bool,
+    was_key_pressed: bool,
+    lines: VecDeque<(Level, String)>,
+}
+
+impl LogOverlay {
+    fn new(config: &LogOverlayConfig) -> Self {
+        LogOverlay {
+            toggle_key: Keycode::from_str(&config.toggle_key).ok(),
+            max_lines: config.lines,
+            visible: false,
+            was_key_pressed: false,
+            lines: VecDeque::new(),
+        }
+    }
+}
+
+/// In-game overlay with the state of the game and the mod, and the lines plugins add to it.
+///
+/// Hidden until the user presses the toggle key.
+struct DebugOverlay {
+    toggle_key: Option<Keycode>,
+    visible: bool,
+    was_key_pressed: bool,
+    /// Lines the plugins added, by the plugin's name.
+    plugin_lines: BTreeMap<String, Vec<String>>,
+}
+
+impl DebugOverlay {
+    fn new(config: &DebugOverlayConfig) -> Self {
+        DebugOverlay {
+            toggle_key: Keycode::from_str(&config.toggle_key).ok(),
+            visible: false,
+            was_key_pressed: false,
+            plugin_lines: BTreeMap::new(),
+        }
+    }
+}
+
+/// Configure the overlays.
+///
+/// Keeps the lines that were logged before.
+pub fn init(config: &LogOverlayConfig, debug_config: &DebugOverlayConfig, show_performance: bool) {
+    SHOW_PERFORMANCE.store(show_performance, Ordering::Relaxed);
+
+    if let Err(_) = Keycode::from_str(&config.toggle_key) {
+        warn!("Invalid toggle key '{}' for the log overlay, the overlay is disabled", config.toggle_key);
+    }
+
+    if let Err(_) = Keycode::from_str(&debug_config.toggle_key) {
+        warn!("Invalid toggle key '{}' for the debug overlay, the overlay is disabled", debug_config.toggle_key);
+    }
+
+    if let Ok(mut debug_overlay) = DEBUG_OVERLAY.lock() {
+        let plugin_lines = std::mem::take(&mut debug_overlay.plugin_lines);
+        *debug_overlay = DebugOverlay::new(debug_config);
+        debug_overlay.plugin_lines = plugin_lines;
+    }
+
+    let mut overlay = match OVERLAY.lock() {
+        Ok(overlay) => overlay,
+        Err(_) => return,
+    };
+
+    let lines = std::mem::take(&mut overlay.lines);
+    *overlay = LogOverlay::new(config);
+    overlay.lines = lines;
+    while overlay.lines.len() > overlay.max_lines {
+        overlay.lines.pop_front();
+    }
+}
+
+/// Render the overlays.
+///
+/// Must be called once per frame of the mission's game loop after the key state and metrics were updated.
+pub fn on_frame(players: &[Option<*mut PlayerEntity>; 2]) {
+    if SHOW_PERFORMANCE.load(Ordering::Relaxed) {
+        render_performance();
+    }
+
+    render_log();
+    render_debug(players);
+}
+
+/// Set the lines the plugin adds to the debug overlay, replacing its previous lines.
+///
+/// Lines are cut off after [`MAX_PLUGIN_DEBUG_LINES`]. No lines remove the plugin from the overlay.
+pub fn set_plugin_lines(plugin: &str, lines: Vec<String>) {
+    let mut debug_overlay = match DEBUG_OVERLAY.lock() {
+        Ok(debug_overlay) => debug_overlay,
+        Err(_) => return,
+    };
+
+    if lines.is_empty() {
+        debug_overlay.plugin_lines.remove(plugin);
+        return;
+    }
+
+    let lines = lines.iter().take(MAX_PLUGIN_DEBUG_LINES).map(|line| sanitize(line, DEBUG_LINE_LENGTH)).collect();
+    debug_overlay.plugin_lines.insert(plugin.to_string(), lines);
+}
+
+/// Remove the lines of the plugin from the debug overlay, e.g., when it is unloaded.
+pub fn clear_plugin_lines(plugin: &str) {
+    if let Ok(mut debug_overlay) = DEBUG_OVERLAY.lock() {
+        debug_overlay.plugin_lines.remove(plugin);
+    }
+}
+
+/// Toggle the debug overlay if the toggle key was pressed and render it if it's visible.
+///
+/// Shows the FPS, the frame, the players' positions, the number of enabled plugins, the latest errors, and the
+/// lines of the plugins in a box on the right side of the screen.
+fn render_debug(players: &[Option<*mut PlayerEntity>; 2]) {
+    let plugin_lines = {
+        let mut debug_overlay = match DEBUG_OVERLAY.lock() {
+            Ok(debug_overlay) => debug_overlay,
+            Err(_) => return,
+        };
+
+        let toggle_key = match debug_overlay.toggle_key {
+            Some(key) => key,
+            None => return,
+        };
+
+        let is_key_pressed = KeyState::new().is_key_pressed(toggle_key).unwrap_or(false);
+        if is_key_pressed && !debug_overlay.was_key_pressed {
+            debug_overlay.visible = !debug_overlay.visible;
+        }
+        debug_overlay.was_key_pressed = is_key_pressed;
+
+        if !debug_overlay.visible {
+            return;
+        }
+
+        debug_overlay.plugin_lines.clone()
+    };
+
+    let mut lines: Vec<(TextPalette, String)> = Vec::new();
+
+    let fps = metrics::get().map(|metrics| metrics.fps).unwrap_or_default();
+    lines.push((TextPalette::White, format!("FPS {:.0} Frame {}", fps, debugger::get_state().frame)));
+
+    for (index, player) in players.iter().enumerate() {
+        let line = match player {
+            Some(player) => unsafe { format!("P{} {} {} {}", index + 1, (**player).position_x, (**player).position_y, (**player).position_z) },
+            None => format!("P{} -", index + 1),
+        };
+        lines.push((TextPalette::White, line));
+    }
+
+    let plugins = snapshot::get();
+    let enabled = plugins.values().filter(|plugin| plugin.enabled).count();
+    lines.push((TextPalette::White, format!("Plugins {}/{} enabled", enabled, plugins.len())));
+
+    for error in latest_errors() {
+        lines.push((TextPalette::Red, sanitize(&error, DEBUG_LINE_LENGTH)));
+    }
+
+    for (plugin, plugin_lines) in plugin_lines.iter() {
+        lines.push((TextPalette::Yellow, sanitize(plugin, DEBUG_LINE_LENGTH)));
+        for line in plugin_lines {
+            lines.push((TextPalette::LightGreen, line.clone()));
+        }
+    }
+
+    let (screen_width, _) = viewport::screen_size();
+    let width = DEBUG_LINE_LENGTH as u32 * CHARACTER_WIDTH + 4;
+    let pos_x = screen_width.saturating_sub(width + POSITION_X);
+    let height = lines.len() as u32 * LINE_HEIGHT + 4;
+
+    render_rectangle(DEBUG_BACKGROUND, pos_x as u16, PERFORMANCE_POSITION_Y as u16, width as u16, height.min(u16::MAX as u32) as u16, true);
+
+    for (index, (palette, line)) in lines.iter().enumerate() {
+        render_text(pos_x + 2, PERFORMANCE_POSITION_Y + 2 + index as u32 * LINE_HEIGHT, *palette, line);
+    }
+}
+
+/// The latest errors of the log overlay, newest last.
+fn latest_errors() -> Vec<String> {
+    let overlay = match OVERLAY.lock() {
+        Ok(overlay) => overlay,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut errors: Vec<String> = overlay.lines
+        .iter()
+        .rev()
+        .filter(|(level, _)| *level == Level::Error)
+        .take(DEBUG_ERRORS)
+        .map(|(_, line)| line.clone())
+        .collect();
+    errors.reverse();
+
+    errors
+}
+
+/// Render the FPS, frame time, the time spent in plugins, and the garbage collector's statistics.
+fn render_performance() {
+    let metrics = match metrics::get() {
+        Some(metrics) => metrics,
+        None => return,
+    };
+
+    let line = format!(
+        "FPS: {:.0} Frame: {:.1} ms Plugins: {:.2} ms ({:.0}%)",
+        metrics.fps, metrics.frame_time, metrics.plugin_time, metrics.plugin_share,
+    );
+
+    render_text(POSITION_X, PERFORMANCE_POSITION_Y, TextPalette::White, &line);
+
+    let gc_line = format!(
+        "Lua: {:.1} MB GC: {:.2} ms (max {:.2} ms)",
+        gc::used_memory() / 1024.0, metrics.gc_time, metrics.max_gc_time,
+    );
+
+    render_text(POSITION_X, PERFORMANCE_POSITION_Y + LINE_HEIGHT, TextPalette::White, &gc_line);
+}
+
+/// Toggle the log overlay if the toggle key was pressed and render it if it's visible.
+fn render_log() {
+    let lines: Vec<(Level, String)> = {
+        let mut overlay = match OVERLAY.lock() {
+            Ok(overlay) => overlay,
+            Err(_) => return,
+        };
+
+        let toggle_key = match overlay.toggle_key {
+            Some(key) => key,
+            None => return,
+        };
+
+        let is_key_pressed = KeyState::new().is_key_pressed(toggle_key).unwrap_or(false);
+        if is_key_pressed && !overlay.was_key_pressed {
+            overlay.visible = !overlay.visible;
+        }
+        overlay.was_key_pressed = is_key_pressed;
+
+        if !overlay.visible {
+            return;
+        }
+
+        overlay.lines.iter().cloned().collect()
+    };
+
+    // Render without holding the lock, rendering may log
+    for (index, (level, line)) in lines.iter().enumerate() {
+        let palette = match level {
+            Level::Error => TextPalette::Red,
+            _ => TextPalette::Yellow,
+        };
+
+        render_text(POSITION_X, POSITION_Y + index as u32 * LINE_HEIGHT, palette, line);
+    }
+}
+
+/// Only keep characters that the game's font can render, and cut the line off after `length` characters.
+pub(crate) fn sanitize(line: &str, length: usize) -> String {
+    line.chars()
+        .map(|character| match character.is_ascii_graphic() {
+            true => character,
+            false => ' ',
+        })
+        .take(length)
+        .collect()
+}
+
+/// Log appender that keeps the latest warnings and errors for the overlay.
+#[derive(Debug)]
+pub struct OverlayLogger;
+
+impl Log for OverlayLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        // Never block the thread that logs, e.g., if the overlay itself logs while rendering
+        let mut overlay = match OVERLAY.try_lock() {
+            Ok(overlay) => overlay,
+            Err(_) => return,
+        };
+
+        if overlay.max_lines == 0 {
+            return;
+        }
+
+        let source = match record.key_values().get(kv::Key::from("plugin")) {
+            Some(plugin) => plugin.to_string(),
+            None => record.target().to_string(),
+        };
+
+        overlay.lines.push_back((record.level(), sanitize(&format!("{} {}: {}", record.level(), source, record.args()), MAX_LINE_LENGTH)));
+        while overlay.lines.len() > overlay.max_lines {
+            overlay.lines.pop_front();
+        }
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,114 @@
+use std::{collections::HashMap, path::Path, sync::{atomic::{AtomicBool, Ordering}, Mutex}};
+
+use futuremod_data::audit::{AuditReport, PluginAudit};
+use log::*;
+
+/// Functions of the dangerous library whose first argument is a memory address.
+const ADDRESS_FUNCTIONS: [&str; 5] = ["hook", "writeMemory", "readMemory", "getNativeFunction", "createNativeStruct"];
+
+/// Maximum number of distinct addresses recorded per plugin.
+const MAX_ADDRESSES: usize = 4096;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref REPORT: Mutex<HashMap<String, PluginAudit>> = Mutex::new(HashMap::new());
+}
+
+/// Enable or disable the audit mode.
+///
+/// Only affects plugins loaded afterwards, as the dangerous library is instrumented when a plugin is loaded.
+pub fn set_enabled(enabled: bool) {
+    if enabled {
+        info!("Sandbox audit mode is enabled");
+    }
+
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+pub fn get_report() -> Result<AuditReport, anyhow::Error> {
+    let report = REPORT.lock().map_err(|e| anyhow::anyhow!("could not get lock to the audit report: {}", e))?;
+
+    Ok(AuditReport {
+        enabled: is_enabled(),
+        plugins: report.clone(),
+    })
+}
+
+/// Record that the plugin loaded the file.
+pub fn record_file(plugin: &str, path: &Path) {
+    if !is_enabled() {
+        return;
+    }
+
+    info!(target: "audit", plugin:% = plugin; "Plugin '{}' loaded file {}", plugin, path.display());
+    with_plugin_audit(plugin, |audit| {
+        audit.files.insert(path.to_path_buf());
+    });
+}
+
+/// Record a call of a function of the dangerous library.
+fn record_call(plugin: &str, function: &str, address: Option<u32>) {
+    match address {
+        Some(address) => debug!(target: "audit", plugin:% = plugin; "Plugin '{}' called dangerous.{} with address {:#x}", plugin, function, address),
+        None => debug!(target: "audit", plugin:% = plugin; "Plugin '{}' called dangerous.{}", plugin, function),
+    }
+
+    with_plugin_audit(plugin, |audit| {
+        *audit.dangerous_calls.entry(function.to_string()).or_insert(0) += 1;
+
+        if let Some(address) = address {
+            if audit.addresses.len() < MAX_ADDRESSES {
+                audit.addresses.insert(address);
+            } else if !audit.addresses.contains(&address) {
+                audit.addresses_truncated = true;
+            }
+        }
+    });
+}
+
+/// Wrap every function of the library table, such that calls are recorded for the plugin.
+///
+/// Calls of methods of objects returned by the library, e.g., of a `NativeFunction`, aren't recorded.
+pub fn instrument_library(lua: &mlua::Lua, plugin: &str, library: &mlua::Table) -> Result<(), mlua::Error> {
+    let functions: Vec<(String, mlua::Function)> = library
+        .clone()
+        .pairs::<String, mlua::Function>()
+        .filter_map(|pair| pair.ok())
+        .collect();
+
+    for (name, function) in functions {
+        let key = lua.create_registry_value(function)?;
+        let plugin = plugin.to_string();
+        let takes_address = ADDRESS_FUNCTIONS.contains(&name.as_str());
+        let function_name = name.clone();
+
+        let wrapper = lua.create_function(move |lua, args: mlua::MultiValue| {
+            let address = match args.iter().next() {
+                Some(mlua::Value::Integer(address)) if takes_address => Some(*address as u32),
+                Some(mlua::Value::Number(address)) if takes_address => Some(*address as u32),
+                _ => None,
+            };
+
+            record_call(&plugin, &function_name, address);
+
+            let function: mlua::Function = lua.registry_value(&key)?;
+            function.call::<_, mlua::MultiValue>(args)
+        })?;
+
+        library.set(name, wrapper)?;
+    }
+
+    Ok(())
+}
+
+fn with_plugin_audit(plugin: &str, f: impl FnOnce(&mut PluginAudit)) {
+    match REPORT.lock() {
+        Ok(mut report) => f(report.entry(plugin.to_string()).or_default()),
+        Err(e) => warn!("Could not get lock to the audit report: {}", e),
+    }
+}
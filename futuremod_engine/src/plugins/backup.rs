@@ -0,0 +1,121 @@
+use std::{fs, io::{Cursor, Write}, path::Path, time::SystemTime};
+
+use anyhow::{anyhow, bail};
+use log::*;
+use walkdir::WalkDir;
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+/// File that marks an archive as a backup of the plugins directory. Contains the time the backup was created.
+const MARKER_FILE_NAME: &str = "futuremod-backup.txt";
+
+/// Archive the plugins directory.
+///
+/// The backup contains the installed plugins together with their settings and stored data,
+/// and the mod's files in the plugins directory, e.g., which plugins are enabled and the key bindings.
+/// Plugins installed in developer mode are only links to the developer's folder and aren't backed up.
+/// Returns the content of the zip archive.
+pub fn create(plugins_directory: &Path) -> Result<Vec<u8>, anyhow::Error> {
+    info!("Backing up the plugins directory {}", plugins_directory.display());
+
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default();
+
+    archive.start_file(MARKER_FILE_NAME, options)?;
+    archive.write_all(humantime::format_rfc3339_seconds(SystemTime::now()).to_string().as_bytes())?;
+
+    let mut files = 0;
+    for entry in WalkDir::new(plugins_directory).min_depth(1) {
+        let entry = entry?;
+        let name = archive_name(entry.path().strip_prefix(plugins_directory)?)?;
+
+        // Links of plugins installed in developer mode aren't followed
+        if entry.file_type().is_symlink() {
+            debug!("Not backing up {}, it's a plugin installed in developer mode", name);
+            continue;
+        }
+
+        if entry.file_type().is_dir() {
+            archive.add_directory(name, options)?;
+        } else if entry.file_type().is_file() {
+            archive.start_file(name, options)?;
+            archive.write_all(&fs::read(entry.path())?)?;
+            files += 1;
+        }
+    }
+
+    let content = archive.finish()?.into_inner();
+    info!("Backed up {} files, the backup has {} bytes", files, content.len());
+
+    Ok(content)
+}
+
+/// Name of the file in the archive, which always uses `/` as separator.
+fn archive_name(relative_path: &Path) -> Result<String, anyhow::Error> {
+    let components = relative_path.components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<&str>>>()
+        .ok_or_else(|| anyhow!("{} is not a valid UTF-8 path", relative_path.display()))?;
+
+    Ok(components.join("/"))
+}
+
+/// Extract the backup into the folder.
+///
+/// Fails if the archive isn't a backup, e.g., a plugin package selected by accident.
+pub fn extract(backup: &Path, destination: &Path) -> Result<(), anyhow::Error> {
+    let mut archive = ZipArchive::new(fs::File::open(backup)?)?;
+
+    if archive.by_name(MARKER_FILE_NAME).is_err() {
+        bail!("the file is not a backup of the plugins directory");
+    }
+
+    archive.extract(destination)?;
+    fs::remove_file(destination.join(MARKER_FILE_NAME))?;
+
+    Ok(())
+}
+
+/// Replace the content of the plugins directory with the extracted backup.
+///
+/// Plugins installed in developer mode are kept, as they aren't part of backups.
+/// Must only be called while no plugin is loaded.
+pub fn replace_directory_content(plugins_directory: &Path, backup: &Path) -> Result<(), anyhow::Error> {
+    for entry in fs::read_dir(plugins_directory)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+
+        if file_type.is_symlink() {
+            continue;
+        }
+
+        match file_type.is_dir() {
+            true => fs::remove_dir_all(entry.path())?,
+            false => fs::remove_file(entry.path())?,
+        }
+    }
+
+    let mut entries = WalkDir::new(backup).min_depth(1).into_iter();
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let destination = plugins_directory.join(entry.path().strip_prefix(backup)?);
+
+        // Never write into the folder of a plugin installed in developer mode
+        if fs::symlink_metadata(&destination).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+            warn!("Not restoring {}, a plugin in developer mode is installed in its place", destination.display());
+
+            if entry.file_type().is_dir() {
+                entries.skip_current_dir();
+            }
+            continue;
+        }
+
+        match entry.file_type().is_dir() {
+            true => fs::create_dir_all(&destination)?,
+            false => {
+                fs::copy(entry.path(), &destination)?;
+            },
+        }
+    }
+
+    Ok(())
+}
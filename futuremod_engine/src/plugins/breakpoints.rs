@@ -0,0 +1,49 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use futuremod_data::plugin::{ErrorSnapshot, PluginError};
+use log::*;
+
+use crate::{debugger, server};
+
+/// Number of the plugin's latest log lines kept in a snapshot.
+const SNAPSHOT_LOG_LINES: usize = 20;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable error breakpoints.
+///
+/// If enabled, errors of plugins carry a snapshot of the plugin's state,
+/// and plugins whose callbacks throw an error are stopped in the error state.
+pub fn set_enabled(enabled: bool) {
+    if enabled {
+        info!("Error breakpoints are enabled");
+    }
+
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Attach a snapshot of the plugin's state to the script error.
+///
+/// Other errors and errors while breakpoints are disabled are returned unchanged.
+pub fn capture(plugin: &str, error: PluginError, arguments: Vec<String>) -> PluginError {
+    if !is_enabled() {
+        return error;
+    }
+
+    match error {
+        PluginError::ScriptError(mut script_error) => {
+            script_error.snapshot = Some(ErrorSnapshot {
+                frame: debugger::get_state().frame,
+                arguments,
+                logs: server::recent_plugin_logs(plugin, SNAPSHOT_LOG_LINES),
+            });
+
+            PluginError::ScriptError(script_error)
+        },
+        error => error,
+    }
+}
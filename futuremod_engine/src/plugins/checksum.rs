@@ -0,0 +1,82 @@
+use std::{collections::HashMap, fs, path::{Path, PathBuf}};
+
+use anyhow::anyhow;
+use log::*;
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use super::{settings::SETTINGS_FILE_NAME, storage};
+
+/// Compute the SHA-256 checksum of the plugin's files in `path`.
+///
+/// Hashes the relative path and the content of every file in a fixed order.
+/// Thus, the checksum changes if a file is added, removed, renamed, or modified.
+/// The plugin's storage and settings are skipped, since they change by design.
+pub fn compute(path: &Path) -> Result<String, anyhow::Error> {
+    let mut hasher = Sha256::new();
+
+    for entry in WalkDir::new(path).sort_by_file_name() {
+        let entry = entry.map_err(|e| anyhow!("could not read the plugin folder: {}", e))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let relative_path = entry.path().strip_prefix(path)?.to_string_lossy().replace('\\', "/");
+        if storage::is_storage_file(&relative_path) || relative_path == SETTINGS_FILE_NAME {
+            continue;
+        }
+
+        let content = fs::read(entry.path()).map_err(|e| anyhow!("could not read {}: {}", entry.path().display(), e))?;
+
+        hasher.update(relative_path.as_bytes());
+        hasher.update([0]);
+        hasher.update((content.len() as u64).to_le_bytes());
+        hasher.update(&content);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Checksums of the installed plugins, pinned when the plugin was installed.
+#[derive(Debug, Clone)]
+pub struct PluginChecksums {
+    checksums: HashMap<String, String>,
+    path: PathBuf,
+}
+
+impl PluginChecksums {
+    pub fn new(path: &Path) -> Result<PluginChecksums, anyhow::Error> {
+        debug!("Reading plugin checksums from '{}'", path.display());
+
+        let checksums: HashMap<String, String> = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| anyhow!("could not parse the plugin checksums file: {}", e))?,
+            Err(_) => HashMap::new(),
+        };
+
+        Ok(PluginChecksums { checksums, path: path.to_path_buf() })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.checksums.get(name)
+    }
+
+    pub fn insert(&mut self, name: &str, checksum: String) -> Result<(), anyhow::Error> {
+        self.checksums.insert(name.into(), checksum);
+
+        self.write_to_file()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), anyhow::Error> {
+        if self.checksums.remove(name).is_none() {
+            return Ok(());
+        }
+
+        self.write_to_file()
+    }
+
+    fn write_to_file(&self) -> Result<(), anyhow::Error> {
+        let content = serde_json::to_string_pretty(&self.checksums).map_err(|e| anyhow!("could not serialize plugin checksums: {}", e))?;
+
+        fs::write(&self.path, content).map_err(|e| anyhow!("could not write plugin checksums: {}", e))
+    }
+}
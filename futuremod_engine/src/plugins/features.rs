@@ -0,0 +1,80 @@
+use futuremod_data::{plugin::PluginError, version::{SemanticVersion, PLUGIN_API_VERSION}};
+
+/// Features of the engine that plugins can require in their manifest.
+///
+/// Plugins that require a feature missing from this list aren't loaded, instead of failing
+/// with `nil` errors when they use it.
+pub const ENGINE_FEATURES: [&str; 21] = [
+    // Register HTTP routes with `http.route`
+    "httpRoutes",
+    // Be notified of splits with `timer.onSplit`
+    "splitCallbacks",
+    // Positions of the previous run's ghost with the `replay` library
+    "ghostPositions",
+    // Set the Discord status with the `presence` library
+    "richPresence",
+    // Exchange data with a peer with the `multiplayer` library
+    "multiplayer",
+    // Hook the game's functions with `dangerous.hook`
+    "nativeHooks",
+    // Render text and rectangles with the `ui` library
+    "textRendering",
+    // Queue synthesized key presses with the `macro` library
+    "inputMacros",
+    // Persist values between game sessions with the `storage` library
+    "persistentStorage",
+    // Show timed captions with the `captions` library
+    "captions",
+    // React to key presses and register key bindings with `input.onKeyDown` and `input.registerBinding`
+    "keyBindings",
+    // Place HUDs in a design resolution with `ui.setLayout`
+    "uiLayouts",
+    // Declare settings users change in the GUI and read them with the `config` library
+    "pluginSettings",
+    // Access the game's structs with the definitions of the `structs` library
+    "gameStructs",
+    // Send HTTP requests to the declared hosts with the `net` library
+    "netRequests",
+    // Schedule callbacks with `time.after` and `time.every`
+    "scheduler",
+    // Subscribe to events of the game with `events.on`
+    "gameEvents",
+    // Run the declared external programs with `system.exec`
+    "externalPrograms",
+    // Inject keys and axes into the mod's and the game's key state with `input.sendKey` and `input.setAxis`
+    "inputInjection",
+    // Build in-game menus with the widgets of `ui.widgets`
+    "uiWidgets",
+    // Play WAV files shipped in the plugin's folder with `sound.playFile`
+    "soundPlayback",
+];
+
+/// Error if the engine doesn't support the version of the plugin API the plugin targets.
+///
+/// Plugins that don't declare a version are always supported. Versions that can't be parsed are never supported.
+pub fn check_api_version(required: &Option<String>) -> Result<(), PluginError> {
+    let required = match required {
+        Some(required) => required,
+        None => return Ok(()),
+    };
+
+    let supported = SemanticVersion::parse(PLUGIN_API_VERSION).expect("the plugin API version is a semantic version");
+    let is_compatible = SemanticVersion::parse(required)
+        .map(|required| required.is_compatible_with(&supported))
+        .unwrap_or(false);
+
+    if is_compatible {
+        return Ok(());
+    }
+
+    Err(PluginError::IncompatibleApiVersion { required: required.clone(), supported: PLUGIN_API_VERSION.to_string() })
+}
+
+/// Features the plugin requires that the engine doesn't support.
+pub fn missing_features(required: &[String]) -> Vec<String> {
+    required
+        .iter()
+        .filter(|feature| !ENGINE_FEATURES.contains(&feature.as_str()))
+        .cloned()
+        .collect()
+}
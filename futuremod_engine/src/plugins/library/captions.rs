@@ -0,0 +1,46 @@
+use std::{sync::Arc, time::Duration};
+
+use futuremod_data::{docs::FunctionDocs, plugin::PluginDependency};
+use mlua::Lua;
+
+use crate::captions;
+
+use super::{Library, LibraryBuilder};
+
+/// Seconds a caption is shown if the plugin doesn't specify it.
+const DEFAULT_SECONDS: f32 = 3.0;
+
+pub fn create_captions_library(lua: Arc<Lua>, plugin_name: &str) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Captions, "Show captions, e.g., subtitles or translations, in a styled area whose position and size the user configures. Captions of all plugins are shown one after the other.")?;
+
+  let show_plugin_name = plugin_name.to_string();
+  let show_fn = lua.create_function(move |_, (text, seconds): (String, Option<f32>)| {
+    let duration = Duration::try_from_secs_f32(seconds.unwrap_or(DEFAULT_SECONDS))
+      .map_err(|e| mlua::Error::RuntimeError(format!("invalid number of seconds: {}", e)))?;
+
+    captions::push(&show_plugin_name, &text, duration);
+
+    Ok(())
+  })?;
+  library.function(
+    "show",
+    show_fn,
+    FunctionDocs::new("Queue the caption to be shown for the number of seconds, by default `3`, after the captions queued before it. Text that doesn't fit into the area is cut off.")
+      .param("text", "string")
+      .param("seconds", "number?"),
+  )?;
+
+  let clear_plugin_name = plugin_name.to_string();
+  let clear_fn = lua.create_function(move |_, ()| {
+    captions::clear(&clear_plugin_name);
+
+    Ok(())
+  })?;
+  library.function(
+    "clear",
+    clear_fn,
+    FunctionDocs::new("Remove the shown and queued captions of the plugin."),
+  )?;
+
+  Ok(library.build())
+}
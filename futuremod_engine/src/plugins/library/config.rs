@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use futuremod_data::{docs::FunctionDocs, plugin::{PluginDependency, PluginInfo}, settings::Setting};
+use mlua::{Lua, LuaSerdeExt, Table, Value};
+
+use crate::plugins::settings::{self, SETTINGS_FILE_NAME};
+
+use super::{Library, LibraryBuilder};
+
+fn to_lua_error(e: anyhow::Error) -> mlua::Error {
+  mlua::Error::RuntimeError(e.to_string())
+}
+
+pub fn create_config_library(lua: Arc<Lua>, info: &PluginInfo) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Config, &format!("Read the plugin's settings, which users change on the plugin's details page in the GUI. Settings are declared in the plugin's `info.toml` or with `define`. Values are stored in the file `{}` in the plugin's folder.", SETTINGS_FILE_NAME))?;
+
+  let info = Arc::new(info.clone());
+
+  let define_info = info.clone();
+  let define_fn = lua.create_function(move |lua, (name, options): (String, Table)| {
+    options.set("name", name)?;
+
+    let setting: Setting = lua.from_value(Value::Table(options)).map_err(|e| mlua::Error::RuntimeError(format!("invalid setting: {}", e)))?;
+    let value = settings::define(&define_info, setting).map_err(to_lua_error)?;
+
+    lua.to_value(&value)
+  })?;
+  library.function(
+    "define",
+    define_fn,
+    FunctionDocs::new("Define a setting like in the `info.toml` and return its current value. The type is `boolean`, `number` with an optional `min` and `max`, `enum` with its `options`, or `keyBinding`, which is registered as key binding of the input library.")
+      .param("name", "string")
+      .param("options", "{type: string, default: any, label: string?, description: string?, min: number?, max: number?, options: {string}?}")
+      .returns("any"),
+  )?;
+
+  let get_info = info.clone();
+  let get_fn = lua.create_function(move |lua, name: String| {
+    match settings::get_value(&get_info, &name).map_err(to_lua_error)? {
+      Some(value) => lua.to_value(&value),
+      None => Ok(Value::Nil),
+    }
+  })?;
+  library.function(
+    "get",
+    get_fn,
+    FunctionDocs::new("Get the current value of the setting. Changes of the user apply immediately. Returns `nil` if the setting doesn't exist.")
+      .param("name", "string")
+      .returns("any"),
+  )?;
+
+  Ok(library.build())
+}
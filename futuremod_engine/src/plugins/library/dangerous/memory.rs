@@ -93,6 +93,11 @@ pub fn read_memory_function<'lua>(lua: &'lua Lua, (address, type_name): (u32, St
   unsafe {
     value = match value_type {
       Type::Float => mlua::Value::Number(*(address as *const f32) as f64),
+      Type::Double => mlua::Value::Number(*(address as *const f64)),
+      Type::Pointer | Type::Struct => match *(address as *const u32) {
+        0 => mlua::Value::Nil,
+        pointer => mlua::Value::Number(pointer.into()),
+      },
       Type::String => {
         let mut string_bytes: Vec<u8> = Vec::new();
         let string_pointer = address as *const u8;
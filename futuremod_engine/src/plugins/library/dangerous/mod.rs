@@ -1,41 +1,191 @@
 use std::sync::Arc;
 
-use mlua::Lua;
-use native::{create_native_struct_definition_fn, create_native_struct_fn};
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::{PluginDependency, PluginPermission}, structs::StructDefinition};
+use mlua::{AnyUserData, Function, Lua, LuaSerdeExt, MultiValue};
+use native::{create_native_struct_definition_fn, create_native_struct_fn, struct_fields};
 use futuremod_hook::lua::{get_native_function, create_native_function_function};
 
 mod memory;
 mod native;
 
+pub use native::definition_from_fields;
+
 use futuremod_hook::lua::hook_function;
 use memory::*;
 
-
-pub fn create_dangerous_library(lua: Arc<Lua>) -> Result<mlua::OwnedTable, mlua::Error> {
-  let table = lua.create_table()?;
-
-  let hook_fn = lua.create_function(hook_function)?;
-  table.set("hook", hook_fn)?;
+use crate::{bookmarks, crash, structs};
+
+use super::{Library, LibraryBuilder};
+
+/// Permissions a plugin must be granted to use each function.
+///
+/// Native structs read and write their fields, thus, they require both memory permissions.
+const FUNCTION_PERMISSIONS: [(&str, &[PluginPermission]); 9] = [
+  ("hook", &[PluginPermission::Hook]),
+  ("writeMemory", &[PluginPermission::MemoryWrite]),
+  ("readMemory", &[PluginPermission::MemoryRead]),
+  ("bookmark", &[PluginPermission::MemoryRead]),
+  ("createNativeFunction", &[PluginPermission::Hook]),
+  ("getNativeFunction", &[PluginPermission::Hook]),
+  ("createNativeStructDefinition", &[PluginPermission::MemoryRead, PluginPermission::MemoryWrite]),
+  ("createNativeStruct", &[PluginPermission::MemoryRead, PluginPermission::MemoryWrite]),
+  ("registerStruct", &[PluginPermission::MemoryRead]),
+];
+
+
+/// Create the dangerous library with only the functions the granted permissions allow.
+pub fn create_dangerous_library(lua: Arc<Lua>, plugin_name: &str, permissions: &[PluginPermission]) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Dangerous, "Low-level access to the game's memory and functions. Wrong usage crashes the game.")?;
+
+  let hook_plugin_name = plugin_name.to_string();
+  let hook_fn = lua.create_function(move |lua, (address, arg_types, return_type, callback): (u32, Vec<String>, String, Function)| {
+    // Attribute crashes in the hook to the plugin
+    let callback_key = lua.create_registry_value(callback)?;
+    let callback_plugin_name = hook_plugin_name.clone();
+    let callback = lua.create_function(move |lua, args: MultiValue| {
+      let _active = crash::enter_hook(&callback_plugin_name, address);
+
+      lua.registry_value::<Function>(&callback_key)?.call::<_, MultiValue>(args)
+    })?;
+
+    let mut hook = hook_function(lua, (address, arg_types, return_type, callback))?;
+
+    // Mark the hook as the plugin's so it is removed when the plugin is unloaded
+    hook.set_owner(&hook_plugin_name).map_err(|e| mlua::Error::RuntimeError(format!("Could not set the owner of the hook: {:?}", e)))?;
+
+    Ok(hook)
+  })?;
+  library.function(
+    "hook",
+    hook_fn,
+    FunctionDocs::new("Hook the native function at the address. The hook function gets the original function as first argument, followed by the converted arguments. Hooks of the same function are chained in the order they were set, the original function of a hook calls the next hook. The hook is removed when the plugin is unloaded or reloaded.")
+      .param("address", "number")
+      .param("argTypes", "{string}")
+      .param("returnType", "string")
+      .param("hookFn", "function")
+      .returns("Hook"),
+  )?;
 
   let write_fn = lua.create_function(write_memory_function)?;
-  table.set("writeMemory", write_fn)?;
+  library.function(
+    "writeMemory",
+    write_fn,
+    FunctionDocs::new("Write the value into the game's memory at the address.").param("address", "number").param("value", "T"),
+  )?;
 
   let read_fn = lua.create_function(read_memory_function)?;
-  table.set("readMemory", read_fn)?;
+  library.function(
+    "readMemory",
+    read_fn,
+    FunctionDocs::new("Read a value of the given type from the game's memory at the address.").param("address", "number").param("type", "string").returns("T"),
+  )?;
+
+  let bookmark_fn = lua.create_function(|lua, name: String| {
+    match bookmarks::get(&name) {
+      Some(bookmark) => lua.to_value(&bookmark),
+      None => Ok(mlua::Value::Nil),
+    }
+  })?;
+  library.function(
+    "bookmark",
+    bookmark_fn,
+    FunctionDocs::new("Get the bookmark with the name, e.g., an address found in the GUI's bookmarks. Returns `nil` if no such bookmark exists.")
+      .param("name", "string")
+      .returns("Bookmark?"),
+  )?;
 
   let create_native_function_fn = lua.create_function(create_native_function_function)?;
-  table.set("createNativeFunction", create_native_function_fn)?;
+  library.function(
+    "createNativeFunction",
+    create_native_function_fn,
+    FunctionDocs::new("Create a native function that calls the lua function, e.g., to pass it to the game as a callback. It is called in the calling convention, by default `cdecl`.")
+      .param("argTypes", "{string}")
+      .param("returnType", "string")
+      .param("luaFn", "function")
+      .param("convention", "(\"cdecl\" | \"stdcall\" | \"thiscall\" | \"fastcall\")?")
+      .returns("NativeFunction"),
+  )?;
 
   let get_native_function_fn = lua.create_function(get_native_function)?;
-  table.set("getNativeFunction", get_native_function_fn)?;
+  library.function(
+    "getNativeFunction",
+    get_native_function_fn,
+    FunctionDocs::new("Get a callable wrapper of the native function at the address. It is called in the calling convention, by default `cdecl`.")
+      .param("address", "number")
+      .param("argTypes", "{string}")
+      .param("returnType", "string")
+      .param("convention", "(\"cdecl\" | \"stdcall\" | \"thiscall\" | \"fastcall\")?")
+      .returns("NativeFunction"),
+  )?;
 
   let create_native_struct_definition = lua.create_function(create_native_struct_definition_fn)?;
-  table.set("createNativeStructDefinition", create_native_struct_definition)?;
+  library.function(
+    "createNativeStructDefinition",
+    create_native_struct_definition,
+    FunctionDocs::new("Define the layout of a native struct.")
+      .param("fields", "{[string]: {offset: number, type: string | ComplexType}}")
+      .returns("NativeStructDefinition"),
+  )?;
 
   let create_native_struct = lua.create_function(create_native_struct_fn)?;
-  table.set("createNativeStruct", create_native_struct)?;
-
-  Ok(table.into_owned())
+  library.function(
+    "createNativeStruct",
+    create_native_struct,
+    FunctionDocs::new("Access the native struct at the address with the given definition.")
+      .param("address", "number")
+      .param("definition", "NativeStructDefinition")
+      .returns("NativeStruct"),
+  )?;
+
+  let register_plugin_name = plugin_name.to_string();
+  let register_struct_fn = lua.create_function(move |_, (name, definition, description): (String, AnyUserData, Option<String>)| {
+    let definition = StructDefinition {
+      name,
+      description: description.unwrap_or_default(),
+      plugin: None,
+      fields: struct_fields(&definition)?,
+    };
+
+    structs::register(&register_plugin_name, definition).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+  })?;
+  library.function(
+    "registerStruct",
+    register_struct_fn,
+    FunctionDocs::new("Show the struct definition in the GUI's struct viewer under the name, to inspect instances of it live. The definition is removed when the plugin is unloaded or reloaded.")
+      .param("name", "string")
+      .param("definition", "NativeStructDefinition")
+      .param("description", "string?"),
+  )?;
+
+  library.document_type(
+    TypeDocs::new("Hook", "Hook on a native function.")
+      .method("unhook", FunctionDocs::new("Remove the hook and restore the original function."))
+  );
+
+  library.document_type(
+    TypeDocs::new("Bookmark", "Named address in the game's memory, shared by the GUI and all plugins.")
+      .field("name", "string", "Name the bookmark is accessed with.")
+      .field("address", "number", "Address of the value.")
+      .field("type", "string", "Type of the value, to pass to `readMemory`.")
+      .field("description", "string", "What the value is, e.g., the boss's health.")
+  );
+
+  library.document_type(
+    TypeDocs::new("NativeFunction", "Native function located at some address with a specific signature.")
+      .method("getAddress", FunctionDocs::new("Get the function's address.").returns("number"))
+      .method("call", FunctionDocs::new("Call the function. Arguments and return value are converted based on the function's signature.").param("...", "any").returns("any"))
+  );
+
+  library.document_type(
+    TypeDocs::new("NativeStructDefinition", "Layout of a native struct.")
+      .method("cast", FunctionDocs::new("Access the native struct at the address with this definition.").param("address", "number").returns("NativeStruct"))
+  );
+
+  for (name, required) in FUNCTION_PERMISSIONS {
+    library.require_permissions(name, required, permissions)?;
+  }
+
+  Ok(library.build())
 }
 
 
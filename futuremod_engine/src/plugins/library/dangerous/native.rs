@@ -3,7 +3,8 @@ use std::{cell::Ref, collections::HashMap};
 use log::debug;
 use mlua::{AnyUserData, AnyUserDataExt, Lua, MetaMethod, UserData};
 
-use futuremod_hook::types::{lua_to_native, native_to_lua, Type};
+use futuremod_data::structs::StructField;
+use futuremod_hook::types::{lua_to_native, native_words_to_lua, Type};
 
 use crate::plugins::library::LuaResult;
 
@@ -45,8 +46,8 @@ impl UserData for NativeStruct {
         match &native_field.field_type {
           FieldType::Primitive(primitive) => {
             unsafe {
-              let value = *(field_ptr as *const u32);
-              native_to_lua(lua, *primitive, value)
+              let words = std::slice::from_raw_parts(field_ptr as *const u32, primitive.words());
+              native_words_to_lua(lua, *primitive, words)
             }
           },
           FieldType::Complex(complex) => {
@@ -102,8 +103,8 @@ impl UserData for NativeStruct {
             };
 
             // Report if the lua value was converted into more bytes than expected
-            if native_value.len() > 1 {
-              debug!("Converted lua value is larger than one double word: {:?}", value);
+            if native_value.len() > primitive.words() {
+              debug!("Converted lua value is larger than its type: {:?}", value);
             }
 
             match primitive {
@@ -142,6 +143,14 @@ impl UserData for NativeStruct {
                   *field_ptr = native_value[0] as i32;
                 }
               }
+              Type::Double => {
+                let field_ptr = field_addr as *mut u32;
+
+                unsafe {
+                  *field_ptr = native_value[0];
+                  *field_ptr.add(1) = native_value[1];
+                }
+              },
               _ => {
                 let field_ptr = field_addr as *mut u32;
 
@@ -306,4 +315,65 @@ pub fn create_native_struct_fn<'lua>(lua: &'lua Lua, (address, definition_userda
   debug!("Create new native struct at 0x{:x}", address);
 
   native_struct_from_definition(lua, address, definition_userdata)
+}
+
+/// Name of the primitive type as used by `readMemory`.
+fn primitive_name(primitive: Type) -> &'static str {
+  match primitive {
+    Type::String => "string",
+    Type::Integer => "int",
+    Type::UnsignedInteger => "uint",
+    Type::Byte => "byte",
+    Type::UnsignedByte => "ubyte",
+    Type::Short => "short",
+    Type::UnsignedShort => "ushort",
+    Type::Float => "float",
+    Type::Double => "double",
+    Type::Pointer => "pointer",
+    Type::Struct => "struct",
+    Type::Void => "void",
+  }
+}
+
+/// Fields of the definition ordered by their offset, e.g., to show them in the GUI's struct viewer.
+///
+/// Fields of complex types have the type `complex`, since only the plugin knows how to read them.
+pub fn struct_fields(definition: &AnyUserData) -> LuaResult<Vec<StructField>> {
+  let definition: Ref<NativeStructDefinition> = definition.borrow()
+    .map_err(|_| mlua::Error::RuntimeError("Definition must be a native struct definition".to_string()))?;
+
+  let mut fields: Vec<StructField> = definition.fields
+    .iter()
+    .map(|(name, field)| StructField {
+      name: name.clone(),
+      offset: field.offset,
+      field_type: match field.field_type {
+        FieldDefinitionType::Primitive(primitive) => primitive_name(primitive).to_string(),
+        FieldDefinitionType::Complex(_) => "complex".to_string(),
+      },
+    })
+    .collect();
+
+  fields.sort_by_key(|field| field.offset);
+
+  Ok(fields)
+}
+
+/// Create the definition of a native struct from the fields of a struct the mod knows.
+///
+/// Only supports primitive types.
+pub fn definition_from_fields<'lua>(lua: &'lua Lua, fields: &[StructField]) -> LuaResult<AnyUserData<'lua>> {
+  let mut native_fields: HashMap<String, FieldDefinition> = HashMap::new();
+
+  for field in fields {
+    let primitive = Type::try_from_str(&field.field_type)
+      .ok_or_else(|| mlua::Error::RuntimeError(format!("Field {} has the unsupported type {}", field.name, field.field_type)))?;
+
+    native_fields.insert(field.name.clone(), FieldDefinition {
+      offset: field.offset,
+      field_type: FieldDefinitionType::Primitive(primitive),
+    });
+  }
+
+  lua.create_userdata(NativeStructDefinition{fields: native_fields})
 }
\ No newline at end of file
@@ -0,0 +1,116 @@
+use std::sync::{atomic::{AtomicU32, Ordering}, Arc};
+
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::PluginDependency};
+use mlua::{Function, Lua, LuaSerdeExt, Table};
+
+use crate::events::{GameEvent, EVENT_NAMES};
+
+use super::{Library, LibraryBuilder};
+
+static NEXT_SUBSCRIPTION_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Name of the registry value that holds the plugin's subscriptions by their id.
+fn registry_key(plugin_name: &str) -> String {
+  format!("futuremod.events.{}", plugin_name)
+}
+
+/// Get the plugin's subscriptions, creating the table if it doesn't exist.
+fn get_subscriptions<'lua>(lua: &'lua Lua, plugin_name: &str) -> Result<Table<'lua>, mlua::Error> {
+  let key = registry_key(plugin_name);
+
+  if let Some(subscriptions) = lua.named_registry_value::<Option<Table>>(&key)? {
+    return Ok(subscriptions);
+  }
+
+  let subscriptions = lua.create_table()?;
+  lua.set_named_registry_value(&key, subscriptions.clone())?;
+
+  Ok(subscriptions)
+}
+
+pub fn create_events_library(lua: Arc<Lua>, plugin_name: &str) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Events, "Subscribe to events of the game, such as a player taking damage, instead of hooking the game's functions. Callbacks are called at the start of the next frame of a mission, or right away when the mission ends. Disabling or reloading the plugin removes all of its subscriptions.")?;
+
+  let on_plugin_name = plugin_name.to_string();
+  let on_fn = lua.create_function(move |lua, (event, callback): (String, Function)| {
+    if !EVENT_NAMES.contains(&event.as_str()) {
+      return Err(mlua::Error::RuntimeError(format!("unknown event '{}', must be one of {}", event, EVENT_NAMES.join(", "))));
+    }
+
+    let subscription = lua.create_table()?;
+    subscription.set("event", event)?;
+    subscription.set("callback", callback)?;
+
+    let id = NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::SeqCst);
+    get_subscriptions(lua, &on_plugin_name)?.set(id, subscription)?;
+
+    Ok(id)
+  })?;
+  library.function(
+    "on",
+    on_fn,
+    FunctionDocs::new("Call the function with the event's data every time the event happens. Returns the subscription's id to remove it.")
+      .param("event", "\"playerSpawned\" | \"playerDamaged\" | \"playerDied\" | \"missionLoaded\" | \"missionEnded\" | \"gameModeChanged\"")
+      .param("callback", "(event: GameEvent) -> ()")
+      .returns("number"),
+  )?;
+
+  let off_plugin_name = plugin_name.to_string();
+  let off_fn = lua.create_function(move |lua, id: u32| {
+    let subscriptions = get_subscriptions(lua, &off_plugin_name)?;
+    let is_subscribed = subscriptions.contains_key(id)?;
+    subscriptions.set(id, mlua::Value::Nil)?;
+
+    Ok(is_subscribed)
+  })?;
+  library.function(
+    "off",
+    off_fn,
+    FunctionDocs::new("Remove the subscription. Returns `false` if it was removed before.")
+      .param("id", "number")
+      .returns("boolean"),
+  )?;
+
+  library.document_type(
+    TypeDocs::new("GameEvent", "Data of an event. Which fields exist depends on the event's type.")
+      .field("type", "string", "Name of the event, e.g., `playerDamaged`.")
+      .field("player", "number?", "Index of the player, `0` for player one and `1` for player two. Set for `playerSpawned`, `playerDamaged`, and `playerDied`.")
+      .field("damage", "number?", "Health the player lost since the last frame. Set for `playerDamaged`.")
+      .field("health", "number?", "Health left after the damage. Set for `playerDamaged`.")
+      .field("deaths", "number?", "Number of the player's deaths. Set for `playerDied`.")
+      .field("gameMode", "string?", "The new game mode, `CrimeWar` or `PrecinctAssault`. Set for `gameModeChanged`."),
+  );
+
+  Ok(library.build())
+}
+
+/// Call the plugin's callbacks of the event, in the order they subscribed.
+pub fn dispatch_event(lua: &Lua, plugin_name: &str, event: &GameEvent) -> Result<(), mlua::Error> {
+  let subscriptions = match lua.named_registry_value::<Option<Table>>(&registry_key(plugin_name))? {
+    Some(subscriptions) => subscriptions,
+    None => return Ok(()),
+  };
+
+  // Collect the callbacks first, as callbacks may subscribe or unsubscribe
+  let mut matching = Vec::new();
+  for pair in subscriptions.pairs::<u32, Table>() {
+    let (id, subscription) = pair?;
+
+    if subscription.get::<_, String>("event")? == event.name() {
+      matching.push((id, subscription.get::<_, Function>("callback")?));
+    }
+  }
+  matching.sort_by_key(|(id, _)| *id);
+
+  let data = lua.to_value(event)?;
+  for (_, callback) in matching {
+    callback.call::<_, ()>(data.clone())?;
+  }
+
+  Ok(())
+}
+
+/// Remove all of the plugin's subscriptions, e.g., when the plugin is disabled.
+pub fn clear_subscriptions(lua: &Lua, plugin_name: &str) -> Result<(), mlua::Error> {
+  lua.unset_named_registry_value(&registry_key(plugin_name))
+}
@@ -1,10 +1,14 @@
 use std::sync::Arc;
 
 use log::debug;
-use mlua::{FromLua, IntoLua, Lua, LuaSerdeExt, OwnedTable, UserData};
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::{PluginDependency, PluginPermission}};
+use futuremod_hook::lua::NativeFunction;
+use mlua::{FromLua, IntoLua, Lua, LuaSerdeExt, UserData};
 use serde::Serialize;
 
-use crate::futurecop::{self, global::GetterSetter, state::FUTURE_COP, PLAYER_ARRAY_ADDR};
+use crate::{debugger, futurecop::{self, get_game_function, global::GetterSetter, state::FUTURE_COP, GAME_FUNCTIONS, PLAYER_ARRAY_ADDR}};
+
+use super::{Library, LibraryBuilder};
 
 #[derive(Debug, Clone, Serialize)]
 enum GameMode {
@@ -41,134 +45,236 @@ struct GameState {
 
 
 
+/// A player's entity.
+///
+/// The player is looked up again on every access, so that a player that was destroyed, e.g., because the mission ended, isn't accessed anymore.
+/// Fields can only be accessed with their type, values that don't fit into a field error instead of overwriting the memory after it.
 #[derive(Debug)]
 struct PlayerEntity {
-  player_entity: *mut futurecop::PlayerEntity
+  player: u8,
+  player_entity: *mut futurecop::PlayerEntity,
+}
+
+impl PlayerEntity {
+  /// Get the player's entity, failing if the player doesn't exist anymore.
+  fn get(&self) -> Result<*mut futurecop::PlayerEntity, mlua::Error> {
+    match get_player_address(self.player) {
+      Some(address) if address == self.player_entity as u32 => Ok(self.player_entity),
+      _ => Err(mlua::Error::RuntimeError("the player doesn't exist anymore, get the player again".into())),
+    }
+  }
+
+  /// Get the player's data, such as the weapons.
+  fn get_player(&self) -> Result<*mut futurecop::Player, mlua::Error> {
+    let player = unsafe { (*self.get()?).player };
+
+    if player.is_null() {
+      return Err(mlua::Error::RuntimeError("the player's data doesn't exist".into()));
+    }
+
+    Ok(player)
+  }
+}
+
+/// Add a getter and a setter for the field of the player's entity.
+fn entity_field<'lua, T, F>(name: &str, fields: &mut F, extractor: fn(*mut futurecop::PlayerEntity) -> *mut T)
+where
+  F: mlua::prelude::LuaUserDataFields<'lua, PlayerEntity>,
+  T: IntoLua<'lua> + FromLua<'lua> + 'static + Copy,
+{
+  fields.add_field_method_get(name, move |_, this| Ok(unsafe { *extractor(this.get()?) }));
+
+  fields.add_field_method_set(name, move |_, this, value: T| {
+    unsafe { *extractor(this.get()?) = value };
+    Ok(())
+  });
+}
+
+/// Add a getter and a setter for the field of the player's data.
+fn player_field<'lua, T, F>(name: &str, fields: &mut F, extractor: fn(*mut futurecop::Player) -> *mut T)
+where
+  F: mlua::prelude::LuaUserDataFields<'lua, PlayerEntity>,
+  T: IntoLua<'lua> + FromLua<'lua> + 'static + Copy,
+{
+  fields.add_field_method_get(name, move |_, this| Ok(unsafe { *extractor(this.get_player()?) }));
+
+  fields.add_field_method_set(name, move |_, this, value: T| {
+    unsafe { *extractor(this.get_player()?) = value };
+    Ok(())
+  });
 }
 
 impl UserData for PlayerEntity {
   fn add_fields<'lua, F: mlua::prelude::LuaUserDataFields<'lua, Self>>(fields: &mut F) {
-      fields.add_field_method_get("health", |_, this| {
-        Ok(unsafe {
-          (*this.player_entity).health.health
-        })
-      });
+      fields.add_field_method_get("index", |_, this| Ok(this.player));
+
+      fields.add_field_method_get("health", |_, this| Ok(unsafe { (*this.get()?).health.health }));
 
       fields.add_field_method_set("health", |_, this, health: i16| {
-        unsafe {
-          (*this.player_entity).health.health = health;
+        let player_entity = this.get()?;
+        let max_health = unsafe { (*player_entity).health.max_health };
+
+        if !(0..=max_health).contains(&health) {
+          return Err(mlua::Error::RuntimeError(format!("health must be between 0 and the maximum health {}, got {}", max_health, health)));
         }
 
+        unsafe { (*player_entity).health.health = health };
         Ok(())
       });
 
-      fields.add_field_method_get("positionX", |_, this| {
-        Ok(unsafe {
-          (*this.player_entity).position_x
-        })
-      });
+      fields.add_field_method_get("maxHealth", |_, this| Ok(unsafe { (*this.get()?).health.max_health }));
+
+      entity_field("positionX", fields, |player| unsafe { &mut (*player).position_x });
+      entity_field("positionY", fields, |player| unsafe { &mut (*player).position_y });
+      entity_field("positionZ", fields, |player| unsafe { &mut (*player).position_z });
+      entity_field("idleTimer", fields, |player| unsafe { &mut (*player).idle_timer });
+
+      player_field("enemiesKilled", fields, |player| unsafe { &mut (*player).enemies_killed });
+      player_field("deaths", fields, |player| unsafe { &mut (*player).deaths });
+      player_field("currentAction", fields, |player| unsafe { &mut (*player).current_action });
+      player_field("movementMode", fields, |player| unsafe { &mut (*player).movement_mode });
+      player_field("currentTargetType", fields, |player| unsafe { &mut (*player).current_target_type });
+      player_field("currentTarget", fields, |player| unsafe { &mut (*player).current_target });
+      player_field("lastTarget", fields, |player| unsafe { &mut (*player).last_target });
+      player_field("gunWeaponTimeout", fields, |player| unsafe { &mut (*player).gun_weapon_timeout });
+      player_field("heavyWeaponTimeout", fields, |player| unsafe { &mut (*player).heavy_weapon_timeout });
+      player_field("specialWeaponTimeout", fields, |player| unsafe { &mut (*player).special_weapon_timeout });
+      player_field("gunWeaponAmmo", fields, |player| unsafe { &mut (*player).gun_weapon_ammo });
+      player_field("heavyWeaponAmmo", fields, |player| unsafe { &mut (*player).heavy_weapon_ammo });
+      player_field("specialWeaponAmmo", fields, |player| unsafe { &mut (*player).special_weapon_ammo });
+      player_field("selectedGunWeapon", fields, |player| unsafe { &mut (*player).selected_gun_weapon });
+      player_field("selectedHeavyWeapon", fields, |player| unsafe { &mut (*player).selected_heavy_weapon });
+      player_field("selectedSpecialWeapon", fields, |player| unsafe { &mut (*player).selected_special_weapon });
+      player_field("playerNumber", fields, |player| unsafe { &mut (*player).player_number });
+      player_field("accelerationX", fields, |player| unsafe { &mut (*player).acceleration_x });
+      player_field("accelerationZ", fields, |player| unsafe { &mut (*player).acceleration_z });
+      player_field("accelerationY", fields, |player| unsafe { &mut (*player).acceleration_y });
+  }
 
-      fields.add_field_method_set("positionX", |_, this, position_x: u32| {
-        unsafe {
-          (*this.player_entity).position_x = position_x;
-        }
+  fn add_methods<'lua, M: mlua::prelude::LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+      methods.add_method("getMaxHealth", |_, this, ()| Ok(unsafe { (*this.get()?).health.max_health }));
 
-        Ok(())
-      });
+      methods.add_method("isValid", |_, this, ()| Ok(this.get().is_ok()));
+  }
+}
 
-      fields.add_field_method_get("positionY", |_, this| {
-        Ok(unsafe {
-          (*this.player_entity).position_y
-        })
-      });
+/// Entity of the game's entity list, e.g., an enemy or a building.
+///
+/// Only valid in the frame it was queried in, since the game frees entities while it runs.
+#[derive(Debug, Clone, Copy)]
+struct Entity {
+  entity: *mut futurecop::Entity,
+  frame: u64,
+}
 
-      fields.add_field_method_set("positionY", |_, this, position_y: u32| {
-        unsafe {
-          (*this.player_entity).position_y = position_y;
-        }
+impl Entity {
+  fn new(entity: *mut futurecop::Entity) -> Self {
+    Entity { entity, frame: debugger::get_state().frame }
+  }
 
-        Ok(())
+  /// Get the entity, failing if it was queried in a previous frame.
+  fn get(&self) -> Result<*mut futurecop::Entity, mlua::Error> {
+    if debugger::get_state().frame != self.frame {
+      return Err(mlua::Error::RuntimeError("the entity is from a previous frame and may not exist anymore, query the entities again".into()));
+    }
+
+    Ok(self.entity)
+  }
+}
+
+/// Number of players the game supports.
+const MAX_PLAYERS: u8 = 2;
+
+/// Get the address of the player's entity, if the player exists.
+fn get_player_address(player: u8) -> Option<u32> {
+  if player >= MAX_PLAYERS {
+    return None;
+  }
+
+  let player_array_item = unsafe { *((PLAYER_ARRAY_ADDR + u32::from(player) * 8) as *const u32) };
+
+  (player_array_item != 0).then_some(player_array_item)
+}
+
+/// Get the player's entity at the address, if the entity is one of the players.
+fn get_player_entity(address: u32) -> Option<*mut futurecop::PlayerEntity> {
+  (0..MAX_PLAYERS)
+    .filter_map(get_player_address)
+    .find(|player_address| *player_address == address)
+    .map(futurecop::PlayerEntity::from_address)
+}
+
+impl UserData for Entity {
+  fn add_fields<'lua, F: mlua::prelude::LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+      fields.add_field_method_get("address", |_, this| Ok(this.get()? as u32));
+
+      fields.add_field_method_get("id", |_, this| {
+        let entity = this.get()?;
+        Ok(unsafe {(*entity).id})
       });
 
-      fields.add_field_method_get("positionZ", |_, this| {
-        Ok(unsafe {
-          (*this.player_entity).position_z
-        })
+      fields.add_field_method_get("behaviorType", |_, this| {
+        let entity = this.get()?;
+        Ok(unsafe {(*entity).behavior_type})
       });
 
-      fields.add_field_method_set("positionZ", |_, this, position_z: u32| {
-        unsafe {
-          (*this.player_entity).position_z = position_z;
-        }
+      fields.add_field_method_get("mapMarker", |_, this| {
+        let entity = this.get()?;
+        Ok(unsafe {(*entity).map_marker})
+      });
 
-        Ok(())
+      fields.add_field_method_get("health", |_, this| {
+        let entity = this.get()?;
+        Ok(get_player_entity(entity as u32).map(|player_entity| unsafe {(*player_entity).health.health}))
       });
 
-      fields.add_field_method_get("idleTimer", |_, this| {
-        Ok(unsafe{(*this.player_entity).idle_timer})
+      fields.add_field_method_get("positionX", |_, this| {
+        let entity = this.get()?;
+        Ok(unsafe {(*entity).position.x})
       });
 
-      fields.add_field_method_set("idleTimer", |_, this, idle_timer: u8| {
-        unsafe{(*this.player_entity).idle_timer = idle_timer};
+      fields.add_field_method_set("positionX", |_, this, position_x: u32| {
+        let entity = this.get()?;
+        unsafe {(*entity).position.x = position_x};
         Ok(())
       });
 
-      fn create_getter_setter<'lua, T, F>(name: &str, fields: &mut F, extractor: fn(*mut futurecop::PlayerEntity) -> *mut T)
-      where
-        F: mlua::prelude::LuaUserDataFields<'lua, PlayerEntity>,
-        T: IntoLua<'lua> + FromLua<'lua> + 'static + Copy,  {
-          fields.add_field_method_get(name, move |_, this| {
-            let field: T;
-            unsafe {field = *extractor(this.player_entity)};
-      
-            Ok(field)
-          });
-      
-          fields.add_field_method_set(name, move |_, this, value: T| {
-            unsafe {
-              *extractor(this.player_entity) = value;
-            }
-      
-            Ok(())
-          });
-      }
+      fields.add_field_method_get("positionY", |_, this| {
+        let entity = this.get()?;
+        Ok(unsafe {(*entity).position.y})
+      });
 
+      fields.add_field_method_set("positionY", |_, this, position_y: u32| {
+        let entity = this.get()?;
+        unsafe {(*entity).position.y = position_y};
+        Ok(())
+      });
 
-      fn get_enemies_killed(player: *mut futurecop::PlayerEntity) -> *mut u16 {
-        unsafe {&mut (*(*player).player).enemies_killed}
-      }
+      fields.add_field_method_get("positionZ", |_, this| {
+        let entity = this.get()?;
+        Ok(unsafe {(*entity).position.z})
+      });
 
-      create_getter_setter::<u16, _>("enemiesKilled", fields, get_enemies_killed);
-      create_getter_setter("deaths", fields, |player| {unsafe {&mut (*(*player).player).deaths}});
-      create_getter_setter("currentAction", fields, |player| {unsafe {&mut (*(*player).player).current_action}});
-      create_getter_setter("movementMode", fields, |player| unsafe {&mut (*(*player).player).movement_mode});
-      create_getter_setter("currentTargetType", fields, |player| unsafe{&mut (*(*player).player).current_target_type});
-      create_getter_setter("currentTarget", fields, |player| unsafe{&mut (*(*player).player).current_target});
-      create_getter_setter("lastTarget", fields, |player| unsafe{&mut (*(*player).player).last_target});
-      create_getter_setter("gunWeaponTimeout", fields, |player| unsafe{&mut (*(*player).player).gun_weapon_timeout});
-      create_getter_setter("heavyWeaponTimeout", fields, |player| unsafe{&mut (*(*player).player).heavy_weapon_ammo});
-      create_getter_setter("specialWeaponTimeout", fields, |player| unsafe{&mut (*(*player).player).special_weapon_timeout});
-      create_getter_setter("gunWeaponAmmo", fields, |player| unsafe{&mut (*(*player).player).gun_weapon_ammo});
-      create_getter_setter("heavyWeaponAmmo", fields, |player| unsafe{&mut (*(*player).player).heavy_weapon_ammo});
-      create_getter_setter("specialWeaponAmmo", fields, |player| unsafe{&mut (*(*player).player).special_weapon_ammo});
-      create_getter_setter("selectedGunWeapon", fields, |player| unsafe{&mut (*(*player).player).selected_gun_weapon});
-      create_getter_setter("selectedHeavyWeapon", fields, |player| unsafe{&mut (*(*player).player).selected_heavy_weapon});
-      create_getter_setter("selectedSpecialWeapon", fields, |player| unsafe{&mut (*(*player).player).selected_special_weapon});
-      create_getter_setter("playerNumber", fields, |player| unsafe{&mut (*(*player).player).player_number});
-      create_getter_setter("accelerationX", fields, |player| unsafe{&mut (*(*player).player).acceleration_x});
-      create_getter_setter("accelerationZ", fields, |player| unsafe{&mut (*(*player).player).acceleration_z});
-      create_getter_setter("accelerationY", fields, |player| unsafe{&mut (*(*player).player).acceleration_y});
+      fields.add_field_method_set("positionZ", |_, this, position_z: u32| {
+        let entity = this.get()?;
+        unsafe {(*entity).position.z = position_z};
+        Ok(())
+      });
   }
 
   fn add_methods<'lua, M: mlua::prelude::LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
-      methods.add_method("getMaxHealth", |_, this, ()| {
-        Ok(unsafe {
-          (*this.player_entity).health.max_health
-        })
-      })
+      methods.add_method("isValid", |_, this, ()| Ok(this.get().is_ok()));
   }
 }
 
+/// Entities of the game's entity list that match the filter.
+fn find_entities(filter: impl Fn(&futurecop::Entity) -> bool) -> Vec<Entity> {
+  futurecop::get_entities()
+    .into_iter()
+    .filter(|entity| filter(unsafe {&**entity}))
+    .map(Entity::new)
+    .collect()
+}
 
 impl GameState {
   pub fn new() -> Self {
@@ -186,37 +292,147 @@ impl GameState {
   }
 }
 
-pub fn create_game_library(lua: Arc<Lua>) -> Result<OwnedTable, mlua::Error> {
-  let functions = lua.create_table()?;
+/// Create the game library. `fn` is only available with the `hook` permission.
+pub fn create_game_library(lua: Arc<Lua>, permissions: &[PluginPermission]) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Game, "Read and modify the game's state, such as the players.")?;
 
   let get_game_state = lua.create_function(|lua, ()| {
     let state = GameState::new();
 
     Ok(lua.to_value(&state))
   })?;
-  functions.set("getState", get_game_state)?;
+  library.function(
+    "getState",
+    get_game_state,
+    FunctionDocs::new("Get the game's current state.").returns("{isInMission: boolean, gameMode: \"CrimeWar\" | \"PrecinctAssault\", playerCount: number}"),
+  )?;
 
   let get_player = lua.create_function(|_, player: u8| {
     debug!("Getting player {}", player);
 
-    if player > 1 {
+    if player >= MAX_PLAYERS {
       return Err(mlua::Error::RuntimeError("Can only get player one or two at the moment.".into()))
     }
 
-    let player_array_item: u32;
-    unsafe {
-      player_array_item = *((PLAYER_ARRAY_ADDR + Into::<u32>::into(player) * 8) as *const u32);
-    }
-
-    if player_array_item == 0 {
-      return Err(mlua::Error::RuntimeError("Player doesn't exist".into()));
+    match get_player_address(player) {
+      Some(address) => Ok(PlayerEntity { player, player_entity: futurecop::PlayerEntity::from_address(address) }),
+      None => Err(mlua::Error::RuntimeError("Player doesn't exist".into())),
     }
+  })?;
+  library.function(
+    "getPlayer",
+    get_player,
+    FunctionDocs::new("Get player one (`0`) or player two (`1`). Errors if the player doesn't exist.").param("player", "number").returns("Player"),
+  )?;
+
+  let get_players = lua.create_function(|_, ()| {
+    Ok(
+      (0..MAX_PLAYERS)
+        .filter_map(|player| get_player_address(player).map(|address| PlayerEntity { player, player_entity: futurecop::PlayerEntity::from_address(address) }))
+        .collect::<Vec<_>>()
+    )
+  })?;
+  library.function(
+    "getPlayers",
+    get_players,
+    FunctionDocs::new("Get all players that exist, player one first.").returns("{Player}"),
+  )?;
+
+  let entities_fn = lua.create_function(|_, ()| Ok(find_entities(|_| true)))?;
+  library.function(
+    "entities",
+    entities_fn,
+    FunctionDocs::new("Get all entities of the game's entity list, in the list's order. Entities are only valid in the current frame.").returns("{Entity}"),
+  )?;
+
+  let find_by_behavior_fn = lua.create_function(|_, behavior_type: u16| {
+    Ok(find_entities(|entity| entity.behavior_type == behavior_type))
+  })?;
+  library.function(
+    "findEntitiesByBehavior",
+    find_by_behavior_fn,
+    FunctionDocs::new("Get the entities with the behavior type, e.g., `0xa0`.")
+      .param("behaviorType", "number")
+      .returns("{Entity}"),
+  )?;
+
+  let find_by_id_fn = lua.create_function(|_, id: u32| {
+    Ok(find_entities(|entity| entity.id == id).into_iter().next())
+  })?;
+  library.function(
+    "findEntityById",
+    find_by_id_fn,
+    FunctionDocs::new("Get the entity with the id. Returns `nil` if no entity has the id.")
+      .param("id", "number")
+      .returns("Entity?"),
+  )?;
+
+  let game_fn = lua.create_function(|_, name: String| {
+    let function = get_game_function(&name).ok_or_else(|| mlua::Error::RuntimeError(format!("unknown game function '{}'", name)))?;
+
+    Ok(NativeFunction::new(function.resolved_address(), function.arg_types.to_vec(), function.return_type, function.convention))
+  })?;
+  let known_functions: Vec<String> = GAME_FUNCTIONS.iter().map(|function| format!("`{}`", function.name)).collect();
+  library.function(
+    "fn",
+    game_fn,
+    FunctionDocs::new(&format!("Get a known function of the game by its name, typed with the function's signature. Errors if the function is unknown. Known functions: {}.", known_functions.join(", ")))
+      .param("name", "string")
+      .returns("NativeFunction"),
+  )?;
+  library.require_permissions("fn", &[PluginPermission::Hook], permissions)?;
+
+  library.document_type(player_docs());
+  library.document_type(entity_docs());
+
+  Ok(library.build())
+}
 
-    let player_entity = futurecop::PlayerEntity::from_address(player_array_item);
 
-    Ok(PlayerEntity {player_entity})
-  })?;
-  functions.set("getPlayer", get_player)?;
+/// Documentation of the fields and methods of [`PlayerEntity`].
+fn player_docs() -> TypeDocs {
+  TypeDocs::new("Player", "A player's entity. Changes to its fields are directly written into the game's memory. Accessing it after the player was destroyed, e.g., because the mission ended, errors. Values that don't fit into a field error.")
+    .field("index", "number", "Index of the player, `0` for player one and `1` for player two. Read-only.")
+    .field("health", "number", "Current health, between 0 and the maximum health.")
+    .field("maxHealth", "number", "Maximum health. Read-only.")
+    .field("positionX", "number", "Position on the x-axis.")
+    .field("positionY", "number", "Position on the y-axis.")
+    .field("positionZ", "number", "Position on the z-axis.")
+    .field("idleTimer", "number", "Time until the idle animation plays.")
+    .field("enemiesKilled", "number", "Number of killed enemies.")
+    .field("deaths", "number", "Number of deaths.")
+    .field("currentAction", "number", "Action the player currently performs.")
+    .field("movementMode", "number", "Current movement mode.")
+    .field("currentTargetType", "number", "Type of the current target.")
+    .field("currentTarget", "number", "Current target.")
+    .field("lastTarget", "number", "Last target.")
+    .field("gunWeaponTimeout", "number", "Timeout until the gun weapon can be fired again.")
+    .field("heavyWeaponTimeout", "number", "Timeout until the heavy weapon can be fired again.")
+    .field("specialWeaponTimeout", "number", "Timeout until the special weapon can be fired again.")
+    .field("gunWeaponAmmo", "number", "Ammunition of the gun weapon.")
+    .field("heavyWeaponAmmo", "number", "Ammunition of the heavy weapon.")
+    .field("specialWeaponAmmo", "number", "Ammunition of the special weapon.")
+    .field("selectedGunWeapon", "number", "Selected gun weapon.")
+    .field("selectedHeavyWeapon", "number", "Selected heavy weapon.")
+    .field("selectedSpecialWeapon", "number", "Selected special weapon.")
+    .field("playerNumber", "number", "Number of the player.")
+    .field("accelerationX", "number", "Acceleration on the x-axis.")
+    .field("accelerationZ", "number", "Acceleration on the z-axis.")
+    .field("accelerationY", "number", "Acceleration on the y-axis.")
+    .method("getMaxHealth", FunctionDocs::new("Get the player's maximum health.").returns("number"))
+    .method("isValid", FunctionDocs::new("Whether the player still exists and can be accessed.").returns("boolean"))
+}
 
-  Ok(functions.into_owned())
-}
\ No newline at end of file
+/// Documentation of the fields and methods of [`Entity`].
+fn entity_docs() -> TypeDocs {
+  TypeDocs::new("Entity", "Entity of the game's entity list. Only valid in the frame it was queried in, accessing it in a later frame errors. Changes to its position are directly written into the game's memory.")
+    .field("address", "number", "Address of the entity, e.g., to cast it with the `structs` library.")
+    .field("id", "number", "Id of the entity.")
+    .field("behaviorType", "number", "Type of the entity's behavior.")
+    .field("mapMarker", "number", "Marker of the entity on the map.")
+    .field("health", "number?", "Current health, only available for the players' entities.")
+    .field("positionX", "number", "Position on the x-axis.")
+    .field("positionY", "number", "Position on the y-axis.")
+    .field("positionZ", "number", "Position on the z-axis.")
+    .method("isValid", FunctionDocs::new("Whether the entity was queried in the current frame and can be accessed.").returns("boolean"))
+}
@@ -0,0 +1,116 @@
+use std::{collections::HashMap, sync::Arc};
+
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::PluginDependency};
+use mlua::{Function, Lua, LuaSerdeExt, SerializeOptions, Table, Value};
+
+use super::{Library, LibraryBuilder};
+
+/// Name of the registry value that holds the plugin's routes and snapshot.
+///
+/// Routes are stored per plugin and not per library table,
+/// since every file the plugin requires gets its own instance of the library.
+fn registry_key(plugin_name: &str) -> String {
+  format!("futuremod.http.{}", plugin_name)
+}
+
+/// Get the table with the plugin's routes and snapshot, creating it if it doesn't exist.
+fn get_state<'lua>(lua: &'lua Lua, plugin_name: &str) -> Result<Table<'lua>, mlua::Error> {
+  let key = registry_key(plugin_name);
+
+  if let Some(state) = lua.named_registry_value::<Option<Table>>(&key)? {
+    return Ok(state);
+  }
+
+  let state = lua.create_table()?;
+  state.set("routes", lua.create_table()?)?;
+  lua.set_named_registry_value(&key, state.clone())?;
+
+  Ok(state)
+}
+
+/// Copy a value by converting it to JSON and back.
+///
+/// Only data can be copied, i.e., no functions or userdata.
+/// `nil` stays `nil` instead of becoming the `null` placeholder.
+fn deep_copy<'lua>(lua: &'lua Lua, value: Value<'lua>) -> Result<Value<'lua>, mlua::Error> {
+  let json: serde_json::Value = lua.from_value(value)?;
+
+  lua.to_value_with(&json, SerializeOptions::new().serialize_unit_to_null(false))
+}
+
+/// Normalize a route's path such that `/stats/`, `stats`, and `/stats` are the same route.
+fn normalize_path(path: &str) -> String {
+  path.trim_matches('/').to_string()
+}
+
+pub fn create_http_library(lua: Arc<Lua>, plugin_name: &str) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Http, "Expose the plugin's data to external tools with read-only HTTP routes served by the mod under `/plugin/<name>/api/`.")?;
+
+  let route_plugin_name = plugin_name.to_string();
+  let route_fn = lua.create_function(move |lua, (path, handler): (String, Function)| {
+    let routes: Table = get_state(lua, &route_plugin_name)?.get("routes")?;
+
+    routes.set(normalize_path(&path), handler)
+  })?;
+  library.function(
+    "route",
+    route_fn,
+    FunctionDocs::new("Register a handler for GET requests to the path. The handler is called off the game thread with the request and the last snapshot and must not access the game. Its return value is sent as JSON.")
+      .param("path", "string")
+      .param("handler", "(request: Request, snapshot: any) -> any"),
+  )?;
+
+  let snapshot_plugin_name = plugin_name.to_string();
+  let set_snapshot_fn = lua.create_function(move |lua, value: Value| {
+    let snapshot = deep_copy(lua, value)?;
+
+    get_state(lua, &snapshot_plugin_name)?.set("snapshot", snapshot)
+  })?;
+  library.function(
+    "setSnapshot",
+    set_snapshot_fn,
+    FunctionDocs::new("Store a copy of the data passed to route handlers. Call it from the game thread, e.g., in `onUpdate`, whenever the data changes.")
+      .param("value", "any"),
+  )?;
+
+  library.document_type(
+    TypeDocs::new("Request", "Request passed to route handlers.")
+      .field("path", "string", "Path of the route without leading or trailing slashes.")
+      .field("query", "{[string]: string}", "Query parameters of the request.")
+  );
+
+  Ok(library.build())
+}
+
+/// Call the plugin's handler for the route and convert its result to JSON.
+///
+/// Returns `None` if the plugin didn't register a handler for the route.
+/// The handler gets a copy of the snapshot, so it cannot change the snapshot for later requests.
+pub fn handle_request(lua: &Lua, plugin_name: &str, path: &str, query: HashMap<String, String>) -> Result<Option<serde_json::Value>, mlua::Error> {
+  let path = normalize_path(path);
+
+  let state = match lua.named_registry_value::<Option<Table>>(&registry_key(plugin_name))? {
+    Some(state) => state,
+    None => return Ok(None),
+  };
+
+  let routes: Table = state.get("routes")?;
+  let handler = match routes.get::<_, Option<Function>>(path.as_str())? {
+    Some(handler) => handler,
+    None => return Ok(None),
+  };
+
+  let request = lua.create_table()?;
+  request.set("path", path)?;
+  request.set("query", query)?;
+
+  let snapshot = deep_copy(lua, state.get("snapshot")?)?;
+  let response: Value = handler.call((request, snapshot))?;
+
+  Ok(Some(lua.from_value(response)?))
+}
+
+/// Remove the plugin's routes and snapshot, e.g., when the plugin is unloaded.
+pub fn clear_routes(lua: &Lua, plugin_name: &str) -> Result<(), mlua::Error> {
+  lua.unset_named_registry_value(&registry_key(plugin_name))
+}
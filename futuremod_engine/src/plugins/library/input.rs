@@ -2,14 +2,17 @@ use std::{str::FromStr, sync::Arc};
 
 use device_query::Keycode;
 use log::*;
-use mlua::{Lua, OwnedTable};
+use futuremod_data::{docs::FunctionDocs, plugin::PluginDependency};
+use mlua::{Function, Lua, Table};
 
-use crate::input::KeyState;
+use crate::{injection, input::KeyState, key_bindings, remapping::start_keyboard_hook};
+
+use super::{Library, LibraryBuilder};
 
 
 /// List of supported key codes.
 /// Copied from [`device_query::Keycode`]
-const SUPPORTED_KEYCODES: [Keycode; 100] = [
+pub(crate) const SUPPORTED_KEYCODES: [Keycode; 100] = [
   Keycode::Key0,
   Keycode::Key1,
   Keycode::Key2,
@@ -113,30 +116,51 @@ const SUPPORTED_KEYCODES: [Keycode; 100] = [
 ];
 
 
-fn keycode_to_string(keycode: Keycode) -> String {
+pub(crate) fn keycode_to_string(keycode: Keycode) -> String {
   format!("Key{}", keycode.to_string())
 }
 
 
-fn keycode_from_string(key: String) -> Result<Keycode, mlua::Error> {
+pub(crate) fn keycode_from_string(key: String) -> Result<Keycode, mlua::Error> {
   let code_name = key.replace("Key", "");
 
   Keycode::from_str(&code_name).map_err(|_| mlua::Error::RuntimeError("Invalid key code".into()))
 }
 
 
-fn insert_keycode(table: &mlua::Table, code: Keycode) -> Result<(), mlua::Error> {
+fn insert_keycode(library: &mut LibraryBuilder, code: Keycode) -> Result<(), mlua::Error> {
   let code = keycode_to_string(code);
-  table.set(code.clone(), code)
+  library.constant(&code, code.clone(), "string", "Key code for `isKeyPressed`.")
 }
 
 
-pub fn create_input_library(lua: Arc<Lua>) -> Result<OwnedTable, mlua::Error> {
-  let library = lua.create_table()?;
+/// Name of the registry value that holds the plugin's key callbacks by key or binding.
+///
+/// Like split callbacks, callbacks are stored per plugin, since every file the plugin requires gets its own instance of the library.
+fn registry_key(plugin_name: &str) -> String {
+  format!("futuremod.input.{}", plugin_name)
+}
+
+/// Get the table of the plugin's key callbacks, creating it if it doesn't exist.
+fn get_callbacks<'lua>(lua: &'lua Lua, plugin_name: &str) -> Result<Table<'lua>, mlua::Error> {
+  let key = registry_key(plugin_name);
+
+  if let Some(callbacks) = lua.named_registry_value::<Option<Table>>(&key)? {
+    return Ok(callbacks);
+  }
+
+  let callbacks = lua.create_table()?;
+  lua.set_named_registry_value(&key, callbacks.clone())?;
+
+  Ok(callbacks)
+}
+
+pub fn create_input_library(lua: Arc<Lua>, plugin_name: &str) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Input, "Query the keyboard's state, react to key presses, and inject input into the frame's key state. Keys can be bound to named bindings that users can change in the GUI.")?;
 
   // Insert supported key codes into library table.
   for key in SUPPORTED_KEYCODES {
-    insert_keycode(&library, key)?;
+    insert_keycode(&mut library, key)?;
   }
 
   let key_state = KeyState::new();
@@ -153,7 +177,132 @@ pub fn create_input_library(lua: Arc<Lua>) -> Result<OwnedTable, mlua::Error> {
       }
     }
   })?;
-  library.set("isKeyPressed", is_key_pressed_function)?;
+  library.function(
+    "isKeyPressed",
+    is_key_pressed_function,
+    FunctionDocs::new("Check whether the key is pressed in the current frame.").param("key", "string").returns("boolean"),
+  )?;
+
+  let on_key_down_plugin_name = plugin_name.to_string();
+  let on_key_down_fn = lua.create_function(move |lua, (target, callback): (String, Function)| {
+    // Bindings are resolved when a key is pressed, so changes of the user apply immediately
+    let target = match key_bindings::get_key(&on_key_down_plugin_name, &target) {
+      Some(_) => target,
+      None => key_bindings::canonical_key(&target).map_err(|e| mlua::Error::RuntimeError(format!("{} and not a registered binding", e)))?,
+    };
+
+    start_keyboard_hook();
+
+    let callbacks = get_callbacks(lua, &on_key_down_plugin_name)?;
+    let target_callbacks = match callbacks.get::<_, Option<Table>>(target.as_str())? {
+      Some(target_callbacks) => target_callbacks,
+      None => {
+        let target_callbacks = lua.create_table()?;
+        callbacks.set(target.as_str(), target_callbacks.clone())?;
+        target_callbacks
+      },
+    };
+
+    target_callbacks.push(callback)
+  })?;
+  library.function(
+    "onKeyDown",
+    on_key_down_fn,
+    FunctionDocs::new("Register a function that is called with the key's name at the start of the next frame after the key was pressed while the game had the focus. Accepts a key, e.g., `F5`, or the name of a registered binding.")
+      .param("keyOrBinding", "string")
+      .param("callback", "(key: string) -> ()"),
+  )?;
+
+  let register_binding_plugin_name = plugin_name.to_string();
+  let register_binding_fn = lua.create_function(move |_, (name, default_key): (String, String)| {
+    start_keyboard_hook();
+
+    key_bindings::register(&register_binding_plugin_name, &name, &default_key).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+  })?;
+  library.function(
+    "registerBinding",
+    register_binding_fn,
+    FunctionDocs::new("Register a named key binding with its default key. Users can bind another key in the GUI. Returns the key the binding uses.")
+      .param("name", "string")
+      .param("defaultKey", "string")
+      .returns("string"),
+  )?;
+
+  let get_binding_plugin_name = plugin_name.to_string();
+  let get_binding_fn = lua.create_function(move |_, name: String| Ok(key_bindings::get_key(&get_binding_plugin_name, &name)))?;
+  library.function(
+    "getBinding",
+    get_binding_fn,
+    FunctionDocs::new("Get the key the binding uses. Returns `nil` if the binding isn't registered.")
+      .param("name", "string")
+      .returns("string?"),
+  )?;
+
+  let send_key_plugin_name = plugin_name.to_string();
+  let send_key_fn = lua.create_function(move |_, (key, down_frames): (String, Option<u32>)| {
+    let keycode = keycode_from_string(key)?;
+    injection::send_key(&send_key_plugin_name, keycode, down_frames.unwrap_or(1));
+
+    Ok(())
+  })?;
+  library.function(
+    "sendKey",
+    send_key_fn,
+    FunctionDocs::new("Hold the key down for the number of frames, by default `1`, starting with the next frame. The key is added to the mod's key state of the frame, it isn't sent to the OS and the game doesn't see it.")
+      .param("key", "string")
+      .param("downFrames", "number?"),
+  )?;
+
+  let set_axis_plugin_name = plugin_name.to_string();
+  let set_axis_fn = lua.create_function(move |_, (negative_key, positive_key, value): (String, String, f32)| {
+    let negative = keycode_from_string(negative_key)?;
+    let positive = keycode_from_string(positive_key)?;
+    injection::set_axis(&set_axis_plugin_name, negative, positive, value);
+
+    Ok(())
+  })?;
+  library.function(
+    "setAxis",
+    set_axis_fn,
+    FunctionDocs::new("Set the axis between two keys from `-1` to `1`, starting with the next frame. Holds the negative key at `-0.5` or less and the positive key at `0.5` or more, like a controller's stick. The axis keeps its value until it is set again, `0` releases it.")
+      .param("negativeKey", "string")
+      .param("positiveKey", "string")
+      .param("value", "number"),
+  )?;
+
+  Ok(library.build())
+}
+
+/// Call the plugin's callbacks of the key and of the bindings that use the key.
+pub fn dispatch_key_down(lua: &Lua, plugin_name: &str, key: &str) -> Result<(), mlua::Error> {
+  let callbacks = match lua.named_registry_value::<Option<Table>>(&registry_key(plugin_name))? {
+    Some(callbacks) => callbacks,
+    None => return Ok(()),
+  };
+
+  // Collect the callbacks first, as callbacks may register further callbacks
+  let mut matching: Vec<Function> = Vec::new();
+  for pair in callbacks.pairs::<String, Table>() {
+    let (target, target_callbacks) = pair?;
+
+    let target_key = key_bindings::get_key(plugin_name, &target).unwrap_or(target);
+    if target_key != key {
+      continue;
+    }
+
+    for callback in target_callbacks.sequence_values::<Function>() {
+      matching.push(callback?);
+    }
+  }
+
+  for callback in matching {
+    callback.call::<_, ()>(key)?;
+  }
+
+  Ok(())
+}
 
-  Ok(library.into_owned())
+/// Remove the plugin's key callbacks, e.g., when the plugin is unloaded.
+pub fn clear_callbacks(lua: &Lua, plugin_name: &str) -> Result<(), mlua::Error> {
+  lua.unset_named_registry_value(&registry_key(plugin_name))
 }
\ No newline at end of file
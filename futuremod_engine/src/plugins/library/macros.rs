@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use futuremod_data::{docs::FunctionDocs, plugin::PluginDependency};
+use mlua::{Lua, Value};
+
+use crate::{macros::{self, Step}, remapping::parse_key};
+
+use super::{Library, LibraryBuilder};
+
+pub fn create_macro_library(lua: Arc<Lua>, plugin_name: &str) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Macro, "Queue timed sequences of synthesized key presses, e.g., for accessibility tools or recording demos. The game receives them like the user's key presses. The macro pauses while the game isn't focused.")?;
+
+  let press_plugin_name = plugin_name.to_string();
+  let press_fn = lua.create_function(move |_, (keys, frames): (Value, Option<u32>)| {
+    let names: Vec<String> = match keys {
+      Value::String(key) => vec![key.to_str()?.to_string()],
+      Value::Table(keys) => keys.sequence_values::<String>().collect::<Result<_, _>>()?,
+      _ => return Err(mlua::Error::RuntimeError("keys must be a key or a list of keys".to_string())),
+    };
+
+    let keys = names
+      .iter()
+      .map(|name| parse_key(name))
+      .collect::<Result<Vec<_>, _>>()
+      .map_err(|e| mlua::Error::RuntimeError(e.to_string()))?;
+
+    macros::push(&press_plugin_name, Step::Press(keys, frames.unwrap_or(1)));
+
+    Ok(())
+  })?;
+  library.function(
+    "press",
+    press_fn,
+    FunctionDocs::new("Queue holding the keys down together for the number of frames, by default `1`. Keys use the names of the `input` library. Add a wait between two presses of the same key, so the game sees it released.")
+      .param("keys", "string | {string}")
+      .param("frames", "number?"),
+  )?;
+
+  let wait_plugin_name = plugin_name.to_string();
+  let wait_fn = lua.create_function(move |_, frames: u32| {
+    macros::push(&wait_plugin_name, Step::Wait(frames));
+
+    Ok(())
+  })?;
+  library.function(
+    "wait",
+    wait_fn,
+    FunctionDocs::new("Queue waiting for the number of frames before the next step.").param("frames", "number"),
+  )?;
+
+  let cancel_plugin_name = plugin_name.to_string();
+  let cancel_fn = lua.create_function(move |_, ()| {
+    macros::cancel(&cancel_plugin_name);
+
+    Ok(())
+  })?;
+  library.function(
+    "cancel",
+    cancel_fn,
+    FunctionDocs::new("Remove all queued steps and release the held keys."),
+  )?;
+
+  let is_running_plugin_name = plugin_name.to_string();
+  let is_running_fn = lua.create_function(move |_, ()| Ok(macros::is_running(&is_running_plugin_name)))?;
+  library.function(
+    "isRunning",
+    is_running_fn,
+    FunctionDocs::new("Whether steps of the macro are left.").returns("boolean"),
+  )?;
+
+  Ok(library.build())
+}
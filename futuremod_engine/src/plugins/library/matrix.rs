@@ -1,32 +1,65 @@
 use std::{cell::Ref, fmt, marker::PhantomData, mem::size_of, ops::{AddAssign, MulAssign}, sync::{Arc, Mutex}};
 
 use log::info;
-use mlua::{AnyUserData, FromLua, IntoLua, Lua, MetaMethod, OwnedTable, UserData, UserDataMethods};
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::PluginDependency};
+use mlua::{AnyUserData, FromLua, IntoLua, Lua, MetaMethod, UserData, UserDataMethods};
 use nalgebra::{DMatrix, Matrix4, Scalar, Vector3};
 use num::{traits::{FromBytes, ToBytes}, Num, One, Zero};
 
-use super::LuaResult;
+use super::{Library, LibraryBuilder, LuaResult};
 
-pub fn create_matrix_library(lua: Arc<Lua>) -> Result<OwnedTable, mlua::Error> {
-  let table = lua.create_table()?;
+pub fn create_matrix_library(lua: Arc<Lua>) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Matrix, "Dynamically sized matrices and 4x4 model matrices for 3D transformations.")?;
 
   // Float-based dynamic matrix
-  table.set("FloatMatrixType", lua.create_proxy::<MatrixType<i32>>()?)?;
-  table.set("zerosFloat", lua.create_function(create_zero_matrix::<f32>)?)?;
-  table.set("identityFloat", lua.create_function(create_identity_matrix::<f32>)?)?;
-  table.set("newFloat", lua.create_function(create_matrix::<f32>)?)?;
+  library.constant("FloatMatrixType", lua.create_proxy::<MatrixType<i32>>()?, "MatrixType", "Type of float matrices, can be used as a complex type in the dangerous library.")?;
+  library.function("zerosFloat", lua.create_function(create_zero_matrix::<f32>)?, FunctionDocs::new("Create a float matrix filled with zeros.").param("rows", "number").param("columns", "number").returns("Matrix<number>"))?;
+  library.function("identityFloat", lua.create_function(create_identity_matrix::<f32>)?, FunctionDocs::new("Create a square float identity matrix.").param("size", "number").returns("Matrix<number>"))?;
+  library.function("newFloat", lua.create_function(create_matrix::<f32>)?, FunctionDocs::new("Create a float matrix from a table of rows.").param("data", "{{number}}").returns("Matrix<number>"))?;
 
   // Integer-based dynamic matrix
-  table.set("IntMatrixType", lua.create_proxy::<MatrixType<i32>>()?)?;
-  table.set("zerosInt", lua.create_function(create_zero_matrix::<i32>)?)?;
-  table.set("identityInt", lua.create_function(create_identity_matrix::<i32>)?)?;
-  table.set("newInt", lua.create_function(create_matrix::<i32>)?)?;
+  library.constant("IntMatrixType", lua.create_proxy::<MatrixType<i32>>()?, "MatrixType", "Type of integer matrices, can be used as a complex type in the dangerous library.")?;
+  library.function("zerosInt", lua.create_function(create_zero_matrix::<i32>)?, FunctionDocs::new("Create an integer matrix filled with zeros.").param("rows", "number").param("columns", "number").returns("Matrix<integer>"))?;
+  library.function("identityInt", lua.create_function(create_identity_matrix::<i32>)?, FunctionDocs::new("Create a square integer identity matrix.").param("size", "number").returns("Matrix<integer>"))?;
+  library.function("newInt", lua.create_function(create_matrix::<i32>)?, FunctionDocs::new("Create an integer matrix from a table of rows.").param("data", "{{integer}}").returns("Matrix<integer>"))?;
 
   // Model matrix
-  table.set("ModelMatrix", lua.create_proxy::<ModelMatrix>()?)?;
-  table.set("newModel", lua.create_function(create_model_matrix)?)?;
-
-  Ok(table.into_owned())
+  library.constant("ModelMatrix", lua.create_proxy::<ModelMatrix>()?, "ModelMatrix", "Type of model matrices, can be used as a complex type in the dangerous library.")?;
+  library.function("newModel", lua.create_function(create_model_matrix)?, FunctionDocs::new("Create a model matrix that is initialized as the identity matrix.").returns("ModelMatrix"))?;
+
+  library.document_type(
+    TypeDocs::new("Matrix<T>", "Dynamically sized matrix. Supports `+` and `*` with other matrices of the same type.")
+      .field("nrows", "number", "Number of rows.")
+      .field("ncols", "number", "Number of columns.")
+      .method("at", FunctionDocs::new("Get the value at the given row and column.").param("row", "number").param("col", "number").returns("T"))
+      .method("set", FunctionDocs::new("Set the value at the given row and column.").param("row", "number").param("col", "number").param("value", "T"))
+      .method("toBytes", FunctionDocs::new("Convert the matrix into its native byte representation.").returns("{number}"))
+  );
+
+  library.document_type(
+    TypeDocs::new("MatrixType", "Type of dynamically sized matrices. Converts matrices between their lua and native representation.")
+      .method("new", FunctionDocs::new("Create a zero-initialized matrix type with a fixed size, e.g., for native struct fields.").param("rows", "number").param("columns", "number").returns("MatrixType"))
+      .method("getByteSize", FunctionDocs::new("Get the size of the native representation in bytes.").returns("number"))
+      .method("toBytes", FunctionDocs::new("Convert the matrix into its native byte representation.").param("matrix", "Matrix<T>").returns("{number}"))
+      .method("fromBytes", FunctionDocs::new("Create a matrix from its native byte representation.").param("bytes", "{number}").returns("Matrix<T>"))
+  );
+
+  library.document_type(
+    TypeDocs::new("ModelMatrix", "4x4 float matrix used by the game to position 3D objects. Supports `+` and `*` with other model matrices.")
+      .field("nrows", "number", "Number of rows.")
+      .field("ncols", "number", "Number of columns.")
+      .method("at", FunctionDocs::new("Get the value at the given row and column.").param("row", "number").param("col", "number").returns("number"))
+      .method("set", FunctionDocs::new("Set the value at the given row and column.").param("row", "number").param("col", "number").param("value", "number"))
+      .method("translate", FunctionDocs::new("Translate the matrix.").param("x", "number").param("y", "number").param("z", "number"))
+      .method("scale", FunctionDocs::new("Scale the matrix along every axis.").param("x", "number").param("y", "number").param("z", "number"))
+      .method("uniformScale", FunctionDocs::new("Scale the matrix uniformly.").param("scaling", "number"))
+      .method("rotate", FunctionDocs::new("Rotate the matrix around the given axis.").param("x", "number").param("y", "number").param("z", "number").param("angle", "number"))
+      .method("getByteSize", FunctionDocs::new("Get the size of the native representation in bytes.").returns("number"))
+      .method("toBytes", FunctionDocs::new("Convert the matrix into the game's native representation.").param("matrix", "ModelMatrix").returns("{number}"))
+      .method("fromBytes", FunctionDocs::new("Create a model matrix from the game's native representation.").param("bytes", "{number}").returns("ModelMatrix"))
+  );
+
+  Ok(library.build())
 }
 
 
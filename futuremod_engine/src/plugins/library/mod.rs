@@ -1,8 +1,144 @@
+use std::{path::Path, sync::Arc};
+
+use futuremod_data::{docs::{FunctionDocs, LibraryDocs, TypeDocs, ValueDocs}, plugin::{PluginDependency, PluginInfo, PluginPermission}};
+use mlua::{IntoLua, Lua, OwnedTable};
+
 pub mod dangerous;
+pub mod events;
 pub mod game;
+pub mod http;
 pub mod input;
 pub mod ui;
+pub mod storage;
+pub mod captions;
+pub mod config;
 pub mod system;
 pub mod matrix;
+pub mod multiplayer;
+pub mod net;
+pub mod macros;
+pub mod presence;
+pub mod replay;
+pub mod time;
+pub mod timer;
+pub mod structs;
+pub mod sound;
+
+type LuaResult<T> = Result<T, mlua::Error>;
+
+/// A library's table together with its documentation.
+pub struct Library {
+  pub table: OwnedTable,
+  pub docs: LibraryDocs,
+}
+
+/// Creates a library's table and documents everything added to it.
+///
+/// Functions and constants can only be added together with their documentation.
+/// Thus, the API reference is derived from the same code that registers the library and cannot drift from it.
+pub struct LibraryBuilder<'lua> {
+  table: mlua::Table<'lua>,
+  docs: LibraryDocs,
+}
+
+impl<'lua> LibraryBuilder<'lua> {
+  pub fn new(lua: &'lua Lua, dependency: PluginDependency, description: &str) -> LuaResult<Self> {
+    Ok(LibraryBuilder {
+      table: lua.create_table()?,
+      docs: LibraryDocs::new(dependency, description),
+    })
+  }
+
+  /// Add a function.
+  pub fn function(&mut self, name: &str, function: mlua::Function<'lua>, docs: FunctionDocs) -> LuaResult<()> {
+    self.table.set(name, function)?;
+    self.docs.functions.push(FunctionDocs { name: name.to_string(), ..docs });
+
+    Ok(())
+  }
+
+  /// Add a constant value.
+  pub fn constant<V: IntoLua<'lua>>(&mut self, name: &str, value: V, type_name: &str, description: &str) -> LuaResult<()> {
+    self.table.set(name, value)?;
+    self.docs.constants.push(ValueDocs::new(name, type_name, description));
+
+    Ok(())
+  }
+
+  /// Guard the function with permissions.
+  ///
+  /// If all of them are granted, its documentation mentions them. Otherwise, the function is removed.
+  pub fn require_permissions(&mut self, name: &str, required: &[PluginPermission], granted: &[PluginPermission]) -> LuaResult<()> {
+    if required.iter().all(|permission| granted.contains(permission)) {
+      let required: Vec<String> = required.iter().map(|permission| format!("`{}`", permission)).collect();
+
+      if let Some(docs) = self.docs.functions.iter_mut().find(|docs| docs.name == name) {
+        docs.description = format!("{} Requires {}.", docs.description, required.join(", "));
+      }
+
+      return Ok(());
+    }
+
+    self.table.raw_remove(name)?;
+    self.docs.functions.retain(|docs| docs.name != name);
+
+    Ok(())
+  }
+
+  /// Document a type returned by the library's functions.
+  pub fn document_type(&mut self, docs: TypeDocs) {
+    self.docs.types.push(docs);
+  }
+
+  pub fn build(self) -> Library {
+    Library {
+      table: self.table.into_owned(),
+      docs: self.docs,
+    }
+  }
+}
+
+/// Create the API reference of all libraries.
+///
+/// Every library is created in a separate lua state only to collect its documentation.
+pub fn create_api_docs() -> LuaResult<Vec<LibraryDocs>> {
+  let lua = Arc::new(Lua::new());
+
+  let mut docs = vec![
+    game::create_game_library(lua.clone(), &[PluginPermission::Hook])?.docs,
+    input::create_input_library(lua.clone(), "")?.docs,
+    ui::create_ui_library(lua.clone(), "")?.docs,
+    system::create_system_library(lua.clone(), &PluginInfo::default(), &[PluginPermission::Exec])?.docs,
+    matrix::create_matrix_library(lua.clone())?.docs,
+    http::create_http_library(lua.clone(), "")?.docs,
+    replay::create_replay_library(lua.clone())?.docs,
+    timer::create_timer_library(lua.clone(), "")?.docs,
+    presence::create_presence_library(lua.clone(), "")?.docs,
+    multiplayer::create_multiplayer_library(lua.clone(), "")?.docs,
+    macros::create_macro_library(lua.clone(), "")?.docs,
+    storage::create_storage_library(lua.clone(), "", Path::new(""))?.docs,
+    captions::create_captions_library(lua.clone(), "")?.docs,
+    config::create_config_library(lua.clone(), &PluginInfo::default())?.docs,
+    dangerous::create_dangerous_library(lua.clone(), "", PluginDependency::Dangerous.permissions())?.docs,
+    structs::create_structs_library(lua.clone())?.docs,
+    net::create_net_library(lua.clone(), &PluginInfo::default())?.docs,
+    time::create_time_library(lua.clone(), "")?.docs,
+    events::create_events_library(lua.clone(), "")?.docs,
+    sound::create_sound_library(lua.clone(), "", Path::new(""))?.docs,
+  ];
+
+  // Standard libraries are provided by luau
+  let standard_libraries = [
+    (PluginDependency::Math, "https://luau-lang.org/library#math-library"),
+    (PluginDependency::Table, "https://luau-lang.org/library#table-library"),
+    (PluginDependency::String, "https://luau-lang.org/library#string-library"),
+    (PluginDependency::Bit32, "https://luau-lang.org/library#bit32-library"),
+    (PluginDependency::Utf8, "https://luau-lang.org/library#utf8-library"),
+  ];
+
+  for (dependency, link) in standard_libraries {
+    docs.push(LibraryDocs::new(dependency, &format!("Luau standard library, see <{}>.", link)));
+  }
 
-type LuaResult<T> = Result<T, mlua::Error>;
\ No newline at end of file
+  Ok(docs)
+}
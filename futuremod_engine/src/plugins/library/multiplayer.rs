@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::PluginDependency};
+use mlua::{Lua, LuaSerdeExt, SerializeOptions, Value};
+
+use crate::multiplayer;
+
+use super::{Library, LibraryBuilder};
+
+pub fn create_multiplayer_library(lua: Arc<Lua>, plugin_name: &str) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Multiplayer, "Prototype for online co-op. Access the state of another player's game that is synced over the network. The connection is managed in the GUI.")?;
+
+  let is_connected_fn = lua.create_function(|_, ()| Ok(multiplayer::is_connected()))?;
+  library.function(
+    "isConnected",
+    is_connected_fn,
+    FunctionDocs::new("Whether the state of the peer was received recently.").returns("boolean"),
+  )?;
+
+  let get_peer_players_fn = lua.create_function(|lua, ()| {
+    match multiplayer::get_peer_players() {
+      Some(players) => lua.to_value(&players),
+      None => Ok(Value::Nil),
+    }
+  })?;
+  library.function(
+    "getPeerPlayers",
+    get_peer_players_fn,
+    FunctionDocs::new("Get the players of the peer's game, player one first. Returns `nil` if the peer isn't connected.").returns("{PeerPlayer}?"),
+  )?;
+
+  let set_data_plugin_name = plugin_name.to_string();
+  let set_data_fn = lua.create_function(move |lua, value: Value| {
+    let data: Option<serde_json::Value> = match value {
+      Value::Nil => None,
+      value => Some(lua.from_value(value)?),
+    };
+
+    multiplayer::set_data(&set_data_plugin_name, data);
+
+    Ok(())
+  })?;
+  library.function(
+    "setData",
+    set_data_fn,
+    FunctionDocs::new("Set the data that is sent to the peer's instance of this plugin every frame. Only data can be sent, i.e., no functions or userdata. `nil` stops sending data.")
+      .param("value", "any"),
+  )?;
+
+  let get_peer_data_plugin_name = plugin_name.to_string();
+  let get_peer_data_fn = lua.create_function(move |lua, ()| {
+    match multiplayer::get_peer_data(&get_peer_data_plugin_name) {
+      Some(data) => lua.to_value_with(&data, SerializeOptions::new().serialize_unit_to_null(false)),
+      None => Ok(Value::Nil),
+    }
+  })?;
+  library.function(
+    "getPeerData",
+    get_peer_data_fn,
+    FunctionDocs::new("Get the latest data the peer's instance of this plugin set. Returns `nil` if the peer isn't connected or didn't set data.").returns("any"),
+  )?;
+
+  library.document_type(
+    TypeDocs::new("PeerPlayer", "State of a player in the peer's game.")
+      .field("x", "number", "Same as the player's `positionX`.")
+      .field("y", "number", "Same as the player's `positionY`.")
+      .field("z", "number", "Same as the player's `positionZ`.")
+      .field("rotation", "number", "Rotation of the player.")
+      .field("health", "number", "Health of the player.")
+  );
+
+  Ok(library.build())
+}
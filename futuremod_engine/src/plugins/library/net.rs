@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::{PluginDependency, PluginInfo}};
+use mlua::{Function, Lua, LuaSerdeExt, Table};
+
+use crate::net::{self, RequestBody, Response};
+
+use super::{Library, LibraryBuilder};
+
+/// Name of the registry value that holds the callbacks of the plugin's pending requests by the request's id.
+fn registry_key(plugin_name: &str) -> String {
+  format!("futuremod.net.{}", plugin_name)
+}
+
+/// Get the callbacks of the plugin's pending requests, creating the table if it doesn't exist.
+fn get_callbacks<'lua>(lua: &'lua Lua, plugin_name: &str) -> Result<Table<'lua>, mlua::Error> {
+  let key = registry_key(plugin_name);
+
+  if let Some(callbacks) = lua.named_registry_value::<Option<Table>>(&key)? {
+    return Ok(callbacks);
+  }
+
+  let callbacks = lua.create_table()?;
+  lua.set_named_registry_value(&key, callbacks.clone())?;
+
+  Ok(callbacks)
+}
+
+/// Send the request and keep the callback until its response arrives.
+fn send(lua: &Lua, info: &PluginInfo, url: &str, body: Option<RequestBody>, callback: Function) -> Result<u32, mlua::Error> {
+  let id = net::send(info, url, body).map_err(|e| mlua::Error::RuntimeError(format!("could not send the request: {}", e)))?;
+
+  get_callbacks(lua, &info.name)?.set(id, callback)?;
+
+  Ok(id)
+}
+
+pub fn create_net_library(lua: Arc<Lua>, info: &PluginInfo) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Net, "Send HTTP requests, e.g., to submit times to a leaderboard. Requests are only sent to the hosts the plugin declares in its info file, after the user approved them in the GUI. Responses are passed to the callback at the start of a later frame.")?;
+
+  let get_info = info.clone();
+  let get_fn = lua.create_function(move |lua, (url, callback): (String, Function)| {
+    send(lua, &get_info, &url, None, callback)
+  })?;
+  library.function(
+    "get",
+    get_fn,
+    FunctionDocs::new("Send a GET request to the URL. Errors if the host isn't declared or approved. The callback gets the response, or `nil` and the error if the request failed.")
+      .param("url", "string")
+      .param("callback", "(response: Response?, error: string?) -> ()")
+      .returns("number"),
+  )?;
+
+  let post_info = info.clone();
+  let post_fn = lua.create_function(move |lua, (url, body, callback): (String, mlua::Value, Function)| {
+    let body = match body {
+      mlua::Value::String(body) => RequestBody { content: body.as_bytes().to_vec(), content_type: "text/plain" },
+      body => {
+        let json = lua.from_value::<serde_json::Value>(body)?;
+
+        RequestBody { content: json.to_string().into_bytes(), content_type: "application/json" }
+      },
+    };
+
+    send(lua, &post_info, &url, Some(body), callback)
+  })?;
+  library.function(
+    "post",
+    post_fn,
+    FunctionDocs::new("Send a POST request to the URL. Strings are sent as text, other values as JSON. Errors if the host isn't declared or approved. The callback gets the response, or `nil` and the error if the request failed.")
+      .param("url", "string")
+      .param("body", "string | any")
+      .param("callback", "(response: Response?, error: string?) -> ()")
+      .returns("number"),
+  )?;
+
+  library.document_type(
+    TypeDocs::new("Response", "Response to a request. Redirects aren't followed.")
+      .field("status", "number", "HTTP status code, e.g., `200`.")
+      .field("body", "string", "Body of the response.")
+      .field("json", "any?", "Body decoded as JSON, `nil` if the body isn't JSON.")
+  );
+
+  Ok(library.build())
+}
+
+/// Call the callback of the request with its response.
+pub fn dispatch_response(lua: &Lua, plugin_name: &str, response: &Response) -> Result<(), mlua::Error> {
+  let callbacks = match lua.named_registry_value::<Option<Table>>(&registry_key(plugin_name))? {
+    Some(callbacks) => callbacks,
+    None => return Ok(()),
+  };
+
+  // The request was sent before the plugin was loaded again
+  let callback = match callbacks.get::<_, Option<Function>>(response.id)? {
+    Some(callback) => callback,
+    None => return Ok(()),
+  };
+  callbacks.set(response.id, mlua::Value::Nil)?;
+
+  match &response.result {
+    Ok(http_response) => {
+      let table = lua.create_table()?;
+      table.set("status", http_response.status)?;
+      table.set("body", http_response.body.as_str())?;
+
+      if let Ok(json) = serde_json::from_str::<serde_json::Value>(&http_response.body) {
+        table.set("json", lua.to_value(&json)?)?;
+      }
+
+      callback.call::<_, ()>((table, mlua::Value::Nil))
+    },
+    Err(e) => callback.call::<_, ()>((mlua::Value::Nil, e.as_str())),
+  }
+}
+
+/// Forget the callbacks of the plugin's pending requests, e.g., when the plugin is unloaded.
+pub fn clear_callbacks(lua: &Lua, plugin_name: &str) -> Result<(), mlua::Error> {
+  lua.unset_named_registry_value(&registry_key(plugin_name))
+}
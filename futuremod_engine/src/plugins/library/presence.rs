@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use futuremod_data::{docs::FunctionDocs, plugin::PluginDependency};
+use mlua::Lua;
+
+use crate::rich_presence;
+
+use super::{Library, LibraryBuilder};
+
+pub fn create_presence_library(lua: Arc<Lua>, plugin_name: &str) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Presence, "Customize the Discord rich presence. Only has an effect if the user enabled the rich presence in the mod's config.")?;
+
+  let details_plugin_name = plugin_name.to_string();
+  let set_details_fn = lua.create_function(move |_, details: Option<String>| {
+    rich_presence::set_details(&details_plugin_name, details);
+
+    Ok(())
+  })?;
+  library.function(
+    "setDetails",
+    set_details_fn,
+    FunctionDocs::new("Replace the first line of the presence, by default the game mode. `nil` restores the default text.")
+      .param("details", "string?"),
+  )?;
+
+  let state_plugin_name = plugin_name.to_string();
+  let set_state_fn = lua.create_function(move |_, state: Option<String>| {
+    rich_presence::set_state(&state_plugin_name, state);
+
+    Ok(())
+  })?;
+  library.function(
+    "setState",
+    set_state_fn,
+    FunctionDocs::new("Replace the second line of the presence, by default player one's health. `nil` restores the default text.")
+      .param("state", "string?"),
+  )?;
+
+  Ok(library.build())
+}
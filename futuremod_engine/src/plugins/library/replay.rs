@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::PluginDependency};
+use mlua::{Lua, LuaSerdeExt};
+
+use crate::ghost;
+
+use super::{Library, LibraryBuilder};
+
+pub fn create_replay_library(lua: Arc<Lua>) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Replay, "Race against a ghost. The mod records player one's position in every frame of a run and keeps the previous run as the ghost.")?;
+
+  let is_recording_fn = lua.create_function(|_, ()| Ok(ghost::current_frame().is_some()))?;
+  library.function(
+    "isRecording",
+    is_recording_fn,
+    FunctionDocs::new("Whether the current run is recorded, i.e., whether a mission is running.").returns("boolean"),
+  )?;
+
+  let get_frame_fn = lua.create_function(|_, ()| Ok(ghost::current_frame()))?;
+  library.function(
+    "getFrame",
+    get_frame_fn,
+    FunctionDocs::new("Get the frame of the current run, counted from `0`. Returns `nil` if no run is recorded.").returns("number?"),
+  )?;
+
+  let get_ghost_length_fn = lua.create_function(|_, ()| Ok(ghost::ghost_length()))?;
+  library.function(
+    "getGhostLength",
+    get_ghost_length_fn,
+    FunctionDocs::new("Get the number of frames of the ghost. Returns `0` if there is no ghost.").returns("number"),
+  )?;
+
+  let get_ghost_position_fn = lua.create_function(|lua, frame: Option<usize>| {
+    let frame = match frame.or_else(ghost::current_frame) {
+      Some(frame) => frame,
+      None => return Ok(mlua::Value::Nil),
+    };
+
+    match ghost::ghost_position(frame) {
+      Some(position) => lua.to_value(&position),
+      None => Ok(mlua::Value::Nil),
+    }
+  })?;
+  library.function(
+    "getGhostPosition",
+    get_ghost_position_fn,
+    FunctionDocs::new("Get the ghost's position in the frame, by default in the current frame. Returns `nil` if the ghost's run is shorter.")
+      .param("frame", "number?")
+      .returns("Position?"),
+  )?;
+
+  let clear_ghost_fn = lua.create_function(|_, ()| {
+    ghost::clear_ghost();
+
+    Ok(())
+  })?;
+  library.function(
+    "clearGhost",
+    clear_ghost_fn,
+    FunctionDocs::new("Discard the ghost. The next finished run becomes the new ghost."),
+  )?;
+
+  library.document_type(
+    TypeDocs::new("Position", "Position of player one in a frame of a run.")
+      .field("x", "number", "Same as the player's `positionX`.")
+      .field("y", "number", "Same as the player's `positionY`.")
+      .field("z", "number", "Same as the player's `positionZ`.")
+  );
+
+  Ok(library.build())
+}
@@ -0,0 +1,57 @@
+use std::{path::{Path, PathBuf}, sync::Arc};
+
+use futuremod_data::{docs::FunctionDocs, plugin::PluginDependency};
+use mlua::Lua;
+
+use crate::sound::{self, MAX_PLAYING, MAX_SOUND_SIZE};
+
+use super::{Library, LibraryBuilder};
+
+pub fn create_sound_library(lua: Arc<Lua>, plugin_name: &str, plugin_folder: &Path) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Sound, &format!("Play WAV files that the plugin ships in its folder, each up to {} MB. Sounds of all plugins play at the same time, up to {} per plugin.", MAX_SOUND_SIZE / 1024 / 1024, MAX_PLAYING))?;
+
+  let plugin: Arc<(String, PathBuf)> = Arc::new((plugin_name.to_string(), plugin_folder.to_path_buf()));
+
+  // `play` is left for the game's own sound effects by their id
+  let play_file_plugin = plugin.clone();
+  let play_file_fn = lua.create_function(move |_, file: String| {
+    sound::play(&play_file_plugin.0, &play_file_plugin.1, &file).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+  })?;
+  library.function(
+    "playFile",
+    play_file_fn,
+    FunctionDocs::new("Play the WAV file, given by its path relative to the plugin's folder, e.g., `sounds/hit.wav`. Errors if the file doesn't exist, is outside the plugin's folder, or isn't a WAV file.")
+      .param("file", "string"),
+  )?;
+
+  let set_volume_plugin_name = plugin_name.to_string();
+  let set_volume_fn = lua.create_function(move |_, volume: f32| {
+    if !(0.0..=1.0).contains(&volume) {
+      return Err(mlua::Error::RuntimeError(format!("volume must be between 0 and 1, but is {}", volume)));
+    }
+
+    sound::set_volume(&set_volume_plugin_name, volume);
+
+    Ok(())
+  })?;
+  library.function(
+    "setVolume",
+    set_volume_fn,
+    FunctionDocs::new("Set the volume of the plugin's sounds, including the ones that are playing, from `0` (muted) to `1` (default).")
+      .param("volume", "number"),
+  )?;
+
+  let stop_plugin_name = plugin_name.to_string();
+  let stop_fn = lua.create_function(move |_, ()| {
+    sound::stop(&stop_plugin_name);
+
+    Ok(())
+  })?;
+  library.function(
+    "stop",
+    stop_fn,
+    FunctionDocs::new("Stop the plugin's sounds that are playing."),
+  )?;
+
+  Ok(library.build())
+}
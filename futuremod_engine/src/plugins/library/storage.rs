@@ -0,0 +1,63 @@
+use std::{path::{Path, PathBuf}, sync::Arc};
+
+use futuremod_data::{docs::FunctionDocs, plugin::PluginDependency};
+use mlua::{Lua, LuaSerdeExt, Value};
+
+use crate::plugins::storage::{self, MAX_STORAGE_SIZE};
+
+use super::{Library, LibraryBuilder};
+
+fn to_lua_error(e: anyhow::Error) -> mlua::Error {
+  mlua::Error::RuntimeError(e.to_string())
+}
+
+pub fn create_storage_library(lua: Arc<Lua>, plugin_name: &str, plugin_folder: &Path) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Storage, &format!("Save settings or progress between game sessions. Values are stored in the file `{}` in the plugin's folder, limited to {} KB per plugin.", storage::STORAGE_FILE_NAME, MAX_STORAGE_SIZE / 1024))?;
+
+  let plugin: Arc<(String, PathBuf)> = Arc::new((plugin_name.to_string(), plugin_folder.to_path_buf()));
+
+  let set_plugin = plugin.clone();
+  let set_fn = lua.create_function(move |lua, (key, value): (String, Value)| {
+    let value: serde_json::Value = lua.from_value(value)?;
+
+    match value {
+      serde_json::Value::Null => storage::delete(&set_plugin.0, &set_plugin.1, &key),
+      value => storage::set(&set_plugin.0, &set_plugin.1, &key, value),
+    }
+    .map_err(to_lua_error)
+  })?;
+  library.function(
+    "set",
+    set_fn,
+    FunctionDocs::new("Store the value under the key. Only data can be stored, i.e., no functions or userdata. Storing `nil` deletes the key. Errors if the plugin's storage would exceed its quota.")
+      .param("key", "string")
+      .param("value", "any"),
+  )?;
+
+  let get_plugin = plugin.clone();
+  let get_fn = lua.create_function(move |lua, key: String| {
+    match storage::get(&get_plugin.0, &get_plugin.1, &key).map_err(to_lua_error)? {
+      Some(value) => lua.to_value(&value),
+      None => Ok(Value::Nil),
+    }
+  })?;
+  library.function(
+    "get",
+    get_fn,
+    FunctionDocs::new("Get the value stored under the key. Returns `nil` if no value is stored.")
+      .param("key", "string")
+      .returns("any"),
+  )?;
+
+  let delete_plugin = plugin.clone();
+  let delete_fn = lua.create_function(move |_, key: String| {
+    storage::delete(&delete_plugin.0, &delete_plugin.1, &key).map_err(to_lua_error)
+  })?;
+  library.function(
+    "delete",
+    delete_fn,
+    FunctionDocs::new("Delete the value stored under the key.").param("key", "string"),
+  )?;
+
+  Ok(library.build())
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::PluginDependency};
+use mlua::Lua;
+
+use crate::structs;
+
+use super::{dangerous::definition_from_fields, Library, LibraryBuilder};
+
+pub fn create_structs_library(lua: Arc<Lua>) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Structs, "Definitions of the game's structs the mod knows, to access instances of them without declaring their offsets. Cast an address to a definition to read and write the instance's fields. Wrong addresses crash the game.")?;
+
+  for definition in structs::catalog() {
+    let fields: Vec<String> = definition.fields.iter().map(|field| format!("`{}` ({})", field.name, field.field_type)).collect();
+
+    library.constant(
+      &definition.name,
+      definition_from_fields(&lua, &definition.fields)?,
+      "NativeStructDefinition",
+      &format!("{} Fields: {}.", definition.description, fields.join(", ")),
+    )?;
+  }
+
+  library.document_type(
+    TypeDocs::new("NativeStructDefinition", "Layout of a native struct.")
+      .method("cast", FunctionDocs::new("Access the native struct at the address with this definition.").param("address", "number").returns("NativeStruct"))
+  );
+
+  Ok(library.build())
+}
@@ -1,9 +1,34 @@
 use std::{sync::Arc, time::{SystemTime, UNIX_EPOCH}};
 
-use mlua::{Lua, OwnedTable};
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::{PluginDependency, PluginInfo, PluginPermission}};
+use mlua::{Function, Lua, Table};
 
-pub fn create_system_library(lua: Arc<Lua>) -> Result<OwnedTable, mlua::Error> {
-  let library = lua.create_table()?;
+use crate::exec::{self, ExecResult};
+
+use super::{Library, LibraryBuilder};
+
+/// Name of the registry value that holds the callbacks of the plugin's running programs by the execution's id.
+fn registry_key(plugin_name: &str) -> String {
+  format!("futuremod.system.{}", plugin_name)
+}
+
+/// Get the callbacks of the plugin's running programs, creating the table if it doesn't exist.
+fn get_callbacks<'lua>(lua: &'lua Lua, plugin_name: &str) -> Result<Table<'lua>, mlua::Error> {
+  let key = registry_key(plugin_name);
+
+  if let Some(callbacks) = lua.named_registry_value::<Option<Table>>(&key)? {
+    return Ok(callbacks);
+  }
+
+  let callbacks = lua.create_table()?;
+  lua.set_named_registry_value(&key, callbacks.clone())?;
+
+  Ok(callbacks)
+}
+
+/// Create the system library. `exec` is only available with the `exec` permission.
+pub fn create_system_library(lua: Arc<Lua>, info: &PluginInfo, permissions: &[PluginPermission]) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::System, "Access to system information, such as the time, and to the external programs the plugin declares.")?;
 
   let get_time_fn = lua.create_function(|_, ()| {
     let time = SystemTime::now();
@@ -13,7 +38,67 @@ pub fn create_system_library(lua: Arc<Lua>) -> Result<OwnedTable, mlua::Error> {
       Err(e) => Err(mlua::Error::RuntimeError(format!("could not get time: {}", e))),
     }
   })?;
-  library.set("getTime", get_time_fn)?;
+  library.function("getTime", get_time_fn, FunctionDocs::new("Get the current system time as milliseconds since the unix epoch.").returns("number"))?;
+
+  let exec_info = info.clone();
+  let exec_fn = lua.create_function(move |lua, (command, args, callback): (String, Option<Vec<String>>, Option<Function>)| {
+    let id = exec::run(&exec_info, &command, args.unwrap_or_default()).map_err(|e| mlua::Error::RuntimeError(format!("could not run the command: {}", e)))?;
+
+    if let Some(callback) = callback {
+      get_callbacks(lua, &exec_info.name)?.set(id, callback)?;
+    }
+
+    Ok(id)
+  })?;
+  library.function(
+    "exec",
+    exec_fn,
+    FunctionDocs::new("Run one of the commands the plugin declares in its info file with the arguments, without a shell. Errors if the command isn't declared or approved. The callback gets the program's output once it exited, or `nil` and the error if it failed or ran longer than 10 seconds.")
+      .param("command", "string")
+      .param("args", "{string}?")
+      .param("callback", "((output: ProgramOutput?, error: string?) -> ())?")
+      .returns("number"),
+  )?;
+  library.require_permissions("exec", &[PluginPermission::Exec], permissions)?;
+
+  library.document_type(
+    TypeDocs::new("ProgramOutput", "Output of a program that exited. Output beyond 64 KiB is dropped.")
+      .field("exitCode", "number?", "Exit code of the program, `nil` if it was terminated.")
+      .field("stdout", "string", "Standard output of the program.")
+      .field("stderr", "string", "Standard error of the program.")
+  );
+
+  Ok(library.build())
+}
+
+/// Call the callback of the execution with the program's output.
+pub fn dispatch_result(lua: &Lua, plugin_name: &str, result: &ExecResult) -> Result<(), mlua::Error> {
+  let callbacks = match lua.named_registry_value::<Option<Table>>(&registry_key(plugin_name))? {
+    Some(callbacks) => callbacks,
+    None => return Ok(()),
+  };
+
+  // The program was run without a callback or before the plugin was loaded again
+  let callback = match callbacks.get::<_, Option<Function>>(result.id)? {
+    Some(callback) => callback,
+    None => return Ok(()),
+  };
+  callbacks.set(result.id, mlua::Value::Nil)?;
+
+  match &result.result {
+    Ok(output) => {
+      let table = lua.create_table()?;
+      table.set("exitCode", output.exit_code)?;
+      table.set("stdout", output.stdout.as_str())?;
+      table.set("stderr", output.stderr.as_str())?;
+
+      callback.call::<_, ()>((table, mlua::Value::Nil))
+    },
+    Err(e) => callback.call::<_, ()>((mlua::Value::Nil, e.as_str())),
+  }
+}
 
-  Ok(library.into_owned())
-}
\ No newline at end of file
+/// Forget the callbacks of the plugin's running programs, e.g., when the plugin is unloaded.
+pub fn clear_callbacks(lua: &Lua, plugin_name: &str) -> Result<(), mlua::Error> {
+  lua.unset_named_registry_value(&registry_key(plugin_name))
+}
@@ -0,0 +1,161 @@
+use std::{cmp::Ordering, sync::{atomic::{self, AtomicU32}, Arc}};
+
+use futuremod_data::{docs::FunctionDocs, plugin::PluginDependency};
+use mlua::{Function, Lua, Table};
+
+use crate::{clock, debugger};
+
+use super::{Library, LibraryBuilder};
+
+static NEXT_TIMER_ID: AtomicU32 = AtomicU32::new(1);
+
+/// Name of the registry value that holds the plugin's timers by their id.
+fn registry_key(plugin_name: &str) -> String {
+  format!("futuremod.time.{}", plugin_name)
+}
+
+/// Get the plugin's timers, creating the table if it doesn't exist.
+fn get_timers<'lua>(lua: &'lua Lua, plugin_name: &str) -> Result<Table<'lua>, mlua::Error> {
+  let key = registry_key(plugin_name);
+
+  if let Some(timers) = lua.named_registry_value::<Option<Table>>(&key)? {
+    return Ok(timers);
+  }
+
+  let timers = lua.create_table()?;
+  lua.set_named_registry_value(&key, timers.clone())?;
+
+  Ok(timers)
+}
+
+/// Schedule the callback in the given seconds, repeating it with the interval if there is one.
+fn schedule(lua: &Lua, plugin_name: &str, seconds: f64, interval: Option<f64>, callback: Function) -> Result<u32, mlua::Error> {
+  if !seconds.is_finite() || seconds < 0.0 {
+    return Err(mlua::Error::RuntimeError(format!("invalid number of seconds: {}", seconds)));
+  }
+
+  let timer = lua.create_table()?;
+  timer.set("callback", callback)?;
+  timer.set("due", clock::now() + seconds)?;
+  timer.set("interval", interval)?;
+
+  let id = NEXT_TIMER_ID.fetch_add(1, atomic::Ordering::SeqCst);
+  get_timers(lua, plugin_name)?.set(id, timer)?;
+
+  Ok(id)
+}
+
+pub fn create_time_library(lua: Arc<Lua>, plugin_name: &str) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Time, "Run functions after some time or repeatedly, independent of the frame rate. Timers are checked at the start of every frame in a mission and only advance while the game loop runs. Disabling or reloading the plugin cancels all of its timers.")?;
+
+  let after_plugin_name = plugin_name.to_string();
+  let after_fn = lua.create_function(move |lua, (seconds, callback): (f64, Function)| {
+    schedule(lua, &after_plugin_name, seconds, None, callback)
+  })?;
+  library.function(
+    "after",
+    after_fn,
+    FunctionDocs::new("Call the function once after the seconds passed. Returns the timer's id to cancel it.")
+      .param("seconds", "number")
+      .param("callback", "() -> ()")
+      .returns("number"),
+  )?;
+
+  let every_plugin_name = plugin_name.to_string();
+  let every_fn = lua.create_function(move |lua, (seconds, callback): (f64, Function)| {
+    if seconds <= 0.0 {
+      return Err(mlua::Error::RuntimeError("the interval must be longer than 0 seconds".to_string()));
+    }
+
+    schedule(lua, &every_plugin_name, seconds, Some(seconds), callback)
+  })?;
+  library.function(
+    "every",
+    every_fn,
+    FunctionDocs::new("Call the function every time the seconds passed, at most once per frame. Returns the timer's id to cancel it.")
+      .param("seconds", "number")
+      .param("callback", "() -> ()")
+      .returns("number"),
+  )?;
+
+  let cancel_plugin_name = plugin_name.to_string();
+  let cancel_fn = lua.create_function(move |lua, id: u32| {
+    let timers = get_timers(lua, &cancel_plugin_name)?;
+    let is_pending = timers.contains_key(id)?;
+    timers.set(id, mlua::Value::Nil)?;
+
+    Ok(is_pending)
+  })?;
+  library.function(
+    "cancel",
+    cancel_fn,
+    FunctionDocs::new("Cancel the timer. Returns `false` if the timer already ran or was cancelled before.")
+      .param("id", "number")
+      .returns("boolean"),
+  )?;
+
+  let frame_fn = lua.create_function(|_, ()| Ok(debugger::get_state().frame))?;
+  library.function(
+    "frame",
+    frame_fn,
+    FunctionDocs::new("Get the number of frames the game loop ran since the mod was injected.").returns("number"),
+  )?;
+
+  let now_fn = lua.create_function(|_, ()| Ok(clock::now()))?;
+  library.function(
+    "now",
+    now_fn,
+    FunctionDocs::new("Get the seconds the game loop ran since the mod was injected, the clock the timers use.").returns("number"),
+  )?;
+
+  Ok(library.build())
+}
+
+/// Call the callbacks of the plugin's timers that are due, in the order they are due.
+///
+/// One-shot timers are removed and repeating timers are scheduled again before their callback is called.
+pub fn dispatch_timers(lua: &Lua, plugin_name: &str, now: f64) -> Result<(), mlua::Error> {
+  let timers = match lua.named_registry_value::<Option<Table>>(&registry_key(plugin_name))? {
+    Some(timers) => timers,
+    None => return Ok(()),
+  };
+
+  // Timers the callbacks schedule are collected in the next frame at the earliest
+  let mut due = Vec::new();
+  for pair in timers.clone().pairs::<u32, Table>() {
+    let (id, timer) = pair?;
+    let at: f64 = timer.get("due")?;
+
+    if at <= now {
+      due.push((at, id));
+    }
+  }
+  due.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+  for (at, id) in due {
+    // Cancelled by a callback that ran before
+    let timer = match timers.get::<_, Option<Table>>(id)? {
+      Some(timer) => timer,
+      None => continue,
+    };
+    let callback: Function = timer.get("callback")?;
+
+    match timer.get::<_, Option<f64>>("interval")? {
+      Some(interval) => {
+        // A timer that fell behind, e.g., because of a long frame, isn't called repeatedly to catch up
+        let next = at + interval;
+        timer.set("due", if next <= now { now + interval } else { next })?;
+      },
+      None => timers.set(id, mlua::Value::Nil)?,
+    }
+
+    callback.call::<_, ()>(())?;
+  }
+
+  Ok(())
+}
+
+/// Cancel all of the plugin's timers, e.g., when the plugin is disabled.
+pub fn clear_timers(lua: &Lua, plugin_name: &str) -> Result<(), mlua::Error> {
+  lua.unset_named_registry_value(&registry_key(plugin_name))
+}
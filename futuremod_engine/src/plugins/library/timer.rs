@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::PluginDependency};
+use mlua::{Function, Lua, LuaSerdeExt, Table};
+
+use crate::run_timer::{self, Split};
+
+use super::{Library, LibraryBuilder};
+
+/// Name of the registry value that holds the plugin's split callbacks.
+///
+/// Like HTTP routes, callbacks are stored per plugin, since every file the plugin requires gets its own instance of the library.
+fn registry_key(plugin_name: &str) -> String {
+  format!("futuremod.timer.{}", plugin_name)
+}
+
+/// Get the list of the plugin's split callbacks, creating it if it doesn't exist.
+fn get_callbacks<'lua>(lua: &'lua Lua, plugin_name: &str) -> Result<Table<'lua>, mlua::Error> {
+  let key = registry_key(plugin_name);
+
+  if let Some(callbacks) = lua.named_registry_value::<Option<Table>>(&key)? {
+    return Ok(callbacks);
+  }
+
+  let callbacks = lua.create_table()?;
+  lua.set_named_registry_value(&key, callbacks.clone())?;
+
+  Ok(callbacks)
+}
+
+pub fn create_timer_library(lua: Arc<Lua>, plugin_name: &str) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::Timer, "Time runs of missions with splits, e.g., for speedrunning. A run starts when a mission starts and ends with a split when the mission ends.")?;
+
+  let on_split_plugin_name = plugin_name.to_string();
+  let on_split_fn = lua.create_function(move |lua, callback: Function| {
+    let callbacks = get_callbacks(lua, &on_split_plugin_name)?;
+
+    callbacks.push(callback)
+  })?;
+  library.function(
+    "onSplit",
+    on_split_fn,
+    FunctionDocs::new("Register a function that is called with every split of the run at the start of the next frame, including the split at the end of the mission.")
+      .param("callback", "(split: Split) -> ()"),
+  )?;
+
+  let split_fn = lua.create_function(|lua, name: Option<String>| {
+    let split = run_timer::split(name.unwrap_or_else(|| "Split".to_string()))
+      .map_err(|e| mlua::Error::RuntimeError(format!("could not split: {}", e)))?;
+
+    lua.to_value(&split)
+  })?;
+  library.function(
+    "split",
+    split_fn,
+    FunctionDocs::new("Split the current run, e.g., when an objective is completed. Errors if no run is timed.")
+      .param("name", "string?")
+      .returns("Split"),
+  )?;
+
+  let get_time_fn = lua.create_function(|_, ()| Ok(run_timer::get_time()))?;
+  library.function(
+    "getTime",
+    get_time_fn,
+    FunctionDocs::new("Get the seconds since the start of the current run or the time of the finished run. Returns `nil` if no run was started yet.").returns("number?"),
+  )?;
+
+  let is_running_fn = lua.create_function(|_, ()| Ok(run_timer::is_running()))?;
+  library.function(
+    "isRunning",
+    is_running_fn,
+    FunctionDocs::new("Whether a run is currently timed.").returns("boolean"),
+  )?;
+
+  library.document_type(
+    TypeDocs::new("Split", "Split of a run.")
+      .field("index", "number", "Number of the split in the run, counted from `1`.")
+      .field("name", "string", "Name of the split. The split at the end of the mission is named `Mission End`.")
+      .field("time", "number", "Seconds since the start of the run.")
+      .field("segmentTime", "number", "Seconds since the previous split or the start of the run.")
+  );
+
+  Ok(library.build())
+}
+
+/// Call the plugin's split callbacks with the split.
+pub fn dispatch_split(lua: &Lua, plugin_name: &str, split: &Split) -> Result<(), mlua::Error> {
+  let callbacks = match lua.named_registry_value::<Option<Table>>(&registry_key(plugin_name))? {
+    Some(callbacks) => callbacks,
+    None => return Ok(()),
+  };
+
+  for callback in callbacks.sequence_values::<Function>() {
+    callback?.call::<_, ()>(lua.to_value(split)?)?;
+  }
+
+  Ok(())
+}
+
+/// Remove the plugin's split callbacks, e.g., when the plugin is unloaded.
+pub fn clear_callbacks(lua: &Lua, plugin_name: &str) -> Result<(), mlua::Error> {
+  lua.unset_named_registry_value(&registry_key(plugin_name))
+}
@@ -1,32 +1,213 @@
 use std::sync::Arc;
 
-use mlua::{Lua, LuaSerdeExt, OwnedTable, Value};
+use futuremod_data::{docs::{FunctionDocs, TypeDocs}, plugin::PluginDependency};
+use mlua::{Lua, LuaSerdeExt, Table, Value};
 
-use crate::api::{self, ui::{Color, TextPalette, TEXT_PALETTES}};
+use crate::{api::{self, ui::{Color, TextPalette, TEXT_PALETTES}}, overlay, viewport::{self, Anchor, Layout}, widgets};
 
-pub fn create_ui_library(lua: Arc<Lua>) -> Result<OwnedTable, mlua::Error> {
-  let library = lua.create_table()?;
+use super::{Library, LibraryBuilder};
+
+pub fn create_ui_library(lua: Arc<Lua>, plugin_name: &str) -> Result<Library, mlua::Error> {
+  let mut library = LibraryBuilder::new(&lua, PluginDependency::UI, "Render text and shapes on the screen. Must be called every frame in `onUpdate`. Positions and sizes are in the plugin's layout, by default the game's original resolution of 320x240, and are scaled to the screen.")?;
+
+  let render_text_plugin_name = plugin_name.to_string();
+  let render_text = lua.create_function(move |_, (text, pos_x, pos_y, palette): (String, f32, f32, u32)| {
+    let (pos_x, pos_y) = viewport::get_layout(&render_text_plugin_name).to_screen(pos_x, pos_y);
 
-  let render_text = lua.create_function(|_, (text, pos_x, pos_y, palette): (String, u32, u32, u32)| {
     api::ui::render_text(pos_x, pos_y, TextPalette::from(palette), &text);
 
     Ok(())
   })?;
-  library.set("renderText", render_text)?;
+  library.function(
+    "renderText",
+    render_text,
+    FunctionDocs::new("Render text at the given position with one of the game's text palettes. The game's font can't be scaled, so only the position is scaled.")
+      .param("text", "string")
+      .param("x", "number")
+      .param("y", "number")
+      .param("palette", "number"),
+  )?;
 
-  let render_rectangle = lua.create_function(|lua, (color, pos_x, pos_y, width, height, semi_transparent): (Value, u16, u16, u16, u16, bool)| {
+  let render_rectangle_plugin_name = plugin_name.to_string();
+  let render_rectangle = lua.create_function(move |lua, (color, pos_x, pos_y, width, height, semi_transparent): (Value, f32, f32, f32, f32, bool)| {
     // Convert the color lua value into the rust type
     let color: Color = lua.from_value(color)?;
 
-    api::ui::render_rectangle(color, pos_x, pos_y, width, height, semi_transparent);
+    let layout = viewport::get_layout(&render_rectangle_plugin_name);
+    let (pos_x, pos_y) = layout.to_screen(pos_x, pos_y);
+    let (width, height) = layout.to_screen_size(width, height);
+
+    api::ui::render_rectangle(color, clamp_u16(pos_x), clamp_u16(pos_y), clamp_u16(width), clamp_u16(height), semi_transparent);
 
     Ok(())
   })?;
-  library.set("renderRectangle", render_rectangle)?;
+  library.function(
+    "renderRectangle",
+    render_rectangle,
+    FunctionDocs::new("Render a rectangle at the given position. Semi-transparent rectangles are blended with the background.")
+      .param("color", "{red: number, green: number, blue: number}")
+      .param("x", "number")
+      .param("y", "number")
+      .param("width", "number")
+      .param("height", "number")
+      .param("semiTransparent", "boolean"),
+  )?;
+
+  let set_layout_plugin_name = plugin_name.to_string();
+  let set_layout_fn = lua.create_function(move |lua, options: Table| {
+    let default_layout = Layout::default();
+
+    let anchor = match options.get::<_, Option<Value>>("anchor")? {
+      Some(anchor) => lua.from_value::<Anchor>(anchor).map_err(|_| mlua::Error::RuntimeError("invalid anchor".into()))?,
+      None => default_layout.anchor,
+    };
+    let layout = Layout {
+      width: options.get::<_, Option<u32>>("width")?.unwrap_or(default_layout.width),
+      height: options.get::<_, Option<u32>>("height")?.unwrap_or(default_layout.height),
+      anchor,
+    };
+
+    if layout.width == 0 || layout.height == 0 {
+      return Err(mlua::Error::RuntimeError("the layout's width and height must be greater than 0".into()));
+    }
+
+    viewport::set_layout(&set_layout_plugin_name, layout);
+
+    Ok(())
+  })?;
+  library.function(
+    "setLayout",
+    set_layout_fn,
+    FunctionDocs::new("Set the design resolution the plugin places its HUD in and the anchor the HUD sticks to if the screen's aspect ratio differs. The HUD is scaled to fit the screen. Anchors are `topLeft` (default), `top`, `topRight`, `left`, `center`, `right`, `bottomLeft`, `bottom`, and `bottomRight`.")
+      .param("options", "{width: number?, height: number?, anchor: string?}"),
+  )?;
+
+  let to_screen_plugin_name = plugin_name.to_string();
+  let to_screen_fn = lua.create_function(move |_, (pos_x, pos_y): (f32, f32)| {
+    Ok(viewport::get_layout(&to_screen_plugin_name).to_screen(pos_x, pos_y))
+  })?;
+  library.function(
+    "toScreen",
+    to_screen_fn,
+    FunctionDocs::new("Convert the position in the plugin's layout into the position on the screen.")
+      .param("x", "number")
+      .param("y", "number")
+      .returns("(number, number)"),
+  )?;
+
+  let get_screen_size_fn = lua.create_function(|lua, ()| {
+    let (width, height) = viewport::screen_size();
+
+    let size = lua.create_table()?;
+    size.set("width", width)?;
+    size.set("height", height)?;
+
+    Ok(size)
+  })?;
+  library.function(
+    "getScreenSize",
+    get_screen_size_fn,
+    FunctionDocs::new("Get the size of the screen space the game renders in, which a resolution patch may change.")
+      .returns("{width: number, height: number}"),
+  )?;
+
+  let set_debug_lines_plugin_name = plugin_name.to_string();
+  let set_debug_lines_fn = lua.create_function(move |_, lines: Option<Vec<String>>| {
+    overlay::set_plugin_lines(&set_debug_lines_plugin_name, lines.unwrap_or_default());
+
+    Ok(())
+  })?;
+  library.function(
+    "setDebugLines",
+    set_debug_lines_fn,
+    FunctionDocs::new(&format!("Show the lines in the debug overlay under the plugin's name, replacing its previous lines. Unlike the other functions, the lines stay until they are replaced, so call it whenever they change. Shows at most {} lines of {} characters. `nil` or an empty table removes the plugin from the overlay.", overlay::MAX_PLUGIN_DEBUG_LINES, overlay::DEBUG_LINE_LENGTH))
+      .param("lines", "{string}?"),
+  )?;
+
+  let (widgets_table, widgets_docs) = create_widgets_table(&lua, plugin_name)?;
+  library.constant("widgets", widgets_table, "Widgets", "Panels with labels, buttons, checkboxes, and sliders for in-game menus.")?;
+  library.document_type(widgets_docs);
 
   for palette in TEXT_PALETTES {
-    library.set(format!("Palette{}", palette), Into::<u32>::into(palette))?;
+    library.constant(&format!("Palette{}", palette), Into::<u32>::into(palette), "number", "Text palette for `renderText`.")?;
   }
 
-  Ok(library.into_owned())
+  Ok(library.build())
+}
+
+/// Create the table of `ui.widgets` together with its documentation.
+fn create_widgets_table<'lua>(lua: &'lua Lua, plugin_name: &str) -> Result<(Table<'lua>, TypeDocs), mlua::Error> {
+  let table = lua.create_table()?;
+
+  let begin_panel_plugin_name = plugin_name.to_string();
+  table.set("beginPanel", lua.create_function(move |_, (id, pos_x, pos_y, width): (String, f32, f32, f32)| {
+    widgets::begin_panel(&begin_panel_plugin_name, &id, pos_x, pos_y, width).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+  })?)?;
+
+  let end_panel_plugin_name = plugin_name.to_string();
+  table.set("endPanel", lua.create_function(move |_, ()| {
+    widgets::end_panel(&end_panel_plugin_name).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+  })?)?;
+
+  let label_plugin_name = plugin_name.to_string();
+  table.set("label", lua.create_function(move |_, text: String| {
+    widgets::label(&label_plugin_name, &text).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+  })?)?;
+
+  let button_plugin_name = plugin_name.to_string();
+  table.set("button", lua.create_function(move |_, label: String| {
+    widgets::button(&button_plugin_name, &label).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+  })?)?;
+
+  let checkbox_plugin_name = plugin_name.to_string();
+  table.set("checkbox", lua.create_function(move |_, (label, checked): (String, bool)| {
+    widgets::checkbox(&checkbox_plugin_name, &label, checked).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+  })?)?;
+
+  let slider_plugin_name = plugin_name.to_string();
+  table.set("slider", lua.create_function(move |_, (label, value, min, max, step): (String, f32, f32, f32, Option<f32>)| {
+    widgets::slider(&slider_plugin_name, &label, value, min, max, step).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+  })?)?;
+
+  let focus_plugin_name = plugin_name.to_string();
+  table.set("focus", lua.create_function(move |_, id: String| {
+    widgets::focus_panel(&focus_plugin_name, &id).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+  })?)?;
+
+  let has_focus_plugin_name = plugin_name.to_string();
+  table.set("hasFocus", lua.create_function(move |_, ()| Ok(widgets::has_focus(&has_focus_plugin_name)))?)?;
+
+  let docs = TypeDocs::new("Widgets", "Immediate-mode widgets. Describe the panel with its widgets every frame in `onUpdate` between `beginPanel` and `endPanel`, the widgets are placed below each other and return the user's input. Clicking a widget gives its panel the keyboard focus. While a panel has the focus, Tab and the arrow keys move between its widgets and change sliders, Enter and Space press buttons and toggle checkboxes, and Escape releases the focus. The game doesn't receive these keys while a panel has the focus.")
+    .method("beginPanel", FunctionDocs::new("Begin the panel with the id at the position and with the width in the plugin's layout. Its height grows with its widgets. Widgets keep their focus as long as the panel is rendered with the same id every frame.")
+      .param("id", "string")
+      .param("x", "number")
+      .param("y", "number")
+      .param("width", "number"))
+    .method("endPanel", FunctionDocs::new("End the panel and render it."))
+    .method("label", FunctionDocs::new("Add a line of text to the panel. Text longer than the panel is cut off.")
+      .param("text", "string"))
+    .method("button", FunctionDocs::new("Add a button to the panel. Returns `true` in the frame it was clicked or pressed with the keyboard.")
+      .param("label", "string")
+      .returns("boolean"))
+    .method("checkbox", FunctionDocs::new("Add a checkbox to the panel. Returns whether it is checked after the user's input, pass it back in the next frame.")
+      .param("label", "string")
+      .param("checked", "boolean")
+      .returns("boolean"))
+    .method("slider", FunctionDocs::new("Add a slider between `min` and `max` to the panel, which the user can drag or move with the arrow keys. Returns its value after the user's input, pass it back in the next frame. The value snaps to multiples of `step` if given.")
+      .param("label", "string")
+      .param("value", "number")
+      .param("min", "number")
+      .param("max", "number")
+      .param("step", "number?")
+      .returns("number"))
+    .method("focus", FunctionDocs::new("Give the keyboard focus to the first widget of the panel with the id, e.g., when the plugin opens its menu with a key binding. The focus is lost if the panel isn't rendered in the next frame.")
+      .param("id", "string"))
+    .method("hasFocus", FunctionDocs::new("Whether one of the plugin's panels has the keyboard focus.")
+      .returns("boolean"));
+
+  Ok((table, docs))
+}
+
+fn clamp_u16(value: u32) -> u16 {
+  value.min(u16::MAX as u32) as u16
 }
\ No newline at end of file
@@ -0,0 +1,95 @@
+use std::{sync::{atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering}, Arc}, time::{Duration, Instant}};
+
+use log::*;
+use mlua::{Lua, VmState};
+
+use crate::config::ExecutionLimitsConfig;
+
+/// Instructions each `onUpdate` call of a plugin may run, `0` for no limit.
+static UPDATE_BUDGET: AtomicU64 = AtomicU64::new(0);
+
+/// Megabytes each plugin's Lua state may use, `0` for no limit.
+static MEMORY_LIMIT: AtomicU32 = AtomicU32::new(0);
+
+/// Set the limits. Must be called when the mod is injected, before the plugin manager is initialized.
+///
+/// The limits keep a plugin with an endless loop or a memory leak from freezing the game.
+/// The memory limit applies to each plugin's Lua state.
+pub fn init(config: &ExecutionLimitsConfig) {
+    info!("Limiting onUpdate calls to {} instructions and each plugin's memory to {} MB", config.update_budget, config.memory_limit);
+
+    UPDATE_BUDGET.store(config.update_budget, Ordering::SeqCst);
+    MEMORY_LIMIT.store(config.memory_limit, Ordering::SeqCst);
+}
+
+pub fn update_budget() -> u64 {
+    UPDATE_BUDGET.load(Ordering::SeqCst)
+}
+
+pub fn memory_limit() -> u32 {
+    MEMORY_LIMIT.load(Ordering::SeqCst)
+}
+
+/// Limit the memory of the Lua state to the configured limit. Must be called when the state is created.
+pub fn apply_memory_limit(lua: &Lua) {
+    let bytes = memory_limit() as usize * 1024 * 1024;
+
+    if let Err(e) = lua.set_memory_limit(bytes) {
+        warn!("Could not limit the memory of the plugins: {}", e);
+    }
+}
+
+/// Call the function with the budget of instructions, `0` for no limit.
+///
+/// Luau interrupts running scripts at function calls and loop iterations. Each interrupt counts as an instruction,
+/// and the call is aborted with an error once the budget is spent.
+/// Returns the function's result and whether the budget was exceeded.
+pub fn with_budget<R>(lua: &Lua, budget: u64, f: impl FnOnce() -> R) -> (R, bool) {
+    if budget == 0 {
+        return (f(), false);
+    }
+
+    let exceeded = Arc::new(AtomicBool::new(false));
+    let spent = AtomicU64::new(0);
+
+    let interrupted = exceeded.clone();
+    lua.set_interrupt(move |_| {
+        // Keeps failing once the budget is spent, so that a plugin can't catch the error with `pcall` and continue
+        if spent.fetch_add(1, Ordering::Relaxed) >= budget {
+            interrupted.store(true, Ordering::Relaxed);
+            return Err(mlua::Error::RuntimeError(format!("exceeded the budget of {} instructions", budget)));
+        }
+
+        Ok(VmState::Continue)
+    });
+
+    let result = f();
+    lua.remove_interrupt();
+
+    (result, exceeded.load(Ordering::Relaxed))
+}
+
+/// Call the function and abort the Lua code it runs once it ran longer than the timeout.
+///
+/// Unlike [`with_budget`], used for code that doesn't run in the game loop, e.g., handlers of a plugin's HTTP routes.
+pub fn with_timeout<R>(lua: &Lua, timeout: Duration, f: impl FnOnce() -> R) -> R {
+    let start = Instant::now();
+    lua.set_interrupt(move |_| match start.elapsed() > timeout {
+        true => Err(mlua::Error::RuntimeError(format!("took longer than {}ms", timeout.as_millis()))),
+        false => Ok(VmState::Continue),
+    });
+
+    let result = f();
+    lua.remove_interrupt();
+
+    result
+}
+
+/// Whether the error was caused by the memory limit, also if it was thrown in a callback.
+pub fn is_memory_error(error: &mlua::Error) -> bool {
+    match error {
+        mlua::Error::MemoryError(_) => memory_limit() > 0,
+        mlua::Error::CallbackError { cause, .. } => is_memory_error(cause),
+        _ => false,
+    }
+}
@@ -0,0 +1,19 @@
+use std::sync::Arc;
+
+use mlua::{Lua, StdLib};
+
+use super::limits;
+
+/// Create a Lua state with the standard library plugins may use, limited to the memory limit.
+///
+/// Every plugin runs in its own Lua state, which is created when the plugin is loaded and dropped when it is unloaded.
+/// Thus, plugins can't change each other's globals or libraries, an error that leaves a state broken only affects its
+/// plugin, and unloading a plugin frees all of its memory.
+pub fn create() -> Result<Arc<Lua>, mlua::Error> {
+    let lua = Lua::new();
+    lua.load_from_std_lib(StdLib::STRING | StdLib::BIT | StdLib::MATH | StdLib::TABLE)?;
+    limits::apply_memory_limit(&lua);
+
+    Ok(Arc::new(lua))
+}
+
@@ -1,7 +1,21 @@
 pub mod plugin;
 pub mod plugin_info;
 pub mod plugin_manager;
+pub mod audit;
+pub mod checksum;
+pub mod suspect;
+pub mod breakpoints;
+pub mod features;
+pub mod watcher;
+pub mod storage;
+pub mod settings;
+pub mod backup;
+pub mod persistence;
+pub mod tweaks;
+pub mod limits;
+pub mod lua_state;
+pub mod snapshot;
 mod plugin_environment;
-mod library;
+pub(crate) mod library;
 
 pub use plugin_manager::PluginManager;
\ No newline at end of file
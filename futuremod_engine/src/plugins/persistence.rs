@@ -0,0 +1,102 @@
+use std::{ffi::OsString, fs, io::Write, path::{Path, PathBuf}, thread, time::Duration};
+
+use anyhow::anyhow;
+use log::*;
+use serde::de::DeserializeOwned;
+
+use crate::teardown;
+
+use super::plugin_manager::GlobalPluginManager;
+
+/// Interval in which changes are persisted.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Start persisting changes in the background until the engine is torn down.
+///
+/// Changes are only kept in memory and written in an interval, so that plugins storing values every frame don't write every frame.
+/// Must be called after the global plugin manager was initialized.
+pub fn start() {
+    thread::spawn(|| {
+        let _worker = teardown::worker();
+
+        // The teardown persists the last changes itself
+        while teardown::sleep(PERSIST_INTERVAL) {
+            // The plugin manager is locked, so that its files aren't replaced while they are written, e.g., when a backup is restored
+            let result = GlobalPluginManager::with_plugin_manager_mut(|manager| {
+                manager.persist();
+                Ok(())
+            });
+
+            if let Err(e) = result {
+                warn!("Could not persist the plugin states: {}", e);
+            }
+        }
+    });
+}
+
+/// Path of the file with `suffix` appended to the file's name, e.g., `plugins.json.bak`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(suffix);
+
+    path.with_file_name(name)
+}
+
+/// Path of the snapshot of the file's last content that was written successfully.
+pub fn snapshot_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+/// Path of the temporary file the file's content is written to before it replaces the file.
+pub fn temporary_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".tmp")
+}
+
+/// Replace the file's content, without leaving a partially written file if the game crashes while writing.
+fn write_atomic(path: &Path, content: &[u8]) -> Result<(), anyhow::Error> {
+    let temporary = temporary_path(path);
+
+    let mut file = fs::File::create(&temporary).map_err(|e| anyhow!("could not create {}: {}", temporary.display(), e))?;
+    file.write_all(content).map_err(|e| anyhow!("could not write {}: {}", temporary.display(), e))?;
+    file.sync_all().map_err(|e| anyhow!("could not flush {}: {}", temporary.display(), e))?;
+    drop(file);
+
+    fs::rename(&temporary, path).map_err(|e| anyhow!("could not replace {}: {}", path.display(), e))
+}
+
+/// Write the content to the file and to its snapshot.
+///
+/// The file is written atomically. The snapshot is used to recover the file if it is corrupted anyway, e.g.,
+/// because the user edited it.
+pub fn write(path: &Path, content: &[u8]) -> Result<(), anyhow::Error> {
+    write_atomic(path, content)?;
+    write_atomic(&snapshot_path(path), content)
+}
+
+/// Read and parse the file, recovering it from its snapshot if it is corrupted.
+///
+/// Returns `None` if the file doesn't exist.
+/// Fails if neither the file nor its snapshot can be parsed.
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, anyhow::Error> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => return Ok(None),
+    };
+
+    let error = match serde_json::from_str(&content) {
+        Ok(value) => return Ok(Some(value)),
+        Err(e) => e,
+    };
+
+    warn!("{} is corrupted, recovering it from the last snapshot: {}", path.display(), error);
+
+    let snapshot = fs::read_to_string(snapshot_path(path)).map_err(|_| anyhow!("could not parse {} and it has no snapshot: {}", path.display(), error))?;
+    let value = serde_json::from_str(&snapshot).map_err(|e| anyhow!("could not parse {} or its snapshot: {}", path.display(), e))?;
+
+    if let Err(e) = write_atomic(path, snapshot.as_bytes()) {
+        warn!("Could not restore {} from its snapshot: {}", path.display(), e);
+    }
+    info!("Recovered {} from its snapshot", path.display());
+
+    Ok(Some(value))
+}
@@ -1,13 +1,18 @@
-use std::{fs, path::PathBuf, sync::Arc};
-use futuremod_data::plugin::{PluginError, PluginInfo};
+use std::{collections::HashMap, fs, path::PathBuf, sync::Arc, time::Duration};
+use futuremod_data::{debugger::EnvironmentEntry, plugin::{LifecycleEvent, PluginError, PluginInfo, ScriptError}};
+use futuremod_hook::native::unset_hooks_of;
 use log::*;
 use mlua::{OwnedFunction, Lua, Table, Function};
+use regex::Regex;
 use serde::{ser::SerializeStruct, Serialize};
+use crate::{captions, compatibility, crash, events::GameEvent, exec, injection, key_bindings, macros, multiplayer, net, overlay, rich_presence, run_timer::Split, session, sound, statistics, structs, viewport, widgets};
+use super::{audit, breakpoints, features, limits, lua_state, settings, storage};
+use super::library::{events as events_library, http, input, net as net_library, system as system_library, time as time_library, timer};
 use super::plugin_environment::PluginEnvironment;
 
 
 const MAIN_FILE_NAME: &str = "main";
-const ALLOWED_EXTENSIONS: [&str; 2] = ["lua", "luau"];
+pub const ALLOWED_EXTENSIONS: [&str; 2] = ["lua", "luau"];
 
 /// Installed mod plugin.
 /// 
@@ -28,17 +33,42 @@ pub struct Plugin {
     /// Plugin information such as the description and authors.
     pub info: PluginInfo,
 
-    /// Reference to lua.
+    /// Link in the plugins directory that points to the plugin's folder.
+    ///
+    /// Only set for plugins installed in developer mode, i.e., linked instead of copied into the plugins directory.
+    pub dev_link: Option<PathBuf>,
+
+    /// SHA-256 checksum of the plugin's files, pinned when the plugin was installed.
+    ///
+    /// Not set for plugins installed in developer mode, since their files change by design.
+    pub checksum: Option<String>,
+
+    /// Whether the plugin's files don't match the pinned checksum anymore.
+    pub tampered: bool,
+
+    /// Whether the game crashed shortly after the plugin was enabled.
+    pub suspect: bool,
+
+    /// The plugin's own Lua state, created anew whenever the plugin is loaded and `None` while it's unloaded.
     #[serde(skip)]
-    lua: Arc<Lua>,
+    lua: Option<Arc<Lua>>,
 }
 
 impl Into<futuremod_data::plugin::Plugin> for Plugin {
     fn into(self) -> futuremod_data::plugin::Plugin {
+        let network_approved = net::is_approved(&self.info);
+        let commands_approved = exec::is_approved(&self.info);
+
         futuremod_data::plugin::Plugin {
             enabled: self.enabled,
             state: self.state.into(),
             info: self.info.into(),
+            dev_link: self.dev_link,
+            checksum: self.checksum,
+            tampered: self.tampered,
+            suspect: self.suspect,
+            network_approved,
+            commands_approved,
         }
     }
 }
@@ -127,21 +157,68 @@ impl Plugin {
     /// into memory.
     /// 
     /// To load the plugin into memory use [`Plugin::load`].
-    pub fn new(lua: Arc<Lua>, info: PluginInfo) -> Self {
-        Plugin { info, state: PluginState::Unloaded, enabled: false, lua: lua.clone() }
+    pub fn new(info: PluginInfo) -> Self {
+        Plugin { info, state: PluginState::Unloaded, enabled: false, dev_link: None, checksum: None, tampered: false, suspect: false, lua: None }
+    }
+
+    /// The plugin's Lua state, or `None` if the plugin isn't loaded.
+    pub fn lua(&self) -> Option<&Arc<Lua>> {
+        self.lua.as_ref()
+    }
+
+    /// The plugin's Lua state, or [`PluginError::NotLoaded`] if the plugin isn't loaded.
+    fn loaded_lua(&self) -> Result<&Arc<Lua>, PluginError> {
+        self.lua.as_ref().ok_or(PluginError::NotLoaded)
     }
 
     fn set_error(&mut self, e: PluginError) -> PluginError {
+        session::record_plugin_error(&self.info);
         self.state = PluginState::Error(e.clone());
         return e;
     }
 
+    /// Create a [`PluginError::ScriptError`] with a snapshot of the plugin's state if error breakpoints are enabled.
+    fn capture_error(&self, context: &str, error: mlua::Error, arguments: Vec<String>) -> PluginError {
+        breakpoints::capture(&self.info.name, script_error(context, error), arguments)
+    }
+
+    /// Stop the plugin at the error one of its callbacks threw, so the error and its snapshot show in the GUI.
+    ///
+    /// Unloads the plugin and keeps the error as its state until the plugin is reloaded.
+    pub fn break_on_error(&mut self, error: PluginError) {
+        warn!("Plugin '{}' hit an error breakpoint, stopping it", self.info.name);
+        self.stop(error);
+    }
+
+    /// Unload the plugin and keep the error as its state until the plugin is reloaded.
+    pub fn stop(&mut self, error: PluginError) {
+        if let Err(e) = self.unload() {
+            warn!("Could not unload plugin '{}' while stopping it: {:?}", self.info.name, e);
+        }
+
+        self.state = PluginState::Error(error);
+    }
+
     /// Load the plugin.
     /// 
     /// This method will load the plugin into memory, create its environment and execute the plugin's
     /// main file.
     pub fn load(&mut self) -> Result<(), PluginError> {
         let info = &self.info;
+
+        if let Err(e) = features::check_api_version(&info.api_version) {
+            warn!("Plugin {} targets an incompatible plugin API: {}", info.name, e);
+
+            return Err(self.set_error(e));
+        }
+
+        let missing_features = features::missing_features(&info.features);
+        if !missing_features.is_empty() {
+            warn!("Plugin {} requires unsupported engine features: {}", info.name, missing_features.join(", "));
+
+            return Err(self.set_error(PluginError::MissingFeatures { features: missing_features }));
+        }
+
         let main_file = match discover_main_file(&info.path) {
             Ok(file) => file,
             Err(e) => {
@@ -151,6 +228,22 @@ impl Plugin {
             }
         };
 
+        audit::record_file(&info.name, &main_file);
+
+        key_bindings::clear(&info.name);
+        settings::reload(&info);
+        macros::cancel(&info.name);
+        injection::release(&info.name);
+        captions::clear(&info.name);
+        sound::clear(&info.name);
+        overlay::clear_plugin_lines(&info.name);
+        viewport::clear(&info.name);
+        widgets::clear(&info.name);
+        structs::clear(&info.name);
+        storage::forget(&info.name);
+        // Hooks of a previous load call into its dropped environment, e.g., if it failed before being unloaded
+        remove_hooks(&info.name);
+
         debug!("Check if file readable");
         let main_file_content = match fs::read_to_string(&main_file) {
             Ok(main_file_content) => main_file_content,
@@ -159,17 +252,29 @@ impl Plugin {
             },
         };
 
-        let environment = match PluginEnvironment::new(self.lua.clone(), &info) {
+        // Callbacks and routes of a previous load are dropped together with its state
+        let lua = match lua_state::create() {
+            Ok(lua) => lua,
+            Err(e) => {
+                return Err(self.set_error(PluginError::Error(format!("Could not create the plugin's Lua state: {:?}", e))));
+            }
+        };
+        self.lua = Some(lua.clone());
+
+        let environment = match PluginEnvironment::new(lua.clone(), &info) {
             Ok(env) => env,
             Err(e) => {
                 return Err(self.set_error(PluginError::Error(format!("Could not create mod environment: {:?}", e))));
             }
         };
 
-        match self.lua.load(main_file_content).set_environment(environment.table.clone()).exec() {
+        let _active = crash::enter_plugin(&info.name);
+
+        // Name the chunk after the file, so that errors contain the file's path
+        match lua.load(main_file_content).set_name(format!("@{}", main_file.display())).set_environment(environment.table.clone()).exec() {
             Ok(_) => (),
             Err(e) => {
-                return Err(self.set_error(PluginError::ScriptError(format!("Could not load module: {:?}", e))));
+                return Err(self.set_error(self.capture_error("Could not load module", e, Vec::new())));
             },
         };
 
@@ -198,7 +303,7 @@ impl Plugin {
                 Ok(_) => debug!("Successfully called onLoad"),
                 Err(e) => {
                     warn!("Main function threw error: {:?}", e);
-                    return Err(self.set_error(PluginError::ScriptError(format!("Error while executing onLoad function: {:?}", e))));
+                    return Err(self.set_error(self.capture_error("Error while executing onLoad function", e, Vec::new())));
                 },
             },
             None => (),
@@ -212,8 +317,8 @@ impl Plugin {
     /// Unload the plugin.
     /// 
     /// If the plugin is enabled, this function will additionally disable the plugin first.
-    /// Unloading the plugin will remove the plugin from memory.
-    /// *Should be tested to what extend this actually removes the plugin from memory.*
+    /// Calls the plugin's `onUnload` function before its hooks are removed.
+    /// Unloading the plugin drops its Lua state, which frees all of the plugin's memory.
     pub fn unload(&mut self) -> Result<(), PluginError> {
         match &self.state {
             PluginState::Loaded(_) => (),
@@ -226,12 +331,23 @@ impl Plugin {
             }
         }
 
-        // This should drop `environment`, thus also dropping all functions and data stored
-        // in the plugin's environment.
-        self.state = PluginState::Unloaded;
+        if let PluginState::Loaded(context) = &self.state {
+            if let Some(on_unload) = &context.on_unload {
+                let _active = crash::enter_plugin(&self.info.name);
+                if let Err(e) = on_unload.call::<_, ()>(()) {
+                    warn!("Plugin '{}' threw an error in its onUnload function: {:?}", self.info.name, self.capture_error("Error while executing onUnload function", e, Vec::new()));
+                }
+            }
+        }
+
+        // Hooks must be removed before the plugin's environment is dropped, as they would call into it
+        remove_hooks(&self.info.name);
+        overlay::clear_plugin_lines(&self.info.name);
+        widgets::clear(&self.info.name);
 
-        self.lua.gc_collect().map_err(|e| PluginError::ScriptError(format!("{:?}", e)))?;
-        self.lua.gc_collect().map_err(|e| PluginError::ScriptError(format!("{:?}", e)))?;
+        // Dropping the environment and the state drops all functions, callbacks, and data of the plugin
+        self.state = PluginState::Unloaded;
+        self.lua = None;
 
         Ok(())
     }
@@ -266,9 +382,25 @@ impl Plugin {
         match &self.state {
             PluginState::Loaded(context) => {
                 self.enabled = false;
+                statistics::on_plugin_disabled(&self.info.name);
+                rich_presence::clear_overrides(&self.info.name);
+                multiplayer::set_data(&self.info.name, None);
+                macros::cancel(&self.info.name);
+                injection::release(&self.info.name);
+                captions::clear(&self.info.name);
+                sound::stop(&self.info.name);
+                if let Some(lua) = &self.lua {
+                    if let Err(e) = time_library::clear_timers(lua, &self.info.name) {
+                        warn!("Could not cancel the timers of plugin {}: {}", self.info.name, e);
+                    }
+                    if let Err(e) = events_library::clear_subscriptions(lua, &self.info.name) {
+                        warn!("Could not remove the event subscriptions of plugin {}: {}", self.info.name, e);
+                    }
+                }
 
                 if let Some(on_disabled) = &context.on_disable {
-                    on_disabled.call(()).map_err(|e| PluginError::ScriptError(e.to_string()))?;
+                    let _active = crash::enter_plugin(&self.info.name);
+                    on_disabled.call(()).map_err(|e| self.capture_error("Error while executing onDisable function", e, Vec::new()))?;
                 }
             },
             _ => (),
@@ -289,8 +421,12 @@ impl Plugin {
             PluginState::Loaded(context) => {
                 self.enabled = true;
 
+                compatibility::record_session(&self.info);
+                statistics::on_plugin_enabled(&self.info.name);
+
                 if let Some(on_enabled) = &context.on_enable {
-                    on_enabled.call(()).map_err(|e| PluginError::ScriptError(e.to_string()))?;
+                    let _active = crash::enter_plugin(&self.info.name);
+                    on_enabled.call(()).map_err(|e| self.capture_error("Error while executing onEnable function", e, Vec::new()))?;
                 }
             },
             _ => {
@@ -302,10 +438,37 @@ impl Plugin {
         Ok(())
     }
 
+    /// Call the plugin's `onInstall` function after the plugin was installed and loaded.
+    ///
+    /// `previous_version` is the version the plugin was updated from, `None` if it was newly installed.
+    pub fn on_install(&self, previous_version: Option<&str>) -> Result<(), PluginError> {
+        match &self.state {
+            PluginState::Loaded(PluginContext { on_install: Some(on_install), .. }) => {
+                let _active = crash::enter_plugin(&self.info.name);
+                on_install.call::<_, ()>(previous_version).map_err(|e| self.capture_error("Error while executing onInstall function", e, Vec::new()))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Call the plugin's `onUninstall` function before the plugin is uninstalled or replaced by another version.
+    ///
+    /// `next_version` is the version the plugin is updated to, `None` if it is uninstalled.
+    pub fn on_uninstall(&self, next_version: Option<&str>) -> Result<(), PluginError> {
+        match &self.state {
+            PluginState::Loaded(PluginContext { on_uninstall: Some(on_uninstall), .. }) => {
+                let _active = crash::enter_plugin(&self.info.name);
+                on_uninstall.call::<_, ()>(next_version).map_err(|e| self.capture_error("Error while executing onUninstall function", e, Vec::new()))
+            },
+            _ => Ok(()),
+        }
+    }
+
     /// Call the plugin's `onUpdate` function.
     /// 
     /// Returns an error if the plugin is not enabled.
     /// Will not call the function if the plugin is in an error state.
+    /// Returns [`PluginError::LimitExceeded`] if the call exceeded the instruction budget or the memory limit.
     pub fn on_update(&self) -> Result<(), PluginError> {
         if !self.enabled {
             return Err(PluginError::NotEnabledError);
@@ -315,7 +478,15 @@ impl Plugin {
             PluginState::Loaded(context) => {
                 if let Some(on_update) = &context.on_update {
                     debug!("Plugin '{}': Calling on_update", self.info.name);
-                    on_update.call(()).map_err(|e| PluginError::ScriptError(e.to_string()))?;
+                    let _active = crash::enter_plugin(&self.info.name);
+                    let budget = limits::update_budget();
+
+                    match limits::with_budget(self.loaded_lua()?, budget, || on_update.call::<_, ()>(())) {
+                        (Err(_), true) => return Err(PluginError::LimitExceeded(format!("its budget of {} instructions per onUpdate call", budget))),
+                        (Err(e), _) if limits::is_memory_error(&e) => return Err(PluginError::LimitExceeded(format!("the memory limit of {} MB", limits::memory_limit()))),
+                        (Err(e), _) => return Err(self.capture_error("Error while executing onUpdate function", e, Vec::new())),
+                        (Ok(_), _) => (),
+                    }
                     debug!("Plugin '{}: Called on_update", self.info.name);
                 } else {
                     debug!("Plugin '{}': on_update not set", self.info.name);
@@ -327,10 +498,191 @@ impl Plugin {
         Ok(())
     }
 
+    /// Call the plugin's handler of the HTTP route.
+    ///
+    /// Returns `None` if the plugin didn't register the route.
+    /// Only enabled plugins serve routes.
+    /// The handler is aborted with an error once it ran longer than the timeout.
+    pub fn handle_http_request(&self, path: &str, query: HashMap<String, String>, timeout: Duration) -> Result<Option<serde_json::Value>, PluginError> {
+        if !self.enabled {
+            return Err(PluginError::NotEnabledError);
+        }
+
+        let lua = self.loaded_lua()?;
+        let _active = crash::enter_plugin(&self.info.name);
+        limits::with_timeout(lua, timeout, || http::handle_request(lua, &self.info.name, path, query.clone()))
+            .map_err(|e| self.capture_error(&format!("Error while handling the HTTP request to '{}'", path), e, vec![path.to_string(), format!("{:?}", query)]))
+    }
+
+    /// Call the callbacks of the plugin's timers that are due.
+    pub fn on_timers(&self, now: f64) -> Result<(), PluginError> {
+        if !self.enabled {
+            return Err(PluginError::NotEnabledError);
+        }
+
+        let lua = self.loaded_lua()?;
+        let _active = crash::enter_plugin(&self.info.name);
+        time_library::dispatch_timers(lua, &self.info.name, now)
+            .map_err(|e| self.capture_error("Error while executing a timer", e, vec![format!("{}", now)]))
+    }
+
+    /// Call the plugin's callbacks of the game event.
+    pub fn on_game_event(&self, event: &GameEvent) -> Result<(), PluginError> {
+        if !self.enabled {
+            return Err(PluginError::NotEnabledError);
+        }
+
+        let lua = self.loaded_lua()?;
+        let _active = crash::enter_plugin(&self.info.name);
+        events_library::dispatch_event(lua, &self.info.name, event)
+            .map_err(|e| self.capture_error(&format!("Error while handling event {}", event.name()), e, vec![format!("{:?}", event)]))
+    }
+
+    /// Call the plugin's split callbacks.
+    pub fn on_split(&self, split: &Split) -> Result<(), PluginError> {
+        if !self.enabled {
+            return Err(PluginError::NotEnabledError);
+        }
+
+        let lua = self.loaded_lua()?;
+        let _active = crash::enter_plugin(&self.info.name);
+        timer::dispatch_split(lua, &self.info.name, split)
+            .map_err(|e| self.capture_error("Error while executing a split callback", e, vec![format!("{:?}", split)]))
+    }
+
+    /// Call the plugin's callbacks of the pressed key.
+    pub fn on_key_down(&self, key: &str) -> Result<(), PluginError> {
+        if !self.enabled {
+            return Err(PluginError::NotEnabledError);
+        }
+
+        let lua = self.loaded_lua()?;
+        let _active = crash::enter_plugin(&self.info.name);
+        input::dispatch_key_down(lua, &self.info.name, key)
+            .map_err(|e| self.capture_error("Error while executing a key callback", e, vec![key.to_string()]))
+    }
+
+    /// Call the plugin's callback of the request with its response.
+    pub fn on_net_response(&self, response: &net::Response) -> Result<(), PluginError> {
+        if !self.enabled {
+            return Err(PluginError::NotEnabledError);
+        }
+
+        let lua = self.loaded_lua()?;
+        let _active = crash::enter_plugin(&self.info.name);
+        net_library::dispatch_response(lua, &self.info.name, response)
+            .map_err(|e| self.capture_error("Error while executing a request callback", e, vec![format!("{:?}", response.result)]))
+    }
+
+    /// Call the plugin's callback of the program with its output.
+    pub fn on_exec_result(&self, result: &exec::ExecResult) -> Result<(), PluginError> {
+        if !self.enabled {
+            return Err(PluginError::NotEnabledError);
+        }
+
+        let lua = self.loaded_lua()?;
+        let _active = crash::enter_plugin(&self.info.name);
+        system_library::dispatch_result(lua, &self.info.name, result)
+            .map_err(|e| self.capture_error("Error while executing a program callback", e, vec![format!("{:?}", result.result)]))
+    }
+
+    /// Globals the plugin set in its environment, e.g., to inspect them in the GUI's debugger.
+    pub fn inspect_environment(&self) -> Result<Vec<EnvironmentEntry>, PluginError> {
+        match &self.state {
+            PluginState::Loaded(context) => context.environment
+                .inspect(self.loaded_lua()?)
+                .map_err(|e| script_error("Could not inspect the environment", e)),
+            _ => Err(PluginError::NotLoaded),
+        }
+    }
+
+    /// Globals of the loaded plugin, e.g., to run a developer's Lua chunk in them.
+    pub fn environment(&self) -> Result<&PluginEnvironment, PluginError> {
+        match &self.state {
+            PluginState::Loaded(context) => Ok(&context.environment),
+            _ => Err(PluginError::NotLoaded),
+        }
+    }
+
     /// Whether the plugin is enabled or not.
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    /// Call the plugin's lifecycle function without changing the plugin's state.
+    ///
+    /// Allows developers to exercise a plugin's lifecycle code without, e.g., starting a mission.
+    /// Returns `false` if the plugin doesn't define the function.
+    pub fn invoke_lifecycle(&self, event: LifecycleEvent) -> Result<bool, PluginError> {
+        let context = match &self.state {
+            PluginState::Loaded(context) => context,
+            _ => return Err(PluginError::NotLoaded),
+        };
+
+        let function = match event {
+            LifecycleEvent::Load => &context.on_load,
+            LifecycleEvent::Enable => &context.on_enable,
+            LifecycleEvent::Disable => &context.on_disable,
+            LifecycleEvent::Update => &context.on_update,
+            LifecycleEvent::Unload => &context.on_unload,
+        };
+
+        match function {
+            Some(function) => {
+                info!("Plugin '{}': manually calling {}", self.info.name, event);
+                let _active = crash::enter_plugin(&self.info.name);
+                function.call::<_, ()>(()).map_err(|e| self.capture_error(&format!("Error while executing {} function", event), e, Vec::new()))?;
+
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+}
+
+lazy_static! {
+    /// Matches the location of a Lua error, e.g., `C:\plugins\example\main.lua:12:`.
+    static ref ERROR_LOCATION: Regex = Regex::new(r"((?:[A-Za-z]:)?[^:\n]*?\.luau?):(\d+):").unwrap();
+}
+
+const TRACEBACK_MARKER: &str = "stack traceback:";
+
+/// Create a [`PluginError::ScriptError`] from a Lua error.
+///
+/// Extracts the error's file, line, and stack traceback from the message, if it contains them.
+pub fn script_error(context: &str, error: mlua::Error) -> PluginError {
+    let error = error.to_string();
+
+    let (message, traceback) = match error.find(TRACEBACK_MARKER) {
+        Some(index) => (error[..index].trim().to_string(), Some(error[index..].trim().to_string())),
+        None => (error.trim().to_string(), None),
+    };
+
+    let (file, line) = match ERROR_LOCATION.captures(&message) {
+        Some(captures) => (
+            Some(PathBuf::from(captures[1].trim())),
+            captures[2].parse().ok(),
+        ),
+        None => (None, None),
+    };
+
+    PluginError::ScriptError(ScriptError {
+        context: context.to_string(),
+        message,
+        file,
+        line,
+        traceback,
+        snapshot: None,
+    })
+}
+
+/// Remove all hooks the plugin set with the `dangerous` library.
+fn remove_hooks(name: &str) {
+    let removed = unsafe { unset_hooks_of(name) };
+
+    if removed > 0 {
+        info!("Removed {} hooks of plugin {}", removed, name);
+    }
 }
 
 fn get_lua_function_or_none<'lua>(module: &'lua Table, name: &str) -> Option<OwnedFunction> {
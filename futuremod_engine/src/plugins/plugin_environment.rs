@@ -2,9 +2,10 @@ use std::{collections::HashMap, fmt::Debug, fs, path::{Path, PathBuf}, sync::{Ar
 
 use anyhow::bail;
 use log::*;
-use mlua::{Lua, OwnedTable};
-use futuremod_data::plugin::{PluginInfo, PluginDependency};
-use super::library::{dangerous::create_dangerous_library, game::create_game_library, input::create_input_library, matrix::create_matrix_library, system::create_system_library, ui::create_ui_library};
+use mlua::{Lua, LuaSerdeExt, OwnedTable};
+use futuremod_data::{debugger::EnvironmentEntry, plugin::{PluginInfo, PluginDependency}};
+use super::audit;
+use super::library::{captions::create_captions_library, config::create_config_library, dangerous::create_dangerous_library, events::create_events_library, game::create_game_library, http::create_http_library, input::create_input_library, macros::create_macro_library, matrix::create_matrix_library, multiplayer::create_multiplayer_library, net::create_net_library, presence::create_presence_library, replay::create_replay_library, sound::create_sound_library, storage::create_storage_library, structs::create_structs_library, system::create_system_library, time::create_time_library, timer::create_timer_library, ui::create_ui_library};
 
 /// Holds the entire plugin environment.
 /// 
@@ -44,6 +45,12 @@ impl Type {
 
 const MAX_STRING: u16 = 1024;
 
+/// Values of inspected globals are truncated to this many characters.
+const MAX_VALUE_LENGTH: usize = 200;
+
+/// Number of entries of an inspected table that are listed.
+const MAX_TABLE_FIELDS: usize = 100;
+
 unsafe fn raw_to_lua<'a>(lua: &'a Lua, lua_type: Type, raw_value: u32) -> Result<mlua::Value<'a>, mlua::Error> {
   let value = match lua_type {
     Type::Integer => mlua::Value::Integer(raw_value as i32),
@@ -95,20 +102,51 @@ unsafe fn lua_to_raw<'a>(lua_type: Type, lua_value: &'a mlua::Value) -> Result<V
 /// Prepare available libraries based on the plugin information.
 /// 
 /// For each library mentioned in the plugin's information, this function
-/// will initialize the library and add it to the library list.
+/// will initialize the library and add it to the library list, if the plugin
+/// is granted the library's permissions.
 fn prepare_libraries(lua: Arc<Lua>, info: &PluginInfo) -> Result<HashMap<&'static str, mlua::OwnedTable>, mlua::Error> {
   let mut libraries = HashMap::new();
 
   let globals = lua.globals();
+  let permissions = info.granted_permissions();
 
   for library in info.dependencies.iter() {
+    // Libraries the plugin lacks the permissions for are absent, as if the plugin never requested them
+    if !library.is_permitted(&permissions) {
+      let required: Vec<String> = library.permissions().iter().map(ToString::to_string).collect();
+      warn!("Plugin '{}' requires the library '{}' without requesting its permissions: {}", info.name, library.library_name(), required.join(", "));
+      continue;
+    }
+
     match library {
-      PluginDependency::Dangerous => libraries.insert("dangerous", create_dangerous_library(lua.clone())?),
-      PluginDependency::Game => libraries.insert("game", create_game_library(lua.clone())?),
-      PluginDependency::Input => libraries.insert("input", create_input_library(lua.clone())?),
-      PluginDependency::UI => libraries.insert("ui", create_ui_library(lua.clone())?),
-      PluginDependency::System => libraries.insert("system", create_system_library(lua.clone())?),
-      PluginDependency::Matrix => libraries.insert("matrix", create_matrix_library(lua.clone())?),
+      PluginDependency::Dangerous => {
+        let library = create_dangerous_library(lua.clone(), &info.name, &permissions)?.table;
+
+        if audit::is_enabled() {
+          audit::instrument_library(&lua, &info.name, &library.to_ref())?;
+        }
+
+        libraries.insert("dangerous", library)
+      },
+      PluginDependency::Game => libraries.insert("game", create_game_library(lua.clone(), &permissions)?.table),
+      PluginDependency::Input => libraries.insert("input", create_input_library(lua.clone(), &info.name)?.table),
+      PluginDependency::UI => libraries.insert("ui", create_ui_library(lua.clone(), &info.name)?.table),
+      PluginDependency::System => libraries.insert("system", create_system_library(lua.clone(), info, &permissions)?.table),
+      PluginDependency::Matrix => libraries.insert("matrix", create_matrix_library(lua.clone())?.table),
+      PluginDependency::Http => libraries.insert("http", create_http_library(lua.clone(), &info.name)?.table),
+      PluginDependency::Replay => libraries.insert("replay", create_replay_library(lua.clone())?.table),
+      PluginDependency::Timer => libraries.insert("timer", create_timer_library(lua.clone(), &info.name)?.table),
+      PluginDependency::Presence => libraries.insert("presence", create_presence_library(lua.clone(), &info.name)?.table),
+      PluginDependency::Multiplayer => libraries.insert("multiplayer", create_multiplayer_library(lua.clone(), &info.name)?.table),
+      PluginDependency::Macro => libraries.insert("macro", create_macro_library(lua.clone(), &info.name)?.table),
+      PluginDependency::Storage => libraries.insert("storage", create_storage_library(lua.clone(), &info.name, &info.path)?.table),
+      PluginDependency::Captions => libraries.insert("captions", create_captions_library(lua.clone(), &info.name)?.table),
+      PluginDependency::Config => libraries.insert("config", create_config_library(lua.clone(), info)?.table),
+      PluginDependency::Structs => libraries.insert("structs", create_structs_library(lua.clone())?.table),
+      PluginDependency::Net => libraries.insert("net", create_net_library(lua.clone(), info)?.table),
+      PluginDependency::Time => libraries.insert("time", create_time_library(lua.clone(), &info.name)?.table),
+      PluginDependency::Events => libraries.insert("events", create_events_library(lua.clone(), &info.name)?.table),
+      PluginDependency::Sound => libraries.insert("sound", create_sound_library(lua.clone(), &info.name, &info.path)?.table),
       PluginDependency::Math => libraries.insert("math", globals.get("math").to_owned()?),
       PluginDependency::Bit32 => libraries.insert("bit32", globals.get("bit32").to_owned()?),
       PluginDependency::String => libraries.insert("string", globals.get("string").to_owned()?),
@@ -152,6 +190,36 @@ fn add_default_globals(table: &mlua::Table, globals: &mlua::Table) -> Result<(),
   Ok(())
 }
 
+/// Create the plugin's `print` function.
+/// 
+/// Instead of writing to stdout, which nobody can see while the mod is injected into the game,
+/// every call is turned into a log record at INFO level.
+/// The record's target is `plugin::<name>` and it carries the plugin's name in the key `plugin`
+/// such that the GUI can attribute the message to the plugin and filter for it.
+/// 
+/// Like Lua's `print`, it accepts any number of values and joins them with tabs.
+fn create_print_function<'lua>(lua: &'lua Lua, plugin_name: &str) -> Result<mlua::Function<'lua>, mlua::Error> {
+  let plugin_name = plugin_name.to_string();
+  let target = format!("plugin::{}", plugin_name);
+
+  lua.create_function(move |_, values: mlua::Variadic<mlua::Value>| {
+    // Convert every value into a string.
+    // If a value cannot be converted to string, use it's debug representation
+    let msg = values
+      .iter()
+      .map(|value| match value.to_string() {
+        Ok(value) => value,
+        Err(_) => format!("{:?}", value),
+      })
+      .collect::<Vec<String>>()
+      .join("\t");
+
+    info!(target: target.as_str(), plugin:% = plugin_name; "{}", msg);
+
+    Ok(())
+  })
+}
+
 impl PluginEnvironment {
   /// Create a new plugin environment for a plugin with the given information.
   pub fn new(lua: Arc<Lua>, plugin_info: &PluginInfo) -> Result<Self, mlua::Error> {
@@ -161,20 +229,7 @@ impl PluginEnvironment {
     table.set("NAME", plugin_info.name.clone())?;
 
     // Create and set functions
-    let print_target = plugin_info.name.to_string();
-    let print_fn = lua.create_function(move |_, msg: mlua::Value| {
-      // Convert the message into a string.
-      // If the value cannot be converted to string, use it's debug representation
-      let msg = match msg.to_string() {
-        Ok(msg) => msg,
-        Err(_) => format!("{:?}", msg),
-      };
-      let plugin_name = print_target.clone();
-
-      info!(target: format!("plugin::{}", print_target).as_str(), plugin:% = plugin_name; "{}", msg);
-
-      Ok(())
-    })?;
+    let print_fn = create_print_function(&lua, &plugin_info.name)?;
 
     let libraries = prepare_libraries(lua.clone(), &plugin_info)?;
     let package_cache: Arc<Mutex<HashMap<PathBuf, OwnedTable>>> = Arc::new(Mutex::new(HashMap::new()));
@@ -231,9 +286,13 @@ impl PluginEnvironment {
       debug!("Preparing plugin environment for required file");
       let file_environment = PluginEnvironment::new(lua_ref.clone(), &plugin_info_clone)?;
 
+      audit::record_file(&plugin_name, &absolute_require_path);
+
       // Read the file content
       let content = fs::read_to_string(&absolute_require_path).map_err(|e| mlua::Error::RuntimeError(format!("Could not require file: {:?}", e)))?;
-      let file_chunk = lua.load(content).set_environment(file_environment.table.clone());
+      let file_chunk = lua.load(content)
+        .set_name(format!("@{}", absolute_require_path.display()))
+        .set_environment(file_environment.table.clone());
 
       debug!("Executing required file");
       file_chunk.exec()?;
@@ -253,6 +312,71 @@ impl PluginEnvironment {
     Ok(PluginEnvironment { table: table.into_owned(), package_cache })
   }
 
+  /// Globals the plugin set in its environment ordered by their name, e.g., to inspect them in the GUI's debugger.
+  ///
+  /// Globals the environment provides, such as `print`, are left out. Tables list their entries one level deep.
+  /// Luau can't access the upvalues of functions, so they aren't listed.
+  pub fn inspect(&self, lua: &Lua) -> Result<Vec<EnvironmentEntry>, mlua::Error> {
+    let mut entries = Vec::new();
+
+    for pair in self.table.to_ref().pairs::<mlua::Value, mlua::Value>() {
+      let (key, value) = pair?;
+      let name = key_name(&key)?;
+
+      if DEFAULT_GLOBALS.contains(&name.as_str()) || name == "print" || name == "require" {
+        continue;
+      }
+
+      let mut entry = describe_entry(lua, name, &value)?;
+
+      if let mlua::Value::Table(table) = &value {
+        for pair in table.clone().pairs::<mlua::Value, mlua::Value>().take(MAX_TABLE_FIELDS) {
+          let (key, value) = pair?;
+          entry.fields.push(describe_entry(lua, key_name(&key)?, &value)?);
+        }
+      }
+
+      entries.push(entry);
+    }
+
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(entries)
+  }
+}
+
+fn key_name(key: &mlua::Value) -> Result<String, mlua::Error> {
+  match key {
+    mlua::Value::String(key) => Ok(key.to_string_lossy().to_string()),
+    key => Ok(format!("[{}]", key.to_string()?)),
+  }
+}
+
+fn describe_entry(lua: &Lua, name: String, value: &mlua::Value) -> Result<EnvironmentEntry, mlua::Error> {
+  let description = match value {
+    mlua::Value::String(string) => format!("{:?}", string.to_string_lossy()),
+    mlua::Value::Function(function) => {
+      let info = function.info();
+
+      match (info.short_src, info.line_defined) {
+        (Some(source), Some(line)) => format!("defined at {}:{}", source, line),
+        _ => String::from("native function"),
+      }
+    },
+    // Tables with values JSON can't represent, e.g., functions, are described like other values
+    mlua::Value::Table(_) => match lua.from_value::<serde_json::Value>(value.clone()) {
+      Ok(json) => json.to_string(),
+      Err(_) => value.to_string()?,
+    },
+    value => value.to_string()?,
+  };
+
+  Ok(EnvironmentEntry {
+    name,
+    value_type: value.type_name().to_string(),
+    value: description.chars().take(MAX_VALUE_LENGTH).collect(),
+    fields: Vec::new(),
+  })
 }
 
 impl Debug for PluginEnvironment {
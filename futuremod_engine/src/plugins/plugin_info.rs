@@ -39,6 +39,24 @@ pub fn load_plugin_info(path: PathBuf) -> Result<futuremod_data::plugin::PluginI
       authors: plugin_info.authors,
       version: plugin_info.version,
       dependencies: plugin_info.dependencies,
+      permissions: plugin_info.permissions,
+      hosts: plugin_info.hosts,
+      commands: plugin_info.commands,
       description: plugin_info.description,
+      features: plugin_info.features,
+      api_version: plugin_info.api_version,
+      settings: plugin_info.settings,
+      translations: plugin_info.translations,
+      display_name: None,
     })
-  }
\ No newline at end of file
+  }
+
+/// Get the link if the plugin folder is a symbolic link or junction.
+///
+/// Plugins installed in developer mode are linked into the plugins directory instead of copied.
+pub fn get_dev_link(path: &Path) -> Option<PathBuf> {
+    match fs::symlink_metadata(path) {
+      Ok(metadata) if metadata.file_type().is_symlink() => Some(path.to_path_buf()),
+      _ => None,
+    }
+}
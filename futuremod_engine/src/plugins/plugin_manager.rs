@@ -1,19 +1,25 @@
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
 use std::{collections::HashMap, fs};
-use futuremod_data::plugin::PluginError;
+use futuremod_data::{debugger::{ChunkResult, EnvironmentEntry}, plugin::{LifecycleEvent, PluginDependency, PluginError, PluginInfo}};
 use log::*;
-use mlua::{Lua, StdLib};
+use mlua::{Lua, LuaSerdeExt, MultiValue, OwnedTable, Variadic, VmState};
 use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
-use crate::plugins::plugin_info::load_plugin_info;
-use regex::Regex;
+use crate::{bookmarks, compatibility, config::TweakConfig, crash, events::GameEvent, exec, gc, key_bindings, net, run_timer::Split, session, session_recording, statistics, watches};
+use crate::plugins::plugin_info::{get_dev_link, load_plugin_info};
 use anyhow::{anyhow, bail};
 
+use super::checksum::{self, PluginChecksums};
+use super::suspect::{self, PluginSuspects};
+use super::{backup, breakpoints, limits, lua_state, persistence, settings, snapshot, storage};
 use super::plugin::*;
+use super::plugin_environment::PluginEnvironment;
+use super::tweaks::Tweak;
 use super::plugin_info::PluginInfoError;
 
-static mut GLOBAL_PLUGIN_MANAGER: OnceLock<Arc<Mutex<PluginManager>>> = OnceLock::new();
+static mut GLOBAL_PLUGIN_MANAGER: OnceLock<Arc<RwLock<PluginManager>>> = OnceLock::new();
 
 /// Global plugin manager.
 /// 
@@ -24,31 +30,48 @@ static mut GLOBAL_PLUGIN_MANAGER: OnceLock<Arc<Mutex<PluginManager>>> = OnceLock
 /// struct and its methods instead.
 /// 
 /// This struct is initialized (or should at least) the start of mod's lifecycle.
+///
+/// The plugin manager is behind a read-write lock. The game loop holds the write lock while updating the plugins,
+/// thus, endpoints that only show the plugins read the [`snapshot`] instead.
 pub struct GlobalPluginManager;
 
 impl GlobalPluginManager {
-    pub fn get() -> Arc<Mutex<PluginManager>> {
+    pub fn get() -> Arc<RwLock<PluginManager>> {
         let plugin_manager;
         unsafe {plugin_manager = GLOBAL_PLUGIN_MANAGER.get().unwrap()};
 
         return plugin_manager.clone();
     }
 
+    /// Get the global plugin manager if it was already initialized.
+    /// 
+    /// Unlike [`GlobalPluginManager::get`], this function doesn't panic if the plugin manager
+    /// wasn't initialized yet.
+    pub fn try_get() -> Option<Arc<RwLock<PluginManager>>> {
+        unsafe { GLOBAL_PLUGIN_MANAGER.get().cloned() }
+    }
+
     pub fn with_plugin_manager<F, R>(f: F) -> Result<R, anyhow::Error>
     where F: Fn(&PluginManager) -> Result<R, anyhow::Error> {
-        match GlobalPluginManager::get().lock() {
+        match GlobalPluginManager::get().read() {
             Ok(m) => f(&m),
             Err(e) => return Err(anyhow!("could not get lock to plugin manager: {:?}", e)),
         }
     }
 
+    /// Call the function with the plugin manager's write lock, and publish the [`snapshot`] of the changed plugins.
     pub fn with_plugin_manager_mut<F, R>(f: F) -> Result<R, anyhow::Error>
     where F: Fn(&mut PluginManager) -> Result<R, anyhow::Error> {
         let plugin_manager;
         unsafe {plugin_manager = GLOBAL_PLUGIN_MANAGER.get().unwrap()}
 
-        match plugin_manager.lock() {
-            Ok(mut m) => f(&mut m),
+        match plugin_manager.write() {
+            Ok(mut m) => {
+                let result = f(&mut m);
+                snapshot::publish(&m);
+
+                result
+            },
             Err(e) => return Err(anyhow!("could not get mutable lock to plugin manager: {:?}", e)),
         }
     }
@@ -65,7 +88,9 @@ impl GlobalPluginManager {
                 anyhow::bail!("{:?}", e)
             }
         };
-        let p = Arc::new(Mutex::new(plugin_manager));
+        snapshot::publish(&plugin_manager);
+
+        let p = Arc::new(RwLock::new(plugin_manager));
         unsafe { GLOBAL_PLUGIN_MANAGER.set(p).map_err(|_| anyhow!("global plugin manager already initialized")) }
     }
 }
@@ -77,6 +102,10 @@ pub enum PluginManagerError {
     Plugin(PluginError),
     Other(String),
     AlreadyLoaded,
+    /// The plugin wasn't installed in developer mode.
+    NotDevPlugin,
+    /// The plugin's files don't match the checksum pinned when it was installed.
+    Tampered,
 }
 
 #[derive(Debug)]
@@ -85,6 +114,10 @@ pub enum PluginInstallError {
     InvalidName,
     Copy(String),
     AlreadyInstalled,
+    /// The plugin to update isn't installed.
+    NotInstalled,
+    /// The plugin to update is installed in developer mode, i.e., updated by changing its source folder.
+    Linked,
     Plugin(String),
 }
 
@@ -106,18 +139,20 @@ enum PersistentPluginState {
 struct PersistentPluginStates {
     states: HashMap<String, PersistentPluginState>,
     path: PathBuf,
+    /// Whether the states changed since they were last written, see [`persistence`].
+    #[serde(skip)]
+    changed: bool,
 }
 
 impl PersistentPluginStates {
     pub fn new(path: &Path) -> Result<PersistentPluginStates, anyhow::Error> {
         debug!("Reading plugin states from '{}'", path.display());
 
-        let states: HashMap<String, PersistentPluginState> = match fs::read_to_string(path) {
-            Ok(content) => serde_json::from_str(&content).map_err(|e| anyhow!("could not parse the plugin states file: {}", e.to_string()))?,
-            Err(_) => HashMap::new(),
-        };
+        let states: HashMap<String, PersistentPluginState> = persistence::read(path)
+            .map_err(|e| anyhow!("could not read the plugin states file: {}", e))?
+            .unwrap_or_default();
 
-        Ok(PersistentPluginStates { states, path: path.to_path_buf() })
+        Ok(PersistentPluginStates { states, path: path.to_path_buf(), changed: false })
     }
 
     pub fn get_state(&self, name: &str) -> Option<&PersistentPluginState> {
@@ -126,8 +161,9 @@ impl PersistentPluginStates {
 
     pub fn insert(&mut self, name: &str, state: PersistentPluginState) -> Result<(), anyhow::Error>{
         self.states.insert(name.into(), state);
+        self.changed = true;
 
-        self.write_to_file()
+        Ok(())
     }
 
     pub fn update(&mut self, name: &str, state: PersistentPluginState) -> Result<(), anyhow::Error> {
@@ -137,20 +173,30 @@ impl PersistentPluginStates {
         };
 
         *plugin_state = state;
+        self.changed = true;
 
-        self.write_to_file()
+        Ok(())
     }
 
-    pub fn write_to_file(&self) -> Result<(), anyhow::Error> {
+    /// Write the states to the file if they changed since they were last written.
+    pub fn write_to_file(&mut self) -> Result<(), anyhow::Error> {
+        if !self.changed {
+            return Ok(());
+        }
+
         let content = serde_json::to_string(&self.states).map_err(|e| anyhow!("could not serialize plugin states to string: {}", e.to_string()))?;
 
-        fs::write(&self.path, content).map_err(|e| anyhow!("could not persist change: {}", e.to_string()))
+        persistence::write(&self.path, content.as_bytes()).map_err(|e| anyhow!("could not persist change: {}", e.to_string()))?;
+        self.changed = false;
+
+        Ok(())
     }
 
     pub fn remove(&mut self, name: &str) -> Result<(), anyhow::Error> {
         self.states.remove(name);
+        self.changed = true;
 
-        self.write_to_file()
+        Ok(())
     }
 }
 
@@ -161,6 +207,16 @@ fn persist_plugin_state_change(states: &mut PersistentPluginStates, plugin: &Plu
     }
 }
 
+/// Stop the plugin at the error its callback threw, if error breakpoints are enabled.
+fn break_on_error(plugin: &mut Plugin, error: PluginError) {
+    if !breakpoints::is_enabled() {
+        return;
+    }
+
+    // Keep the persisted state, so the plugin starts enabled again after the error is fixed
+    plugin.break_on_error(error);
+}
+
 fn remove_plugin_from_persistence(states: &mut PersistentPluginStates, plugin_name: &str) {
     debug!("Removing plugin {} from persistence", plugin_name);
     if let Err(e) = states.remove(&plugin_name) {
@@ -168,6 +224,49 @@ fn remove_plugin_from_persistence(states: &mut PersistentPluginStates, plugin_na
     }
 }
 
+/// Compute the checksum of the plugin's files and pin it, replacing the previously pinned checksum.
+fn pin_plugin_checksum(checksums: &mut PluginChecksums, plugin: &mut Plugin) -> Result<(), anyhow::Error> {
+    let checksum = checksum::compute(&plugin.info.path)?;
+    debug!("Pinning checksum {} of plugin {}", checksum, plugin.info.name);
+
+    checksums.insert(&plugin.info.name, checksum.clone())?;
+    plugin.checksum = Some(checksum);
+    plugin.tampered = false;
+
+    Ok(())
+}
+
+/// Verify the plugin's files against the pinned checksum.
+///
+/// Marks the plugin as tampered if they don't match.
+/// Plugins without a pinned checksum, e.g., installed before checksums existed, get their current checksum pinned.
+/// Plugins installed in developer mode are not verified.
+fn verify_plugin_checksum(checksums: &mut PluginChecksums, plugin: &mut Plugin) -> Result<(), PluginManagerError> {
+    if plugin.dev_link.is_some() {
+        return Ok(());
+    }
+
+    let expected = match checksums.get(&plugin.info.name) {
+        Some(checksum) => checksum.clone(),
+        None => {
+            info!("Plugin {} has no pinned checksum, pinning its current files", plugin.info.name);
+            return pin_plugin_checksum(checksums, plugin).map_err(|e| PluginManagerError::Other(e.to_string()));
+        },
+    };
+
+    let actual = checksum::compute(&plugin.info.path).map_err(|e| PluginManagerError::Other(e.to_string()))?;
+
+    plugin.checksum = Some(expected.clone());
+    plugin.tampered = actual != expected;
+
+    if plugin.tampered {
+        warn!("Files of plugin {} changed since it was installed, expected checksum {} but got {}", plugin.info.name, expected, actual);
+        return Err(PluginManagerError::Tampered);
+    }
+
+    Ok(())
+}
+
 /// Manages plugins.
 /// 
 /// **Should never be instantiated manually. [`GlobalPluginManager`] should be used to
@@ -180,8 +279,18 @@ pub struct PluginManager {
   pub plugins_directory: PathBuf,
   /// Persistence state
   persistent_states: PersistentPluginStates,
-  /// Reference to lua
-  lua: Arc<Lua>,
+  /// Checksums of the plugins' files pinned at installation
+  checksums: PluginChecksums,
+  /// Plugins suspected of crashing the game
+  suspects: PluginSuspects,
+  /// Lua state of the developer environment. Plugins and tweaks run in their own states.
+  developer_lua: Arc<Lua>,
+  /// Environment watch expressions are evaluated in. Created when first used.
+  developer_environment: Option<PluginEnvironment>,
+  /// Environment of the REPL, keeps its globals between chunks. Created when first used.
+  scratch_environment: Option<PluginEnvironment>,
+  /// Tweaks from the engine's config that ran at startup
+  tweaks: Vec<Tweak>,
 }
 
 impl PluginManager {
@@ -191,12 +300,21 @@ impl PluginManager {
   /// if it exists. This file persists whether the user enabled or disabled a plugin.
   /// For plugins not in the persistence file, they will be loaded but disabled.
   pub fn new(plugins_directory: PathBuf) -> Result<Self, PluginManagerError> {
-      let lua = Arc::new(Lua::new());
-      if let Err(e) = lua.load_from_std_lib(StdLib::STRING | StdLib::BIT | StdLib::MATH | StdLib::TABLE) {
-        error!("Could not load subset of standard library: {}", e);
-        return Err(PluginManagerError::Other(format!("Standard library error import: {}", e)));
-      }
+      let developer_lua = match lua_state::create() {
+        Ok(lua) => lua,
+        Err(e) => {
+          error!("Could not create the Lua state of the developer environment: {}", e);
+          return Err(PluginManagerError::Other(format!("Could not create Lua state: {}", e)));
+        }
+      };
 
+      PluginManager::load(developer_lua, plugins_directory)
+  }
+
+  /// Load all plugins from the given folder, each into its own Lua state.
+  ///
+  /// See [`PluginManager::new`].
+  fn load(developer_lua: Arc<Lua>, plugins_directory: PathBuf) -> Result<Self, PluginManagerError> {
       if !plugins_directory.is_dir() {
         info!("Plugin directory doesn't exist, creating it.");
         if let Err(e) = fs::create_dir_all(&plugins_directory) {
@@ -208,6 +326,12 @@ impl PluginManager {
       let plugin_states_file = Path::join(&plugins_directory, "plugins.json");
       let mut persistent_states = PersistentPluginStates::new(&plugin_states_file).map_err(|e| PluginManagerError::Other(e.to_string()))?;
 
+      let checksums_file = Path::join(&plugins_directory, "checksums.json");
+      let mut checksums = PluginChecksums::new(&checksums_file).map_err(|e| PluginManagerError::Other(e.to_string()))?;
+
+      let suspects_file = Path::join(&plugins_directory, "suspects.json");
+      let suspects = PluginSuspects::new(&suspects_file).map_err(|e| PluginManagerError::Other(e.to_string()))?;
+
       info!("Loading plugins from {:?}", plugins_directory);
       let plugin_directories = plugins_directory.read_dir().map_err(PluginManagerError::Io)?
           .filter_map(|path| {
@@ -234,6 +358,8 @@ impl PluginManager {
 
           let plugin_folder_path = plugin_folder.path();
 
+          let dev_link = get_dev_link(&plugin_folder_path);
+
           let plugin_info = match load_plugin_info(plugin_folder_path) {
             Ok(v) => v,
             Err(e) => {
@@ -242,13 +368,14 @@ impl PluginManager {
             }
           };
 
-          if plugins.contains_key(&plugin_info.name) {
-            debug!("Already found a plugin with the same name");
+          if plugins.keys().any(|name| is_same_name(name, &plugin_info.name)) {
+            warn!("Already found a plugin with the name '{}', ignoring {:?}", plugin_info.name, plugin_folder.path());
             continue;
           }
               
           debug!("Creating plugin {}", plugin_info.name);
-          let plugin: Plugin = Plugin::new(lua.clone(), plugin_info);
+          let mut plugin: Plugin = Plugin::new(plugin_info);
+          plugin.dev_link = dev_link;
   
           match plugin.state {
               PluginState::Error(ref e) => {
@@ -264,6 +391,7 @@ impl PluginManager {
 
       let mut successfully_loads = 0;
       let mut errored_loads = 0;
+      let mut tampered_plugins = 0;
 
       info!("Loading plugins");
       for (name, plugin) in plugins.iter_mut() {
@@ -279,6 +407,19 @@ impl PluginManager {
             Some(state) => state.clone(),
         };
 
+        plugin.suspect = suspects.contains(name);
+
+        // Don't execute plugins whose files were changed after installing them
+        match verify_plugin_checksum(&mut checksums, plugin) {
+            Err(PluginManagerError::Tampered) => {
+                warn!("Not loading plugin {}, its files were tampered with", name);
+                tampered_plugins += 1;
+                continue;
+            },
+            Err(e) => warn!("Could not verify the checksum of plugin {}: {:?}", name, e),
+            Ok(_) => (),
+        }
+
         let success = match plugin.load() {
             Ok(_) => {
                 info!("Successfully loaded plugin {}", name);
@@ -294,11 +435,20 @@ impl PluginManager {
 
         if success {
             match state {
+                // Don't start a crash loop by enabling the plugin that likely crashed the game
+                PersistentPluginState::Enabled if plugin.suspect => {
+                    warn!("Game crashed shortly after plugin {} was enabled, starting it disabled", name);
+
+                    if let Err(e) = persistent_states.insert(name, PersistentPluginState::Disabled) {
+                        warn!("Could not persist that plugin {} is disabled: {}", name, e);
+                    }
+                }
                 PersistentPluginState::Enabled => {
                     info!("Plugin was persisted as enabled, enabling plugin");
 
-                    if let Err(e) = plugin.enable() {
-                        warn!("Error while enabling plugin: {:?}", e);
+                    match plugin.enable() {
+                        Ok(_) => suspect::record_enabled(name),
+                        Err(e) => warn!("Error while enabling plugin: {:?}", e),
                     }
                 }
                 _ => (),
@@ -306,7 +456,7 @@ impl PluginManager {
         }
       }
 
-      info!("Loaded {} plugins, {} errored", successfully_loads, errored_loads);
+      info!("Loaded {} plugins, {} errored, {} tampered", successfully_loads, errored_loads, tampered_plugins);
 
       info!("Loaded the following plugins:");
 
@@ -322,25 +472,331 @@ impl PluginManager {
       }
 
       Ok(
-          PluginManager { plugins, plugins_directory, lua, persistent_states }
+          PluginManager { plugins, plugins_directory, developer_lua, persistent_states, checksums, suspects, developer_environment: None, scratch_environment: None, tweaks: Vec::new() }
       )
   }
 
   /// Call `onUpdate` function of all enabled plugins.
-  pub fn on_update(&self) {
-      for (_, plugin) in &self.plugins {
+  /// 
+  /// Doesn't call any plugin if the engine crashed.
+  pub fn on_update(&mut self) {
+      if crash::has_crashed() {
+          debug!("Engine crashed, not calling on_update of any plugin");
+          return;
+      }
+
+      for (_, plugin) in &mut self.plugins {
           
           if plugin.is_enabled() {
               debug!("Calling on_update for plugin '{}'", plugin.info.name);
 
               match plugin.on_update() {
-                  Err(e) => warn!("Plugin '{}' main function threw error: {:?}", plugin.info.name, e),
+                  Err(PluginError::LimitExceeded(limit)) => {
+                      warn!("Plugin '{}' exceeded {}, disabling it", plugin.info.name, limit);
+                      session::record_plugin_error(&plugin.info);
+
+                      // The plugin's onDisable function runs with a budget too, as it might loop just like onUpdate
+                      match plugin.lua().cloned() {
+                          Some(lua) => limits::with_budget(&lua, limits::update_budget(), || plugin.stop(PluginError::LimitExceeded(limit))).0,
+                          None => plugin.stop(PluginError::LimitExceeded(limit)),
+                      }
+                      persist_plugin_state_change(&mut self.persistent_states, plugin, PersistentPluginState::Disabled);
+                      suspect::record_disabled(&plugin.info.name);
+                      session_recording::record_event("pluginDisabled", Some(plugin.info.name.clone()));
+                  },
+                  Err(e) => {
+                      warn!("Plugin '{}' main function threw error: {:?}", plugin.info.name, e);
+                      session::record_plugin_error(&plugin.info);
+                      break_on_error(plugin, e);
+                  },
                   _ => debug!("Called on_update of plugin '{}'", plugin.info.name),
               }
           } else {
               debug!("Not calling on_update for plugin '{}', plugin not enabled", plugin.info.name);
           }
       }
+
+      for tweak in &mut self.tweaks {
+          tweak.on_update();
+      }
+  }
+
+  /// Run the tweaks from the engine's config. Tweaks that fail are skipped.
+  pub fn run_tweaks(&mut self, tweaks: &[TweakConfig]) {
+      for config in tweaks {
+          if self.tweaks.iter().any(|tweak| tweak.name == config.name) {
+              warn!("Tweak '{}' is defined multiple times, only running it once", config.name);
+              continue;
+          }
+
+          let result = lua_state::create()
+              .map_err(|e| anyhow!("could not create the Lua state: {}", e))
+              .and_then(|lua| Tweak::run(lua, &self.plugins_directory, config));
+
+          match result {
+              Ok(tweak) => {
+                  info!("Ran tweak '{}'", config.name);
+                  self.tweaks.push(tweak);
+              },
+              Err(e) => warn!("Tweak '{}' failed: {}", config.name, e),
+          }
+      }
+  }
+
+  /// Collect garbage after the frame, if the garbage collector runs in steps after each frame.
+  ///
+  /// Returns the time spent collecting garbage.
+  pub fn collect_frame_garbage(&self) -> Duration {
+      let states: Vec<&Lua> = self.plugins.values().filter_map(|plugin| plugin.lua().map(|lua| lua.as_ref()))
+          .chain(self.tweaks.iter().map(|tweak| tweak.lua()))
+          .chain(std::iter::once(self.developer_lua.as_ref()))
+          .collect();
+
+      gc::on_frame(&states)
+  }
+
+  /// Run the due timers of all enabled plugins.
+  pub fn on_timers(&mut self, now: f64) {
+      if crash::has_crashed() {
+          return;
+      }
+
+      for (_, plugin) in &mut self.plugins {
+          if !plugin.is_enabled() {
+              continue;
+          }
+
+          if let Err(e) = plugin.on_timers(now) {
+              warn!("Plugin '{}' threw error while running a timer: {:?}", plugin.info.name, e);
+              session::record_plugin_error(&plugin.info);
+              break_on_error(plugin, e);
+          }
+      }
+  }
+
+  /// Pass the game event to all enabled plugins.
+  pub fn on_game_event(&mut self, event: &GameEvent) {
+      if crash::has_crashed() {
+          return;
+      }
+
+      for (_, plugin) in &mut self.plugins {
+          if !plugin.is_enabled() {
+              continue;
+          }
+
+          if let Err(e) = plugin.on_game_event(event) {
+              warn!("Plugin '{}' threw error while handling event {}: {:?}", plugin.info.name, event.name(), e);
+              session::record_plugin_error(&plugin.info);
+              break_on_error(plugin, e);
+          }
+      }
+  }
+
+  /// Pass the split of the run timer to all enabled plugins.
+  pub fn on_split(&mut self, split: &Split) {
+      if crash::has_crashed() {
+          return;
+      }
+
+      for (_, plugin) in &mut self.plugins {
+          if !plugin.is_enabled() {
+              continue;
+          }
+
+          if let Err(e) = plugin.on_split(split) {
+              warn!("Plugin '{}' threw error while handling a split: {:?}", plugin.info.name, e);
+              session::record_plugin_error(&plugin.info);
+              break_on_error(plugin, e);
+          }
+      }
+  }
+
+  /// Pass the key the user pressed to all enabled plugins.
+  pub fn on_key_down(&mut self, key: &str) {
+      if crash::has_crashed() {
+          return;
+      }
+
+      for (_, plugin) in &mut self.plugins {
+          if !plugin.is_enabled() {
+              continue;
+          }
+
+          if let Err(e) = plugin.on_key_down(key) {
+              warn!("Plugin '{}' threw error while handling key {}: {:?}", plugin.info.name, key, e);
+              session::record_plugin_error(&plugin.info);
+              break_on_error(plugin, e);
+          }
+      }
+  }
+
+  /// Pass the response of a request to the plugin that sent it.
+  ///
+  /// Responses of plugins that were disabled in the meantime are dropped.
+  pub fn on_net_response(&mut self, response: &net::Response) {
+      if crash::has_crashed() {
+          return;
+      }
+
+      let plugin = match self.plugins.get_mut(&response.plugin) {
+          Some(plugin) if plugin.is_enabled() => plugin,
+          _ => {
+              debug!("Dropping response {} of plugin '{}', it isn't enabled", response.id, response.plugin);
+              return;
+          },
+      };
+
+      if let Err(e) = plugin.on_net_response(response) {
+          warn!("Plugin '{}' threw error while handling a response: {:?}", plugin.info.name, e);
+          session::record_plugin_error(&plugin.info);
+          break_on_error(plugin, e);
+      }
+  }
+
+  /// Pass the output of a program to the plugin that ran it.
+  ///
+  /// Outputs of plugins that were disabled in the meantime are dropped.
+  pub fn on_exec_result(&mut self, result: &exec::ExecResult) {
+      if crash::has_crashed() {
+          return;
+      }
+
+      let plugin = match self.plugins.get_mut(&result.plugin) {
+          Some(plugin) if plugin.is_enabled() => plugin,
+          _ => {
+              debug!("Dropping output {} of plugin '{}', it isn't enabled", result.id, result.plugin);
+              return;
+          },
+      };
+
+      if let Err(e) = plugin.on_exec_result(result) {
+          warn!("Plugin '{}' threw error while handling a program's output: {:?}", plugin.info.name, e);
+          session::record_plugin_error(&plugin.info);
+          break_on_error(plugin, e);
+      }
+  }
+
+  /// Evaluate the Lua expression in the developer environment and describe its value.
+  ///
+  /// The developer environment has the libraries to read the game's state as globals, e.g., `game`.
+  /// The evaluation is aborted after the timeout, so an endless loop can't freeze the game.
+  pub fn evaluate(&mut self, expression: &str, timeout: Duration) -> Result<String, anyhow::Error> {
+      if crash::has_crashed() {
+          bail!("engine crashed, not evaluating expressions");
+      }
+
+      let environment = self.developer_environment()?;
+
+      let start = Instant::now();
+      self.developer_lua.set_interrupt(move |_| match start.elapsed() > timeout {
+          true => Err(mlua::Error::RuntimeError(format!("expression took longer than {}ms", timeout.as_millis()))),
+          false => Ok(VmState::Continue),
+      });
+
+      let result = self.developer_lua
+          .load(format!("return {}", expression))
+          .set_name("=watch")
+          .set_environment(environment.table.clone())
+          .eval::<mlua::Value>()
+          .and_then(|value| describe_value(&self.developer_lua, value));
+
+      self.developer_lua.remove_interrupt();
+
+      result.map_err(|e| anyhow!("{}", e))
+  }
+
+  /// Run the Lua chunk in the plugin's environment, or in the developer environment if no plugin is given.
+  ///
+  /// Chunks that are an expression, e.g., `game.getPlayer(0).health`, return its value.
+  /// While the chunk runs, `print` is captured instead of logged, including in the plugin's functions the chunk calls.
+  /// The chunk is aborted after the timeout, so an endless loop can't freeze the game.
+  pub fn run_chunk(&mut self, plugin: Option<&str>, chunk: &str, timeout: Duration) -> Result<ChunkResult, anyhow::Error> {
+      if crash::has_crashed() {
+          bail!("engine crashed, not running chunks");
+      }
+
+      let (lua, environment) = match plugin {
+          Some(name) => {
+              let plugin = self.plugins.get(name).ok_or(anyhow!("plugin '{}' doesn't exist", name))?;
+              let environment = plugin.environment().map_err(|_| anyhow!("plugin '{}' isn't loaded", name))?;
+              let lua = plugin.lua().ok_or(anyhow!("plugin '{}' isn't loaded", name))?;
+
+              (lua.clone(), environment.table.clone())
+          },
+          None => (self.developer_lua.clone(), self.developer_environment()?.table),
+      };
+
+      run_chunk_with_timeout(&lua, &environment, chunk, timeout)
+  }
+
+  /// Run the Lua chunk in the scratch environment, see [`PluginManager::run_chunk`].
+  ///
+  /// Unlike the developer environment, the scratch environment doesn't have the dangerous, structs, and input libraries.
+  /// Thus, it's safe to run chunks from clients of the mod's server that aren't in developer mode.
+  pub fn run_scratch_chunk(&mut self, chunk: &str, timeout: Duration) -> Result<ChunkResult, anyhow::Error> {
+      if crash::has_crashed() {
+          bail!("engine crashed, not running chunks");
+      }
+
+      let environment = self.scratch_environment()?;
+
+      run_chunk_with_timeout(&self.developer_lua, &environment.table, chunk, timeout)
+  }
+
+  /// Get the developer environment, creating it when it's used the first time.
+  fn developer_environment(&mut self) -> Result<PluginEnvironment, anyhow::Error> {
+      if let Some(environment) = &self.developer_environment {
+          return Ok(environment.clone());
+      }
+
+      let environment = create_global_environment(self.developer_lua.clone(), DEVELOPER_ENVIRONMENT_NAME, &DEVELOPER_LIBRARIES, &self.plugins_directory)
+          .map_err(|e| anyhow!("could not create the developer environment: {}", e))?;
+
+      self.developer_environment = Some(environment.clone());
+      Ok(environment)
+  }
+
+  /// Get the scratch environment, creating it when it's used the first time.
+  fn scratch_environment(&mut self) -> Result<PluginEnvironment, anyhow::Error> {
+      if let Some(environment) = &self.scratch_environment {
+          return Ok(environment.clone());
+      }
+
+      let environment = create_global_environment(self.developer_lua.clone(), SCRATCH_ENVIRONMENT_NAME, &SCRATCH_LIBRARIES, &self.plugins_directory)
+          .map_err(|e| anyhow!("could not create the scratch environment: {}", e))?;
+
+      self.scratch_environment = Some(environment.clone());
+      Ok(environment)
+  }
+
+  /// Disable all plugins without persisting the change.
+  /// 
+  /// Used when plugins must be stopped for the current session, e.g., after the engine crashed.
+  /// As the change isn't persisted, plugins are enabled as usual on the next start.
+  pub fn disable_all_plugins(&mut self) {
+      info!("Disabling all plugins");
+
+      for (name, plugin) in self.plugins.iter_mut() {
+          if let Err(e) = plugin.disable() {
+              warn!("Plugin '{}' threw an error while it was disabled: {:?}", name, e);
+          }
+      }
+  }
+
+  /// Unload all plugins and stop the tweaks without persisting the change.
+  ///
+  /// Used when the engine is torn down. Plugins are loaded as usual when the engine is injected again.
+  pub fn unload_all_plugins(&mut self) {
+      info!("Unloading all plugins");
+
+      for (name, plugin) in self.plugins.iter_mut() {
+          if let Err(e) = plugin.unload() {
+              warn!("Plugin '{}' threw an error while unloading: {:?}", name, e);
+          }
+      }
+
+      self.tweaks.clear();
   }
 
   /// Enable the plugin
@@ -356,6 +812,18 @@ impl PluginManager {
 
       plugin.enable().map_err(PluginManagerError::Plugin)?;
       persist_plugin_state_change(&mut self.persistent_states, plugin, PersistentPluginState::Enabled);
+      suspect::record_enabled(name);
+      session_recording::record_event("pluginEnabled", Some(name.clone()));
+
+      // Enabling a suspect again is the user's decision to try it once more
+      if plugin.suspect {
+          info!("Plugin '{}' was enabled again, it's not suspected of crashing the game anymore", name);
+          plugin.suspect = false;
+
+          if let Err(e) = self.suspects.remove(name) {
+              warn!("Could not remove plugin '{}' from the suspects: {}", name, e);
+          }
+      }
 
       Ok(())
     }
@@ -367,6 +835,8 @@ impl PluginManager {
           Some(game_plugin) => {
               game_plugin.disable().map_err(PluginManagerError::Plugin)?;
               persist_plugin_state_change(&mut self.persistent_states, game_plugin, PersistentPluginState::Disabled);
+              suspect::record_disabled(name);
+              session_recording::record_event("pluginDisabled", Some(name.clone()));
 
               Ok(())
           },
@@ -386,13 +856,93 @@ impl PluginManager {
         Some(p) => p,
     };
 
+    verify_plugin_checksum(&mut self.checksums, plugin)?;
+    session_recording::record_event("pluginReloaded", Some(name.to_string()));
     plugin.reload().map_err(PluginManagerError::Plugin)
   }
 
+  /// Reload the plugin and its info file.
+  ///
+  /// Unlike [`PluginManager::reload_plugin`], this also picks up changes to the plugin's `info.toml`,
+  /// e.g., new dependencies of a plugin that is developed in place.
+  /// The plugin's name cannot change, since plugins are identified by it.
+  pub fn force_reload_plugin(&mut self, name: &str) -> Result<(), PluginManagerError> {
+    info!("Force reloading plugin '{}'", name);
+
+    let plugin = match self.plugins.get_mut(name) {
+        None => return Err(PluginManagerError::PluginNotFound),
+        Some(p) => p,
+    };
+
+    let path = plugin.dev_link.clone().unwrap_or(plugin.info.path.clone());
+    let info = load_plugin_info(path).map_err(|e| PluginManagerError::Other(format!("could not read the plugin's info file: {:?}", e)))?;
+
+    if info.name != plugin.info.name {
+        return Err(PluginManagerError::Other(format!("the plugin's name changed from '{}' to '{}', reinstall the plugin instead", plugin.info.name, info.name)));
+    }
+
+    plugin.info = info;
+    verify_plugin_checksum(&mut self.checksums, plugin)?;
+    plugin.reload().map_err(PluginManagerError::Plugin)
+  }
+
+  /// Call the plugin's lifecycle function without changing its state.
+  ///
+  /// Returns `false` if the plugin doesn't define the function.
+  pub fn invoke_lifecycle(&self, name: &str, event: LifecycleEvent) -> Result<bool, PluginManagerError> {
+    let plugin = match self.plugins.get(name) {
+        None => return Err(PluginManagerError::PluginNotFound),
+        Some(p) => p,
+    };
+
+    plugin.invoke_lifecycle(event).map_err(PluginManagerError::Plugin)
+  }
+
+  /// Globals the plugin set in its environment.
+  pub fn inspect_environment(&self, name: &str) -> Result<Vec<EnvironmentEntry>, PluginManagerError> {
+    let plugin = match self.plugins.get(name) {
+        None => return Err(PluginManagerError::PluginNotFound),
+        Some(p) => p,
+    };
+
+    plugin.inspect_environment().map_err(PluginManagerError::Plugin)
+  }
+
+  /// Call the plugin's handler of the HTTP route.
+  ///
+  /// Returns `None` if the plugin didn't register the route, see [`Plugin::handle_http_request`] for the timeout.
+  /// Doesn't call any plugin if the engine crashed.
+  pub fn handle_http_request(&self, name: &str, path: &str, query: HashMap<String, String>, timeout: Duration) -> Result<Option<serde_json::Value>, PluginManagerError> {
+    if crash::has_crashed() {
+        return Err(PluginManagerError::Other("the engine crashed".to_string()));
+    }
+
+    let plugin = match self.plugins.get(name) {
+        None => return Err(PluginManagerError::PluginNotFound),
+        Some(p) => p,
+    };
+
+    plugin.handle_http_request(path, query, timeout).map_err(PluginManagerError::Plugin)
+  }
+
   pub fn get_plugins(&self) -> &HashMap<String, Plugin> {
     return &self.plugins;
   }
 
+  /// Names and source folders of the plugins installed in developer mode.
+  pub fn get_dev_plugin_folders(&self) -> Vec<(String, PathBuf)> {
+    self.plugins
+      .values()
+      .filter(|plugin| plugin.dev_link.is_some())
+      .map(|plugin| (plugin.info.name.clone(), plugin.info.path.clone()))
+      .collect()
+  }
+
+  /// Name of the installed plugin with the same name, ignoring case.
+  fn installed_name(&self, name: &str) -> Option<&String> {
+    self.plugins.keys().find(|installed| is_same_name(installed, name))
+  }
+
   /// Install a plugin from a folder.
   ///
   /// This method will install the plugin stored at the specified `folder`.
@@ -403,8 +953,8 @@ impl PluginManager {
     info!("Installing plugin from {}", folder.display());
     let plugin_info = load_plugin_info(folder.clone()).map_err(PluginInstallError::InfoFile)?;
 
-    if self.plugins.contains_key(&plugin_info.name) {
-        warn!("Plugin '{}' already installed", plugin_info.name);
+    if let Some(installed) = self.installed_name(&plugin_info.name) {
+        warn!("Plugin '{}' already installed as '{}'", plugin_info.name, installed);
         return Err(PluginInstallError::AlreadyInstalled);
     }
 
@@ -417,29 +967,14 @@ impl PluginManager {
     let destination = self.plugins_directory.clone().join(plugin_folder_name);
     debug!("Plugin folder will be '{}'", destination.display());
 
-    debug!("Copying files from plugin package to destination");
-    for file in WalkDir::new(folder).into_iter().filter_map(|e| e.ok()) {
-        let path = file.path();
-
-        let relative_path = match path.strip_prefix(folder) {
-            Ok(v) => v,
-            Err(err) => return Err(PluginInstallError::Copy(format!("Could not get relative path of {}: {}", path.display(), err.to_string()))),
-        };
-        let destination_path = Path::join(&destination.clone(), &relative_path);
-
-        if path.is_dir() {
-            match fs::create_dir_all(&destination_path) {
-                Err(err) => return Err(PluginInstallError::Copy(format!("Could not destination directory {}: {}", destination_path.display(), err.to_string()))),
-                _ => (),
-            }
-        } else if path.is_file() {
-        debug!("Copy {} to {}", path.display(), destination_path.display());
-            match fs::copy(path, destination_path) {
-                Err(err) => return Err(PluginInstallError::Copy(format!("Could not copy {}: {}", path.display(), err.to_string()))),
-                _ => (),
-            }
-        }
+    // Names that differ only in case or punctuation result in the same folder
+    if destination.exists() {
+        warn!("Plugin folder '{}' already exists", destination.display());
+        return Err(PluginInstallError::AlreadyInstalled);
     }
+
+    debug!("Copying files from plugin package to destination");
+    copy_plugin_files(folder, &destination)?;
     
     debug!("Copying finished, loading plugin");
     // Create a new plugin info struct based on the freshly copied plugin.
@@ -449,13 +984,217 @@ impl PluginManager {
     let plugin_name = plugin_info.name.clone();
 
     // Create and load the plugin
-    let plugin = Plugin::new(self.lua.clone(), plugin_info);
+    let mut plugin = Plugin::new(plugin_info);
     persist_plugin_state_change(&mut self.persistent_states, &plugin, PersistentPluginState::Disabled);
+    if let Err(e) = pin_plugin_checksum(&mut self.checksums, &mut plugin) {
+        warn!("Could not pin the checksum of plugin {}: {}", plugin_name, e);
+    }
     self.plugins.insert(plugin_name.clone(), plugin);
 
     let plugin = self.plugins.get_mut(&plugin_name).unwrap();
     plugin.load().map_err(|e| PluginInstallError::Plugin(format!("{:?}", e)))?;
 
+    if let Err(e) = plugin.on_install(None) {
+        warn!("Plugin {} threw an error in its onInstall function: {:?}", plugin_name, e);
+    }
+
+    Ok(())
+  }
+
+  /// Update an installed plugin to the version stored at the specified `folder`.
+  ///
+  /// The new version's files are copied into a staging folder next to the plugin's folder, which then replaces the
+  /// plugin's folder. Thus, the installed version stays untouched if copying fails.
+  /// The plugin's stored values and settings are carried over, and it keeps its persisted state, i.e., it is enabled
+  /// again if it was enabled.
+  /// The old version's `onUninstall` function is called with the new version, and the new version's `onInstall`
+  /// function with the old version.
+  pub fn update_plugin_from_folder(&mut self, folder: &PathBuf) -> Result<(), PluginInstallError> {
+    info!("Updating plugin from {}", folder.display());
+    let plugin_info = load_plugin_info(folder.clone()).map_err(PluginInstallError::InfoFile)?;
+    let name = plugin_info.name.clone();
+
+    let plugin = match self.plugins.get_mut(&name) {
+        Some(plugin) => plugin,
+        None => return Err(PluginInstallError::NotInstalled),
+    };
+
+    if plugin.dev_link.is_some() {
+        return Err(PluginInstallError::Linked);
+    }
+
+    let destination = plugin.info.path.clone();
+    let staging = sibling_folder(&destination, "update");
+    let previous = sibling_folder(&destination, "previous");
+
+    // Left over if the game exited during a previous update
+    for folder in [&staging, &previous] {
+        if folder.exists() {
+            fs::remove_dir_all(folder).map_err(|e| PluginInstallError::Copy(format!("Could not remove {}: {}", folder.display(), e)))?;
+        }
+    }
+
+    debug!("Copying the new version's files to {}", staging.display());
+    if let Err(e) = copy_plugin_files(folder, &staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+
+    let previous_version = plugin.info.version.clone();
+    info!("Updating plugin {} from version {} to {}", name, previous_version, plugin_info.version);
+
+    if let Err(e) = plugin.on_uninstall(Some(&plugin_info.version)) {
+        warn!("Plugin {} threw an error in its onUninstall function: {:?}", name, e);
+    }
+    if let Err(e) = plugin.unload() {
+        warn!("Plugin {} threw an error while unloading: {:?}", name, e);
+    }
+
+    // Writes the stored values into the old folder, from where they are carried over
+    storage::forget(&name);
+
+    let state = self.persistent_states.get_state(&name).copied();
+
+    if let Err(e) = replace_plugin_files(&destination, &staging, &previous) {
+        let _ = fs::remove_dir_all(&staging);
+
+        // Keep running the old version
+        if let Err(e) = plugin.load() {
+            warn!("Could not load the old version of plugin {} again: {:?}", name, e);
+        } else if let Some(PersistentPluginState::Enabled) = state {
+            if let Err(e) = plugin.enable() {
+                warn!("Could not enable the old version of plugin {} again: {:?}", name, e);
+            }
+        }
+
+        return Err(e);
+    }
+
+    if let Err(e) = fs::remove_dir_all(&previous) {
+        warn!("Could not remove the files of the old version of plugin {}: {}", name, e);
+    }
+
+    plugin.info = load_plugin_info(destination).map_err(PluginInstallError::InfoFile)?;
+    if let Err(e) = pin_plugin_checksum(&mut self.checksums, plugin) {
+        warn!("Could not pin the checksum of plugin {}: {}", name, e);
+    }
+
+    plugin.load().map_err(|e| PluginInstallError::Plugin(format!("{:?}", e)))?;
+
+    if let Err(e) = plugin.on_install(Some(&previous_version)) {
+        warn!("Plugin {} threw an error in its onInstall function: {:?}", name, e);
+    }
+
+    if let Some(PersistentPluginState::Enabled) = state {
+        plugin.enable().map_err(|e| PluginInstallError::Plugin(format!("{:?}", e)))?;
+        suspect::record_enabled(&name);
+    }
+
+    session_recording::record_event("pluginUpdated", Some(name));
+
+    Ok(())
+  }
+
+  /// Install the plugin stored at the specified `folder` in developer mode.
+  ///
+  /// Instead of copying the plugin's files, the folder is linked into the plugins folder with a junction.
+  /// Thus, changes to the plugin's files take effect when reloading it.
+  /// Like [`PluginManager::install_plugin_from_folder`], this loads the plugin.
+  pub fn link_plugin_from_folder(&mut self, folder: &PathBuf) -> Result<(), PluginInstallError> {
+    info!("Installing plugin from {} in developer mode", folder.display());
+    let plugin_info = load_plugin_info(folder.clone()).map_err(PluginInstallError::InfoFile)?;
+
+    if let Some(installed) = self.installed_name(&plugin_info.name) {
+        warn!("Plugin '{}' already installed as '{}'", plugin_info.name, installed);
+        return Err(PluginInstallError::AlreadyInstalled);
+    }
+
+    let plugin_folder_name = match sanitize_name(&plugin_info.name) {
+        None => return Err(PluginInstallError::InvalidName),
+        Some(v) => v,
+    };
+
+    let destination = self.plugins_directory.clone().join(plugin_folder_name);
+    if destination.exists() {
+        warn!("Plugin folder '{}' already exists", destination.display());
+        return Err(PluginInstallError::AlreadyInstalled);
+    }
+
+    debug!("Linking {} to {}", destination.display(), plugin_info.path.display());
+    junction::create(&plugin_info.path, &destination)
+        .map_err(|e| PluginInstallError::Copy(format!("Could not link the plugin folder: {}", e)))?;
+
+    let plugin_name = plugin_info.name.clone();
+
+    let mut plugin = Plugin::new(plugin_info);
+    plugin.dev_link = Some(destination);
+    persist_plugin_state_change(&mut self.persistent_states, &plugin, PersistentPluginState::Disabled);
+    self.plugins.insert(plugin_name.clone(), plugin);
+
+    let plugin = self.plugins.get_mut(&plugin_name).unwrap();
+    plugin.load().map_err(|e| PluginInstallError::Plugin(format!("{:?}", e)))?;
+
+    Ok(())
+  }
+
+  /// Convert a plugin installed in developer mode to a regular installation.
+  ///
+  /// Copies the files of the linked folder into the plugins folder and replaces the link with the copy.
+  /// The plugin is reloaded from the copy and keeps its state.
+  /// Afterwards, changes to the developer's folder don't affect the installed plugin anymore.
+  pub fn promote_plugin(&mut self, name: &str) -> Result<(), PluginManagerError> {
+    info!("Converting plugin '{}' to a regular installation", name);
+
+    let plugin = match self.plugins.get_mut(name) {
+        None => return Err(PluginManagerError::PluginNotFound),
+        Some(p) => p,
+    };
+
+    let link = match &plugin.dev_link {
+        Some(link) => link.clone(),
+        None => return Err(PluginManagerError::NotDevPlugin),
+    };
+
+    // Copy next to the link first, so the link is only replaced if copying succeeded
+    let mut copy_name = link.file_name().unwrap_or_default().to_os_string();
+    copy_name.push(".promote");
+    let copy = link.with_file_name(copy_name);
+
+    if copy.exists() {
+        fs::remove_dir_all(&copy).map_err(PluginManagerError::Io)?;
+    }
+
+    if let Err(e) = copy_plugin_files(&plugin.info.path, &copy) {
+        let _ = fs::remove_dir_all(&copy);
+        return Err(PluginManagerError::Other(format!("could not copy the plugin's files: {:?}", e)));
+    }
+
+    // Unload the plugin before replacing its files
+    let was_enabled = plugin.is_enabled();
+    if let Err(e) = plugin.unload() {
+        warn!("Plugin {} threw an error while unloading: {:?}", name, e);
+    }
+
+    remove_link(&link).map_err(PluginManagerError::Io)?;
+    fs::rename(&copy, &link).map_err(PluginManagerError::Io)?;
+
+    plugin.info = load_plugin_info(link).map_err(|e| PluginManagerError::Other(format!("could not read the plugin's info file: {:?}", e)))?;
+    plugin.dev_link = None;
+    if let Err(e) = pin_plugin_checksum(&mut self.checksums, plugin) {
+        warn!("Could not pin the checksum of plugin {}: {}", name, e);
+    }
+
+    plugin.load().map_err(PluginManagerError::Plugin)?;
+    if was_enabled {
+        plugin.enable().map_err(PluginManagerError::Plugin)?;
+    }
+
+    let state = match was_enabled {
+        true => PersistentPluginState::Enabled,
+        false => PersistentPluginState::Disabled,
+    };
+    persist_plugin_state_change(&mut self.persistent_states, plugin, state);
+
     Ok(())
   }
 
@@ -470,10 +1209,52 @@ impl PluginManager {
         Some(p) => p,
     };
 
+    verify_plugin_checksum(&mut self.checksums, plugin)?;
     persist_plugin_state_change(&mut self.persistent_states, &plugin, PersistentPluginState::Disabled);
     plugin.load().map_err(PluginManagerError::Plugin)
   }
 
+  /// Trust the current files of a plugin whose files changed since it was installed.
+  ///
+  /// Pins the checksum of the current files and loads the plugin like on start, i.e., enables it
+  /// if it was enabled before.
+  pub fn trust_plugin(&mut self, name: &str) -> Result<(), PluginManagerError> {
+    info!("Trusting the current files of plugin '{}'", name);
+
+    let plugin = match self.plugins.get_mut(name) {
+        None => return Err(PluginManagerError::PluginNotFound),
+        Some(p) => p,
+    };
+
+    pin_plugin_checksum(&mut self.checksums, plugin).map_err(|e| PluginManagerError::Other(e.to_string()))?;
+
+    if let PluginState::Loaded(_) = plugin.state {
+        return Ok(());
+    }
+
+    plugin.load().map_err(PluginManagerError::Plugin)?;
+
+    if let Some(PersistentPluginState::Enabled) = self.persistent_states.get_state(name) {
+        plugin.enable().map_err(PluginManagerError::Plugin)?;
+        suspect::record_enabled(name);
+    }
+
+    Ok(())
+  }
+
+  /// Stop suspecting the plugin of crashing the game without enabling it.
+  pub fn dismiss_suspect(&mut self, name: &str) -> Result<(), PluginManagerError> {
+    info!("Dismissing that plugin '{}' is suspected of crashing the game", name);
+
+    let plugin = match self.plugins.get_mut(name) {
+        None => return Err(PluginManagerError::PluginNotFound),
+        Some(p) => p,
+    };
+
+    plugin.suspect = false;
+    self.suspects.remove(name).map_err(|e| PluginManagerError::Other(e.to_string()))
+  }
+
   /// Unload the plugin with the specified name.
   pub fn unload_plugin(&mut self, name: &str) -> Result<(), PluginManagerError> {
     info!("Unload plugin: {}", name);
@@ -487,6 +1268,60 @@ impl PluginManager {
     plugin.unload().map_err(PluginManagerError::Plugin)
   }
 
+  /// Replace all plugins with the plugins of a backup that was extracted into the folder.
+  ///
+  /// Unloads all plugins, replaces the content of the plugins directory, and loads the restored plugins
+  /// as if the mod was injected again.
+  pub fn restore_backup(&mut self, backup: &Path) -> Result<(), PluginManagerError> {
+    info!("Restoring the plugins directory from a backup");
+
+    for (name, plugin) in self.plugins.iter_mut() {
+        if let Err(e) = plugin.disable() {
+            warn!("Plugin {} threw an error while it was disabled: {:?}", name, e);
+        }
+        if let Err(e) = plugin.unload() {
+            warn!("Plugin {} threw an error while unloading: {:?}", name, e);
+        }
+
+        suspect::record_disabled(name);
+        storage::forget(name);
+        settings::forget(name);
+        key_bindings::clear(name);
+    }
+
+    // Drops the plugins' Lua states, thus, all of their references and objects
+    self.plugins.clear();
+    self.developer_environment = None;
+    self.scratch_environment = None;
+
+    // Load whatever is in the plugins directory, even if the backup was only partially restored
+    let result = backup::replace_directory_content(&self.plugins_directory, backup);
+
+    key_bindings::init(&self.plugins_directory.join("key-bindings.json"));
+    watches::init(&self.plugins_directory.join("watches.json"));
+    bookmarks::init(&self.plugins_directory.join("bookmarks.json"));
+    net::init(&self.plugins_directory.join("network-approvals.json"));
+    exec::init(&self.plugins_directory.join("command-approvals.json"));
+
+    *self = PluginManager::load(self.developer_lua.clone(), self.plugins_directory.clone())?;
+
+    result.map_err(|e| PluginManagerError::Other(format!("could not restore the backup: {}", e)))
+  }
+
+  /// Write the plugin states and the plugins' stored values that changed since they were last written.
+  pub fn persist(&mut self) {
+    if let Err(e) = self.persistent_states.write_to_file() {
+        warn!("Could not persist the plugin states: {}", e);
+    }
+    storage::persist();
+    if let Err(e) = compatibility::persist() {
+        warn!("Could not persist the compatibility data: {}", e);
+    }
+    if let Err(e) = statistics::persist() {
+        warn!("Could not persist the usage statistics: {}", e);
+    }
+  }
+
   // Uninstall the plugin.
   pub fn uninstall_plugin(&mut self, name: &str) -> Result<(), PluginManagerError> {
     info!("Uninstalling plugin: {}", name);
@@ -498,6 +1333,28 @@ impl PluginManager {
 
     // Persist change
     remove_plugin_from_persistence(&mut self.persistent_states, &plugin.info.name);
+    if let Err(e) = self.checksums.remove(&plugin.info.name) {
+        warn!("Could not remove the checksum of plugin {}: {}", name, e);
+    }
+    if let Err(e) = self.suspects.remove(&plugin.info.name) {
+        warn!("Could not remove plugin {} from the suspects: {}", name, e);
+    }
+    suspect::record_disabled(name);
+    storage::forget(&plugin.info.name);
+    settings::forget(&plugin.info.name);
+    if let Err(e) = key_bindings::remove(&plugin.info.name) {
+        warn!("Could not remove the key bindings of plugin {}: {}", name, e);
+    }
+    if let Err(e) = net::revoke(&plugin.info.name) {
+        warn!("Could not revoke the network approval of plugin {}: {}", name, e);
+    }
+    if let Err(e) = exec::revoke(&plugin.info.name) {
+        warn!("Could not revoke the command approval of plugin {}: {}", name, e);
+    }
+
+    if let Err(e) = plugin.on_uninstall(None) {
+        warn!("Plugin {} threw an error in its onUninstall function: {:?}", name, e);
+    }
 
     // We will execute the plugin's disable function just that it has a chance to be uninstalled cleanly.
     // However, we won't care if the plugin's disable function will throw an error and still remove it afterwards.
@@ -506,13 +1363,15 @@ impl PluginManager {
     };
 
     // Unload the plugin.
-    // This should drop all references to lua objects and also run lua's garbage collector.
+    // This drops the plugin's Lua state together with all of its objects.
     // However, it this call fails, removing the Plugin from the map should still work
     if let Err(e) = plugin.unload() {
         warn!("Plugin {} threw an error while unloading: {:?}", name, e);
     }
 
+    // For plugins installed in developer mode, only remove the link and keep the developer's files
     let plugin_path = plugin.info.path.clone();
+    let dev_link = plugin.dev_link.clone();
 
     // Remove the plugin from the plugin map.
     // This should only return None due to race conditions.
@@ -521,35 +1380,286 @@ impl PluginManager {
         warn!("Could not find plugin '{}' while removing it from the internal map", name);
     }
 
-    // Ensure that all lua references and objects are destroyed properly.
-    let _ = self.lua.gc_collect();
-    let _ = self.lua.gc_collect();
-
     // Lastly, remove the plugin's file from the plugin folder
-    fs::remove_dir_all(plugin_path).map_err(PluginManagerError::Io)?;
+    match dev_link {
+        Some(link) => remove_link(&link).map_err(PluginManagerError::Io)?,
+        None => fs::remove_dir_all(plugin_path).map_err(PluginManagerError::Io)?,
+    }
 
     Ok(())
   }
 }
 
-/// Sanitizes the given name to be used as a folder name.
+/// Copy all files of the plugin in `from` into the folder `to`.
+fn copy_plugin_files(from: &Path, to: &Path) -> Result<(), PluginInstallError> {
+    for file in WalkDir::new(from).into_iter().filter_map(|e| e.ok()) {
+        let path = file.path();
+
+        let relative_path = match path.strip_prefix(from) {
+            Ok(v) => v,
+            Err(err) => return Err(PluginInstallError::Copy(format!("Could not get relative path of {}: {}", path.display(), err.to_string()))),
+        };
+        let destination_path = Path::join(to, &relative_path);
+
+        if path.is_dir() {
+            match fs::create_dir_all(&destination_path) {
+                Err(err) => return Err(PluginInstallError::Copy(format!("Could not destination directory {}: {}", destination_path.display(), err.to_string()))),
+                _ => (),
+            }
+        } else if path.is_file() {
+            debug!("Copy {} to {}", path.display(), destination_path.display());
+            match fs::copy(path, destination_path) {
+                Err(err) => return Err(PluginInstallError::Copy(format!("Could not copy {}: {}", path.display(), err.to_string()))),
+                _ => (),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Folder next to the plugin's folder with the suffix, e.g., `example.update` for `example`.
+fn sibling_folder(folder: &Path, suffix: &str) -> PathBuf {
+    let mut name = folder.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+
+    folder.with_file_name(name)
+}
+
+/// Replace the plugin's files in `destination` with the new version's files in `staging`.
+///
+/// The plugin's stored values and settings are copied into the new version first. The old version is moved to
+/// `previous`, and moved back if the new version can't be moved into place.
+fn replace_plugin_files(destination: &Path, staging: &Path, previous: &Path) -> Result<(), PluginInstallError> {
+    let entries = fs::read_dir(destination)
+        .map_err(|e| PluginInstallError::Copy(format!("Could not read {}: {}", destination.display(), e)))?;
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !storage::is_storage_file(&file_name) && file_name != settings::SETTINGS_FILE_NAME {
+            continue;
+        }
+
+        debug!("Carrying {} over into the new version", file_name);
+        fs::copy(entry.path(), staging.join(&file_name))
+            .map_err(|e| PluginInstallError::Copy(format!("Could not carry {} over: {}", file_name, e)))?;
+    }
+
+    fs::rename(destination, previous)
+        .map_err(|e| PluginInstallError::Copy(format!("Could not move the old version to {}: {}", previous.display(), e)))?;
+
+    if let Err(e) = fs::rename(staging, destination) {
+        if let Err(e) = fs::rename(previous, destination) {
+            error!("Could not move the old version back to {}: {}", destination.display(), e);
+        }
+
+        return Err(PluginInstallError::Copy(format!("Could not move the new version to {}: {}", destination.display(), e)));
+    }
+
+    Ok(())
+}
+
+/// Remove a directory link without touching the directory it points to.
+///
+/// On Windows, directory symlinks and junctions are removed like directories, elsewhere like files.
+fn remove_link(link: &Path) -> std::io::Result<()> {
+    fs::remove_dir(link).or_else(|_| fs::remove_file(link))
+}
+
+/// Whether the plugin names are the same, ignoring case.
+///
+/// Plugins are identified by their name, which must be unique regardless of case, as Windows' file names are.
+fn is_same_name(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+/// File names Windows reserves for devices, regardless of their extension.
+const RESERVED_FOLDER_NAMES: [&str; 22] = [
+    "con", "prn", "aux", "nul",
+    "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8", "com9",
+    "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Sanitizes the given name to be used as a folder name, i.e., turns it into a slug.
 /// 
 /// This function returns `Some` if the name can be sanitized and
 /// and `None` if it can't.
 /// If it returns `Some`, this will contain the sanitized name.
 /// 
-/// A name can only be sanitized if the it solely consists of
-/// ASCII characters.
+/// The name is lowercased and letters and digits of any script are kept, e.g., `Café Plugin 1.0` becomes
+/// `café_plugin_1-0`. Whitespace becomes `_`, dots become `-`, and other characters are dropped.
+/// A name can't be sanitized if no letter or digit is left or if it is reserved by Windows.
+/// The plugin's name itself is kept as it is and shown to users.
 fn sanitize_name(name: &str) -> Option<String> {
-    let name = name.to_ascii_lowercase();
-    let re = Regex::new(r"^[a-z0-9 \.]*$").unwrap();
+    let mut slug = String::with_capacity(name.len());
+
+    for c in name.trim().chars().flat_map(char::to_lowercase) {
+        match c {
+            c if c.is_alphanumeric() => slug.push(c),
+            c if c.is_whitespace() || c == '_' => slug.push('_'),
+            '.' | '-' => slug.push('-'),
+            _ => (),
+        }
+    }
 
-    if !re.is_match(&name) {
+    if !slug.chars().any(char::is_alphanumeric) || RESERVED_FOLDER_NAMES.contains(&slug.as_str()) {
         return None;
     }
 
-    Some(
-        name.replace(" ", "_")
-            .replace(".", "-")
-    )
+    Some(slug)
+}
+
+/// Name of the developer environment, e.g., shown as the source of its `print` calls.
+const DEVELOPER_ENVIRONMENT_NAME: &str = "developer";
+
+/// Libraries of the developer environment.
+const DEVELOPER_LIBRARIES: [PluginDependency; 10] = [
+    PluginDependency::Game,
+    PluginDependency::Dangerous,
+    PluginDependency::Structs,
+    PluginDependency::Input,
+    PluginDependency::Matrix,
+    PluginDependency::Math,
+    PluginDependency::Table,
+    PluginDependency::String,
+    PluginDependency::Bit32,
+    PluginDependency::Utf8,
+];
+
+/// Name of the scratch environment of the REPL.
+const SCRATCH_ENVIRONMENT_NAME: &str = "scratch";
+
+/// Libraries of the scratch environment.
+///
+/// The REPL is available outside of developer mode, so it has no library that can write memory or send input.
+const SCRATCH_LIBRARIES: [PluginDependency; 7] = [
+    PluginDependency::Game,
+    PluginDependency::Matrix,
+    PluginDependency::Math,
+    PluginDependency::Table,
+    PluginDependency::String,
+    PluginDependency::Bit32,
+    PluginDependency::Utf8,
+];
+
+/// Create an environment that isn't a plugin's, e.g., the one watch expressions are evaluated in.
+///
+/// Unlike a plugin's environment, its libraries are globals, so expressions don't need to require them.
+fn create_global_environment(lua: Arc<Lua>, name: &str, libraries: &[PluginDependency], plugins_directory: &Path) -> Result<PluginEnvironment, mlua::Error> {
+    let info = PluginInfo {
+        name: name.to_string(),
+        path: plugins_directory.to_path_buf(),
+        dependencies: libraries.to_vec(),
+        ..Default::default()
+    };
+
+    let environment = PluginEnvironment::new(lua, &info)?;
+    let table = environment.table.to_ref();
+    let require: mlua::Function = table.get("require")?;
+
+    for library in libraries.iter() {
+        let name = library.library_name();
+        table.set(name, require.call::<_, mlua::Value>(name)?)?;
+    }
+
+    Ok(environment)
+}
+
+/// Lines a chunk may print. Further lines are dropped.
+const MAX_CHUNK_OUTPUT: usize = 100;
+
+/// Run the chunk in the environment, see [`run_chunk_in`], and abort it after the timeout.
+fn run_chunk_with_timeout(lua: &Lua, environment: &OwnedTable, chunk: &str, timeout: Duration) -> Result<ChunkResult, anyhow::Error> {
+    let start = Instant::now();
+    lua.set_interrupt(move |_| match start.elapsed() > timeout {
+        true => Err(mlua::Error::RuntimeError(format!("chunk took longer than {}ms", timeout.as_millis()))),
+        false => Ok(VmState::Continue),
+    });
+
+    let result = run_chunk_in(lua, environment, chunk);
+
+    lua.remove_interrupt();
+
+    result.map_err(|e| anyhow!("could not run the chunk: {}", e))
+}
+
+/// Run the chunk in the environment with a `print` that captures its lines.
+///
+/// Errors of the chunk are part of the result. Only errors of preparing the environment are returned.
+fn run_chunk_in(lua: &Lua, environment: &OwnedTable, chunk: &str) -> Result<ChunkResult, mlua::Error> {
+    let mut result = ChunkResult::default();
+
+    // Chunks are expressions if they compile as one, statements otherwise
+    let function = lua
+        .load(format!("return {}", chunk))
+        .set_name("=console")
+        .set_environment(environment.clone())
+        .into_function()
+        .or_else(|_| lua.load(chunk).set_name("=console").set_environment(environment.clone()).into_function());
+
+    let function = match function {
+        Ok(function) => function,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return Ok(result);
+        },
+    };
+
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let print_output = output.clone();
+    let print_fn = lua.create_function(move |_, values: Variadic<mlua::Value>| {
+        let line = values
+            .iter()
+            .map(|value| value.to_string().unwrap_or_else(|_| format!("{:?}", value)))
+            .collect::<Vec<String>>()
+            .join("\t");
+
+        if let Ok(mut output) = print_output.lock() {
+            if output.len() < MAX_CHUNK_OUTPUT {
+                output.push(line);
+            }
+        }
+
+        Ok(())
+    })?;
+
+    let table = environment.to_ref();
+    let print: mlua::Value = table.get("print")?;
+    table.set("print", print_fn)?;
+
+    let values = function.call::<_, MultiValue>(());
+
+    table.set("print", print)?;
+
+    match values {
+        Ok(values) => {
+            let descriptions = values
+                .into_iter()
+                .map(|value| describe_value(lua, value))
+                .collect::<Result<Vec<String>, mlua::Error>>()?;
+
+            if !descriptions.is_empty() {
+                result.value = Some(descriptions.join(", "));
+            }
+        },
+        Err(e) => result.error = Some(e.to_string()),
+    }
+
+    result.output = output.lock().map(|output| output.clone()).unwrap_or_default();
+
+    Ok(result)
+}
+
+/// Describe the value of an expression, e.g., `42`, `"text"` or `{"x":1}`.
+fn describe_value(lua: &Lua, value: mlua::Value) -> Result<String, mlua::Error> {
+    match value {
+        mlua::Value::String(string) => Ok(format!("{:?}", string.to_string_lossy())),
+        // Tables with values JSON can't represent, e.g., functions, are described like other values
+        mlua::Value::Table(_) => match lua.from_value::<serde_json::Value>(value.clone()) {
+            Ok(json) => Ok(json.to_string()),
+            Err(_) => value.to_string(),
+        },
+        value => value.to_string(),
+    }
 }
@@ -0,0 +1,177 @@
+use std::{collections::{BTreeMap, HashMap}, fs, path::PathBuf, sync::Mutex};
+
+use anyhow::{anyhow, bail};
+use futuremod_data::{plugin::PluginInfo, settings::{PluginSettings, Setting, SettingKind, SettingValue}};
+use log::*;
+
+use crate::key_bindings;
+
+/// File in the plugin's folder that holds the values the user chose for the plugin's settings.
+///
+/// Excluded from the plugin's checksum, since the user changes it by design.
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+
+lazy_static! {
+    /// Settings of the plugins by the plugin's name, read when first used.
+    static ref SETTINGS: Mutex<HashMap<String, PluginSettingsState>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug)]
+struct PluginSettingsState {
+    /// Settings of the plugin's info followed by the settings the plugin defined.
+    settings: Vec<Setting>,
+    /// Values the user chose. Settings without a value use their default.
+    values: BTreeMap<String, SettingValue>,
+    path: PathBuf,
+}
+
+impl PluginSettingsState {
+    fn new(info: &PluginInfo) -> PluginSettingsState {
+        let path = info.path.join(SETTINGS_FILE_NAME);
+        debug!("Reading plugin settings from '{}'", path.display());
+
+        let values = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Could not parse the settings of plugin {}, using the defaults: {}", info.name, e);
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+
+        let mut state = PluginSettingsState { settings: Vec::new(), values, path };
+        for setting in info.settings.iter() {
+            if let Err(e) = state.define(&info.name, setting.clone()) {
+                warn!("Ignoring setting '{}' of plugin {}: {}", setting.name, info.name, e);
+            }
+        }
+
+        state
+    }
+
+    fn define(&mut self, plugin: &str, setting: Setting) -> Result<(), anyhow::Error> {
+        setting.validate(&setting.default_value()).map_err(|e| anyhow!("invalid default: {}", e))?;
+
+        if let SettingKind::KeyBinding { default } = &setting.kind {
+            key_bindings::register(plugin, &setting.name, default)?;
+        }
+
+        match self.settings.iter_mut().find(|defined| defined.name == setting.name) {
+            Some(defined) => *defined = setting,
+            None => self.settings.push(setting),
+        }
+
+        Ok(())
+    }
+
+    /// Current value of the setting, or its default if the user's value doesn't fit the setting anymore.
+    fn value(&self, plugin: &str, setting: &Setting) -> SettingValue {
+        if let SettingKind::KeyBinding { default } = &setting.kind {
+            return SettingValue::Text(key_bindings::get_key(plugin, &setting.name).unwrap_or_else(|| default.clone()));
+        }
+
+        match self.values.get(&setting.name) {
+            Some(value) if setting.validate(value).is_ok() => value.clone(),
+            _ => setting.default_value(),
+        }
+    }
+
+    fn write_to_file(&self) -> Result<(), anyhow::Error> {
+        let content = serde_json::to_string_pretty(&self.values).map_err(|e| anyhow!("could not serialize the plugin's settings: {}", e))?;
+
+        fs::write(&self.path, content).map_err(|e| anyhow!("could not write the plugin's settings: {}", e))
+    }
+}
+
+fn with_settings<F, R>(info: &PluginInfo, f: F) -> Result<R, anyhow::Error>
+where F: FnOnce(&mut PluginSettingsState) -> Result<R, anyhow::Error> {
+    let mut settings = SETTINGS.lock().map_err(|e| anyhow!("could not get lock to the plugin settings: {}", e))?;
+
+    let state = settings
+        .entry(info.name.clone())
+        .or_insert_with(|| PluginSettingsState::new(info));
+
+    f(state)
+}
+
+/// Read the plugin's settings again and register its key binding settings.
+///
+/// Called when the plugin is loaded, after its previous key bindings were cleared.
+pub fn reload(info: &PluginInfo) {
+    match SETTINGS.lock() {
+        Ok(mut settings) => {
+            settings.insert(info.name.clone(), PluginSettingsState::new(info));
+        },
+        Err(e) => warn!("Could not get lock to the plugin settings: {}", e),
+    }
+}
+
+/// Define a setting in addition to the settings of the plugin's info and return its current value.
+///
+/// A setting with the same name is replaced.
+pub fn define(info: &PluginInfo, setting: Setting) -> Result<SettingValue, anyhow::Error> {
+    with_settings(info, |state| {
+        state.define(&info.name, setting.clone())?;
+
+        Ok(state.value(&info.name, &setting))
+    })
+}
+
+/// Get the current value of the setting, or `None` if the plugin has no such setting.
+pub fn get_value(info: &PluginInfo, name: &str) -> Result<Option<SettingValue>, anyhow::Error> {
+    with_settings(info, |state| {
+        Ok(state.settings.iter().find(|setting| setting.name == name).map(|setting| state.value(&info.name, setting)))
+    })
+}
+
+/// Get the plugin's settings and their current values.
+pub fn get_settings(info: &PluginInfo) -> Result<PluginSettings, anyhow::Error> {
+    with_settings(info, |state| {
+        let values = state
+            .settings
+            .iter()
+            .map(|setting| (setting.name.clone(), state.value(&info.name, setting)))
+            .collect();
+
+        Ok(PluginSettings { settings: state.settings.clone(), values })
+    })
+}
+
+/// Change the values of the plugin's settings and persist them. The plugin reads the new values immediately.
+///
+/// Changes no value if any value is invalid. Keys of key bindings must be changed with [`key_bindings::set_binding`].
+pub fn set_values(info: &PluginInfo, values: BTreeMap<String, SettingValue>) -> Result<(), anyhow::Error> {
+    with_settings(info, |state| {
+        for (name, value) in values.iter() {
+            let setting = match state.settings.iter().find(|setting| &setting.name == name) {
+                Some(setting) => setting,
+                None => bail!("plugin '{}' has no setting '{}'", info.name, name),
+            };
+
+            if let SettingKind::KeyBinding { .. } = setting.kind {
+                bail!("'{}' is a key binding, change it with the plugin's key bindings", name);
+            }
+
+            setting.validate(value).map_err(|e| anyhow!(e))?;
+        }
+
+        let previous = state.values.clone();
+        state.values.extend(values);
+
+        if let Err(e) = state.write_to_file() {
+            state.values = previous;
+            return Err(e);
+        }
+
+        info!("Changed the settings of plugin {}", info.name);
+        Ok(())
+    })
+}
+
+/// Forget the plugin's settings, so they are read from its info and file again when used next time.
+///
+/// Called when the plugin is uninstalled.
+pub fn forget(plugin: &str) {
+    if let Ok(mut settings) = SETTINGS.lock() {
+        settings.remove(plugin);
+    }
+}
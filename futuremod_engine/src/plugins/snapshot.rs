@@ -0,0 +1,55 @@
+use std::{collections::HashMap, sync::{Arc, Mutex, RwLock}, time::{Duration, Instant}};
+
+use futuremod_data::plugin::Plugin;
+use log::*;
+
+use super::PluginManager;
+
+/// How often the game loop publishes the snapshot.
+const INTERVAL: Duration = Duration::from_millis(250);
+
+lazy_static! {
+    static ref SNAPSHOT: RwLock<Arc<HashMap<String, Plugin>>> = RwLock::new(Arc::new(HashMap::new()));
+    static ref PUBLISHED_AT: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+/// Replace the snapshot with the current data of the manager's plugins.
+///
+/// The game loop holds the plugin manager's write lock while it updates the plugins every frame. Endpoints that only
+/// show the plugins, e.g., the GUI's plugin list, read the snapshot instead, so they never wait for the update pass.
+/// Must be called after every change outside the game loop.
+pub fn publish(manager: &PluginManager) {
+    let plugins: HashMap<String, Plugin> = manager.get_plugins()
+        .iter()
+        .map(|(name, plugin)| (name.clone(), plugin.clone().into()))
+        .collect();
+
+    match SNAPSHOT.write() {
+        Ok(mut snapshot) => *snapshot = Arc::new(plugins),
+        Err(e) => warn!("Could not get lock to the plugin snapshot: {}", e),
+    }
+
+    if let Ok(mut published_at) = PUBLISHED_AT.lock() {
+        *published_at = Some(Instant::now());
+    }
+}
+
+/// Publish the snapshot if the last one is older than [`INTERVAL`]. Called by the game loop every frame.
+pub fn publish_if_due(manager: &PluginManager) {
+    let is_due = match PUBLISHED_AT.lock() {
+        Ok(published_at) => published_at.map_or(true, |published_at| published_at.elapsed() >= INTERVAL),
+        Err(_) => false,
+    };
+
+    if is_due {
+        publish(manager);
+    }
+}
+
+/// The latest snapshot of the plugins, by their name.
+pub fn get() -> Arc<HashMap<String, Plugin>> {
+    match SNAPSHOT.read() {
+        Ok(snapshot) => snapshot.clone(),
+        Err(e) => e.into_inner().clone(),
+    }
+}
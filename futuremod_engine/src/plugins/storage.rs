@@ -0,0 +1,155 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::Mutex};
+
+use anyhow::{anyhow, bail};
+use log::*;
+use serde_json::{Map, Value};
+
+use super::persistence;
+
+/// File in the plugin's folder that holds the values the plugin stored.
+///
+/// Excluded from the plugin's checksum, since the plugin changes it by design.
+pub const STORAGE_FILE_NAME: &str = "storage.json";
+
+/// Maximum size of a plugin's stored values serialized as JSON, so a plugin can't fill the disk.
+pub const MAX_STORAGE_SIZE: usize = 1024 * 1024;
+
+/// Whether the file in the plugin's folder belongs to the plugin's storage, i.e., the storage file, its snapshot, or its temporary file.
+pub fn is_storage_file(relative_path: &str) -> bool {
+    let path = Path::new(STORAGE_FILE_NAME);
+
+    relative_path == STORAGE_FILE_NAME
+        || Path::new(relative_path) == persistence::snapshot_path(path)
+        || Path::new(relative_path) == persistence::temporary_path(path)
+}
+
+lazy_static! {
+    /// Stored values of the plugins by the plugin's name, read from the file when first used.
+    static ref STORAGES: Mutex<HashMap<String, PluginStorage>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug)]
+struct PluginStorage {
+    values: Map<String, Value>,
+    path: PathBuf,
+    /// Whether the values changed since they were last written, see [`persistence`].
+    changed: bool,
+}
+
+impl PluginStorage {
+    fn new(folder: &Path) -> Result<PluginStorage, anyhow::Error> {
+        let path = folder.join(STORAGE_FILE_NAME);
+        debug!("Reading plugin storage from '{}'", path.display());
+
+        let values = persistence::read(&path)
+            .map_err(|e| anyhow!("could not read the plugin's storage: {}", e))?
+            .unwrap_or_default();
+
+        Ok(PluginStorage { values, path, changed: false })
+    }
+
+    fn serialize(&self) -> Result<String, anyhow::Error> {
+        serde_json::to_string(&self.values).map_err(|e| anyhow!("could not serialize the plugin's storage: {}", e))
+    }
+
+    /// Check that the values don't exceed the quota and mark them to be written.
+    fn change(&mut self) -> Result<(), anyhow::Error> {
+        if self.serialize()?.len() > MAX_STORAGE_SIZE {
+            bail!("the plugin's storage would exceed its quota of {} KB", MAX_STORAGE_SIZE / 1024);
+        }
+
+        self.changed = true;
+
+        Ok(())
+    }
+
+    /// Write the values to the file if they changed since they were last written.
+    fn write_to_file(&mut self) -> Result<(), anyhow::Error> {
+        if !self.changed {
+            return Ok(());
+        }
+
+        persistence::write(&self.path, self.serialize()?.as_bytes()).map_err(|e| anyhow!("could not write the plugin's storage: {}", e))?;
+        self.changed = false;
+
+        Ok(())
+    }
+}
+
+fn with_storage<F, R>(plugin: &str, folder: &Path, f: F) -> Result<R, anyhow::Error>
+where F: FnOnce(&mut PluginStorage) -> Result<R, anyhow::Error> {
+    let mut storages = STORAGES.lock().map_err(|e| anyhow!("could not get lock to the plugin storages: {}", e))?;
+
+    let storage = match storages.entry(plugin.to_string()) {
+        std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+        std::collections::hash_map::Entry::Vacant(entry) => entry.insert(PluginStorage::new(folder)?),
+    };
+
+    f(storage)
+}
+
+/// Get the value the plugin stored under the key.
+pub fn get(plugin: &str, folder: &Path, key: &str) -> Result<Option<Value>, anyhow::Error> {
+    with_storage(plugin, folder, |storage| Ok(storage.values.get(key).cloned()))
+}
+
+/// Store the value under the key. The value is written with the next [`persist`].
+///
+/// Fails without changing the storage if the plugin's storage would exceed its quota.
+pub fn set(plugin: &str, folder: &Path, key: &str, value: Value) -> Result<(), anyhow::Error> {
+    with_storage(plugin, folder, |storage| {
+        let previous = storage.values.insert(key.to_string(), value);
+
+        if let Err(e) = storage.change() {
+            match previous {
+                Some(previous) => storage.values.insert(key.to_string(), previous),
+                None => storage.values.remove(key),
+            };
+
+            return Err(e);
+        }
+
+        Ok(())
+    })
+}
+
+/// Remove the value stored under the key. The removal is written with the next [`persist`].
+pub fn delete(plugin: &str, folder: &Path, key: &str) -> Result<(), anyhow::Error> {
+    with_storage(plugin, folder, |storage| match storage.values.remove(key) {
+        Some(_) => storage.change(),
+        None => Ok(()),
+    })
+}
+
+/// Write the values of all plugins that changed since they were last written.
+pub fn persist() {
+    let mut storages = match STORAGES.lock() {
+        Ok(storages) => storages,
+        Err(e) => {
+            warn!("Could not get lock to the plugin storages: {}", e);
+            return;
+        },
+    };
+
+    // Plugins that were uninstalled after they stored values
+    storages.retain(|_, storage| storage.path.parent().is_some_and(Path::is_dir));
+
+    for (plugin, storage) in storages.iter_mut() {
+        if let Err(e) = storage.write_to_file() {
+            warn!("Could not persist the storage of plugin {}: {}", plugin, e);
+        }
+    }
+}
+
+/// Write the plugin's changed values and forget them, so they are read from its file again when used next time.
+///
+/// Called when the plugin is loaded or uninstalled.
+pub fn forget(plugin: &str) {
+    if let Ok(mut storages) = STORAGES.lock() {
+        if let Some(mut storage) = storages.remove(plugin) {
+            if let Err(e) = storage.write_to_file() {
+                warn!("Could not persist the storage of plugin {}: {}", plugin, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::{collections::{BTreeSet, HashMap}, fs, path::{Path, PathBuf}, sync::{atomic::{AtomicU64, Ordering}, Mutex}, time::{Duration, Instant}};
+
+use anyhow::anyhow;
+use log::*;
+
+/// Seconds after enabling a plugin in which a crash makes the plugin a suspect.
+static WINDOW_SECONDS: AtomicU64 = AtomicU64::new(30);
+
+lazy_static! {
+    /// Time at which the enabled plugins were enabled.
+    static ref ENABLED_AT: Mutex<HashMap<String, Instant>> = Mutex::new(HashMap::new());
+
+    /// File of the suspects, known once the plugin manager read it.
+    static ref SUSPECTS_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// Set the number of seconds after enabling a plugin in which a crash makes the plugin a suspect.
+pub fn set_window(seconds: u64) {
+    WINDOW_SECONDS.store(seconds, Ordering::SeqCst);
+}
+
+/// Record that the plugin was enabled.
+pub fn record_enabled(name: &str) {
+    if let Ok(mut enabled_at) = ENABLED_AT.lock() {
+        enabled_at.insert(name.to_string(), Instant::now());
+    }
+}
+
+/// Record that the plugin was disabled. Disabled plugins can't cause a crash.
+pub fn record_disabled(name: &str) {
+    if let Ok(mut enabled_at) = ENABLED_AT.lock() {
+        enabled_at.remove(name);
+    }
+}
+
+/// Persist the plugins that were enabled shortly before the crash as suspects.
+///
+/// Called by the crash handler before it disables the plugins.
+/// The crash might have happened while holding a lock, therefore, only tries to get the locks.
+pub fn record_crash() {
+    let window = Duration::from_secs(WINDOW_SECONDS.load(Ordering::SeqCst));
+
+    let names: Vec<(String, Duration)> = match ENABLED_AT.try_lock() {
+        Ok(enabled_at) => enabled_at
+            .iter()
+            .map(|(name, at)| (name.clone(), at.elapsed()))
+            .filter(|(_, elapsed)| *elapsed <= window)
+            .collect(),
+        Err(_) => {
+            warn!("Could not get lock to the enabled plugins, no plugin is marked as suspect");
+            return;
+        },
+    };
+
+    if names.is_empty() {
+        return;
+    }
+
+    let path = match SUSPECTS_FILE.try_lock().ok().and_then(|path| path.clone()) {
+        Some(path) => path,
+        None => {
+            warn!("Suspects file is unknown, no plugin is marked as suspect");
+            return;
+        },
+    };
+
+    let mut suspects = match PluginSuspects::new(&path) {
+        Ok(suspects) => suspects,
+        Err(e) => {
+            error!("Could not read the suspects: {}", e);
+            return;
+        },
+    };
+
+    for (name, elapsed) in names {
+        warn!("Game crashed {:.1}s after plugin '{}' was enabled, it starts disabled next time", elapsed.as_secs_f32(), name);
+        suspects.names.insert(name);
+    }
+
+    if let Err(e) = suspects.write_to_file() {
+        error!("Could not write the suspects: {}", e);
+    }
+}
+
+/// Plugins suspected of crashing the game, because the game crashed shortly after they were enabled.
+///
+/// Suspects start disabled until the user enables them again or dismisses the suspicion.
+#[derive(Debug, Clone)]
+pub struct PluginSuspects {
+    names: BTreeSet<String>,
+    path: PathBuf,
+}
+
+impl PluginSuspects {
+    pub fn new(path: &Path) -> Result<PluginSuspects, anyhow::Error> {
+        debug!("Reading plugin suspects from '{}'", path.display());
+
+        let names: BTreeSet<String> = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| anyhow!("could not parse the plugin suspects file: {}", e))?,
+            Err(_) => BTreeSet::new(),
+        };
+
+        if let Ok(mut suspects_file) = SUSPECTS_FILE.lock() {
+            *suspects_file = Some(path.to_path_buf());
+        }
+
+        Ok(PluginSuspects { names, path: path.to_path_buf() })
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), anyhow::Error> {
+        if !self.names.remove(name) {
+            return Ok(());
+        }
+
+        self.write_to_file()
+    }
+
+    fn write_to_file(&self) -> Result<(), anyhow::Error> {
+        let content = serde_json::to_string_pretty(&self.names).map_err(|e| anyhow!("could not serialize plugin suspects: {}", e))?;
+
+        fs::write(&self.path, content).map_err(|e| anyhow!("could not write plugin suspects: {}", e))
+    }
+}
@@ -0,0 +1,112 @@
+use std::{path::Path, sync::Arc, time::{Duration, Instant}};
+
+use futuremod_data::plugin::{PluginDependency, PluginInfo};
+use log::*;
+use mlua::{Function, Lua, OwnedFunction, VmState};
+
+use crate::config::TweakConfig;
+
+use super::plugin_environment::PluginEnvironment;
+
+/// Time a tweak's script may run at startup before it's stopped.
+const TWEAK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Libraries tweaks may use.
+///
+/// Libraries whose callbacks are passed to plugins, e.g., `events`, are missing, as tweaks aren't plugins.
+const TWEAK_LIBRARIES: [PluginDependency; 8] = [
+    PluginDependency::Game,
+    PluginDependency::Input,
+    PluginDependency::Matrix,
+    PluginDependency::Math,
+    PluginDependency::Table,
+    PluginDependency::String,
+    PluginDependency::Bit32,
+    PluginDependency::Utf8,
+];
+
+/// Small Lua snippet from the engine's config that runs without a plugin package.
+///
+/// Tweaks run in a restricted environment: they only get the `TWEAK_LIBRARIES`, which neither access memory directly,
+/// the filesystem, nor the network, and they can't require files.
+pub struct Tweak {
+    pub name: String,
+    /// The tweak's own Lua state.
+    lua: Arc<Lua>,
+    /// Keeps the globals of the tweak's functions alive.
+    _environment: PluginEnvironment,
+    /// The tweak's `onUpdate` function, called every frame of a mission.
+    on_update: Option<OwnedFunction>,
+}
+
+impl Tweak {
+    /// Run the tweak's script once in the Lua state. The tweak's libraries are globals, so the script doesn't need to require them.
+    pub fn run(lua: Arc<Lua>, plugins_directory: &Path, config: &TweakConfig) -> Result<Tweak, anyhow::Error> {
+        let dependencies: Vec<PluginDependency> = config.dependencies.iter()
+            .filter(|dependency| match TWEAK_LIBRARIES.contains(dependency) {
+                true => true,
+                false => {
+                    warn!("Tweak '{}' can't use the {} library, ignoring it", config.name, dependency);
+                    false
+                },
+            })
+            .cloned()
+            .collect();
+
+        let info = PluginInfo {
+            name: format!("tweak:{}", config.name),
+            // Doesn't exist, so tweaks can't require files
+            path: plugins_directory.join("tweaks").join(&config.name),
+            dependencies: dependencies.clone(),
+            permissions: Some(Vec::new()),
+            ..Default::default()
+        };
+
+        let environment = PluginEnvironment::new(lua.clone(), &info)?;
+        let table = environment.table.to_ref();
+        let require: Function = table.get("require")?;
+
+        for library in dependencies.iter() {
+            let name = library.library_name();
+            table.set(name, require.call::<_, mlua::Value>(name)?)?;
+        }
+
+        let start = Instant::now();
+        lua.set_interrupt(move |_| match start.elapsed() > TWEAK_TIMEOUT {
+            true => Err(mlua::Error::RuntimeError(format!("tweak took longer than {}ms", TWEAK_TIMEOUT.as_millis()))),
+            false => Ok(VmState::Continue),
+        });
+
+        let result = lua
+            .load(config.script.as_str())
+            .set_name(format!("=tweak:{}", config.name))
+            .set_environment(environment.table.clone())
+            .exec();
+
+        lua.remove_interrupt();
+        result?;
+
+        let on_update = table.get::<_, Option<Function>>("onUpdate")?.map(|function| function.into_owned());
+
+        Ok(Tweak { name: config.name.clone(), lua, _environment: environment, on_update })
+    }
+
+    pub fn lua(&self) -> &Lua {
+        &self.lua
+    }
+
+    /// Call the tweak's `onUpdate` function, if it has one.
+    ///
+    /// If it throws an error, it isn't called anymore.
+    pub fn on_update(&mut self) {
+        let on_update = match &self.on_update {
+            Some(on_update) => on_update,
+            None => return,
+        };
+
+        if let Err(e) = on_update.call::<_, ()>(()) {
+            warn!("Tweak '{}' threw error in onUpdate, not calling it anymore: {}", self.name, e);
+            self.on_update = None;
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, thread, time::{Duration, SystemTime}};
+
+use anyhow::anyhow;
+use futuremod_data::plugin::HOT_RELOAD_LOG_TARGET;
+use log::*;
+use walkdir::WalkDir;
+
+use crate::teardown;
+
+use super::plugin::ALLOWED_EXTENSIONS;
+use super::plugin_manager::{GlobalPluginManager, PluginManagerError};
+
+/// Interval in which the folders of plugins installed in developer mode are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+const INFO_FILE_NAME: &str = "info.toml";
+
+/// Modification times of a plugin's Lua files and its info file.
+type Snapshot = HashMap<PathBuf, SystemTime>;
+
+/// Start watching the folders of plugins installed in developer mode and reload them when their files change.
+///
+/// Must be called after the global plugin manager was initialized.
+pub fn start() {
+    thread::spawn(run);
+}
+
+fn run() {
+    let _worker = teardown::worker();
+    let mut snapshots: HashMap<String, Snapshot> = HashMap::new();
+
+    while teardown::sleep(POLL_INTERVAL) {
+
+        let dev_plugins = match GlobalPluginManager::with_plugin_manager(|manager| Ok(manager.get_dev_plugin_folders())) {
+            Ok(dev_plugins) => dev_plugins,
+            Err(e) => {
+                warn!("Could not get the plugins to watch: {}", e);
+                continue;
+            },
+        };
+
+        snapshots.retain(|name, _| dev_plugins.iter().any(|(dev_plugin, _)| dev_plugin == name));
+
+        for (name, folder) in dev_plugins {
+            let snapshot = take_snapshot(&folder);
+
+            // The first snapshot of a plugin is only the baseline
+            let changed_files = match snapshots.insert(name.clone(), snapshot.clone()) {
+                Some(previous) => changed_files(&previous, &snapshot),
+                None => continue,
+            };
+
+            if !changed_files.is_empty() {
+                reload(&name, &folder, &changed_files);
+            }
+        }
+    }
+}
+
+/// Whether changes to the file affect the plugin.
+fn is_watched(path: &Path) -> bool {
+    let is_info_file = path.file_name().is_some_and(|name| name == INFO_FILE_NAME);
+    let is_lua_file = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| ALLOWED_EXTENSIONS.contains(&extension));
+
+    is_info_file || is_lua_file
+}
+
+fn take_snapshot(folder: &Path) -> Snapshot {
+    WalkDir::new(folder)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_watched(entry.path()))
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((entry.into_path(), modified))
+        })
+        .collect()
+}
+
+/// Files that were added, removed, or modified.
+fn changed_files(previous: &Snapshot, current: &Snapshot) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = current
+        .iter()
+        .filter(|(path, modified)| previous.get(*path) != Some(*modified))
+        .map(|(path, _)| path.clone())
+        .collect();
+
+    changed.extend(previous.keys().filter(|path| !current.contains_key(*path)).cloned());
+
+    changed
+}
+
+/// Reload the plugin, including its info file if it changed.
+///
+/// Logs the result with the hot reload target, so the GUI can refresh the plugin.
+fn reload(name: &str, folder: &Path, changed_files: &[PathBuf]) {
+    let info_changed = changed_files.iter().any(|path| path.file_name().is_some_and(|file_name| file_name == INFO_FILE_NAME));
+
+    let result = GlobalPluginManager::with_plugin_manager_mut(|manager| {
+        let result = match info_changed {
+            true => manager.force_reload_plugin(name),
+            false => manager.reload_plugin(name),
+        };
+
+        result.map_err(|e| match e {
+            PluginManagerError::Plugin(e) => anyhow!("{}", e),
+            e => anyhow!("{:?}", e),
+        })
+    });
+
+    let file = changed_files[0].strip_prefix(folder).unwrap_or(&changed_files[0]).display().to_string();
+
+    match result {
+        Ok(_) => info!(target: HOT_RELOAD_LOG_TARGET, plugin = name; "Reloaded plugin '{}' after '{}' changed", name, file),
+        Err(e) => error!(target: HOT_RELOAD_LOG_TARGET, plugin = name; "Could not reload plugin '{}' after '{}' changed: {}", name, file, e),
+    }
+}
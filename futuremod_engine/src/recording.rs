@@ -0,0 +1,93 @@
+use std::{collections::HashSet, fs::File, io::{BufRead, BufReader, Write}, path::Path, sync::Mutex};
+
+use anyhow::anyhow;
+use futuremod_hook::recorder::{self, HookInvocation};
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::HookRecordingConfig, futurecop::{global::GetterSetter, FRAME_NUMBER}};
+
+/// Invocation of a hooked function in a recording.
+///
+/// Recordings are stored as JSON lines, one invocation per line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedInvocation {
+    /// Frame in which the hooked function was called.
+    pub frame: u32,
+
+    /// Address of the hooked function.
+    pub address: u32,
+
+    /// Raw arguments, first argument first.
+    ///
+    /// Pointer arguments are recorded as they are, the memory they point to is not recorded.
+    pub arguments: Vec<u32>,
+
+    /// Raw value returned by the hook.
+    pub return_value: u32,
+}
+
+/// Start recording invocations of the configured hooks.
+///
+/// Every invocation of a plugin hook on one of the configured addresses is appended to the configured file.
+pub fn start(config: &HookRecordingConfig) -> Result<(), anyhow::Error> {
+    let file = File::create(&config.file).map_err(|e| anyhow!("could not create recording file '{}': {}", config.file, e))?;
+    let file = Mutex::new(file);
+    let addresses: HashSet<u32> = config.addresses.iter().cloned().collect();
+
+    recorder::set_recorder(move |invocation: HookInvocation| {
+        if !addresses.contains(&invocation.address) {
+            return;
+        }
+
+        let recorded = RecordedInvocation {
+            frame: *FRAME_NUMBER.get(),
+            address: invocation.address,
+            arguments: invocation.arguments,
+            return_value: invocation.return_value,
+        };
+
+        let line = match serde_json::to_string(&recorded) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Could not serialize hook invocation: {}", e);
+                return;
+            },
+        };
+
+        match file.lock() {
+            Ok(mut file) => {
+                if let Err(e) = writeln!(file, "{}", line) {
+                    warn!("Could not write hook invocation to the recording: {}", e);
+                }
+            },
+            Err(e) => warn!("Could not get lock to the recording file: {}", e),
+        }
+    });
+
+    info!("Recording invocations of hooks on {:x?} to '{}'", config.addresses, config.file);
+
+    Ok(())
+}
+
+/// Load a recording from a file.
+pub fn load(path: &Path) -> Result<Vec<RecordedInvocation>, anyhow::Error> {
+    let file = File::open(path).map_err(|e| anyhow!("could not open recording '{}': {}", path.display(), e))?;
+
+    let mut invocations = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| anyhow!("could not read recording '{}': {}", path.display(), e))?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let invocation: RecordedInvocation = serde_json::from_str(&line)
+            .map_err(|e| anyhow!("invalid invocation in line {} of recording '{}': {}", number + 1, path.display(), e))?;
+
+        invocations.push(invocation);
+    }
+
+    Ok(invocations)
+}
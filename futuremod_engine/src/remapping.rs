@@ -0,0 +1,372 @@
+use std::{collections::HashMap, sync::{atomic::{AtomicU32, Ordering}, Once, RwLock}, thread};
+
+use anyhow::anyhow;
+use device_query::Keycode;
+use futuremod_data::input::InputRemap;
+use log::*;
+use windows::{core::PCSTR, Win32::{Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM}, System::{LibraryLoader::GetModuleHandleA, Threading::GetCurrentThreadId}, UI::{Input::KeyboardAndMouse::*, WindowsAndMessaging::*}}};
+
+use crate::{console, futurecop::state::FUTURE_COP, game_input, input::KeyState, key_bindings, teardown, widgets, plugins::library::input::{keycode_from_string, keycode_to_string, SUPPORTED_KEYCODES}};
+
+lazy_static! {
+    /// Remaps from the key the user presses to the key the game reads.
+    static ref REMAPS: RwLock<HashMap<Keycode, Keycode>> = RwLock::new(HashMap::new());
+    static ref CONFIGURED_REMAPS: RwLock<Vec<InputRemap>> = RwLock::new(Vec::new());
+}
+
+static START_HOOK: Once = Once::new();
+
+/// Id of the thread that runs the keyboard hook, `0` if it doesn't run.
+static HOOK_THREAD: AtomicU32 = AtomicU32::new(0);
+
+/// Get the Windows virtual key of the key.
+///
+/// Returns `None` for keys that don't exist on Windows.
+pub(crate) fn virtual_key(keycode: Keycode) -> Option<VIRTUAL_KEY> {
+    let key = match keycode {
+        Keycode::Key0 => VK_0,
+        Keycode::Key1 => VK_1,
+        Keycode::Key2 => VK_2,
+        Keycode::Key3 => VK_3,
+        Keycode::Key4 => VK_4,
+        Keycode::Key5 => VK_5,
+        Keycode::Key6 => VK_6,
+        Keycode::Key7 => VK_7,
+        Keycode::Key8 => VK_8,
+        Keycode::Key9 => VK_9,
+        Keycode::A => VK_A,
+        Keycode::B => VK_B,
+        Keycode::C => VK_C,
+        Keycode::D => VK_D,
+        Keycode::E => VK_E,
+        Keycode::F => VK_F,
+        Keycode::G => VK_G,
+        Keycode::H => VK_H,
+        Keycode::I => VK_I,
+        Keycode::J => VK_J,
+        Keycode::K => VK_K,
+        Keycode::L => VK_L,
+        Keycode::M => VK_M,
+        Keycode::N => VK_N,
+        Keycode::O => VK_O,
+        Keycode::P => VK_P,
+        Keycode::Q => VK_Q,
+        Keycode::R => VK_R,
+        Keycode::S => VK_S,
+        Keycode::T => VK_T,
+        Keycode::U => VK_U,
+        Keycode::V => VK_V,
+        Keycode::W => VK_W,
+        Keycode::X => VK_X,
+        Keycode::Y => VK_Y,
+        Keycode::Z => VK_Z,
+        Keycode::F1 => VK_F1,
+        Keycode::F2 => VK_F2,
+        Keycode::F3 => VK_F3,
+        Keycode::F4 => VK_F4,
+        Keycode::F5 => VK_F5,
+        Keycode::F6 => VK_F6,
+        Keycode::F7 => VK_F7,
+        Keycode::F8 => VK_F8,
+        Keycode::F9 => VK_F9,
+        Keycode::F10 => VK_F10,
+        Keycode::F11 => VK_F11,
+        Keycode::F12 => VK_F12,
+        Keycode::Escape => VK_ESCAPE,
+        Keycode::Space => VK_SPACE,
+        Keycode::LControl => VK_LCONTROL,
+        Keycode::RControl => VK_RCONTROL,
+        Keycode::LShift => VK_LSHIFT,
+        Keycode::RShift => VK_RSHIFT,
+        Keycode::LAlt => VK_LMENU,
+        Keycode::RAlt => VK_RMENU,
+        Keycode::LMeta => VK_LWIN,
+        Keycode::RMeta => VK_RWIN,
+        Keycode::Enter => VK_RETURN,
+        Keycode::Up => VK_UP,
+        Keycode::Down => VK_DOWN,
+        Keycode::Left => VK_LEFT,
+        Keycode::Right => VK_RIGHT,
+        Keycode::Backspace => VK_BACK,
+        Keycode::CapsLock => VK_CAPITAL,
+        Keycode::Tab => VK_TAB,
+        Keycode::Home => VK_HOME,
+        Keycode::End => VK_END,
+        Keycode::PageUp => VK_PRIOR,
+        Keycode::PageDown => VK_NEXT,
+        Keycode::Insert => VK_INSERT,
+        Keycode::Delete => VK_DELETE,
+        Keycode::Numpad0 => VK_NUMPAD0,
+        Keycode::Numpad1 => VK_NUMPAD1,
+        Keycode::Numpad2 => VK_NUMPAD2,
+        Keycode::Numpad3 => VK_NUMPAD3,
+        Keycode::Numpad4 => VK_NUMPAD4,
+        Keycode::Numpad5 => VK_NUMPAD5,
+        Keycode::Numpad6 => VK_NUMPAD6,
+        Keycode::Numpad7 => VK_NUMPAD7,
+        Keycode::Numpad8 => VK_NUMPAD8,
+        Keycode::Numpad9 => VK_NUMPAD9,
+        Keycode::NumpadSubtract => VK_SUBTRACT,
+        Keycode::NumpadAdd => VK_ADD,
+        Keycode::NumpadDivide => VK_DIVIDE,
+        Keycode::NumpadMultiply => VK_MULTIPLY,
+        Keycode::Grave => VK_OEM_3,
+        Keycode::Minus => VK_OEM_MINUS,
+        Keycode::Equal => VK_OEM_PLUS,
+        Keycode::LeftBracket => VK_OEM_4,
+        Keycode::RightBracket => VK_OEM_6,
+        Keycode::BackSlash => VK_OEM_5,
+        Keycode::Semicolon => VK_OEM_1,
+        Keycode::Apostrophe => VK_OEM_7,
+        Keycode::Comma => VK_OEM_COMMA,
+        Keycode::Dot => VK_OEM_PERIOD,
+        Keycode::Slash => VK_OEM_2,
+        _ => return None,
+    };
+
+    Some(key)
+}
+
+/// Keys that are sent with an extended scan code.
+fn is_extended_key(key: VIRTUAL_KEY) -> bool {
+    matches!(
+        key,
+        VK_RCONTROL | VK_RMENU | VK_LWIN | VK_RWIN | VK_UP | VK_DOWN | VK_LEFT | VK_RIGHT | VK_HOME | VK_END | VK_PRIOR | VK_NEXT | VK_INSERT | VK_DELETE | VK_DIVIDE
+    )
+}
+
+/// Get the name of the virtual key as used by the input library.
+///
+/// Returns `None` for keys the input library doesn't support.
+pub(crate) fn key_name(key: VIRTUAL_KEY) -> Option<String> {
+    SUPPORTED_KEYCODES
+        .iter()
+        .find(|keycode| virtual_key(**keycode) == Some(key))
+        .map(|keycode| keycode_to_string(*keycode))
+}
+
+pub(crate) fn parse_key(name: &str) -> Result<VIRTUAL_KEY, anyhow::Error> {
+    parse_keycode(name).map(|(_, key)| key)
+}
+
+/// Parse the key into its keycode and its Windows virtual key.
+fn parse_keycode(name: &str) -> Result<(Keycode, VIRTUAL_KEY), anyhow::Error> {
+    keycode_from_string(name.to_string())
+        .ok()
+        .and_then(|keycode| Some((keycode, virtual_key(keycode)?)))
+        .ok_or(anyhow!("'{}' is not a supported key", name))
+}
+
+/// Names of the keys that can be remapped.
+pub fn get_supported_keys() -> Vec<String> {
+    SUPPORTED_KEYCODES
+        .iter()
+        .filter(|keycode| virtual_key(**keycode).is_some())
+        .map(|keycode| keycode_to_string(*keycode))
+        .collect()
+}
+
+pub fn get_remaps() -> Vec<InputRemap> {
+    match CONFIGURED_REMAPS.read() {
+        Ok(remaps) => remaps.clone(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Replace the remaps of keys the user presses to other keys, configured in the GUI.
+///
+/// Remaps are applied in the game's input poll, see [`game_input`], so the game reads the target key instead of the
+/// pressed key. The key state of the input library and the key events of key bindings get the target key as well.
+/// The remaps apply immediately. Fails without changing the remaps if a remap is invalid.
+pub fn set_remaps(remaps: &[InputRemap]) -> Result<(), anyhow::Error> {
+    let mut keycode_remaps = HashMap::new();
+    let mut virtual_remaps = HashMap::new();
+
+    for remap in remaps {
+        let (from_keycode, from) = parse_keycode(&remap.from)?;
+        let (to_keycode, to) = parse_keycode(&remap.to)?;
+
+        if virtual_remaps.insert(from.0, to.0).is_some() {
+            return Err(anyhow!("'{}' is remapped more than once", remap.from));
+        }
+        keycode_remaps.insert(from_keycode, to_keycode);
+    }
+
+    *REMAPS.write().map_err(|e| anyhow!("could not get lock to the input remaps: {}", e))? = keycode_remaps;
+    *CONFIGURED_REMAPS.write().map_err(|e| anyhow!("could not get lock to the input remaps: {}", e))? = remaps.to_vec();
+    game_input::set_remaps(virtual_remaps);
+
+    info!("Remapping {} keys", remaps.len());
+
+    Ok(())
+}
+
+/// Get the key the game reads for the pressed key.
+fn remap(key: VIRTUAL_KEY) -> VIRTUAL_KEY {
+    let remaps = match REMAPS.try_read() {
+        Ok(remaps) => remaps,
+        Err(_) => return key,
+    };
+
+    remaps
+        .iter()
+        .find(|(from, _)| virtual_key(**from) == Some(key))
+        .and_then(|(_, to)| virtual_key(*to))
+        .unwrap_or(key)
+}
+
+/// Replace remapped keys in the key state with their target, so that plugins see the keys the game reads.
+///
+/// Must be called once per frame after the key state was updated and before keys are injected, as injected keys aren't remapped.
+pub fn on_frame(key_state: &KeyState) {
+    let remaps = match REMAPS.read() {
+        Ok(remaps) if !remaps.is_empty() => remaps,
+        _ => return,
+    };
+
+    let result = key_state.get_state().and_then(|keys| {
+        let keys = keys.into_iter().map(|key| remaps.get(&key).copied().unwrap_or(key)).collect();
+        key_state.set_state(keys)
+    });
+
+    if let Err(e) = result {
+        warn!("Could not remap the key state: {}", e);
+    }
+}
+
+/// Install the keyboard hook if it isn't installed yet.
+///
+/// The hook is only installed once it is needed, i.e., for the key events of plugins, the console, or panels.
+pub(crate) fn start_keyboard_hook() {
+    START_HOOK.call_once(|| {
+        thread::spawn(run_keyboard_hook);
+    });
+}
+
+/// Install the keyboard hook and process its messages.
+///
+/// Windows calls low-level keyboard hooks on the thread that installed them, which must process messages.
+fn run_keyboard_hook() {
+    let _worker = teardown::worker();
+
+    unsafe {
+        let module = GetModuleHandleA(PCSTR::null()).unwrap_or_default();
+
+        let hook = match SetWindowsHookExA(WH_KEYBOARD_LL, Some(keyboard_hook), HINSTANCE(module.0), 0) {
+            Ok(hook) => hook,
+            Err(e) => {
+                error!("Could not install the keyboard hook for key events: {}", e);
+                return;
+            },
+        };
+        HOOK_THREAD.store(GetCurrentThreadId(), Ordering::SeqCst);
+
+        // Ends when the hook is stopped
+        let mut message = MSG::default();
+        while GetMessageA(&mut message, HWND::default(), 0, 0).as_bool() {
+            let _ = TranslateMessage(&message);
+            DispatchMessageA(&message);
+        }
+
+        if let Err(e) = UnhookWindowsHookEx(hook) {
+            warn!("Could not remove the keyboard hook: {}", e);
+        }
+    }
+}
+
+/// Remove the keyboard hook and stop its thread, e.g., when the engine is torn down.
+pub(crate) fn stop_keyboard_hook() {
+    let thread = HOOK_THREAD.swap(0, Ordering::SeqCst);
+    if thread == 0 {
+        return;
+    }
+
+    unsafe {
+        if let Err(e) = PostThreadMessageA(thread, WM_QUIT, WPARAM(0), LPARAM(0)) {
+            warn!("Could not stop the keyboard hook: {}", e);
+        }
+    }
+}
+
+/// Whether the game's window has the keyboard focus.
+///
+/// Keys are only sent for the game and not for other applications.
+pub(crate) fn is_game_focused() -> bool {
+    unsafe {
+        let main_window = *FUTURE_COP.handles.main_window.get();
+
+        main_window != 0 && GetForegroundWindow().0 as usize as u32 == main_window
+    }
+}
+
+/// Record the key events for plugins, with remapped keys replaced by their target key.
+///
+/// Low-level keyboard hook of the OS, which sees the keys before the game's window does.
+unsafe extern "system" fn keyboard_hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code == HC_ACTION as i32 {
+        let event = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+        let is_key_up = matches!(wparam.0 as u32, WM_KEYUP | WM_SYSKEYUP);
+        let is_focused = is_game_focused();
+
+        // Keys sent by controllers and macros aren't typed into the console or panels
+        let is_injected = event.flags.0 & LLKHF_INJECTED.0 != 0;
+
+        // Keys typed into the Lua console are meant for the console and not for the game
+        if !is_injected && is_focused && console::record_key(VIRTUAL_KEY(event.vkCode as u16), is_key_up) {
+            return LRESULT(1);
+        }
+
+        // Keys that navigate a plugin's panel are meant for the panel and not for the game
+        if !is_injected && is_focused && widgets::record_key(VIRTUAL_KEY(event.vkCode as u16), is_key_up) {
+            return LRESULT(1);
+        }
+
+        let key = remap(VIRTUAL_KEY(event.vkCode as u16));
+
+        // Releases are always recorded, so keys released outside the game don't stay held
+        if is_focused || is_key_up {
+            key_bindings::record_key(key, is_key_up);
+        }
+    }
+
+    CallNextHookEx(HHOOK::default(), code, wparam, lparam)
+}
+
+/// Press or release the key, e.g., for controllers and macros.
+///
+/// Sends the key's scan code, since games often read scan codes instead of virtual keys.
+pub(crate) fn send_key(key: VIRTUAL_KEY, is_key_up: bool) {
+    let mut flags = KEYEVENTF_SCANCODE;
+    if is_key_up {
+        flags |= KEYEVENTF_KEYUP;
+    }
+    if is_extended_key(key) {
+        flags |= KEYEVENTF_EXTENDEDKEY;
+    }
+
+    unsafe {
+        let input = INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: key,
+                    wScan: MapVirtualKeyA(key.0 as u32, MAPVK_VK_TO_VSC) as u16,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        };
+
+        if SendInput(&[input], std::mem::size_of::<INPUT>() as i32) == 0 {
+            debug!("Could not send remapped key {:?}", key);
+        }
+    }
+}
+
+/// Apply the configured remaps. Must be called when the mod is injected.
+pub fn init(remaps: &[InputRemap]) {
+    if let Err(e) = set_remaps(remaps) {
+        error!("Invalid input remaps in the config: {}", e);
+    }
+}
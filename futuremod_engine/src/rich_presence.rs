@@ -0,0 +1,280 @@
+use std::{fs::{File, OpenOptions}, io::{Read, Write}, sync::{atomic::{AtomicBool, Ordering}, Mutex}, thread, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use anyhow::anyhow;
+use log::*;
+use serde::Serialize;
+
+use crate::{config::RichPresenceConfig, futurecop::{global::GetterSetter, state::FUTURE_COP, GameMode, PlayerEntity}, teardown};
+
+/// Discord limits the length of the presence's texts.
+const MAX_TEXT_LENGTH: usize = 128;
+
+/// Discord listens on the first free pipe of these.
+const IPC_PIPES: u8 = 10;
+
+const OPCODE_HANDSHAKE: u32 = 0;
+const OPCODE_FRAME: u32 = 1;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref PRESENCE: Mutex<Presence> = Mutex::new(Presence::default());
+}
+
+/// Text of the presence set by a plugin.
+#[derive(Debug, Clone)]
+struct PresenceOverride {
+    plugin: String,
+    details: Option<String>,
+    state: Option<String>,
+}
+
+/// State of the game shown in the presence.
+#[derive(Debug, Default)]
+struct Presence {
+    in_mission: bool,
+    mission_started: u64,
+    game_mode: &'static str,
+    two_players: bool,
+    health: Option<(i16, i16)>,
+
+    /// Overrides of plugins, the most recently changed override last.
+    overrides: Vec<PresenceOverride>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Activity {
+    details: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamps: Option<Timestamps>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Timestamps {
+    start: u64,
+}
+
+impl Presence {
+    fn activity(&self) -> Activity {
+        let (details, state) = match self.in_mission {
+            true => {
+                let details = match self.two_players {
+                    true => format!("{} (2 players)", self.game_mode),
+                    false => self.game_mode.to_string(),
+                };
+                let state = self.health.map(|(health, max_health)| format!("Health {}/{}", health, max_health));
+
+                (details, state)
+            },
+            false => ("In the menu".to_string(), None),
+        };
+
+        let details = self.overrides.iter().rev().find_map(|o| o.details.clone()).unwrap_or(details);
+        let state = self.overrides.iter().rev().find_map(|o| o.state.clone()).or(state);
+
+        Activity {
+            details: truncate(details),
+            state: state.map(truncate),
+            timestamps: match self.in_mission {
+                true => Some(Timestamps { start: self.mission_started }),
+                false => None,
+            },
+        }
+    }
+
+    fn set_override(&mut self, plugin: &str, f: impl FnOnce(&mut PresenceOverride)) {
+        let mut presence_override = match self.overrides.iter().position(|o| o.plugin == plugin) {
+            Some(index) => self.overrides.remove(index),
+            None => PresenceOverride { plugin: plugin.to_string(), details: None, state: None },
+        };
+
+        f(&mut presence_override);
+
+        if presence_override.details.is_some() || presence_override.state.is_some() {
+            self.overrides.push(presence_override);
+        }
+    }
+}
+
+fn truncate(text: String) -> String {
+    text.chars().take(MAX_TEXT_LENGTH).collect()
+}
+
+/// Connection to the Discord client over its local IPC pipe.
+struct DiscordIpc {
+    pipe: File,
+    nonce: u64,
+}
+
+impl DiscordIpc {
+    fn connect(client_id: &str) -> Result<DiscordIpc, anyhow::Error> {
+        let pipe = (0..IPC_PIPES)
+            .find_map(|i| OpenOptions::new().read(true).write(true).open(format!(r"\\?\pipe\discord-ipc-{}", i)).ok())
+            .ok_or(anyhow!("Discord is not running"))?;
+
+        let mut ipc = DiscordIpc { pipe, nonce: 0 };
+        ipc.send(OPCODE_HANDSHAKE, serde_json::json!({"v": 1, "client_id": client_id}))?;
+
+        Ok(ipc)
+    }
+
+    fn set_activity(&mut self, activity: &Activity) -> Result<(), anyhow::Error> {
+        self.nonce += 1;
+
+        self.send(OPCODE_FRAME, serde_json::json!({
+            "cmd": "SET_ACTIVITY",
+            "args": {
+                "pid": std::process::id(),
+                "activity": activity,
+            },
+            "nonce": self.nonce.to_string(),
+        }))
+    }
+
+    /// Send the payload and wait for Discord's response.
+    fn send(&mut self, opcode: u32, payload: serde_json::Value) -> Result<(), anyhow::Error> {
+        let payload = serde_json::to_vec(&payload)?;
+
+        let mut frame = Vec::with_capacity(payload.len() + 8);
+        frame.extend_from_slice(&opcode.to_le_bytes());
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(&payload);
+        self.pipe.write_all(&frame)?;
+
+        let mut header = [0u8; 8];
+        self.pipe.read_exact(&mut header)?;
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+        let mut response = vec![0u8; length as usize];
+        self.pipe.read_exact(&mut response)?;
+        debug!("Discord responded with '{}'", String::from_utf8_lossy(&response));
+
+        Ok(())
+    }
+}
+
+/// Start publishing the presence if it is configured. Must be called when the mod is injected.
+pub fn init(config: &Option<RichPresenceConfig>) {
+    let config = match config {
+        Some(config) => config.clone(),
+        None => return,
+    };
+
+    info!("Publishing the rich presence to Discord");
+    ENABLED.store(true, Ordering::Relaxed);
+
+    // Talking to Discord blocks. Thus, the presence is published from its own thread and never from the game's thread.
+    thread::spawn(move || publish(config));
+}
+
+fn publish(config: RichPresenceConfig) {
+    let _worker = teardown::worker();
+    let mut ipc: Option<DiscordIpc> = None;
+    let mut published: Option<Activity> = None;
+    let mut warned = false;
+
+    loop {
+        if ipc.is_none() {
+            match DiscordIpc::connect(&config.client_id) {
+                Ok(connection) => {
+                    info!("Connected to Discord");
+                    ipc = Some(connection);
+                    published = None;
+                    warned = false;
+                },
+                Err(e) if !warned => {
+                    warn!("Could not connect to Discord, retrying in the background: {}", e);
+                    warned = true;
+                },
+                Err(_) => (),
+            }
+        }
+
+        let activity = match PRESENCE.lock() {
+            Ok(presence) => Some(presence.activity()),
+            Err(e) => {
+                warn!("Could not get lock to the rich presence: {}", e);
+                None
+            },
+        };
+
+        if let (Some(connection), Some(activity)) = (ipc.as_mut(), activity) {
+            if published.as_ref() != Some(&activity) {
+                match connection.set_activity(&activity) {
+                    Ok(_) => published = Some(activity),
+                    Err(e) => {
+                        warn!("Could not update the rich presence, reconnecting: {}", e);
+                        ipc = None;
+                    },
+                }
+            }
+        }
+
+        // Dropping the connection clears the presence
+        if !teardown::sleep(Duration::from_secs(config.interval.max(1))) {
+            return;
+        }
+    }
+}
+
+/// Show that a mission is played. Must be called when a mission starts.
+pub fn on_mission_start() {
+    with_presence(|presence| {
+        presence.in_mission = true;
+        presence.mission_started = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+        presence.health = None;
+    });
+}
+
+/// Show that no mission is played. Must be called when a mission ends.
+pub fn on_mission_end() {
+    with_presence(|presence| presence.in_mission = false);
+}
+
+/// Update the game mode and player one's health.
+///
+/// Must be called once per frame of the mission's game loop.
+pub fn on_frame(player: Option<&PlayerEntity>) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let (game_mode, two_players) = unsafe {
+        let game_mode = match FUTURE_COP.state.game_mode.get() {
+            GameMode::CrimeWar => "Crime War",
+            GameMode::PrecinctAssault => "Precinct Assault",
+        };
+
+        (game_mode, *FUTURE_COP.state.is_two_player.get())
+    };
+
+    with_presence(|presence| {
+        presence.game_mode = game_mode;
+        presence.two_players = two_players;
+        presence.health = player.map(|player| (player.health.health, player.health.max_health));
+    });
+}
+
+/// Replace the first line of the presence. `None` restores the default text.
+pub fn set_details(plugin: &str, details: Option<String>) {
+    with_presence(|presence| presence.set_override(plugin, |o| o.details = details));
+}
+
+/// Replace the second line of the presence. `None` restores the default text.
+pub fn set_state(plugin: &str, state: Option<String>) {
+    with_presence(|presence| presence.set_override(plugin, |o| o.state = state));
+}
+
+/// Remove the texts set by the plugin, e.g., when the plugin is disabled.
+pub fn clear_overrides(plugin: &str) {
+    with_presence(|presence| presence.overrides.retain(|o| o.plugin != plugin));
+}
+
+fn with_presence(f: impl FnOnce(&mut Presence)) {
+    match PRESENCE.lock() {
+        Ok(mut presence) => f(&mut presence),
+        Err(e) => warn!("Could not get lock to the rich presence: {}", e),
+    }
+}
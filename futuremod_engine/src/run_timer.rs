@@ -0,0 +1,202 @@
+use std::{io::Write, net::{SocketAddr, TcpStream}, sync::Mutex, time::{Duration, Instant}};
+
+use anyhow::anyhow;
+use log::*;
+use serde::Serialize;
+
+use crate::config::LiveSplitConfig;
+
+/// Maximum time to wait for LiveSplit to accept the connection.
+///
+/// Connecting blocks the game's thread. Thus, keep it short.
+const LIVE_SPLIT_CONNECT_TIMEOUT: Duration = Duration::from_millis(50);
+
+lazy_static! {
+    static ref RUN_TIMER: Mutex<RunTimer> = Mutex::new(RunTimer::new());
+}
+
+/// Split of a run.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Split {
+    /// Number of the split in the run, counted from `1`.
+    pub index: u32,
+
+    pub name: String,
+
+    /// Seconds since the start of the run.
+    pub time: f64,
+
+    /// Seconds since the previous split or the start of the run.
+    pub segment_time: f64,
+}
+
+enum State {
+    Idle,
+    Running {
+        started: Instant,
+        last_split: Duration,
+        splits: u32,
+    },
+    Finished {
+        time: Duration,
+    },
+}
+
+/// Timer that runs from the start to the end of a mission.
+///
+/// Splits are queued until they are passed to the plugins at the start of the next frame.
+struct RunTimer {
+    state: State,
+    pending: Vec<Split>,
+    live_split: Option<LiveSplit>,
+}
+
+impl RunTimer {
+    fn new() -> Self {
+        RunTimer { state: State::Idle, pending: Vec::new(), live_split: None }
+    }
+
+    fn split(&mut self, name: String) -> Result<Split, anyhow::Error> {
+        let (started, last_split, splits) = match &mut self.state {
+            State::Running { started, last_split, splits } => (started, last_split, splits),
+            _ => return Err(anyhow!("the run timer is not running")),
+        };
+
+        let time = started.elapsed();
+        *splits += 1;
+
+        let split = Split {
+            index: *splits,
+            name,
+            time: time.as_secs_f64(),
+            segment_time: (time - *last_split).as_secs_f64(),
+        };
+        *last_split = time;
+
+        debug!("Split '{}' at {:.3}s", split.name, split.time);
+        self.pending.push(split.clone());
+
+        if let Some(live_split) = &mut self.live_split {
+            live_split.send("split", false);
+        }
+
+        Ok(split)
+    }
+}
+
+/// Client of LiveSplit's server component.
+///
+/// The connection is only (re)established when a run starts, so an unavailable LiveSplit doesn't slow down every split.
+struct LiveSplit {
+    address: String,
+    stream: Option<TcpStream>,
+}
+
+impl LiveSplit {
+    fn send(&mut self, command: &str, connect: bool) {
+        if self.stream.is_none() && connect {
+            self.stream = match self.connect() {
+                Ok(stream) => Some(stream),
+                Err(e) => {
+                    warn!("Could not connect to LiveSplit at '{}': {}", self.address, e);
+                    None
+                },
+            };
+        }
+
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => return,
+        };
+
+        if let Err(e) = stream.write_all(format!("{}\r\n", command).as_bytes()) {
+            warn!("Could not send '{}' to LiveSplit, disconnecting: {}", command, e);
+            self.stream = None;
+        }
+    }
+
+    fn connect(&self) -> Result<TcpStream, anyhow::Error> {
+        let address: SocketAddr = self.address.parse().map_err(|e| anyhow!("invalid address: {}", e))?;
+        let stream = TcpStream::connect_timeout(&address, LIVE_SPLIT_CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+
+        info!("Connected to LiveSplit at '{}'", self.address);
+
+        Ok(stream)
+    }
+}
+
+/// Configure the connection to LiveSplit. Must be called when the mod is injected.
+pub fn init(config: &Option<LiveSplitConfig>) {
+    with_run_timer(|run_timer| {
+        run_timer.live_split = config.as_ref().map(|config| LiveSplit { address: config.address.clone(), stream: None });
+    });
+}
+
+/// Start a new run. Must be called when a mission starts.
+pub fn on_mission_start() {
+    with_run_timer(|run_timer| {
+        run_timer.state = State::Running { started: Instant::now(), last_split: Duration::ZERO, splits: 0 };
+
+        if let Some(live_split) = &mut run_timer.live_split {
+            live_split.send("starttimer", true);
+        }
+    });
+}
+
+/// Split and stop the run. Must be called when a mission ends.
+pub fn on_mission_end() {
+    with_run_timer(|run_timer| {
+        if let Ok(split) = run_timer.split("Mission End".to_string()) {
+            info!("Run finished after {:.3}s", split.time);
+            run_timer.state = State::Finished { time: Duration::from_secs_f64(split.time) };
+        }
+    });
+}
+
+/// Split the current run.
+pub fn split(name: String) -> Result<Split, anyhow::Error> {
+    let mut run_timer = RUN_TIMER.lock().map_err(|e| anyhow!("could not get lock to the run timer: {}", e))?;
+
+    run_timer.split(name)
+}
+
+/// Seconds since the start of the current run or the duration of the finished run.
+///
+/// Returns `None` if no run was started yet.
+pub fn get_time() -> Option<f64> {
+    let run_timer = RUN_TIMER.lock().ok()?;
+
+    match &run_timer.state {
+        State::Idle => None,
+        State::Running { started, .. } => Some(started.elapsed().as_secs_f64()),
+        State::Finished { time } => Some(time.as_secs_f64()),
+    }
+}
+
+/// Whether a run is currently timed.
+pub fn is_running() -> bool {
+    match RUN_TIMER.lock() {
+        Ok(run_timer) => matches!(run_timer.state, State::Running { .. }),
+        Err(_) => false,
+    }
+}
+
+/// Take the splits that weren't passed to the plugins yet.
+pub fn take_splits() -> Vec<Split> {
+    match RUN_TIMER.lock() {
+        Ok(mut run_timer) => std::mem::take(&mut run_timer.pending),
+        Err(e) => {
+            warn!("Could not get lock to the run timer: {}", e);
+            Vec::new()
+        },
+    }
+}
+
+fn with_run_timer(f: impl FnOnce(&mut RunTimer)) {
+    match RUN_TIMER.lock() {
+        Ok(mut run_timer) => f(&mut run_timer),
+        Err(e) => warn!("Could not get lock to the run timer: {}", e),
+    }
+}
@@ -0,0 +1,227 @@
+use std::{ffi::c_void, fs::{self, File}, io::BufWriter, mem, path::{Component, Path, PathBuf}, str::FromStr, sync::RwLock, thread, time::{Duration, UNIX_EPOCH}};
+
+use anyhow::{anyhow, bail};
+use device_query::{DeviceQuery, DeviceState, Keycode};
+use futuremod_data::screenshot::Screenshot;
+use log::*;
+use walkdir::WalkDir;
+use windows::Win32::{Foundation::{HWND, RECT}, Graphics::Gdi::*, UI::WindowsAndMessaging::GetClientRect};
+
+use crate::{config::ScreenshotConfig, futurecop::{global::GetterSetter, state::FUTURE_COP}, remapping::is_game_focused, run_timer, teardown};
+
+/// Interval in which the screenshot key is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Folder of screenshots taken outside of missions.
+const MENU_FOLDER: &str = "Menu";
+
+lazy_static! {
+    /// Directory the screenshots are saved to.
+    static ref DIRECTORY: RwLock<PathBuf> = RwLock::new(PathBuf::from("screenshots"));
+}
+
+/// Start listening for the screenshot key. Must be called when the mod is injected.
+pub fn init(config: &ScreenshotConfig) {
+    match DIRECTORY.write() {
+        Ok(mut directory) => *directory = PathBuf::from(&config.directory),
+        Err(e) => warn!("Could not get lock to the screenshot directory: {}", e),
+    }
+
+    let key = match Keycode::from_str(&config.key) {
+        Ok(key) => key,
+        Err(_) => {
+            warn!("Invalid screenshot key '{}', screenshots are disabled", config.key);
+            return;
+        },
+    };
+
+    thread::spawn(move || {
+        let _worker = teardown::worker();
+        let device_state = DeviceState::new();
+        let mut was_key_pressed = false;
+
+        loop {
+            let is_key_pressed = device_state.get_keys().contains(&key);
+
+            if is_key_pressed && !was_key_pressed && is_game_focused() {
+                match take_screenshot() {
+                    Ok(path) => info!("Saved screenshot to '{}'", path.display()),
+                    Err(e) => error!("Could not take a screenshot: {}", e),
+                }
+            }
+            was_key_pressed = is_key_pressed;
+
+            if !teardown::sleep(POLL_INTERVAL) {
+                return;
+            }
+        }
+    });
+}
+
+fn get_directory() -> Result<PathBuf, anyhow::Error> {
+    DIRECTORY
+        .read()
+        .map(|directory| directory.clone())
+        .map_err(|e| anyhow!("could not get lock to the screenshot directory: {}", e))
+}
+
+/// Folder the current screenshot is organized in.
+///
+/// The game mode of the running mission, since the mission's name is unknown, or `Menu` outside of missions.
+fn current_folder() -> String {
+    if !run_timer::is_running() {
+        return MENU_FOLDER.to_string();
+    }
+
+    unsafe { format!("{:?}", FUTURE_COP.state.game_mode.get()) }
+}
+
+/// Capture the game's window and save it as PNG.
+///
+/// Returns the path of the screenshot.
+pub fn take_screenshot() -> Result<PathBuf, anyhow::Error> {
+    let main_window = unsafe { *FUTURE_COP.handles.main_window.get() };
+    if main_window == 0 {
+        bail!("the game's window doesn't exist");
+    }
+
+    let (width, height, pixels) = unsafe { capture_window(HWND(main_window as isize))? };
+
+    let folder = get_directory()?.join(current_folder());
+    fs::create_dir_all(&folder).map_err(|e| anyhow!("could not create '{}': {}", folder.display(), e))?;
+
+    let path = folder.join(format!("{}.png", chrono::Local::now().format("%Y-%m-%d_%H-%M-%S-%3f")));
+    let file = File::create(&path).map_err(|e| anyhow!("could not create '{}': {}", path.display(), e))?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|e| anyhow!("could not encode '{}': {}", path.display(), e))?;
+
+    Ok(path)
+}
+
+/// Copy the client area of the window.
+///
+/// Returns the width, height, and the RGB pixels row by row from the top.
+unsafe fn capture_window(window: HWND) -> Result<(u32, u32, Vec<u8>), anyhow::Error> {
+    let mut rect = RECT::default();
+    GetClientRect(window, &mut rect).map_err(|e| anyhow!("could not get the window's size: {}", e))?;
+
+    let width = rect.right - rect.left;
+    let height = rect.bottom - rect.top;
+    if width <= 0 || height <= 0 {
+        bail!("the game's window is minimized");
+    }
+
+    let window_dc = GetDC(window);
+    let memory_dc = CreateCompatibleDC(window_dc);
+    let bitmap = CreateCompatibleBitmap(window_dc, width, height);
+    let previous = SelectObject(memory_dc, bitmap);
+
+    let copied = BitBlt(memory_dc, 0, 0, width, height, window_dc, 0, 0, SRCCOPY);
+
+    // Negative height requests the rows from the top
+    let mut info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut bgra = vec![0u8; (width * height * 4) as usize];
+    let lines = GetDIBits(memory_dc, bitmap, 0, height as u32, Some(bgra.as_mut_ptr() as *mut c_void), &mut info, DIB_RGB_COLORS);
+
+    SelectObject(memory_dc, previous);
+    let _ = DeleteObject(bitmap);
+    let _ = DeleteDC(memory_dc);
+    ReleaseDC(window, window_dc);
+
+    copied.map_err(|e| anyhow!("could not copy the window's content: {}", e))?;
+    if lines == 0 {
+        bail!("could not read the window's content");
+    }
+
+    let pixels = bgra
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[2], pixel[1], pixel[0]])
+        .collect();
+
+    Ok((width as u32, height as u32, pixels))
+}
+
+/// Get all screenshots in the screenshot directory, newest first.
+pub fn get_screenshots() -> Result<Vec<Screenshot>, anyhow::Error> {
+    let directory = get_directory()?;
+    if !directory.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut screenshots = Vec::new();
+
+    for entry in WalkDir::new(&directory).min_depth(1).max_depth(2) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Could not read the screenshot directory: {}", e);
+                continue;
+            },
+        };
+
+        let is_png = entry.path().extension().is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+        if !entry.file_type().is_file() || !is_png {
+            continue;
+        }
+
+        let relative_path = match entry.path().strip_prefix(&directory) {
+            Ok(path) => path,
+            Err(_) => continue,
+        };
+
+        let folder = match relative_path.parent() {
+            Some(parent) if parent != Path::new("") => parent.to_string_lossy().to_string(),
+            _ => String::new(),
+        };
+
+        let metadata = entry.metadata()?;
+        let taken_at = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        screenshots.push(Screenshot {
+            path: relative_path.to_string_lossy().replace('\\', "/"),
+            folder,
+            taken_at,
+            size: metadata.len(),
+        });
+    }
+
+    screenshots.sort_by(|a, b| b.taken_at.cmp(&a.taken_at).then_with(|| b.path.cmp(&a.path)));
+
+    Ok(screenshots)
+}
+
+/// Get the path of the screenshot.
+///
+/// Only accepts paths inside the screenshot directory.
+pub fn get_screenshot_path(path: &str) -> Result<PathBuf, anyhow::Error> {
+    let relative_path = Path::new(path);
+
+    if !relative_path.components().all(|component| matches!(component, Component::Normal(_))) {
+        bail!("'{}' is not a path inside the screenshot directory", path);
+    }
+
+    Ok(get_directory()?.join(relative_path))
+}
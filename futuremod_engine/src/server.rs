@@ -1,60 +1,187 @@
-use std::{collections::HashMap, path::{Path, PathBuf}, sync::{Arc, RwLock}, thread::JoinHandle, time::SystemTime};
+use std::{collections::{BTreeMap, HashMap}, path::PathBuf, sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex, RwLock}, thread::JoinHandle, time::{Duration, SystemTime}};
 use anyhow::{Error, anyhow};
 use axum::{
-    body::Bytes, extract::{ws::{Message, WebSocket, WebSocketUpgrade}, BodyStream}, http::StatusCode, response::{IntoResponse, Response}, routing::{get, post, put}, BoxError, Json, Router,
+    body::{Bytes, StreamBody}, extract::{ws::{Message, WebSocket, WebSocketUpgrade}, BodyStream}, http::{header, HeaderMap, StatusCode}, response::{IntoResponse, Response}, routing::{delete, get, post, put}, BoxError, Json, Router,
 };
-use futuremod_data::plugin::PluginInfo;
+use futuremod_data::{bookmark::Bookmark, benchmark::{BenchmarkState, StartBenchmark}, compatibility::{CompatibilityReport, SetCompatibilityReporting}, audit::AuditReport, crash::CrashReport, debugger::{ChunkResult, DebuggerState, EnvironmentEntry, ReplRequest, StepFrames}, docs::LibraryDocs, input::{InputRemap, KeyBinding, SetKeyBinding}, metrics::{GarbageCollectionStats, Metrics}, diagnostics::{Diagnostics, EngineHealth}, integrity::IntegrityStatus, memory::{MemoryAddress, MemoryValue, NextScan, ScanState, StartScan}, multiplayer::{ConnectPeer, MultiplayerStatus}, screenshot::Screenshot, plugin::{InstallDevPlugin, InvokeLifecycle, PluginError, PluginInfo}, session::{RecordingState, SessionInfo}, settings::SettingValue, statistics::UsageStatistics, structs::{ReadStruct, StructDefinition, StructFieldValue}, version::{API_VERSION, PLUGIN_API_VERSION}, watch::WatchValue};
 use kv::Key;
 use log::*;
 use serde::{Serialize, Deserialize};
-use tokio::{fs, io, runtime::Runtime, sync::broadcast::{self, Receiver, Sender}};
+use tokio::{fs, io, runtime::Runtime, sync::{broadcast::{self, Receiver, Sender}, oneshot}};
 use std::thread;
 use futures::Stream;
-use rand::distributions::{Alphanumeric, DistString};
 use futures::TryStreamExt;
 use tokio::{fs::File, io::BufWriter};
-use tokio_util::io::StreamReader;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::io::{ReaderStream, StreamReader};
 
-use crate::{config::Config, plugins::{plugin_info::{load_plugin_info, PluginInfoError}, plugin_manager::{GlobalPluginManager, PluginInstallError}}};
+use crate::{benchmark, bookmarks, compatibility, config::Config, crash, debugger, diagnostics, exec, gc, integrity, key_bindings, memory_scan, metrics, multiplayer, net, remapping, screenshot, session, session_recording, statistics, structs, teardown, temporary::Artifact, watches, plugins::{audit, backup, features::ENGINE_FEATURES, library::create_api_docs, settings, snapshot, plugin_info::{load_plugin_info, PluginInfoError}, plugin_manager::{GlobalPluginManager, PluginInstallError}}};
 
 use super::plugins::{PluginManager, plugin_manager::PluginManagerError};
 
 lazy_static! {
     pub static ref LOG_PUBLISHER: LogPublisher = LogPublisher::new();
     static ref LOG_HISTORY: Arc<RwLock<Vec<(u64, LogRecord)>>> =  Arc::new(RwLock::new(Vec::new()));
+    /// Stops the running server when sent.
+    static ref SHUTDOWN: Mutex<Option<oneshot::Sender<()>>> = Mutex::new(None);
+    /// Held by handlers with the plugin manager's read lock, see [`with_plugin_manager`].
+    static ref READERS: Mutex<()> = Mutex::new(());
 }
 
+/// Id of the next log record. Ids keep increasing when the history is cleared.
+static NEXT_LOG_ID: AtomicU64 = AtomicU64::new(0);
+
 /// Start the mod server in a separate thread.
 /// 
 /// Returns the thread's handle.
 pub fn start_server(config: Config) -> JoinHandle<()> {
     let handle = thread::spawn(move || {
+        let _worker = teardown::worker();
         let _ = serve(config);
     });
 
     handle
 }
 
+/// Stop the server, e.g., when the engine is torn down.
+///
+/// Requests that are handled finish first. The server's thread ends once they finished.
+pub fn stop_server() {
+    let shutdown = match SHUTDOWN.lock() {
+        Ok(mut shutdown) => shutdown.take(),
+        Err(e) => {
+            warn!("Could not get lock to stop the server: {}", e);
+            return;
+        },
+    };
+
+    if let Some(shutdown) = shutdown {
+        info!("Stopping the server");
+        let _ = shutdown.send(());
+    }
+}
+
+/// Time a chunk sent to the REPL may run. The game waits for the chunk, as it needs the plugin manager.
+const REPL_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Time the handler of a plugin's HTTP route may run.
+const PLUGIN_ROUTE_TIMEOUT: Duration = Duration::from_millis(100);
+
 /// Start the server
 fn serve(config: Config) -> Result<(), Error> {
+    let developer_mode = config.developer_mode;
+
     let result = std::panic::catch_unwind(|| {
         let rt = Runtime::new().unwrap();
         rt.block_on(async {
             let app = Router::new()
                 .route("/ping", get(ping))
+                .route("/shutdown", post(shutdown))
+                .route("/health", get(get_health))
                 .route("/read", post(read_memory))
                 .route("/read-hex", post(read_memory_hex))
                 .route("/plugins", get(get_plugins))
                 .route("/plugin/enable", put(enable_plugin))
                 .route("/plugin/disable", put(disable_plugin))
                 .route("/plugin/reload", put(reload_plugin))
+                .route("/plugin/force-reload", put(force_reload_plugin))
                 .route("/plugin/install", post(install_plugin))
+                .route("/plugin/install-dev", post(install_dev_plugin))
+                .route("/plugin/update", post(update_plugin))
                 .route("/plugin/uninstall", post(uninstall_plugin))
                 .route("/plugin/info", put(get_plugin_info))
-                .route("/log", get(log_handler));
+                .route("/plugin/lifecycle", post(move |payload: Json<InvokeLifecycle>| invoke_lifecycle(payload, developer_mode)))
+                .route("/plugin/:name/environment", get(move |name: axum::extract::Path<String>| get_plugin_environment(name, developer_mode)))
+                .route("/plugin/:name/promote", post(promote_plugin))
+                .route("/plugin/:name/trust", post(trust_plugin))
+                .route("/plugin/:name/dismiss-suspect", post(dismiss_suspect))
+                .route("/plugin/:name/bindings", get(get_key_bindings).put(set_key_binding))
+                .route("/plugin/:name/config", get(get_plugin_config).put(set_plugin_config))
+                .route("/plugin/:name/network", put(approve_network).delete(revoke_network))
+                .route("/plugin/:name/commands", put(approve_commands).delete(revoke_commands))
+                .route("/plugin/:name/api/*path", get(plugin_http_route))
+                .route("/backup", get(create_backup))
+                .route("/backup/restore", post(restore_backup))
+                .route("/api-docs", get(get_api_docs))
+                .route("/benchmark", get(get_benchmark))
+                .route("/benchmark/start", post(start_benchmark))
+                .route("/benchmark/cancel", post(cancel_benchmark))
+                .route("/input/keys", get(get_remappable_keys))
+                .route("/input/remaps", get(get_input_remaps).put(set_input_remaps))
+                .route("/multiplayer", get(get_multiplayer_status))
+                .route("/multiplayer/connect", post(connect_multiplayer))
+                .route("/multiplayer/disconnect", post(disconnect_multiplayer))
+                .route("/screenshots", get(get_screenshots))
+                .route("/screenshots/*path", get(get_screenshot))
+                .route("/session", get(get_session))
+                .route("/session/recording", get(get_session_recording))
+                .route("/session/recording/start", post(start_session_recording))
+                .route("/session/recording/stop", post(stop_session_recording))
+                .route("/session/recording/bundle", get(get_session_bundle))
+                .route("/compatibility", get(get_compatibility_report).put(set_compatibility_reporting).delete(clear_compatibility_report))
+                .route("/statistics", get(get_statistics).delete(reset_statistics))
+                .route("/metrics", get(get_metrics))
+                .route("/diagnostics", get(get_diagnostics))
+                .route("/crash", get(get_last_crash).delete(dismiss_last_crash))
+                .route("/debugger", get(get_debugger_state))
+                .route("/debugger/pause", post(move || pause_game(developer_mode)))
+                .route("/debugger/resume", post(move || resume_game(developer_mode)))
+                .route("/debugger/step", post(move |payload: Json<StepFrames>| step_game(payload, developer_mode)))
+                .route("/audit", get(get_audit_report))
+                .route("/integrity", get(get_integrity_status))
+                .route(
+                    "/watches",
+                    get(move || get_watches(developer_mode))
+                        .put(move |payload: Json<Vec<String>>, headers: HeaderMap| set_watches(payload, headers, developer_mode)),
+                )
+                .route("/watches/stream", get(move |ws: WebSocketUpgrade, headers: HeaderMap| watches_handler(ws, headers, developer_mode)))
+                .route("/repl", get(move |ws: WebSocketUpgrade, headers: HeaderMap| repl_handler(ws, headers, developer_mode)))
+                .route("/bookmarks", get(get_bookmarks).put(set_bookmark))
+                .route("/bookmarks/:name", delete(remove_bookmark))
+                .route(
+                    "/memory/scan",
+                    get(move || get_memory_scan(developer_mode))
+                        .post(move |payload: Json<StartScan>| start_memory_scan(payload, developer_mode))
+                        .delete(move || reset_memory_scan(developer_mode)),
+                )
+                .route("/memory/scan/next", post(move |payload: Json<NextScan>| next_memory_scan(payload, developer_mode)))
+                .route("/memory/values", post(move |payload: Json<Vec<MemoryAddress>>| read_memory_values(payload, developer_mode)))
+                .route("/structs", get(get_struct_definitions))
+                .route("/structs/read", post(read_struct))
+                .route("/log", get(log_handler).delete(clear_log_history))
+                .route("/logs/file", get(get_log_files));
+
+            let address = format!("{}:{}", config.server.host, config.server.port);
+            let server = address
+                .parse()
+                .map_err(|e| anyhow!("invalid address: {}", e))
+                .and_then(|socket_address| axum::Server::try_bind(&socket_address).map_err(|e| anyhow!("{}", e)));
+
+            diagnostics::record_server_bind(&address, server.as_ref().err());
+
+            let server = match server {
+                Ok(server) => server,
+                Err(e) => {
+                    error!("Could not start the server: {}", e);
+                    return;
+                },
+            };
 
-            axum::Server::bind(&format!("{}:{}", config.server.host, config.server.port).parse().unwrap())
+            let (shutdown, stopped) = oneshot::channel::<()>();
+            match SHUTDOWN.lock() {
+                Ok(mut current) => *current = Some(shutdown),
+                Err(e) => warn!("Could not get lock to the server's shutdown, the server can't be stopped: {}", e),
+            }
+
+            // Upgraded connections, e.g., the log stream, don't keep the server running and end with the runtime
+            server
                 .serve(app.into_make_service())
+                .with_graceful_shutdown(async {
+                    // Without a sender, the server can't be stopped and runs until the game exits
+                    if stopped.await.is_err() {
+                        std::future::pending::<()>().await;
+                    }
+                })
                 .await
                 .unwrap();
         });
@@ -66,6 +193,47 @@ fn serve(config: Config) -> Result<(), Error> {
     }
 }
 
+/// Download the engine's log files, including rotated ones.
+///
+/// The files are concatenated from oldest to newest, so users get a single file to attach to bug reports.
+async fn get_log_files() -> impl IntoResponse {
+    let mut reader: Box<dyn AsyncRead + Send + Unpin> = Box::new(io::empty());
+
+    for path in crate::log_files() {
+        match File::open(&path).await {
+            Ok(file) => reader = Box::new(reader.chain(file)),
+            Err(e) => warn!("Could not open log file '{}': {}", path.display(), e),
+        }
+    }
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"fcop_mod.log\""),
+        ],
+        StreamBody::new(ReaderStream::new(reader)),
+    )
+}
+
+/// The plugin's latest log messages, oldest first.
+pub fn recent_plugin_logs(plugin: &str, count: usize) -> Vec<String> {
+    let log_history = match LOG_HISTORY.read() {
+        Ok(log_history) => log_history,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut logs: Vec<String> = log_history
+        .iter()
+        .rev()
+        .filter(|(_, record)| record.plugin.as_deref() == Some(plugin))
+        .take(count)
+        .map(|(_, record)| format!("{} [{}] {}", record.timestamp, record.level, record.message))
+        .collect();
+    logs.reverse();
+
+    logs
+}
+
 async fn log_handler(
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
@@ -79,7 +247,7 @@ async fn handle_log(mut socket: WebSocket) {
     let (last_history_id, log_history) = {
         let log_history = LOG_HISTORY.read().unwrap();
         let mut copy_of_log_history: Vec<(u64, LogRecord)> = Vec::new();
-        let last_seen_id_of_history = log_history.len() as u64;
+        let last_seen_id_of_history = log_history.last().map(|(record_id, _)| *record_id);
 
         for (record_id, log_record) in log_history.iter() {
             copy_of_log_history.push((*record_id, log_record.clone()));
@@ -111,7 +279,7 @@ async fn handle_log(mut socket: WebSocket) {
             Err(_) => continue,
         };
 
-        if id > last_history_id {
+        if last_history_id.map_or(true, |last_history_id| id > last_history_id) {
             match socket.send(Message::Text(message)).await {
                 Err(_) => return,
                 _ => (),
@@ -120,6 +288,19 @@ async fn handle_log(mut socket: WebSocket) {
     }
 }
 
+/// Clear the log history, so consumers that connect later only receive new records.
+///
+/// The log files are kept.
+async fn clear_log_history() -> StatusCode {
+    match LOG_HISTORY.write() {
+        Ok(mut log_history) => {
+            log_history.clear();
+            StatusCode::NO_CONTENT
+        },
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 async fn ping() -> &'static str {
     "Pong"
 }
@@ -206,37 +387,643 @@ async fn read_memory_hex(Json(payload): Json<ReadMemoryHex>) -> impl IntoRespons
     Ok(Json(memory))
 }
 
+/// Call the function with the plugin manager's write lock.
+///
+/// Waiting for the lock, e.g., while the game loop updates the plugins, blocks the handler's thread.
+/// Thus, the runtime moves its other requests to other threads first, so that they don't wait as well.
 fn with_plugin_manager_mut<F, R>(f: F) -> Result<R, AppError>
 where F: Fn(&mut PluginManager) -> R {
-    match GlobalPluginManager::get().lock() {
-        Ok(mut plugin_manager) => {
-            Ok(f(&mut plugin_manager))
-        },
-        Err(e) => Err(AppError(anyhow!("could not get lock to plugin manager: {:?}", e))),
+    tokio::task::block_in_place(|| {
+        GlobalPluginManager::with_plugin_manager_mut(|plugin_manager| Ok(f(plugin_manager)))
+    })
+    .map_err(AppError)
+}
+
+/// Call the function with the plugin manager's read lock, see [`with_plugin_manager_mut`].
+///
+/// Used by handlers that don't change the plugins, so they don't publish a new snapshot.
+/// Plugins' Lua states can't be used by two threads at once, thus, these handlers still run one at a time.
+fn with_plugin_manager<F, R>(f: F) -> Result<R, AppError>
+where F: Fn(&PluginManager) -> R {
+    tokio::task::block_in_place(|| {
+        let _reader = READERS.lock().map_err(|e| anyhow!("could not get the readers' lock: {}", e))?;
+        GlobalPluginManager::with_plugin_manager(|plugin_manager| Ok(f(plugin_manager)))
+    })
+    .map_err(AppError)
+}
+
+/// Language the GUI shows plugins' names and descriptions in, e.g., `de` or `pt-BR`.
+#[derive(Debug, Deserialize)]
+struct LanguageQuery {
+    language: Option<String>,
+}
+
+/// Get all plugins. Their names and descriptions are translated into the requested language, if they translate them.
+///
+/// Reads the plugins' snapshot, so it never waits for the game loop.
+async fn get_plugins(axum::extract::Query(query): axum::extract::Query<LanguageQuery>) -> Result<Json<HashMap<String, futuremod_data::plugin::Plugin>>, String> {
+    let plugins = snapshot::get();
+
+    let mut plugin_response: HashMap<String, futuremod_data::plugin::Plugin> = HashMap::new();
+
+    for (name, plugin) in plugins.iter() {
+        let mut plugin = plugin.clone();
+        if let Some(language) = &query.language {
+            plugin.info.localize(language);
+        }
+
+        plugin_response.insert(name.clone(), plugin);
+    }
+
+    Ok(Json(plugin_response))
+}
+
+/// Get the API reference of all libraries plugins can require.
+async fn get_api_docs() -> Result<Json<Vec<LibraryDocs>>, AppError> {
+    let docs = create_api_docs().map_err(|e| anyhow!("could not create API docs: {}", e))?;
+
+    Ok(Json(docs))
+}
+
+/// Get statistics of the current game session, e.g., the uptime and plugin error counts.
+async fn get_session() -> Result<Json<SessionInfo>, AppError> {
+    Ok(Json(session::get_info()?))
+}
+
+/// Get whether the session is recorded and the size of the recording.
+async fn get_session_recording() -> Result<Json<RecordingState>, AppError> {
+    Ok(Json(session_recording::get_state()?))
+}
+
+/// Start recording logs, engine events, and samples of the game's state.
+async fn start_session_recording() -> Response {
+    match session_recording::start() {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::CONFLICT, e.to_string()).into_response(),
+    }
+}
+
+async fn stop_session_recording() -> Response {
+    match session_recording::stop() {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::CONFLICT, e.to_string()).into_response(),
     }
 }
 
-fn with_plugin_manager<F, R>(f: F) -> Result<R, anyhow::Error> where F: Fn(&PluginManager) -> Result<R, anyhow::Error> {
-    match GlobalPluginManager::get().lock() {
-        Ok(mut plugin_manager) => {
-            Ok(f(&mut plugin_manager)?)
+/// Download the running or the last recording as a compressed bundle.
+async fn get_session_bundle() -> Response {
+    match session_recording::create_bundle() {
+        Ok(content) => ([(header::CONTENT_TYPE, "application/zip")], content).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    }
+}
+
+/// Get which game builds, engine versions, and plugin versions ran together and how often the plugins failed.
+async fn get_compatibility_report() -> Result<Json<CompatibilityReport>, AppError> {
+    Ok(Json(compatibility::get_report()?))
+}
+
+/// Enable or disable collecting the compatibility data. Returns the report after the change.
+async fn set_compatibility_reporting(Json(payload): Json<SetCompatibilityReporting>) -> Result<Json<CompatibilityReport>, AppError> {
+    compatibility::set_enabled(payload.enabled)?;
+
+    Ok(Json(compatibility::get_report()?))
+}
+
+async fn clear_compatibility_report() -> Result<Json<CompatibilityReport>, AppError> {
+    compatibility::clear()?;
+
+    Ok(Json(compatibility::get_report()?))
+}
+
+/// Eject the engine from the game. It is torn down and unloaded after the response was sent.
+async fn shutdown() -> Result<StatusCode, AppError> {
+    teardown::eject()?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Get the local usage statistics, e.g., the playtime and how long each plugin was enabled.
+async fn get_statistics() -> Result<Json<UsageStatistics>, AppError> {
+    Ok(Json(statistics::get()?))
+}
+
+/// Delete the usage statistics and start over. Returns the statistics after the reset.
+async fn reset_statistics() -> Result<Json<UsageStatistics>, AppError> {
+    statistics::reset()?;
+
+    Ok(Json(statistics::get()?))
+}
+
+/// Get the performance metrics of the last frames and the garbage collector's statistics.
+///
+/// The averages are 0 until a mission ran.
+async fn get_metrics() -> Json<Metrics> {
+    let frame_metrics = metrics::get();
+
+    Json(Metrics {
+        fps: frame_metrics.map_or(0.0, |metrics| metrics.fps),
+        frame_time: frame_metrics.map_or(0.0, |metrics| metrics.frame_time),
+        plugin_time: frame_metrics.map_or(0.0, |metrics| metrics.plugin_time),
+        plugin_share: frame_metrics.map_or(0.0, |metrics| metrics.plugin_share),
+        garbage_collection: GarbageCollectionStats {
+            used_memory: gc::used_memory(),
+            frame_stepping: gc::is_frame_stepping(),
+            step_time: frame_metrics.map_or(0.0, |metrics| metrics.gc_time),
+            max_step_time: frame_metrics.map_or(0.0, |metrics| metrics.max_gc_time),
+            cycles: gc::cycles(),
         },
-        Err(e) => Err(anyhow!("Could not get lock to plugin manager: {:?}", e)),
+    })
+}
+
+/// Get the engine's version, the features plugins can require, and the plugin API version.
+async fn get_health() -> Json<EngineHealth> {
+    Json(EngineHealth {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        api_version: API_VERSION,
+        features: ENGINE_FEATURES.iter().map(|feature| feature.to_string()).collect(),
+        plugin_api_version: PLUGIN_API_VERSION.to_string(),
+    })
+}
+
+/// Get the results of the diagnostic checks that ran when the mod was injected.
+async fn get_diagnostics() -> Result<Json<Diagnostics>, AppError> {
+    Ok(Json(diagnostics::get()?))
+}
+
+/// Get the report of the sandbox audit mode.
+async fn get_audit_report() -> Result<Json<AuditReport>, AppError> {
+    Ok(Json(audit::get_report()?))
+}
+
+/// Get the state of the integrity reports and the latest report.
+async fn get_integrity_status() -> Result<Json<IntegrityStatus>, AppError> {
+    Ok(Json(integrity::get_status()?))
+}
+
+/// Get the state of the plugin benchmark and its result if it finished.
+async fn get_benchmark() -> Result<Json<BenchmarkState>, AppError> {
+    Ok(Json(benchmark::get_state()?))
+}
+
+/// Start measuring the overhead of every enabled plugin.
+///
+/// The benchmark starts with the next frame of a running mission.
+async fn start_benchmark(payload: Option<Json<StartBenchmark>>) -> Response {
+    let Json(payload) = payload.unwrap_or_default();
+
+    match benchmark::start(payload.frames) {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::CONFLICT, e.to_string()).into_response(),
+    }
+}
+
+async fn cancel_benchmark() -> impl IntoResponse {
+    with_plugin_manager_mut(|plugin_manager| -> Response {
+        match benchmark::cancel(plugin_manager) {
+            Ok(_) => StatusCode::NO_CONTENT.into_response(),
+            Err(e) => (StatusCode::CONFLICT, e.to_string()).into_response(),
+        }
+    })
+}
+
+/// Get the names of the keys that can be remapped.
+async fn get_remappable_keys() -> Json<Vec<String>> {
+    Json(remapping::get_supported_keys())
+}
+
+async fn get_input_remaps() -> Json<Vec<InputRemap>> {
+    Json(remapping::get_remaps())
+}
+
+/// Replace the input remaps. They apply immediately but are not persisted by the mod.
+async fn set_input_remaps(Json(payload): Json<Vec<InputRemap>>) -> Response {
+    match remapping::set_remaps(&payload) {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Get the watch expressions.
+async fn get_watches(developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("watching expressions");
+    }
+
+    Json(watches::get_expressions()).into_response()
+}
+
+/// Replace the watch expressions. They are persisted and evaluated from the next second on.
+///
+/// Expressions are evaluated in the developer environment, so they can only be set in developer mode and not by browsers.
+async fn set_watches(Json(payload): Json<Vec<String>>, headers: HeaderMap, developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("watching expressions");
+    }
+
+    if is_from_browser(&headers) {
+        warn!("Rejected watch expressions from origin {:?}", headers.get(header::ORIGIN));
+        return (StatusCode::FORBIDDEN, "watch expressions from browsers are not allowed").into_response();
+    }
+
+    match watches::set_expressions(payload) {
+        Ok(_) => Json(watches::get_expressions()).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Get all bookmarks ordered by their name.
+async fn get_bookmarks() -> Json<Vec<Bookmark>> {
+    Json(bookmarks::get_bookmarks())
+}
+
+/// Add a bookmark or replace the bookmark with the same name. Plugins can use it immediately.
+///
+/// Returns all bookmarks after the change.
+async fn set_bookmark(Json(payload): Json<Bookmark>) -> Response {
+    match bookmarks::set(payload) {
+        Ok(_) => Json(bookmarks::get_bookmarks()).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Remove the bookmark. Returns all bookmarks after the change.
+async fn remove_bookmark(axum::extract::Path(name): axum::extract::Path<String>) -> Response {
+    match bookmarks::remove(&name) {
+        Ok(_) => Json(bookmarks::get_bookmarks()).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Get the state of the memory scan with the current values of its first matches.
+async fn get_memory_scan(developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("scanning memory");
+    }
+
+    match memory_scan::get_state() {
+        Ok(state) => Json::<ScanState>(state).into_response(),
+        Err(e) => AppError::from(e).into_response(),
+    }
+}
+
+/// Start a new memory scan for a value, replacing the current scan. Only available in developer mode.
+///
+/// Scanning reads all of the game's writable memory, so the runtime moves its other requests to other threads first.
+async fn start_memory_scan(Json(payload): Json<StartScan>, developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("scanning memory");
+    }
+
+    match tokio::task::block_in_place(|| memory_scan::start(&payload)) {
+        Ok(state) => Json(state).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Filter the matches of the current memory scan.
+async fn next_memory_scan(Json(payload): Json<NextScan>, developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("scanning memory");
+    }
+
+    match tokio::task::block_in_place(|| memory_scan::next(&payload)) {
+        Ok(state) => Json(state).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+async fn reset_memory_scan(developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("scanning memory");
+    }
+
+    memory_scan::reset();
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Read the current values at the addresses, e.g., to watch addresses found by a memory scan.
+async fn read_memory_values(Json(payload): Json<Vec<MemoryAddress>>, developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("reading scanned values");
+    }
+
+    match memory_scan::read_values(&payload) {
+        Ok(values) => Json::<Vec<MemoryValue>>(values).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Get the report of the last crash, or `null` if the game didn't crash since the report was dismissed.
+async fn get_last_crash() -> Json<Option<CrashReport>> {
+    Json(crash::get_last_crash())
+}
+
+/// Dismiss the report of the last crash.
+async fn dismiss_last_crash() -> Response {
+    match crash::dismiss_last_crash() {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn get_debugger_state() -> Json<DebuggerState> {
+    Json(debugger::get_state())
+}
+
+/// Pause the game loop before the next frame. Only available in developer mode.
+async fn pause_game(developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("pausing the game");
+    }
+
+    debugger::pause();
+    Json(debugger::get_state()).into_response()
+}
+
+async fn resume_game(developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("resuming the game");
     }
+
+    debugger::resume();
+    Json(debugger::get_state()).into_response()
+}
+
+/// Run the game for the number of frames and pause it again. Only available in developer mode.
+async fn step_game(Json(payload): Json<StepFrames>, developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("stepping the game");
+    }
+
+    debugger::step(payload.frames);
+    Json(debugger::get_state()).into_response()
+}
+
+/// Get the known game structs followed by the structs plugins registered.
+async fn get_struct_definitions() -> Json<Vec<StructDefinition>> {
+    Json(structs::get_definitions())
 }
 
-async fn get_plugins() -> Result<Json<HashMap<String, futuremod_data::plugin::Plugin>>, String> {
-    GlobalPluginManager::with_plugin_manager(|plugin_manager| {
-        let plugins = plugin_manager.get_plugins();
+/// Read the fields of the struct instance at the address.
+async fn read_struct(Json(payload): Json<ReadStruct>) -> Response {
+    match structs::read(&payload.definition, payload.address) {
+        Ok(values) => Json::<Vec<StructFieldValue>>(values).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
 
-        let mut plugin_response: HashMap<String, futuremod_data::plugin::Plugin> = HashMap::new();
+/// Accept a consumer of the watched values. Rejects browsers like [`repl_handler`].
+async fn watches_handler(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    developer_mode: bool,
+) -> Response {
+    if !developer_mode {
+        return developer_mode_required("watching expressions");
+    }
 
-        for (name, plugin) in plugins.iter() {
-            plugin_response.insert(name.clone(), plugin.clone().into());
+    if is_from_browser(&headers) {
+        warn!("Rejected watch consumer with origin {:?}", headers.get(header::ORIGIN));
+        return (StatusCode::FORBIDDEN, "watch consumers from browsers are not allowed").into_response();
+    }
+
+    debug!("Registering new watch consumer");
+    ws.on_upgrade(handle_watches)
+}
+
+/// Send the latest values of the watch expressions and then the values of every evaluation.
+async fn handle_watches(mut socket: WebSocket) {
+    let mut receiver = watches::subscribe();
+
+    let mut values = watches::get_values();
+    loop {
+        let message = match serde_json::to_string::<Vec<WatchValue>>(&values) {
+            Ok(m) => m,
+            Err(_) => return,
+        };
+
+        if socket.send(Message::Text(message)).await.is_err() {
+            return;
         }
 
-        Ok(Json(plugin_response))
-    }).map_err(|e| e.to_string())
+        values = match receiver.recv().await {
+            Ok(values) => values,
+            // Skip values the socket was too slow for
+            Err(broadcast::error::RecvError::Lagged(_)) => watches::get_values(),
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+    }
+}
+
+/// Accept a REPL client.
+///
+/// The GUI doesn't send an `Origin` header, so upgrades with one come from a browser and are rejected,
+/// to not let websites run chunks in the game.
+async fn repl_handler(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    developer_mode: bool,
+) -> Response {
+    if is_from_browser(&headers) {
+        warn!("Rejected REPL client with origin {:?}", headers.get(header::ORIGIN));
+        return (StatusCode::FORBIDDEN, "REPL clients from browsers are not allowed").into_response();
+    }
+
+    debug!("Registering new REPL client");
+    ws.on_upgrade(move |socket| handle_repl(socket, developer_mode))
+}
+
+/// Run the chunks the client sends and send back the result of each chunk.
+///
+/// Errors, e.g., of invalid requests, are sent as the result's error, so the connection stays open.
+async fn handle_repl(mut socket: WebSocket, developer_mode: bool) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let request = match message {
+            Message::Text(request) => serde_json::from_str::<ReplRequest>(&request),
+            Message::Close(_) => return,
+            _ => continue,
+        };
+
+        let result = match request {
+            Ok(request) => run_repl_chunk(&request, developer_mode),
+            Err(e) => ChunkResult { error: Some(format!("invalid request: {}", e)), ..Default::default() },
+        };
+
+        let message = match serde_json::to_string(&result) {
+            Ok(m) => m,
+            Err(_) => return,
+        };
+
+        if socket.send(Message::Text(message)).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Run the chunk in the scratch environment, or in the plugin's environment in developer mode.
+fn run_repl_chunk(request: &ReplRequest, developer_mode: bool) -> ChunkResult {
+    let result = match &request.plugin {
+        Some(_) if !developer_mode => Err(anyhow!("running chunks in a plugin's environment requires developer mode")),
+        Some(plugin) => {
+            debug!("Running REPL chunk in the environment of plugin {}", plugin);
+
+            with_plugin_manager_mut(|plugin_manager| plugin_manager.run_chunk(Some(plugin), &request.chunk, REPL_TIMEOUT))
+                .map_err(|e| e.0)
+                .and_then(|result| result)
+        },
+        None => {
+            debug!("Running REPL chunk in the scratch environment");
+
+            with_plugin_manager_mut(|plugin_manager| plugin_manager.run_scratch_chunk(&request.chunk, REPL_TIMEOUT))
+                .map_err(|e| e.0)
+                .and_then(|result| result)
+        },
+    };
+
+    result.unwrap_or_else(|e| ChunkResult { error: Some(e.to_string()), ..Default::default() })
+}
+
+/// Get the key bindings the plugin registered with the keys they use.
+async fn get_key_bindings(axum::extract::Path(name): axum::extract::Path<String>) -> Json<Vec<KeyBinding>> {
+    Json(key_bindings::get_bindings(&name))
+}
+
+/// Change the key of a plugin's binding. The change applies immediately and is persisted.
+///
+/// Returns the plugin's bindings after the change.
+async fn set_key_binding(axum::extract::Path(name): axum::extract::Path<String>, Json(payload): Json<SetKeyBinding>) -> Response {
+    match key_bindings::set_binding(&name, &payload) {
+        Ok(_) => Json(key_bindings::get_bindings(&name)).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Info of the installed plugin, or the response if it isn't installed.
+fn get_installed_plugin_info(name: &str) -> Result<PluginInfo, Response> {
+    match snapshot::get().get(name) {
+        Some(plugin) => Ok(plugin.info.clone()),
+        None => Err((StatusCode::NOT_FOUND, AppError(anyhow!("plugin doesn't exist"))).into_response()),
+    }
+}
+
+/// Get the plugin's settings and their current values.
+async fn get_plugin_config(axum::extract::Path(name): axum::extract::Path<String>) -> Response {
+    let info = match get_installed_plugin_info(&name) {
+        Ok(info) => info,
+        Err(response) => return response,
+    };
+
+    match settings::get_settings(&info) {
+        Ok(settings) => Json(settings).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+/// Change the values of the plugin's settings. The changes apply immediately and are persisted.
+///
+/// Returns the plugin's settings after the change.
+async fn set_plugin_config(axum::extract::Path(name): axum::extract::Path<String>, Json(payload): Json<BTreeMap<String, SettingValue>>) -> Response {
+    let info = match get_installed_plugin_info(&name) {
+        Ok(info) => info,
+        Err(response) => return response,
+    };
+
+    if let Err(e) = settings::set_values(&info, payload) {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+
+    match settings::get_settings(&info) {
+        Ok(settings) => Json(settings).into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+/// Approve that the plugin sends requests to the hosts it declares.
+async fn approve_network(axum::extract::Path(name): axum::extract::Path<String>) -> Response {
+    let info = match get_installed_plugin_info(&name) {
+        Ok(info) => info,
+        Err(response) => return response,
+    };
+
+    if info.hosts.is_empty() {
+        return (StatusCode::BAD_REQUEST, "the plugin doesn't declare any hosts").into_response();
+    }
+
+    match net::approve(&info) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+/// Revoke the approval of the plugin's hosts, so its requests are refused.
+async fn revoke_network(axum::extract::Path(name): axum::extract::Path<String>) -> Response {
+    match net::revoke(&name) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+/// Approve that the plugin runs the commands it declares.
+async fn approve_commands(axum::extract::Path(name): axum::extract::Path<String>) -> Response {
+    let info = match get_installed_plugin_info(&name) {
+        Ok(info) => info,
+        Err(response) => return response,
+    };
+
+    if info.commands.is_empty() {
+        return (StatusCode::BAD_REQUEST, "the plugin doesn't declare any commands").into_response();
+    }
+
+    match exec::approve(&info) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+/// Revoke the approval of the plugin's commands, so it can't run them anymore.
+async fn revoke_commands(axum::extract::Path(name): axum::extract::Path<String>) -> Response {
+    match exec::revoke(&name) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => AppError(e).into_response(),
+    }
+}
+
+/// Get the state of the connection to another player's game.
+async fn get_multiplayer_status() -> Result<Json<MultiplayerStatus>, AppError> {
+    Ok(Json(multiplayer::get_status()?))
+}
+
+/// Start syncing the game state with another player's game.
+async fn connect_multiplayer(Json(payload): Json<ConnectPeer>) -> Response {
+    match multiplayer::connect(&payload) {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+async fn disconnect_multiplayer() -> Result<StatusCode, AppError> {
+    multiplayer::disconnect()?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_screenshots() -> Result<Json<Vec<Screenshot>>, AppError> {
+    Ok(Json(screenshot::get_screenshots()?))
+}
+
+/// Get the PNG file of a screenshot by its path relative to the screenshot directory.
+async fn get_screenshot(axum::extract::Path(path): axum::extract::Path<String>) -> Response {
+    let path = match screenshot::get_screenshot_path(&path) {
+        Ok(path) => path,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    match fs::read(&path).await {
+        Ok(content) => ([(header::CONTENT_TYPE, "image/png")], content).into_response(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (StatusCode::NOT_FOUND, "screenshot doesn't exist").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("could not read the screenshot: {}", e)).into_response(),
+    }
 }
 
 #[derive(Deserialize)]
@@ -283,6 +1070,24 @@ async fn reload_plugin(Json(payload): Json<PluginByName>) -> impl IntoResponse {
                 PluginManagerError::PluginNotFound => {
                     (StatusCode::NOT_FOUND, AppError(anyhow!("plugin doesn't exist"))).into_response()
                 },
+                PluginManagerError::Tampered => tampered_response(),
+                e => (StatusCode::INTERNAL_SERVER_ERROR, AppError(anyhow!("could not reload plugin: {:?}", e))).into_response(),
+            }
+            _ => StatusCode::NO_CONTENT.into_response(),
+        }
+    })
+}
+
+/// Reload a plugin including its info file.
+async fn force_reload_plugin(Json(payload): Json<PluginByName>) -> impl IntoResponse {
+    with_plugin_manager_mut(|plugin_manager| -> Response {
+        match plugin_manager.force_reload_plugin(&payload.name) {
+            Err(e) => match e {
+                PluginManagerError::PluginNotFound => {
+                    (StatusCode::NOT_FOUND, AppError(anyhow!("plugin doesn't exist"))).into_response()
+                },
+                PluginManagerError::Other(e) => (StatusCode::BAD_REQUEST, AppError(anyhow!("could not reload plugin: {}", e))).into_response(),
+                PluginManagerError::Tampered => tampered_response(),
                 e => (StatusCode::INTERNAL_SERVER_ERROR, AppError(anyhow!("could not reload plugin: {:?}", e))).into_response(),
             }
             _ => StatusCode::NO_CONTENT.into_response(),
@@ -290,7 +1095,140 @@ async fn reload_plugin(Json(payload): Json<PluginByName>) -> impl IntoResponse {
     })
 }
 
-const TEMPORARY_DIRECTORY: &str = "fcop";
+/// Convert a plugin installed in developer mode to a regular installation.
+async fn promote_plugin(axum::extract::Path(name): axum::extract::Path<String>) -> impl IntoResponse {
+    with_plugin_manager_mut(|plugin_manager| -> Response {
+        match plugin_manager.promote_plugin(&name) {
+            Err(e) => match e {
+                PluginManagerError::PluginNotFound => {
+                    (StatusCode::NOT_FOUND, AppError(anyhow!("plugin doesn't exist"))).into_response()
+                },
+                PluginManagerError::NotDevPlugin => {
+                    (StatusCode::BAD_REQUEST, AppError(anyhow!("plugin wasn't installed in developer mode"))).into_response()
+                },
+                e => (StatusCode::INTERNAL_SERVER_ERROR, AppError(anyhow!("could not convert plugin: {:?}", e))).into_response(),
+            }
+            _ => StatusCode::NO_CONTENT.into_response(),
+        }
+    })
+}
+
+/// Serve a route registered by a plugin with its `http` library.
+///
+/// The plugin's handler runs on the server's thread and only gets the snapshot the plugin stored.
+/// The game loop waits for the handler, so it's aborted after [`PLUGIN_ROUTE_TIMEOUT`].
+async fn plugin_http_route(
+    axum::extract::Path((name, path)): axum::extract::Path<(String, String)>,
+    axum::extract::Query(query): axum::extract::Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    with_plugin_manager(|plugin_manager| -> Response {
+        match plugin_manager.handle_http_request(&name, &path, query.clone(), PLUGIN_ROUTE_TIMEOUT) {
+            Ok(Some(response)) => Json(response).into_response(),
+            Ok(None) => (StatusCode::NOT_FOUND, format!("plugin '{}' has no route '{}'", name, path)).into_response(),
+            Err(PluginManagerError::PluginNotFound) => {
+                (StatusCode::NOT_FOUND, AppError(anyhow!("plugin doesn't exist"))).into_response()
+            },
+            Err(PluginManagerError::Plugin(PluginError::NotEnabledError)) => {
+                (StatusCode::SERVICE_UNAVAILABLE, format!("plugin '{}' is not enabled", name)).into_response()
+            },
+            Err(PluginManagerError::Plugin(PluginError::ScriptError(e))) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, format!("route handler threw an error: {}", e.message)).into_response()
+            },
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, AppError(anyhow!("could not handle request: {:?}", e))).into_response(),
+        }
+    })
+}
+
+/// Pin the checksum of a tampered plugin's current files and load it.
+async fn trust_plugin(axum::extract::Path(name): axum::extract::Path<String>) -> impl IntoResponse {
+    with_plugin_manager_mut(|plugin_manager| -> Response {
+        match plugin_manager.trust_plugin(&name) {
+            Err(e) => match e {
+                PluginManagerError::PluginNotFound => {
+                    (StatusCode::NOT_FOUND, AppError(anyhow!("plugin doesn't exist"))).into_response()
+                },
+                e => (StatusCode::INTERNAL_SERVER_ERROR, AppError(anyhow!("could not trust plugin: {:?}", e))).into_response(),
+            }
+            _ => StatusCode::NO_CONTENT.into_response(),
+        }
+    })
+}
+
+/// Stop suspecting a plugin of crashing the game, so it's enabled as usual on the next start.
+async fn dismiss_suspect(axum::extract::Path(name): axum::extract::Path<String>) -> impl IntoResponse {
+    with_plugin_manager_mut(|plugin_manager| -> Response {
+        match plugin_manager.dismiss_suspect(&name) {
+            Err(e) => match e {
+                PluginManagerError::PluginNotFound => {
+                    (StatusCode::NOT_FOUND, AppError(anyhow!("plugin doesn't exist"))).into_response()
+                },
+                e => (StatusCode::INTERNAL_SERVER_ERROR, AppError(anyhow!("could not dismiss the suspicion: {:?}", e))).into_response(),
+            }
+            _ => StatusCode::NO_CONTENT.into_response(),
+        }
+    })
+}
+
+/// Whether the request comes from a browser, e.g., of a website the player visits.
+///
+/// The GUI doesn't send an `Origin` header, but browsers do.
+fn is_from_browser(headers: &HeaderMap) -> bool {
+    headers.contains_key(header::ORIGIN)
+}
+
+/// Response of the developer tools' routes outside of developer mode.
+fn developer_mode_required(action: &str) -> Response {
+    (StatusCode::FORBIDDEN, format!("{} requires developer mode", action)).into_response()
+}
+
+fn tampered_response() -> Response {
+    (StatusCode::CONFLICT, AppError(anyhow!("the plugin's files changed since it was installed, trust its current files to load it"))).into_response()
+}
+
+/// Call a plugin's lifecycle function without changing its state.
+///
+/// Used by the GUI's developer tools, thus, only available in developer mode.
+async fn invoke_lifecycle(Json(payload): Json<InvokeLifecycle>, developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("calling lifecycle functions");
+    }
+
+    with_plugin_manager_mut(|plugin_manager| -> Response {
+        match plugin_manager.invoke_lifecycle(&payload.name, payload.event) {
+            Ok(true) => StatusCode::NO_CONTENT.into_response(),
+            Ok(false) => (StatusCode::BAD_REQUEST, format!("plugin doesn't define {}", payload.event)).into_response(),
+            Err(PluginManagerError::PluginNotFound) => {
+                (StatusCode::NOT_FOUND, AppError(anyhow!("plugin doesn't exist"))).into_response()
+            },
+            Err(PluginManagerError::Plugin(PluginError::ScriptError(e))) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, format!("{} threw an error: {}", payload.event, e.message)).into_response()
+            },
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, AppError(anyhow!("could not call {}: {:?}", payload.event, e))).into_response(),
+        }
+    })
+    .into_response()
+}
+
+/// Get the globals the plugin set in its environment. Only available in developer mode.
+async fn get_plugin_environment(axum::extract::Path(name): axum::extract::Path<String>, developer_mode: bool) -> Response {
+    if !developer_mode {
+        return developer_mode_required("inspecting a plugin's environment");
+    }
+
+    with_plugin_manager(|plugin_manager| -> Response {
+        match plugin_manager.inspect_environment(&name) {
+            Ok(entries) => Json::<Vec<EnvironmentEntry>>(entries).into_response(),
+            Err(PluginManagerError::PluginNotFound) => {
+                (StatusCode::NOT_FOUND, AppError(anyhow!("plugin doesn't exist"))).into_response()
+            },
+            Err(PluginManagerError::Plugin(PluginError::NotLoaded)) => {
+                (StatusCode::BAD_REQUEST, AppError(anyhow!("plugin isn't loaded"))).into_response()
+            },
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, AppError(anyhow!("could not inspect the environment: {:?}", e))).into_response(),
+        }
+    })
+    .into_response()
+}
 
 enum InstallError {
     ExtractionError(String),
@@ -298,31 +1236,26 @@ enum InstallError {
 }
 
 
-async fn get_plugin_info(request: BodyStream) -> (StatusCode, Result<Json<PluginInfo>, String>) {
+async fn get_plugin_info(axum::extract::Query(query): axum::extract::Query<LanguageQuery>, request: BodyStream) -> (StatusCode, Result<Json<PluginInfo>, String>) {
     info!("Get plugin info");
 
-    let random_file_name: String = Alphanumeric.sample_string(&mut rand::thread_rng(), 16);
-    let mut random_file_path = PathBuf::from(random_file_name);
-    random_file_path.set_extension("zip");
-
-    let fcop_temp_folder = Path::new(&std::env::temp_dir()).join(PathBuf::from(TEMPORARY_DIRECTORY));
-    if !fcop_temp_folder.exists() {
-        if let Err(err) = fs::create_dir(&fcop_temp_folder).await {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("Could not create temporary directory for fcop mod: {}", err.to_string())));
-        }
-    }
+    // Deleted with the extracted plugin when the request finishes, also if it fails
+    let temporary_file = match Artifact::new("zip") {
+        Ok(temporary_file) => temporary_file,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("Could not create temporary directory for fcop mod: {}", err))),
+    };
 
-    let temporary_file_path = fcop_temp_folder.join(&random_file_path);
+    let temporary_file_path = temporary_file.path();
     debug!("Storing incoming plugin package in temporary file: {}", temporary_file_path.to_str().unwrap_or("unknown"));
 
-    match write_to_temp_file(&temporary_file_path, request).await {
+    match write_to_temp_file(temporary_file_path, request).await {
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Err(e.0.to_string())),
         _ => (),
     };
     debug!("Wrote plugin file into temporary file");
 
     info!("Extracting plugin package");
-    let temporary_plugin_folder = match extract_temp_file(&temporary_file_path).await {
+    let temporary_plugin_folder = match extract_temp_file(temporary_file_path).await {
         Err(e) => match e {
             InstallError::ExtractionError(msg) => return (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("Error while extracting the plugin package: {}", msg))),
             InstallError::Other(msg) => return (StatusCode::INTERNAL_SERVER_ERROR, Err(msg)),
@@ -331,7 +1264,7 @@ async fn get_plugin_info(request: BodyStream) -> (StatusCode, Result<Json<Plugin
     };
 
     info!("Reading plugin information");
-    let info = match load_plugin_info(temporary_plugin_folder.clone()) {
+    let mut info = match load_plugin_info(temporary_plugin_folder) {
         Err(err) => match err {
             PluginInfoError::FileNotFound => return (StatusCode::BAD_REQUEST, Err("Plugin package doesn't contain a info file".to_string())),
             PluginInfoError::Format(msg) => return (StatusCode::BAD_REQUEST, Err(format!("Plugin info file has invalid format: {}", msg))),
@@ -340,48 +1273,45 @@ async fn get_plugin_info(request: BodyStream) -> (StatusCode, Result<Json<Plugin
         Ok(v) => v,
     };
 
-    info!("Deleting temporary plugin");
-    match fs::remove_dir_all(temporary_plugin_folder).await {
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("Error while deleting the temporarily created plugin: {:?}", e))),
-        Ok(()) => (),
-    };
+    if let Some(language) = &query.language {
+        info.localize(language);
+    }
 
     (StatusCode::OK, Ok(Json(info)))
 }
 
 
-async fn install_plugin(request: BodyStream) -> (StatusCode, Result<(), String>) {
-    info!("Installing new plugin");
-
-    let random_file_name: String = Alphanumeric.sample_string(&mut rand::thread_rng(), 16);
-    let mut random_file_path = PathBuf::from(random_file_name);
-    random_file_path.set_extension("zip");
+/// Store the uploaded plugin package in a temporary file and extract it.
+///
+/// Returns the temporary file, which deletes the extracted plugin when dropped, and the extracted plugin's folder.
+async fn receive_plugin_package(request: BodyStream) -> Result<(Artifact, PathBuf), String> {
+    let temporary_file = Artifact::new("zip").map_err(|err| format!("Could not create temporary directory for fcop mod: {}", err))?;
 
-    let fcop_temp_folder = Path::new(&std::env::temp_dir()).join(PathBuf::from(TEMPORARY_DIRECTORY));
-    if !fcop_temp_folder.exists() {
-        if let Err(err) = fs::create_dir(&fcop_temp_folder).await {
-            return (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("Could not create temporary directory for fcop mod: {}", err.to_string())));
-        }
-    }
-
-    let temporary_file_path = fcop_temp_folder.join(&random_file_path);
+    let temporary_file_path = temporary_file.path();
     debug!("Storing incoming plugin package in temporary file: {}", temporary_file_path.to_str().unwrap_or("unknown"));
 
-    match write_to_temp_file(&temporary_file_path, request).await {
-        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("{:?}", e))),
-        _ => (),
-    };
+    write_to_temp_file(temporary_file_path, request).await.map_err(|e| format!("{:?}", e))?;
     debug!("Wrote plugin file into temporary file");
 
     info!("Extracting plugin package");
-    let temporary_plugin_folder = match extract_temp_file(&temporary_file_path).await {
-        Err(e) => match e {
-            InstallError::ExtractionError(msg) => return (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("Error while extracting the plugin package: {}", msg))),
-            InstallError::Other(msg) => return (StatusCode::INTERNAL_SERVER_ERROR, Err(msg)),
-        },
+    let temporary_plugin_folder = match extract_temp_file(temporary_file_path).await {
+        Err(InstallError::ExtractionError(msg)) => return Err(format!("Error while extracting the plugin package: {}", msg)),
+        Err(InstallError::Other(msg)) => return Err(msg),
         Ok(v) => v,
     };
 
+    Ok((temporary_file, temporary_plugin_folder))
+}
+
+async fn install_plugin(request: BodyStream) -> (StatusCode, Result<(), String>) {
+    info!("Installing new plugin");
+
+    // Deleted with the extracted plugin when the request finishes, also if it fails
+    let (_temporary_file, temporary_plugin_folder) = match receive_plugin_package(request).await {
+        Ok(package) => package,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Err(e)),
+    };
+
     info!("Reading plugin information");
     let info = match load_plugin_info(temporary_plugin_folder.clone()) {
         Err(err) => match err {
@@ -402,8 +1332,61 @@ async fn install_plugin(request: BodyStream) -> (StatusCode, Result<(), String>)
             Ok(()) => (StatusCode::OK, Ok(())),
             Err(err) => match err {
                 PluginInstallError::AlreadyInstalled => (StatusCode::BAD_REQUEST, Err("plugin is already installed".to_string())),
-                PluginInstallError::InvalidName => (StatusCode::BAD_REQUEST, Err("plugin has an invalid name".to_string())),
+                PluginInstallError::InvalidName => (StatusCode::BAD_REQUEST, Err("plugin has an invalid name, it must contain a letter or digit and must not be reserved by Windows".to_string())),
+                PluginInstallError::InfoFile(e) => (StatusCode::BAD_REQUEST, Err(format!("plugin package info error: {:?}", e))),
+                PluginInstallError::Plugin(e) => (StatusCode::BAD_REQUEST, Err(format!("Plugin was installed but immediately errored: {:?}", e))),
+                _ => (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("Error while installing plugin: {:?}", err))),
+            }
+        }
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("Error while installing plugin: {:?}", err))),
+    }
+}
+
+/// Update an installed plugin to the uploaded version, keeping its state, stored values, and settings.
+async fn update_plugin(request: BodyStream) -> (StatusCode, Result<(), String>) {
+    info!("Updating plugin");
+
+    // Deleted with the extracted plugin when the request finishes, also if it fails
+    let (_temporary_file, temporary_plugin_folder) = match receive_plugin_package(request).await {
+        Ok(package) => package,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, Err(e)),
+    };
+
+    match with_plugin_manager_mut(move |plugin_manager| {
+        plugin_manager.update_plugin_from_folder(&temporary_plugin_folder)
+    }) {
+        Ok(result) => match result {
+            Ok(()) => (StatusCode::OK, Ok(())),
+            Err(err) => match err {
+                PluginInstallError::NotInstalled => (StatusCode::NOT_FOUND, Err("plugin isn't installed".to_string())),
+                PluginInstallError::Linked => (StatusCode::BAD_REQUEST, Err("plugin is installed in developer mode, change its source folder instead".to_string())),
                 PluginInstallError::InfoFile(e) => (StatusCode::BAD_REQUEST, Err(format!("plugin package info error: {:?}", e))),
+                PluginInstallError::Plugin(e) => (StatusCode::BAD_REQUEST, Err(format!("Plugin was updated but immediately errored: {:?}", e))),
+                _ => (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("Error while updating plugin: {:?}", err))),
+            }
+        }
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("Error while updating plugin: {:?}", err))),
+    }
+}
+
+/// Install a plugin in developer mode by linking its folder into the plugins folder.
+///
+/// Unlike [`install_plugin`], the plugin isn't uploaded, so the folder must be accessible by the mod.
+async fn install_dev_plugin(Json(payload): Json<InstallDevPlugin>) -> (StatusCode, Result<(), String>) {
+    if !payload.path.is_dir() {
+        return (StatusCode::BAD_REQUEST, Err(format!("{} is not a folder", payload.path.display())));
+    }
+
+    match with_plugin_manager_mut(move |plugin_manager| {
+        plugin_manager.link_plugin_from_folder(&payload.path)
+    }) {
+        Ok(result) => match result {
+            Ok(()) => (StatusCode::OK, Ok(())),
+            Err(err) => match err {
+                PluginInstallError::AlreadyInstalled => (StatusCode::BAD_REQUEST, Err("plugin is already installed".to_string())),
+                PluginInstallError::InvalidName => (StatusCode::BAD_REQUEST, Err("plugin has an invalid name, it must contain a letter or digit and must not be reserved by Windows".to_string())),
+                PluginInstallError::InfoFile(PluginInfoError::FileNotFound) => (StatusCode::BAD_REQUEST, Err("folder doesn't contain an info.toml file".to_string())),
+                PluginInstallError::InfoFile(e) => (StatusCode::BAD_REQUEST, Err(format!("plugin info error: {:?}", e))),
                 PluginInstallError::Plugin(e) => (StatusCode::BAD_REQUEST, Err(format!("Plugin was installed but immediately errored: {:?}", e))),
                 _ => (StatusCode::INTERNAL_SERVER_ERROR, Err(format!("Error while installing plugin: {:?}", err))),
             }
@@ -443,8 +1426,7 @@ async fn extract_temp_file(path: &PathBuf) -> Result<PathBuf, InstallError> {
 
     let mut archive = zip::ZipArchive::new(plugin_package).map_err(|err| InstallError::ExtractionError(err.to_string()))?;
 
-    let mut destination = path.clone();
-    destination.set_extension("");
+    let destination = path.with_extension("");
 
     // Actually extract the archive to the destination folder
     archive.extract(&destination).map_err(|err| InstallError::ExtractionError(err.to_string()))?;
@@ -452,6 +1434,48 @@ async fn extract_temp_file(path: &PathBuf) -> Result<PathBuf, InstallError> {
     Ok(destination)
 }
 
+/// Download a backup of the plugins directory as zip archive.
+async fn create_backup() -> Response {
+    let backup = GlobalPluginManager::with_plugin_manager_mut(|plugin_manager| {
+        // The backup must contain the changes that weren't persisted yet
+        plugin_manager.persist();
+
+        backup::create(&plugin_manager.plugins_directory)
+    });
+
+    match backup {
+        Ok(content) => ([(header::CONTENT_TYPE, "application/zip")], content).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("could not create the backup: {}", e)).into_response(),
+    }
+}
+
+/// Replace all plugins with the uploaded backup of the plugins directory.
+async fn restore_backup(request: BodyStream) -> Response {
+    info!("Restoring a backup of the plugins directory");
+
+    // Deleted with the extracted backup when the request finishes
+    let temporary_file = match Artifact::new("zip") {
+        Ok(temporary_file) => temporary_file,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("Could not create temporary directory for fcop mod: {}", err)).into_response(),
+    };
+
+    if let Err(e) = write_to_temp_file(temporary_file.path(), request).await {
+        return e.into_response();
+    }
+
+    let extracted_folder = temporary_file.folder();
+
+    // Check the entire backup before the current plugins are removed
+    match backup::extract(temporary_file.path(), &extracted_folder) {
+        Ok(()) => match with_plugin_manager_mut(|plugin_manager| plugin_manager.restore_backup(&extracted_folder)) {
+            Ok(Ok(())) => StatusCode::NO_CONTENT.into_response(),
+            Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)).into_response(),
+            Err(e) => e.into_response(),
+        },
+        Err(e) => (StatusCode::BAD_REQUEST, format!("invalid backup: {}", e)).into_response(),
+    }
+}
+
 async fn uninstall_plugin(Json(payload): Json<PluginByName>) -> impl IntoResponse {
     with_plugin_manager_mut(|plugin_manager| {
         match plugin_manager.uninstall_plugin(payload.name.as_str()) {
@@ -513,7 +1537,7 @@ impl Log for LogPublisher {
 
     fn log(&self, record: &log::Record) {
         let mut log_history = LOG_HISTORY.write().unwrap();
-        let record_id = log_history.len() as u64;
+        let record_id = NEXT_LOG_ID.fetch_add(1, Ordering::Relaxed);
 
         let message = (record_id, LogRecord::from(record));
 
@@ -526,3 +1550,56 @@ impl Log for LogPublisher {
         
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use futuremod_data::memory::ScanComparison;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn debugger_requires_developer_mode() {
+        assert_eq!(pause_game(false).await.status(), StatusCode::FORBIDDEN);
+        assert_eq!(resume_game(false).await.status(), StatusCode::FORBIDDEN);
+        assert_eq!(step_game(Json(StepFrames { frames: 1 }), false).await.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn memory_scan_requires_developer_mode() {
+        let scan = StartScan { value_type: "int".to_string(), value: "100".to_string() };
+        let next = NextScan { comparison: ScanComparison::Changed, value: None };
+        let addresses = vec![MemoryAddress { address: 0x46BB28, value_type: "int".to_string() }];
+
+        assert_eq!(get_memory_scan(false).await.status(), StatusCode::FORBIDDEN);
+        assert_eq!(start_memory_scan(Json(scan), false).await.status(), StatusCode::FORBIDDEN);
+        assert_eq!(next_memory_scan(Json(next), false).await.status(), StatusCode::FORBIDDEN);
+        assert_eq!(reset_memory_scan(false).await.status(), StatusCode::FORBIDDEN);
+        assert_eq!(read_memory_values(Json(addresses), false).await.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn watches_require_developer_mode() {
+        let expressions = vec!["1 + 1".to_string()];
+
+        assert_eq!(get_watches(false).await.status(), StatusCode::FORBIDDEN);
+        assert_eq!(set_watches(Json(expressions), HeaderMap::new(), false).await.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn watches_reject_browsers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ORIGIN, header::HeaderValue::from_static("https://example.com"));
+
+        let response = set_watches(Json(vec!["1 + 1".to_string()]), headers, true).await;
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert!(watches::get_expressions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn environment_inspection_requires_developer_mode() {
+        let response = get_plugin_environment(axum::extract::Path("example".to_string()), false).await;
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
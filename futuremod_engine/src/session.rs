@@ -0,0 +1,83 @@
+use std::{collections::HashMap, sync::Mutex, time::{Instant, SystemTime, UNIX_EPOCH}};
+
+use futuremod_data::{plugin::PluginInfo, session::SessionInfo};
+use log::*;
+
+use crate::{compatibility, session_recording};
+
+lazy_static! {
+    static ref SESSION: Mutex<Session> = Mutex::new(Session::new());
+}
+
+/// Statistics of the current game session.
+struct Session {
+    injected_at: SystemTime,
+    started: Instant,
+    frames: u64,
+    missions: u32,
+    plugin_errors: HashMap<String, u32>,
+}
+
+impl Session {
+    fn new() -> Self {
+        Session {
+            injected_at: SystemTime::now(),
+            started: Instant::now(),
+            frames: 0,
+            missions: 0,
+            plugin_errors: HashMap::new(),
+        }
+    }
+
+    fn info(&self) -> SessionInfo {
+        SessionInfo {
+            injected_at: self.injected_at.duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0),
+            uptime: self.started.elapsed().as_secs(),
+            frames: self.frames,
+            missions: self.missions,
+            plugin_errors: self.plugin_errors.clone(),
+        }
+    }
+}
+
+/// Start the session. Must be called when the mod is injected.
+pub fn start() {
+    with_session(|session| *session = Session::new());
+}
+
+/// Count a frame of the mission's game loop.
+pub fn on_frame() {
+    with_session(|session| session.frames += 1);
+}
+
+/// Count a started mission.
+pub fn on_mission_start() {
+    with_session(|session| session.missions += 1);
+}
+
+/// Count an error of the plugin.
+pub fn record_plugin_error(info: &PluginInfo) {
+    with_session(|session| *session.plugin_errors.entry(info.name.clone()).or_insert(0) += 1);
+    session_recording::record_event("pluginError", Some(info.name.clone()));
+    compatibility::record_error(info);
+}
+
+pub fn get_info() -> Result<SessionInfo, anyhow::Error> {
+    let session = SESSION.lock().map_err(|e| anyhow::anyhow!("could not get lock to the session: {}", e))?;
+
+    Ok(session.info())
+}
+
+/// Get the session's statistics without waiting for the lock.
+///
+/// Used by the crash handler, which might run while the session is locked.
+pub fn try_get_info() -> Option<SessionInfo> {
+    SESSION.try_lock().ok().map(|session| session.info())
+}
+
+fn with_session(f: impl FnOnce(&mut Session)) {
+    match SESSION.lock() {
+        Ok(mut session) => f(&mut session),
+        Err(e) => warn!("Could not get lock to the session: {}", e),
+    }
+}
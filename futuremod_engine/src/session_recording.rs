@@ -0,0 +1,201 @@
+use std::{io::{Cursor, Write}, sync::Mutex, time::{Instant, SystemTime}};
+
+use anyhow::{anyhow, bail};
+use futuremod_data::{session::{GameSample, PlayerSample, RecordingState, SessionRecording, TimelineEntry, TimelineEvent, SESSION_BUNDLE_FILE_NAME}, version::API_VERSION};
+use log::{info, kv, Log, Metadata, Record};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{debugger, futurecop::{self, PlayerEntity}};
+
+/// Entries recorded at most, so a forgotten recording can't use up the memory.
+const MAX_ENTRIES: usize = 100_000;
+
+/// Number of mission frames between two samples of the game's state.
+const SAMPLE_INTERVAL: u64 = 30;
+
+lazy_static! {
+    static ref RECORDER: Mutex<Recorder> = Mutex::new(Recorder { current: None, last: None });
+}
+
+/// Records the session's timeline while the user enabled the recording.
+///
+/// Nothing may be logged while the recorder is locked, as the [`SessionLogger`] locks it too.
+struct Recorder {
+    current: Option<Recording>,
+
+    /// Recording that was stopped last, kept to be downloaded.
+    last: Option<SessionRecording>,
+}
+
+struct Recording {
+    started: Instant,
+    started_at: SystemTime,
+    truncated: bool,
+    entries: Vec<TimelineEntry>,
+}
+
+impl Recording {
+    fn new() -> Self {
+        Recording {
+            started: Instant::now(),
+            started_at: SystemTime::now(),
+            truncated: false,
+            entries: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, event: TimelineEvent) {
+        if self.entries.len() >= MAX_ENTRIES {
+            self.truncated = true;
+            return;
+        }
+
+        self.entries.push(TimelineEntry {
+            time: self.started.elapsed().as_secs_f64(),
+            frame: debugger::get_state().frame,
+            event,
+        });
+    }
+
+    fn to_session_recording(&self) -> SessionRecording {
+        SessionRecording {
+            started_at: humantime::format_rfc3339_seconds(self.started_at).to_string(),
+            duration: self.started.elapsed().as_secs_f64(),
+            api_version: API_VERSION,
+            truncated: self.truncated,
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+fn with_recording(f: impl FnOnce(&mut Recording)) {
+    if let Ok(mut recorder) = RECORDER.lock() {
+        if let Some(recording) = &mut recorder.current {
+            f(recording);
+        }
+    }
+}
+
+/// Start recording the session.
+pub fn start() -> Result<(), anyhow::Error> {
+    {
+        let mut recorder = RECORDER.lock().map_err(|e| anyhow!("could not get lock to the session recorder: {}", e))?;
+
+        if recorder.current.is_some() {
+            bail!("the session is already recorded");
+        }
+
+        recorder.current = Some(Recording::new());
+    }
+
+    info!("Started recording the session");
+    Ok(())
+}
+
+/// Stop recording the session. The recording is kept until the next recording starts.
+pub fn stop() -> Result<(), anyhow::Error> {
+    let entries = {
+        let mut recorder = RECORDER.lock().map_err(|e| anyhow!("could not get lock to the session recorder: {}", e))?;
+
+        let recording = match recorder.current.take() {
+            Some(recording) => recording.to_session_recording(),
+            None => bail!("the session isn't recorded"),
+        };
+        let entries = recording.entries.len();
+        recorder.last = Some(recording);
+
+        entries
+    };
+
+    info!("Stopped recording the session with {} entries", entries);
+    Ok(())
+}
+
+pub fn get_state() -> Result<RecordingState, anyhow::Error> {
+    let recorder = RECORDER.lock().map_err(|e| anyhow!("could not get lock to the session recorder: {}", e))?;
+
+    let entries = match (&recorder.current, &recorder.last) {
+        (Some(recording), _) => recording.entries.len(),
+        (None, Some(recording)) => recording.entries.len(),
+        (None, None) => 0,
+    };
+
+    Ok(RecordingState {
+        recording: recorder.current.is_some(),
+        entries,
+        available: recorder.current.is_some() || recorder.last.is_some(),
+    })
+}
+
+/// Compress the running or the last recording into a bundle that users attach to bug reports.
+///
+/// The bundle is a zip archive with the recording as JSON.
+pub fn create_bundle() -> Result<Vec<u8>, anyhow::Error> {
+    let recording = {
+        let recorder = RECORDER.lock().map_err(|e| anyhow!("could not get lock to the session recorder: {}", e))?;
+
+        match (&recorder.current, &recorder.last) {
+            (Some(recording), _) => recording.to_session_recording(),
+            (None, Some(recording)) => recording.clone(),
+            (None, None) => bail!("no session was recorded"),
+        }
+    };
+
+    let content = serde_json::to_vec(&recording)?;
+
+    let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+    archive.start_file(SESSION_BUNDLE_FILE_NAME, FileOptions::default().compression_method(CompressionMethod::Deflated))?;
+    archive.write_all(&content)?;
+
+    Ok(archive.finish()?.into_inner())
+}
+
+/// Record an event of the engine, e.g., that a mission started.
+pub fn record_event(name: &str, detail: Option<String>) {
+    with_recording(|recording| recording.push(TimelineEvent::Engine { name: name.to_string(), detail }));
+}
+
+/// Sample the game's state every [`SAMPLE_INTERVAL`] frames. Must be called in every frame of a mission.
+pub fn on_frame(players: &[Option<*mut PlayerEntity>]) {
+    if debugger::get_state().frame % SAMPLE_INTERVAL != 0 {
+        return;
+    }
+
+    with_recording(|recording| {
+        let players = players
+            .iter()
+            .flatten()
+            .map(|player| unsafe {
+                PlayerSample {
+                    health: (**player).health.health,
+                    position: [(**player).position_x, (**player).position_y, (**player).position_z],
+                }
+            })
+            .collect();
+
+        recording.push(TimelineEvent::Sample(GameSample { players, entities: futurecop::get_entities().len() }));
+    });
+}
+
+/// Log appender that adds log records to the running recording.
+#[derive(Debug)]
+pub struct SessionLogger;
+
+impl Log for SessionLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        with_recording(|recording| recording.push(TimelineEvent::Log {
+            level: record.level().as_str().to_string(),
+            target: record.target().to_string(),
+            message: format!("{}", record.args()),
+            plugin: record.key_values().get(kv::Key::from("plugin")).map(|plugin| plugin.to_string()),
+        }));
+    }
+
+    fn flush(&self) {
+
+    }
+}
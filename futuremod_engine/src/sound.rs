@@ -0,0 +1,181 @@
+use std::{collections::HashMap, fs, io::Cursor, path::{Path, PathBuf}, sync::{mpsc::{self, Receiver, Sender}, Arc, Mutex}, thread};
+
+use anyhow::{anyhow, bail};
+use log::*;
+use rodio::{Decoder, OutputStream, Sink};
+
+use crate::plugins::audit;
+
+/// Largest sound file a plugin can play, in bytes.
+pub const MAX_SOUND_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Sounds of a plugin that play at the same time. Further sounds are dropped.
+pub const MAX_PLAYING: usize = 16;
+
+type Sound = Decoder<Cursor<Arc<[u8]>>>;
+
+/// Command to the thread that plays the sounds.
+///
+/// The audio output can't be moved between threads. Thus, sounds are played by a thread that owns the output.
+/// The thread is started when the first sound is played and keeps running afterwards.
+enum Command {
+    Play { plugin: String, sound: Sound, volume: f32 },
+    SetVolume { plugin: String, volume: f32 },
+    Stop { plugin: String },
+}
+
+lazy_static! {
+    static ref PLAYER: Mutex<Option<Sender<Command>>> = Mutex::new(None);
+    /// Content of the sound files a plugin played, by the plugin's name and the file's path, so files are read only once.
+    static ref FILES: Mutex<HashMap<String, HashMap<PathBuf, Arc<[u8]>>>> = Mutex::new(HashMap::new());
+    /// Volume of each plugin's sounds. Plugins that didn't set a volume play at full volume.
+    static ref VOLUMES: Mutex<HashMap<String, f32>> = Mutex::new(HashMap::new());
+}
+
+/// Play the WAV file in the plugin's folder, exposed to plugins as `sound.playFile`.
+///
+/// The game's own sound effects can't be played yet, as the game's functions that play them aren't reverse engineered.
+/// Once they are, they should be exposed as `sound.play(id)`.
+/// Errors if the file is outside the plugin's folder, too large, or not a WAV file.
+pub fn play(plugin: &str, plugin_folder: &Path, file: &str) -> Result<(), anyhow::Error> {
+    let path = plugin_folder.join(file).canonicalize().map_err(|e| anyhow!("Could not find the sound '{}': {}", file, e))?;
+
+    if !path.starts_with(plugin_folder) {
+        warn!("Plugin {} played {} which is outside its plugin folder", plugin, path.display());
+        bail!("Permission denied: Playing a sound outside of the plugin folder is not allowed");
+    }
+
+    let content = read(plugin, &path)?;
+    let sound = Decoder::new_wav(Cursor::new(content)).map_err(|e| anyhow!("'{}' is not a WAV file: {}", file, e))?;
+
+    send(Command::Play { plugin: plugin.to_string(), sound, volume: volume(plugin) }, true);
+
+    Ok(())
+}
+
+/// Set the volume of the plugin's sounds, including the ones that are playing, from `0` to `1`.
+pub fn set_volume(plugin: &str, volume: f32) {
+    match VOLUMES.lock() {
+        Ok(mut volumes) => { volumes.insert(plugin.to_string(), volume); },
+        Err(e) => warn!("Could not set the sound volume of plugin {}: {}", plugin, e),
+    }
+
+    send(Command::SetVolume { plugin: plugin.to_string(), volume }, false);
+}
+
+/// Stop the plugin's sounds that are playing.
+pub fn stop(plugin: &str) {
+    send(Command::Stop { plugin: plugin.to_string() }, false);
+}
+
+/// Stop the plugin's sounds and forget its volume and files, e.g., when the plugin is unloaded.
+pub fn clear(plugin: &str) {
+    stop(plugin);
+
+    if let Ok(mut volumes) = VOLUMES.lock() {
+        volumes.remove(plugin);
+    }
+
+    if let Ok(mut files) = FILES.lock() {
+        files.remove(plugin);
+    }
+}
+
+fn volume(plugin: &str) -> f32 {
+    match VOLUMES.lock() {
+        Ok(volumes) => volumes.get(plugin).copied().unwrap_or(1.0),
+        Err(_) => 1.0,
+    }
+}
+
+/// Get the content of the sound file, reading it if the plugin didn't play it before.
+fn read(plugin: &str, path: &Path) -> Result<Arc<[u8]>, anyhow::Error> {
+    let mut files = FILES.lock().map_err(|e| anyhow!("Could not get the sound files: {}", e))?;
+    let plugin_files = files.entry(plugin.to_string()).or_default();
+
+    if let Some(content) = plugin_files.get(path) {
+        return Ok(content.clone());
+    }
+
+    let size = fs::metadata(path)?.len();
+    if size > MAX_SOUND_SIZE {
+        bail!("The sound is {} KB, but sounds can have at most {} KB", size / 1024, MAX_SOUND_SIZE / 1024);
+    }
+
+    let content: Arc<[u8]> = fs::read(path)?.into();
+    audit::record_file(plugin, path);
+    plugin_files.insert(path.to_path_buf(), content.clone());
+
+    Ok(content)
+}
+
+/// Send the command to the thread that plays the sounds.
+///
+/// The thread is only started if `start` is set. Otherwise, the command is dropped if no sound was played yet.
+fn send(command: Command, start: bool) {
+    let mut player = match PLAYER.lock() {
+        Ok(player) => player,
+        Err(e) => {
+            warn!("Could not get the sound player: {}", e);
+            return;
+        },
+    };
+
+    if player.is_none() && start {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || run(receiver));
+
+        *player = Some(sender);
+    }
+
+    if let Some(sender) = player.as_ref() {
+        if sender.send(command).is_err() {
+            debug!("The sound player isn't running, dropping the command");
+        }
+    }
+}
+
+/// Play the sounds of the commands until the sender is dropped.
+fn run(receiver: Receiver<Command>) {
+    let (_stream, handle) = match OutputStream::try_default() {
+        Ok(output) => output,
+        Err(e) => {
+            warn!("Could not open the audio output, sounds aren't played: {}", e);
+            return;
+        },
+    };
+    info!("Opened the audio output to play sounds");
+
+    // Sounds that are playing, by the plugin that plays them
+    let mut playing: HashMap<String, Vec<Sink>> = HashMap::new();
+
+    for command in receiver {
+        match command {
+            Command::Play { plugin, sound, volume } => {
+                let sinks = playing.entry(plugin.clone()).or_default();
+                sinks.retain(|sink| !sink.empty());
+
+                if sinks.len() >= MAX_PLAYING {
+                    debug!("Plugin {} plays {} sounds, dropping the sound", plugin, sinks.len());
+                    continue;
+                }
+
+                match Sink::try_new(&handle) {
+                    Ok(sink) => {
+                        sink.set_volume(volume);
+                        sink.append(sound);
+                        sinks.push(sink);
+                    },
+                    Err(e) => warn!("Could not play the sound of plugin {}: {}", plugin, e),
+                }
+            },
+            Command::SetVolume { plugin, volume } => {
+                for sink in playing.get(&plugin).into_iter().flatten() {
+                    sink.set_volume(volume);
+                }
+            },
+            // Dropping the sinks stops their sounds
+            Command::Stop { plugin } => { playing.remove(&plugin); },
+        }
+    }
+}
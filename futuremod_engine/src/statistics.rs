@@ -0,0 +1,165 @@
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::Mutex, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use anyhow::anyhow;
+use futuremod_data::statistics::UsageStatistics;
+use log::*;
+
+use crate::plugins::persistence;
+
+lazy_static! {
+    static ref STATISTICS: Mutex<Statistics> = Mutex::new(Statistics::default());
+}
+
+/// Local statistics of how the mod is used, e.g., the playtime, the completed missions, and how long each plugin was enabled.
+///
+/// Nothing is sent anywhere, users look at the statistics in the GUI.
+#[derive(Default)]
+struct Statistics {
+    usage: UsageStatistics,
+    path: Option<PathBuf>,
+
+    /// Start of the playtime that wasn't added to the statistics yet.
+    playing_since: Option<Instant>,
+    /// Start of the running mission's time that wasn't added to the statistics yet.
+    mission_since: Option<Instant>,
+    /// Start of the time of each enabled plugin that wasn't added to the statistics yet.
+    enabled_since: HashMap<String, Instant>,
+    changed: bool,
+}
+
+impl Statistics {
+    /// Add the time since the last call to the playtime, the running mission's time, and the enabled plugins' time.
+    fn add_elapsed_time(&mut self) {
+        if let Some(since) = self.playing_since.as_mut() {
+            self.usage.playtime += take_seconds(since);
+        }
+        if let Some(since) = self.mission_since.as_mut() {
+            self.usage.mission_time += take_seconds(since);
+        }
+        for (name, since) in self.enabled_since.iter_mut() {
+            self.usage.plugins.entry(name.clone()).or_default().enabled_time += take_seconds(since);
+        }
+
+        self.changed = true;
+    }
+}
+
+/// Whole seconds since the instant. The instant is moved forward by them, so that fractions of a second aren't lost.
+fn take_seconds(since: &mut Instant) -> u64 {
+    let seconds = since.elapsed().as_secs();
+    *since += Duration::from_secs(seconds);
+
+    seconds
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0)
+}
+
+/// Read the statistics and count the session. Must be called when the mod is injected.
+pub fn init(path: &Path) {
+    let mut usage = match persistence::read::<UsageStatistics>(path) {
+        Ok(usage) => usage.unwrap_or_default(),
+        Err(e) => {
+            warn!("Could not read the usage statistics, starting over: {}", e);
+            UsageStatistics::default()
+        },
+    };
+
+    usage.since.get_or_insert_with(now);
+    usage.sessions += 1;
+
+    match STATISTICS.lock() {
+        Ok(mut statistics) => *statistics = Statistics {
+            usage,
+            path: Some(path.to_path_buf()),
+            playing_since: Some(Instant::now()),
+            changed: true,
+            ..Default::default()
+        },
+        Err(e) => warn!("Could not get lock to the usage statistics: {}", e),
+    }
+}
+
+/// Count a started mission and measure its time. Must be called when a mission starts.
+pub fn on_mission_start() {
+    with_statistics(|statistics| {
+        statistics.usage.missions_started += 1;
+        statistics.mission_since = Some(Instant::now());
+        statistics.changed = true;
+    });
+}
+
+/// Count the mission as completed. Must be called when a mission ends.
+pub fn on_mission_end() {
+    with_statistics(|statistics| {
+        if let Some(mut since) = statistics.mission_since.take() {
+            statistics.usage.mission_time += take_seconds(&mut since);
+            statistics.usage.missions_completed += 1;
+            statistics.changed = true;
+        }
+    });
+}
+
+/// Start measuring how long the plugin is enabled. Must be called when the plugin is enabled.
+pub fn on_plugin_enabled(name: &str) {
+    with_statistics(|statistics| {
+        statistics.usage.plugins.entry(name.to_string()).or_default().enabled_count += 1;
+        statistics.enabled_since.entry(name.to_string()).or_insert_with(Instant::now);
+        statistics.changed = true;
+    });
+}
+
+/// Stop measuring how long the plugin is enabled. Must be called when the plugin is disabled.
+pub fn on_plugin_disabled(name: &str) {
+    with_statistics(|statistics| {
+        if let Some(mut since) = statistics.enabled_since.remove(name) {
+            statistics.usage.plugins.entry(name.to_string()).or_default().enabled_time += take_seconds(&mut since);
+            statistics.changed = true;
+        }
+    });
+}
+
+/// Get the statistics, including the time of the running session.
+pub fn get() -> Result<UsageStatistics, anyhow::Error> {
+    let mut statistics = STATISTICS.lock().map_err(|e| anyhow!("could not get lock to the usage statistics: {}", e))?;
+    statistics.add_elapsed_time();
+
+    Ok(statistics.usage.clone())
+}
+
+/// Delete the statistics and start over. The running session, mission, and enabled plugins are measured from now on.
+pub fn reset() -> Result<(), anyhow::Error> {
+    {
+        let mut statistics = STATISTICS.lock().map_err(|e| anyhow!("could not get lock to the usage statistics: {}", e))?;
+        statistics.add_elapsed_time();
+        statistics.usage = UsageStatistics { since: Some(now()), ..Default::default() };
+    }
+
+    info!("Reset the usage statistics");
+    persist()
+}
+
+/// Write the statistics if they changed.
+pub fn persist() -> Result<(), anyhow::Error> {
+    let mut statistics = STATISTICS.lock().map_err(|e| anyhow!("could not get lock to the usage statistics: {}", e))?;
+    statistics.add_elapsed_time();
+
+    let path = match (&statistics.path, statistics.changed) {
+        (Some(path), true) => path.clone(),
+        _ => return Ok(()),
+    };
+
+    let content = serde_json::to_vec_pretty(&statistics.usage).map_err(|e| anyhow!("could not serialize the usage statistics: {}", e))?;
+    persistence::write(&path, &content)?;
+    statistics.changed = false;
+
+    Ok(())
+}
+
+fn with_statistics(f: impl FnOnce(&mut Statistics)) {
+    match STATISTICS.lock() {
+        Ok(mut statistics) => f(&mut statistics),
+        Err(e) => warn!("Could not get lock to the usage statistics: {}", e),
+    }
+}
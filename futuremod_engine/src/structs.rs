@@ -0,0 +1,253 @@
+use std::{ffi::c_void, mem::{self, offset_of}, sync::Mutex};
+
+use anyhow::{anyhow, bail};
+use futuremod_data::structs::{StructDefinition, StructField, StructFieldValue};
+use windows::Win32::System::Memory::{VirtualQuery, MEMORY_BASIC_INFORMATION, MEM_COMMIT, PAGE_GUARD, PAGE_NOACCESS, PAGE_PROTECTION_FLAGS};
+
+use crate::{api::graphics::RenderItem, futurecop::{Entity, Player, PlayerEntity, PlayerHealth, Position}};
+
+/// Strings are read up to their terminator or this many bytes.
+const MAX_STRING_LENGTH: u32 = 64;
+
+lazy_static! {
+    /// Definitions plugins registered, in the order they were registered.
+    static ref REGISTERED: Mutex<Vec<StructDefinition>> = Mutex::new(Vec::new());
+}
+
+macro_rules! field {
+    ($name:literal, $offset:expr, $field_type:literal) => {
+        StructField { name: $name.to_string(), offset: ($offset) as u32, field_type: $field_type.to_string() }
+    };
+}
+
+/// Definitions of the game structs the mod knows.
+///
+/// Offsets are taken from the mod's own definitions, so the catalog stays in sync with them.
+/// Plugins get the same definitions from the `structs` library.
+pub fn catalog() -> Vec<StructDefinition> {
+    let health = offset_of!(PlayerEntity, health);
+    let position = offset_of!(Entity, position);
+
+    vec![
+        StructDefinition {
+            name: "PlayerEntity".to_string(),
+            description: "Entity of a player, e.g., at the address of `game.getPlayer`.".to_string(),
+            plugin: None,
+            fields: vec![
+                field!("id", offset_of!(PlayerEntity, id), "uint"),
+                field!("health", health + offset_of!(PlayerHealth, health), "short"),
+                field!("maxHealth", health + offset_of!(PlayerHealth, max_health), "short"),
+                field!("lowHealthThreshold", health + offset_of!(PlayerHealth, low_health_threshold), "uint"),
+                field!("positionX", offset_of!(PlayerEntity, position_x), "int"),
+                field!("positionY", offset_of!(PlayerEntity, position_y), "int"),
+                field!("positionZ", offset_of!(PlayerEntity, position_z), "int"),
+                field!("rotation", offset_of!(PlayerEntity, rotation), "int"),
+                field!("player", offset_of!(PlayerEntity, player), "uint"),
+                field!("idleAnimationPlays", offset_of!(PlayerEntity, idle_animation_plays), "uint"),
+                field!("idleTimer", offset_of!(PlayerEntity, idle_timer), "ubyte"),
+            ],
+        },
+        StructDefinition {
+            name: "Player".to_string(),
+            description: "Player data a player entity's `player` field points to.".to_string(),
+            plugin: None,
+            fields: vec![
+                field!("enemiesKilled", offset_of!(Player, enemies_killed), "ushort"),
+                field!("deaths", offset_of!(Player, deaths), "ushort"),
+                field!("currentAction", offset_of!(Player, current_action), "uint"),
+                field!("movementMode", offset_of!(Player, movement_mode), "uint"),
+                field!("currentTargetType", offset_of!(Player, current_target_type), "uint"),
+                field!("currentTarget", offset_of!(Player, current_target), "uint"),
+                field!("lastTarget", offset_of!(Player, last_target), "uint"),
+                field!("gunWeaponTimeout", offset_of!(Player, gun_weapon_timeout), "uint"),
+                field!("heavyWeaponTimeout", offset_of!(Player, heavy_weapon_timeout), "uint"),
+                field!("specialWeaponTimeout", offset_of!(Player, special_weapon_timeout), "uint"),
+                field!("gunWeaponAmmo", offset_of!(Player, gun_weapon_ammo), "ushort"),
+                field!("heavyWeaponAmmo", offset_of!(Player, heavy_weapon_ammo), "ushort"),
+                field!("specialWeaponAmmo", offset_of!(Player, special_weapon_ammo), "ushort"),
+                field!("selectedGunWeapon", offset_of!(Player, selected_gun_weapon), "ubyte"),
+                field!("selectedHeavyWeapon", offset_of!(Player, selected_heavy_weapon), "ubyte"),
+                field!("selectedSpecialWeapon", offset_of!(Player, selected_special_weapon), "ubyte"),
+                field!("cameraMode", offset_of!(Player, camera_mode), "uint"),
+                field!("playerNumber", offset_of!(Player, player_number), "ubyte"),
+                field!("accelerationX", offset_of!(Player, acceleration_x), "int"),
+                field!("accelerationY", offset_of!(Player, acceleration_y), "int"),
+                field!("accelerationZ", offset_of!(Player, acceleration_z), "int"),
+            ],
+        },
+        StructDefinition {
+            name: "Entity".to_string(),
+            description: "Entity of the game's entity list, e.g., an enemy or a building.".to_string(),
+            plugin: None,
+            fields: vec![
+                field!("nextEntity", offset_of!(Entity, next_entity), "uint"),
+                field!("updateMethod", offset_of!(Entity, update_method), "uint"),
+                field!("id", offset_of!(Entity, id), "uint"),
+                field!("behaviorType", offset_of!(Entity, behavior_type), "ushort"),
+                field!("mapMarker", offset_of!(Entity, map_marker), "ubyte"),
+                field!("modelDataRef", offset_of!(Entity, model_data_ref), "uint"),
+                field!("positionX", position + offset_of!(Position, x), "int"),
+                field!("positionY", position + offset_of!(Position, y), "int"),
+                field!("positionZ", position + offset_of!(Position, z), "int"),
+            ],
+        },
+        StructDefinition {
+            name: "RenderItem".to_string(),
+            description: "Sprite or box the game renders on the screen, e.g., an element of the HUD.".to_string(),
+            plugin: None,
+            fields: vec![
+                field!("itemType", offset_of!(RenderItem, item_type), "ubyte"),
+                field!("spriteOffsetX", offset_of!(RenderItem, sprite_offset_x), "ubyte"),
+                field!("spriteOffsetY", offset_of!(RenderItem, sprite_offset_y), "ubyte"),
+                field!("spriteWidth", offset_of!(RenderItem, sprite_width), "ubyte"),
+                field!("spriteHeight", offset_of!(RenderItem, sprite_height), "ubyte"),
+                field!("colorRed", offset_of!(RenderItem, color_red), "ubyte"),
+                field!("colorGreen", offset_of!(RenderItem, color_green), "ubyte"),
+                field!("colorBlue", offset_of!(RenderItem, color_blue), "ubyte"),
+                field!("screenPositionX", offset_of!(RenderItem, screen_pos_x), "ushort"),
+                field!("screenPositionY", offset_of!(RenderItem, screen_pos_y), "ushort"),
+                field!("boxWidth", offset_of!(RenderItem, box_width), "ushort"),
+                field!("boxHeight", offset_of!(RenderItem, box_height), "ushort"),
+            ],
+        },
+    ]
+}
+
+/// Get the definitions of the catalog followed by the definitions plugins registered.
+pub fn get_definitions() -> Vec<StructDefinition> {
+    let mut definitions = catalog();
+
+    if let Ok(registered) = REGISTERED.lock() {
+        definitions.extend(registered.iter().cloned());
+    }
+
+    definitions
+}
+
+/// Register the plugin's definition to show it in the GUI's struct viewer.
+///
+/// Replaces the plugin's definition with the same name.
+pub fn register(plugin: &str, definition: StructDefinition) -> Result<(), anyhow::Error> {
+    if catalog().iter().any(|known| known.name == definition.name) {
+        bail!("'{}' is the name of a known game struct", definition.name);
+    }
+
+    let mut registered = REGISTERED.lock().map_err(|e| anyhow!("could not get lock to the struct definitions: {}", e))?;
+
+    if registered.iter().any(|known| known.name == definition.name && known.plugin.as_deref() != Some(plugin)) {
+        bail!("another plugin already registered a struct named '{}'", definition.name);
+    }
+
+    let definition = StructDefinition { plugin: Some(plugin.to_string()), ..definition };
+    match registered.iter_mut().find(|known| known.name == definition.name) {
+        Some(known) => *known = definition,
+        None => registered.push(definition),
+    }
+
+    Ok(())
+}
+
+/// Remove the definitions the plugin registered, e.g., before it is loaded again.
+pub fn clear(plugin: &str) {
+    if let Ok(mut registered) = REGISTERED.lock() {
+        registered.retain(|definition| definition.plugin.as_deref() != Some(plugin));
+    }
+}
+
+/// Read the fields of the struct instance at the address.
+///
+/// Fields in memory that isn't readable have no value instead of crashing the game.
+pub fn read(definition: &str, address: u32) -> Result<Vec<StructFieldValue>, anyhow::Error> {
+    let definition = get_definitions()
+        .into_iter()
+        .find(|known| known.name == definition)
+        .ok_or(anyhow!("no struct named '{}' is known", definition))?;
+
+    let values = definition
+        .fields
+        .into_iter()
+        .map(|field| {
+            let bytes = field_size(&field.field_type)
+                .and_then(|size| read_bytes(address.wrapping_add(field.offset), size));
+
+            StructFieldValue {
+                value: bytes.as_deref().and_then(|bytes| describe(&field.field_type, bytes)),
+                hex: bytes.as_deref().map(to_hex),
+                name: field.name,
+                offset: field.offset,
+                field_type: field.field_type,
+            }
+        })
+        .collect();
+
+    Ok(values)
+}
+
+/// Size of a field of the type in bytes, or `None` if fields of the type can't be read.
+fn field_size(field_type: &str) -> Option<u32> {
+    match field_type {
+        "int" | "uint" | "float" | "pointer" | "struct" => Some(4),
+        "double" => Some(8),
+        "short" | "ushort" => Some(2),
+        "byte" | "ubyte" => Some(1),
+        "string" => Some(MAX_STRING_LENGTH),
+        _ => None,
+    }
+}
+
+fn describe(field_type: &str, bytes: &[u8]) -> Option<String> {
+    let value = match field_type {
+        "int" => i32::from_le_bytes(bytes.try_into().ok()?).to_string(),
+        "uint" => {
+            let value = u32::from_le_bytes(bytes.try_into().ok()?);
+            format!("{} ({:#010x})", value, value)
+        },
+        "float" => f32::from_le_bytes(bytes.try_into().ok()?).to_string(),
+        "double" => f64::from_le_bytes(bytes.try_into().ok()?).to_string(),
+        "pointer" | "struct" => format!("{:#010x}", u32::from_le_bytes(bytes.try_into().ok()?)),
+        "short" => i16::from_le_bytes(bytes.try_into().ok()?).to_string(),
+        "ushort" => u16::from_le_bytes(bytes.try_into().ok()?).to_string(),
+        "byte" => (bytes[0] as i8).to_string(),
+        "ubyte" => bytes[0].to_string(),
+        "string" => {
+            let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+            format!("{:?}", String::from_utf8_lossy(&bytes[..end]))
+        },
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<String>>().join(" ")
+}
+
+/// Read the bytes at the address, or `None` if any of them isn't readable.
+fn read_bytes(address: u32, size: u32) -> Option<Vec<u8>> {
+    if address == 0 || address.checked_add(size).is_none() || !is_readable(address, size) {
+        return None;
+    }
+
+    let bytes = unsafe { std::slice::from_raw_parts(address as *const u8, size as usize) };
+    Some(bytes.to_vec())
+}
+
+/// Whether the memory from the address on is committed and readable.
+fn is_readable(address: u32, size: u32) -> bool {
+    let end = address as u64 + size as u64;
+    let mut current = address as u64;
+
+    while current < end {
+        let mut info = MEMORY_BASIC_INFORMATION::default();
+        let queried = unsafe { VirtualQuery(Some(current as *const c_void), &mut info, mem::size_of::<MEMORY_BASIC_INFORMATION>()) };
+
+        if queried == 0 || info.State != MEM_COMMIT || info.Protect & (PAGE_NOACCESS | PAGE_GUARD) != PAGE_PROTECTION_FLAGS(0) {
+            return false;
+        }
+
+        current = info.BaseAddress as u64 + info.RegionSize as u64;
+    }
+
+    true
+}
@@ -0,0 +1,173 @@
+use std::{ffi::c_void, sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering}, thread, time::{Duration, Instant}};
+
+use anyhow::{anyhow, bail};
+use futuremod_hook::native::{free_unused_memory, unset_all_hooks, wait_for_hook_calls};
+use log::*;
+use windows::Win32::{Foundation::{CloseHandle, HINSTANCE, HMODULE}, System::{LibraryLoader::FreeLibraryAndExitThread, Threading::{CreateThread, THREAD_CREATE_RUN_IMMEDIATELY}}};
+
+use crate::{borderless, crash, debugger, game_exit, game_input, plugins::plugin_manager::GlobalPluginManager, remapping, server};
+
+/// Time hook functions, e.g., a frame's `onUpdate` of all plugins, have to return after their hooks were removed.
+const HOOK_CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time background threads have to stop. Longer than the timeout of the plugins' network requests.
+const WORKER_TIMEOUT: Duration = Duration::from_secs(12);
+
+/// Time a thread needs to leave a hook's trampoline after its hook function returned.
+const TRAMPOLINE_GRACE_PERIOD: Duration = Duration::from_millis(100);
+
+/// Interval in which sleeping background threads check whether the teardown was requested.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Set once the teardown starts. The engine can't be used anymore afterwards.
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Number of the engine's background threads that still run, see [`Worker`].
+static WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Handle of the engine's DLL, to unload it when the engine is ejected.
+static MODULE: AtomicIsize = AtomicIsize::new(0);
+
+/// Marks that a background thread of the engine runs, for as long as it is alive.
+///
+/// The teardown waits until all workers were dropped, as the threads would otherwise run code of the ejected engine.
+pub struct Worker(());
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        WORKERS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Register the calling thread as a background thread of the engine.
+///
+/// Must be kept alive until the thread's function returns.
+pub fn worker() -> Worker {
+    WORKERS.fetch_add(1, Ordering::SeqCst);
+    Worker(())
+}
+
+/// Whether the engine is torn down. Background threads must stop once it is.
+pub fn is_requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Remember the handle of the engine's DLL. Must be called when the DLL is attached.
+pub fn set_module(module: HINSTANCE) {
+    MODULE.store(module.0, Ordering::SeqCst);
+}
+
+/// Sleep for the duration, but wake up early when the engine is torn down.
+///
+/// Returns `false` if the engine is torn down, so that background threads know to stop.
+pub fn sleep(duration: Duration) -> bool {
+    let start = Instant::now();
+
+    while !is_requested() {
+        let remaining = duration.saturating_sub(start.elapsed());
+        if remaining.is_zero() {
+            return true;
+        }
+
+        thread::sleep(remaining.min(POLL_INTERVAL));
+    }
+
+    false
+}
+
+/// Tear down the engine, so that it can be ejected from the game and injected again without restarting the game.
+///
+/// Unloads all plugins, restores the preludes of all hooked functions, stops the server and the engine's background
+/// threads, and frees the hooks' trampolines once no thread can be inside them anymore.
+/// Only the first call tears down the engine, later calls do nothing.
+/// Returns an error if hook functions or background threads still ran after their timeout. The engine must not be
+/// ejected then, since threads would continue in its memory.
+pub fn teardown() -> Result<(), anyhow::Error> {
+    if REQUESTED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    info!("Tearing down the engine");
+
+    // The game loop must not block in the debugger, since the plugin manager is only available between frames
+    debugger::resume();
+
+    // Plugins are unloaded first, so that they remove their hooks and nothing calls into them anymore
+    let result = GlobalPluginManager::with_plugin_manager_mut(|manager| {
+        manager.persist();
+        manager.unload_all_plugins();
+        Ok(())
+    });
+    if let Err(e) = result {
+        warn!("Could not unload the plugins: {}", e);
+    }
+
+    let removed = unsafe { unset_all_hooks() };
+    debug!("Removed {} hooks", removed);
+    borderless::restore();
+    game_exit::restore();
+    game_input::restore();
+
+    server::stop_server();
+    remapping::stop_keyboard_hook();
+
+    if !wait_for_hook_calls(HOOK_CALL_TIMEOUT) {
+        bail!("hook functions still run after {} seconds, their trampolines can't be freed", HOOK_CALL_TIMEOUT.as_secs());
+    }
+
+    thread::sleep(TRAMPOLINE_GRACE_PERIOD);
+    let freed = unsafe { free_unused_memory() };
+    debug!("Freed {} trampolines and links of hooks", freed);
+
+    let start = Instant::now();
+    while WORKERS.load(Ordering::SeqCst) > 0 {
+        if start.elapsed() > WORKER_TIMEOUT {
+            bail!("{} background threads still run after {} seconds", WORKERS.load(Ordering::SeqCst), WORKER_TIMEOUT.as_secs());
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    // Last, so that crashes during the teardown are still reported
+    crash::uninstall();
+
+    info!("Tore down the engine");
+
+    Ok(())
+}
+
+/// Tear down the engine and unload its DLL, so that a rebuilt engine can be injected without restarting the game.
+///
+/// The engine is ejected in a new thread, as the teardown waits for the server's thread, which usually requests the
+/// ejection. If the teardown fails, the DLL stays loaded, since threads might still run in its memory.
+pub fn eject() -> Result<(), anyhow::Error> {
+    if is_requested() {
+        bail!("the engine is already torn down");
+    }
+    if MODULE.load(Ordering::SeqCst) == 0 {
+        bail!("the handle of the engine's DLL is unknown");
+    }
+
+    info!("Ejecting the engine");
+
+    unsafe {
+        let thread = CreateThread(None, 0, Some(eject_thread), None, THREAD_CREATE_RUN_IMMEDIATELY, None)
+            .map_err(|e| anyhow!("could not start the thread that ejects the engine: {}", e))?;
+        let _ = CloseHandle(thread);
+    }
+
+    Ok(())
+}
+
+unsafe extern "system" fn eject_thread(_: *mut c_void) -> u32 {
+    if let Err(e) = teardown() {
+        error!("Could not tear down the engine, it stays loaded: {}", e);
+        return 1;
+    }
+
+    // Background threads drop their worker right before they return from the engine's functions
+    thread::sleep(TRAMPOLINE_GRACE_PERIOD);
+
+    info!("Unloading the engine");
+    FreeLibraryAndExitThread(HMODULE(MODULE.load(Ordering::SeqCst)), 0);
+}
@@ -0,0 +1,151 @@
+use std::{fs, io, path::{Path, PathBuf}, sync::OnceLock, time::Duration};
+
+use futuremod_data::diagnostics::TemporaryDirectoryUsage;
+use log::*;
+use rand::distributions::{Alphanumeric, DistString};
+use walkdir::WalkDir;
+
+/// Name of the directory in the system's temporary directory that is used if no directory is configured.
+const DEFAULT_DIRECTORY_NAME: &str = "fcop";
+
+/// Length of the random names of artifacts.
+const NAME_LENGTH: usize = 16;
+
+/// Artifacts older than this are left over. Younger ones might belong to an install of another running game.
+const STALE_AGE: Duration = Duration::from_secs(60 * 60);
+
+static DIRECTORY: OnceLock<PathBuf> = OnceLock::new();
+
+/// Set the temporary directory, the system's temporary directory if `None`. Must be called when the mod is injected.
+pub fn init(directory: &Option<String>) {
+    let directory = match directory {
+        Some(directory) => PathBuf::from(directory),
+        None => default_directory(),
+    };
+
+    info!("Storing temporary files in '{}'", directory.display());
+
+    if DIRECTORY.set(directory).is_err() {
+        warn!("Temporary directory was already set");
+    }
+}
+
+fn default_directory() -> PathBuf {
+    std::env::temp_dir().join(DEFAULT_DIRECTORY_NAME)
+}
+
+pub fn directory() -> PathBuf {
+    DIRECTORY.get().cloned().unwrap_or_else(default_directory)
+}
+
+/// Artifact in the temporary directory, which is deleted together with the folder it was extracted to when dropped.
+///
+/// Artifacts are the uploaded plugin packages and backups of plugin installs. They are dropped when the request that
+/// created them finishes, whether it succeeded or not. Artifacts that are left over, e.g., because the game exited
+/// during an install, are deleted by [`cleanup`] when the mod is injected.
+#[derive(Debug)]
+pub struct Artifact {
+    path: PathBuf,
+}
+
+impl Artifact {
+    /// Reserve a file with a random name and the extension in the temporary directory, which is created if necessary.
+    pub fn new(extension: &str) -> Result<Artifact, io::Error> {
+        let directory = directory();
+        fs::create_dir_all(&directory)?;
+
+        let mut path = directory.join(Alphanumeric.sample_string(&mut rand::thread_rng(), NAME_LENGTH));
+        path.set_extension(extension);
+
+        Ok(Artifact { path })
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Folder the artifact is extracted to, the artifact's path without its extension.
+    pub fn folder(&self) -> PathBuf {
+        self.path.with_extension("")
+    }
+}
+
+impl Drop for Artifact {
+    fn drop(&mut self) {
+        for path in [self.path.clone(), self.folder()] {
+            if let Err(e) = remove(&path) {
+                warn!("Could not delete the temporary file '{}': {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn remove(path: &Path) -> Result<(), io::Error> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else if path.exists() {
+        fs::remove_file(path)
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether the file name is one of an [`Artifact`], so that other files in a configured directory are never deleted.
+fn is_artifact(path: &Path) -> bool {
+    let name = match path.file_stem().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+
+    name.len() == NAME_LENGTH && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Delete artifacts that were left over by previous installs.
+///
+/// Returns the number of deleted artifacts.
+pub fn cleanup() -> Result<usize, io::Error> {
+    let directory = directory();
+    if !directory.is_dir() {
+        return Ok(0);
+    }
+
+    let mut deleted = 0;
+
+    for entry in fs::read_dir(&directory)? {
+        let path = entry?.path();
+        if !is_artifact(&path) {
+            continue;
+        }
+
+        let is_stale = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .map(|modified| modified.elapsed().map(|age| age >= STALE_AGE).unwrap_or(false))
+            .unwrap_or(false);
+        if !is_stale {
+            continue;
+        }
+
+        match remove(&path) {
+            Ok(()) => deleted += 1,
+            Err(e) => warn!("Could not delete the stale temporary file '{}': {}", path.display(), e),
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Disk usage of the temporary directory.
+pub fn usage() -> TemporaryDirectoryUsage {
+    let directory = directory();
+
+    let entries = fs::read_dir(&directory).map(|entries| entries.count()).unwrap_or_default();
+    let size = WalkDir::new(&directory)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum();
+
+    TemporaryDirectoryUsage { path: directory.display().to_string(), size, entries }
+}
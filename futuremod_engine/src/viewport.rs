@@ -0,0 +1,222 @@
+use std::{collections::HashMap, sync::{atomic::{AtomicU32, Ordering}, Mutex, RwLock}};
+
+use log::*;
+use serde::Deserialize;
+use windows::Win32::{Foundation::{HWND, POINT, RECT}, Graphics::Gdi::ScreenToClient, UI::WindowsAndMessaging::GetClientRect};
+
+use crate::{config::RenderResolution, futurecop::{global::GetterSetter, state::FUTURE_COP}};
+
+/// Size of the screen space the game renders text and rectangles in without a resolution patch.
+pub const ORIGINAL_WIDTH: u32 = 320;
+pub const ORIGINAL_HEIGHT: u32 = 240;
+
+/// Size of the screen space the game currently renders text and rectangles in.
+static RENDER_WIDTH: AtomicU32 = AtomicU32::new(ORIGINAL_WIDTH);
+static RENDER_HEIGHT: AtomicU32 = AtomicU32::new(ORIGINAL_HEIGHT);
+
+lazy_static! {
+    static ref RESOLUTION: RwLock<RenderResolution> = RwLock::new(RenderResolution::Original);
+
+    /// Layouts plugins chose by plugin. Plugins without a layout use the default layout.
+    static ref LAYOUTS: Mutex<HashMap<String, Layout>> = Mutex::new(HashMap::new());
+}
+
+/// Point of the screen a layout sticks to when the screen's aspect ratio differs from the layout's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Fraction of the unused space left of and above the layout.
+    fn factors(&self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+/// Virtual coordinate system of a plugin's HUD.
+///
+/// Plugins place their HUD in their design resolution. The layout is scaled to fit the screen while
+/// keeping its aspect ratio and placed at its anchor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub width: u32,
+    pub height: u32,
+    pub anchor: Anchor,
+}
+
+impl Default for Layout {
+    /// The game's original resolution, which maps coordinates one to one without a resolution patch.
+    fn default() -> Self {
+        Layout { width: ORIGINAL_WIDTH, height: ORIGINAL_HEIGHT, anchor: Anchor::TopLeft }
+    }
+}
+
+impl Layout {
+    /// Scale of the layout on the screen and its offset from the screen's top left corner.
+    fn transform(&self) -> (f32, f32, f32) {
+        let (screen_width, screen_height) = screen_size();
+        let (screen_width, screen_height) = (screen_width as f32, screen_height as f32);
+
+        let scale = (screen_width / self.width.max(1) as f32).min(screen_height / self.height.max(1) as f32);
+        let (anchor_x, anchor_y) = self.anchor.factors();
+
+        let offset_x = (screen_width - self.width as f32 * scale) * anchor_x;
+        let offset_y = (screen_height - self.height as f32 * scale) * anchor_y;
+
+        (scale, offset_x, offset_y)
+    }
+
+    /// Convert the position in the layout into the position on the screen.
+    pub fn to_screen(&self, x: f32, y: f32) -> (u32, u32) {
+        let (scale, offset_x, offset_y) = self.transform();
+
+        (to_pixels(offset_x + x * scale), to_pixels(offset_y + y * scale))
+    }
+
+    /// Convert the size in the layout into the size on the screen.
+    pub fn to_screen_size(&self, width: f32, height: f32) -> (u32, u32) {
+        let (scale, _, _) = self.transform();
+
+        (to_pixels(width * scale), to_pixels(height * scale))
+    }
+}
+
+fn to_pixels(value: f32) -> u32 {
+    value.round().max(0.0) as u32
+}
+
+/// Configure the size of the screen space the game renders in. Must be called when the mod is injected.
+pub fn init(resolution: &RenderResolution) {
+    if let RenderResolution::Fixed { width, height } = resolution {
+        if *width == 0 || *height == 0 {
+            warn!("Invalid render resolution {}x{}, using the original resolution", width, height);
+            return;
+        }
+    }
+
+    match RESOLUTION.write() {
+        Ok(mut current) => *current = resolution.clone(),
+        Err(e) => warn!("Could not get lock to the render resolution: {}", e),
+    }
+
+    on_frame();
+}
+
+/// Update the size of the screen space, e.g., after the game's window moved to a monitor with another DPI.
+///
+/// Must be called once per frame of the mission's game loop before anything is rendered.
+pub fn on_frame() {
+    let resolution = match RESOLUTION.read() {
+        Ok(resolution) => resolution.clone(),
+        Err(_) => return,
+    };
+
+    let (width, height) = match resolution {
+        RenderResolution::Original => (ORIGINAL_WIDTH, ORIGINAL_HEIGHT),
+        RenderResolution::Fixed { width, height } => (width, height),
+        RenderResolution::Window => match window_size() {
+            Some(size) => size,
+            // Keep the last size, e.g., while the window is minimized
+            None => return,
+        },
+    };
+
+    RENDER_WIDTH.store(width, Ordering::Relaxed);
+    RENDER_HEIGHT.store(height, Ordering::Relaxed);
+}
+
+/// Size of the game window's client area.
+///
+/// The size is in the game's DPI awareness, which is the size the game renders at.
+/// If Windows scales the game's window on a high-DPI monitor, this is the size before scaling.
+fn window_size() -> Option<(u32, u32)> {
+    let main_window = unsafe { *FUTURE_COP.handles.main_window.get() };
+    if main_window == 0 {
+        return None;
+    }
+
+    let mut rect = RECT::default();
+    unsafe { GetClientRect(HWND(main_window as isize), &mut rect).ok()? };
+
+    let width = rect.right - rect.left;
+    let height = rect.bottom - rect.top;
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+
+    Some((width as u32, height as u32))
+}
+
+/// Convert a position on the desktop, e.g., the mouse's, into the screen space the game renders in.
+///
+/// Returns `None` if the position is outside of the game window's client area.
+pub fn from_desktop(x: i32, y: i32) -> Option<(f32, f32)> {
+    let main_window = unsafe { *FUTURE_COP.handles.main_window.get() };
+    if main_window == 0 {
+        return None;
+    }
+
+    let mut point = POINT { x, y };
+    unsafe { ScreenToClient(HWND(main_window as isize), &mut point).ok()? };
+
+    let (window_width, window_height) = window_size()?;
+    if point.x < 0 || point.y < 0 || point.x >= window_width as i32 || point.y >= window_height as i32 {
+        return None;
+    }
+
+    let (width, height) = screen_size();
+
+    Some((
+        point.x as f32 * width as f32 / window_width as f32,
+        point.y as f32 * height as f32 / window_height as f32,
+    ))
+}
+
+/// Size of the screen space the game currently renders text and rectangles in.
+pub fn screen_size() -> (u32, u32) {
+    (RENDER_WIDTH.load(Ordering::Relaxed), RENDER_HEIGHT.load(Ordering::Relaxed))
+}
+
+/// Get the layout of the plugin's HUD.
+pub fn get_layout(plugin: &str) -> Layout {
+    LAYOUTS
+        .lock()
+        .ok()
+        .and_then(|layouts| layouts.get(plugin).copied())
+        .unwrap_or_default()
+}
+
+/// Set the layout of the plugin's HUD.
+pub fn set_layout(plugin: &str, layout: Layout) {
+    if let Ok(mut layouts) = LAYOUTS.lock() {
+        layouts.insert(plugin.to_string(), layout);
+    }
+}
+
+/// Reset the plugin's layout to the default layout, e.g., before the plugin is loaded again.
+pub fn clear(plugin: &str) {
+    if let Ok(mut layouts) = LAYOUTS.lock() {
+        layouts.remove(plugin);
+    }
+}
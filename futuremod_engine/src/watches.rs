@@ -0,0 +1,173 @@
+use std::{fs, path::{Path, PathBuf}, sync::Mutex, time::{Duration, Instant}};
+
+use anyhow::{anyhow, bail};
+use futuremod_data::watch::WatchValue;
+use log::*;
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+use crate::plugins::PluginManager;
+
+/// Time between two evaluations of the watch expressions.
+const EVALUATION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watch expressions that can be set at the same time.
+const MAX_WATCHES: usize = 32;
+
+const MAX_EXPRESSION_LENGTH: usize = 500;
+
+/// Longer descriptions of values are cut off, e.g., of large tables.
+const MAX_VALUE_LENGTH: usize = 200;
+
+/// Evaluating an expression is aborted after this time.
+///
+/// Expressions are evaluated in the game loop, so a slow expression would freeze the game.
+const MAX_EVALUATION_TIME: Duration = Duration::from_millis(10);
+
+lazy_static! {
+    static ref WATCHES: Mutex<Watches> = Mutex::new(Watches { expressions: Vec::new(), values: Vec::new(), path: None, last_evaluation: None });
+
+    /// Publishes the values of every evaluation to the GUIs that watch them.
+    static ref PUBLISHER: Sender<Vec<WatchValue>> = broadcast::channel(16).0;
+}
+
+/// Lua expressions the developer watches and their latest values.
+struct Watches {
+    /// Persisted, so they survive restarts of the game.
+    expressions: Vec<String>,
+    values: Vec<WatchValue>,
+    path: Option<PathBuf>,
+    last_evaluation: Option<Instant>,
+}
+
+impl Watches {
+    fn write_to_file(&self) -> Result<(), anyhow::Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let content = serde_json::to_string_pretty(&self.expressions).map_err(|e| anyhow!("could not serialize watch expressions: {}", e))?;
+
+        fs::write(path, content).map_err(|e| anyhow!("could not write watch expressions: {}", e))
+    }
+}
+
+/// Read the persisted watch expressions. Must be called when the mod is injected.
+pub fn init(path: &Path) {
+    debug!("Reading watch expressions from '{}'", path.display());
+
+    let expressions = match fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(expressions) => expressions,
+            Err(e) => {
+                warn!("Could not parse the watch expressions, starting without watches: {}", e);
+                Vec::new()
+            },
+        },
+        Err(_) => Vec::new(),
+    };
+
+    match WATCHES.lock() {
+        Ok(mut watches) => {
+            watches.expressions = expressions;
+            watches.path = Some(path.to_path_buf());
+        },
+        Err(e) => warn!("Could not get lock to the watch expressions: {}", e),
+    }
+}
+
+pub fn get_expressions() -> Vec<String> {
+    WATCHES.lock().map(|watches| watches.expressions.clone()).unwrap_or_default()
+}
+
+/// Replace the watch expressions and persist them. They are evaluated from the next evaluation on.
+pub fn set_expressions(expressions: Vec<String>) -> Result<(), anyhow::Error> {
+    if expressions.len() > MAX_WATCHES {
+        bail!("at most {} expressions can be watched", MAX_WATCHES);
+    }
+
+    let expressions: Vec<String> = expressions.iter().map(|expression| expression.trim().to_string()).collect();
+    for expression in expressions.iter() {
+        if expression.is_empty() {
+            bail!("expressions must not be empty");
+        }
+        if expression.len() > MAX_EXPRESSION_LENGTH {
+            bail!("expressions must not be longer than {} characters", MAX_EXPRESSION_LENGTH);
+        }
+    }
+
+    let mut watches = WATCHES.lock().map_err(|e| anyhow!("could not get lock to the watch expressions: {}", e))?;
+
+    info!("Watching {} expressions", expressions.len());
+    watches.expressions = expressions;
+    watches.values.clear();
+    // Evaluate the new expressions right away
+    watches.last_evaluation = None;
+
+    watches.write_to_file()
+}
+
+/// Values of the latest evaluation.
+pub fn get_values() -> Vec<WatchValue> {
+    WATCHES.lock().map(|watches| watches.values.clone()).unwrap_or_default()
+}
+
+/// Receive the values of every following evaluation.
+pub fn subscribe() -> Receiver<Vec<WatchValue>> {
+    PUBLISHER.subscribe()
+}
+
+/// Evaluate the watch expressions if a second passed since the last evaluation and publish their values.
+///
+/// Must be called once per frame of the mission's game loop. Thus, watches are only evaluated during missions.
+pub fn on_frame(plugin_manager: &mut PluginManager) {
+    let expressions = {
+        let mut watches = match WATCHES.lock() {
+            Ok(watches) => watches,
+            Err(_) => return,
+        };
+
+        if watches.expressions.is_empty() || watches.last_evaluation.is_some_and(|last_evaluation| last_evaluation.elapsed() < EVALUATION_INTERVAL) {
+            return;
+        }
+
+        watches.last_evaluation = Some(Instant::now());
+        watches.expressions.clone()
+    };
+
+    // Evaluate without holding the lock, expressions may log
+    let values: Vec<WatchValue> = expressions
+        .into_iter()
+        .map(|expression| match plugin_manager.evaluate(&expression, MAX_EVALUATION_TIME) {
+            Ok(value) => WatchValue { expression, value: Some(truncate(value)), error: None },
+            Err(e) => WatchValue { expression, value: None, error: Some(truncate(e.to_string())) },
+        })
+        .collect();
+
+    if let Ok(mut watches) = WATCHES.lock() {
+        // The expressions changed while evaluating, the values belong to the old expressions
+        if !watches.expressions.iter().eq(values.iter().map(|value| &value.expression)) {
+            return;
+        }
+
+        watches.values = values.clone();
+    }
+
+    // Fails if no GUI watches the values
+    let _ = PUBLISHER.send(values);
+}
+
+fn truncate(mut description: String) -> String {
+    if description.len() <= MAX_VALUE_LENGTH {
+        return description;
+    }
+
+    let mut end = MAX_VALUE_LENGTH;
+    while !description.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    description.truncate(end);
+    description.push_str("...");
+    description
+}
@@ -0,0 +1,542 @@
+use std::{collections::HashMap, sync::{atomic::{AtomicBool, Ordering}, Mutex}};
+
+use anyhow::anyhow;
+use device_query::{DeviceQuery, DeviceState};
+use log::*;
+use windows::Win32::UI::Input::KeyboardAndMouse::{VIRTUAL_KEY, VK_DOWN, VK_ESCAPE, VK_LEFT, VK_RETURN, VK_RIGHT, VK_SPACE, VK_TAB, VK_UP};
+
+use crate::{api::ui::{render_rectangle, render_text, Color, TextPalette}, overlay::{sanitize, CHARACTER_WIDTH}, remapping::{is_game_focused, start_keyboard_hook}, viewport};
+
+/// Height of a widget, fits one line of the game's font, which isn't scaled with the plugin's layout.
+const ROW_HEIGHT: u32 = 12;
+
+/// Space between widgets and between the panel's border and its widgets.
+const SPACING: u32 = 2;
+const PADDING: u32 = 4;
+
+/// Offset of a text from the top of its widget.
+const TEXT_OFFSET_Y: u32 = 2;
+
+const CHECKBOX_SIZE: u32 = 8;
+
+/// Navigation keys that are recorded until the next frame, more are dropped.
+const MAX_PENDING_KEYS: usize = 16;
+
+const PANEL_BACKGROUND: Color = Color { red: 0, green: 0, blue: 0 };
+const WIDGET_COLOR: Color = Color { red: 6, green: 6, blue: 8 };
+const HOVER_COLOR: Color = Color { red: 10, green: 10, blue: 13 };
+const FOCUS_COLOR: Color = Color { red: 6, green: 10, blue: 20 };
+const ACCENT_COLOR: Color = Color { red: 4, green: 18, blue: 6 };
+
+/// Whether a panel has the keyboard focus, read by the keyboard hook without locking.
+static HAS_FOCUS: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref WIDGETS: Mutex<Widgets> = Mutex::new(Widgets::default());
+
+    /// Navigation keys the keyboard hook swallowed since the last frame.
+    static ref PENDING_KEYS: Mutex<Vec<NavigationKey>> = Mutex::new(Vec::new());
+}
+
+/// Key that navigates the panel with the keyboard focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NavigationKey {
+    Next,
+    Previous,
+    Decrease,
+    Increase,
+    Activate,
+    Release,
+}
+
+impl NavigationKey {
+    fn from_virtual_key(key: VIRTUAL_KEY) -> Option<Self> {
+        match key {
+            VK_TAB | VK_DOWN => Some(NavigationKey::Next),
+            VK_UP => Some(NavigationKey::Previous),
+            VK_LEFT => Some(NavigationKey::Decrease),
+            VK_RIGHT => Some(NavigationKey::Increase),
+            VK_RETURN | VK_SPACE => Some(NavigationKey::Activate),
+            VK_ESCAPE => Some(NavigationKey::Release),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PanelId {
+    plugin: String,
+    panel: String,
+}
+
+/// Widget identified by its panel and its position among the panel's interactive widgets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WidgetId {
+    panel: PanelId,
+    index: usize,
+}
+
+/// Area of a widget in the screen space the game renders in.
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Bounds {
+    fn contains(&self, (x, y): (f32, f32)) -> bool {
+        x >= self.x as f32 && x < (self.x + self.width) as f32 && y >= self.y as f32 && y < (self.y + self.height) as f32
+    }
+}
+
+/// Input of the current frame.
+#[derive(Debug, Default)]
+struct FrameInput {
+    /// Position of the mouse in the screen space the game renders in.
+    ///
+    /// `None` if the mouse is outside of the game's window or the game doesn't have the focus.
+    mouse: Option<(f32, f32)>,
+    is_mouse_down: bool,
+    mouse_pressed: bool,
+    mouse_released: bool,
+    /// Keys that weren't used by a widget yet.
+    keys: Vec<NavigationKey>,
+    /// Whether a widget was pressed in this frame, the focus is released otherwise.
+    is_widget_pressed: bool,
+}
+
+/// Panel a plugin is adding widgets to. It is rendered when it ends.
+#[derive(Debug)]
+struct Panel {
+    id: PanelId,
+    x: u32,
+    y: u32,
+    width: u32,
+    /// Vertical position of the next widget.
+    next_y: u32,
+    /// Number of interactive widgets added so far.
+    widgets: usize,
+    rectangles: Vec<(Color, Bounds)>,
+    texts: Vec<(TextPalette, u32, u32, String)>,
+}
+
+impl Panel {
+    /// Reserve a row for the next widget.
+    fn next_row(&mut self) -> Bounds {
+        let bounds = Bounds { x: self.x + PADDING, y: self.next_y, width: self.width.saturating_sub(2 * PADDING), height: ROW_HEIGHT };
+        self.next_y += ROW_HEIGHT + SPACING;
+
+        bounds
+    }
+
+    fn next_widget(&mut self) -> (WidgetId, Bounds) {
+        let id = WidgetId { panel: self.id.clone(), index: self.widgets };
+        self.widgets += 1;
+
+        (id, self.next_row())
+    }
+
+    /// Add a text at the position, cut off at the width.
+    fn text(&mut self, palette: TextPalette, x: u32, y: u32, width: u32, text: &str) {
+        let length = (width / CHARACTER_WIDTH) as usize;
+        self.texts.push((palette, x, y + TEXT_OFFSET_Y, sanitize(text, length)));
+    }
+
+    fn rectangle(&mut self, color: Color, bounds: Bounds) {
+        self.rectangles.push((color, bounds));
+    }
+
+    /// Render the panel's background, then its rectangles, then its texts on top of them.
+    fn render(&self) {
+        let height = (self.next_y + PADDING).saturating_sub(self.y + SPACING);
+        render_rectangle(PANEL_BACKGROUND, clamp_u16(self.x), clamp_u16(self.y), clamp_u16(self.width), clamp_u16(height), true);
+
+        for (color, bounds) in &self.rectangles {
+            render_rectangle(*color, clamp_u16(bounds.x), clamp_u16(bounds.y), clamp_u16(bounds.width), clamp_u16(bounds.height), false);
+        }
+
+        for (palette, x, y, text) in &self.texts {
+            render_text(*x, *y, *palette, text);
+        }
+    }
+}
+
+/// Interaction of the user with a widget in the current frame.
+struct Interaction {
+    is_hovered: bool,
+    is_focused: bool,
+    /// The mouse button was pressed and released on the widget.
+    is_clicked: bool,
+    /// The mouse button was pressed on the widget and is still held.
+    is_dragged: bool,
+}
+
+impl Interaction {
+    fn color(&self) -> Color {
+        match (self.is_focused, self.is_hovered) {
+            (true, _) => FOCUS_COLOR,
+            (false, true) => HOVER_COLOR,
+            (false, false) => WIDGET_COLOR,
+        }
+    }
+}
+
+/// Immediate-mode widgets plugins build in-game menus with, see `ui.widgets`.
+///
+/// Plugins describe their panels and widgets every frame in `onUpdate`. The widgets place themselves below each other,
+/// test the mouse against their bounds, and move the keyboard focus between them. While a panel has the keyboard focus,
+/// the keyboard hook swallows the keys that navigate it, so the game doesn't react to them.
+#[derive(Debug, Default)]
+struct Widgets {
+    input: FrameInput,
+    /// Widget with the keyboard focus.
+    focus: Option<WidgetId>,
+    /// Widget the mouse button was pressed on until it is released.
+    active: Option<WidgetId>,
+    /// Number of interactive widgets of the panels rendered in the last frame.
+    panels: HashMap<PanelId, usize>,
+    /// Number of interactive widgets of the panels rendered in the current frame.
+    rendered: HashMap<PanelId, usize>,
+    /// Panel each plugin is adding widgets to, by the plugin's name.
+    open: HashMap<String, Panel>,
+}
+
+impl Widgets {
+    fn set_focus(&mut self, focus: Option<WidgetId>) {
+        HAS_FOCUS.store(focus.is_some(), Ordering::Relaxed);
+        self.focus = focus;
+    }
+
+    fn interact(&mut self, id: &WidgetId, bounds: Bounds) -> Interaction {
+        let is_hovered = self.input.mouse.map_or(false, |mouse| bounds.contains(mouse));
+
+        if is_hovered && self.input.mouse_pressed {
+            self.input.is_widget_pressed = true;
+            self.active = Some(id.clone());
+            self.set_focus(Some(id.clone()));
+        }
+
+        let is_active = self.active.as_ref() == Some(id);
+
+        Interaction {
+            is_hovered,
+            is_focused: self.focus.as_ref() == Some(id),
+            is_clicked: is_active && is_hovered && self.input.mouse_released,
+            is_dragged: is_active && self.input.is_mouse_down,
+        }
+    }
+
+    /// Use the key if it was pressed and the widget has the keyboard focus.
+    fn take_key(&mut self, interaction: &Interaction, key: NavigationKey) -> bool {
+        if !interaction.is_focused {
+            return false;
+        }
+
+        match self.input.keys.iter().position(|pressed| *pressed == key) {
+            Some(position) => {
+                self.input.keys.remove(position);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn panel(&mut self, plugin: &str) -> Result<&mut Panel, anyhow::Error> {
+        self.open.get_mut(plugin).ok_or(anyhow!("no panel was begun, call beginPanel first"))
+    }
+}
+
+/// Read the input of the frame and move the keyboard focus.
+///
+/// Must be called once per frame before plugins are updated.
+pub fn on_frame() {
+    let keys = match PENDING_KEYS.lock() {
+        Ok(mut pending) => std::mem::take(&mut *pending),
+        Err(_) => Vec::new(),
+    };
+    let (mouse, is_mouse_down) = read_mouse();
+
+    let mut widgets = match WIDGETS.lock() {
+        Ok(widgets) => widgets,
+        Err(_) => return,
+    };
+    let widgets = &mut *widgets;
+
+    if widgets.input.mouse_pressed && !widgets.input.is_widget_pressed {
+        widgets.set_focus(None);
+    }
+    if !widgets.input.is_mouse_down {
+        widgets.active = None;
+    }
+
+    for (plugin, panel) in widgets.open.drain() {
+        warn!("Plugin '{}' didn't end its panel '{}', it isn't rendered", plugin, panel.id.panel);
+    }
+
+    // The focus is lost once its panel isn't rendered anymore
+    widgets.panels = std::mem::take(&mut widgets.rendered);
+    let focus = widgets.focus.clone().and_then(|focus| match widgets.panels.get(&focus.panel) {
+        Some(0) | None => None,
+        Some(count) => Some(WidgetId { index: focus.index.min(count - 1), ..focus }),
+    });
+
+    let mut remaining = Vec::new();
+    let focus = keys.into_iter().fold(focus, |focus, key| {
+        let focus = focus?;
+        let count = widgets.panels.get(&focus.panel).copied().unwrap_or(1);
+
+        match key {
+            NavigationKey::Next => Some(WidgetId { index: (focus.index + 1) % count, ..focus }),
+            NavigationKey::Previous => Some(WidgetId { index: (focus.index + count - 1) % count, ..focus }),
+            NavigationKey::Release => None,
+            key => {
+                remaining.push(key);
+                Some(focus)
+            },
+        }
+    });
+    widgets.set_focus(focus);
+
+    widgets.input = FrameInput {
+        mouse,
+        is_mouse_down,
+        mouse_pressed: is_mouse_down && !widgets.input.is_mouse_down,
+        mouse_released: !is_mouse_down && widgets.input.is_mouse_down,
+        keys: remaining,
+        is_widget_pressed: false,
+    };
+}
+
+/// Position of the mouse in the screen space the game renders in and whether its left button is down.
+fn read_mouse() -> (Option<(f32, f32)>, bool) {
+    if !is_game_focused() {
+        return (None, false);
+    }
+
+    let mouse = DeviceState::new().get_mouse();
+    let is_down = mouse.button_pressed.get(1).copied().unwrap_or(false);
+
+    (viewport::from_desktop(mouse.coords.0, mouse.coords.1), is_down)
+}
+
+/// Record a key event of the keyboard hook and return whether the game must not see the key.
+///
+/// Called from the keyboard hook, which must never block. Only presses of navigation keys are swallowed while a panel has
+/// the keyboard focus. Releases always pass, so keys pressed before a panel got the focus don't stay held in the game.
+pub(crate) fn record_key(key: VIRTUAL_KEY, is_key_up: bool) -> bool {
+    if is_key_up || !HAS_FOCUS.load(Ordering::Relaxed) {
+        return false;
+    }
+
+    let key = match NavigationKey::from_virtual_key(key) {
+        Some(key) => key,
+        None => return false,
+    };
+
+    if let Ok(mut pending) = PENDING_KEYS.try_lock() {
+        if pending.len() < MAX_PENDING_KEYS {
+            pending.push(key);
+        }
+    }
+
+    true
+}
+
+/// Begin a panel at the position and with the width in the plugin's layout. Widgets are added below each other until it ends.
+pub fn begin_panel(plugin: &str, panel: &str, x: f32, y: f32, width: f32) -> Result<(), anyhow::Error> {
+    // Navigation keys are only swallowed by the hook
+    start_keyboard_hook();
+
+    let layout = viewport::get_layout(plugin);
+    let (x, y) = layout.to_screen(x, y);
+    let (width, _) = layout.to_screen_size(width, 0.0);
+
+    let mut widgets = WIDGETS.lock().map_err(|e| anyhow!("could not get lock to the widgets: {}", e))?;
+
+    if let Some(open) = widgets.open.get(plugin) {
+        return Err(anyhow!("panel '{}' wasn't ended, call endPanel first", open.id.panel));
+    }
+
+    let id = PanelId { plugin: plugin.to_string(), panel: panel.to_string() };
+    if widgets.rendered.contains_key(&id) {
+        return Err(anyhow!("panel '{}' was already rendered in this frame", panel));
+    }
+
+    widgets.open.insert(plugin.to_string(), Panel {
+        id,
+        x,
+        y,
+        width,
+        next_y: y + PADDING,
+        widgets: 0,
+        rectangles: Vec::new(),
+        texts: Vec::new(),
+    });
+
+    Ok(())
+}
+
+/// End the plugin's panel and render it.
+pub fn end_panel(plugin: &str) -> Result<(), anyhow::Error> {
+    let panel = {
+        let mut widgets = WIDGETS.lock().map_err(|e| anyhow!("could not get lock to the widgets: {}", e))?;
+        let panel = widgets.open.remove(plugin).ok_or(anyhow!("no panel was begun, call beginPanel first"))?;
+        widgets.rendered.insert(panel.id.clone(), panel.widgets);
+
+        panel
+    };
+
+    // Render without holding the lock, rendering may log
+    panel.render();
+
+    Ok(())
+}
+
+/// Add a line of text to the plugin's panel.
+pub fn label(plugin: &str, text: &str) -> Result<(), anyhow::Error> {
+    let mut widgets = WIDGETS.lock().map_err(|e| anyhow!("could not get lock to the widgets: {}", e))?;
+    let panel = widgets.panel(plugin)?;
+
+    let bounds = panel.next_row();
+    panel.text(TextPalette::White, bounds.x, bounds.y, bounds.width, text);
+
+    Ok(())
+}
+
+/// Add a button to the plugin's panel. Returns whether it was clicked or activated with the keyboard.
+pub fn button(plugin: &str, label: &str) -> Result<bool, anyhow::Error> {
+    let mut widgets = WIDGETS.lock().map_err(|e| anyhow!("could not get lock to the widgets: {}", e))?;
+    let (id, bounds) = widgets.panel(plugin)?.next_widget();
+
+    let interaction = widgets.interact(&id, bounds);
+    let is_clicked = interaction.is_clicked || widgets.take_key(&interaction, NavigationKey::Activate);
+
+    let panel = widgets.panel(plugin)?;
+    panel.rectangle(interaction.color(), bounds);
+    panel.text(TextPalette::White, bounds.x + PADDING, bounds.y, bounds.width.saturating_sub(2 * PADDING), label);
+
+    Ok(is_clicked)
+}
+
+/// Add a checkbox to the plugin's panel. Returns whether it is checked after the user's input.
+pub fn checkbox(plugin: &str, label: &str, checked: bool) -> Result<bool, anyhow::Error> {
+    let mut widgets = WIDGETS.lock().map_err(|e| anyhow!("could not get lock to the widgets: {}", e))?;
+    let (id, bounds) = widgets.panel(plugin)?.next_widget();
+
+    let interaction = widgets.interact(&id, bounds);
+    let is_toggled = interaction.is_clicked || widgets.take_key(&interaction, NavigationKey::Activate);
+    let checked = checked != is_toggled;
+
+    let panel = widgets.panel(plugin)?;
+    let check_y = bounds.y + (ROW_HEIGHT - CHECKBOX_SIZE) / 2;
+    panel.rectangle(interaction.color(), Bounds { x: bounds.x, y: check_y, width: CHECKBOX_SIZE, height: CHECKBOX_SIZE });
+    if checked {
+        panel.rectangle(ACCENT_COLOR, Bounds { x: bounds.x + 2, y: check_y + 2, width: CHECKBOX_SIZE - 4, height: CHECKBOX_SIZE - 4 });
+    }
+
+    let text_x = bounds.x + CHECKBOX_SIZE + PADDING;
+    panel.text(TextPalette::White, text_x, bounds.y, bounds.width.saturating_sub(CHECKBOX_SIZE + PADDING), label);
+
+    Ok(checked)
+}
+
+/// Add a slider between `min` and `max` to the plugin's panel. Returns its value after the user's input.
+///
+/// The value snaps to multiples of `step` from `min`. Without a step, the value isn't snapped and the arrow keys move it by
+/// a twentieth of the range.
+pub fn slider(plugin: &str, label: &str, value: f32, min: f32, max: f32, step: Option<f32>) -> Result<f32, anyhow::Error> {
+    if !min.is_finite() || !max.is_finite() || min >= max {
+        return Err(anyhow!("the slider's minimum must be less than its maximum"));
+    }
+    if let Some(step) = step {
+        if !step.is_finite() || step <= 0.0 {
+            return Err(anyhow!("the slider's step must be greater than 0"));
+        }
+    }
+
+    let mut widgets = WIDGETS.lock().map_err(|e| anyhow!("could not get lock to the widgets: {}", e))?;
+    let (id, bounds) = widgets.panel(plugin)?.next_widget();
+
+    let interaction = widgets.interact(&id, bounds);
+    let mut value = value.clamp(min, max);
+
+    if let (true, Some((mouse_x, _))) = (interaction.is_dragged, widgets.input.mouse) {
+        let fraction = (mouse_x - bounds.x as f32) / bounds.width.max(1) as f32;
+        value = min + fraction.clamp(0.0, 1.0) * (max - min);
+    }
+
+    let key_step = step.unwrap_or((max - min) / 20.0);
+    if widgets.take_key(&interaction, NavigationKey::Decrease) {
+        value -= key_step;
+    }
+    if widgets.take_key(&interaction, NavigationKey::Increase) {
+        value += key_step;
+    }
+
+    if let Some(step) = step {
+        value = min + ((value - min) / step).round() * step;
+    }
+    let value = value.clamp(min, max);
+
+    let panel = widgets.panel(plugin)?;
+    panel.rectangle(interaction.color(), bounds);
+
+    let filled = ((value - min) / (max - min) * bounds.width as f32).round() as u32;
+    if filled > 0 {
+        panel.rectangle(ACCENT_COLOR, Bounds { width: filled.min(bounds.width), ..bounds });
+    }
+
+    let text = format!("{}: {}", label, format_value(value));
+    panel.text(TextPalette::White, bounds.x + PADDING, bounds.y, bounds.width.saturating_sub(2 * PADDING), &text);
+
+    Ok(value)
+}
+
+/// Give the keyboard focus to the first widget of the plugin's panel, e.g., when the plugin opens its menu with a key.
+pub fn focus_panel(plugin: &str, panel: &str) -> Result<(), anyhow::Error> {
+    let mut widgets = WIDGETS.lock().map_err(|e| anyhow!("could not get lock to the widgets: {}", e))?;
+
+    widgets.set_focus(Some(WidgetId { panel: PanelId { plugin: plugin.to_string(), panel: panel.to_string() }, index: 0 }));
+
+    Ok(())
+}
+
+/// Whether one of the plugin's panels has the keyboard focus.
+pub fn has_focus(plugin: &str) -> bool {
+    WIDGETS
+        .lock()
+        .map(|widgets| widgets.focus.as_ref().map_or(false, |focus| focus.panel.plugin == plugin))
+        .unwrap_or(false)
+}
+
+/// Forget the plugin's panels and release its focus, e.g., before the plugin is loaded again.
+pub fn clear(plugin: &str) {
+    let mut widgets = match WIDGETS.lock() {
+        Ok(widgets) => widgets,
+        Err(_) => return,
+    };
+
+    widgets.open.remove(plugin);
+    widgets.panels.retain(|id, _| id.plugin != plugin);
+    widgets.rendered.retain(|id, _| id.plugin != plugin);
+
+    if widgets.active.as_ref().map_or(false, |active| active.panel.plugin == plugin) {
+        widgets.active = None;
+    }
+    if widgets.focus.as_ref().map_or(false, |focus| focus.panel.plugin == plugin) {
+        widgets.set_focus(None);
+    }
+}
+
+/// Format the slider's value without trailing zeros.
+fn format_value(value: f32) -> String {
+    let formatted = format!("{:.2}", value);
+
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn clamp_u16(value: u32) -> u16 {
+    value.min(u16::MAX as u32) as u16
+}
@@ -1,4 +1,5 @@
 pub mod types;
 pub mod lua;
 pub mod native;
+pub mod recorder;
 
@@ -1,11 +1,12 @@
-use std::arch::asm;
+use std::{arch::asm, panic::{self, AssertUnwindSafe}};
 
 use log::{debug, error, warn};
 use mlua::{Function, Lua, MultiValue, UserData};
 use windows::Win32::System::Memory::{VirtualAlloc, MEM_COMMIT, MEM_RESERVE, PAGE_EXECUTE_READWRITE};
 
-use crate::types::{lua_to_native, lua_to_native_implied, native_to_lua, Type};
-use crate::native::{memory_copy, Hook};
+use crate::types::{lua_to_native, lua_to_native_implied, native_to_lua, native_words_to_lua, CallingConvention, Type};
+use crate::native::{Hook, HookCall};
+use crate::recorder;
 
 /// Create a hook on any function with a given lua function.
 pub fn hook_function<'lua>(lua: &'lua Lua, (address, arg_type_names, return_type_name, callback): (u32, Vec<String>, String, Function)) -> Result<Hook, mlua::Error> {
@@ -16,6 +17,7 @@ pub fn hook_function<'lua>(lua: &'lua Lua, (address, arg_type_names, return_type
     Some(value) => value,
     None => return Err(mlua::Error::RuntimeError(format!("return type invalid: type '{}' doesn't exist", return_type_name)))
   };
+  check_return_type(return_type)?;
 
   let mut argument_types: Vec<Type> = Vec::new();
   for arg_type_name in arg_type_names {
@@ -41,139 +43,168 @@ pub fn hook_function<'lua>(lua: &'lua Lua, (address, arg_type_names, return_type
     let mut hook = Hook::new(address);
 
     let hook_closure = move |original_fn: u32, args: u32| {
-      debug!("Called closure for hook of {:#08x}", address);
+      let _call = HookCall::enter();
 
-      let wrapper_return_type = hook_return_type.clone();
-      let hook_return_type = hook_return_type.clone();
-      let wrapper_argument_types = hook_arg_types.clone();
+      // The closure is called directly from the game's code. Unwinding into it is undefined behavior
+      // and will crash the game. Errors of the lua hook are returned and fall back to the original function.
+      // Unexpected panics are caught as well, but are still reported by the engine's crash handler.
+      let arg_pointer = &args as *const u32;
+      let arg_words: usize = argument_types.iter().map(Type::words).sum();
 
-      let original_fn_clone = original_fn.clone() as *const u32;
+      let result = panic::catch_unwind(AssertUnwindSafe(|| -> Result<u32, String> {
+        debug!("Called closure for hook of {:#08x}", address);
 
-      // Create a lua function to call the original function (the function that was hooked)
-      // This lua will do three things.
-      // 1. Convert the arguments from lua values into native values
-      // 2. Call the original function with the arguments
-      // 3. Convert the return value back to a lua value and return it
-      let original_wrapper = match lua.create_function::<_, mlua::Value, _>(move |lua, args: MultiValue| {
-        debug!("Lua called original function");
+        let wrapper_return_type = hook_return_type.clone();
+        let hook_return_type = hook_return_type.clone();
+        let wrapper_argument_types = hook_arg_types.clone();
 
-        // Convert the arguments from lua values into actual native values.
-        let lua_args = args.into_vec();
+        let original_fn_clone = original_fn.clone() as *const u32;
 
-        let mut converted_lua_args: Vec<u32> = Vec::new();
+        // Create a lua function to call the original function (the function that was hooked)
+        // This lua will do three things.
+        // 1. Convert the arguments from lua values into native values
+        // 2. Call the original function with the arguments
+        // 3. Convert the return value back to a lua value and return it
+        let original_wrapper = match lua.create_function::<_, mlua::Value, _>(move |lua, args: MultiValue| {
+          debug!("Lua called original function");
 
-        for arg_idx in (0..wrapper_argument_types.len()).rev() {
-          let lua_arg = &lua_args[arg_idx];
-          let arg_type = &wrapper_argument_types[arg_idx];
+          // Convert the arguments from lua values into actual native values.
+          let lua_args = args.into_vec();
 
-          let mut converted_arg = match lua_to_native(*arg_type, lua_arg) {
-            Ok(value) => value,
-            Err(e) => return Err(mlua::Error::RuntimeError(format!("could not converted argument {} into {:?}: {:?}", arg_idx, *arg_type, e))),
-          };
+          let mut converted_lua_args: Vec<u32> = Vec::new();
 
-          converted_lua_args.append(&mut converted_arg);
-        }
+          for arg_idx in 0..wrapper_argument_types.len() {
+            let lua_arg = &lua_args[arg_idx];
+            let arg_type = &wrapper_argument_types[arg_idx];
 
-        let raw_args = converted_lua_args.as_ptr();
-        let arg_len = converted_lua_args.len();
-
-        // This variable will hold the return value of the original function
-        #[allow(unused_assignments)]
-        let mut original_fn_return: u32 = 0;
-
-        // Unfortunately I couldn't find a way force rust to behave as I wanted to.
-        // Therefore, ugly assembly code.
-        // The following assembly code acts the trampoline to the original function.
-        // It takes all the converted arguments given by the lua function that called this closure and passes them all
-        // to the original function. As we don't know the amount of arguments and cannot use a tuple to represent variadic arguments,
-        // we use the assembly code to manually push all arguments to the stack and call the function.
-        asm!(
-          "push ebx",
-          "push ecx",
-          "push edx",
-          "push esi",
-          "push edi",
-          "mov {tmp}, {len}",
-          "2:",
-          "mov eax, [{args}]",
-          "push eax",
-          "add {args}, 4",
-          "sub {tmp}, 1",
-          "ja 2b",
-          "call {address}",
-          "mov {tmp}, {len}",
-          "shl {tmp}, 2",
-          "add esp, {tmp}",
-          "pop edi",
-          "pop esi",
-          "pop edx",
-          "pop ecx",
-          "pop ebx",
-          address = in(reg) original_fn_clone,
-          args = in(reg) raw_args,
-          len = in(reg) arg_len,
-          tmp = out(reg) _,
-          out("eax") original_fn_return,
-        );
-
-        // Don't know if this necessary, but it fixed some weird issue.
-        drop(lua_args);
-
-        // Convert the return value of the original function into a lua value
-        native_to_lua(lua, wrapper_return_type, original_fn_return as u32)
-      }) {
-        Ok(w) => w,
-        Err(e) => {
-          warn!("Error while creating wrapper for the original function: {:?}. Panicking...", e);
-          panic!("Could not create a wrapper for the original function of a hook: {:?}", e);
-        }
-      };
+            let mut converted_arg = match lua_to_native(*arg_type, lua_arg) {
+              Ok(value) => value,
+              Err(e) => return Err(mlua::Error::RuntimeError(format!("could not converted argument {} into {:?}: {:?}", arg_idx, *arg_type, e))),
+            };
 
-      let mut callback_args: Vec<mlua::Value> = vec![mlua::Value::Function(original_wrapper)];
-      let arg_pointer = &args as *const u32;
-
-      for i in 0..argument_types.len() {
-        let arg_type = argument_types[i];
+            converted_lua_args.append(&mut converted_arg);
+          }
 
-        match native_to_lua(lua, arg_type, *arg_pointer.byte_offset(i as isize * 4)) {
-          Ok(value) => callback_args.push(value),
-          Err(e) => {
-            warn!("could not convert {} argument to lua value: {:?}. Panicking...", i, e);
-            panic!("could not convert a raw argument to a lua value: {:?}", e);
+          // The words are pushed in reverse order, so that the first word of the first argument ends up at the lowest address
+          converted_lua_args.reverse();
+
+          let raw_args = converted_lua_args.as_ptr();
+          let arg_len = converted_lua_args.len();
+
+          // This variable will hold the return value of the original function
+          #[allow(unused_assignments)]
+          let mut original_fn_return: u32 = 0;
+
+          // Unfortunately I couldn't find a way force rust to behave as I wanted to.
+          // Therefore, ugly assembly code.
+          // The following assembly code acts the trampoline to the original function.
+          // It takes all the converted arguments given by the lua function that called this closure and passes them all
+          // to the original function. As we don't know the amount of arguments and cannot use a tuple to represent variadic arguments,
+          // we use the assembly code to manually push all arguments to the stack and call the function.
+          asm!(
+            "push ebx",
+            "push ecx",
+            "push edx",
+            "push esi",
+            "push edi",
+            "mov {tmp}, {len}",
+            "2:",
+            "mov eax, [{args}]",
+            "push eax",
+            "add {args}, 4",
+            "sub {tmp}, 1",
+            "ja 2b",
+            "call {address}",
+            "mov {tmp}, {len}",
+            "shl {tmp}, 2",
+            "add esp, {tmp}",
+            "pop edi",
+            "pop esi",
+            "pop edx",
+            "pop ecx",
+            "pop ebx",
+            address = in(reg) original_fn_clone,
+            args = in(reg) raw_args,
+            len = in(reg) arg_len,
+            tmp = out(reg) _,
+            out("eax") original_fn_return,
+          );
+
+          // Don't know if this necessary, but it fixed some weird issue.
+          drop(lua_args);
+
+          // Convert the return value of the original function into a lua value
+          native_to_lua(lua, wrapper_return_type, original_fn_return as u32)
+        }) {
+          Ok(w) => w,
+          Err(e) => return Err(format!("could not create a wrapper for the original function: {:?}", e)),
+        };
+
+        let mut callback_args: Vec<mlua::Value> = vec![mlua::Value::Function(original_wrapper)];
+        let arg_pointer = &args as *const u32;
+        let mut word = 0;
+
+        for i in 0..argument_types.len() {
+          let arg_type = argument_types[i];
+          let arg_words = std::slice::from_raw_parts(arg_pointer.add(word), arg_type.words());
+          word += arg_type.words();
+
+          match native_words_to_lua(lua, arg_type, arg_words) {
+            Ok(value) => callback_args.push(value),
+            Err(e) => return Err(format!("could not convert argument {} to a lua value: {:?}", i, e)),
           }
         }
-      }
 
-      // Call the lua hook
-      let return_value = match callback.call::<_, mlua::Value>(mlua::MultiValue::from_vec(callback_args)) {
-        Ok(value) => value,
-        Err(e) => {
-          warn!("Lua hook threw error: {:?}. Panicking...", e);
-          panic!("Lua hook threw an error: {:?}", e);
-        }
-      };
+        // Call the lua hook
+        let return_value = match callback.call::<_, mlua::Value>(mlua::MultiValue::from_vec(callback_args)) {
+          Ok(value) => value,
+          Err(e) => return Err(format!("lua hook threw an error: {:?}", e)),
+        };
+
+        // Convert the return value of the lua hook into a native value
+        let raw_value = match lua_to_native(hook_return_type, &return_value) {
+          Ok(raw_value) => {
+            if raw_value.len() < 1 {
+              return Err(String::from("lua hook returned an invalid value: return value could not be converted to a full word"));
+            } else if raw_value.len() > 1 {
+              warn!("Lua hook returned an invalid value: return value too large. Handling by truncating the value. May lead to undesired results");
+              raw_value[0]
+            } else {
+              raw_value[0]
+            }
+          },
+          Err(e) => return Err(format!("could not convert the return value of the lua hook: {:?}", e)),
+        };
+
+        // Return the lua return value
+        Ok(raw_value)
+      }));
+
+      let return_value = match result {
+        Ok(Ok(raw_value)) => raw_value,
+        error => {
+          match error {
+            Ok(Err(e)) => warn!("Hook of {:#08x} failed: {}. Calling the original function instead", address, e),
+            _ => error!("Hook of {:#08x} panicked, calling the original function instead", address),
+          }
 
-      // Convert the return value of the lua hook into a native value
-      let raw_value = match lua_to_native(hook_return_type, &return_value) {
-        Ok(raw_value) => {
-          if raw_value.len() < 1 {
-            error!("Lua hook returned an invalid value: return value could not be converted to a full word. Cannot handle this error panicking...");
-            panic!("Lua hook returned an invalid value: could not be converted to a full word");
-          } else if raw_value.len() > 1 {
-            warn!("Lua hook returned an invalid value: return value too large. Handling by truncating the value. May lead to undesired results");
-            raw_value[0]
-          } else {
-            raw_value[0]
+          // Arguments are pushed in reverse order
+          let mut raw_args: Vec<u32> = Vec::new();
+          for i in (0..arg_words).rev() {
+            raw_args.push(*arg_pointer.add(i));
           }
-        },
-        Err(e) => {
-          error!("Could not convert lua hook return value into: {:?}. Panicking...", e);
-          panic!("Error while converting the return value of a lua hook: {:?}", e);
-        },
+
+          call_native_raw(original_fn, &raw_args)
+        }
       };
 
-      // Return the lua return value
-      return raw_value;
+      recorder::record(
+        address,
+        || (0..arg_words).map(|i| *arg_pointer.add(i)).collect(),
+        return_value,
+      );
+
+      return_value
     };
 
     let boxed_closure: Box<dyn FnMut(u32, u32) -> u32> = Box::new(hook_closure);
@@ -185,64 +216,169 @@ pub fn hook_function<'lua>(lua: &'lua Lua, (address, arg_type_names, return_type
   }
 }
 
+/// Call the native function at `address` with the given raw arguments.
+/// 
+/// The arguments must be in reverse order, i.e., the last argument first, as they are
+/// pushed onto the stack in the given order.
+/// Returns the raw value of `eax` after the call.
+///
+/// # Safety
+///
+/// `address` must be the start of a function that is mapped as executable and uses the `cdecl` convention,
+/// since the arguments are removed from the stack after the call. The function must take exactly `args.len()`
+/// 4-byte arguments and must not return a value larger than `eax`, otherwise it reads or corrupts the stack.
+/// Any pointers among the arguments must be valid for what the function does with them.
+pub unsafe fn call_native_raw(address: u32, args: &[u32]) -> u32 {
+  let raw_args = args.as_ptr();
+  let arg_len = args.len();
+
+  #[allow(unused_assignments)]
+  let mut return_value: u32 = 0;
+
+  asm!(
+    "push ebx",
+    "push ecx",
+    "push edx",
+    "push esi",
+    "push edi",
+    "mov {tmp}, {len}",
+    "test {tmp}, {tmp}",
+    "jz 3f",
+    "2:",
+    "mov eax, [{args}]",
+    "push eax",
+    "add {args}, 4",
+    "sub {tmp}, 1",
+    "ja 2b",
+    "3:",
+    "call {address}",
+    "mov {tmp}, {len}",
+    "shl {tmp}, 2",
+    "add esp, {tmp}",
+    "pop edi",
+    "pop esi",
+    "pop edx",
+    "pop ecx",
+    "pop ebx",
+    address = in(reg) address,
+    args = in(reg) raw_args,
+    len = in(reg) arg_len,
+    tmp = out(reg) _,
+    out("eax") return_value,
+  );
+
+  return_value
+}
+
+/// Arguments of [`call_native_with_convention`], read by its assembly.
+#[repr(C)]
+struct CallFrame {
+  address: u32,
+  stack_args: *const u32,
+  stack_args_len: u32,
+  ecx: u32,
+  edx: u32,
+  saved_esp: u32,
+}
+
+/// Call the native function at `address` with the given raw arguments in the calling convention.
+///
+/// The arguments must be in their declared order. Register arguments are passed in `ecx` and `edx`, the others are pushed
+/// in reverse order. The stack pointer is restored after the call, thus, the stack stays balanced no matter who cleans it up.
+/// Returns the raw value of `eax` after the call.
+///
+/// # Safety
+///
+/// `address` must be the start of a function that is mapped as executable and uses `convention`.
+/// The function must take exactly `args.len()` 4-byte arguments. Restoring the stack pointer keeps the stack balanced
+/// if the function removes another number of arguments, but a function that expects more arguments reads past them.
+/// Any pointers among the arguments must be valid for what the function does with them.
+pub unsafe fn call_native_with_convention(address: u32, convention: CallingConvention, args: &[u32]) -> u32 {
+  let (register_args, stack_args) = args.split_at(convention.register_args().min(args.len()));
+  let stack_args: Vec<u32> = stack_args.iter().rev().copied().collect();
+
+  let mut frame = CallFrame {
+    address,
+    stack_args: stack_args.as_ptr(),
+    stack_args_len: stack_args.len() as u32,
+    ecx: register_args.first().copied().unwrap_or(0),
+    edx: register_args.get(1).copied().unwrap_or(0),
+    saved_esp: 0,
+  };
+
+  let return_value: u32;
+
+  asm!(
+    "push ebx",
+    "push esi",
+    "mov [edi + 20], esp",
+    "mov esi, [edi + 4]",
+    "mov ebx, [edi + 8]",
+    "test ebx, ebx",
+    "jz 3f",
+    "2:",
+    "push dword ptr [esi]",
+    "add esi, 4",
+    "sub ebx, 1",
+    "jnz 2b",
+    "3:",
+    "mov ecx, [edi + 12]",
+    "mov edx, [edi + 16]",
+    "call dword ptr [edi]",
+    "mov esp, [edi + 20]",
+    "pop esi",
+    "pop ebx",
+    in("edi") &mut frame as *mut CallFrame,
+    out("eax") return_value,
+    clobber_abi("C"),
+  );
+
+  return_value
+}
+
 pub struct NativeFunction {
   // Generic native closure that wraps a lua function
   address: u32,
-  #[allow(dead_code)]
   arg_types: Vec<Type>,
   return_type: Type,
+  convention: CallingConvention,
 }
 
 impl NativeFunction {
-  pub fn new(address: u32, arg_types: Vec<Type>, return_type: Type) -> NativeFunction {
+  pub fn new(address: u32, arg_types: Vec<Type>, return_type: Type, convention: CallingConvention) -> NativeFunction {
     NativeFunction {
       address,
       arg_types,
       return_type,
+      convention,
     }
   }
 
+  pub fn address(&self) -> u32 {
+    self.address
+  }
+
   pub fn call<'lua>(&self, lua: &'lua Lua, args: mlua::MultiValue) -> Result<mlua::Value<'lua>, mlua::Error> {
     let args = args.into_vec();
 
-    debug!("Calling function at address {:x} with ({:?}), expecting return type {:?}", self.address, args, self.return_type);
+    debug!("Calling {:?} function at address {:x} with ({:?}), expecting return type {:?}", self.convention, self.address, args, self.return_type);
 
     let mut arg_bytes: Vec<u32> = Vec::new();
 
-    for arg in args.iter().rev() {
-      let mut arg_byte = unsafe {lua_to_native_implied(&arg).map_err(|e| mlua::Error::RuntimeError(format!("could not convert lua value into bytes: {}", e.to_string())))?};
+    // Arguments are converted with their declared type, additional arguments with their implied type
+    for (i, arg) in args.iter().enumerate() {
+      let arg_byte = unsafe {
+        match self.arg_types.get(i) {
+          Some(arg_type) => lua_to_native(*arg_type, arg),
+          None => lua_to_native_implied(arg),
+        }
+      };
+      let mut arg_byte = arg_byte.map_err(|e| mlua::Error::RuntimeError(format!("could not convert argument {} into bytes: {}", i, e.to_string())))?;
       arg_bytes.append(&mut arg_byte);
     }
 
-    let native_fn_address = self.address;
-
-    let raw_args = arg_bytes.as_ptr();
-    let arg_len = args.len();
-
     unsafe {
-      #[allow(unused_assignments)]
-      let mut raw_response: u32 = 0;
-
-        // Call native function with arguments
-        // Use raw assembly because we don't know how many arguments we have at compile time
-        asm!(
-          "mov {tmp}, {len}",
-          "2:",
-          "mov eax, [{args}]",
-          "push eax",
-          "add {args}, 0x4",
-          "sub {tmp}, 0x1",
-          "ja 2b",  // Jumps if tmp is above zero
-          "call {address}",
-          "mov {tmp}, {len}",
-          "shl {tmp}, 0x2",
-          "add esp, {tmp}",
-          address = in(reg) native_fn_address,
-          len = in(reg) arg_len,
-          args = in(reg) raw_args,
-          tmp = out(reg) _,
-          out("eax") raw_response,
-        );
+      let raw_response = call_native_with_convention(self.address, self.convention, &arg_bytes);
 
       let lua_response = native_to_lua(lua, self.return_type, raw_response);
 
@@ -251,6 +387,123 @@ impl NativeFunction {
   }
 }
 
+/// Parse the name of a calling convention, `cdecl` if none is given.
+fn parse_convention(name: Option<String>) -> Result<CallingConvention, mlua::Error> {
+  match name {
+    None => Ok(CallingConvention::Cdecl),
+    Some(name) => CallingConvention::try_from_str(&name).ok_or_else(|| mlua::Error::RuntimeError(format!("unsupported calling convention '{}'", name))),
+  }
+}
+
+/// Check that values of the return type are returned in `eax`.
+///
+/// Doubles are returned on the FPU stack, which isn't supported.
+fn check_return_type(return_type: Type) -> Result<(), mlua::Error> {
+  match return_type.words() {
+    1 => Ok(()),
+    _ => Err(mlua::Error::RuntimeError(format!("unsupported return type {:?}: only values that fit into eax can be returned", return_type))),
+  }
+}
+
+/// Check that the arguments the calling convention passes in registers fit into a register.
+fn check_register_args(convention: CallingConvention, arg_types: &[Type]) -> Result<(), mlua::Error> {
+  let register_args = convention.register_args().min(arg_types.len());
+
+  match arg_types[..register_args].iter().find(|arg_type| arg_type.words() > 1) {
+    Some(arg_type) => Err(mlua::Error::RuntimeError(format!("unsupported argument type {:?}: the first {} arguments of a {:?} function are passed in registers", arg_type, register_args, convention))),
+    None => Ok(()),
+  }
+}
+
+/// Machine code of the wrapper at `wrapper_address` that is called in the calling convention and calls the native closure.
+///
+/// The closure reads the arguments from the address of its first argument, therefore, the wrapper copies the register
+/// and stack arguments into one block after the closure's data. Arguments in registers must fit into one word, see
+/// [`check_register_args`].
+///
+/// ```asm
+/// push esi
+/// push edi
+/// mov esi, ecx
+/// mov edi, edx
+/// mov eax, esp
+/// add eax, {0x8 + stack_args_len}
+/// lea edx, [esp + 0x8]
+/// loop:                   ; only with stack arguments
+/// push dword [eax]
+/// sub eax, 0x4
+/// cmp edx, eax
+/// jb loop
+/// push edi                ; only with two register arguments
+/// push esi                ; only with register arguments
+/// push data
+/// call native_closure
+/// add esp, {0x4 + args_len}  ; all words of the arguments
+/// pop edi
+/// pop esi
+/// ret {stack_args_len}    ; only `ret` if the caller cleans up
+/// ```
+fn closure_wrapper_code(wrapper_address: u32, convention: CallingConvention, arg_types: &[Type], data: u32, native_closure: u32) -> Vec<u8> {
+  let register_args = convention.register_args().min(arg_types.len());
+  let args_len: usize = arg_types.iter().map(Type::words).sum();
+  let stack_args_len = (args_len - register_args) as u32 * 4;
+
+  let mut code: Vec<u8> = vec![
+    0x56, // push esi
+    0x57, // push edi
+    0x89, 0xce, // mov esi, ecx
+    0x89, 0xd7, // mov edi, edx
+    0x89, 0xe0, // mov eax, esp
+    0x05, // add eax, imm32
+  ];
+  code.extend_from_slice(&(0x8 + stack_args_len).to_le_bytes());
+  code.extend_from_slice(&[0x8d, 0x54, 0x24, 0x08]); // lea edx, [esp + 0x8]
+
+  if stack_args_len > 0 {
+    code.extend_from_slice(&[
+      0xff, 0x30, // loop: push dword [eax]
+      0x83, 0xe8, 0x04, // sub eax, 0x4
+      0x39, 0xc2, // cmp edx, eax
+      0x72, 0xf7, // jb loop
+    ]);
+  }
+
+  if register_args > 1 {
+    code.push(0x57); // push edi
+  }
+  if register_args > 0 {
+    code.push(0x56); // push esi
+  }
+
+  // push data
+  code.push(0x68);
+  code.extend_from_slice(&data.to_le_bytes());
+
+  // call native_closure
+  let call_end = wrapper_address + code.len() as u32 + 5;
+  code.push(0xe8);
+  code.extend_from_slice(&native_closure.wrapping_sub(call_end).to_le_bytes());
+
+  // add esp, {0x4 + args_len}
+  code.extend_from_slice(&[0x81, 0xc4]);
+  code.extend_from_slice(&(0x4 + args_len as u32 * 4).to_le_bytes());
+
+  code.extend_from_slice(&[
+    0x5f, // pop edi
+    0x5e, // pop esi
+  ]);
+
+  match convention.is_callee_cleanup() && stack_args_len > 0 {
+    true => {
+      code.push(0xc2); // ret imm16
+      code.extend_from_slice(&(stack_args_len as u16).to_le_bytes());
+    },
+    false => code.push(0xc3), // ret
+  }
+
+  code
+}
+
 impl UserData for NativeFunction {
     fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
       methods.add_method("getAddress", |_, native_function, ()| {
@@ -264,10 +517,10 @@ impl UserData for NativeFunction {
     }
 }
 
-pub fn create_native_function_function<'lua>(lua: &'lua Lua, (arg_types, return_type, lua_fn): (Vec<String>, String, mlua::Function)) -> Result<NativeFunction, mlua::Error> {
-  debug!("Creating native function with signature ({:?}) -> {:?}. Calls lua function: {:?}", arg_types, return_type, lua_fn);
+pub fn create_native_function_function<'lua>(lua: &'lua Lua, (arg_types, return_type, lua_fn, convention): (Vec<String>, String, mlua::Function, Option<String>)) -> Result<NativeFunction, mlua::Error> {
+  debug!("Creating {:?} native function with signature ({:?}) -> {:?}. Calls lua function: {:?}", convention, arg_types, return_type, lua_fn);
 
-  let args_len = arg_types.len();
+  let convention = parse_convention(convention)?;
 
   // Convert lua argument types
   let mut lua_arg_types: Vec<Type> = Vec::new();
@@ -279,6 +532,8 @@ pub fn create_native_function_function<'lua>(lua: &'lua Lua, (arg_types, return_
     }
   }
 
+  check_register_args(convention, &lua_arg_types)?;
+
   let lua_arg_types_clone = lua_arg_types.clone();
 
   // Convert lua return type
@@ -286,51 +541,62 @@ pub fn create_native_function_function<'lua>(lua: &'lua Lua, (arg_types, return_
     Some(value) => value,
     None => return Err(mlua::Error::RuntimeError("unsupported return type".to_string())),
   };
+  check_return_type(lua_ret_type)?;
 
   let lua_ret_type_clone = lua_ret_type.clone();
 
   // Type must be explicitly set, otherwise, rust doesn't know what to when splitting the fat pointer
   let native_closure: Box<dyn FnMut(u32) -> u32> = Box::new(move |args: u32| -> u32 {
-    debug!("Called native function");
+    // Same as with hooks, the closure is called from native code and must never unwind into it, errors are returned instead.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| -> Result<u32, String> {
+      debug!("Called native function");
 
-    let arg_pointer = &args as *const u32;
+      let arg_pointer = &args as *const u32;
 
-    let mut lua_args: Vec<mlua::Value> = Vec::new();
+      let mut lua_args: Vec<mlua::Value> = Vec::new();
+      let mut word = 0;
 
-    for i in 0..lua_arg_types.len() {
-      let arg_type = lua_arg_types[i];
+      for i in 0..lua_arg_types.len() {
+        let arg_type = lua_arg_types[i];
 
-      unsafe {
-        match native_to_lua(lua, arg_type, *arg_pointer.add(i)) {
-          Ok(value) => lua_args.push(value),
-          Err(e) => {
-            warn!("could not convert {} argument into lua value: {:?}", i, e);
-            panic!("could not convert raw argument into lua value: {:?}", e);
+        unsafe {
+          let arg_words = std::slice::from_raw_parts(arg_pointer.add(word), arg_type.words());
+          word += arg_type.words();
+
+          match native_words_to_lua(lua, arg_type, arg_words) {
+            Ok(value) => lua_args.push(value),
+            Err(e) => return Err(format!("could not convert argument {} into a lua value: {:?}", i, e)),
           }
         }
       }
-    }
 
-    let return_value = match lua_fn.call::<_, mlua::Value>(mlua::MultiValue::from_vec(lua_args)) {
-      Ok(value) => value,
-      Err(e) => {
-        warn!("Lua function threw unexpected error: {:?}. Panicking...", e);
-        panic!("Lua function in native wrapper threw unexpected error: {:?}", e);
-      }
-    };
+      let return_value = match lua_fn.call::<_, mlua::Value>(mlua::MultiValue::from_vec(lua_args)) {
+        Ok(value) => value,
+        Err(e) => return Err(format!("lua function threw an error: {:?}", e)),
+      };
 
     
-    let native_return_value = unsafe {
-      match lua_to_native(lua_ret_type, &return_value) {
-        Ok(value) => value,
-        Err(e) => {
-          warn!("could not convert lua return value into native value: {:?}. Panicking...", e);
-          panic!("could not convert lua return value into native value: {:?}", e);
+      let native_return_value = unsafe {
+        match lua_to_native(lua_ret_type, &return_value) {
+          Ok(value) => value,
+          Err(e) => return Err(format!("could not convert the lua return value into a native value: {:?}", e)),
         }
-      }
-    };
+      };
 
-    native_return_value[0]
+      Ok(native_return_value[0])
+    }));
+
+    match result {
+      Ok(Ok(value)) => value,
+      Ok(Err(e)) => {
+        warn!("Native function wrapping a lua function failed: {}. Returning 0", e);
+        0
+      },
+      Err(_) => {
+        error!("Native function wrapping a lua function panicked, returning 0");
+        0
+      }
+    }
   });
 
   unsafe {
@@ -344,104 +610,21 @@ pub fn create_native_function_function<'lua>(lua: &'lua Lua, (arg_types, return_
     // The wrapper acts similar to a trampoline when hooking, therefore we must manually allocate and write the function
     let closure_wrapper = VirtualAlloc(None, 100, MEM_COMMIT | MEM_RESERVE, PAGE_EXECUTE_READWRITE);
 
-    // Write the following assembly into the closure wrapper
-    // mov eax, {arg_len}
-    // mov ecx, esp
-    // mov edx, esp
-    // add ecx, eax
-    // loop:
-    // push dword [ecx]
-    // sub ecx, 0x4
-    // cmp ecx, edx
-    // jb loop
-    // push data
-    // call native_closure
-    // mov ecx, {arg_len + 0x4}
-    // add esp, ecx
-    // ret
-
-    let arg_len_in_bytes: u32 = args_len as u32 * 4;
-
-    let mut offset = 0;
-
-    // mov eax, {arg_len}
-    let store_args_in_eax_addr = closure_wrapper as *mut u8;
-    *store_args_in_eax_addr = 0xb8;
-    memory_copy(&arg_len_in_bytes as *const u32 as u32, store_args_in_eax_addr.add(1) as u32, 4);
-    offset += 5;
-
-    // Insert static instructions
-    // mov ecx, esp
-    // add ecx, eax
-    // mov edx, esp
-    // loop:
-    // push dword [ecx]
-    // sub ecx, 0x4
-    // cmp edx, ecx
-    // jb loop
-    let start_and_loop_instructions: &[u8; 15] = &[
-      0x89, 0xe1, // mov ecx, esp
-      0x01, 0xc1, // add ecx, eax
-      0x89, 0xe2, // mov edx, esp 
-      0xff, 0x31, // loop: push dword [ecx]
-      0x83, 0xe9, 0x04, // sub ecx, 0x4
-      0x39, 0xca, // cmp edx, ecx
-      0x72, 0xf7  // jb loop
-    ];
-
-    let start_and_loop_addr = closure_wrapper.add(offset) as *mut u8;
-
-    for i in 0..start_and_loop_instructions.len() {
-      *(start_and_loop_addr.add(i)) = start_and_loop_instructions[i];
-    }
-    offset += start_and_loop_instructions.len();
-
-    // push data
-    let push_data_addr = closure_wrapper.add(offset) as *mut u8;
-    *push_data_addr = 0x68u8;
-    memory_copy(&data as *const u32 as u32, push_data_addr.add(1) as u32, 4);
-    offset += 5;
-
-    // call native_closure
-    let jmp_src = closure_wrapper.add(offset + 5) as u32;
-    let jmp_dst = native_address;
-    let jmp_delta = jmp_dst as i32 - jmp_src as i32;
-    let call_closure_addr = closure_wrapper.add(offset) as *mut u8;
-    *call_closure_addr = 0xe8u8;
-    memory_copy(&jmp_delta as *const i32 as u32, call_closure_addr.add(1) as u32, 4);
-    offset += 5;
-
-    // mov ecx, {arg_len+0x4}
-    let mov_arg_len_in_ecx_addr = closure_wrapper.add(offset) as *mut u8;
-    *mov_arg_len_in_ecx_addr = 0xb9u8;
-    let args_with_data_len = arg_len_in_bytes + 4;
-    memory_copy(&args_with_data_len as *const u32 as u32, mov_arg_len_in_ecx_addr.add(1) as u32, 4);
-    offset += 5;
-
-    // End
-    // add esp, ecx,
-    // ret
-    let end_instructions: &[u8; 3] = &[
-      0x01, 0xcc, // add esp, ecx
-      0xc3, // ret
-    ];
-
-    let end_addr = closure_wrapper.add(offset) as *mut u8;
-
-    for i in 0..end_instructions.len() {
-      *(end_addr.add(i)) = end_instructions[i];
-    }
-
+    let code = closure_wrapper_code(closure_wrapper as u32, convention, &lua_arg_types_clone, data, native_address);
+    std::ptr::copy_nonoverlapping(code.as_ptr(), closure_wrapper as *mut u8, code.len());
 
     Ok(NativeFunction {
       address: closure_wrapper as u32,
       arg_types: lua_arg_types_clone,
       return_type: lua_ret_type_clone,
+      convention,
     })
   }
 }
 
-pub fn get_native_function<'lua>(_: &'lua Lua, (address, arg_types, return_type): (u32, Vec<String>, String)) -> Result<NativeFunction, mlua::Error> {
+pub fn get_native_function<'lua>(_: &'lua Lua, (address, arg_types, return_type, convention): (u32, Vec<String>, String, Option<String>)) -> Result<NativeFunction, mlua::Error> {
+  let convention = parse_convention(convention)?;
+
   let mut lua_arg_types: Vec<Type> = Vec::new();
   for arg_type in arg_types {
     match Type::try_from_str(&arg_type) {
@@ -449,13 +632,15 @@ pub fn get_native_function<'lua>(_: &'lua Lua, (address, arg_types, return_type)
       None => return Err(mlua::Error::RuntimeError("unsupported argument type".to_string())),
     }
   }
+  check_register_args(convention, &lua_arg_types)?;
 
   let lua_ret_type = match Type::try_from_str(&return_type) {
     Some(ret) => ret,
     None => return Err(mlua::Error::RuntimeError("invalid return type".to_string())),
   };
+  check_return_type(lua_ret_type)?;
 
-  let native_function = NativeFunction::new(address, lua_arg_types, lua_ret_type);
+  let native_function = NativeFunction::new(address, lua_arg_types, lua_ret_type, convention);
 
   Ok(native_function)
 }
\ No newline at end of file
@@ -1,13 +1,56 @@
-use std::{collections::HashMap, ffi::c_void, mem::{self, size_of}, sync::{Arc, Mutex}};
+use std::{collections::HashMap, ffi::c_void, mem::{self, size_of}, sync::{atomic::{AtomicU64, AtomicUsize, Ordering}, Arc, Mutex}, thread, time::{Duration, Instant}};
 use log::{debug, error, warn};
 use mlua::UserData;
-use windows::Win32::{Foundation::CloseHandle, System::{Diagnostics::ToolHelp::{CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32}, Memory::*, Threading::{GetCurrentProcessId, GetCurrentThreadId}}};
+use windows::Win32::{Foundation::{CloseHandle, HANDLE}, System::{Diagnostics::ToolHelp::{CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32}, Memory::*, Threading::{GetCurrentProcessId, GetCurrentThreadId, OpenThread, ResumeThread, SuspendThread, THREAD_SUSPEND_RESUME}}};
 use iced_x86::{Code, Decoder, DecoderOptions};
 use anyhow::{anyhow, bail};
 use lazy_static::lazy_static;
 
 lazy_static!{
   static ref HOOKS: Arc<Mutex<HashMap<u32, Arc<Mutex<Inner>>>>> = Arc::new(Mutex::new(HashMap::new()));
+  /// Functions hooked with [`install_hook`], which aren't part of any chain.
+  static ref INSTALLED_HOOKS: Mutex<Vec<InstalledHook>> = Mutex::new(Vec::new());
+  /// Trampolines and links of removed hooks, which are only freed by [`free_unused_memory`].
+  static ref UNUSED_MEMORY: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+}
+
+/// Number of hook functions that currently run, see [`HookCall`].
+static ACTIVE_HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Interval in which [`wait_for_hook_calls`] checks whether hook functions still run.
+const HOOK_CALL_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Function hooked with [`install_hook`].
+struct InstalledHook {
+  address: u32,
+  prelude: Vec<u8>,
+  trampoline: u32,
+}
+
+/// Marks that a hook function runs, for as long as it is alive.
+///
+/// Hook functions create it first, so that [`wait_for_hook_calls`] knows when no thread is inside a hook anymore.
+pub struct HookCall(());
+
+impl HookCall {
+  pub fn enter() -> HookCall {
+    ACTIVE_HOOK_CALLS.fetch_add(1, Ordering::SeqCst);
+    HookCall(())
+  }
+}
+
+impl Drop for HookCall {
+  fn drop(&mut self) {
+    ACTIVE_HOOK_CALLS.fetch_sub(1, Ordering::SeqCst);
+  }
+}
+
+/// Remember that the memory isn't used anymore, so [`free_unused_memory`] frees it.
+fn add_unused_memory(addresses: &[u32]) {
+  match UNUSED_MEMORY.lock() {
+    Ok(mut unused) => unused.extend_from_slice(addresses),
+    Err(e) => warn!("Couldn't get lock to the unused memory of hooks, leaking it: {}", e),
+  }
 }
 
 
@@ -48,6 +91,9 @@ pub unsafe fn install_hook<Fn>(target_fn_address: usize, hook_fn: Fn) -> Option<
       return None;
   }
 
+  // Copy the prelude for later restoration by unset_all_hooks
+  let prelude: Vec<u8> = std::slice::from_raw_parts(target_fn_address as *const u8, prelude_size).to_vec();
+
   let trampoline_size = prelude_size + 5;
 
   // Allocate memory to hold the trampoline
@@ -91,6 +137,11 @@ pub unsafe fn install_hook<Fn>(target_fn_address: usize, hook_fn: Fn) -> Option<
       }
   }
 
+  match INSTALLED_HOOKS.lock() {
+      Ok(mut installed) => installed.push(InstalledHook { address: target_fn_address as u32, prelude, trampoline: trampoline as u32 }),
+      Err(e) => warn!("Couldn't get lock to installed hooks, the hook of {:#08x} can't be removed: {}", target_fn_address, e),
+  }
+
   return Some(std::mem::transmute_copy(&trampoline));
 }
 
@@ -105,52 +156,237 @@ pub enum HookError {
 }
 
 
+/// Id of the next hook set on any address.
+static NEXT_HOOK_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Size of the memory allocated for a [`Link`], the indirect jump followed by its destination.
+const LINK_SIZE: usize = 16;
+
+/// Indirect jump through a cell in memory, i.e., `jmp [cell]`.
+///
+/// The hooks of a function are chained with links. Changing the destination of a link is a single aligned write,
+/// so hooks can be added to and removed from the chain while the game calls the function.
+#[derive(Debug, Clone, Copy)]
+struct Link {
+    /// Address of the jump instruction.
+    jump: u32,
+    /// Address of the jump's destination.
+    cell: u32,
+}
+
+impl Link {
+    unsafe fn new(destination: u32) -> Result<Link, HookError> {
+        let memory = VirtualAlloc(None, LINK_SIZE, MEM_COMMIT | MEM_RESERVE, PAGE_EXECUTE_READWRITE);
+        if memory.is_null() {
+            return Err(HookError::Other("could not allocate memory for a link of the hook chain".to_string()));
+        }
+
+        let jump = memory as u32;
+        let cell = jump + 8;
+
+        // jmp dword [cell]
+        *(jump as *mut u8) = 0xff;
+        *(jump as *mut u8).add(1) = 0x25;
+        memory_copy(&cell as *const u32 as u32, jump + 2, 4);
+
+        let link = Link { jump, cell };
+        link.set(destination);
+
+        Ok(link)
+    }
+
+    unsafe fn set(&self, destination: u32) {
+        std::ptr::write_volatile(self.cell as *mut u32, destination);
+    }
+}
+
+/// Patch of a hooked function, shared by all hooks of the function.
+#[derive(Debug)]
+struct Patch {
+    /// Original prelude of the function, restored when the last hook is removed.
+    prelude: Vec<u8>,
+    /// Trampoline that executes the relocated prelude and continues in the original function.
+    original: u32,
+    /// Link the function jumps through to the first hook.
+    entry: Link,
+}
+
 #[derive(Debug)]
 struct InnerHook {
-    prelude: Vec<u8>,
-    allocated_sections: Vec<u32>,
+    id: u64,
+    /// Trampoline of the hook, the destination of the previous link in the chain.
+    trampoline: u32,
+    /// Link the hook calls as the original function. Leads to the next hook or to the original function.
+    next: Link,
+    /// Name of whoever set the hook, e.g., the plugin. Used to remove all hooks of a plugin when it is unloaded.
+    owner: Option<String>,
 }
 
+/// State of all hooks on one address.
 struct Inner {
   pub address: u32,
-  pub hook: Option<InnerHook>,
+  /// Set while the function has at least one hook.
+  patch: Option<Patch>,
+  /// Hooks in the order they are called.
+  ///
+  /// The function calls the first hook, each hook's original function is the next hook,
+  /// and the original function of the last hook is the hooked function.
+  hooks: Vec<InnerHook>,
+}
+
+impl Inner {
+    /// Patch the function to jump to the chain of hooks, if it isn't patched yet.
+    unsafe fn patch(&mut self) -> Result<&Patch, HookError> {
+        if self.patch.is_none() {
+            self.patch = Some(patch_function(self.address)?);
+        }
+
+        Ok(self.patch.as_ref().unwrap())
+    }
+
+    /// Append the hook to the end of the chain, right before the original function.
+    ///
+    /// The function must already be patched. Returns the hook's id.
+    unsafe fn push(&mut self, trampoline: u32, next: Link) -> Result<u64, HookError> {
+        let patch = self.patch.as_ref().ok_or(HookError::NotHooked)?;
+
+        // Complete the new hook before the chain leads to it
+        next.set(patch.original);
+
+        let previous = match self.hooks.last() {
+            Some(hook) => hook.next,
+            None => patch.entry,
+        };
+        previous.set(trampoline);
+
+        let id = NEXT_HOOK_ID.fetch_add(1, Ordering::SeqCst);
+        self.hooks.push(InnerHook { id, trampoline, next, owner: None });
+
+        Ok(id)
+    }
+
+    /// Remove the hook from the chain. Restores the function's prelude if it was the last hook.
+    unsafe fn remove(&mut self, id: u64) -> Result<(), HookError> {
+        let index = self.hooks.iter().position(|hook| hook.id == id).ok_or(HookError::NotHooked)?;
+
+        if self.hooks.len() == 1 {
+            self.unpatch();
+            let hook = self.hooks.remove(0);
+            add_unused_memory(&[hook.trampoline, hook.next.jump]);
+            return Ok(());
+        }
+
+        let patch = self.patch.as_ref().ok_or(HookError::NotHooked)?;
+
+        let previous = match index {
+            0 => patch.entry,
+            _ => self.hooks[index - 1].next,
+        };
+        let following = match self.hooks.get(index + 1) {
+            Some(hook) => hook.trampoline,
+            None => patch.original,
+        };
+
+        // A thread that is inside the removed hook still continues through its link, so the link stays intact
+        previous.set(following);
+        let hook = self.hooks.remove(index);
+        add_unused_memory(&[hook.trampoline, hook.next.jump]);
+
+        Ok(())
+    }
+
+    /// Restore the original prelude of the function.
+    ///
+    /// The prelude is restored while all other threads are suspended, so no thread executes a half-restored prelude.
+    unsafe fn unpatch(&mut self) {
+        let patch = match &self.patch {
+            None => return,
+            Some(patch) => patch,
+        };
+
+        let threads = suspend_other_threads();
+
+        // Other threads are suspended and might hold the heap's lock, therefore, nothing must be allocated until they are resumed.
+        let prelude_ptr = self.address as *mut u8;
+
+        for idx in 0..patch.prelude.len() {
+            *(prelude_ptr.add(idx)) = patch.prelude[idx];
+        }
+
+        resume_threads(threads);
+
+        add_unused_memory(&[patch.original, patch.entry.jump]);
+        self.patch = None;
+    }
 }
 
 pub struct Hook {
   inner: Arc<Mutex<Inner>>,
+  /// Id of the hook in the chain of its address, while the hook is set.
+  id: Option<u64>,
+}
+
+/// Size of the memory allocated for the trampoline to the original function.
+/// 
+/// Relocated instructions can be larger than the original ones (a short jump becomes a near jump).
+/// Therefore, the trampoline can be larger than the prelude plus the jump back to the original function.
+const TARGET_TRAMPOLINE_SIZE: usize = 64;
+
+/// Prelude of a function that was relocated to a new address.
+struct PatchedPrelude {
+    /// Instructions of the prelude patched to work at the new address.
+    bytes: Vec<u8>,
+    /// Size of the prelude in the original function.
+    original_size: usize,
 }
 
-unsafe fn get_patched_prelude(address: u32, required_size: usize, new_address: u32) -> Result<Vec<u8>, HookError> {
+unsafe fn get_patched_prelude(address: u32, required_size: usize, new_address: u32) -> Result<PatchedPrelude, HookError> {
       let target_fn_data = std::slice::from_raw_parts(address as *mut u8, 20);
       let mut decoder = Decoder::with_ip(32, target_fn_data, address as u64, DecoderOptions::NONE);
       let mut prelude_size = 0;
       let mut patched_prelude: Vec<u8> = Vec::new();
+      let mut branch_targets: Vec<u32> = Vec::new();
 
       for instruction in &mut decoder {
           if instruction.is_invalid() {
               return Err(HookError::InvalidTarget);
           }
 
-          match instruction.code() {
-              Code::Call_rel32_32 => {
-                  // A relative cannot be simply moved around.
-                  // Moving it around would change the call destination.
-                  // We have to patch the destination address.
-                  // Since we don't know yet where the trampoline is stored at, we cannot patch the destination address.
-                  // Instead we get the absolute destination address and convert the relative call to an absolut call.
+          // These branches only exist in their short form and can't be converted into a near branch
+          if instruction.is_loop() || instruction.is_loopcc() || instruction.is_jcx_short() {
+              return Err(HookError::InvalidTarget);
+          }
+
+          // Relative branches cannot be simply moved around.
+          // Moving them around would change their destination.
+          // We have to patch the destination address.
+          // Short branches might not reach their destination from the new address, therefore, they are converted
+          // into their near form. This changes the size of the instruction, so the new source is calculated
+          // from the already patched prelude and not from the original one.
+          let relocated_opcode: Option<Vec<u8>> = match instruction.code() {
+              Code::Call_rel32_32 => Some(vec![0xe8]),
+              Code::Jmp_rel8_32 | Code::Jmp_rel32_32 => Some(vec![0xe9]),
+              _ if instruction.is_jcc_short() => Some(vec![0x0f, 0x80 | (target_fn_data[prelude_size] & 0x0f)]),
+              _ if instruction.is_jcc_near() => Some(vec![0x0f, 0x80 | (target_fn_data[prelude_size + 1] & 0x0f)]),
+              _ => None,
+          };
+
+          match relocated_opcode {
+              Some(opcode) => {
                   let target_address = instruction.near_branch32();
+                  branch_targets.push(target_address);
 
-                  let new_source = new_address + prelude_size as u32 + 5;
+                  let new_source = new_address + (patched_prelude.len() + opcode.len()) as u32 + 4;
                   let new_relative_target: i32 = target_address as i32 - new_source as i32;
 
-                  patched_prelude.push(0xe8);
+                  patched_prelude.extend(opcode);
 
                   let target_bytes = new_relative_target.to_le_bytes();
                   for b in target_bytes {
                       patched_prelude.push(b);
                   }
               }
-              _ => {
+              None => {
                   for i in prelude_size..prelude_size+instruction.len() {
                       patched_prelude.push(target_fn_data[i]);
                   }
@@ -168,7 +404,193 @@ unsafe fn get_patched_prelude(address: u32, required_size: usize, new_address: u
           return Err(HookError::TargetTooShort);
       }
 
-      Ok(patched_prelude)
+      // The prelude is overwritten by the jump to the hooks, so branches into it would land in the middle of the jump
+      let prelude = address..address + prelude_size as u32;
+      if branch_targets.iter().any(|target| prelude.contains(target)) {
+          return Err(HookError::InvalidTarget);
+      }
+
+      Ok(PatchedPrelude { bytes: patched_prelude, original_size: prelude_size })
+}
+
+/// Relocate the function's prelude into a trampoline and patch the function to jump through a link to its hooks.
+///
+/// The link initially leads to the trampoline, i.e., the function behaves as before until the first hook is added.
+unsafe fn patch_function(address: u32) -> Result<Patch, HookError> {
+    let mut prelude_size = 0;
+    let required_bytes = 5;
+
+    let target_fn_data = std::slice::from_raw_parts(address as *mut u8, 20);
+    let mut decoder = Decoder::with_ip(32, target_fn_data, address as u64, DecoderOptions::NONE);
+
+    for instruction in &mut decoder {
+        prelude_size += instruction.len();
+
+        if instruction.is_invalid() {
+            return Err(HookError::InvalidTarget);
+        }
+
+        if prelude_size >= required_bytes {
+            break
+        }
+    }
+
+    if prelude_size < required_bytes {
+        return Err(HookError::TargetTooShort);
+    }
+
+    // Allocate memory to hold the trampoline
+    // The trampoline will contain the relocated prelude of the target function and
+    // 5 additional bytes to jump to the original function
+    let target_trampoline = VirtualAlloc(None, TARGET_TRAMPOLINE_SIZE, MEM_COMMIT | MEM_RESERVE, PAGE_EXECUTE_READWRITE);
+
+    // Set permissions on memory of target function to be able to write into it
+    let mut old_protect: PAGE_PROTECTION_FLAGS = Default::default();
+    VirtualProtect(address as *const c_void, 1024, PAGE_EXECUTE_READWRITE,&mut old_protect as *mut PAGE_PROTECTION_FLAGS).unwrap();
+
+    let patched_prelude = match get_patched_prelude(address, required_bytes, target_trampoline as u32) {
+        Ok(patched_prelude) => patched_prelude,
+        Err(e) => {
+            let _ = VirtualFree(target_trampoline, 0, MEM_RELEASE);
+            return Err(e);
+        },
+    };
+    let patched_prelude_size = patched_prelude.bytes.len();
+    prelude_size = patched_prelude.original_size;
+
+    // For some reason std::ptr::copy_nonoverlapping doesn't work here to copy the prelude from the target to the trampoline
+    // because it doesn't copy the first byte correctly.
+    for i in 0..patched_prelude_size {
+        *((target_trampoline as *mut u8).add(i)) = patched_prelude.bytes[i];
+    }
+
+    // Calculate the distance between the hook function and the target function
+    let target_trampoline_dst = address as usize + prelude_size;
+    let target_trampoline_src = target_trampoline as usize + patched_prelude_size + 5;
+    let target_trampoline_delta = target_trampoline_dst as isize - target_trampoline_src as isize;
+
+    // Manually write the instructions into the trampoline memory to jump to the original function
+    let target_trampoline_jmp_address = target_trampoline.add(patched_prelude_size) as *mut u8;
+    *target_trampoline_jmp_address = 0xe9u8;
+
+    // Write the jump address into the trampoline
+    memory_copy(&target_trampoline_delta as *const isize as u32, (target_trampoline as usize + patched_prelude_size + 1) as u32, 4);
+
+    let entry = Link::new(target_trampoline as u32)?;
+
+    // Copy the prelude for later restoration
+    let mut prelude_copy: Vec<u8> = Vec::new();
+    for i in 0..prelude_size {
+      prelude_copy.push(*(address as *const u8).add(i));
+    }
+
+    let jmp_dst = entry.jump as usize;
+    let jmp_src = address as usize + 5;
+    let jmp_delta = jmp_dst as isize - jmp_src as isize;
+
+    // Write jmp instruction from target to the chain's entry into first bytes of target function
+    let target_jmp_address = address as *mut u8;
+    *target_jmp_address = 0xe9;
+    memory_copy(&jmp_delta as *const isize as u32, (address as usize + 1) as u32, 4);
+
+    // If prelude is larger than 5 bytes, fill the left over bytes with noops to avoid broken instructions
+    if prelude_size > 5 {
+        for n in 5..prelude_size {
+            *(address as *mut u8).add(n) = 0x90;
+        }
+    }
+
+    Ok(Patch {
+        prelude: prelude_copy,
+        original: target_trampoline as u32,
+        entry,
+    })
+}
+
+/// Create the trampoline that calls the hook function.
+///
+/// Copy stack frame of caller without the actual return address.
+/// We cannot rely on ebp to determine the stack frame size, since I identified at least one
+/// function call where ebp is not used as a frame pointer.
+/// Instead, we use a static and hard-coded size of 50 addresses (200 bytes or 50 parameter).
+/// In the future, we might give the developer the option to determine size manually.
+/// Instead push the link to the next hook of the chain onto the stack.
+/// If `data` is set, e.g., for closures, also push it.
+/// Then, call the hook.
+/// When the hook returns, clean the stack
+/// Otherwise, we cannot conform to calling conventions
+///
+/// Assembly
+/// --------
+/// ```asm
+/// push ebx  // Store ebx to restore it later, ebx is used to hold the stack frame size to use after calling the hook.
+///           // However, ebx is call-preserved so we must restore it before returning
+/// mov ebx, esp  // Store the target stack address in ebx
+/// add ebx, 0x4  // Ignore return address
+/// mov eax, esp  // Store source address to copy stack memory from in eax, is incremented in every iteration until it reaches ebx
+/// add eax, 0xc8
+/// loop:
+/// push [eax]  // Push one address from stack frame of caller to stack
+/// sub eax, 0x4  // Load next address
+/// cmp eax, ebx  // Check if target address reached (ebx)
+/// lt loop
+/// push next
+/// push data  // Only if data is set
+/// call hook
+/// mov esp, ebx  // Clean up stack pointer
+/// add esp, 0x4
+/// pop ebx  // Restore ebx
+/// ret
+/// ```
+unsafe fn create_hook_trampoline(next: u32, data: Option<u32>, hook_fn: u32) -> u32 {
+    let hook_trampoline = VirtualAlloc(None, 50, MEM_COMMIT | MEM_RESERVE, PAGE_EXECUTE_READWRITE);
+
+    let hook_trampoline_first: [u8; 23] = [0x53, 0x89, 0xe3, 0x83, 0xc3, 0x04, 0x89, 0xe0, 0x05, 0xc8, 0x00, 0x00, 0x00, 0xff, 0x30, 0x83, 0xe8, 0x04, 0x39, 0xd8, 0x7f, 0xf7, 0x68];
+    let hook_trampoline_second: [u8; 1] = [0xe8];
+    let hook_trampoline_third: [u8; 7] = [0x89, 0xdc, 0x83, 0xec, 0x04, 0x5b, 0xc3];
+
+    let mut current_offset = 0;
+
+    for i in 0..hook_trampoline_first.len() {
+        let trampoline_address = hook_trampoline.add(i) as *mut u8;
+        *trampoline_address = hook_trampoline_first[i];
+    }
+
+    current_offset += hook_trampoline_first.len();
+
+    memory_copy(&next as *const u32 as u32, hook_trampoline.add(current_offset) as u32, 4);
+    current_offset += 4;
+
+    // Push data
+    if let Some(data) = data {
+        let push_data_instruction_address = hook_trampoline.add(current_offset) as *mut u8;
+        *push_data_instruction_address = 0x68;
+        current_offset += 1;
+        memory_copy(&data as *const u32 as u32, hook_trampoline.add(current_offset) as u32, 4);
+        current_offset += 4;
+    }
+
+    for i in 0..hook_trampoline_second.len() {
+        let trampoline_address = hook_trampoline.add(current_offset + i) as *mut u8;
+        *trampoline_address = hook_trampoline_second[i];
+    }
+
+    current_offset += hook_trampoline_second.len();
+
+    let hook_trampoline_jump_dst = hook_fn;
+    let hook_trampoline_jump_src = hook_trampoline.add(current_offset + 4);
+    let hook_trampoline_jump_delta = hook_trampoline_jump_dst as isize - hook_trampoline_jump_src as isize;
+
+    memory_copy(&hook_trampoline_jump_delta as *const isize as u32, hook_trampoline.add(current_offset) as u32, 4);
+
+    current_offset += 4;
+
+    for i in 0..hook_trampoline_third.len() {
+        let trampoline_address = hook_trampoline.add(current_offset + i) as *mut u8;
+        *trampoline_address = hook_trampoline_third[i];
+    }
+
+    hook_trampoline as u32
 }
 
 impl Hook {
@@ -185,7 +607,7 @@ impl Hook {
                   Some(inner) => inner.clone(),
                   None => {
                       debug!("No reference yet, creating new one");
-                      let inner = Arc::new(Mutex::new(Inner{address, hook: None}));
+                      let inner = Arc::new(Mutex::new(Inner{address, patch: None, hooks: Vec::new()}));
 
                       hooks.insert(address, inner.clone());
                       inner
@@ -196,20 +618,27 @@ impl Hook {
 
 
       debug!("Created hook instance");
-      Hook{inner}        
+      Hook{inner, id: None}
   }
 
   /// Sets the hook using a closure.
   /// 
   /// The parameter `closure_address` should be the address to the closure with the FnMut trait.
   /// It is expected to be fat pointer.
+  ///
+  /// If the function already has hooks, the hook is added to the end of their chain.
+  /// Its original function is then the original function of the function.
   pub unsafe fn set_closure<T: ?Sized>(&mut self, closure: Box<T>) -> Result<(), HookError> {
       let mut inner = self.inner.lock().map_err(|e| HookError::Other(format!("{}", e)))?;
 
-      if let Some(_) = inner.hook {
+      if self.id.is_some() {
           return Err(HookError::AlreadyHooked);
       }
 
+      // Patch the function first, so that the closure isn't leaked if the function can't be hooked
+      let next = Link::new(0)?;
+      inner.patch()?;
+
       let boxed_closure_address = Box::into_raw(closure);
 
       // Split fat pointer of closure address into data and vtable part
@@ -219,376 +648,298 @@ impl Hook {
       // Layout is: DropInPlace + Length + Align + FnOnce + FnMut
       let hook_address = *vtable.add(4);
 
-      let _ = Some(boxed_closure_address as *const () as u32);
-
-      let mut prelude_size = 0;
-      let required_bytes = 5;
-
-      let target_fn_data = std::slice::from_raw_parts(inner.address as *mut u8, 20);
-      let mut decoder = Decoder::with_ip(32, target_fn_data, inner.address as u64, DecoderOptions::NONE);
-
-      for instruction in &mut decoder {
-          prelude_size += instruction.len();
-
-          if instruction.is_invalid() {
-              return Err(HookError::InvalidTarget);
-          }
-
-          if prelude_size >= required_bytes {
-              break
-          }
-      }
-
-      if prelude_size < required_bytes {
-          return Err(HookError::TargetTooShort);
-      }
-
-      let trampoline_size = prelude_size + 5;
+      let hook_trampoline = create_hook_trampoline(next.jump, Some(data), hook_address);
+      self.id = Some(inner.push(hook_trampoline, next)?);
 
-      // Keep track of allocated memory sections
-      let mut allocated_sections: Vec<u32> = Vec::new();
-
-      // Allocate memory to hold the trampoline
-      // The trampoline will contain the first prelude_size bytes from the target function and
-      // 5 additional bytes to jump to the original function
-      let target_trampoline = VirtualAlloc(None, trampoline_size, MEM_COMMIT | MEM_RESERVE, PAGE_EXECUTE_READWRITE);
-      allocated_sections.push(target_trampoline as u32);
+      Ok(())
+  }
 
-      // Set permissions on memory of target function to be able to write into it
-      let mut old_protect: PAGE_PROTECTION_FLAGS = Default::default();
-      VirtualProtect(inner.address as *const c_void, 1024, PAGE_EXECUTE_READWRITE,&mut old_protect as *mut PAGE_PROTECTION_FLAGS).unwrap();
-      
-      let patched_prelude = get_patched_prelude(inner.address, required_bytes, target_trampoline as u32)?;
-      prelude_size = patched_prelude.len();
+  /// Sets the hook to the function at `hook_fn`, which gets the original function as its first argument.
+  ///
+  /// If the function already has hooks, the hook is added to the end of their chain.
+  pub unsafe fn stack_aware_set_hook(&mut self, hook_fn: u32) -> Result<(), HookError> {
+      let mut inner = self.inner.lock().map_err(|e| HookError::Other(format!("{}", e.to_string())))?;
 
-      // For some reason std::ptr::copy_nonoverlapping doesn't work here to copy the prelude from the target to the trampoline
-      // because it doesn't copy the first byte correctly.
-      for i in 0..prelude_size {
-        *((target_trampoline as *mut u8).add(i)) = patched_prelude[i];
+      if self.id.is_some() {
+          return Err(HookError::AlreadyHooked);
       }
 
-      // Calculate the distance between the hook function and the target function
-      let target_trampoline_dst = inner.address as usize + prelude_size;
-      let target_trampoline_src = target_trampoline as usize + trampoline_size;
-      let target_trampoline_delta = target_trampoline_dst as isize - target_trampoline_src as isize;
-
-      // Manually write the instructions into the trampoline memory to jump to the original function
-      let target_trampoline_jmp_address = target_trampoline.add(prelude_size) as *mut u8;
-      *target_trampoline_jmp_address = 0xe9u8;
-
-      // Write the jump address into the trampoline
-      memory_copy(&target_trampoline_delta as *const isize as *const u8 as u32, (target_trampoline as usize + prelude_size as usize + 1) as *mut u8 as u32, 4);
-
-      // New approach
-      // Copy stack frame of caller without the actual return address.
-      // We cannot rely on ebp to determine the stack frame size, since I identified at least one
-      // function call where ebp is not used as a frame pointer.
-      // Instead, we use a static and hard-coded size of 50 addresses (200 bytes or 50 parameter).
-      // In the future, we might give the developer the option to determine size manually.
-      // Instead push the trampoline onto the stack.
-      // Then, call the hook.
-      // When the hook returns, clean the stack
-      // Otherwise, we cannot conform to calling conventions
-      // Assembly
-      // --------
-      // push ebx  // Store ebx to restore it later, ebx is used to hold the stack frame size to use after calling the hook.
-      //           // However, ebx is call-preserved so we must restore it before returning
-      // mov ebx, esp  // Store the target stack address in ebx
-      // add ebx, 0x4  // Ignore return address
-      // mov eax, esp  // Store source address to copy stack memory from in eax, is incremented in every iteration until it reaches ebx
-      // add eax, 0xc8
-      // loop:
-      // push [eax]  // Push one address from stack frame of caller to stack
-      // sub eax, 0x4  // Load next address
-      // cmp eax, ebx  // Check if target address reached (ebx)
-      // lt loop
-      // push trampoline
-      // push data
-      // call closure_hook
-      // mov esp, ebx  // Clean up stack pointer
-      // add esp, 0x4
-      // pop ebx  // Restore ebx
-      // ret
-      let hook_trampoline = VirtualAlloc(None, 50, MEM_COMMIT | MEM_RESERVE, PAGE_EXECUTE_READWRITE);
-      allocated_sections.push(hook_trampoline as u32);
-
-      let hook_trampoline_first: [u8; 23] = [0x53, 0x89, 0xe3, 0x83, 0xc3, 0x04, 0x89, 0xe0, 0x05, 0xc8, 0x00, 0x00, 0x00, 0xff, 0x30, 0x83, 0xe8, 0x04, 0x39, 0xd8, 0x7f, 0xf7, 0x68];
-      let hook_trampoline_second: [u8; 1] = [0xe8];
-      let hook_trampoline_third: [u8; 7] = [0x89, 0xdc, 0x83, 0xec, 0x04, 0x5b, 0xc3];
-
-      //let hook_trampoline_start: [u8; 2] = [0x5b, 0x68];
-      let hook_trampoline_jump_address: u32 = target_trampoline as u32;
-      //let hook_trampoline_jump: [u8; 1] = [0xe8];
-      //let hook_trampoline_end: [u8; 5] = [0x83, 0xc4, 0x04, 0x53, 0xc3];
-
-      let mut current_offset = 0;
-
-      for i in 0..hook_trampoline_first.len() {
-          let trampoline_address = hook_trampoline.add(i) as *mut u8;
-          *trampoline_address = hook_trampoline_first[i];
-      }
+      let next = Link::new(0)?;
+      inner.patch()?;
 
-      current_offset += hook_trampoline_first.len();
+      let hook_trampoline = create_hook_trampoline(next.jump, None, hook_fn);
+      self.id = Some(inner.push(hook_trampoline, next)?);
 
-      memory_copy(&hook_trampoline_jump_address as *const u32 as u32, hook_trampoline.add(current_offset) as *mut u32 as u32, 4);
-      current_offset += 4;
+      Ok(())
+  }
 
-      // Push data
-      let push_data_instruction_address = hook_trampoline.add(current_offset) as *mut u8;
-      *push_data_instruction_address = 0x68;
-      current_offset += 1;
-      memory_copy(&data as *const u32 as u32, hook_trampoline.add(current_offset) as u32, 4);
-      current_offset += 4;
+  /// Remove the hook from the chain of hooks on its function.
+  ///
+  /// Other hooks on the same function stay. Removing the last hook restores the original prelude of the function.
+  /// The trampolines are only freed by [`free_unused_memory`] and the closure is intentionally never freed.
+  /// A thread might still be inside the hook, e.g., waiting for a lua hook to return, and would otherwise return into freed memory.
+  pub unsafe fn unset_hook(&mut self) -> Result<(), HookError> {
+    let mut inner = self.inner.lock().map_err(|e| HookError::Other(format!("Could not get lock to inner hook: {}", e)))?;
 
-      for i in 0..hook_trampoline_second.len() {
-          let trampoline_address = hook_trampoline.add(current_offset + i) as *mut u8;
-          *trampoline_address = hook_trampoline_second[i];
-      }
+    let id = self.id.ok_or(HookError::NotHooked)?;
+    self.id = None;
 
-      current_offset += hook_trampoline_second.len();
+    inner.remove(id)
+  }
 
-      let hook_trampoline_jump_dst = hook_address;
-      let hook_trampoline_jump_src = hook_trampoline.add(current_offset + 4);
-      let hook_trampoline_jump_delta = hook_trampoline_jump_dst as isize - hook_trampoline_jump_src as isize;
+  /// Mark the hook as set by `owner`, so that [`unset_hooks_of`] removes it.
+  pub fn set_owner(&mut self, owner: &str) -> Result<(), HookError> {
+    let mut inner = self.inner.lock().map_err(|e| HookError::Other(format!("Could not get lock to inner hook: {}", e)))?;
 
-      memory_copy(&hook_trampoline_jump_delta as *const isize as u32, hook_trampoline.add(current_offset) as u32, 4);
+    let hook = match self.id {
+        None => None,
+        Some(id) => inner.hooks.iter_mut().find(|hook| hook.id == id),
+    };
 
-      current_offset += 4;
+    match hook {
+        None => Err(HookError::NotHooked),
+        Some(hook) => {
+            hook.owner = Some(owner.to_string());
+            Ok(())
+        },
+    }
+  }
+}
 
-      for i in 0..hook_trampoline_third.len() {
-          let trampoline_address = hook_trampoline.add(current_offset + i) as *mut u8;
-          *trampoline_address = hook_trampoline_third[i];
+/// Remove all hooks that were set by `owner`.
+///
+/// Hooks of others on the same functions stay in place.
+/// Returns the number of removed hooks.
+///
+/// # Safety
+///
+/// The caller must not hold the lock of any hook. Preludes are restored while all other threads are suspended, so the
+/// caller must not hold a lock, e.g., of the heap, that another thread needs before it can be resumed.
+/// A thread that is still running in a removed hook's trampoline continues through it to the original function, as its
+/// memory is only freed by [`free_unused_memory`].
+pub unsafe fn unset_hooks_of(owner: &str) -> usize {
+  let inners: Vec<Arc<Mutex<Inner>>> = match HOOKS.lock() {
+      Ok(hooks) => hooks.values().cloned().collect(),
+      Err(e) => {
+          error!("Couldn't get lock to hooks: {}", e.to_string());
+          return 0;
       }
+  };
 
-      // Create a copy of the prelude to be able to restore it later
-      let mut prelude_copy: Vec<u8> = Vec::new();
-      for i in 0..prelude_size {
-        prelude_copy.push(*(inner.address as *const u8).add(i));
-      }
+  let mut removed = 0;
 
-      let jmp_dst = hook_trampoline;
-      let jmp_src = inner.address as usize + 5;
-      let jmp_delta = jmp_dst as isize - jmp_src as isize;
+  for inner in inners {
+      let mut inner = match inner.lock() {
+          Ok(inner) => inner,
+          Err(e) => {
+              warn!("Couldn't get lock to inner hook: {}", e);
+              continue;
+          }
+      };
 
-      // Write jmp instruction from target to hook into first bytes of target function
-      let target_jmp_address = inner.address as *mut u8;
-      *target_jmp_address = 0xe9;
-      memory_copy(&jmp_delta as *const isize as u32, (inner.address as usize + 1) as u32, 4);
+      let owned: Vec<u64> = inner.hooks.iter()
+          .filter(|hook| hook.owner.as_deref() == Some(owner))
+          .map(|hook| hook.id)
+          .collect();
 
-      // If prelude is larger than 5 bytes, fill the left over bytes with noops to avoid broken instructions
-      if prelude_size > 5 {
-          for n in 5..prelude_size {
-              *(inner.address as *mut u8).add(n) = 0x90;
+      for id in owned {
+          match inner.remove(id) {
+              Ok(_) => removed += 1,
+              Err(e) => warn!("Could not remove hook of {:#08x}: {:?}", inner.address, e),
           }
       }
-
-      inner.hook = Some(InnerHook {
-        prelude: prelude_copy,
-        allocated_sections: allocated_sections,
-      });
-
-      Ok(())
   }
 
-  pub unsafe fn stack_aware_set_hook(&mut self, hook_fn: u32) -> Result<(), HookError> {
-      let mut inner = self.inner.lock().map_err(|e| HookError::Other(format!("{}", e.to_string())))?;
+  removed
+}
 
-      if let Some(_) = inner.hook {
-          return Err(HookError::AlreadyHooked);
+/// Remove all hooks and restore the preludes of all hooked functions, including the ones hooked with [`install_hook`].
+///
+/// Used to tear down the mod, so that it can be ejected from the game. Hooks that are still set can't be unset afterwards.
+/// Returns the number of removed hooks.
+///
+/// # Safety
+///
+/// Same as [`unset_hooks_of`]: other threads are suspended while preludes are restored, and threads that are still
+/// running in a hook's trampoline or hook function keep running until they return, since nothing is freed here.
+/// No hook must be set concurrently, as it would be left in place with the functions restored around it.
+pub unsafe fn unset_all_hooks() -> usize {
+  let inners: Vec<Arc<Mutex<Inner>>> = match HOOKS.lock() {
+      Ok(hooks) => hooks.values().cloned().collect(),
+      Err(e) => {
+          error!("Couldn't get lock to hooks: {}", e.to_string());
+          Vec::new()
       }
+  };
 
-      let mut allocated_sections: Vec<u32> = Vec::new();
-
-      let mut prelude_size = 0;
-      let required_bytes = 5;
-
-      let target_fn_data = std::slice::from_raw_parts(inner.address as *mut u8, 20);
-      let mut decoder = Decoder::with_ip(32, target_fn_data, inner.address as u64, DecoderOptions::NONE);
-
-      for instruction in &mut decoder {
-          prelude_size += instruction.len();
+  let mut removed = 0;
 
-          if instruction.is_invalid() {
-              return Err(HookError::InvalidTarget);
+  for inner in inners {
+      let mut inner = match inner.lock() {
+          Ok(inner) => inner,
+          Err(e) => {
+              warn!("Couldn't get lock to inner hook: {}", e);
+              continue;
           }
+      };
 
-          if prelude_size >= required_bytes {
-              break
+      let ids: Vec<u64> = inner.hooks.iter().map(|hook| hook.id).collect();
+
+      for id in ids {
+          match inner.remove(id) {
+              Ok(_) => removed += 1,
+              Err(e) => warn!("Could not remove hook of {:#08x}: {:?}", inner.address, e),
           }
       }
+  }
 
-      if prelude_size < required_bytes {
-          return Err(HookError::TargetTooShort);
+  let installed = match INSTALLED_HOOKS.lock() {
+      Ok(mut installed) => mem::take(&mut *installed),
+      Err(e) => {
+          error!("Couldn't get lock to installed hooks: {}", e);
+          Vec::new()
       }
+  };
 
-      let trampoline_size = prelude_size + 5;
-
-      // Allocate memory to hold the trampoline
-      // The trampoline will contain the first prelude_size bytes from the target function and
-      // 5 additional bytes to jump to the original function
-      let target_trampoline = VirtualAlloc(None, trampoline_size, MEM_COMMIT | MEM_RESERVE, PAGE_EXECUTE_READWRITE);
-      allocated_sections.push(target_trampoline as u32);
-
-      // Set permissions on memory of target function to be able to write into it
-      let mut old_protect: PAGE_PROTECTION_FLAGS = Default::default();
-      VirtualProtect(inner.address as *const c_void, 1024, PAGE_EXECUTE_READWRITE,&mut old_protect as *mut PAGE_PROTECTION_FLAGS).unwrap();
-      
-      // For some reason std::ptr::copy_nonoverlapping doesn't work here to copy the prelude from the target to the trampoline
-      // because it doesn't copy the first byte correctly.
-      for i in 0..prelude_size {
-          *((target_trampoline as *mut u8).add(i)) = target_fn_data[i];
-      }
+  for hook in installed {
+      let threads = suspend_other_threads();
 
-      // Calculate the distance between the hook function and the target function
-      let target_trampoline_dst = inner.address as usize + prelude_size;
-      let target_trampoline_src = target_trampoline as usize + trampoline_size;
-      let target_trampoline_delta = target_trampoline_dst as isize - target_trampoline_src as isize;
-
-      // Manually write the instructions into the trampoline memory to jump to the original function
-      let target_trampoline_jmp_address = target_trampoline.add(prelude_size) as *mut u8;
-      *target_trampoline_jmp_address = 0xe9u8;
-
-      // Write the jump address into the trampoline
-      memory_copy(&target_trampoline_delta as *const isize as u32, (target_trampoline as usize + prelude_size as usize + 1) as u32, 4);
-
-      // New approach
-      // Copy stack frame of caller without the actual return address.
-      // We cannot rely on ebp to determine the stack frame size, since I identified at least one
-      // function call where ebp is not used as a frame pointer.
-      // Instead, we use a static and hard-coded size of 50 addresses (200 bytes or 50 parameter).
-      // In the future, we might give the developer the option to determine size manually.
-      // Instead push the trampoline onto the stack.
-      // Then, call the hook.
-      // When the hook returns, clean the stack
-      // Otherwise, we cannot conform to calling conventions
-      // Assembly
-      // --------
-      // push ebx  // Store ebx to restore it later, ebx is used to hold the stack frame size to use after calling the hook.
-      //           // However, ebx is call-preserved so we must restore it before returning
-      // mov ebx, esp  // Store the target stack address in ebx
-      // add ebx, 0x4  // Ignore return address
-      // mov eax, esp  // Store source address to copy stack memory from in eax, is incremented in every iteration until it reaches ebx
-      // add eax, 0xc8
-      // loop:
-      // push [eax]  // Push one address from stack frame of caller to stack
-      // sub eax, 0x4  // Load next address
-      // cmp eax, ebx  // Check if target address reached (ebx)
-      // lt loop
-      // push trampoline
-      // call hook
-      // mov esp, ebx  // Clean up stack pointer
-      // add esp, 0x4
-      // pop ebx  // Restore ebx
-      // ret
-      let hook_trampoline = VirtualAlloc(None, 50, MEM_COMMIT | MEM_RESERVE, PAGE_EXECUTE_READWRITE);
-      allocated_sections.push(hook_trampoline as u32);
-
-      let hook_trampoline_first: [u8; 23] = [0x53, 0x89, 0xe3, 0x83, 0xc3, 0x04, 0x89, 0xe0, 0x05, 0xc8, 0x00, 0x00, 0x00, 0xff, 0x30, 0x83, 0xe8, 0x04, 0x39, 0xd8, 0x7f, 0xf7, 0x68];
-      let hook_trampoline_second: [u8; 1] = [0xe8];
-      let hook_trampoline_third: [u8; 7] = [0x89, 0xdc, 0x83, 0xec, 0x04, 0x5b, 0xc3];
-
-      //let hook_trampoline_start: [u8; 2] = [0x5b, 0x68];
-      let hook_trampoline_jump_address: u32 = target_trampoline as u32;
-      //let hook_trampoline_jump: [u8; 1] = [0xe8];
-      //let hook_trampoline_end: [u8; 5] = [0x83, 0xc4, 0x04, 0x53, 0xc3];
-
-      let mut current_offset = 0;
-
-      for i in 0..hook_trampoline_first.len() {
-          let trampoline_address = hook_trampoline.add(i) as *mut u8;
-          *trampoline_address = hook_trampoline_first[i];
+      // Same as in Inner::unpatch, nothing must be allocated until the threads are resumed
+      for idx in 0..hook.prelude.len() {
+          *(hook.address as *mut u8).add(idx) = hook.prelude[idx];
       }
 
-      current_offset += hook_trampoline_first.len();
+      resume_threads(threads);
+
+      add_unused_memory(&[hook.trampoline]);
+      removed += 1;
+  }
+
+  removed
+}
 
-      memory_copy(&hook_trampoline_jump_address as *const u32 as u32, hook_trampoline.add(current_offset) as u32, 4);
-      current_offset += 4;
+/// Wait until no hook function runs anymore, i.e., no [`HookCall`] is alive.
+///
+/// Returns `false` if hook functions still run after the timeout.
+pub fn wait_for_hook_calls(timeout: Duration) -> bool {
+  let start = Instant::now();
 
-      for i in 0..hook_trampoline_second.len() {
-          let trampoline_address = hook_trampoline.add(current_offset + i) as *mut u8;
-          *trampoline_address = hook_trampoline_second[i];
+  while ACTIVE_HOOK_CALLS.load(Ordering::SeqCst) > 0 {
+      if start.elapsed() > timeout {
+          return false;
       }
 
-      current_offset += hook_trampoline_second.len();
+      thread::sleep(HOOK_CALL_POLL_INTERVAL);
+  }
 
-      let hook_trampoline_jump_dst = hook_fn;
-      let hook_trampoline_jump_src = hook_trampoline.add(current_offset + 4);
-      let hook_trampoline_jump_delta = hook_trampoline_jump_dst as isize - hook_trampoline_jump_src as isize;
+  true
+}
 
-      memory_copy(&hook_trampoline_jump_delta as *const isize as u32, hook_trampoline.add(current_offset) as u32, 4);
+/// Hook that is currently set, see [`active_hooks`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ActiveHook {
+  /// Address of the hooked function.
+  pub address: u32,
+  /// Name of whoever set the hook, `None` for hooks without an owner, e.g., the ones set with [`install_hook`].
+  pub owner: Option<String>,
+}
 
-      current_offset += 4;
+/// Get all hooks that are currently set, ordered by their address and owner.
+pub fn active_hooks() -> Vec<ActiveHook> {
+  let mut active: Vec<ActiveHook> = Vec::new();
 
-      for i in 0..hook_trampoline_third.len() {
-          let trampoline_address = hook_trampoline.add(current_offset + i) as *mut u8;
-          *trampoline_address = hook_trampoline_third[i];
+  let inners: Vec<Arc<Mutex<Inner>>> = match HOOKS.lock() {
+      Ok(hooks) => hooks.values().cloned().collect(),
+      Err(e) => {
+          error!("Couldn't get lock to hooks: {}", e.to_string());
+          Vec::new()
       }
+  };
 
-      // Copy the prelude for later restoration
-      let mut prelude_copy: Vec<u8> = Vec::new();
-      for i in 0..prelude_size {
-        prelude_copy.push(*(inner.address as *const u8).add(i));
+  for inner in inners {
+      match inner.lock() {
+          Ok(inner) => active.extend(inner.hooks.iter().map(|hook| ActiveHook { address: inner.address, owner: hook.owner.clone() })),
+          Err(e) => warn!("Couldn't get lock to inner hook: {}", e),
       }
+  }
 
-      let jmp_dst = hook_trampoline;
-      let jmp_src = inner.address as usize + 5;
-      let jmp_delta = jmp_dst as isize - jmp_src as isize;
+  match INSTALLED_HOOKS.lock() {
+      Ok(installed) => active.extend(installed.iter().map(|hook| ActiveHook { address: hook.address, owner: None })),
+      Err(e) => error!("Couldn't get lock to installed hooks: {}", e),
+  }
 
-      // Write jmp instruction from target to hook into first bytes of target function
-      let target_jmp_address = inner.address as *mut u8;
-      *target_jmp_address = 0xe9;
-      memory_copy(&jmp_delta as *const isize as u32, (inner.address as usize + 1) as u32, 4);
+  active.sort();
+  active
+}
 
-      // If prelude is larger than 5 bytes, fill the left over bytes with noops to avoid broken instructions
-      if prelude_size > 5 {
-          for n in 5..prelude_size {
-              *(inner.address as *mut u8).add(n) = 0x90;
-          }
+/// Free the trampolines and links of all removed hooks.
+///
+/// Returns the number of freed allocations.
+///
+/// # Safety
+///
+/// No thread may execute or later enter a removed hook's trampoline or link, otherwise it jumps into freed memory.
+/// This holds once all hooks were removed and [`wait_for_hook_calls`] returned `true`, and enough time passed for threads
+/// that were between a patched prelude and the hook function's [`HookCall`] to reach it.
+pub unsafe fn free_unused_memory() -> usize {
+  let unused = match UNUSED_MEMORY.lock() {
+      Ok(mut unused) => mem::take(&mut *unused),
+      Err(e) => {
+          error!("Couldn't get lock to the unused memory of hooks: {}", e);
+          return 0;
       }
+  };
 
-      inner.hook = Some(InnerHook {
-        allocated_sections: allocated_sections,
-        prelude: prelude_copy,
-      });
-
-      Ok(())
+  let mut freed = 0;
+  for address in unused {
+      match VirtualFree(address as *mut c_void, 0, MEM_RELEASE) {
+          Ok(_) => freed += 1,
+          Err(e) => warn!("Could not free memory of a hook at {:#08x}: {}", address, e),
+      }
   }
 
-  pub unsafe fn unhook(&mut self) -> Result<(), HookError> {
-    let mut inner = self.inner.lock().map_err(|e| HookError::Other(format!("Could not get lock to inner hook: {}", e)))?;
-
-    let hook = match &inner.hook {
-        None => return Err(HookError::NotHooked),
-        Some(v) => v,
-    };
-
-    // Free allocated memory
-    for section in &hook.allocated_sections {
-        // Don't return if we cannot free memory. At least make sure to try to free all allocated memory sections
-        if let Err(e) = VirtualFree(*section as *mut c_void, 0, MEM_RELEASE) {
-            warn!("Could not free section {:?}: {}", section, e);
-        }
-    }
-    
-    // Restore original prelude of the function
-    let prelude_ptr = inner.address as *mut u8;
+  freed
+}
 
-    for idx in 0..hook.prelude.len() {
-        let prelude_byte = hook.prelude[idx];
+/// Suspend all other threads of the process.
+///
+/// Returns the handles of the suspended threads, which must be passed to [`resume_threads`].
+unsafe fn suspend_other_threads() -> Vec<HANDLE> {
+  let threads = match get_other_threads() {
+      Ok(threads) => threads,
+      Err(e) => {
+          warn!("Could not get other threads, modifying code without suspending them: {}", e);
+          return Vec::new();
+      }
+  };
+
+  let mut handles = Vec::with_capacity(threads.len());
+  for thread in threads.iter() {
+      match OpenThread(THREAD_SUSPEND_RESUME, false, thread.th32ThreadID) {
+          Ok(handle) => handles.push(handle),
+          // Thread might have exited in the meantime
+          Err(e) => debug!("Could not get handle to thread {}: {}", thread.th32ThreadID, e),
+      }
+  }
 
-        *(prelude_ptr.add(idx)) = prelude_byte;
-    }
+  for handle in handles.iter() {
+      SuspendThread(*handle);
+  }
 
-    inner.hook = None;
+  handles
+}
 
-    Ok(())
+unsafe fn resume_threads(handles: Vec<HANDLE>) {
+  for handle in handles {
+      ResumeThread(handle);
+      let _ = CloseHandle(handle);
   }
 }
 
 impl UserData for Hook {
     fn add_methods<'lua, M: mlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
         methods.add_method_mut("unhook", |_, this, ()| {
-            unsafe {this.unhook().map_err(|e| mlua::Error::RuntimeError(format!("Could not unhook: {:?}", e)))?};
+            unsafe {this.unset_hook().map_err(|e| mlua::Error::RuntimeError(format!("Could not unhook: {:?}", e)))?};
 
             Ok(())
         });
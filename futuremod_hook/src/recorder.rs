@@ -0,0 +1,57 @@
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use log::warn;
+
+/// A single invocation of a hooked function.
+#[derive(Debug, Clone)]
+pub struct HookInvocation {
+  /// Address of the hooked function.
+  pub address: u32,
+  /// Raw arguments, first argument first.
+  pub arguments: Vec<u32>,
+  /// Raw value returned by the hook.
+  pub return_value: u32,
+}
+
+type Recorder = Box<dyn Fn(HookInvocation) + Send>;
+
+lazy_static!{
+  static ref RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+}
+
+/// Set the recorder that is called for every invocation of a lua hook.
+/// 
+/// Replaces any previously set recorder.
+/// The recorder is called on the game's thread while the game waits for the hook to return,
+/// therefore, it should be fast.
+pub fn set_recorder<F>(recorder: F)
+where F: Fn(HookInvocation) + Send + 'static {
+  match RECORDER.lock() {
+    Ok(mut current) => *current = Some(Box::new(recorder)),
+    Err(e) => warn!("Could not get lock to the hook recorder: {}", e),
+  }
+}
+
+/// Remove the recorder.
+pub fn remove_recorder() {
+  match RECORDER.lock() {
+    Ok(mut current) => *current = None,
+    Err(e) => warn!("Could not get lock to the hook recorder: {}", e),
+  }
+}
+
+/// Pass the invocation to the recorder if one is set.
+/// 
+/// Arguments are only collected if a recorder is set.
+pub(crate) fn record<F>(address: u32, collect_arguments: F, return_value: u32)
+where F: FnOnce() -> Vec<u32> {
+  let recorder = match RECORDER.lock() {
+    Ok(recorder) => recorder,
+    Err(_) => return,
+  };
+
+  if let Some(recorder) = recorder.as_ref() {
+    recorder(HookInvocation { address, arguments: collect_arguments(), return_value });
+  }
+}
@@ -12,6 +12,15 @@ pub enum Type {
   Short,
   UnsignedShort,
   Float,
+  /// 64-bit float, spans two words, the low word first.
+  ///
+  /// Only supported for arguments that are passed on the stack. Functions return doubles in `st(0)` instead of `eax`,
+  /// which native calls, hooks, and native functions don't read or set, so they reject it as a return type.
+  Double,
+  /// Address, `nil` for null pointers.
+  Pointer,
+  /// Pointer to a struct. Passed as the address of a string with the struct's bytes, e.g., from `string.pack`, or as an address.
+  Struct,
   Void,
 }
 
@@ -27,17 +36,88 @@ impl Type {
       "ushort" => Type::UnsignedShort,
       "byte" => Type::Byte,
       "ubyte" => Type::UnsignedByte,
+      "double" => Type::Double,
+      "pointer" => Type::Pointer,
+      "struct" => Type::Struct,
       _ => return None,
     };
 
     Some(type_value)
   }
+
+  /// Number of words a value of the type takes up on the stack.
+  pub fn words(&self) -> usize {
+    match self {
+      Type::Double => 2,
+      _ => 1,
+    }
+  }
+}
+
+/// Calling convention of a native function, i.e., how arguments are passed and who cleans up the stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CallingConvention {
+  /// All arguments on the stack, the caller cleans up.
+  #[default]
+  Cdecl,
+  /// All arguments on the stack, the function cleans up.
+  Stdcall,
+  /// First argument (`this`) in `ecx`, the others on the stack, the function cleans up.
+  Thiscall,
+  /// First two arguments in `ecx` and `edx`, the others on the stack, the function cleans up.
+  Fastcall,
+}
+
+impl CallingConvention {
+  pub fn try_from_str(name: &str) -> Option<CallingConvention> {
+    let convention = match name {
+      "cdecl" => CallingConvention::Cdecl,
+      "stdcall" => CallingConvention::Stdcall,
+      "thiscall" => CallingConvention::Thiscall,
+      "fastcall" => CallingConvention::Fastcall,
+      _ => return None,
+    };
+
+    Some(convention)
+  }
+
+  /// Number of arguments passed in registers, `ecx` first, then `edx`.
+  pub fn register_args(&self) -> usize {
+    match self {
+      CallingConvention::Cdecl | CallingConvention::Stdcall => 0,
+      CallingConvention::Thiscall => 1,
+      CallingConvention::Fastcall => 2,
+    }
+  }
+
+  /// Whether the called function removes its stack arguments.
+  pub fn is_callee_cleanup(&self) -> bool {
+    *self != CallingConvention::Cdecl
+  }
 }
 
 pub const MAX_STRING: u16 = 1024;
 
 /// Convert a native value into its lua value given the type name.
+///
+/// Only supports types that fit into one word, use [`native_words_to_lua`] for the others.
 pub unsafe fn native_to_lua<'a>(lua: &'a Lua, lua_type: Type, raw_value: u32) -> Result<mlua::Value<'a>, mlua::Error> {
+  native_words_to_lua(lua, lua_type, &[raw_value])
+}
+
+/// Convert a native value that spans the words, as returned by [`Type::words`], into its lua value given the type name.
+///
+/// # Safety
+///
+/// For [`Type::String`], the first word must be the address of a readable string that is null-terminated or at least
+/// [`MAX_STRING`] bytes long. The other types only reinterpret the words and don't read memory.
+pub unsafe fn native_words_to_lua<'a>(lua: &'a Lua, lua_type: Type, words: &[u32]) -> Result<mlua::Value<'a>, mlua::Error> {
+  if words.len() < lua_type.words() {
+    return Err(mlua::Error::RuntimeError(format!("{:?} spans {} words, got {}", lua_type, lua_type.words(), words.len())));
+  }
+
+  let raw_value = words[0];
+
   let value = match lua_type {
     Type::String => {
       let mut string_bytes: Vec<u8> = Vec::new();
@@ -55,7 +135,12 @@ pub unsafe fn native_to_lua<'a>(lua: &'a Lua, lua_type: Type, raw_value: u32) ->
 
       mlua::Value::String(lua.create_string(string_bytes.as_slice())?)
     },
-    Type::Float => mlua::Value::Number(f64::from(raw_value as f32)),
+    Type::Float => mlua::Value::Number(f64::from(f32::from_bits(raw_value))),
+    Type::Double => mlua::Value::Number(f64::from_bits((u64::from(words[1]) << 32) | u64::from(raw_value))),
+    Type::Pointer | Type::Struct => match raw_value {
+      0 => mlua::Value::Nil,
+      address => mlua::Value::Number(f64::from(address)),
+    },
     Type::Void => mlua::Value::Nil,
     Type::Integer => mlua::Value::Integer(raw_value as i32),
     Type::UnsignedInteger => mlua::Value::Integer(raw_value as i32),
@@ -69,15 +154,30 @@ pub unsafe fn native_to_lua<'a>(lua: &'a Lua, lua_type: Type, raw_value: u32) ->
 }
 
 /// Convert a lua value into its native representation given a specific lua type.
+///
+/// Returns the words in the order they are in memory, i.e., the low word first.
+/// Strings and structs are passed as pointers to the lua string's bytes, which are only valid as long as the lua value is alive.
 pub unsafe fn lua_to_native<'a>(lua_type: Type, lua_value: &'a mlua::Value) -> Result<Vec<u32>, anyhow::Error> {
   let actual_type_name = lua_value.type_name();
 
   let value: Vec<u32> = match lua_type {
 
-    Type::Float => match lua_value.as_f32() {
-      Some(value) => vec![value as u32],
+    Type::Float => match lua_to_number(lua_value) {
+      Some(value) => vec![(value as f32).to_bits()],
       None => bail!("value {} is not a float", actual_type_name),
     }
+    Type::Double => match lua_to_number(lua_value) {
+      Some(value) => {
+        let bits = value.to_bits();
+        vec![bits as u32, (bits >> 32) as u32]
+      },
+      None => bail!("value {} is not a double", actual_type_name),
+    },
+    Type::Pointer => vec![lua_to_address(lua_value)?],
+    Type::Struct => match lua_value {
+      mlua::Value::String(bytes) => vec![bytes.as_bytes().as_ptr() as u32],
+      value => vec![lua_to_address(value)?],
+    },
     Type::Void => vec![0u32],
     Type::String => match lua_value.as_str() {
       Some(value) => {
@@ -114,6 +214,28 @@ pub unsafe fn lua_to_native<'a>(lua_type: Type, lua_value: &'a mlua::Value) -> R
   Ok(value)
 }
 
+/// Convert a number into a float, Luau numbers without a fraction are integers.
+fn lua_to_number(lua_value: &mlua::Value) -> Option<f64> {
+  match lua_value {
+    mlua::Value::Integer(value) => Some(f64::from(*value)),
+    mlua::Value::Number(value) => Some(*value),
+    _ => None,
+  }
+}
+
+/// Convert `nil`, a number, or light userdata into an address.
+fn lua_to_address(lua_value: &mlua::Value) -> Result<u32, anyhow::Error> {
+  let address = match lua_value {
+    mlua::Value::Nil => 0,
+    mlua::Value::Integer(value) => *value as u32,
+    mlua::Value::Number(value) => *value as u32,
+    mlua::Value::LightUserData(value) => value.0 as u32,
+    value => bail!("value {} is not a pointer", value.type_name()),
+  };
+
+  Ok(address)
+}
+
 pub unsafe fn lua_to_native_implied<'a>(value: &'a mlua::Value) -> Result<Vec<u32>, anyhow::Error> {
   let value: Vec<u32> = match value {
     mlua::Value::Nil => vec![0u32],
@@ -0,0 +1,937 @@
+// Every test assembles small native functions at runtime, hooks them, and calls them like the game would.
+// The tests cover the hand-written trampolines and must be run on the same target as the mod (`i686-pc-windows-msvc`).
+use std::{ffi::c_void, sync::{atomic::{AtomicU32, Ordering}, Arc}};
+
+use futuremod_hook::{lua::{create_native_function_function, get_native_function, hook_function, NativeFunction}, native::{active_hooks, unset_hooks_of, ActiveHook, Hook, HookError}, types::{lua_to_native, native_words_to_lua, Type}};
+use mlua::{IntoLuaMulti, Lua};
+use windows::Win32::System::Memory::{VirtualAlloc, MEM_COMMIT, MEM_RESERVE, PAGE_EXECUTE_READWRITE};
+
+type NoArgFunction = extern "C" fn() -> u32;
+type TwoArgFunction = extern "C" fn(u32, u32) -> u32;
+type ThreeArgFunction = extern "C" fn(u32, u32, u32) -> u32;
+
+
+///////////////////////////////////////////////////////////
+// Synthetic functions
+///////////////////////////////////////////////////////////
+
+/// Assemble the given machine code into executable memory and return its address.
+///
+/// The function is padded with `int3`, so that decoding past its end never produces an invalid instruction.
+fn assemble(code: &[u8]) -> u32 {
+  unsafe {
+    let memory = VirtualAlloc(None, 0x100, MEM_COMMIT | MEM_RESERVE, PAGE_EXECUTE_READWRITE) as *mut u8;
+    assert!(!memory.is_null(), "could not allocate memory for a synthetic function");
+
+    for i in 0..0x100 {
+      *memory.add(i) = 0xcc;
+    }
+
+    for (i, byte) in code.iter().enumerate() {
+      *memory.add(i) = *byte;
+    }
+
+    memory as *mut c_void as u32
+  }
+}
+
+/// `add(a, b) = a + b` with a standard frame pointer prelude.
+///
+/// ```asm
+/// push ebp
+/// mov ebp, esp
+/// mov eax, [ebp + 0x8]
+/// add eax, [ebp + 0xc]
+/// pop ebp
+/// ret
+/// ```
+fn add_function() -> u32 {
+  assemble(&[0x55, 0x89, 0xe5, 0x8b, 0x45, 0x08, 0x03, 0x45, 0x0c, 0x5d, 0xc3])
+}
+
+/// `sub(a, b, c) = a - b - c` without a frame pointer.
+///
+/// ```asm
+/// mov eax, [esp + 0x4]
+/// sub eax, [esp + 0x8]
+/// sub eax, [esp + 0xc]
+/// ret
+/// ```
+fn sub_function() -> u32 {
+  assemble(&[0x8b, 0x44, 0x24, 0x04, 0x2b, 0x44, 0x24, 0x08, 0x2b, 0x44, 0x24, 0x0c, 0xc3])
+}
+
+/// Tiny function whose prelude is exactly the size of the hook's jump.
+///
+/// ```asm
+/// mov eax, value
+/// ret
+/// ```
+fn constant_function(value: u32) -> u32 {
+  let value = value.to_le_bytes();
+
+  assemble(&[0xb8, value[0], value[1], value[2], value[3], 0xc3])
+}
+
+/// Function that calls another function with a relative call in its prelude.
+///
+/// ```asm
+/// call callee
+/// add eax, 1
+/// ret
+/// ```
+fn relative_call_function(callee: u32) -> u32 {
+  let address = assemble(&[0xe8, 0x00, 0x00, 0x00, 0x00, 0x83, 0xc0, 0x01, 0xc3]);
+  let relative_callee = (callee as i32 - (address as i32 + 5)).to_le_bytes();
+
+  unsafe {
+    for (i, byte) in relative_callee.iter().enumerate() {
+      *(address as *mut u8).add(1 + i) = *byte;
+    }
+  }
+
+  address
+}
+
+/// Function with a short jump in its prelude that skips over the rest of the prelude.
+///
+/// ```asm
+///   jmp short 1f
+///   mov eax, 0
+///   nop
+/// 1:
+///   mov eax, 7
+///   ret
+/// ```
+fn short_jump_function() -> u32 {
+  assemble(&[0xeb, 0x06, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x90, 0xb8, 0x07, 0x00, 0x00, 0x00, 0xc3])
+}
+
+/// Function with a conditional short jump in its prelude.
+///
+/// ```asm
+///   xor eax, eax
+///   jz short 1f
+///   mov eax, 1
+///   ret
+/// 1:
+///   mov eax, 2
+///   ret
+/// ```
+fn conditional_jump_function() -> u32 {
+  assemble(&[0x31, 0xc0, 0x74, 0x06, 0xb8, 0x01, 0x00, 0x00, 0x00, 0xc3, 0xb8, 0x02, 0x00, 0x00, 0x00, 0xc3])
+}
+
+/// `sub(a, b) = a - b` that removes its arguments from the stack (`stdcall`).
+///
+/// ```asm
+/// mov eax, [esp + 0x4]
+/// sub eax, [esp + 0x8]
+/// ret 0x8
+/// ```
+fn stdcall_sub_function() -> u32 {
+  assemble(&[0x8b, 0x44, 0x24, 0x04, 0x2b, 0x44, 0x24, 0x08, 0xc2, 0x08, 0x00])
+}
+
+/// `sub(this, b) = this - b` with `this` in `ecx` (`thiscall`).
+///
+/// ```asm
+/// mov eax, ecx
+/// sub eax, [esp + 0x4]
+/// ret 0x4
+/// ```
+fn thiscall_sub_function() -> u32 {
+  assemble(&[0x89, 0xc8, 0x2b, 0x44, 0x24, 0x04, 0xc2, 0x04, 0x00])
+}
+
+/// `sub(a, b, c) = a - b - c` with `a` in `ecx` and `b` in `edx` (`fastcall`).
+///
+/// ```asm
+/// mov eax, ecx
+/// sub eax, edx
+/// sub eax, [esp + 0x4]
+/// ret 0x4
+/// ```
+fn fastcall_sub_function() -> u32 {
+  assemble(&[0x89, 0xc8, 0x29, 0xd0, 0x2b, 0x44, 0x24, 0x04, 0xc2, 0x04, 0x00])
+}
+
+/// `high_word(a, b) = (high word of the double a) + b`, to check that doubles span two stack slots.
+///
+/// ```asm
+/// mov eax, [esp + 0x8]
+/// add eax, [esp + 0xc]
+/// ret
+/// ```
+fn double_high_word_function() -> u32 {
+  assemble(&[0x8b, 0x44, 0x24, 0x08, 0x03, 0x44, 0x24, 0x0c, 0xc3])
+}
+
+fn as_fn<T>(address: u32) -> T {
+  unsafe { std::mem::transmute_copy(&address) }
+}
+
+
+///////////////////////////////////////////////////////////
+// Stack aware hooks
+///////////////////////////////////////////////////////////
+
+extern "C" fn add_hook(original: TwoArgFunction, a: u32, b: u32) -> u32 {
+  original(a, b) * 10
+}
+
+extern "C" fn add_hook_modifying_arguments(original: TwoArgFunction, a: u32, b: u32) -> u32 {
+  original(a + 1, b + 1)
+}
+
+extern "C" fn sub_hook(original: ThreeArgFunction, a: u32, b: u32, c: u32) -> u32 {
+  assert_eq!((a, b, c), (100, 10, 1));
+
+  original(a, b, c)
+}
+
+extern "C" fn constant_hook(original: NoArgFunction) -> u32 {
+  original() + 1
+}
+
+#[test]
+fn stack_aware_hook_passes_arguments() {
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.stack_aware_set_hook(add_hook as u32).unwrap();
+  }
+
+  assert_eq!(function(2, 3), 50);
+}
+
+#[test]
+fn stack_aware_hook_modifies_arguments() {
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.stack_aware_set_hook(add_hook_modifying_arguments as u32).unwrap();
+  }
+
+  assert_eq!(function(2, 3), 7);
+}
+
+#[test]
+fn stack_aware_hook_without_frame_pointer() {
+  let target = sub_function();
+  let function: ThreeArgFunction = as_fn(target);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.stack_aware_set_hook(sub_hook as u32).unwrap();
+  }
+
+  assert_eq!(function(100, 10, 1), 89);
+}
+
+#[test]
+fn stack_aware_hook_on_tiny_function() {
+  let target = constant_function(41);
+  let function: NoArgFunction = as_fn(target);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.stack_aware_set_hook(constant_hook as u32).unwrap();
+  }
+
+  assert_eq!(function(), 42);
+}
+
+#[test]
+fn stack_aware_hook_relocates_relative_call() {
+  let callee = constant_function(41);
+  let target = relative_call_function(callee);
+  let function: NoArgFunction = as_fn(target);
+
+  assert_eq!(function(), 42);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.stack_aware_set_hook(constant_hook as u32).unwrap();
+  }
+
+  assert_eq!(function(), 43);
+}
+
+#[test]
+fn stack_aware_hook_relocates_short_jump() {
+  let target = short_jump_function();
+  let function: NoArgFunction = as_fn(target);
+
+  assert_eq!(function(), 7);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.stack_aware_set_hook(constant_hook as u32).unwrap();
+  }
+
+  assert_eq!(function(), 8);
+}
+
+#[test]
+fn stack_aware_hook_relocates_conditional_jump() {
+  let target = conditional_jump_function();
+  let function: NoArgFunction = as_fn(target);
+
+  assert_eq!(function(), 2);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.stack_aware_set_hook(constant_hook as u32).unwrap();
+  }
+
+  assert_eq!(function(), 3);
+}
+
+
+///////////////////////////////////////////////////////////
+// Closure hooks
+///////////////////////////////////////////////////////////
+
+/// Create a closure hook for [`add_function`] in the same way as the lua hooks.
+///
+/// The closure gets the original function and the first argument.
+/// All other arguments follow the first argument on the stack.
+fn add_closure(calls: Arc<AtomicU32>) -> Box<dyn FnMut(u32, u32) -> u32> {
+  Box::new(move |original: u32, args: u32| {
+    let arg_pointer = &args as *const u32;
+    let (a, b) = unsafe { (*arg_pointer, *arg_pointer.add(1)) };
+
+    calls.fetch_add(1, Ordering::SeqCst);
+
+    as_fn::<TwoArgFunction>(original)(a, b) * 10
+  })
+}
+
+#[test]
+fn closure_hook_passes_arguments() {
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+  let calls = Arc::new(AtomicU32::new(0));
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.set_closure(add_closure(calls.clone())).unwrap();
+  }
+
+  assert_eq!(function(2, 3), 50);
+  assert_eq!(function(4, 5), 90);
+  assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn closure_hook_relocates_relative_call() {
+  let callee = constant_function(41);
+  let target = relative_call_function(callee);
+  let function: NoArgFunction = as_fn(target);
+
+  let closure: Box<dyn FnMut(u32, u32) -> u32> = Box::new(|original: u32, _| as_fn::<NoArgFunction>(original)() * 2);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.set_closure(closure).unwrap();
+  }
+
+  assert_eq!(function(), 84);
+}
+
+#[test]
+fn hooks_chain_through_relocated_calls() {
+  // Hook the callee and a caller that calls the callee in its prelude.
+  // Calling the caller must go through both hooks.
+  let callee = constant_function(1);
+  let caller = relative_call_function(callee);
+  let function: NoArgFunction = as_fn(caller);
+
+  let callee_closure: Box<dyn FnMut(u32, u32) -> u32> = Box::new(|original: u32, _| as_fn::<NoArgFunction>(original)() + 10);
+  let caller_closure: Box<dyn FnMut(u32, u32) -> u32> = Box::new(|original: u32, _| as_fn::<NoArgFunction>(original)() * 2);
+
+  unsafe {
+    Hook::new(callee).set_closure(callee_closure).unwrap();
+    Hook::new(caller).set_closure(caller_closure).unwrap();
+  }
+
+  // (1 + 10 + 1) * 2
+  assert_eq!(function(), 24);
+}
+
+
+///////////////////////////////////////////////////////////
+// Removal
+///////////////////////////////////////////////////////////
+
+#[test]
+fn unset_hook_restores_original_function() {
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+  let calls = Arc::new(AtomicU32::new(0));
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.set_closure(add_closure(calls.clone())).unwrap();
+    assert_eq!(function(2, 3), 50);
+
+    hook.unset_hook().unwrap();
+  }
+
+  assert_eq!(function(2, 3), 5);
+  assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn unset_hook_restores_relocated_prelude() {
+  let target = short_jump_function();
+  let function: NoArgFunction = as_fn(target);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.stack_aware_set_hook(constant_hook as u32).unwrap();
+    assert_eq!(function(), 8);
+
+    hook.unset_hook().unwrap();
+  }
+
+  assert_eq!(function(), 7);
+}
+
+#[test]
+fn hook_can_be_set_again_after_unset_hook() {
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.stack_aware_set_hook(add_hook as u32).unwrap();
+    hook.unset_hook().unwrap();
+
+    hook.stack_aware_set_hook(add_hook_modifying_arguments as u32).unwrap();
+  }
+
+  assert_eq!(function(2, 3), 7);
+}
+
+#[test]
+fn hook_cannot_be_set_twice() {
+  let target = add_function();
+
+  unsafe {
+    let mut hook = Hook::new(target);
+
+    hook.stack_aware_set_hook(add_hook as u32).unwrap();
+    assert!(matches!(hook.stack_aware_set_hook(add_hook as u32), Err(HookError::AlreadyHooked)));
+
+    hook.unset_hook().unwrap();
+    assert!(matches!(hook.unset_hook(), Err(HookError::NotHooked)));
+  }
+}
+
+#[test]
+fn unset_hooks_of_removes_only_hooks_of_the_owner() {
+  let owned_target = add_function();
+  let other_target = add_function();
+  let owned_function: TwoArgFunction = as_fn(owned_target);
+  let other_function: TwoArgFunction = as_fn(other_target);
+
+  unsafe {
+    let mut owned = Hook::new(owned_target);
+    owned.stack_aware_set_hook(add_hook as u32).unwrap();
+    owned.set_owner("unset_hooks_of_owner").unwrap();
+
+    let mut other = Hook::new(other_target);
+    other.stack_aware_set_hook(add_hook as u32).unwrap();
+    other.set_owner("unset_hooks_of_other").unwrap();
+
+    assert_eq!(unset_hooks_of("unset_hooks_of_owner"), 1);
+    assert_eq!(unset_hooks_of("unset_hooks_of_owner"), 0);
+  }
+
+  assert_eq!(owned_function(2, 3), 5);
+  assert_eq!(other_function(2, 3), 50);
+}
+
+#[test]
+fn unset_hooks_of_ignores_hooks_set_again_by_someone_else() {
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    hook.stack_aware_set_hook(add_hook as u32).unwrap();
+    hook.set_owner("set_again_first").unwrap();
+    hook.unset_hook().unwrap();
+
+    hook.stack_aware_set_hook(add_hook as u32).unwrap();
+    hook.set_owner("set_again_second").unwrap();
+
+    assert_eq!(unset_hooks_of("set_again_first"), 0);
+  }
+
+  assert_eq!(function(2, 3), 50);
+}
+
+#[test]
+fn active_hooks_lists_hooks_until_they_are_unset() {
+  let target = add_function();
+  let hooks_of_target = || active_hooks().into_iter().filter(|hook| hook.address == target).collect::<Vec<ActiveHook>>();
+
+  unsafe {
+    let mut first = Hook::new(target);
+    first.stack_aware_set_hook(add_hook as u32).unwrap();
+    first.set_owner("active_hooks_first").unwrap();
+
+    let mut second = Hook::new(target);
+    second.stack_aware_set_hook(add_hook as u32).unwrap();
+
+    assert_eq!(hooks_of_target(), vec![
+      ActiveHook { address: target, owner: None },
+      ActiveHook { address: target, owner: Some("active_hooks_first".into()) },
+    ]);
+
+    first.unset_hook().unwrap();
+    second.unset_hook().unwrap();
+  }
+
+  assert!(hooks_of_target().is_empty());
+}
+
+#[test]
+fn set_owner_requires_hook() {
+  let target = add_function();
+
+  let mut hook = unsafe { Hook::new(target) };
+  assert!(matches!(hook.set_owner("set_owner_requires_hook"), Err(HookError::NotHooked)));
+}
+
+#[test]
+fn hook_rejects_invalid_target() {
+  let target = assemble(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    assert!(matches!(hook.stack_aware_set_hook(add_hook as u32), Err(HookError::InvalidTarget)));
+  }
+}
+
+
+#[test]
+fn hook_rejects_short_only_branches_in_prelude() {
+  // loop, loope, loopne, and jecxz with a target after the prelude, followed by enough bytes for the jump
+  for branch in [[0xe2, 0x08], [0xe1, 0x08], [0xe0, 0x08], [0xe3, 0x08]] {
+    let mut code = branch.to_vec();
+    code.extend([0xb8, 0x00, 0x00, 0x00, 0x00, 0x90, 0x90, 0x90, 0xc3]);
+    let target = assemble(&code);
+
+    unsafe {
+      let mut hook = Hook::new(target);
+      assert!(matches!(hook.stack_aware_set_hook(add_hook as u32), Err(HookError::InvalidTarget)), "branch {:02x?} wasn't rejected", branch);
+    }
+  }
+}
+
+#[test]
+fn hook_rejects_branch_into_prelude() {
+  // ```asm
+  // 1:
+  //   nop
+  //   nop
+  //   jz short 1b
+  //   mov eax, 0
+  //   ret
+  // ```
+  let target = assemble(&[0x90, 0x90, 0x74, 0xfc, 0xb8, 0x00, 0x00, 0x00, 0x00, 0xc3]);
+
+  unsafe {
+    let mut hook = Hook::new(target);
+    assert!(matches!(hook.stack_aware_set_hook(add_hook as u32), Err(HookError::InvalidTarget)));
+  }
+}
+
+///////////////////////////////////////////////////////////
+// Chains
+///////////////////////////////////////////////////////////
+
+/// Create a closure hook for [`add_function`] that records its call in `order` and adds `summand` to the result.
+fn chained_add_closure(order: Arc<std::sync::Mutex<Vec<u32>>>, summand: u32) -> Box<dyn FnMut(u32, u32) -> u32> {
+  Box::new(move |original: u32, args: u32| {
+    let arg_pointer = &args as *const u32;
+    let (a, b) = unsafe { (*arg_pointer, *arg_pointer.add(1)) };
+
+    order.lock().unwrap().push(summand);
+
+    as_fn::<TwoArgFunction>(original)(a, b) + summand
+  })
+}
+
+#[test]
+fn hooks_on_the_same_address_are_chained() {
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+
+  unsafe {
+    Hook::new(target).stack_aware_set_hook(add_hook as u32).unwrap();
+    Hook::new(target).stack_aware_set_hook(add_hook_modifying_arguments as u32).unwrap();
+  }
+
+  // The first hook calls the second one as its original function: ((2 + 1) + (3 + 1)) * 10
+  assert_eq!(function(2, 3), 70);
+}
+
+#[test]
+fn chained_hooks_are_called_in_the_order_they_were_set() {
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+  let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+  unsafe {
+    for summand in [100, 200, 300] {
+      Hook::new(target).set_closure(chained_add_closure(order.clone(), summand)).unwrap();
+    }
+  }
+
+  assert_eq!(function(2, 3), 605);
+  assert_eq!(*order.lock().unwrap(), vec![100, 200, 300]);
+}
+
+#[test]
+fn unset_hook_removes_only_its_link_of_the_chain() {
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+  let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+  unsafe {
+    let mut first = Hook::new(target);
+    let mut second = Hook::new(target);
+    let mut third = Hook::new(target);
+
+    first.set_closure(chained_add_closure(order.clone(), 100)).unwrap();
+    second.set_closure(chained_add_closure(order.clone(), 200)).unwrap();
+    third.set_closure(chained_add_closure(order.clone(), 300)).unwrap();
+
+    second.unset_hook().unwrap();
+    assert_eq!(function(2, 3), 405);
+
+    first.unset_hook().unwrap();
+    assert_eq!(function(2, 3), 305);
+
+    // Added to the end of the chain, after the remaining hook
+    second.set_closure(chained_add_closure(order.clone(), 200)).unwrap();
+    assert_eq!(function(2, 3), 505);
+
+    third.unset_hook().unwrap();
+    second.unset_hook().unwrap();
+  }
+
+  assert_eq!(function(2, 3), 5);
+  assert_eq!(*order.lock().unwrap(), vec![100, 300, 300, 300, 200]);
+}
+
+#[test]
+fn unset_hooks_of_keeps_hooks_of_others_on_the_same_address() {
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+
+  unsafe {
+    let mut owned = Hook::new(target);
+    owned.stack_aware_set_hook(add_hook as u32).unwrap();
+    owned.set_owner("chain_owner").unwrap();
+
+    let mut other = Hook::new(target);
+    other.stack_aware_set_hook(add_hook_modifying_arguments as u32).unwrap();
+    other.set_owner("chain_other").unwrap();
+
+    assert_eq!(function(2, 3), 70);
+    assert_eq!(unset_hooks_of("chain_owner"), 1);
+  }
+
+  assert_eq!(function(2, 3), 7);
+}
+
+
+///////////////////////////////////////////////////////////
+// Lua hooks
+///////////////////////////////////////////////////////////
+
+#[test]
+fn lua_hook_passes_arguments_and_return_value() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+
+  let callback = lua.load("return function(original, a, b) return original(a, b) + a * b end").eval().unwrap();
+
+  let _hook = hook_function(lua, (target, vec!["int".into(), "int".into()], "int".into(), callback)).unwrap();
+
+  // 2 + 3 + 2 * 3
+  assert_eq!(function(2, 3), 11);
+}
+
+#[test]
+fn lua_hooks_on_the_same_address_are_chained() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+
+  let first = lua.load("return function(original, a, b) return original(a, b) * 2 end").eval().unwrap();
+  let second = lua.load("return function(original, a, b) return original(a, b) + 1 end").eval().unwrap();
+
+  let _first = hook_function(lua, (target, vec!["int".into(), "int".into()], "int".into(), first)).unwrap();
+  let _second = hook_function(lua, (target, vec!["int".into(), "int".into()], "int".into(), second)).unwrap();
+
+  // (2 + 3 + 1) * 2
+  assert_eq!(function(2, 3), 12);
+}
+
+#[test]
+fn lua_hook_falls_back_to_original_function_on_error() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let target = add_function();
+  let function: TwoArgFunction = as_fn(target);
+
+  let callback = lua.load("return function(original, a, b) error('broken hook') end").eval().unwrap();
+
+  let _hook = hook_function(lua, (target, vec!["int".into(), "int".into()], "int".into(), callback)).unwrap();
+
+  assert_eq!(function(2, 3), 5);
+}
+
+
+///////////////////////////////////////////////////////////
+// Native functions
+///////////////////////////////////////////////////////////
+
+fn int_types(count: usize) -> Vec<String> {
+  vec!["int".into(); count]
+}
+
+fn call_native(lua: &'static Lua, function: &NativeFunction, args: impl IntoLuaMulti<'static>) -> i32 {
+  let args = args.into_lua_multi(lua).unwrap();
+
+  lua.unpack(function.call(lua, args).unwrap()).unwrap()
+}
+
+#[test]
+fn native_function_calls_cdecl_function() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let function = get_native_function(lua, (sub_function(), int_types(3), "int".into(), None)).unwrap();
+
+  assert_eq!(call_native(lua, &function, (10, 3, 2)), 5);
+}
+
+#[test]
+fn native_function_calls_stdcall_function() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let function = get_native_function(lua, (stdcall_sub_function(), int_types(2), "int".into(), Some("stdcall".into()))).unwrap();
+
+  assert_eq!(call_native(lua, &function, (10, 3)), 7);
+  assert_eq!(call_native(lua, &function, (10, 3)), 7);
+}
+
+#[test]
+fn native_function_passes_this_in_ecx() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let function = get_native_function(lua, (thiscall_sub_function(), int_types(2), "int".into(), Some("thiscall".into()))).unwrap();
+
+  assert_eq!(call_native(lua, &function, (10, 3)), 7);
+}
+
+#[test]
+fn native_function_passes_fastcall_arguments_in_registers() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let function = get_native_function(lua, (fastcall_sub_function(), int_types(3), "int".into(), Some("fastcall".into()))).unwrap();
+
+  assert_eq!(call_native(lua, &function, (10, 3, 2)), 5);
+}
+
+#[test]
+fn native_function_rejects_unknown_convention() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+
+  assert!(get_native_function(lua, (sub_function(), int_types(3), "int".into(), Some("pascal".into()))).is_err());
+}
+
+#[test]
+fn created_native_function_is_callable_as_stdcall() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let callback = lua.load("return function(a, b) return a - b end").eval().unwrap();
+
+  let native = create_native_function_function(lua, (int_types(2), "int".into(), callback, Some("stdcall".into()))).unwrap();
+  let function: extern "stdcall" fn(u32, u32) -> u32 = as_fn(native.address());
+
+  assert_eq!(function(10, 3), 7);
+  assert_eq!(function(10, 3), 7);
+}
+
+#[test]
+fn created_native_function_is_callable_as_thiscall() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let callback = lua.load("return function(this, b) return this - b end").eval().unwrap();
+
+  let native = create_native_function_function(lua, (int_types(2), "int".into(), callback, Some("thiscall".into()))).unwrap();
+  let function: extern "thiscall" fn(u32, u32) -> u32 = as_fn(native.address());
+
+  assert_eq!(function(10, 3), 7);
+}
+
+#[test]
+fn created_native_function_is_callable_as_fastcall() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let callback = lua.load("return function(a, b, c) return a - b - c end").eval().unwrap();
+
+  let native = create_native_function_function(lua, (int_types(3), "int".into(), callback, Some("fastcall".into()))).unwrap();
+  let function: extern "fastcall" fn(u32, u32, u32) -> u32 = as_fn(native.address());
+
+  assert_eq!(function(10, 3, 2), 5);
+}
+
+#[test]
+fn native_function_rejects_double_in_register() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+
+  assert!(get_native_function(lua, (fastcall_sub_function(), vec!["int".into(), "double".into()], "int".into(), Some("fastcall".into()))).is_err());
+  assert!(get_native_function(lua, (fastcall_sub_function(), vec!["int".into(), "int".into(), "double".into()], "int".into(), Some("fastcall".into()))).is_ok());
+}
+
+#[test]
+fn native_function_rejects_double_return_type() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+
+  assert!(get_native_function(lua, (sub_function(), int_types(3), "double".into(), None)).is_err());
+}
+
+#[test]
+fn lua_hook_rejects_double_return_type() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let callback = lua.load("return function(original, a, b) return original(a, b) end").eval().unwrap();
+
+  assert!(hook_function(lua, (add_function(), int_types(2), "double".into(), callback)).is_err());
+}
+
+#[test]
+fn created_native_function_rejects_double_return_type() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let callback = lua.load("return function(a, b) return a - b end").eval().unwrap();
+
+  assert!(create_native_function_function(lua, (int_types(2), "double".into(), callback, None)).is_err());
+}
+
+
+///////////////////////////////////////////////////////////
+// Marshalling
+///////////////////////////////////////////////////////////
+
+/// Convert the lua value into its native representation and back.
+fn round_trip<'lua>(lua: &'lua Lua, type_name: &str, value: mlua::Value<'lua>) -> mlua::Value<'lua> {
+  let native_type = Type::try_from_str(type_name).unwrap();
+
+  unsafe {
+    let words = lua_to_native(native_type, &value).unwrap();
+    assert_eq!(words.len(), native_type.words());
+
+    native_words_to_lua(lua, native_type, &words).unwrap()
+  }
+}
+
+#[test]
+fn double_round_trips() {
+  let lua = Lua::new();
+
+  for value in [0.1, -1234.5678, f64::MAX, f64::MIN_POSITIVE] {
+    assert_eq!(round_trip(&lua, "double", mlua::Value::Number(value)), mlua::Value::Number(value));
+  }
+}
+
+#[test]
+fn double_is_split_into_low_and_high_word() {
+  let bits = 0.1f64.to_bits();
+  let words = unsafe { lua_to_native(Type::Double, &mlua::Value::Number(0.1)).unwrap() };
+
+  assert_eq!(words, vec![bits as u32, (bits >> 32) as u32]);
+}
+
+#[test]
+fn float_round_trips() {
+  let lua = Lua::new();
+
+  assert_eq!(round_trip(&lua, "float", mlua::Value::Number(1.5)), mlua::Value::Number(1.5));
+  assert_eq!(round_trip(&lua, "float", mlua::Value::Number(-0.25)), mlua::Value::Number(-0.25));
+}
+
+#[test]
+fn pointer_round_trips() {
+  let lua = Lua::new();
+
+  assert_eq!(round_trip(&lua, "pointer", mlua::Value::Nil), mlua::Value::Nil);
+  assert_eq!(round_trip(&lua, "pointer", mlua::Value::Number(4096.0)), mlua::Value::Number(4096.0));
+
+  // Addresses above 2 GiB must not turn negative
+  assert_eq!(round_trip(&lua, "pointer", mlua::Value::Number(2147483648.0)), mlua::Value::Number(2147483648.0));
+}
+
+#[test]
+fn struct_is_passed_as_pointer_to_its_bytes() {
+  let lua = Lua::new();
+  let bytes = lua.create_string([1u8, 0, 0, 0, 2, 0, 0, 0]).unwrap();
+  let value = mlua::Value::String(bytes);
+
+  let words = unsafe { lua_to_native(Type::Struct, &value).unwrap() };
+  let fields = unsafe { std::slice::from_raw_parts(words[0] as *const u32, 2) };
+
+  assert_eq!(fields, &[1, 2]);
+  assert_eq!(round_trip(&lua, "struct", mlua::Value::Number(4096.0)), mlua::Value::Number(4096.0));
+}
+
+#[test]
+fn native_function_passes_double_in_two_stack_slots() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let function = get_native_function(lua, (double_high_word_function(), vec!["double".into(), "int".into()], "int".into(), None)).unwrap();
+
+  let high_word = (2.5f64.to_bits() >> 32) as i32;
+  assert_eq!(call_native(lua, &function, (2.5, 1)), high_word + 1);
+}
+
+#[test]
+fn created_native_function_reads_double_arguments() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let callback = lua.load("return function(a, b, c) return math.floor(a * 4) + b * c end").eval().unwrap();
+
+  let native = create_native_function_function(lua, (vec!["double".into(), "int".into(), "double".into()], "int".into(), callback, None)).unwrap();
+  let function: extern "C" fn(f64, u32, f64) -> u32 = as_fn(native.address());
+
+  // 2.25 * 4 + 3 * 2
+  assert_eq!(function(2.25, 3, 2.0), 15);
+}
+
+#[test]
+fn created_native_function_with_doubles_cleans_up_as_stdcall() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let callback = lua.load("return function(a, b) return math.floor(a - b) end").eval().unwrap();
+
+  let native = create_native_function_function(lua, (vec!["double".into(), "double".into()], "int".into(), callback, Some("stdcall".into()))).unwrap();
+  let function = get_native_function(lua, (native.address(), vec!["double".into(), "double".into()], "int".into(), Some("stdcall".into()))).unwrap();
+
+  assert_eq!(call_native(lua, &function, (10.5, 3.5)), 7);
+  assert_eq!(call_native(lua, &function, (10.5, 3.5)), 7);
+}
+
+#[test]
+fn lua_hook_passes_double_arguments() {
+  let lua: &'static Lua = Box::leak(Box::new(Lua::new()));
+  let target = double_high_word_function();
+  let function: extern "C" fn(f64, u32) -> u32 = as_fn(target);
+
+  let callback = lua.load("return function(original, a, b) return original(a * 2, b + 1) end").eval().unwrap();
+
+  let _hook = hook_function(lua, (target, vec!["double".into(), "int".into()], "int".into(), callback)).unwrap();
+
+  let high_word = (5.0f64.to_bits() >> 32) as u32;
+  assert_eq!(function(2.5, 1), high_word + 2);
+}